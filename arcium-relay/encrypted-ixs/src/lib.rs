@@ -10,12 +10,21 @@ use arcis_imports::*;
 mod circuits {
     use arcis_imports::*;
 
-    /// Batch statistics - simple counters
+    /// Orders a batch can hold (MAX_BATCH_ORDERS in obsidian_mpc).
+    const MAX_BATCH_ORDERS: usize = 32;
+
+    /// Batch statistics. Each order's amount and expiry are kept so
+    /// reveal_batch_total can leave out orders that expired while the
+    /// batch was open.
     pub struct BatchStats {
-        /// Total USDC across all orders
+        /// Total USDC across all orders, expired or not
         pub total_usdc: u64,
         /// Number of orders
         pub order_count: u8,
+        /// Amount of the order at each index; 0 past order_count
+        pub amounts: [u64; MAX_BATCH_ORDERS],
+        /// Expiry (unix seconds) of the order at each index
+        pub expires_at: [u64; MAX_BATCH_ORDERS],
     }
 
     /// Single order data
@@ -26,6 +35,25 @@ mod circuits {
         pub wallet_lo: u128,
         /// Destination wallet high bits
         pub wallet_hi: u128,
+        /// Unix seconds after which the order must not execute;
+        /// u64::MAX for orders that never expire
+        pub expires_at: u64,
+    }
+
+    /// Parallel batches a market can run (MAX_MARKET_LANES in obsidian_mpc).
+    const MAX_MARKET_LANES: usize = 4;
+
+    /// Markets tracked per user portfolio.
+    const MAX_PORTFOLIO_MARKETS: usize = 8;
+
+    /// A user's cumulative shares per market across batches. Persisted
+    /// encrypted to the MXE, so neither the relay nor observers learn what
+    /// a user holds.
+    pub struct UserPortfolio {
+        /// Market keys (first 8 bytes of sha256(market_id)); 0 = empty slot
+        pub markets: [u64; MAX_PORTFOLIO_MARKETS],
+        /// Shares held in the market at the same index
+        pub shares: [u64; MAX_PORTFOLIO_MARKETS],
     }
 
     /// Initialize batch statistics.
@@ -34,57 +62,448 @@ mod circuits {
         let stats = BatchStats {
             total_usdc: 0,
             order_count: 0,
+            amounts: [0u64; MAX_BATCH_ORDERS],
+            expires_at: [0u64; MAX_BATCH_ORDERS],
         };
         mxe.from_arcis(stats)
     }
 
-    /// Add an order's amount to the batch total.
-    /// The individual order amount stays hidden - only the total is tracked.
+    /// Append an order to the batch: store it at index order_count, then
+    /// count it.
+    fn push_order(mut stats: BatchStats, amount: u64, expires_at: u64) -> BatchStats {
+        for i in 0..MAX_BATCH_ORDERS {
+            if stats.order_count == i as u8 {
+                stats.amounts[i] = amount;
+                stats.expires_at[i] = expires_at;
+            }
+        }
+        stats.total_usdc = stats.total_usdc + amount;
+        stats.order_count = stats.order_count + 1;
+        stats
+    }
+
+    /// Total of the orders still live at `now`. Unused slots hold no
+    /// amount, so they add nothing.
+    fn live_total(stats: &BatchStats, now: u64) -> u64 {
+        let mut total = 0u64;
+        for i in 0..MAX_BATCH_ORDERS {
+            if now <= stats.expires_at[i] {
+                total = total + stats.amounts[i];
+            }
+        }
+        total
+    }
+
+    /// An order's pro-rata share of `total_shares`; none once it expired.
+    fn order_shares(amount: u64, expired: bool, batch_total: u64, total_shares: u64) -> u64 {
+        if batch_total > 0 && !expired {
+            ((amount as u128) * (total_shares as u128) / (batch_total as u128)) as u64
+        } else {
+            0u64
+        }
+    }
+
+    /// Add an order to the batch.
+    /// The individual order amount and expiry stay hidden inside the stats.
     #[instruction]
     pub fn add_to_batch(
         usdc_amount: Enc<Shared, u64>,
+        expires_at: Enc<Shared, u64>,
         stats_ctxt: Enc<Mxe, BatchStats>,
     ) -> Enc<Mxe, BatchStats> {
         let amount = usdc_amount.to_arcis();
-        let mut stats = stats_ctxt.to_arcis();
-
-        stats.total_usdc = stats.total_usdc + amount;
-        stats.order_count = stats.order_count + 1;
+        let expires_at = expires_at.to_arcis();
+        let stats = push_order(stats_ctxt.to_arcis(), amount, expires_at);
 
         stats_ctxt.owner.from_arcis(stats)
     }
 
+    /// add_to_batch for markets with a lot size: only whole lots enter the
+    /// batch total, so the revealed total always executes at DFlow.
+    /// Returns, encrypted to the order's key, the normalized amount (the
+    /// order_amount to pass to the distribution circuits) and the dust
+    /// left over, which is credited back to the user.
+    #[instruction]
+    pub fn add_to_batch_normalized(
+        usdc_amount: Enc<Shared, u64>,
+        expires_at: Enc<Shared, u64>,
+        lot_size: u64,         // Plaintext - from the market's MarketLotSize
+        stats_ctxt: Enc<Mxe, BatchStats>,
+    ) -> (Enc<Mxe, BatchStats>, Enc<Shared, u64>, Enc<Shared, u64>) {
+        let amount = usdc_amount.to_arcis();
+        let expires_at = expires_at.to_arcis();
+
+        let dust = if lot_size > 0 { amount % lot_size } else { 0u64 };
+        let normalized = amount - dust;
+
+        let stats = push_order(stats_ctxt.to_arcis(), normalized, expires_at);
+
+        (
+            stats_ctxt.owner.from_arcis(stats),
+            usdc_amount.owner.from_arcis(normalized),
+            usdc_amount.owner.from_arcis(dust),
+        )
+    }
+
+    /// Pick the lane an order joins: the number of `thresholds` its amount
+    /// reaches. Thresholds come from the market's MarketLanes in ascending
+    /// order, padded with u64::MAX. Only the lane is revealed, i.e. which
+    /// amount range the order falls in, never the amount itself.
+    #[instruction]
+    pub fn route_order(
+        usdc_amount: Enc<Shared, u64>,
+        thresholds: [u64; MAX_MARKET_LANES - 1], // Plaintext - from MarketLanes
+    ) -> u8 {
+        let amount = usdc_amount.to_arcis();
+
+        let mut lane = 0u8;
+        for i in 0..MAX_MARKET_LANES - 1 {
+            if amount >= thresholds[i] {
+                lane = lane + 1;
+            }
+        }
+
+        lane.reveal()
+    }
+
     /// Reveal batch total for DFlow execution.
     /// This is the ONLY information revealed to the relay.
+    /// Orders that expired before `now` are left out of the total, so a
+    /// stalled batch doesn't execute them at stale prices. The count still
+    /// covers every order: each one gets a distribution, a full refund if
+    /// it expired.
+    #[instruction]
+    pub fn reveal_batch_total(
+        stats_ctxt: Enc<Mxe, BatchStats>,
+        now: u64,              // Plaintext - the closing transaction's clock
+    ) -> (u64, u8) {
+        let stats = stats_ctxt.to_arcis();
+        (live_total(&stats, now).reveal(), stats.order_count.reveal())
+    }
+
+    /// Reveal only how many orders the batch holds, so frontends can show
+    /// progress while it is open. Totals, amounts and expiries stay
+    /// encrypted, and expired orders still count, as in reveal_batch_total.
+    #[instruction]
+    pub fn peek_order_count(stats_ctxt: Enc<Mxe, BatchStats>) -> u8 {
+        stats_ctxt.to_arcis().order_count.reveal()
+    }
+
+    /// Granularity of bucketed reveals: 1,000 USDC in atomic units.
+    const REVEAL_BUCKET: u64 = 1_000_000_000;
+
+    /// Reveal the batch total rounded up to the next REVEAL_BUCKET.
+    /// The exact total never leaves the MXE; distributions are computed
+    /// against it with compute_distribution_from_stats. Expired orders are
+    /// left out as in reveal_batch_total.
     #[instruction]
-    pub fn reveal_batch_total(stats_ctxt: Enc<Mxe, BatchStats>) -> (u64, u8) {
+    pub fn reveal_bucketed_total(
+        stats_ctxt: Enc<Mxe, BatchStats>,
+        now: u64,              // Plaintext - the closing transaction's clock
+    ) -> (u64, u8) {
         let stats = stats_ctxt.to_arcis();
-        (stats.total_usdc.reveal(), stats.order_count.reveal())
+        let buckets = (live_total(&stats, now) + REVEAL_BUCKET - 1) / REVEAL_BUCKET;
+        ((buckets * REVEAL_BUCKET).reveal(), stats.order_count.reveal())
     }
 
     /// Compute pro-rata share allocation for an order.
     /// order_amount is encrypted (relay can't see it).
-    /// Returns revealed share amount and wallet.
+    /// Returns revealed share amount, USDC refund and wallet. On a partial
+    /// fill the order spent only its pro-rata part of filled_usdc; the rest
+    /// is refunded.
+    /// For stealth distributions the wallet halves carry a one-time claim
+    /// key, and the shares are claimed into the privacy pool as a new note.
+    /// `now` must be the one passed to reveal_batch_total: orders expired
+    /// by then were left out of batch_total and are refunded in full.
     #[instruction]
     pub fn compute_distribution(
         order_amount: Enc<Shared, u64>,
         wallet_lo: Enc<Shared, u128>,
         wallet_hi: Enc<Shared, u128>,
+        expires_at: Enc<Shared, u64>,
         batch_total: u64,      // Plaintext - already revealed
         total_shares: u64,     // Plaintext - from DFlow execution
-    ) -> (u64, u128, u128) {
+        filled_usdc: u64,      // Plaintext - USDC DFlow actually spent
+        now: u64,              // Plaintext - as passed to reveal_batch_total
+    ) -> (u64, u64, u128, u128) {
         let amount = order_amount.to_arcis();
         let w_lo = wallet_lo.to_arcis();
         let w_hi = wallet_hi.to_arcis();
+        let expired = now > expires_at.to_arcis();
 
         // shares = (order_amount / batch_total) * total_shares
-        let shares = if batch_total > 0 {
-            ((amount as u128) * (total_shares as u128) / (batch_total as u128)) as u64
+        // refund = order_amount - (order_amount / batch_total) * filled_usdc
+        let (shares, refund) = if expired {
+            (0u64, amount)
+        } else if batch_total > 0 {
+            let spent = ((amount as u128) * (filled_usdc as u128) / (batch_total as u128)) as u64;
+            (
+                ((amount as u128) * (total_shares as u128) / (batch_total as u128)) as u64,
+                amount - spent,
+            )
+        } else {
+            (0u64, 0u64)
+        };
+
+        (shares.reveal(), refund.reveal(), w_lo.reveal(), w_hi.reveal())
+    }
+
+    /// compute_distribution variant that keeps the allocation private.
+    /// The share amount is re-encrypted to the key the user encrypted
+    /// their order with, so only the recipient can read it; the wallet is
+    /// still revealed for payout. Expired orders get no shares.
+    #[instruction]
+    pub fn compute_encrypted_distribution(
+        order_amount: Enc<Shared, u64>,
+        wallet_lo: Enc<Shared, u128>,
+        wallet_hi: Enc<Shared, u128>,
+        expires_at: Enc<Shared, u64>,
+        batch_total: u64,      // Plaintext - already revealed
+        total_shares: u64,     // Plaintext - from DFlow execution
+        now: u64,              // Plaintext - as passed to reveal_batch_total
+    ) -> (Enc<Shared, u64>, u128, u128) {
+        let amount = order_amount.to_arcis();
+        let w_lo = wallet_lo.to_arcis();
+        let w_hi = wallet_hi.to_arcis();
+        let expired = now > expires_at.to_arcis();
+
+        let shares = order_shares(amount, expired, batch_total, total_shares);
+
+        (order_amount.owner.from_arcis(shares), w_lo.reveal(), w_hi.reveal())
+    }
+
+    /// compute_distribution against the exact encrypted batch total,
+    /// for batches closed with reveal_bucketed_total.
+    #[instruction]
+    pub fn compute_distribution_from_stats(
+        order_amount: Enc<Shared, u64>,
+        wallet_lo: Enc<Shared, u128>,
+        wallet_hi: Enc<Shared, u128>,
+        expires_at: Enc<Shared, u64>,
+        stats_ctxt: Enc<Mxe, BatchStats>,
+        total_shares: u64,     // Plaintext - from DFlow execution
+        filled_usdc: u64,      // Plaintext - USDC DFlow actually spent
+        now: u64,              // Plaintext - as passed to reveal_bucketed_total
+    ) -> (u64, u64, u128, u128) {
+        let amount = order_amount.to_arcis();
+        let w_lo = wallet_lo.to_arcis();
+        let w_hi = wallet_hi.to_arcis();
+        let expired = now > expires_at.to_arcis();
+        let batch_total = live_total(&stats_ctxt.to_arcis(), now);
+
+        // filled_usdc is only bounded by the bucketed total on-chain, so
+        // cap the spend at the order amount
+        let (shares, refund) = if expired {
+            (0u64, amount)
+        } else if batch_total > 0 {
+            let spent = ((amount as u128) * (filled_usdc as u128) / (batch_total as u128)) as u64;
+            let spent = if spent > amount { amount } else { spent };
+            (
+                ((amount as u128) * (total_shares as u128) / (batch_total as u128)) as u64,
+                amount - spent,
+            )
+        } else {
+            (0u64, 0u64)
+        };
+
+        (shares.reveal(), refund.reveal(), w_lo.reveal(), w_hi.reveal())
+    }
+
+    /// Hand an in-flight batch over to a new cluster: re-encrypt the stats
+    /// to the new cluster's x25519 key. Run on the outgoing cluster, then
+    /// pass the result to import_batch_stats on the new one. No value is
+    /// revealed, so the batch continues without being aborted.
+    #[instruction]
+    pub fn rekey_batch_stats(
+        stats_ctxt: Enc<Mxe, BatchStats>,
+        new_cluster: Shared,
+    ) -> Enc<Shared, BatchStats> {
+        new_cluster.from_arcis(stats_ctxt.to_arcis())
+    }
+
+    /// Take over stats exported by rekey_batch_stats under this cluster's
+    /// MXE key.
+    #[instruction]
+    pub fn import_batch_stats(
+        stats_ctxt: Enc<Shared, BatchStats>,
+        mxe: Mxe,
+    ) -> Enc<Mxe, BatchStats> {
+        mxe.from_arcis(stats_ctxt.to_arcis())
+    }
+
+    /// Create an empty portfolio.
+    #[instruction]
+    pub fn init_portfolio(mxe: Mxe) -> Enc<Mxe, UserPortfolio> {
+        let portfolio = UserPortfolio {
+            markets: [0u64; MAX_PORTFOLIO_MARKETS],
+            shares: [0u64; MAX_PORTFOLIO_MARKETS],
+        };
+        mxe.from_arcis(portfolio)
+    }
+
+    /// Add an order's shares from a completed batch to the user's position
+    /// in `market`, claiming the first empty slot for a new market.
+    /// A full portfolio leaves the position unchanged, and so does an
+    /// order that expired before the batch total was revealed.
+    #[instruction]
+    pub fn accumulate_position(
+        order_amount: Enc<Shared, u64>,
+        expires_at: Enc<Shared, u64>,
+        batch_total: u64,      // Plaintext - already revealed
+        total_shares: u64,     // Plaintext - from DFlow execution
+        market: u64,           // Plaintext - batches are per market
+        now: u64,              // Plaintext - as passed to reveal_batch_total
+        portfolio_ctxt: Enc<Mxe, UserPortfolio>,
+    ) -> Enc<Mxe, UserPortfolio> {
+        let amount = order_amount.to_arcis();
+        let expired = now > expires_at.to_arcis();
+        let mut portfolio = portfolio_ctxt.to_arcis();
+
+        let shares = order_shares(amount, expired, batch_total, total_shares);
+
+        let mut applied = false;
+        for i in 0..MAX_PORTFOLIO_MARKETS {
+            if !applied && portfolio.markets[i] == market {
+                portfolio.shares[i] = portfolio.shares[i] + shares;
+                applied = true;
+            }
+        }
+        for i in 0..MAX_PORTFOLIO_MARKETS {
+            if !applied && portfolio.markets[i] == 0 {
+                portfolio.markets[i] = market;
+                portfolio.shares[i] = shares;
+                applied = true;
+            }
+        }
+
+        portfolio_ctxt.owner.from_arcis(portfolio)
+    }
+
+    /// Re-encrypt the portfolio to the user's key. Only the user can read
+    /// the result; nothing is revealed on-chain.
+    #[instruction]
+    pub fn reveal_my_position(
+        portfolio_ctxt: Enc<Mxe, UserPortfolio>,
+        user: Shared,
+    ) -> Enc<Shared, UserPortfolio> {
+        user.from_arcis(portfolio_ctxt.to_arcis())
+    }
+
+    /// Notes one balance summary covers (SUMMARY_NOTES in obsidian_mpc),
+    /// as many as fit one queue instruction.
+    const MAX_SUMMARY_NOTES: usize = 6;
+
+    /// A wallet's references to its pool notes: the leaf each note sits in
+    /// and its amount. Unused slots hold a zero amount.
+    pub struct NoteRefs {
+        pub leaf_indices: [u64; MAX_SUMMARY_NOTES],
+        pub amounts: [u64; MAX_SUMMARY_NOTES],
+    }
+
+    /// Total of the user's notes, encrypted back to the key they were
+    /// submitted under, so a wallet can show its shielded balance without
+    /// the relay or observers learning it. Notes referencing leaves at or
+    /// past `next_index` are not in the tree yet and count for nothing.
+    /// Wallets with more notes sum the totals of several summaries.
+    #[instruction]
+    pub fn sum_my_notes(
+        notes_ctxt: Enc<Shared, NoteRefs>,
+        next_index: u64,       // Plaintext - the pool's next_index
+    ) -> Enc<Shared, u64> {
+        let notes = notes_ctxt.to_arcis();
+        let mut total = 0u64;
+        for i in 0..MAX_SUMMARY_NOTES {
+            if notes.leaf_indices[i] < next_index {
+                total = total + notes.amounts[i];
+            }
+        }
+        notes_ctxt.owner.from_arcis(total)
+    }
+
+    /// Largest jitter a DCA schedule may apply, in basis points of the
+    /// even split (MAX_DCA_JITTER_BPS in obsidian_mpc).
+    const MAX_DCA_JITTER_BPS: u64 = 5_000;
+
+    /// What a subscription has left to spend. Kept encrypted to the MXE
+    /// between enrollments, so no single epoch's amount is ever fixed in
+    /// advance.
+    pub struct DcaSchedule {
+        /// USDC not yet enrolled
+        pub remaining_usdc: u64,
+        /// Enrollments left, including the next one
+        pub remaining_epochs: u64,
+    }
+
+    /// The next epoch's amount: the even split of what is left, moved by a
+    /// random offset of up to `jitter_bps` of it either way. The last
+    /// epoch takes the remainder, so the amounts always add up to the
+    /// subscription's total. With at least two epochs left the even split
+    /// is at most half of `remaining_usdc`, so the amount never exceeds it.
+    fn dca_amount(schedule: &DcaSchedule, jitter_bps: u64, random: u64) -> u64 {
+        let epochs = schedule.remaining_epochs;
+        let divisor = if epochs == 0 { 1u64 } else { epochs };
+        let base = schedule.remaining_usdc / divisor;
+        let jitter_bps = if jitter_bps > MAX_DCA_JITTER_BPS {
+            MAX_DCA_JITTER_BPS
         } else {
+            jitter_bps
+        };
+        let span = ((base as u128) * (jitter_bps as u128) / 10_000u128) as u64;
+        let offset = random % (2 * span + 1);
+
+        if epochs == 0 {
             0u64
+        } else if epochs == 1 {
+            schedule.remaining_usdc
+        } else {
+            base - span + offset
+        }
+    }
+
+    /// Start a subscription's schedule from the total it spends over
+    /// `epochs` enrollments. The total stays encrypted; only the owner,
+    /// who encrypted it, knows it.
+    #[instruction]
+    pub fn init_dca_schedule(
+        total_usdc: Enc<Shared, u64>,
+        epochs: u64,           // Plaintext - the subscription's order count
+        mxe: Mxe,
+    ) -> Enc<Mxe, DcaSchedule> {
+        let schedule = DcaSchedule {
+            remaining_usdc: total_usdc.to_arcis(),
+            remaining_epochs: epochs,
         };
+        mxe.from_arcis(schedule)
+    }
+
+    /// add_to_batch for an enrolled subscription: draw this epoch's
+    /// jittered amount from the schedule and add it to the batch as an
+    /// order that never expires. Recurring amounts then differ from batch
+    /// to batch, so matching amounts across batches can't link them to
+    /// one subscriber. Returns the advanced schedule, the stats, and the
+    /// amount encrypted to the owner, for the distribution circuits.
+    #[instruction]
+    pub fn add_dca_to_batch(
+        schedule_ctxt: Enc<Mxe, DcaSchedule>,
+        stats_ctxt: Enc<Mxe, BatchStats>,
+        owner: Shared,
+        jitter_bps: u64,       // Plaintext - from the Subscription
+    ) -> (Enc<Mxe, DcaSchedule>, Enc<Mxe, BatchStats>, Enc<Shared, u64>) {
+        let mut schedule = schedule_ctxt.to_arcis();
+        let random = ArcisRNG::gen_integer_from_width(64) as u64;
+        let amount = dca_amount(&schedule, jitter_bps, random);
+
+        schedule.remaining_usdc = schedule.remaining_usdc - amount;
+        if schedule.remaining_epochs > 0 {
+            schedule.remaining_epochs = schedule.remaining_epochs - 1;
+        }
+        let stats = push_order(stats_ctxt.to_arcis(), amount, u64::MAX);
 
-        (shares.reveal(), w_lo.reveal(), w_hi.reveal())
+        (
+            schedule_ctxt.owner.from_arcis(schedule),
+            stats_ctxt.owner.from_arcis(stats),
+            owner.from_arcis(amount),
+        )
     }
 
     /// Simple test - add two numbers in MPC
@@ -4,10 +4,173 @@
 //! Coordinates with Arcium MPC to process orders privately.
 
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{
+    get_stack_height, Instruction, TRANSACTION_LEVEL_STACK_HEIGHT,
+};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::token_interface::spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use anchor_spl::token_interface::spl_token_2022::extension::{
+    BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+};
+use anchor_spl::token_interface::spl_token_2022::state::Mint as MintState;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
 use arcium_anchor::prelude::*;
+use solana_instructions_sysvar::{load_current_index_checked, load_instruction_at_checked};
+use solana_sdk_ids::ed25519_program;
+use solana_sha256_hasher::hashv;
 
+pub mod state;
+
+pub use state::*;
+
+#[cfg(all(feature = "devnet", feature = "mainnet"))]
+compile_error!("features `devnet` and `mainnet` are mutually exclusive");
+
+#[cfg(not(feature = "mainnet"))]
 declare_id!("8postM9mUCTKTu6a1vkrhfg8erso2g8eHo8bmc9JZjZc");
 
+// Not deployed to mainnet yet: its declare_id! goes here, with the same ID
+// under [programs.mainnet] in arcium-relay/Anchor.toml
+#[cfg(feature = "mainnet")]
+compile_error!("no mainnet program ID yet; add its declare_id! before building for mainnet");
+
+/// Minimum time a batch stays open before it can be closed.
+/// Zero on devnet so demos can close immediately.
+#[cfg(not(feature = "mainnet"))]
+pub const MIN_BATCH_DURATION: i64 = 0;
+
+/// Minimum time a batch stays open before it can be closed.
+#[cfg(feature = "mainnet")]
+pub const MIN_BATCH_DURATION: i64 = 60;
+
+/// Rolling window over which batch-total reveals are rate limited per market.
+/// Zero on devnet so demos are never throttled.
+#[cfg(not(feature = "mainnet"))]
+pub const REVEAL_WINDOW: i64 = 0;
+
+/// Rolling window over which batch-total reveals are rate limited per market.
+#[cfg(feature = "mainnet")]
+pub const REVEAL_WINDOW: i64 = 10 * 60;
+
+/// Default time a closed batch has to be executed before force_fail_batch
+/// can fail it.
+pub const DEFAULT_EXECUTION_TIMEOUT: i64 = 24 * 60 * 60;
+
+/// Default time an executed batch has to complete its distributions before
+/// force_fail_batch can fail it.
+pub const DEFAULT_COMPLETION_TIMEOUT: i64 = 7 * 24 * 60 * 60;
+
+/// Revealed batch totals allowed per market within `REVEAL_WINDOW`.
+pub const MAX_REVEALS_PER_WINDOW: usize = 1;
+
+/// Maximum length of a client-supplied batch label.
+pub const MAX_BATCH_LABEL_LEN: usize = 32;
+
+/// Token-2022 extensions the protocol's USDC mint may carry; see
+/// check_usdc_mint. Everything else is rejected with
+/// UnsupportedMintExtension.
+pub const SUPPORTED_MINT_EXTENSIONS: [ExtensionType; 4] = [
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::InterestBearingConfig,
+    ExtensionType::MetadataPointer,
+    ExtensionType::TokenMetadata,
+];
+
+/// Computation definitions the MpcRegistry can hold.
+pub const MAX_REGISTERED_CIRCUITS: usize = 32;
+
+/// Relayer keys that can approve large executions
+pub const MAX_EXECUTION_APPROVERS: usize = 5;
+
+/// Programs record_order can accept calls from while the CPI guard is on
+pub const MAX_CPI_CALLERS: usize = 4;
+
+/// Yield adapter instruction parking escrow USDC:
+/// sha256("global:yield_deposit")[..8], then Borsh `amount: u64`.
+/// Accounts: escrow (writable), batch (signer, the escrow owner), USDC
+/// mint, token program, then the adapter's own accounts. The adapter
+/// must move exactly `amount` out of the escrow.
+pub const YIELD_DEPOSIT_DISCRIMINATOR: [u8; 8] = [197, 128, 119, 222, 188, 102, 165, 63];
+
+/// Yield adapter instruction returning the batch's whole position, with
+/// any earnings, to the escrow: sha256("global:yield_withdraw")[..8], no
+/// arguments; same accounts as YIELD_DEPOSIT_DISCRIMINATOR.
+pub const YIELD_WITHDRAW_DISCRIMINATOR: [u8; 8] = [225, 135, 226, 199, 89, 96, 95, 196];
+
+/// Orders per batch (MAX_BATCH_ORDERS in encrypted-ixs): the MPC keeps
+/// each order's amount and expiry so expired orders can be left out of
+/// the revealed total.
+pub const MAX_BATCH_ORDERS: u8 = 32;
+
+/// Distributions mark_distributed_many settles in one instruction; with
+/// the batch's accounts in a lookup table, the transfers and the marker for
+/// this many orders still fit one transaction.
+pub const MAX_DISTRIBUTIONS_PER_IX: usize = 8;
+
+/// Parallel batches a market can run, one per lane (MAX_MARKET_LANES in
+/// encrypted-ixs)
+pub const MAX_MARKET_LANES: usize = 4;
+
+/// Lane index express batches are stored under, clear of the size lanes
+pub const EXPRESS_LANE: u8 = u8::MAX;
+
+/// Depth of a batch's distribution tree: one leaf per possible order index.
+pub const DISTRIBUTION_TREE_DEPTH: usize = 8;
+
+/// Prefix of the message a user signs for a delegated order
+pub const ORDER_INTENT_DOMAIN: &[u8] = b"obsidian:order_intent:v1";
+
+/// Session key scope: sign order intents for record_delegated_order
+pub const SESSION_SCOPE_RECORD_ORDER: u8 = 1 << 0;
+
+/// All session key scope bits
+pub const SESSION_SCOPES: u8 = SESSION_SCOPE_RECORD_ORDER;
+
+/// Longest a session key may live
+pub const MAX_SESSION_DURATION: i64 = 7 * 24 * 60 * 60;
+
+/// Longest market id a subscription may name (market PDAs cap it at 64)
+pub const MAX_SUBSCRIPTION_MARKET_ID_LEN: usize = 64;
+
+/// Largest per-enrollment jitter a subscription may ask add_dca_to_batch
+/// for, in basis points of the even split (MAX_DCA_JITTER_BPS in
+/// encrypted-ixs)
+pub const MAX_DCA_JITTER_BPS: u16 = 5_000;
+
+/// Ciphertexts in an `Enc<Mxe, DcaSchedule>`: remaining USDC and epochs
+pub const DCA_SCHEDULE_CIPHERTEXTS: usize = 2;
+
+/// Markets tracked per user portfolio (MAX_PORTFOLIO_MARKETS in encrypted-ixs).
+pub const PORTFOLIO_MARKETS: usize = 8;
+/// Ciphertexts in an `Enc<Mxe, UserPortfolio>`: one market key and one
+/// share count per market.
+pub const PORTFOLIO_CIPHERTEXTS: usize = 2 * PORTFOLIO_MARKETS;
+
+/// Notes one sum_my_notes call covers (MAX_SUMMARY_NOTES in encrypted-ixs).
+pub const SUMMARY_NOTES: usize = 6;
+
+/// `require!` for relay-driven instructions that the relay retries.
+///
+/// On failure it logs a `ProtocolError` before returning the error. The
+/// event goes through `emit!` rather than `emit_cpi!`: program logs are kept
+/// in the metadata of failed transactions, while a self-CPI would be rolled
+/// back with everything else, so indexers can still count repeated failures.
+macro_rules! require_reported {
+    ($cond:expr, $err:expr, $subject:expr, $actor:expr, $($context:tt)+) => {
+        if !($cond) {
+            emit!(ProtocolError {
+                code: u32::from($err),
+                error: format!("{:?}", $err),
+                subject: $subject,
+                actor: $actor,
+                context: format!($($context)+),
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+            return Err(error!($err));
+        }
+    };
+}
+
 #[program]
 pub mod obsidian_mpc {
     use super::*;
@@ -29,6 +192,14 @@ pub mod obsidian_mpc {
         Ok(())
     }
 
+    /// Initialize the add_to_batch_normalized computation definition
+    pub fn init_add_to_batch_normalized_comp_def(
+        ctx: Context<InitAddToBatchNormalizedCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
     /// Initialize the reveal_batch_total computation definition
     pub fn init_reveal_batch_total_comp_def(ctx: Context<InitRevealBatchTotalCompDef>) -> Result<()> {
         init_comp_def(ctx.accounts, None, None)?;
@@ -41,387 +212,5417 @@ pub mod obsidian_mpc {
         Ok(())
     }
 
-    // ============================================================================
-    // Batch Management Instructions
-    // ============================================================================
+    /// Initialize the reveal_bucketed_total computation definition
+    pub fn init_reveal_bucketed_total_comp_def(ctx: Context<InitRevealBucketedTotalCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
 
-    /// Initialize a new batch.
-    pub fn create_batch(
-        ctx: Context<CreateBatch>,
-        market_id: String,
-        side: u8,
+    /// Initialize the compute_distribution_from_stats computation definition
+    pub fn init_compute_distribution_from_stats_comp_def(
+        ctx: Context<InitComputeDistributionFromStatsCompDef>,
     ) -> Result<()> {
-        let batch = &mut ctx.accounts.batch;
-        let clock = Clock::get()?;
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
 
-        batch.authority = ctx.accounts.authority.key();
-        batch.market_id = market_id.clone();
-        batch.side = side;
-        batch.status = BatchStatus::Open;
-        batch.order_count = 0;
-        batch.total_usdc = 0;
-        batch.total_shares = 0;
-        batch.created_at = clock.unix_timestamp;
+    /// Initialize the compute_encrypted_distribution computation definition
+    pub fn init_compute_encrypted_distribution_comp_def(
+        ctx: Context<InitComputeEncryptedDistributionCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
 
-        emit!(BatchCreated {
-            batch: batch.key(),
-            market_id,
-            side,
+    /// Initialize the rekey_batch_stats computation definition
+    pub fn init_rekey_batch_stats_comp_def(ctx: Context<InitRekeyBatchStatsCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the import_batch_stats computation definition
+    pub fn init_import_batch_stats_comp_def(
+        ctx: Context<InitImportBatchStatsCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the init_portfolio computation definition
+    pub fn init_init_portfolio_comp_def(ctx: Context<InitInitPortfolioCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the accumulate_position computation definition
+    pub fn init_accumulate_position_comp_def(
+        ctx: Context<InitAccumulatePositionCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the reveal_my_position computation definition
+    pub fn init_reveal_my_position_comp_def(
+        ctx: Context<InitRevealMyPositionCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the sum_my_notes computation definition
+    pub fn init_sum_my_notes_comp_def(ctx: Context<InitSumMyNotesCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the route_order computation definition
+    pub fn init_route_order_comp_def(ctx: Context<InitRouteOrderCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the peek_order_count computation definition
+    pub fn init_peek_order_count_comp_def(ctx: Context<InitPeekOrderCountCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the init_dca_schedule computation definition
+    pub fn init_init_dca_schedule_comp_def(
+        ctx: Context<InitInitDcaScheduleCompDef>,
+    ) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Initialize the add_dca_to_batch computation definition
+    pub fn init_add_dca_to_batch_comp_def(ctx: Context<InitAddDcaToBatchCompDef>) -> Result<()> {
+        init_comp_def(ctx.accounts, None, None)?;
+        Ok(())
+    }
+
+    /// Create the registry of initialized computation definitions.
+    pub fn initialize_mpc_registry(ctx: Context<InitializeMpcRegistry>) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        registry.authority = ctx.accounts.authority.key();
+        registry.circuits = Vec::new();
+        Ok(())
+    }
+
+    /// Record that `circuit`'s computation definition exists on-chain.
+    /// Called by the deploy script after each init_*_comp_def.
+    /// Circuits of version 2 and up are deployed under a `_v{version}`
+    /// suffix so every version keeps its own comp-def account.
+    pub fn register_comp_def(
+        ctx: Context<RegisterCompDef>,
+        circuit: String,
+        version: u16,
+    ) -> Result<()> {
+        let comp_def = &ctx.accounts.comp_def_account;
+        require!(!comp_def.data_is_empty(), ErrorCode::CompDefNotInitialized);
+
+        let registry = &mut ctx.accounts.registry;
+        let offset = comp_def_offset(&circuit);
+        let registered_at = Clock::get()?.unix_timestamp;
+
+        match registry.circuits.iter_mut().find(|c| c.offset == offset) {
+            Some(entry) => {
+                require!(entry.version == version, ErrorCode::CircuitVersionMismatch);
+                entry.registered_at = registered_at;
+            }
+            None => {
+                require!(
+                    registry.circuits.len() < MAX_REGISTERED_CIRCUITS,
+                    ErrorCode::RegistryFull
+                );
+                registry.circuits.push(RegisteredCompDef {
+                    offset,
+                    comp_def: comp_def.key(),
+                    version,
+                    registered_at,
+                });
+            }
+        }
+
+        emit_cpi!(CompDefRegistered {
+            circuit,
+            offset,
+            comp_def: comp_def.key(),
+            version,
         });
 
         Ok(())
     }
 
-    /// Record that an order was submitted.
-    /// The actual amount is hidden in the MPC.
-    pub fn record_order(ctx: Context<RecordOrder>) -> Result<()> {
-        let batch = &mut ctx.accounts.batch;
+    /// Fail unless every circuit in `circuits` is registered.
+    /// Read-only; the relay simulates it at startup and deploy scripts
+    /// run it last, so a missing circuit is caught before queueing.
+    pub fn verify_comp_defs(ctx: Context<VerifyCompDefs>, circuits: Vec<String>) -> Result<()> {
+        let registry = &ctx.accounts.registry;
+        for circuit in circuits {
+            let offset = comp_def_offset(&circuit);
+            if !registry.circuits.iter().any(|c| c.offset == offset) {
+                msg!("Computation definition not registered: {}", circuit);
+                return err!(ErrorCode::CompDefNotRegistered);
+            }
+        }
+        Ok(())
+    }
 
-        require!(batch.status == BatchStatus::Open, ErrorCode::BatchNotOpen);
+    /// Fail unless every comp-def account in remaining_accounts is
+    /// registered at the batch's circuit version. The relay bundles this
+    /// ahead of each queued computation, so a circuit upgrade mid-batch
+    /// can't change the math applied to orders already submitted.
+    pub fn verify_batch_comp_defs(ctx: Context<VerifyBatchCompDefs>) -> Result<()> {
+        for comp_def in ctx.remaining_accounts {
+            require_comp_def_version(
+                &ctx.accounts.registry,
+                &ctx.accounts.batch,
+                &comp_def.key(),
+            )?;
+        }
+        Ok(())
+    }
 
-        batch.order_count += 1;
+    // ============================================================================
+    // Protocol Config
+    // ============================================================================
 
-        emit!(OrderRecorded {
-            batch: batch.key(),
-            order_count: batch.order_count,
+    /// Create the protocol config. The payer becomes the admin.
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        cluster_offset: u32,
+        fallback_cluster_offset: u32,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.cluster_offset = cluster_offset;
+        config.fallback_cluster_offset = fallback_cluster_offset;
+        config.circuit_version = 1;
+        config.updated_at = Clock::get()?.unix_timestamp;
+        // Until set_role splits them out, the admin holds every role
+        config.operator = config.admin;
+        config.settler = config.admin;
+        config.execution_timeout = DEFAULT_EXECUTION_TIMEOUT;
+        config.completion_timeout = DEFAULT_COMPLETION_TIMEOUT;
+        config.large_batch_threshold = 0;
+        config.yield_enabled = false;
+        Ok(())
+    }
+
+    /// Assign a protocol role to `key`.
+    /// Keeps the hot operator and settler keys away from config changes:
+    /// only the admin can reassign roles, including handing over admin.
+    pub fn set_role(ctx: Context<UpdateConfig>, role: Role, key: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let previous = match role {
+            Role::Admin => std::mem::replace(&mut config.admin, key),
+            Role::Operator => std::mem::replace(&mut config.operator, key),
+            Role::Settler => std::mem::replace(&mut config.settler, key),
+        };
+        config.updated_at = Clock::get()?.unix_timestamp;
+
+        emit_cpi!(RoleUpdated {
+            role,
+            previous,
+            key,
         });
 
         Ok(())
     }
 
-    /// Close the batch and record the revealed total from MPC.
-    pub fn close_batch(
-        ctx: Context<CloseBatch>,
-        revealed_total: u64,
-        revealed_count: u8,
+    /// Switch the Arcium cluster new batches are processed on.
+    /// The relay calls this with the fallback offset when the active
+    /// cluster is degraded, then moves open batches with migrate_batch_cluster.
+    pub fn set_cluster(
+        ctx: Context<UpdateConfig>,
+        cluster_offset: u32,
+        fallback_cluster_offset: u32,
     ) -> Result<()> {
-        let batch = &mut ctx.accounts.batch;
+        let config = &mut ctx.accounts.config;
+        let previous_cluster_offset = config.cluster_offset;
 
-        require!(batch.status == BatchStatus::Open, ErrorCode::BatchNotOpen);
-        require!(batch.order_count > 0, ErrorCode::BatchEmpty);
+        config.cluster_offset = cluster_offset;
+        config.fallback_cluster_offset = fallback_cluster_offset;
+        config.updated_at = Clock::get()?.unix_timestamp;
 
-        batch.status = BatchStatus::Closed;
-        batch.total_usdc = revealed_total;
+        emit_cpi!(ClusterUpdated {
+            previous_cluster_offset,
+            cluster_offset,
+            fallback_cluster_offset,
+        });
 
-        // Verify count matches
+        Ok(())
+    }
+
+    /// Switch the circuit version new batches are pinned to.
+    /// Batches already created keep the version they started with.
+    pub fn set_circuit_version(ctx: Context<UpdateConfig>, circuit_version: u16) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let previous_version = config.circuit_version;
+
+        config.circuit_version = circuit_version;
+        config.updated_at = Clock::get()?.unix_timestamp;
+
+        emit_cpi!(CircuitVersionUpdated {
+            previous_version,
+            circuit_version,
+        });
+
+        Ok(())
+    }
+
+    /// Set how long batches may sit between states before anyone can fail
+    /// them with force_fail_batch (0 = no timeout).
+    pub fn set_batch_timeouts(
+        ctx: Context<UpdateConfig>,
+        execution_timeout: i64,
+        completion_timeout: i64,
+    ) -> Result<()> {
         require!(
-            revealed_count == batch.order_count,
-            ErrorCode::CountMismatch
+            execution_timeout >= 0 && completion_timeout >= 0,
+            ErrorCode::InvalidTimeout
         );
 
-        emit!(BatchClosed {
-            batch: batch.key(),
-            total_usdc: revealed_total,
-            order_count: revealed_count,
+        let config = &mut ctx.accounts.config;
+        config.execution_timeout = execution_timeout;
+        config.completion_timeout = completion_timeout;
+        config.updated_at = Clock::get()?.unix_timestamp;
+
+        emit_cpi!(BatchTimeoutsUpdated {
+            execution_timeout,
+            completion_timeout,
         });
 
         Ok(())
     }
 
-    /// Record execution result from DFlow.
-    pub fn record_execution(
-        ctx: Context<RecordExecution>,
-        total_shares: u64,
-        tx_signature: String,
+    /// Adapt batch windows to each market's order flow: a batch may close
+    /// once it has been open long enough to collect `target_batch_orders`
+    /// at the market's recent arrival rate, or once it holds that many,
+    /// but never before `min_batch_window` seconds. With no recent orders
+    /// the window is `max_batch_window`. A zero max turns adaptive windows
+    /// off and requires the other two to be zero as well.
+    pub fn set_batch_window(
+        ctx: Context<UpdateConfig>,
+        min_batch_window: i64,
+        max_batch_window: i64,
+        target_batch_orders: u8,
     ) -> Result<()> {
-        let batch = &mut ctx.accounts.batch;
-
+        let enabled = max_batch_window > 0;
         require!(
-            batch.status == BatchStatus::Closed,
-            ErrorCode::BatchNotClosed
+            min_batch_window >= 0
+                && min_batch_window <= max_batch_window
+                && enabled == (target_batch_orders > 0),
+            ErrorCode::InvalidBatchWindow
         );
 
-        batch.status = BatchStatus::Executed;
-        batch.total_shares = total_shares;
+        let config = &mut ctx.accounts.config;
+        config.min_batch_window = min_batch_window;
+        config.max_batch_window = max_batch_window;
+        config.target_batch_orders = target_batch_orders;
+        config.updated_at = Clock::get()?.unix_timestamp;
 
-        emit!(ExecutionRecorded {
-            batch: batch.key(),
-            total_shares,
-            tx_signature,
+        emit_cpi!(BatchWindowUpdated {
+            min_batch_window,
+            max_batch_window,
+            target_batch_orders,
         });
 
         Ok(())
     }
 
-    /// Record a distribution (revealed from MPC).
-    pub fn record_distribution(
-        ctx: Context<RecordDistribution>,
-        order_index: u8,
-        shares: u64,
-        wallet: Pubkey,
+    /// Offer an express lane next to each market's standard batches: an
+    /// express batch may close `batch_window` seconds after it opens,
+    /// ignoring the adaptive window, and pins `fee_bps` for the relay to
+    /// charge on its fills. Express closes are left out of the market's
+    /// arrival counters so they don't shorten standard windows.
+    /// `batch_window = 0` turns the lane off for new batches.
+    pub fn set_express_lane(
+        ctx: Context<UpdateConfig>,
+        batch_window: i64,
+        fee_bps: u16,
     ) -> Result<()> {
-        let batch = &mut ctx.accounts.batch;
-        let dist = &mut ctx.accounts.distribution;
-
         require!(
-            batch.status == BatchStatus::Executed || batch.status == BatchStatus::Distributing,
-            ErrorCode::BatchNotExecuted
+            batch_window >= 0 && fee_bps <= 10_000,
+            ErrorCode::InvalidExpressLane
         );
 
-        if batch.status == BatchStatus::Executed {
-            batch.status = BatchStatus::Distributing;
-        }
-
-        dist.batch = batch.key();
-        dist.order_index = order_index;
-        dist.shares = shares;
-        dist.wallet = wallet;
-        dist.executed = false;
+        let config = &mut ctx.accounts.config;
+        config.express_batch_window = batch_window;
+        config.express_fee_bps = fee_bps;
+        config.updated_at = Clock::get()?.unix_timestamp;
 
-        emit!(DistributionRecorded {
-            batch: batch.key(),
-            order_index,
-            shares,
-            wallet,
+        emit_cpi!(ExpressLaneUpdated {
+            batch_window,
+            fee_bps,
         });
 
         Ok(())
     }
 
-    /// Mark distribution as executed.
-    pub fn mark_distributed(
-        ctx: Context<MarkDistributed>,
-        tx_signature: String,
+    /// Limit how many slots a revealed batch total may stay public before
+    /// its execution is recorded. Past `max_reveal_exec_gap`,
+    /// record_execution fails and anyone may expire_reveal the batch back
+    /// to Open, so it has to be closed and revealed again. 0 = no limit.
+    pub fn set_max_reveal_exec_gap(
+        ctx: Context<UpdateConfig>,
+        max_reveal_exec_gap: u64,
     ) -> Result<()> {
-        let batch = &mut ctx.accounts.batch;
-        let dist = &mut ctx.accounts.distribution;
+        let config = &mut ctx.accounts.config;
+        config.max_reveal_exec_gap = max_reveal_exec_gap;
+        config.updated_at = Clock::get()?.unix_timestamp;
 
-        require!(!dist.executed, ErrorCode::AlreadyDistributed);
+        emit_cpi!(RevealExecGapUpdated {
+            max_reveal_exec_gap,
+        });
 
-        dist.executed = true;
-        batch.distributions_completed += 1;
+        Ok(())
+    }
 
-        if batch.distributions_completed == batch.order_count {
-            batch.status = BatchStatus::Completed;
+    /// Require `threshold` of `approvers` to approve_execution before
+    /// record_execution on batches whose total exceeds
+    /// `large_batch_threshold` (0 = never).
+    pub fn set_execution_approvers(
+        ctx: Context<UpdateConfig>,
+        approvers: Vec<Pubkey>,
+        threshold: u8,
+        large_batch_threshold: u64,
+    ) -> Result<()> {
+        require!(
+            approvers.len() <= MAX_EXECUTION_APPROVERS,
+            ErrorCode::InvalidExecutionApprovers
+        );
+        require!(
+            large_batch_threshold == 0 || (threshold > 0 && threshold as usize <= approvers.len()),
+            ErrorCode::InvalidExecutionApprovers
+        );
+        for (i, approver) in approvers.iter().enumerate() {
+            require!(
+                !approvers[..i].contains(approver),
+                ErrorCode::InvalidExecutionApprovers
+            );
         }
 
-        emit!(DistributionExecuted {
-            batch: batch.key(),
-            order_index: dist.order_index,
-            tx_signature,
+        let config = &mut ctx.accounts.config;
+        config.large_batch_threshold = large_batch_threshold;
+        config.approval_threshold = threshold;
+        config.approver_count = approvers.len() as u8;
+        config.approvers = [Pubkey::default(); MAX_EXECUTION_APPROVERS];
+        config.approvers[..approvers.len()].copy_from_slice(&approvers);
+        config.updated_at = Clock::get()?.unix_timestamp;
+
+        emit_cpi!(ExecutionApproversUpdated {
+            approvers,
+            threshold,
+            large_batch_threshold,
         });
 
         Ok(())
     }
-}
 
-// ============================================================================
-// Accounts
-// ============================================================================
+    /// Require record_order to be a top-level instruction, or called
+    /// directly by one of `allowed_programs`, so wrapper programs cannot
+    /// bundle it with instructions the operator never reviewed.
+    /// `enabled = false` accepts any caller.
+    pub fn set_cpi_guard(
+        ctx: Context<UpdateConfig>,
+        enabled: bool,
+        allowed_programs: Vec<Pubkey>,
+    ) -> Result<()> {
+        require!(
+            allowed_programs.len() <= MAX_CPI_CALLERS,
+            ErrorCode::TooManyCpiCallers
+        );
 
-#[account]
-pub struct Batch {
-    pub authority: Pubkey,
-    pub market_id: String,
-    pub side: u8,
-    pub status: BatchStatus,
-    pub order_count: u8,
-    pub total_usdc: u64,
-    pub total_shares: u64,
-    pub created_at: i64,
-    pub distributions_completed: u8,
-}
+        let config = &mut ctx.accounts.config;
+        config.cpi_guard_enabled = enabled;
+        config.cpi_caller_count = allowed_programs.len() as u8;
+        config.cpi_callers = [Pubkey::default(); MAX_CPI_CALLERS];
+        config.cpi_callers[..allowed_programs.len()].copy_from_slice(&allowed_programs);
+        config.updated_at = Clock::get()?.unix_timestamp;
 
-#[account]
-pub struct Distribution {
-    pub batch: Pubkey,
-    pub order_index: u8,
-    pub shares: u64,
-    pub wallet: Pubkey,
-    pub executed: bool,
-}
+        emit_cpi!(CpiGuardUpdated {
+            enabled,
+            allowed_programs,
+        });
 
-// ============================================================================
-// Enums
-// ============================================================================
+        Ok(())
+    }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
-pub enum BatchStatus {
-    Open,
-    Closed,
-    Executed,
-    Distributing,
-    Completed,
-}
+    /// Whitelist the lending adapter idle batch escrow may be parked in,
+    /// and the USDC token account its earnings go to. None removes it;
+    /// batches already parked recall from the adapter they used.
+    pub fn set_yield_adapter(
+        ctx: Context<UpdateConfig>,
+        adapter: Option<Pubkey>,
+        yield_recipient: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.yield_adapter = adapter.unwrap_or_default();
+        config.yield_recipient = yield_recipient;
+        config.updated_at = Clock::get()?.unix_timestamp;
+
+        emit_cpi!(YieldAdapterUpdated {
+            adapter,
+            yield_recipient,
+            enabled: config.yield_enabled,
+        });
 
-impl Default for BatchStatus {
-    fn default() -> Self {
-        BatchStatus::Open
+        Ok(())
     }
-}
 
-// ============================================================================
-// Computation Definition Account Contexts
-// ============================================================================
+    /// Kill-switch for park_escrow. Recalls stay open either way.
+    pub fn set_yield_enabled(ctx: Context<UpdateConfig>, enabled: bool) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.yield_enabled = enabled;
+        config.updated_at = Clock::get()?.unix_timestamp;
 
-#[init_computation_definition_accounts("init_batch", payer)]
-#[derive(Accounts)]
-pub struct InitInitBatchCompDef<'info> {
+        emit_cpi!(YieldAdapterUpdated {
+            adapter: (config.yield_adapter != Pubkey::default()).then_some(config.yield_adapter),
+            yield_recipient: config.yield_recipient,
+            enabled,
+        });
+
+        Ok(())
+    }
+
+    /// Set the lot size orders in `market_id` are rounded down to by
+    /// add_to_batch_normalized (0 = no normalization).
+    pub fn set_market_lot_size(
+        ctx: Context<SetMarketLotSize>,
+        market_id: String,
+        lot_size: u64,
+    ) -> Result<()> {
+        let market_lot = &mut ctx.accounts.market_lot;
+        market_lot.market_id = market_id.clone();
+        market_lot.lot_size = lot_size;
+        market_lot.updated_at = Clock::get()?.unix_timestamp;
+
+        emit_cpi!(MarketLotSizeUpdated {
+            market_id,
+            lot_size,
+        });
+
+        Ok(())
+    }
+
+    /// Register the outcome mints of `market_id`. close_batch and
+    /// record_execution then reject transactions in which another program
+    /// touches either mint before them.
+    pub fn set_market_outcome_mints(
+        ctx: Context<SetMarketOutcomeMints>,
+        market_id: String,
+        yes_mint: Pubkey,
+        no_mint: Pubkey,
+    ) -> Result<()> {
+        let market_mints = &mut ctx.accounts.market_mints;
+        market_mints.market_id = market_id.clone();
+        market_mints.yes_mint = yes_mint;
+        market_mints.no_mint = no_mint;
+        market_mints.updated_at = Clock::get()?.unix_timestamp;
+
+        emit_cpi!(MarketOutcomeMintsUpdated {
+            market_id,
+            yes_mint,
+            no_mint,
+        });
+
+        Ok(())
+    }
+
+    /// Split `market_id` into lanes by order amount: lane i takes orders
+    /// from thresholds[i - 1] up to thresholds[i], so small orders can
+    /// settle in a busy lane without waiting on large ones, and vice versa.
+    /// Thresholds must be ascending and non-zero; an empty list leaves
+    /// the market with its single create_batch lane.
+    pub fn set_market_lanes(
+        ctx: Context<SetMarketLanes>,
+        market_id: String,
+        thresholds: Vec<u64>,
+    ) -> Result<()> {
+        require!(
+            thresholds.len() < MAX_MARKET_LANES
+                && thresholds.first() != Some(&0)
+                && thresholds.windows(2).all(|pair| pair[0] < pair[1]),
+            ErrorCode::InvalidLaneThresholds
+        );
+
+        let market_lanes = &mut ctx.accounts.market_lanes;
+        market_lanes.market_id = market_id.clone();
+        market_lanes.thresholds = thresholds.clone();
+        market_lanes.updated_at = Clock::get()?.unix_timestamp;
+
+        emit_cpi!(MarketLanesUpdated {
+            market_id,
+            thresholds,
+        });
+
+        Ok(())
+    }
+
+    // ============================================================================
+    // Batch Management Instructions
+    // ============================================================================
+
+    /// Initialize a new batch.
+    ///
+    /// label: free-form client tag, e.g. a strategy name (max 32 bytes).
+    /// intent_hash: hash of the off-chain execution parameters (DFlow route,
+    /// max slippage, ...). record_execution must quote the same hash.
+    ///
+    /// `lane` picks the standard batch or, if set_express_lane enabled it,
+    /// the market's express batch; both can be open at once.
+    pub fn create_batch(
+        ctx: Context<CreateBatch>,
+        market_id: String,
+        side: u8,
+        lane: BatchLane,
+        label: Option<String>,
+        intent_hash: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+        require!(
+            lane == BatchLane::Standard || config.express_batch_window > 0,
+            ErrorCode::ExpressLaneDisabled
+        );
+
+        let label = label.unwrap_or_default();
+        let batch = &mut ctx.accounts.batch;
+        open_batch(
+            batch,
+            ctx.accounts.authority.key(),
+            config,
+            &market_id,
+            side,
+            &label,
+            intent_hash,
+        )?;
+        batch.lane = lane.index();
+        batch.sequence = ctx.accounts.batch_sequence.claim();
+        if lane == BatchLane::Express {
+            batch.fee_bps = config.express_fee_bps;
+        }
+
+        emit_cpi!(BatchCreated {
+            batch: batch.key(),
+            market_id,
+            side,
+            cluster_offset: batch.cluster_offset,
+            circuit_version: batch.circuit_version,
+            label,
+            intent_hash,
+            sequence: batch.sequence,
+        });
+
+        Ok(())
+    }
+
+    /// create_batch for one of the extra lanes set with set_market_lanes.
+    /// Each lane runs its own batch, open alongside the others; route_order
+    /// puts orders into the lane their amount falls in.
+    pub fn create_lane_batch(
+        ctx: Context<CreateLaneBatch>,
+        market_id: String,
+        side: u8,
+        lane: u8,
+        label: Option<String>,
+        intent_hash: Option<[u8; 32]>,
+    ) -> Result<()> {
+        require!(
+            lane > 0 && (lane as usize) < ctx.accounts.market_lanes.lane_count(),
+            ErrorCode::InvalidLane
+        );
+
+        let label = label.unwrap_or_default();
+        let batch = &mut ctx.accounts.batch;
+        open_batch(
+            batch,
+            ctx.accounts.authority.key(),
+            &ctx.accounts.config,
+            &market_id,
+            side,
+            &label,
+            intent_hash,
+        )?;
+        batch.lane = lane;
+        batch.sequence = ctx.accounts.batch_sequence.claim();
+
+        emit_cpi!(BatchCreated {
+            batch: batch.key(),
+            market_id,
+            side,
+            cluster_offset: batch.cluster_offset,
+            circuit_version: batch.circuit_version,
+            label,
+            intent_hash,
+            sequence: batch.sequence,
+        });
+
+        Ok(())
+    }
+
+    /// Return a snapshot of the batch lifecycle.
+    /// Read-only; clients call it via simulateTransaction and decode the return data.
+    pub fn get_batch_status(ctx: Context<GetBatchStatus>) -> Result<BatchStatusView> {
+        let batch = &ctx.accounts.batch;
+
+        Ok(BatchStatusView {
+            status: batch.status(),
+            side: batch.side,
+            order_count: batch.order_count,
+            distributions_completed: batch.distributions_completed,
+            total_usdc: batch.total_usdc,
+            total_shares: batch.total_shares,
+            created_at: batch.created_at,
+        })
+    }
+
+    /// Move an unfinished batch to the configured cluster after a failover.
+    /// Its encrypted stats are carried over with rekey_batch_stats and
+    /// import_batch_stats.
+    pub fn migrate_batch_cluster(ctx: Context<MigrateBatchCluster>) -> Result<()> {
+        let batch = &mut ctx.accounts.batch;
+        let cluster_offset = ctx.accounts.config.cluster_offset;
+
+        require!(
+            batch.status() != BatchStatus::Completed,
+            ErrorCode::BatchAlreadyCompleted
+        );
+        require!(
+            batch.cluster_offset != cluster_offset,
+            ErrorCode::ClusterUnchanged
+        );
+
+        let previous_cluster_offset = batch.cluster_offset;
+        batch.cluster_offset = cluster_offset;
+
+        emit_cpi!(BatchClusterMigrated {
+            batch: batch.key(),
+            previous_cluster_offset,
+            cluster_offset,
+            sequence: batch.sequence,
+        });
+
+        Ok(())
+    }
+
+    /// Record that an order was submitted.
+    /// The actual amount is hidden in the MPC.
+    pub fn record_order(ctx: Context<RecordOrder>) -> Result<()> {
+        check_cpi_caller(&ctx.accounts.config, &ctx.accounts.instructions)?;
+        let actor = ctx.accounts.authority.key();
+        let batch = &mut ctx.accounts.batch;
+
+        require_reported!(
+            batch.status() == BatchStatus::Open,
+            ErrorCode::BatchNotOpen,
+            batch.key(),
+            actor,
+            "record_order: status={:?}",
+            batch.status()
+        );
+        require_reported!(
+            batch.order_count < MAX_BATCH_ORDERS,
+            ErrorCode::BatchFull,
+            batch.key(),
+            actor,
+            "record_order: order_count={}",
+            batch.order_count
+        );
+
+        let slot = &mut ctx.accounts.order_slot;
+        slot.batch = batch.key();
+        slot.order_index = batch.order_count;
+        slot.distributed = false;
+
+        batch.order_count += 1;
+
+        emit_cpi!(OrderRecorded {
+            batch: batch.key(),
+            order_count: batch.order_count,
+            sequence: batch.sequence,
+        });
+
+        Ok(())
+    }
+
+    /// Record an order in the batch of the lane route_order revealed for
+    /// it. The batch must serve that lane of its market, so the relay
+    /// cannot move an order into a lane its amount doesn't fall in.
+    pub fn route_order(ctx: Context<RouteOrder>, lane: u8) -> Result<()> {
+        let actor = ctx.accounts.authority.key();
+        let batch = &mut ctx.accounts.batch;
+
+        require_reported!(
+            batch.status() == BatchStatus::Open,
+            ErrorCode::BatchNotOpen,
+            batch.key(),
+            actor,
+            "route_order: status={:?}",
+            batch.status()
+        );
+        require_reported!(
+            batch.lane == lane && (lane as usize) < ctx.accounts.market_lanes.lane_count(),
+            ErrorCode::InvalidLane,
+            batch.key(),
+            actor,
+            "route_order: lane={} batch_lane={}",
+            lane,
+            batch.lane
+        );
+        require_reported!(
+            batch.order_count < MAX_BATCH_ORDERS,
+            ErrorCode::BatchFull,
+            batch.key(),
+            actor,
+            "route_order: order_count={}",
+            batch.order_count
+        );
+
+        let slot = &mut ctx.accounts.order_slot;
+        slot.batch = batch.key();
+        slot.order_index = batch.order_count;
+        slot.distributed = false;
+
+        batch.order_count += 1;
+
+        emit_cpi!(OrderRecorded {
+            batch: batch.key(),
+            order_count: batch.order_count,
+            sequence: batch.sequence,
+        });
+        emit_cpi!(OrderRouted {
+            batch: batch.key(),
+            lane,
+            order_index: slot.order_index,
+            sequence: batch.sequence,
+        });
+
+        Ok(())
+    }
+
+    /// Post the order count peek_order_count revealed for an open batch,
+    /// for "N orders queued" displays. The encrypted stats can only hold
+    /// orders recorded here, so the count never exceeds batch.order_count.
+    pub fn post_order_count(ctx: Context<PostOrderCount>, order_count: u8) -> Result<()> {
+        let actor = ctx.accounts.authority.key();
+        let batch = &ctx.accounts.batch;
+
+        require_reported!(
+            batch.status() == BatchStatus::Open,
+            ErrorCode::BatchNotOpen,
+            batch.key(),
+            actor,
+            "post_order_count: status={:?}",
+            batch.status()
+        );
+        require_reported!(
+            order_count <= batch.order_count,
+            ErrorCode::OrderCountMismatch,
+            batch.key(),
+            actor,
+            "post_order_count: order_count={} recorded={}",
+            order_count,
+            batch.order_count
+        );
+
+        emit_cpi!(OrderCountPosted {
+            batch: batch.key(),
+            order_count,
+            posted_at: Clock::get()?.unix_timestamp,
+            sequence: batch.sequence,
+        });
+
+        Ok(())
+    }
+
+    /// Record an order submitted by a relayer for a user who signed an
+    /// OrderIntent off-chain, so the user needs no SOL for fees.
+    ///
+    /// The transaction must carry an ed25519 program instruction, directly
+    /// before this one, verifying the user's signature over
+    /// order_intent_message(intent), or that of a live session key of the
+    /// user's when `session` is passed. Intent nonces must increase per user.
+    pub fn record_delegated_order(
+        ctx: Context<RecordDelegatedOrder>,
+        intent: OrderIntent,
+    ) -> Result<()> {
+        let actor = ctx.accounts.relayer.key();
+        let batch = &mut ctx.accounts.batch;
+        let clock = Clock::get()?;
+
+        require_reported!(
+            batch.status() == BatchStatus::Open,
+            ErrorCode::BatchNotOpen,
+            batch.key(),
+            actor,
+            "record_delegated_order: status={:?}",
+            batch.status()
+        );
+        require_reported!(
+            batch.order_count < MAX_BATCH_ORDERS,
+            ErrorCode::BatchFull,
+            batch.key(),
+            actor,
+            "record_delegated_order: order_count={}",
+            batch.order_count
+        );
+        require_reported!(
+            clock.unix_timestamp <= intent.expires_at,
+            ErrorCode::OrderIntentExpired,
+            batch.key(),
+            actor,
+            "record_delegated_order: expires_at={} now={}",
+            intent.expires_at,
+            clock.unix_timestamp
+        );
+        let signer = match &ctx.accounts.session {
+            Some(session) => {
+                require_reported!(
+                    session.scope & SESSION_SCOPE_RECORD_ORDER != 0
+                        && clock.unix_timestamp < session.expires_at,
+                    ErrorCode::SessionKeyNotAuthorized,
+                    batch.key(),
+                    actor,
+                    "record_delegated_order: session scope={} expires_at={}",
+                    session.scope,
+                    session.expires_at
+                );
+                session.session_key
+            }
+            None => intent.user,
+        };
+        verify_ed25519_signature(
+            &ctx.accounts.instructions,
+            &signer,
+            &order_intent_message(&intent),
+        )?;
+        ctx.accounts
+            .intent_nonce
+            .advance(intent.user, intent.nonce)?;
+
+        let slot = &mut ctx.accounts.order_slot;
+        slot.batch = batch.key();
+        slot.order_index = batch.order_count;
+        slot.distributed = false;
+
+        batch.order_count += 1;
+
+        emit_cpi!(OrderRecorded {
+            batch: batch.key(),
+            order_count: batch.order_count,
+            sequence: batch.sequence,
+        });
+        emit_cpi!(DelegatedOrderRecorded {
+            batch: batch.key(),
+            order_index: slot.order_index,
+            user: intent.user,
+            relayer: actor,
+            order_commitment: intent.order_commitment,
+            sequence: batch.sequence,
+        });
+
+        Ok(())
+    }
+
+    /// Close the batch and record the revealed total from MPC.
+    /// Fails if an earlier instruction of the transaction trades the
+    /// market (see set_market_outcome_mints).
+    /// Returns the batch's BatchResult to CPI callers.
+    pub fn close_batch(
+        ctx: Context<CloseBatch>,
+        revealed_total: u64,
+        revealed_count: u8,
+    ) -> Result<BatchResult> {
+        check_no_preceding_market_trades(&ctx.accounts.instructions, &ctx.accounts.market_mints)?;
+        let actor = ctx.accounts.authority.key();
+        let batch = &mut ctx.accounts.batch;
+        let batch_key = batch.key();
+        let clock = Clock::get()?;
+
+        require_reported!(
+            batch.status() == BatchStatus::Open,
+            ErrorCode::BatchNotOpen,
+            batch_key,
+            actor,
+            "close_batch: status={:?}",
+            batch.status()
+        );
+        require_reported!(
+            batch.order_count > 0,
+            ErrorCode::BatchEmpty,
+            batch_key,
+            actor,
+            "close_batch: order_count=0"
+        );
+        let config = &ctx.accounts.config;
+        let stats = &mut ctx.accounts.market_stats;
+        let open_secs = clock.unix_timestamp - batch.created_at;
+        let (min_open, window) = if batch.is_express() {
+            let window = config.express_batch_window.max(MIN_BATCH_DURATION);
+            (window, window)
+        } else {
+            (
+                MIN_BATCH_DURATION.max(config.min_batch_window),
+                stats.batch_window(config),
+            )
+        };
+        let target_reached =
+            config.target_batch_orders > 0 && batch.order_count >= config.target_batch_orders;
+        require_reported!(
+            open_secs >= min_open && (open_secs >= window || target_reached),
+            ErrorCode::BatchWindowNotElapsed,
+            batch_key,
+            actor,
+            "close_batch: opens_until={}",
+            batch.created_at + window
+        );
+        stats.market_id = batch.market_id.clone();
+        if !batch.is_express() {
+            stats.record_close(batch.order_count, open_secs);
+        }
+
+        // Privacy budget: each reveal leaks a market's flow, so cap how often
+        // totals for the same market can be published.
+        let budget = &mut ctx.accounts.reveal_budget;
+        let slot = budget.next as usize;
+        require_reported!(
+            clock.unix_timestamp >= budget.reveal_times[slot] + REVEAL_WINDOW,
+            ErrorCode::RevealBudgetExhausted,
+            batch_key,
+            actor,
+            "close_batch: budget_frees_at={}",
+            budget.reveal_times[slot] + REVEAL_WINDOW
+        );
+        budget.market_id = batch.market_id.clone();
+        budget.reveal_times[slot] = clock.unix_timestamp;
+        budget.next = if slot + 1 == MAX_REVEALS_PER_WINDOW { 0 } else { slot as u8 + 1 };
+
+        batch.transition(BatchStatus::Closed)?;
+        batch.total_usdc = revealed_total;
+        batch.transitioned_at = clock.unix_timestamp;
+        batch.closed_slot = clock.slot;
+
+        // Verify count matches
+        require_reported!(
+            revealed_count == batch.order_count,
+            ErrorCode::CountMismatch,
+            batch_key,
+            actor,
+            "close_batch: revealed_count={}, order_count={}",
+            revealed_count,
+            batch.order_count
+        );
+
+        emit_cpi!(BatchClosed {
+            batch: batch.key(),
+            total_usdc: revealed_total,
+            order_count: revealed_count,
+            sequence: batch.sequence,
+        });
+
+        Ok(batch.result(batch_key))
+    }
+
+    /// Record execution result from DFlow.
+    /// filled_usdc is the USDC DFlow actually spent; anything short of
+    /// total_usdc is a partial fill and is refunded per order through the
+    /// usdc_refund of each distribution.
+    /// intent_hash must match the one the batch was created with, so the
+    /// execution is tied to the parameters clients signed up for.
+    /// Like close_batch, fails if an earlier instruction of the transaction
+    /// trades the market.
+    /// Returns the batch's BatchResult to CPI callers.
+    pub fn record_execution(
+        ctx: Context<RecordExecution>,
+        total_shares: u64,
+        filled_usdc: u64,
+        tx_signature: String,
+        intent_hash: Option<[u8; 32]>,
+    ) -> Result<BatchResult> {
+        check_no_preceding_market_trades(&ctx.accounts.instructions, &ctx.accounts.market_mints)?;
+        let actor = ctx.accounts.authority.key();
+        let batch = &mut ctx.accounts.batch;
+
+        require_reported!(
+            batch.status() == BatchStatus::Closed,
+            ErrorCode::BatchNotClosed,
+            batch.key(),
+            actor,
+            "record_execution: status={:?}",
+            batch.status()
+        );
+        require_reported!(
+            intent_hash == batch.intent_hash,
+            ErrorCode::IntentMismatch,
+            batch.key(),
+            actor,
+            "record_execution: intent_hash_provided={}, batch_has_intent={}",
+            intent_hash.is_some(),
+            batch.intent_hash.is_some()
+        );
+        require_reported!(
+            filled_usdc <= batch.total_usdc,
+            ErrorCode::FillExceedsBatch,
+            batch.key(),
+            actor,
+            "record_execution: filled_usdc={}, total_usdc={}",
+            filled_usdc,
+            batch.total_usdc
+        );
+        require_reported!(
+            batch.parked_usdc == 0,
+            ErrorCode::EscrowParked,
+            batch.key(),
+            actor,
+            "record_execution: parked_usdc={}",
+            batch.parked_usdc
+        );
+        let config = &ctx.accounts.config;
+        let clock = Clock::get()?;
+        require_reported!(
+            !batch.reveal_expired(config.max_reveal_exec_gap, clock.slot),
+            ErrorCode::RevealExpired,
+            batch.key(),
+            actor,
+            "record_execution: closed_slot={}, slot={}, max_gap={}",
+            batch.closed_slot,
+            clock.slot,
+            config.max_reveal_exec_gap
+        );
+        if config.large_batch_threshold > 0 && batch.total_usdc > config.large_batch_threshold {
+            let approvals = match &ctx.accounts.execution_approval {
+                Some(approval)
+                    if approval.total_shares == total_shares
+                        && approval.filled_usdc == filled_usdc =>
+                {
+                    approval.count_valid(config)
+                }
+                _ => 0,
+            };
+            require_reported!(
+                approvals >= config.approval_threshold as usize,
+                ErrorCode::ExecutionNotApproved,
+                batch.key(),
+                actor,
+                "record_execution: approvals={}, threshold={}, total_usdc={}",
+                approvals,
+                config.approval_threshold,
+                batch.total_usdc
+            );
+        }
+
+        batch.transition(BatchStatus::Executed)?;
+        batch.total_shares = total_shares;
+        batch.filled_usdc = filled_usdc;
+        batch.transitioned_at = clock.unix_timestamp;
+        batch.executed_slot = clock.slot;
+
+        emit_cpi!(ExecutionRecorded {
+            batch: batch.key(),
+            total_shares,
+            filled_usdc,
+            tx_signature,
+            intent_hash,
+            sequence: batch.sequence,
+        });
+        emit_cpi!(RevealExecutionGap {
+            batch: batch.key(),
+            closed_slot: batch.closed_slot,
+            executed_slot: clock.slot,
+            gap_slots: clock.slot.saturating_sub(batch.closed_slot),
+            sequence: batch.sequence,
+        });
+
+        Ok(batch.result(batch.key()))
+    }
+
+    /// Approve the execution the operator will record for a large batch.
+    /// The first approval fixes `total_shares` and `filled_usdc`; later
+    /// approvals must match them, and record_execution must too.
+    pub fn approve_execution(
+        ctx: Context<ApproveExecution>,
+        total_shares: u64,
+        filled_usdc: u64,
+    ) -> Result<()> {
+        let approver = ctx.accounts.approver.key();
+        let config = &ctx.accounts.config;
+        let batch = &ctx.accounts.batch;
+
+        require_reported!(
+            config.approvers[..config.approver_count as usize].contains(&approver),
+            ErrorCode::Unauthorized,
+            batch.key(),
+            approver,
+            "approve_execution: not an approver"
+        );
+        require_reported!(
+            batch.status() == BatchStatus::Closed,
+            ErrorCode::BatchNotClosed,
+            batch.key(),
+            approver,
+            "approve_execution: status={:?}",
+            batch.status()
+        );
+
+        let approval = &mut ctx.accounts.execution_approval;
+        if approval.approver_count == 0 {
+            approval.batch = batch.key();
+            approval.total_shares = total_shares;
+            approval.filled_usdc = filled_usdc;
+        }
+        require_reported!(
+            approval.total_shares == total_shares && approval.filled_usdc == filled_usdc,
+            ErrorCode::ExecutionApprovalMismatch,
+            batch.key(),
+            approver,
+            "approve_execution: approved total_shares={} filled_usdc={}",
+            approval.total_shares,
+            approval.filled_usdc
+        );
+        let index = approval.approver_count as usize;
+        require!(
+            !approval.approvers[..index].contains(&approver),
+            ErrorCode::AlreadyApproved
+        );
+        // Approvers replaced since the first approval may fill every slot
+        require!(
+            index < MAX_EXECUTION_APPROVERS,
+            ErrorCode::InvalidExecutionApprovers
+        );
+        approval.approvers[index] = approver;
+        approval.approver_count += 1;
+
+        emit_cpi!(ExecutionApproved {
+            batch: batch.key(),
+            approver,
+            total_shares,
+            filled_usdc,
+            approvals: approval.approver_count,
+            sequence: batch.sequence,
+        });
+
+        Ok(())
+    }
+
+    /// First phase of distribution: commit the Merkle root over every
+    /// distribution the MPC revealed for the batch (see distribution_leaf)
+    /// and how many records will follow, one per order. Set once, before
+    /// any distribution is recorded; the record paths then only accept
+    /// leaves proven against the root, so none can be added after the
+    /// reveal, and a relay taking over a half-recorded batch can tell what
+    /// is left from the batch alone. `shares` is the sum of shares across
+    /// the plaintext and stealth leaves; recorded_shares may never exceed
+    /// it.
+    pub fn stage_distributions(
+        ctx: Context<StageDistributions>,
+        root: [u8; 32],
+        count: u8,
+        shares: u64,
+    ) -> Result<()> {
+        let actor = ctx.accounts.authority.key();
+        let batch = &mut ctx.accounts.batch;
+
+        require_reported!(
+            batch.status() == BatchStatus::Executed,
+            ErrorCode::BatchNotExecuted,
+            batch.key(),
+            actor,
+            "stage_distributions: status={:?}",
+            batch.status()
+        );
+        require_reported!(
+            batch.distribution_root.is_none(),
+            ErrorCode::DistributionRootCommitted,
+            batch.key(),
+            actor,
+            "stage_distributions: root already set"
+        );
+        require_reported!(
+            count == batch.order_count,
+            ErrorCode::DistributionCountMismatch,
+            batch.key(),
+            actor,
+            "stage_distributions: count={}, order_count={}",
+            count,
+            batch.order_count
+        );
+
+        batch.distribution_root = Some(root);
+        batch.staged_distributions = count;
+        batch.staged_shares = shares;
+
+        emit_cpi!(DistributionsStaged {
+            batch: batch.key(),
+            root,
+            count,
+            shares,
+            sequence: batch.sequence,
+        });
+
+        Ok(())
+    }
+
+    /// Record a distribution (revealed from MPC) against the staged root.
+    /// proof holds the sibling hashes from the distribution's leaf up to
+    /// the batch's distribution_root.
+    /// usdc_refund is the order's share of the unfilled USDC; refunds across
+    /// the batch cannot exceed total_usdc - filled_usdc.
+    /// unlock_at optionally delays payout for markets whose shares settle
+    /// late; it is part of the leaf, so the relay cannot change it.
+    pub fn record_distribution(
+        ctx: Context<RecordDistribution>,
+        order_index: u8,
+        shares: u64,
+        usdc_refund: u64,
+        wallet: Pubkey,
+        unlock_at: Option<i64>,
+        proof: [[u8; 32]; DISTRIBUTION_TREE_DEPTH],
+        relay_nonce: u64,
+    ) -> Result<()> {
+        let actor = ctx.accounts.authority.key();
+        ctx.accounts.relay_nonce.advance(actor, relay_nonce)?;
+        let batch = &mut ctx.accounts.batch;
+        let dist = &mut ctx.accounts.distribution;
+
+        require_reported!(
+            batch.status() == BatchStatus::Executed || batch.status() == BatchStatus::Distributing,
+            ErrorCode::BatchNotExecuted,
+            batch.key(),
+            actor,
+            "record_distribution: status={:?}, order_index={}",
+            batch.status(),
+            order_index
+        );
+        require_reported!(
+            batch.recorded_distributions < batch.staged_distributions,
+            ErrorCode::DistributionsNotStaged,
+            batch.key(),
+            actor,
+            "record_distribution: order_index={}, recorded={}, staged={}",
+            order_index,
+            batch.recorded_distributions,
+            batch.staged_distributions
+        );
+        let unlock_at = unlock_at.unwrap_or(0);
+        let leaf = distribution_leaf(order_index, shares, usdc_refund, unlock_at, &wallet);
+        require_reported!(
+            batch.distribution_root == Some(distribution_proof_root(leaf, order_index, &proof)),
+            ErrorCode::InvalidDistributionProof,
+            batch.key(),
+            actor,
+            "record_distribution: order_index={}, root_committed={}",
+            order_index,
+            batch.distribution_root.is_some()
+        );
+
+        let recorded_refunds = batch
+            .recorded_refunds
+            .checked_add(usdc_refund)
+            .ok_or(ErrorCode::Overflow)?;
+        require_reported!(
+            recorded_refunds <= batch.total_usdc - batch.filled_usdc,
+            ErrorCode::RefundExceedsUnfilled,
+            batch.key(),
+            actor,
+            "record_distribution: order_index={}, recorded_refunds={}, unfilled={}",
+            order_index,
+            recorded_refunds,
+            batch.total_usdc - batch.filled_usdc
+        );
+        let recorded_shares = batch.recorded_shares_with(shares)?;
+        require_reported!(
+            recorded_shares <= batch.staged_shares,
+            ErrorCode::SharesExceedStaged,
+            batch.key(),
+            actor,
+            "record_distribution: order_index={}, recorded_shares={}, staged_shares={}",
+            order_index,
+            recorded_shares,
+            batch.staged_shares
+        );
+
+        if batch.status() == BatchStatus::Executed {
+            batch.transition(BatchStatus::Distributing)?;
+        }
+
+        dist.batch = batch.key();
+        dist.order_index = order_index;
+        dist.shares = shares;
+        dist.usdc_refund = usdc_refund;
+        dist.wallet = wallet;
+        dist.executed = false;
+        dist.shortfall_claimed = false;
+        dist.unlock_at = unlock_at;
+        ctx.accounts.order_slot.distribute()?;
+
+        batch.recorded_shares = recorded_shares;
+        batch.recorded_refunds = recorded_refunds;
+        batch.recorded_distributions += 1;
+
+        emit_cpi!(DistributionRecorded {
+            batch: batch.key(),
+            order_index,
+            shares,
+            usdc_refund,
+            wallet,
+            unlock_at: dist.unlock_at,
+            sequence: batch.sequence,
+        });
+
+        Ok(())
+    }
+
+    /// Record a distribution whose share amount stays encrypted to the
+    /// recipient (output of compute_encrypted_distribution), proven
+    /// against the staged root like record_distribution; see
+    /// encrypted_distribution_leaf.
+    /// Shares are not added to recorded_shares since the relay cannot see them.
+    pub fn record_encrypted_distribution(
+        ctx: Context<RecordEncryptedDistribution>,
+        order_index: u8,
+        encryption_key: [u8; 32],
+        nonce: u128,
+        encrypted_shares: [u8; 32],
+        wallet: Pubkey,
+        proof: [[u8; 32]; DISTRIBUTION_TREE_DEPTH],
+        relay_nonce: u64,
+    ) -> Result<()> {
+        let actor = ctx.accounts.authority.key();
+        ctx.accounts.relay_nonce.advance(actor, relay_nonce)?;
+        let batch = &mut ctx.accounts.batch;
+        let dist = &mut ctx.accounts.distribution;
+
+        require_reported!(
+            batch.status() == BatchStatus::Executed || batch.status() == BatchStatus::Distributing,
+            ErrorCode::BatchNotExecuted,
+            batch.key(),
+            actor,
+            "record_encrypted_distribution: status={:?}, order_index={}",
+            batch.status(),
+            order_index
+        );
+        require_reported!(
+            batch.recorded_distributions < batch.staged_distributions,
+            ErrorCode::DistributionsNotStaged,
+            batch.key(),
+            actor,
+            "record_encrypted_distribution: order_index={}, recorded={}, staged={}",
+            order_index,
+            batch.recorded_distributions,
+            batch.staged_distributions
+        );
+        let leaf = encrypted_distribution_leaf(
+            order_index,
+            &encryption_key,
+            nonce,
+            &encrypted_shares,
+            &wallet,
+        );
+        require_reported!(
+            batch.distribution_root == Some(distribution_proof_root(leaf, order_index, &proof)),
+            ErrorCode::InvalidDistributionProof,
+            batch.key(),
+            actor,
+            "record_encrypted_distribution: order_index={}, root_committed={}",
+            order_index,
+            batch.distribution_root.is_some()
+        );
+
+        if batch.status() == BatchStatus::Executed {
+            batch.transition(BatchStatus::Distributing)?;
+        }
+
+        dist.batch = batch.key();
+        dist.order_index = order_index;
+        dist.encryption_key = encryption_key;
+        dist.nonce = nonce;
+        dist.encrypted_shares = encrypted_shares;
+        dist.wallet = wallet;
+        dist.executed = false;
+        ctx.accounts.order_slot.distribute()?;
+        batch.recorded_distributions += 1;
+
+        emit_cpi!(EncryptedDistributionRecorded {
+            batch: batch.key(),
+            order_index,
+            wallet,
+            sequence: batch.sequence,
+        });
+
+        Ok(())
+    }
+
+    /// Record a stealth distribution.
+    /// Uses compute_distribution with a one-time claim key encrypted in place
+    /// of the destination wallet, so the revealed key links to nothing.
+    /// proof places stealth_distribution_leaf under the staged root, and the
+    /// shares count towards the staged_shares bound.
+    pub fn record_stealth_distribution(
+        ctx: Context<RecordStealthDistribution>,
+        order_index: u8,
+        shares: u64,
+        claim_key: Pubkey,
+        proof: [[u8; 32]; DISTRIBUTION_TREE_DEPTH],
+        relay_nonce: u64,
+    ) -> Result<()> {
+        let actor = ctx.accounts.authority.key();
+        ctx.accounts.relay_nonce.advance(actor, relay_nonce)?;
+        let batch = &mut ctx.accounts.batch;
+        let dist = &mut ctx.accounts.distribution;
+
+        require_reported!(
+            batch.status() == BatchStatus::Executed || batch.status() == BatchStatus::Distributing,
+            ErrorCode::BatchNotExecuted,
+            batch.key(),
+            actor,
+            "record_stealth_distribution: status={:?}, order_index={}",
+            batch.status(),
+            order_index
+        );
+        require_reported!(
+            batch.recorded_distributions < batch.staged_distributions,
+            ErrorCode::DistributionsNotStaged,
+            batch.key(),
+            actor,
+            "record_stealth_distribution: order_index={}, recorded={}, staged={}",
+            order_index,
+            batch.recorded_distributions,
+            batch.staged_distributions
+        );
+        let leaf = stealth_distribution_leaf(order_index, shares, &claim_key);
+        require_reported!(
+            batch.distribution_root == Some(distribution_proof_root(leaf, order_index, &proof)),
+            ErrorCode::InvalidDistributionProof,
+            batch.key(),
+            actor,
+            "record_stealth_distribution: order_index={}, root_committed={}",
+            order_index,
+            batch.distribution_root.is_some()
+        );
+        let recorded_shares = batch.recorded_shares_with(shares)?;
+        require_reported!(
+            recorded_shares <= batch.staged_shares,
+            ErrorCode::SharesExceedStaged,
+            batch.key(),
+            actor,
+            "record_stealth_distribution: order_index={}, recorded_shares={}, staged_shares={}",
+            order_index,
+            recorded_shares,
+            batch.staged_shares
+        );
+
+        if batch.status() == BatchStatus::Executed {
+            batch.transition(BatchStatus::Distributing)?;
+        }
+
+        dist.batch = batch.key();
+        dist.order_index = order_index;
+        dist.shares = shares;
+        dist.claim_key = claim_key;
+        dist.commitment = [0u8; 32];
+        dist.claimed = false;
+        ctx.accounts.order_slot.distribute()?;
+
+        batch.recorded_shares = recorded_shares;
+        batch.recorded_distributions += 1;
+
+        emit_cpi!(StealthDistributionRecorded {
+            batch: batch.key(),
+            order_index,
+            shares,
+            sequence: batch.sequence,
+        });
+
+        Ok(())
+    }
+
+    /// Second phase of distribution: a barrier once every staged
+    /// distribution is recorded. Payouts (mark_distributed,
+    /// claim_stealth_distribution) only start after it, so a relay that
+    /// crashes mid-recording leaves nothing paid out, and its replacement
+    /// records the missing orders and finalizes.
+    pub fn finalize_distributions(ctx: Context<FinalizeDistributions>) -> Result<()> {
+        let actor = ctx.accounts.authority.key();
+        let batch = &mut ctx.accounts.batch;
+
+        require_reported!(
+            batch.status() == BatchStatus::Distributing,
+            ErrorCode::BatchNotExecuted,
+            batch.key(),
+            actor,
+            "finalize_distributions: status={:?}",
+            batch.status()
+        );
+        require_reported!(
+            !batch.distributions_finalized,
+            ErrorCode::DistributionsAlreadyFinalized,
+            batch.key(),
+            actor,
+            "finalize_distributions: already finalized"
+        );
+        require_reported!(
+            batch.recorded_distributions == batch.staged_distributions,
+            ErrorCode::DistributionsIncomplete,
+            batch.key(),
+            actor,
+            "finalize_distributions: recorded={}, staged={}",
+            batch.recorded_distributions,
+            batch.staged_distributions
+        );
+
+        batch.distributions_finalized = true;
+
+        emit_cpi!(DistributionsFinalized {
+            batch: batch.key(),
+            count: batch.recorded_distributions,
+            sequence: batch.sequence,
+        });
+
+        Ok(())
+    }
+
+    /// Claim a stealth distribution as a privacy pool note.
+    /// Signed by the one-time claim key; the relay co-signs after verifying
+    /// the stealth_note proof that `commitment` holds exactly `shares`, and
+    /// inserts the commitment with the pool's add_commitment.
+    pub fn claim_stealth_distribution(
+        ctx: Context<ClaimStealthDistribution>,
+        commitment: [u8; 32],
+    ) -> Result<()> {
+        let batch = &mut ctx.accounts.batch;
+        let dist = &mut ctx.accounts.distribution;
+
+        require!(batch.status() != BatchStatus::Failed, ErrorCode::BatchFailed);
+        require!(batch.status() != BatchStatus::Frozen, ErrorCode::BatchFrozen);
+        require!(
+            batch.distributions_finalized,
+            ErrorCode::DistributionsNotFinalized
+        );
+        require!(!dist.claimed, ErrorCode::AlreadyDistributed);
+
+        dist.commitment = commitment;
+        dist.claimed = true;
+        batch.distributions_completed += 1;
+
+        if batch.distributions_completed == batch.order_count {
+            batch.transition(BatchStatus::Completed)?;
+        }
+
+        emit_cpi!(StealthDistributionClaimed {
+            batch: batch.key(),
+            order_index: dist.order_index,
+            commitment,
+            sequence: batch.sequence,
+        });
+
+        Ok(())
+    }
+
+    /// Pull an order's distribution instead of waiting for the relay to
+    /// push it. The order's wallet signs and proves its leaf of the staged
+    /// distribution_root with `receipt`; it is paid receipt.shares from the
+    /// batch's share vault (the batch PDA's associated account for its
+    /// side's outcome mint; see set_market_outcome_mints) and
+    /// receipt.usdc_refund from the escrow.
+    ///
+    /// The claim records and executes the distribution at once, so the
+    /// relay's part ends with stage_distributions and depositing the bought
+    /// shares in the share vault. It creates the same distribution and
+    /// order slot record_distribution does, so each order is paid once,
+    /// pushed or pulled. Claims skip finalize_distributions: the vault holds
+    /// only the shares bought, which bounds what claims can take. A leaf
+    /// with an unlock_at cannot be claimed before it, as with
+    /// mark_distributed; see Batch::check_claim.
+    pub fn claim_shares(
+        ctx: Context<ClaimShares>,
+        order_index: u8,
+        receipt: ShareReceipt,
+    ) -> Result<()> {
+        let wallet = ctx.accounts.claimant.key();
+        let batch = &ctx.accounts.batch;
+        let (recorded_refunds, recorded_shares) =
+            batch.check_claim(order_index, &wallet, &receipt, Clock::get()?.unix_timestamp)?;
+
+        let bump = [ctx.bumps.batch];
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"batch",
+            batch.authority.as_ref(),
+            batch.market_id.as_bytes(),
+            batch.lane_seed(),
+            &bump,
+        ]];
+        if receipt.shares > 0 {
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.share_token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.share_vault.to_account_info(),
+                        mint: ctx.accounts.share_mint.to_account_info(),
+                        to: ctx.accounts.recipient_shares.to_account_info(),
+                        authority: batch.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                receipt.shares,
+                ctx.accounts.share_mint.decimals,
+            )?;
+        }
+        if receipt.usdc_refund > 0 {
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.escrow.to_account_info(),
+                        mint: ctx.accounts.usdc_mint.to_account_info(),
+                        to: ctx.accounts.recipient_usdc.to_account_info(),
+                        authority: batch.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                receipt.usdc_refund,
+                ctx.accounts.usdc_mint.decimals,
+            )?;
+        }
+
+        let batch = &mut ctx.accounts.batch;
+        if batch.status() == BatchStatus::Executed {
+            batch.transition(BatchStatus::Distributing)?;
+        }
+
+        let dist = &mut ctx.accounts.distribution;
+        dist.batch = batch.key();
+        dist.order_index = order_index;
+        dist.shares = receipt.shares;
+        dist.usdc_refund = receipt.usdc_refund;
+        dist.wallet = wallet;
+        dist.executed = true;
+        dist.shortfall_claimed = false;
+        dist.unlock_at = receipt.unlock_at;
+        ctx.accounts.order_slot.distribute()?;
+
+        batch.recorded_shares = recorded_shares;
+        batch.recorded_refunds = recorded_refunds;
+        batch.recorded_distributions += 1;
+        batch.distributions_completed += 1;
+
+        emit_cpi!(SharesClaimed {
+            batch: batch.key(),
+            order_index,
+            wallet,
+            shares: receipt.shares,
+            usdc_refund: receipt.usdc_refund,
+            unlock_at: receipt.unlock_at,
+            sequence: batch.sequence,
+        });
+
+        if batch.distributions_completed == batch.order_count {
+            batch.transition(BatchStatus::Completed)?;
+
+            let stats = &mut ctx.accounts.market_stats;
+            record_completed_batch(stats, batch)?;
+
+            emit_cpi!(MarketStatsUpdated {
+                market_id: stats.market_id.clone(),
+                completed_batches: stats.completed_batches,
+                total_orders: stats.total_orders,
+                total_usdc: stats.total_usdc,
+                total_shares: stats.total_shares,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Mark distribution as executed.
+    pub fn mark_distributed(
+        ctx: Context<MarkDistributed>,
+        tx_signature: String,
+    ) -> Result<()> {
+        let actor = ctx.accounts.authority.key();
+        let batch = &mut ctx.accounts.batch;
+        let dist = &mut ctx.accounts.distribution;
+
+        require_reported!(
+            batch.status() != BatchStatus::Failed,
+            ErrorCode::BatchFailed,
+            batch.key(),
+            actor,
+            "mark_distributed: order_index={}",
+            dist.order_index
+        );
+        require_reported!(
+            batch.status() != BatchStatus::Frozen,
+            ErrorCode::BatchFrozen,
+            batch.key(),
+            actor,
+            "mark_distributed: order_index={}",
+            dist.order_index
+        );
+        require_reported!(
+            batch.distributions_finalized,
+            ErrorCode::DistributionsNotFinalized,
+            batch.key(),
+            actor,
+            "mark_distributed: order_index={}, recorded={}, staged={}",
+            dist.order_index,
+            batch.recorded_distributions,
+            batch.staged_distributions
+        );
+        require_reported!(
+            !dist.executed,
+            ErrorCode::AlreadyDistributed,
+            batch.key(),
+            actor,
+            "mark_distributed: order_index={}",
+            dist.order_index
+        );
+        require_reported!(
+            Clock::get()?.unix_timestamp >= dist.unlock_at,
+            ErrorCode::DistributionLocked,
+            batch.key(),
+            actor,
+            "mark_distributed: order_index={}, unlock_at={}",
+            dist.order_index,
+            dist.unlock_at
+        );
+
+        dist.executed = true;
+        batch.distributions_completed += 1;
+
+        if batch.distributions_completed == batch.order_count {
+            batch.transition(BatchStatus::Completed)?;
+
+            let stats = &mut ctx.accounts.market_stats;
+            record_completed_batch(stats, batch)?;
+
+            emit_cpi!(MarketStatsUpdated {
+                market_id: stats.market_id.clone(),
+                completed_batches: stats.completed_batches,
+                total_orders: stats.total_orders,
+                total_usdc: stats.total_usdc,
+                total_shares: stats.total_shares,
+            });
+        }
+
+        emit_cpi!(DistributionExecuted {
+            batch: batch.key(),
+            order_index: dist.order_index,
+            tx_signature,
+            sequence: batch.sequence,
+        });
+
+        Ok(())
+    }
+
+    /// mark_distributed for up to MAX_DISTRIBUTIONS_PER_IX orders paid out
+    /// by the same transfer transaction. Pass each order's distribution
+    /// account, writable and in `order_indices` order, as
+    /// remaining_accounts. Settling a batch then takes
+    /// ceil(order_count / MAX_DISTRIBUTIONS_PER_IX) transactions instead of
+    /// one per order.
+    pub fn mark_distributed_many<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MarkDistributedMany<'info>>,
+        order_indices: Vec<u8>,
+        tx_signature: String,
+    ) -> Result<()> {
+        let actor = ctx.accounts.authority.key();
+        let batch = &mut ctx.accounts.batch;
+
+        require_reported!(
+            !order_indices.is_empty() && order_indices.len() <= MAX_DISTRIBUTIONS_PER_IX,
+            ErrorCode::DistributionCountOutOfRange,
+            batch.key(),
+            actor,
+            "mark_distributed_many: count={}, max={}",
+            order_indices.len(),
+            MAX_DISTRIBUTIONS_PER_IX
+        );
+        require_reported!(
+            ctx.remaining_accounts.len() == order_indices.len(),
+            ErrorCode::DistributionAccountMismatch,
+            batch.key(),
+            actor,
+            "mark_distributed_many: accounts={}, order_indices={}",
+            ctx.remaining_accounts.len(),
+            order_indices.len()
+        );
+        require_reported!(
+            batch.status() != BatchStatus::Failed,
+            ErrorCode::BatchFailed,
+            batch.key(),
+            actor,
+            "mark_distributed_many: order_indices={:?}",
+            order_indices
+        );
+        require_reported!(
+            batch.status() != BatchStatus::Frozen,
+            ErrorCode::BatchFrozen,
+            batch.key(),
+            actor,
+            "mark_distributed_many: order_indices={:?}",
+            order_indices
+        );
+        require_reported!(
+            batch.distributions_finalized,
+            ErrorCode::DistributionsNotFinalized,
+            batch.key(),
+            actor,
+            "mark_distributed_many: order_indices={:?}, recorded={}, staged={}",
+            order_indices,
+            batch.recorded_distributions,
+            batch.staged_distributions
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        for (info, &order_index) in ctx.remaining_accounts.iter().zip(&order_indices) {
+            // Distributions are only ever created at their (batch, order
+            // index) PDA, so a program-owned one naming both is that PDA
+            let mut dist = Account::<Distribution>::try_from(info)?;
+            require_reported!(
+                info.is_writable && dist.batch == batch.key() && dist.order_index == order_index,
+                ErrorCode::DistributionAccountMismatch,
+                batch.key(),
+                actor,
+                "mark_distributed_many: order_index={}, account={}",
+                order_index,
+                info.key()
+            );
+            // Persisted before the next account is loaded, so an order
+            // listed twice fails here on its second appearance
+            require_reported!(
+                !dist.executed,
+                ErrorCode::AlreadyDistributed,
+                batch.key(),
+                actor,
+                "mark_distributed_many: order_index={}",
+                order_index
+            );
+            require_reported!(
+                now >= dist.unlock_at,
+                ErrorCode::DistributionLocked,
+                batch.key(),
+                actor,
+                "mark_distributed_many: order_index={}, unlock_at={}",
+                order_index,
+                dist.unlock_at
+            );
+
+            dist.executed = true;
+            dist.exit(&crate::ID)?;
+            batch.distributions_completed += 1;
+
+            emit_cpi!(DistributionExecuted {
+                batch: batch.key(),
+                order_index,
+                tx_signature: tx_signature.clone(),
+                sequence: batch.sequence,
+            });
+        }
+
+        if batch.distributions_completed == batch.order_count {
+            batch.transition(BatchStatus::Completed)?;
+
+            let stats = &mut ctx.accounts.market_stats;
+            record_completed_batch(stats, batch)?;
+
+            emit_cpi!(MarketStatsUpdated {
+                market_id: stats.market_id.clone(),
+                completed_batches: stats.completed_batches,
+                total_orders: stats.total_orders,
+                total_usdc: stats.total_usdc,
+                total_shares: stats.total_shares,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Fail a batch the relay stopped advancing: closed but not executed
+    /// within config.execution_timeout, or executed but not completed
+    /// within config.completion_timeout, or frozen by reconcile_batch for
+    /// config.completion_timeout. Permissionless, so funds never depend on
+    /// the relay staying up. Orders of a failed batch are refunded in full
+    /// through record_credit with CreditReason::Refund.
+    pub fn force_fail_batch(ctx: Context<ForceFailBatch>) -> Result<()> {
+        let config = &ctx.accounts.config;
+        let batch = &mut ctx.accounts.batch;
+        let now = Clock::get()?.unix_timestamp;
+
+        let timeout = match batch.status() {
+            BatchStatus::Closed => config.execution_timeout,
+            BatchStatus::Executed | BatchStatus::Distributing | BatchStatus::Frozen => {
+                config.completion_timeout
+            }
+            _ => 0,
+        };
+        require!(
+            timeout > 0 && now >= batch.transitioned_at + timeout,
+            ErrorCode::BatchNotStuck
+        );
+
+        let previous_status = batch.status();
+        batch.transition(BatchStatus::Failed)?;
+        batch.transitioned_at = now;
+
+        emit_cpi!(BatchFailed {
+            batch: batch.key(),
+            previous_status,
+            order_count: batch.order_count,
+            total_usdc: batch.total_usdc,
+            sequence: batch.sequence,
+        });
+
+        Ok(())
+    }
+
+    /// Reopen a Closed batch whose revealed total has waited longer than
+    /// config.max_reveal_exec_gap slots for execution, so the relay must
+    /// close and reveal it again before trading. Pending execution
+    /// approvals are reset: they approved a trade against the old reveal.
+    /// The approval PDA is always passed, so a caller cannot leave it out
+    /// to keep them. Permissionless, like force_fail_batch.
+    pub fn expire_reveal(ctx: Context<ExpireReveal>) -> Result<()> {
+        let config = &ctx.accounts.config;
+        let batch = &mut ctx.accounts.batch;
+        let clock = Clock::get()?;
+
+        require!(
+            batch.status() == BatchStatus::Closed,
+            ErrorCode::BatchNotClosed
+        );
+        require!(
+            batch.reveal_expired(config.max_reveal_exec_gap, clock.slot),
+            ErrorCode::RevealNotExpired
+        );
+        // Parked escrow has to come back before the batch can reopen
+        require!(batch.parked_usdc == 0, ErrorCode::EscrowParked);
+
+        batch.transition(BatchStatus::Open)?;
+        batch.transitioned_at = clock.unix_timestamp;
+        let approval_info = &ctx.accounts.execution_approval;
+        if approval_info.owner == &ID && !approval_info.data_is_empty() {
+            let mut approval =
+                ExecutionApproval::try_deserialize(&mut &approval_info.try_borrow_data()?[..])?;
+            approval.approver_count = 0;
+            let mut data = approval_info.try_borrow_mut_data()?;
+            approval.try_serialize(&mut &mut data[..])?;
+        }
+
+        emit_cpi!(RevealExpired {
+            batch: batch.key(),
+            closed_slot: batch.closed_slot,
+            slot: clock.slot,
+            max_reveal_exec_gap: config.max_reveal_exec_gap,
+            sequence: batch.sequence,
+        });
+
+        Ok(())
+    }
+
+    /// Check the batch escrow (the batch PDA's USDC associated token
+    /// account) against the batch's accounting (see
+    /// Batch::escrow_shortfall). If it holds less, the batch is frozen and
+    /// ReconciliationMismatch emitted instead of failing, so the freeze
+    /// sticks; a surplus is left alone. Permissionless, so anyone watching
+    /// can catch relay drift.
+    pub fn reconcile_batch(ctx: Context<ReconcileBatch>) -> Result<()> {
+        let batch = &mut ctx.accounts.batch;
+        let actual = ctx.accounts.escrow.amount;
+        let Some(expected) = batch.escrow_shortfall(actual)? else {
+            return Ok(());
+        };
+
+        let previous_status = batch.status();
+        batch.transition(BatchStatus::Frozen)?;
+        batch.transitioned_at = Clock::get()?.unix_timestamp;
+
+        emit_cpi!(ReconciliationMismatch {
+            batch: batch.key(),
+            previous_status,
+            expected,
+            actual,
+            sequence: batch.sequence,
+        });
+
+        Ok(())
+    }
+
+    // ============================================================================
+    // Yield Adapter
+    // ============================================================================
+
+    /// Park `amount` of a closed batch's escrow in the whitelisted yield
+    /// adapter until execution. Pass the adapter's own accounts as
+    /// remaining_accounts. The escrow must drop by exactly `amount`.
+    pub fn park_escrow<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ParkEscrow<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        let actor = ctx.accounts.authority.key();
+        let config = &ctx.accounts.config;
+        let batch = &ctx.accounts.batch;
+
+        require!(
+            config.yield_enabled && config.yield_adapter != Pubkey::default(),
+            ErrorCode::YieldDisabled
+        );
+        require_reported!(
+            batch.status() == BatchStatus::Closed,
+            ErrorCode::BatchNotClosed,
+            batch.key(),
+            actor,
+            "park_escrow: status={:?}",
+            batch.status()
+        );
+        require_reported!(
+            batch.parked_usdc == 0,
+            ErrorCode::EscrowParked,
+            batch.key(),
+            actor,
+            "park_escrow: parked_usdc={}",
+            batch.parked_usdc
+        );
+
+        let before = ctx.accounts.escrow.amount;
+        invoke_yield_adapter(
+            &ctx.accounts.adapter_program,
+            YIELD_DEPOSIT_DISCRIMINATOR,
+            Some(amount),
+            &ctx.accounts.escrow.to_account_info(),
+            &ctx.accounts.batch,
+            ctx.bumps.batch,
+            &ctx.accounts.usdc_mint.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+            ctx.remaining_accounts,
+        )?;
+        ctx.accounts.escrow.reload()?;
+        require_reported!(
+            before.checked_sub(ctx.accounts.escrow.amount) == Some(amount),
+            ErrorCode::AdapterAccountingMismatch,
+            ctx.accounts.batch.key(),
+            actor,
+            "park_escrow: before={}, after={}, amount={}",
+            before,
+            ctx.accounts.escrow.amount,
+            amount
+        );
+
+        let batch = &mut ctx.accounts.batch;
+        batch.parked_usdc = amount;
+        batch.parked_adapter = config.yield_adapter;
+
+        emit_cpi!(EscrowParked {
+            batch: batch.key(),
+            adapter: batch.parked_adapter,
+            amount,
+            sequence: batch.sequence,
+        });
+
+        Ok(())
+    }
+
+    /// Recall a batch's parked escrow from the adapter it was parked in.
+    /// At least the parked amount must come back; earnings above it go to
+    /// config.yield_recipient so the escrow matches the batch accounting.
+    /// Permissionless, so parked funds never depend on the relay.
+    pub fn recall_escrow<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RecallEscrow<'info>>,
+    ) -> Result<()> {
+        let principal = ctx.accounts.batch.parked_usdc;
+        require!(principal > 0, ErrorCode::NothingParked);
+
+        let before = ctx.accounts.escrow.amount;
+        invoke_yield_adapter(
+            &ctx.accounts.adapter_program,
+            YIELD_WITHDRAW_DISCRIMINATOR,
+            None,
+            &ctx.accounts.escrow.to_account_info(),
+            &ctx.accounts.batch,
+            ctx.bumps.batch,
+            &ctx.accounts.usdc_mint.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+            ctx.remaining_accounts,
+        )?;
+        ctx.accounts.escrow.reload()?;
+        let returned = ctx.accounts.escrow.amount.saturating_sub(before);
+        require!(returned >= principal, ErrorCode::AdapterShortfall);
+
+        let earnings = returned - principal;
+        if earnings > 0 {
+            let batch = &ctx.accounts.batch;
+            let bump = [ctx.bumps.batch];
+            let signer_seeds: &[&[&[u8]]] = &[&[
+                b"batch",
+                batch.authority.as_ref(),
+                batch.market_id.as_bytes(),
+                batch.lane_seed(),
+                &bump,
+            ]];
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.escrow.to_account_info(),
+                        mint: ctx.accounts.usdc_mint.to_account_info(),
+                        to: ctx.accounts.yield_recipient.to_account_info(),
+                        authority: batch.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                earnings,
+                ctx.accounts.usdc_mint.decimals,
+            )?;
+        }
+
+        let batch = &mut ctx.accounts.batch;
+        let adapter = batch.parked_adapter;
+        batch.parked_usdc = 0;
+        batch.parked_adapter = Pubkey::default();
+
+        emit_cpi!(EscrowRecalled {
+            batch: batch.key(),
+            adapter,
+            principal,
+            earnings,
+            sequence: batch.sequence,
+        });
+
+        Ok(())
+    }
+
+    // ============================================================================
+    // Portfolios
+    // ============================================================================
+
+    /// Store a user's encrypted portfolio (output of init_portfolio or
+    /// accumulate_position). The ciphertexts are encrypted to the MXE, so
+    /// the account shows only that `owner` has a portfolio - not which
+    /// markets it holds or how many shares.
+    pub fn store_portfolio(
+        ctx: Context<StorePortfolio>,
+        owner: Pubkey,
+        nonce: u128,
+        encrypted_positions: [[u8; 32]; PORTFOLIO_CIPHERTEXTS],
+    ) -> Result<()> {
+        let portfolio = &mut ctx.accounts.portfolio;
+
+        portfolio.authority = ctx.accounts.authority.key();
+        portfolio.owner = owner;
+        portfolio.nonce = nonce;
+        portfolio.encrypted_positions = encrypted_positions;
+        portfolio.updates += 1;
+        portfolio.updated_at = Clock::get()?.unix_timestamp;
+
+        emit_cpi!(PortfolioUpdated {
+            owner,
+            updates: portfolio.updates,
+        });
+
+        Ok(())
+    }
+
+    // ============================================================================
+    // Insurance Fund
+    // ============================================================================
+
+    /// Create the insurance fund and its USDC vault.
+    /// fee_share_bps is the slice of protocol fees the relay forwards to the fund.
+    pub fn initialize_insurance_fund(
+        ctx: Context<InitializeInsuranceFund>,
+        fee_share_bps: u16,
+    ) -> Result<()> {
+        require!(fee_share_bps <= 10_000, ErrorCode::InvalidFeeShare);
+        check_usdc_mint(&ctx.accounts.usdc_mint.to_account_info())?;
+
+        let fund = &mut ctx.accounts.insurance_fund;
+        fund.authority = ctx.accounts.authority.key();
+        fund.usdc_mint = ctx.accounts.usdc_mint.key();
+        fund.vault = ctx.accounts.vault.key();
+        fund.fee_share_bps = fee_share_bps;
+        fund.total_funded = 0;
+        fund.total_claimed = 0;
+        fund.bump = ctx.bumps.insurance_fund;
+
+        Ok(())
+    }
+
+    /// Deposit USDC into the insurance fund. The fund counts what the vault
+    /// receives, after any transfer fee.
+    pub fn fund_insurance(ctx: Context<FundInsurance>, amount: u64) -> Result<()> {
+        let received = received_amount(&ctx.accounts.usdc_mint.to_account_info(), amount)?;
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.funder_usdc.to_account_info(),
+                    mint: ctx.accounts.usdc_mint.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.funder.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.usdc_mint.decimals,
+        )?;
+
+        let fund = &mut ctx.accounts.insurance_fund;
+        fund.total_funded = fund
+            .total_funded
+            .checked_add(received)
+            .ok_or(ErrorCode::Overflow)?;
+
+        emit_cpi!(InsuranceFunded {
+            funder: ctx.accounts.funder.key(),
+            amount: received,
+            total_funded: fund.total_funded,
+        });
+
+        Ok(())
+    }
+
+    /// Compensate a distribution when the batch's recorded distributions
+    /// exceed the shares actually executed (relay error).
+    ///
+    /// The order paid filled_usdc * shares / recorded_shares and is short by
+    /// the fraction shortfall / recorded_shares, so it is refunded
+    /// filled_usdc * shares * shortfall / recorded_shares^2 in USDC.
+    /// Permissionless: the payout can only go to the distribution's wallet.
+    pub fn claim_shortfall(ctx: Context<ClaimShortfall>) -> Result<()> {
+        let batch = &ctx.accounts.batch;
+        let dist = &ctx.accounts.distribution;
+
+        require!(
+            batch.status() == BatchStatus::Completed,
+            ErrorCode::BatchNotCompleted
+        );
+        require!(!dist.shortfall_claimed, ErrorCode::ShortfallAlreadyClaimed);
+        require!(
+            batch.recorded_shares > batch.total_shares,
+            ErrorCode::NoShortfall
+        );
+
+        let shortfall = (batch.recorded_shares - batch.total_shares) as u128;
+        let recorded = batch.recorded_shares as u128;
+        let compensation = ((batch.filled_usdc as u128) * (dist.shares as u128) * shortfall
+            / (recorded * recorded)) as u64;
+        require!(compensation > 0, ErrorCode::NoShortfall);
+
+        let bump = ctx.accounts.insurance_fund.bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[b"insurance_fund", &[bump]]];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.vault.to_account_info(),
+                    mint: ctx.accounts.usdc_mint.to_account_info(),
+                    to: ctx.accounts.recipient_usdc.to_account_info(),
+                    authority: ctx.accounts.insurance_fund.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            compensation,
+            ctx.accounts.usdc_mint.decimals,
+        )?;
+
+        ctx.accounts.distribution.shortfall_claimed = true;
+        let fund = &mut ctx.accounts.insurance_fund;
+        fund.total_claimed = fund
+            .total_claimed
+            .checked_add(compensation)
+            .ok_or(ErrorCode::Overflow)?;
+
+        emit_cpi!(ShortfallClaimed {
+            batch: ctx.accounts.batch.key(),
+            order_index: ctx.accounts.distribution.order_index,
+            wallet: ctx.accounts.distribution.wallet,
+            amount: compensation,
+            sequence: ctx.accounts.batch.sequence,
+        });
+
+        Ok(())
+    }
+
+    // ============================================================================
+    // User Credits
+    // ============================================================================
+
+    /// Create the credit ledger and the USDC vault backing user credits.
+    /// Its mint is the protocol's USDC, which batch escrows also hold, so
+    /// mints with unsupported Token-2022 extensions are rejected here.
+    pub fn initialize_credit_ledger(ctx: Context<InitializeCreditLedger>) -> Result<()> {
+        check_usdc_mint(&ctx.accounts.usdc_mint.to_account_info())?;
+
+        let ledger = &mut ctx.accounts.credit_ledger;
+        ledger.usdc_mint = ctx.accounts.usdc_mint.key();
+        ledger.vault = ctx.accounts.vault.key();
+        ledger.total_credited = 0;
+        ledger.total_claimed = 0;
+        ledger.bump = ctx.bumps.credit_ledger;
+
+        Ok(())
+    }
+
+    /// Credit `owner` with USDC the relay holds on their behalf: dust left
+    /// by add_to_batch_normalized, rounding remainders and refunds.
+    /// The settler deposits the amount into the vault in the same
+    /// instruction, so every credit is backed; the owner is credited what
+    /// the vault receives, after any transfer fee.
+    pub fn record_credit(
+        ctx: Context<RecordCredit>,
+        owner: Pubkey,
+        amount: u64,
+        reason: CreditReason,
+        batch: Pubkey,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::ZeroCredit);
+        let received = received_amount(&ctx.accounts.usdc_mint.to_account_info(), amount)?;
+        require!(received > 0, ErrorCode::ZeroCredit);
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.settler_usdc.to_account_info(),
+                    mint: ctx.accounts.usdc_mint.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.usdc_mint.decimals,
+        )?;
+
+        let credit = &mut ctx.accounts.user_credit;
+        credit.owner = owner;
+        credit.balance = credit
+            .balance
+            .checked_add(received)
+            .ok_or(ErrorCode::Overflow)?;
+        credit.total_credited = credit
+            .total_credited
+            .checked_add(received)
+            .ok_or(ErrorCode::Overflow)?;
+        credit.updated_at = Clock::get()?.unix_timestamp;
+        let ledger = &mut ctx.accounts.credit_ledger;
+        ledger.total_credited = ledger
+            .total_credited
+            .checked_add(received)
+            .ok_or(ErrorCode::Overflow)?;
+
+        emit_cpi!(CreditRecorded {
+            owner,
+            amount: received,
+            reason,
+            batch,
+            balance: credit.balance,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw `amount` of the signer's accumulated credit to their USDC
+    /// account.
+    pub fn claim_credit(ctx: Context<ClaimCredit>, amount: u64) -> Result<()> {
+        let credit = &mut ctx.accounts.user_credit;
+        require!(
+            amount > 0 && amount <= credit.balance,
+            ErrorCode::InsufficientCredit
+        );
+
+        let bump = ctx.accounts.credit_ledger.bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[b"credit_ledger", &[bump]]];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.vault.to_account_info(),
+                    mint: ctx.accounts.usdc_mint.to_account_info(),
+                    to: ctx.accounts.recipient_usdc.to_account_info(),
+                    authority: ctx.accounts.credit_ledger.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+            ctx.accounts.usdc_mint.decimals,
+        )?;
+
+        credit.balance -= amount;
+        credit.total_claimed = credit
+            .total_claimed
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        credit.updated_at = Clock::get()?.unix_timestamp;
+        let ledger = &mut ctx.accounts.credit_ledger;
+        ledger.total_claimed = ledger
+            .total_claimed
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        emit_cpi!(CreditClaimed {
+            owner: credit.owner,
+            amount,
+            balance: credit.balance,
+        });
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // Session Keys
+    // ========================================================================
+
+    /// Authorize `session_key` to act for the signing user until `expiry`,
+    /// limited to the SESSION_SCOPE_* bits in `scope`.
+    pub fn create_session_key(
+        ctx: Context<CreateSessionKey>,
+        expiry: i64,
+        scope: u8,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            scope != 0 && scope & !SESSION_SCOPES == 0,
+            ErrorCode::InvalidSessionScope
+        );
+        require!(
+            expiry > now && expiry - now <= MAX_SESSION_DURATION,
+            ErrorCode::InvalidSessionExpiry
+        );
+
+        let session = &mut ctx.accounts.session;
+        session.user = ctx.accounts.user.key();
+        session.session_key = ctx.accounts.session_key.key();
+        session.expires_at = expiry;
+        session.scope = scope;
+        session.created_at = now;
+
+        emit_cpi!(SessionKeyCreated {
+            user: session.user,
+            session_key: session.session_key,
+            expires_at: expiry,
+            scope,
+        });
+
+        Ok(())
+    }
+
+    /// End a session early and reclaim its rent.
+    pub fn revoke_session_key(ctx: Context<RevokeSessionKey>) -> Result<()> {
+        emit_cpi!(SessionKeyRevoked {
+            user: ctx.accounts.session.user,
+            session_key: ctx.accounts.session.session_key,
+        });
+
+        Ok(())
+    }
+
+    // ========================================================================
+    // Subscriptions
+    // ========================================================================
+
+    /// Subscribe the signing owner to `orders` recurring orders on one side
+    /// of a market, at most one per `cadence` seconds. `ciphertext_ref` is
+    /// the hash of the owner's `Enc<Shared, OrderData>`, which the relay
+    /// keeps and submits to the MXE for every batch the subscription is
+    /// enrolled in; the amount never appears on chain.
+    ///
+    /// With `jitter_bps` > 0 the ciphertext holds the total to spend over
+    /// all `orders` instead. The relay turns it into a DcaSchedule with
+    /// init_dca_schedule, and add_dca_to_batch draws each enrollment's
+    /// amount from it, up to `jitter_bps` off an even split.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_subscription(
+        ctx: Context<CreateSubscription>,
+        id: u64,
+        market_id: String,
+        side: u8,
+        cadence: i64,
+        orders: u32,
+        ciphertext_ref: [u8; 32],
+        jitter_bps: u16,
+    ) -> Result<()> {
+        require!(
+            !market_id.is_empty()
+                && market_id.len() <= MAX_SUBSCRIPTION_MARKET_ID_LEN
+                && cadence >= MIN_BATCH_DURATION.max(1)
+                && orders > 0
+                && jitter_bps <= MAX_DCA_JITTER_BPS,
+            ErrorCode::InvalidSubscription
+        );
+        let now = Clock::get()?.unix_timestamp;
+
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.owner = ctx.accounts.owner.key();
+        subscription.id = id;
+        subscription.market_id = market_id.clone();
+        subscription.side = side;
+        subscription.cadence = cadence;
+        subscription.next_enroll_at = now;
+        subscription.remaining = orders;
+        subscription.ciphertext_ref = ciphertext_ref;
+        subscription.last_batch = Pubkey::default();
+        subscription.enrolled = 0;
+        subscription.created_at = now;
+        subscription.jitter_bps = jitter_bps;
+        subscription.schedule_nonce = 0;
+        subscription.encrypted_schedule = [[0; 32]; DCA_SCHEDULE_CIPHERTEXTS];
+
+        emit_cpi!(SubscriptionCreated {
+            subscription: subscription.key(),
+            owner: subscription.owner,
+            market_id,
+            side,
+            cadence,
+            orders,
+            jitter_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Store a jittered subscription's encrypted DcaSchedule, as output by
+    /// init_dca_schedule or add_dca_to_batch. It is encrypted to the MXE,
+    /// so neither the total nor what is left of it can be read here.
+    pub fn store_dca_schedule(
+        ctx: Context<StoreDcaSchedule>,
+        nonce: u128,
+        encrypted_schedule: [[u8; 32]; DCA_SCHEDULE_CIPHERTEXTS],
+    ) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        require!(subscription.jitter_bps > 0, ErrorCode::InvalidSubscription);
+
+        subscription.schedule_nonce = nonce;
+        subscription.encrypted_schedule = encrypted_schedule;
+
+        emit_cpi!(DcaScheduleStored {
+            subscription: subscription.key(),
+            remaining: subscription.remaining,
+        });
+
+        Ok(())
+    }
+
+    /// Stop a subscription and reclaim its rent. Orders already enrolled
+    /// stay in their batches.
+    pub fn cancel_subscription(ctx: Context<CancelSubscription>) -> Result<()> {
+        emit_cpi!(SubscriptionCancelled {
+            subscription: ctx.accounts.subscription.key(),
+            owner: ctx.accounts.subscription.owner,
+            remaining: ctx.accounts.subscription.remaining,
+        });
+
+        Ok(())
+    }
+
+    /// Crank: record a due subscription's order in an open batch of its
+    /// market and side, as record_order would, and schedule the next one
+    /// `cadence` seconds out. A subscription enrolls at most once per batch.
+    /// The event names the subscription but not its owner.
+    pub fn enroll_subscription(ctx: Context<EnrollSubscription>) -> Result<()> {
+        let actor = ctx.accounts.authority.key();
+        let now = Clock::get()?.unix_timestamp;
+        let batch = &mut ctx.accounts.batch;
+        let subscription = &mut ctx.accounts.subscription;
+
+        require_reported!(
+            batch.status() == BatchStatus::Open,
+            ErrorCode::BatchNotOpen,
+            batch.key(),
+            actor,
+            "enroll_subscription: status={:?}",
+            batch.status()
+        );
+        require_reported!(
+            batch.order_count < MAX_BATCH_ORDERS,
+            ErrorCode::BatchFull,
+            batch.key(),
+            actor,
+            "enroll_subscription: order_count={}",
+            batch.order_count
+        );
+        require!(
+            batch.market_id == subscription.market_id && batch.side == subscription.side,
+            ErrorCode::SubscriptionMarketMismatch
+        );
+        require!(
+            subscription.is_due(&batch.key(), now),
+            ErrorCode::SubscriptionNotDue
+        );
+
+        let slot = &mut ctx.accounts.order_slot;
+        slot.batch = batch.key();
+        slot.order_index = batch.order_count;
+        slot.distributed = false;
+
+        batch.order_count += 1;
+        subscription.enroll(batch.key(), now);
+
+        emit_cpi!(OrderRecorded {
+            batch: batch.key(),
+            order_count: batch.order_count,
+            sequence: batch.sequence,
+        });
+        emit_cpi!(SubscriptionEnrolled {
+            subscription: subscription.key(),
+            batch: batch.key(),
+            order_index: slot.order_index,
+            ciphertext_ref: subscription.ciphertext_ref,
+            remaining: subscription.remaining,
+            sequence: batch.sequence,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Accounts
+// ============================================================================
+
+/// Anchor return data of close_batch and record_execution, so programs
+/// composing over CPI can branch on the outcome without reloading the
+/// batch account.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BatchResult {
+    pub batch: Pubkey,
+    pub status: BatchStatus,
+    pub order_count: u8,
+    pub total_usdc: u64,
+    /// 0 until record_execution
+    pub total_shares: u64,
+    /// 0 until record_execution
+    pub filled_usdc: u64,
+}
+
+#[account]
+pub struct Distribution {
+    pub batch: Pubkey,
+    pub order_index: u8,
+    pub shares: u64,
+    /// USDC returned for the unfilled part of the order
+    pub usdc_refund: u64,
+    pub wallet: Pubkey,
+    pub executed: bool,
+    pub shortfall_claimed: bool,
+    /// Payout not allowed before this timestamp (0 = unlocked)
+    pub unlock_at: i64,
+}
+
+/// One recorded order of a batch, created by record_order. A distribution
+/// of any kind can only be recorded against an existing slot, once.
+#[account]
+pub struct OrderSlot {
+    pub batch: Pubkey,
+    pub order_index: u8,
+    pub distributed: bool,
+}
+
+impl OrderSlot {
+    /// Mark the order paid by a distribution; fails if one already was,
+    /// whichever path recorded it.
+    pub fn distribute(&mut self) -> Result<()> {
+        require!(!self.distributed, ErrorCode::OrderAlreadyDistributed);
+        self.distributed = true;
+        Ok(())
+    }
+}
+
+/// Proof that a batch's staged distribution pays an order's wallet:
+/// the leaf's amounts and unlock time, and the sibling hashes up to
+/// distribution_root.
+/// claim_shares takes the wallet and order index from the claim itself.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ShareReceipt {
+    pub shares: u64,
+    pub usdc_refund: u64,
+    pub unlock_at: i64,
+    pub proof: [[u8; 32]; DISTRIBUTION_TREE_DEPTH],
+}
+
+/// Leaf of a batch's distribution tree:
+/// sha256(order_index || shares LE || usdc_refund LE || unlock_at LE || wallet),
+/// unlock_at 0 for distributions payable at once.
+/// Unused order indices hold a zero leaf.
+pub fn distribution_leaf(
+    order_index: u8,
+    shares: u64,
+    usdc_refund: u64,
+    unlock_at: i64,
+    wallet: &Pubkey,
+) -> [u8; 32] {
+    hashv(&[
+        [order_index].as_ref(),
+        &shares.to_le_bytes(),
+        &usdc_refund.to_le_bytes(),
+        &unlock_at.to_le_bytes(),
+        wallet.as_ref(),
+    ])
+    .to_bytes()
+}
+
+/// Leaf of an encrypted distribution:
+/// sha256(order_index || encryption_key || nonce LE || encrypted_shares || wallet).
+/// It commits to the ciphertext the recipient decrypts, and its preimage
+/// length keeps it apart from the other leaf kinds.
+pub fn encrypted_distribution_leaf(
+    order_index: u8,
+    encryption_key: &[u8; 32],
+    nonce: u128,
+    encrypted_shares: &[u8; 32],
+    wallet: &Pubkey,
+) -> [u8; 32] {
+    hashv(&[
+        [order_index].as_ref(),
+        encryption_key,
+        &nonce.to_le_bytes(),
+        encrypted_shares,
+        wallet.as_ref(),
+    ])
+    .to_bytes()
+}
+
+/// Leaf of a stealth distribution:
+/// sha256(order_index || shares LE || claim_key).
+pub fn stealth_distribution_leaf(order_index: u8, shares: u64, claim_key: &Pubkey) -> [u8; 32] {
+    hashv(&[
+        [order_index].as_ref(),
+        &shares.to_le_bytes(),
+        claim_key.as_ref(),
+    ])
+    .to_bytes()
+}
+
+/// Fill in a batch freshly created by create_batch or create_lane_batch.
+fn open_batch(
+    batch: &mut Batch,
+    authority: Pubkey,
+    config: &ProtocolConfig,
+    market_id: &str,
+    side: u8,
+    label: &str,
+    intent_hash: Option<[u8; 32]>,
+) -> Result<()> {
+    require!(label.len() <= MAX_BATCH_LABEL_LEN, ErrorCode::LabelTooLong);
+    let clock = Clock::get()?;
+
+    batch.authority = authority;
+    batch.market_id = market_id.to_string();
+    batch.side = side;
+    // status starts Open: `init` leaves the account zeroed
+    batch.order_count = 0;
+    batch.total_usdc = 0;
+    batch.total_shares = 0;
+    batch.created_at = clock.unix_timestamp;
+    batch.cluster_offset = config.cluster_offset;
+    batch.circuit_version = config.circuit_version;
+    batch.label = label.to_string();
+    batch.intent_hash = intent_hash;
+    batch.filled_usdc = 0;
+    batch.recorded_refunds = 0;
+    batch.distribution_root = None;
+    batch.transitioned_at = clock.unix_timestamp;
+    batch.parked_usdc = 0;
+    batch.parked_adapter = Pubkey::default();
+    Ok(())
+}
+
+/// Roll a completed batch into its market aggregates. Only batch-level
+/// totals are recorded - never per-order data.
+fn record_completed_batch(stats: &mut MarketStats, batch: &Batch) -> Result<()> {
+    stats.market_id = batch.market_id.clone();
+    stats.completed_batches += 1;
+    stats.total_orders += batch.order_count as u64;
+    stats.total_usdc += batch.filled_usdc;
+    stats.total_shares += batch.total_shares;
+    stats.last_completed_at = Clock::get()?.unix_timestamp;
+    Ok(())
+}
+
+/// CPI into a yield adapter with the batch PDA signing for its escrow;
+/// see YIELD_DEPOSIT_DISCRIMINATOR.
+fn invoke_yield_adapter<'info>(
+    adapter: &AccountInfo<'info>,
+    discriminator: [u8; 8],
+    amount: Option<u64>,
+    escrow: &AccountInfo<'info>,
+    batch: &Account<'info, Batch>,
+    batch_bump: u8,
+    usdc_mint: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    adapter_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    let mut data = discriminator.to_vec();
+    if let Some(amount) = amount {
+        data.extend_from_slice(&amount.to_le_bytes());
+    }
+    let mut accounts = vec![
+        AccountMeta::new(escrow.key(), false),
+        AccountMeta::new_readonly(batch.key(), true),
+        AccountMeta::new_readonly(usdc_mint.key(), false),
+        AccountMeta::new_readonly(token_program.key(), false),
+    ];
+    accounts.extend(adapter_accounts.iter().map(|account| AccountMeta {
+        pubkey: account.key(),
+        is_signer: false,
+        is_writable: account.is_writable,
+    }));
+    let mut infos = vec![
+        escrow.clone(),
+        batch.to_account_info(),
+        usdc_mint.clone(),
+        token_program.clone(),
+    ];
+    infos.extend_from_slice(adapter_accounts);
+    infos.push(adapter.clone());
+
+    let bump = [batch_bump];
+    invoke_signed(
+        &Instruction {
+            program_id: adapter.key(),
+            accounts,
+            data,
+        },
+        &infos,
+        &[&[
+            b"batch",
+            batch.authority.as_ref(),
+            batch.market_id.as_bytes(),
+            batch.lane_seed(),
+            &bump,
+        ]],
+    )?;
+    Ok(())
+}
+
+/// Reject a USDC mint carrying a Token-2022 extension outside
+/// SUPPORTED_MINT_EXTENSIONS.
+///
+/// Accounting is in base units throughout: an interest-bearing rate only
+/// changes the balance wallets display, and transfer fees are taken out
+/// with received_amount. Extensions that can move, freeze, hook or hide
+/// escrowed funds would let balances drift from the accounting.
+pub fn check_usdc_mint(mint: &AccountInfo) -> Result<()> {
+    check_mint_extensions(&mint.try_borrow_data()?)
+}
+
+/// check_usdc_mint on raw mint data; legacy SPL Token mints have no
+/// extensions and always pass.
+pub fn check_mint_extensions(data: &[u8]) -> Result<()> {
+    let mint = StateWithExtensions::<MintState>::unpack(data)?;
+    for extension in mint.get_extension_types()? {
+        require!(
+            SUPPORTED_MINT_EXTENSIONS.contains(&extension),
+            ErrorCode::UnsupportedMintExtension
+        );
+    }
+    Ok(())
+}
+
+/// What a transfer_checked of `amount` delivers: `amount` less the mint's
+/// transfer fee for the current epoch, if it charges one.
+fn received_amount(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    mint_received_amount(&mint.try_borrow_data()?, amount, Clock::get()?.epoch)
+}
+
+/// received_amount on raw mint data at `epoch`.
+pub fn mint_received_amount(data: &[u8], amount: u64, epoch: u64) -> Result<u64> {
+    let mint = StateWithExtensions::<MintState>::unpack(data)?;
+    let fee = match mint.get_extension::<TransferFeeConfig>() {
+        Ok(config) => config
+            .calculate_epoch_fee(epoch, amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?,
+        Err(_) => 0,
+    };
+    Ok(amount - fee)
+}
+
+/// Root reached from the leaf at `order_index` and its siblings, bottom
+/// up; a node is sha256(left || right).
+pub fn distribution_proof_root(
+    leaf: [u8; 32],
+    order_index: u8,
+    proof: &[[u8; 32]; DISTRIBUTION_TREE_DEPTH],
+) -> [u8; 32] {
+    let mut node = leaf;
+    for (level, sibling) in proof.iter().enumerate() {
+        node = if (order_index >> level) & 1 == 0 {
+            hashv(&[&node, sibling]).to_bytes()
+        } else {
+            hashv(&[sibling, &node]).to_bytes()
+        };
+    }
+    node
+}
+
+/// Distribution whose share amount is only readable by the recipient.
+/// Decrypt with the user's x25519 secret and the MXE public key.
+#[account]
+pub struct EncryptedDistribution {
+    pub batch: Pubkey,
+    pub order_index: u8,
+    /// x25519 public key the order was encrypted under
+    pub encryption_key: [u8; 32],
+    pub nonce: u128,
+    pub encrypted_shares: [u8; 32],
+    pub wallet: Pubkey,
+    pub executed: bool,
+}
+
+/// Distribution paid into the privacy pool instead of a wallet.
+#[account]
+pub struct StealthDistribution {
+    pub batch: Pubkey,
+    pub order_index: u8,
+    pub shares: u64,
+    /// One-time key that must sign the claim
+    pub claim_key: Pubkey,
+    /// Pool commitment the shares were claimed into
+    pub commitment: [u8; 32],
+    pub claimed: bool,
+}
+
+/// Rolling per-market aggregates over completed batches.
+/// Average order count is total_orders / completed_batches;
+/// the fill rate (shares per USDC) is total_shares / total_usdc.
+/// The recent_* counters track order arrival over closed batches for
+/// adaptive batch windows; see set_batch_window.
+#[account]
+pub struct MarketStats {
+    pub market_id: String,
+    pub completed_batches: u64,
+    pub total_orders: u64,
+    pub total_usdc: u64,
+    pub total_shares: u64,
+    pub last_completed_at: i64,
+    /// Orders of recently closed batches, halved at every close
+    pub recent_orders: u64,
+    /// Seconds those batches were open, halved alongside recent_orders
+    pub recent_open_secs: u64,
+}
+
+impl MarketStats {
+    pub const SIZE: usize = 68 + 8 + 8 + 8 + 8 + 8 + 8 + 8;
+
+    /// Seconds a batch in this market must stay open before close_batch,
+    /// unless it reaches config.target_batch_orders first: the time the
+    /// recent arrival rate takes to bring in that many orders, within the
+    /// configured bounds. MIN_BATCH_DURATION while adaptive windows are off.
+    pub fn batch_window(&self, config: &ProtocolConfig) -> i64 {
+        if config.max_batch_window == 0 {
+            return MIN_BATCH_DURATION;
+        }
+        let window = if self.recent_orders == 0 {
+            config.max_batch_window
+        } else {
+            (config.target_batch_orders as u128 * self.recent_open_secs as u128
+                / self.recent_orders as u128)
+                .min(i64::MAX as u128) as i64
+        };
+        window
+            .clamp(config.min_batch_window, config.max_batch_window)
+            .max(MIN_BATCH_DURATION)
+    }
+
+    /// Fold a batch closed after `open_secs` with `order_count` orders into
+    /// the arrival counters. Halving first lets the last few batches
+    /// dominate, so windows follow changes in volume.
+    pub fn record_close(&mut self, order_count: u8, open_secs: i64) {
+        self.recent_orders = self.recent_orders / 2 + order_count as u64;
+        self.recent_open_secs = self.recent_open_secs / 2 + open_secs.max(0) as u64;
+    }
+}
+
+/// Lot size a market executes in at DFlow; the relay passes it to
+/// add_to_batch_normalized.
+#[account]
+pub struct MarketLotSize {
+    pub market_id: String,
+    /// Smallest executable USDC increment in atomic units (0 = any amount)
+    pub lot_size: u64,
+    pub updated_at: i64,
+}
+
+/// Outcome token mints of a market; see set_market_outcome_mints.
+#[account]
+pub struct MarketOutcomeMints {
+    pub market_id: String,
+    pub yes_mint: Pubkey,
+    pub no_mint: Pubkey,
+    pub updated_at: i64,
+}
+
+impl MarketOutcomeMints {
+    /// Mint of the shares a batch on `side` buys (1 = YES, 0 = NO).
+    pub fn outcome_mint(&self, side: u8) -> Pubkey {
+        if side == 1 {
+            self.yes_mint
+        } else {
+            self.no_mint
+        }
+    }
+}
+
+/// Program-wide batch counter. Every batch gets the next number when it
+/// is created, and every batch event carries it, so indexers can order
+/// batches without trusting the order logs arrive in.
+#[account]
+pub struct BatchSequence {
+    /// Sequence of the newest batch; 0 before the first
+    pub last: u64,
+}
+
+impl BatchSequence {
+    pub const SIZE: usize = 8;
+
+    /// Claim the sequence number of a new batch
+    pub fn claim(&mut self) -> u64 {
+        self.last += 1;
+        self.last
+    }
+}
+
+/// Amount thresholds splitting a market into lanes; see set_market_lanes.
+#[account]
+pub struct MarketLanes {
+    pub market_id: String,
+    /// Ascending; lane i takes amounts in [thresholds[i - 1], thresholds[i])
+    pub thresholds: Vec<u64>,
+    pub updated_at: i64,
+}
+
+impl MarketLanes {
+    pub const SIZE: usize = 68 + 4 + 8 * (MAX_MARKET_LANES - 1) + 8;
+
+    pub fn lane_count(&self) -> usize {
+        self.thresholds.len() + 1
+    }
+}
+
+/// Timestamps of the most recent batch-total reveals for one market,
+/// used as a ring buffer by `close_batch`.
+#[account]
+pub struct RevealBudget {
+    pub market_id: String,
+    pub reveal_times: [i64; MAX_REVEALS_PER_WINDOW],
+    pub next: u8,
+}
+
+/// A user's positions across batches as an `Enc<Mxe, UserPortfolio>`.
+/// Fed back into accumulate_position after each batch and re-encrypted to
+/// the user by reveal_my_position.
+#[account]
+pub struct UserPortfolio {
+    pub authority: Pubkey,
+    pub owner: Pubkey,
+    pub nonce: u128,
+    pub encrypted_positions: [[u8; 32]; PORTFOLIO_CIPHERTEXTS],
+    /// Number of times the portfolio has been stored
+    pub updates: u64,
+    pub updated_at: i64,
+}
+
+/// Computation definitions known to be initialized.
+#[account]
+pub struct MpcRegistry {
+    pub authority: Pubkey,
+    pub circuits: Vec<RegisteredCompDef>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RegisteredCompDef {
+    /// comp_def_offset(circuit name): first 4 bytes of sha256(name)
+    pub offset: u32,
+    pub comp_def: Pubkey,
+    pub version: u16,
+    pub registered_at: i64,
+}
+
+/// Fail unless `comp_def` is registered at `batch.circuit_version`.
+pub fn require_comp_def_version(
+    registry: &MpcRegistry,
+    batch: &Batch,
+    comp_def: &Pubkey,
+) -> Result<()> {
+    let entry = registry
+        .circuits
+        .iter()
+        .find(|c| c.comp_def == *comp_def)
+        .ok_or(ErrorCode::CompDefNotRegistered)?;
+    require!(
+        entry.version == batch.circuit_version,
+        ErrorCode::CircuitVersionMismatch
+    );
+    Ok(())
+}
+
+/// Protocol-wide settings.
+#[account]
+#[derive(Default)]
+pub struct ProtocolConfig {
+    pub admin: Pubkey,
+    /// Arcium cluster new batches are processed on
+    pub cluster_offset: u32,
+    /// Cluster the relay fails over to when the active one is degraded
+    pub fallback_cluster_offset: u32,
+    /// Circuit version new batches are pinned to
+    pub circuit_version: u16,
+    pub updated_at: i64,
+    /// Batch lifecycle: create, record orders, close, record execution
+    pub operator: Pubkey,
+    /// Distributions: record, claim and mark distributed
+    pub settler: Pubkey,
+    /// Seconds a closed batch may wait for execution (0 = no timeout)
+    pub execution_timeout: i64,
+    /// Seconds an executed batch may wait for completion (0 = no timeout)
+    pub completion_timeout: i64,
+    /// Batches with total_usdc above this need approve_execution (0 = off)
+    pub large_batch_threshold: u64,
+    /// Approvals record_execution needs on a large batch
+    pub approval_threshold: u8,
+    pub approver_count: u8,
+    pub approvers: [Pubkey; MAX_EXECUTION_APPROVERS],
+    /// Lending adapter batch escrow may be parked in (default = none)
+    pub yield_adapter: Pubkey,
+    /// Kill-switch for park_escrow
+    pub yield_enabled: bool,
+    /// USDC token account adapter earnings are paid to
+    pub yield_recipient: Pubkey,
+    /// Adaptive batch windows; see set_batch_window (max 0 = off)
+    pub min_batch_window: i64,
+    pub max_batch_window: i64,
+    pub target_batch_orders: u8,
+    /// CPI guard on record_order; see set_cpi_guard
+    pub cpi_guard_enabled: bool,
+    pub cpi_caller_count: u8,
+    pub cpi_callers: [Pubkey; MAX_CPI_CALLERS],
+    /// Express lane; see set_express_lane (window 0 = off)
+    pub express_batch_window: i64,
+    pub express_fee_bps: u16,
+    /// Slots a revealed total may wait for record_execution; see
+    /// set_max_reveal_exec_gap (0 = no limit)
+    pub max_reveal_exec_gap: u64,
+}
+
+impl ProtocolConfig {
+    pub fn cpi_callers(&self) -> &[Pubkey] {
+        &self.cpi_callers[..self.cpi_caller_count as usize]
+    }
+
+    /// Whether record_order may run at `stack_height` inside the
+    /// transaction instruction of `top_level`: as that instruction itself,
+    /// or one CPI below an allowed program.
+    pub fn allows_caller(&self, top_level: &Pubkey, stack_height: usize) -> bool {
+        !self.cpi_guard_enabled
+            || (stack_height <= TRANSACTION_LEVEL_STACK_HEIGHT && *top_level == ID)
+            || (stack_height == TRANSACTION_LEVEL_STACK_HEIGHT + 1
+                && self.cpi_callers().contains(top_level))
+    }
+}
+
+/// Approvals of one execution of a large batch; see approve_execution.
+#[account]
+pub struct ExecutionApproval {
+    pub batch: Pubkey,
+    pub total_shares: u64,
+    pub filled_usdc: u64,
+    pub approver_count: u8,
+    pub approvers: [Pubkey; MAX_EXECUTION_APPROVERS],
+}
+
+impl ExecutionApproval {
+    pub const SIZE: usize = 32 + 8 + 8 + 1 + 32 * MAX_EXECUTION_APPROVERS;
+
+    /// Approvals by keys that are still approvers in `config`.
+    pub fn count_valid(&self, config: &ProtocolConfig) -> usize {
+        let current = &config.approvers[..config.approver_count as usize];
+        self.approvers[..self.approver_count as usize]
+            .iter()
+            .filter(|approver| current.contains(approver))
+            .count()
+    }
+}
+
+/// Last nonce used by a relay key on distribution recording, or by a user
+/// on delegated order intents (seeds [b"intent_nonce", user]).
+/// RPC retries can land transactions late or twice; requiring a strictly
+/// increasing nonce rejects the stale and replayed copies.
+#[account]
+pub struct RelayNonce {
+    pub relay: Pubkey,
+    pub nonce: u64,
+}
+
+impl RelayNonce {
+    pub const SIZE: usize = 32 + 8;
+
+    /// Accept `nonce` if it is newer than the last one used.
+    pub fn advance(&mut self, relay: Pubkey, nonce: u64) -> Result<()> {
+        require!(nonce > self.nonce, ErrorCode::StaleRelayNonce);
+        self.relay = relay;
+        self.nonce = nonce;
+        Ok(())
+    }
+}
+
+/// A temporary key a user lets sign for them within `scope`.
+#[account]
+pub struct SessionKey {
+    pub user: Pubkey,
+    pub session_key: Pubkey,
+    pub expires_at: i64,
+    /// SESSION_SCOPE_* bits
+    pub scope: u8,
+    pub created_at: i64,
+}
+
+impl SessionKey {
+    pub const SIZE: usize = 32 + 32 + 8 + 1 + 8;
+}
+
+/// A recurring order on one side of a market: enroll_subscription records
+/// it in a new batch at most once every `cadence` seconds until
+/// `remaining` runs out, so the owner can DCA without signing per batch.
+#[account]
+#[derive(Default)]
+pub struct Subscription {
+    pub owner: Pubkey,
+    /// Owner-chosen; lets one owner hold several subscriptions
+    pub id: u64,
+    pub market_id: String,
+    pub side: u8,
+    /// Minimum seconds between enrollments
+    pub cadence: i64,
+    pub next_enroll_at: i64,
+    /// Enrollments left
+    pub remaining: u32,
+    /// Hash of the encrypted order the relay resubmits at each enrollment
+    pub ciphertext_ref: [u8; 32],
+    /// Batch of the latest enrollment (default = none yet)
+    pub last_batch: Pubkey,
+    pub enrolled: u32,
+    pub created_at: i64,
+    /// Largest deviation of an enrollment's amount from the even split,
+    /// in bps; 0 for a fixed amount per enrollment
+    pub jitter_bps: u16,
+    /// `Enc<Mxe, DcaSchedule>` of a jittered subscription; zero until
+    /// store_dca_schedule
+    pub schedule_nonce: u128,
+    pub encrypted_schedule: [[u8; 32]; DCA_SCHEDULE_CIPHERTEXTS],
+}
+
+impl Subscription {
+    pub const SIZE: usize = 32
+        + 8
+        + (4 + MAX_SUBSCRIPTION_MARKET_ID_LEN)
+        + 1
+        + 8
+        + 8
+        + 4
+        + 32
+        + 32
+        + 4
+        + 8
+        + 2
+        + 16
+        + 32 * DCA_SCHEDULE_CIPHERTEXTS;
+
+    /// Whether the subscription may be enrolled in `batch` at `now`
+    pub fn is_due(&self, batch: &Pubkey, now: i64) -> bool {
+        self.remaining > 0 && now >= self.next_enroll_at && *batch != self.last_batch
+    }
+
+    /// Count an enrollment in `batch` and schedule the next one
+    pub fn enroll(&mut self, batch: Pubkey, now: i64) {
+        self.last_batch = batch;
+        self.next_enroll_at = now.saturating_add(self.cadence);
+        self.remaining -= 1;
+        self.enrolled += 1;
+    }
+}
+
+/// An order a user authorizes a relayer to record for them.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OrderIntent {
+    pub batch: Pubkey,
+    pub user: Pubkey,
+    /// Binds the intent to one encrypted order, e.g. a hash of its ciphertext
+    pub order_commitment: [u8; 32],
+    /// Must exceed the user's last intent nonce
+    pub nonce: u64,
+    pub expires_at: i64,
+}
+
+/// Bytes the user signs for `intent`: ORDER_INTENT_DOMAIN then the Borsh
+/// encoded intent.
+pub fn order_intent_message(intent: &OrderIntent) -> Vec<u8> {
+    let mut message = ORDER_INTENT_DOMAIN.to_vec();
+    intent.serialize(&mut message).unwrap();
+    message
+}
+
+/// Check that the instruction before the current one is an ed25519 program
+/// instruction verifying one signature by `signer` over `message`, with
+/// all of its data inside that instruction.
+fn verify_ed25519_signature(
+    instructions: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<()> {
+    let current = load_current_index_checked(instructions)?;
+    require!(current > 0, ErrorCode::InvalidOrderIntentSignature);
+    let ix = load_instruction_at_checked(current as usize - 1, instructions)?;
+    require_keys_eq!(
+        ix.program_id,
+        ed25519_program::ID,
+        ErrorCode::InvalidOrderIntentSignature
+    );
+
+    // [count, padding, then per signature: signature, pubkey and message
+    // offsets with their instruction indexes, and the message size]
+    let data = &ix.data;
+    require!(
+        data.len() >= 16 && data[0] == 1,
+        ErrorCode::InvalidOrderIntentSignature
+    );
+    let field = |i: usize| u16::from_le_bytes([data[2 + 2 * i], data[3 + 2 * i]]);
+    let (signature_ix, pubkey_offset, pubkey_ix) = (field(1), field(2) as usize, field(3));
+    let (message_offset, message_size, message_ix) =
+        (field(4) as usize, field(5) as usize, field(6));
+    require!(
+        [signature_ix, pubkey_ix, message_ix] == [u16::MAX; 3],
+        ErrorCode::InvalidOrderIntentSignature
+    );
+    require!(
+        data.get(pubkey_offset..pubkey_offset + 32) == Some(signer.as_ref())
+            && data.get(message_offset..message_offset + message_size) == Some(message),
+        ErrorCode::InvalidOrderIntentSignature
+    );
+    Ok(())
+}
+
+/// Fail if the CPI guard is on and the current instruction was reached
+/// through a program it doesn't allow.
+fn check_cpi_caller(config: &ProtocolConfig, instructions: &AccountInfo) -> Result<()> {
+    if !config.cpi_guard_enabled {
+        return Ok(());
+    }
+    let current = load_current_index_checked(instructions)?;
+    let top_level = load_instruction_at_checked(current as usize, instructions)?.program_id;
+    require!(
+        config.allows_caller(&top_level, get_stack_height()),
+        ErrorCode::CpiCallerNotAllowed
+    );
+    Ok(())
+}
+
+/// Fail if an instruction of another program earlier in the transaction
+/// references one of the market's outcome mints, as any trade of the
+/// market has to. Otherwise an operator could trade ahead of the reveal
+/// or execution it is about to record. Markets without registered mints
+/// are not checked.
+fn check_no_preceding_market_trades(
+    instructions: &AccountInfo,
+    market_mints: &AccountInfo,
+) -> Result<()> {
+    if market_mints.owner != &ID || market_mints.data_is_empty() {
+        return Ok(());
+    }
+    let market_mints = MarketOutcomeMints::try_deserialize(&mut &market_mints.data.borrow()[..])?;
+    let mints = [market_mints.yes_mint, market_mints.no_mint];
+
+    let current = load_current_index_checked(instructions)?;
+    for index in 0..current as usize {
+        let ix = load_instruction_at_checked(index, instructions)?;
+        if ix.program_id == ID {
+            continue;
+        }
+        require!(
+            !ix.accounts.iter().any(|meta| mints.contains(&meta.pubkey)),
+            ErrorCode::MarketTradedInTransaction
+        );
+    }
+    Ok(())
+}
+
+/// Keys held in ProtocolConfig; see set_role.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Role {
+    /// Protocol parameters and role assignment
+    Admin,
+    Operator,
+    Settler,
+}
+
+#[account]
+pub struct InsuranceFund {
+    pub authority: Pubkey,
+    pub usdc_mint: Pubkey,
+    pub vault: Pubkey,
+    pub fee_share_bps: u16,
+    pub total_funded: u64,
+    pub total_claimed: u64,
+    pub bump: u8,
+}
+
+/// Totals and vault for user credits; see record_credit.
+#[account]
+pub struct CreditLedger {
+    pub usdc_mint: Pubkey,
+    pub vault: Pubkey,
+    pub total_credited: u64,
+    pub total_claimed: u64,
+    pub bump: u8,
+}
+
+/// USDC owed to one user across batches, claimable with claim_credit.
+#[account]
+pub struct UserCredit {
+    pub owner: Pubkey,
+    pub balance: u64,
+    pub total_credited: u64,
+    pub total_claimed: u64,
+    pub updated_at: i64,
+}
+
+// ============================================================================
+// Enums
+// ============================================================================
+
+/// Why a user was credited (see record_credit).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CreditReason {
+    /// Order remainder below the market lot size
+    Dust,
+    /// Remainder from integer division in distributions
+    Rounding,
+    /// Order amount not executed
+    Refund,
+}
+
+// ============================================================================
+// Computation Definition Account Contexts
+// ============================================================================
+
+#[init_computation_definition_accounts("init_batch", payer)]
+#[derive(Accounts)]
+pub struct InitInitBatchCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: Initialized via CPI
+    #[account(mut)]
+    #[cfg_attr(
+        feature = "strict",
+        account(address = derive_comp_def_pda!(comp_def_offset("init_batch")))
+    )]
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("add_to_batch", payer)]
+#[derive(Accounts)]
+pub struct InitAddToBatchCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: Initialized via CPI
+    #[account(mut)]
+    #[cfg_attr(
+        feature = "strict",
+        account(address = derive_comp_def_pda!(comp_def_offset("add_to_batch")))
+    )]
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("add_to_batch_normalized", payer)]
+#[derive(Accounts)]
+pub struct InitAddToBatchNormalizedCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: Initialized via CPI
+    #[account(mut)]
+    #[cfg_attr(
+        feature = "strict",
+        account(address = derive_comp_def_pda!(comp_def_offset("add_to_batch_normalized")))
+    )]
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("reveal_batch_total", payer)]
+#[derive(Accounts)]
+pub struct InitRevealBatchTotalCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: Initialized via CPI
+    #[account(mut)]
+    #[cfg_attr(
+        feature = "strict",
+        account(address = derive_comp_def_pda!(comp_def_offset("reveal_batch_total")))
+    )]
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("compute_distribution", payer)]
+#[derive(Accounts)]
+pub struct InitComputeDistributionCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: Initialized via CPI
+    #[account(mut)]
+    #[cfg_attr(
+        feature = "strict",
+        account(address = derive_comp_def_pda!(comp_def_offset("compute_distribution")))
+    )]
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("compute_encrypted_distribution", payer)]
+#[derive(Accounts)]
+pub struct InitComputeEncryptedDistributionCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: Initialized via CPI
+    #[account(mut)]
+    #[cfg_attr(
+        feature = "strict",
+        account(address = derive_comp_def_pda!(comp_def_offset("compute_encrypted_distribution")))
+    )]
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("reveal_bucketed_total", payer)]
+#[derive(Accounts)]
+pub struct InitRevealBucketedTotalCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: Initialized via CPI
+    #[account(mut)]
+    #[cfg_attr(
+        feature = "strict",
+        account(address = derive_comp_def_pda!(comp_def_offset("reveal_bucketed_total")))
+    )]
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("compute_distribution_from_stats", payer)]
+#[derive(Accounts)]
+pub struct InitComputeDistributionFromStatsCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: Initialized via CPI
+    #[account(mut)]
+    #[cfg_attr(
+        feature = "strict",
+        account(address = derive_comp_def_pda!(comp_def_offset("compute_distribution_from_stats")))
+    )]
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("rekey_batch_stats", payer)]
+#[derive(Accounts)]
+pub struct InitRekeyBatchStatsCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: Initialized via CPI
+    #[account(mut)]
+    #[cfg_attr(
+        feature = "strict",
+        account(address = derive_comp_def_pda!(comp_def_offset("rekey_batch_stats")))
+    )]
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("import_batch_stats", payer)]
+#[derive(Accounts)]
+pub struct InitImportBatchStatsCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: Initialized via CPI
+    #[account(mut)]
+    #[cfg_attr(
+        feature = "strict",
+        account(address = derive_comp_def_pda!(comp_def_offset("import_batch_stats")))
+    )]
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("init_portfolio", payer)]
+#[derive(Accounts)]
+pub struct InitInitPortfolioCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: Initialized via CPI
+    #[account(mut)]
+    #[cfg_attr(
+        feature = "strict",
+        account(address = derive_comp_def_pda!(comp_def_offset("init_portfolio")))
+    )]
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("accumulate_position", payer)]
+#[derive(Accounts)]
+pub struct InitAccumulatePositionCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: Initialized via CPI
+    #[account(mut)]
+    #[cfg_attr(
+        feature = "strict",
+        account(address = derive_comp_def_pda!(comp_def_offset("accumulate_position")))
+    )]
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("reveal_my_position", payer)]
+#[derive(Accounts)]
+pub struct InitRevealMyPositionCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: Initialized via CPI
+    #[account(mut)]
+    #[cfg_attr(
+        feature = "strict",
+        account(address = derive_comp_def_pda!(comp_def_offset("reveal_my_position")))
+    )]
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("sum_my_notes", payer)]
+#[derive(Accounts)]
+pub struct InitSumMyNotesCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: Initialized via CPI
+    #[account(mut)]
+    #[cfg_attr(
+        feature = "strict",
+        account(address = derive_comp_def_pda!(comp_def_offset("sum_my_notes")))
+    )]
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("route_order", payer)]
+#[derive(Accounts)]
+pub struct InitRouteOrderCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: Initialized via CPI
+    #[account(mut)]
+    #[cfg_attr(
+        feature = "strict",
+        account(address = derive_comp_def_pda!(comp_def_offset("route_order")))
+    )]
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("peek_order_count", payer)]
+#[derive(Accounts)]
+pub struct InitPeekOrderCountCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: Initialized via CPI
+    #[account(mut)]
+    #[cfg_attr(
+        feature = "strict",
+        account(address = derive_comp_def_pda!(comp_def_offset("peek_order_count")))
+    )]
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("init_dca_schedule", payer)]
+#[derive(Accounts)]
+pub struct InitInitDcaScheduleCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: Initialized via CPI
+    #[account(mut)]
+    #[cfg_attr(
+        feature = "strict",
+        account(address = derive_comp_def_pda!(comp_def_offset("init_dca_schedule")))
+    )]
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[init_computation_definition_accounts("add_dca_to_batch", payer)]
+#[derive(Accounts)]
+pub struct InitAddDcaToBatchCompDef<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, address = derive_mxe_pda!())]
+    pub mxe_account: Box<Account<'info, MXEAccount>>,
+    /// CHECK: Initialized via CPI
+    #[account(mut)]
+    #[cfg_attr(
+        feature = "strict",
+        account(address = derive_comp_def_pda!(comp_def_offset("add_dca_to_batch")))
+    )]
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub arcium_program: Program<'info, Arcium>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeMpcRegistry<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 4 + MAX_REGISTERED_CIRCUITS * (4 + 32 + 2 + 8),
+        seeds = [b"mpc_registry"],
+        bump
+    )]
+    pub registry: Account<'info, MpcRegistry>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(circuit: String)]
+pub struct RegisterCompDef<'info> {
+    #[account(mut, seeds = [b"mpc_registry"], bump, has_one = authority)]
+    pub registry: Account<'info, MpcRegistry>,
+    /// CHECK: Address and owner checked; must already be initialized
+    #[account(
+        address = derive_comp_def_pda!(comp_def_offset(&circuit)),
+        owner = Arcium::id()
+    )]
+    pub comp_def_account: UncheckedAccount<'info>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyCompDefs<'info> {
+    #[account(seeds = [b"mpc_registry"], bump)]
+    pub registry: Account<'info, MpcRegistry>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyBatchCompDefs<'info> {
+    #[account(seeds = [b"mpc_registry"], bump)]
+    pub registry: Account<'info, MpcRegistry>,
+    pub batch: Account<'info, Batch>,
+}
+
+// ============================================================================
+// Protocol Config Account Contexts
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + 4 + 4 + 2 + 8 + 32 + 32 + 8 + 8 + 8 + 1 + 1 + 32 * MAX_EXECUTION_APPROVERS + 32 + 1 + 32 + 8 + 8 + 1 + 1 + 1 + 32 * MAX_CPI_CALLERS + 8 + 2 + 8,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(mut, seeds = [b"config"], bump, has_one = admin)]
+    pub config: Account<'info, ProtocolConfig>,
+    pub admin: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(market_id: String)]
+pub struct SetMarketLotSize<'info> {
+    #[account(seeds = [b"config"], bump, has_one = admin)]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 68 + 8 + 8,
+        seeds = [b"market_lot", market_id.as_bytes()],
+        bump
+    )]
+    pub market_lot: Account<'info, MarketLotSize>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(market_id: String)]
+pub struct SetMarketOutcomeMints<'info> {
+    #[account(seeds = [b"config"], bump, has_one = admin)]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + 68 + 32 + 32 + 8,
+        seeds = [b"market_mints", market_id.as_bytes()],
+        bump
+    )]
+    pub market_mints: Account<'info, MarketOutcomeMints>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(market_id: String)]
+pub struct SetMarketLanes<'info> {
+    #[account(seeds = [b"config"], bump, has_one = admin)]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + MarketLanes::SIZE,
+        seeds = [b"market_lanes", market_id.as_bytes()],
+        bump
+    )]
+    pub market_lanes: Account<'info, MarketLanes>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ============================================================================
+// Batch Management Account Contexts
+// ============================================================================
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(market_id: String, side: u8, lane: BatchLane)]
+pub struct CreateBatch<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Batch::SIZE,
+        seeds = [b"batch", authority.key().as_ref(), market_id.as_bytes(), lane.seed()],
+        bump
+    )]
+    pub batch: Account<'info, Batch>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + BatchSequence::SIZE,
+        seeds = [b"batch_sequence"],
+        bump
+    )]
+    pub batch_sequence: Account<'info, BatchSequence>,
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.operator == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(market_id: String, side: u8, lane: u8)]
+pub struct CreateLaneBatch<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Batch::SIZE,
+        seeds = [b"batch", authority.key().as_ref(), market_id.as_bytes(), lane_seed(&lane)],
+        bump
+    )]
+    pub batch: Account<'info, Batch>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + BatchSequence::SIZE,
+        seeds = [b"batch_sequence"],
+        bump
+    )]
+    pub batch_sequence: Account<'info, BatchSequence>,
+    #[account(seeds = [b"market_lanes", market_id.as_bytes()], bump)]
+    pub market_lanes: Account<'info, MarketLanes>,
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.operator == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetBatchStatus<'info> {
+    pub batch: Account<'info, Batch>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MigrateBatchCluster<'info> {
+    #[account(mut)]
+    pub batch: Account<'info, Batch>,
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.operator == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RecordOrder<'info> {
+    #[account(mut)]
+    pub batch: Account<'info, Batch>,
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.operator == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 1 + 1,
+        seeds = [b"order_slot", batch.key().as_ref(), &[batch.order_count]],
+        bump
+    )]
+    pub order_slot: Account<'info, OrderSlot>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: the instructions sysvar, checked by address
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RouteOrder<'info> {
+    #[account(mut)]
+    pub batch: Account<'info, Batch>,
+    #[account(seeds = [b"market_lanes", batch.market_id.as_bytes()], bump)]
+    pub market_lanes: Account<'info, MarketLanes>,
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.operator == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 1 + 1,
+        seeds = [b"order_slot", batch.key().as_ref(), &[batch.order_count]],
+        bump
+    )]
+    pub order_slot: Account<'info, OrderSlot>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct PostOrderCount<'info> {
+    pub batch: Account<'info, Batch>,
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.operator == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(intent: OrderIntent)]
+pub struct RecordDelegatedOrder<'info> {
+    #[account(
+        mut,
+        constraint = batch.key() == intent.batch @ ErrorCode::InvalidOrderIntentSignature
+    )]
+    pub batch: Account<'info, Batch>,
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + 32 + 1 + 1,
+        seeds = [b"order_slot", batch.key().as_ref(), &[batch.order_count]],
+        bump
+    )]
+    pub order_slot: Account<'info, OrderSlot>,
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        space = 8 + RelayNonce::SIZE,
+        seeds = [b"intent_nonce", intent.user.as_ref()],
+        bump
+    )]
+    pub intent_nonce: Account<'info, RelayNonce>,
+    /// Pays fees and rent; the user's signature authorizes the order
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+    /// CHECK: the instructions sysvar, checked by address
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    /// Session key that signed the intent in place of the user
+    #[account(
+        seeds = [b"session", intent.user.as_ref(), session.session_key.as_ref()],
+        bump
+    )]
+    pub session: Option<Account<'info, SessionKey>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CloseBatch<'info> {
+    #[account(mut)]
+    pub batch: Account<'info, Batch>,
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.operator == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 68 + 8 * MAX_REVEALS_PER_WINDOW + 1,
+        seeds = [b"reveal_budget", batch.market_id.as_bytes()],
+        bump
+    )]
+    pub reveal_budget: Account<'info, RevealBudget>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: the market's MarketOutcomeMints PDA, read only if registered
+    #[account(seeds = [b"market_mints", batch.market_id.as_bytes()], bump)]
+    pub market_mints: UncheckedAccount<'info>,
+    /// CHECK: the instructions sysvar, checked by address
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + MarketStats::SIZE,
+        seeds = [b"market_stats", batch.market_id.as_bytes()],
+        bump
+    )]
+    pub market_stats: Account<'info, MarketStats>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RecordExecution<'info> {
+    #[account(mut)]
+    pub batch: Account<'info, Batch>,
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.operator == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    pub authority: Signer<'info>,
+    /// Required when the batch is above config.large_batch_threshold
+    #[account(seeds = [b"execution_approval", batch.key().as_ref()], bump)]
+    pub execution_approval: Option<Account<'info, ExecutionApproval>>,
+    /// CHECK: the market's MarketOutcomeMints PDA, read only if registered
+    #[account(seeds = [b"market_mints", batch.market_id.as_bytes()], bump)]
+    pub market_mints: UncheckedAccount<'info>,
+    /// CHECK: the instructions sysvar, checked by address
+    #[account(address = solana_instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ApproveExecution<'info> {
+    pub batch: Account<'info, Batch>,
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        init_if_needed,
+        payer = approver,
+        space = 8 + ExecutionApproval::SIZE,
+        seeds = [b"execution_approval", batch.key().as_ref()],
+        bump
+    )]
+    pub execution_approval: Account<'info, ExecutionApproval>,
+    #[account(mut)]
+    pub approver: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct StageDistributions<'info> {
+    #[account(mut)]
+    pub batch: Account<'info, Batch>,
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.settler == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FinalizeDistributions<'info> {
+    #[account(mut)]
+    pub batch: Account<'info, Batch>,
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.settler == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(order_index: u8)]
+pub struct RecordDistribution<'info> {
+    #[account(
+        mut,
+        constraint = order_index < batch.order_count @ ErrorCode::OrderIndexOutOfRange
+    )]
+    pub batch: Account<'info, Batch>,
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.settler == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 1 + 8 + 8 + 32 + 1 + 1 + 8,
+        seeds = [b"dist", batch.key().as_ref(), &[order_index]],
+        bump
+    )]
+    pub distribution: Account<'info, Distribution>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + RelayNonce::SIZE,
+        seeds = [b"relay_nonce", authority.key().as_ref()],
+        bump
+    )]
+    pub relay_nonce: Account<'info, RelayNonce>,
+    #[account(
+        mut,
+        seeds = [b"order_slot", batch.key().as_ref(), &[order_index]],
+        bump,
+        has_one = batch,
+        constraint = !order_slot.distributed @ ErrorCode::OrderAlreadyDistributed
+    )]
+    pub order_slot: Account<'info, OrderSlot>,
     #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, address = derive_mxe_pda!())]
-    pub mxe_account: Box<Account<'info, MXEAccount>>,
-    /// CHECK: Initialized via CPI
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(order_index: u8)]
+pub struct RecordEncryptedDistribution<'info> {
+    #[account(
+        mut,
+        constraint = order_index < batch.order_count @ ErrorCode::OrderIndexOutOfRange
+    )]
+    pub batch: Account<'info, Batch>,
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.settler == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 1 + 32 + 16 + 32 + 32 + 1,
+        seeds = [b"enc_dist", batch.key().as_ref(), &[order_index]],
+        bump
+    )]
+    pub distribution: Account<'info, EncryptedDistribution>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + RelayNonce::SIZE,
+        seeds = [b"relay_nonce", authority.key().as_ref()],
+        bump
+    )]
+    pub relay_nonce: Account<'info, RelayNonce>,
+    #[account(
+        mut,
+        seeds = [b"order_slot", batch.key().as_ref(), &[order_index]],
+        bump,
+        has_one = batch,
+        constraint = !order_slot.distributed @ ErrorCode::OrderAlreadyDistributed
+    )]
+    pub order_slot: Account<'info, OrderSlot>,
     #[account(mut)]
-    pub comp_def_account: UncheckedAccount<'info>,
-    pub arcium_program: Program<'info, Arcium>,
+    pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
-#[init_computation_definition_accounts("add_to_batch", payer)]
+#[event_cpi]
 #[derive(Accounts)]
-pub struct InitAddToBatchCompDef<'info> {
+#[instruction(order_index: u8)]
+pub struct RecordStealthDistribution<'info> {
+    #[account(
+        mut,
+        constraint = order_index < batch.order_count @ ErrorCode::OrderIndexOutOfRange
+    )]
+    pub batch: Account<'info, Batch>,
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.settler == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 1 + 8 + 32 + 32 + 1,
+        seeds = [b"stealth_dist", batch.key().as_ref(), &[order_index]],
+        bump
+    )]
+    pub distribution: Account<'info, StealthDistribution>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + RelayNonce::SIZE,
+        seeds = [b"relay_nonce", authority.key().as_ref()],
+        bump
+    )]
+    pub relay_nonce: Account<'info, RelayNonce>,
+    #[account(
+        mut,
+        seeds = [b"order_slot", batch.key().as_ref(), &[order_index]],
+        bump,
+        has_one = batch,
+        constraint = !order_slot.distributed @ ErrorCode::OrderAlreadyDistributed
+    )]
+    pub order_slot: Account<'info, OrderSlot>,
     #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, address = derive_mxe_pda!())]
-    pub mxe_account: Box<Account<'info, MXEAccount>>,
-    /// CHECK: Initialized via CPI
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimStealthDistribution<'info> {
     #[account(mut)]
-    pub comp_def_account: UncheckedAccount<'info>,
-    pub arcium_program: Program<'info, Arcium>,
+    pub batch: Account<'info, Batch>,
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.settler == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(mut, has_one = batch, has_one = claim_key)]
+    pub distribution: Account<'info, StealthDistribution>,
+    pub claim_key: Signer<'info>,
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(order_index: u8)]
+pub struct ClaimShares<'info> {
+    #[account(
+        mut,
+        seeds = [b"batch", batch.authority.as_ref(), batch.market_id.as_bytes(), batch.lane_seed()],
+        bump,
+        constraint = order_index < batch.order_count @ ErrorCode::OrderIndexOutOfRange
+    )]
+    pub batch: Account<'info, Batch>,
+    #[account(
+        init,
+        payer = claimant,
+        space = 8 + 32 + 1 + 8 + 8 + 32 + 1 + 1 + 8,
+        seeds = [b"dist", batch.key().as_ref(), &[order_index]],
+        bump
+    )]
+    pub distribution: Box<Account<'info, Distribution>>,
+    #[account(
+        mut,
+        seeds = [b"order_slot", batch.key().as_ref(), &[order_index]],
+        bump,
+        has_one = batch,
+        constraint = !order_slot.distributed @ ErrorCode::OrderAlreadyDistributed
+    )]
+    pub order_slot: Account<'info, OrderSlot>,
+    #[account(
+        init_if_needed,
+        payer = claimant,
+        space = 8 + MarketStats::SIZE,
+        seeds = [b"market_stats", batch.market_id.as_bytes()],
+        bump
+    )]
+    pub market_stats: Box<Account<'info, MarketStats>>,
+    #[account(seeds = [b"market_mints", batch.market_id.as_bytes()], bump)]
+    pub market_mints: Box<Account<'info, MarketOutcomeMints>>,
+    #[account(address = market_mints.outcome_mint(batch.side) @ ErrorCode::ShareMintMismatch)]
+    pub share_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        associated_token::mint = share_mint,
+        associated_token::authority = batch,
+        associated_token::token_program = share_token_program
+    )]
+    pub share_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, token::mint = share_mint, token::authority = claimant)]
+    pub recipient_shares: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = batch,
+        associated_token::token_program = token_program
+    )]
+    pub escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(seeds = [b"credit_ledger"], bump = credit_ledger.bump, has_one = usdc_mint)]
+    pub credit_ledger: Box<Account<'info, CreditLedger>>,
+    pub usdc_mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mut, token::mint = usdc_mint, token::authority = claimant)]
+    pub recipient_usdc: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// The order's wallet; its key is part of the proven leaf
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    /// Token program of the outcome mint
+    pub share_token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
-#[init_computation_definition_accounts("reveal_batch_total", payer)]
+#[event_cpi]
 #[derive(Accounts)]
-pub struct InitRevealBatchTotalCompDef<'info> {
+pub struct MarkDistributed<'info> {
     #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, address = derive_mxe_pda!())]
-    pub mxe_account: Box<Account<'info, MXEAccount>>,
-    /// CHECK: Initialized via CPI
+    pub batch: Account<'info, Batch>,
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.settler == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(mut, has_one = batch)]
+    pub distribution: Account<'info, Distribution>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + MarketStats::SIZE,
+        seeds = [b"market_stats", batch.market_id.as_bytes()],
+        bump
+    )]
+    pub market_stats: Account<'info, MarketStats>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MarkDistributedMany<'info> {
+    #[account(mut)]
+    pub batch: Account<'info, Batch>,
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.settler == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + MarketStats::SIZE,
+        seeds = [b"market_stats", batch.market_id.as_bytes()],
+        bump
+    )]
+    pub market_stats: Account<'info, MarketStats>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ForceFailBatch<'info> {
+    #[account(mut)]
+    pub batch: Account<'info, Batch>,
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ExpireReveal<'info> {
+    #[account(mut)]
+    pub batch: Account<'info, Batch>,
+    #[account(seeds = [b"config"], bump)]
+    pub config: Account<'info, ProtocolConfig>,
+    /// CHECK: the batch's ExecutionApproval PDA, reset if approve_execution
+    /// has created it
+    #[account(
+        mut,
+        seeds = [b"execution_approval", batch.key().as_ref()],
+        bump
+    )]
+    pub execution_approval: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ReconcileBatch<'info> {
+    #[account(mut)]
+    pub batch: Account<'info, Batch>,
+    #[account(
+        associated_token::mint = usdc_mint,
+        associated_token::authority = batch,
+        associated_token::token_program = token_program
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"credit_ledger"], bump = credit_ledger.bump, has_one = usdc_mint)]
+    pub credit_ledger: Account<'info, CreditLedger>,
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// ============================================================================
+// Yield Adapter Account Contexts
+// ============================================================================
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ParkEscrow<'info> {
+    #[account(
+        mut,
+        seeds = [b"batch", batch.authority.as_ref(), batch.market_id.as_bytes(), batch.lane_seed()],
+        bump
+    )]
+    pub batch: Account<'info, Batch>,
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.operator == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = batch,
+        associated_token::token_program = token_program
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"credit_ledger"], bump = credit_ledger.bump, has_one = usdc_mint)]
+    pub credit_ledger: Account<'info, CreditLedger>,
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    /// CHECK: the whitelisted adapter, checked by address
+    #[account(executable, address = config.yield_adapter @ ErrorCode::YieldDisabled)]
+    pub adapter_program: UncheckedAccount<'info>,
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RecallEscrow<'info> {
+    #[account(
+        mut,
+        seeds = [b"batch", batch.authority.as_ref(), batch.market_id.as_bytes(), batch.lane_seed()],
+        bump
+    )]
+    pub batch: Account<'info, Batch>,
+    #[account(seeds = [b"config"], bump, has_one = yield_recipient)]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = batch,
+        associated_token::token_program = token_program
+    )]
+    pub escrow: InterfaceAccount<'info, TokenAccount>,
+    #[account(seeds = [b"credit_ledger"], bump = credit_ledger.bump, has_one = usdc_mint)]
+    pub credit_ledger: Account<'info, CreditLedger>,
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, token::mint = usdc_mint)]
+    pub yield_recipient: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+    /// CHECK: the adapter the batch was parked in, checked by address
+    #[account(executable, address = batch.parked_adapter @ ErrorCode::NothingParked)]
+    pub adapter_program: UncheckedAccount<'info>,
+}
+
+// ============================================================================
+// Portfolio Account Contexts
+// ============================================================================
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(owner: Pubkey)]
+pub struct StorePortfolio<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 32 + 32 + 16 + 32 * PORTFOLIO_CIPHERTEXTS + 8 + 8,
+        seeds = [b"portfolio", authority.key().as_ref(), owner.as_ref()],
+        bump
+    )]
+    pub portfolio: Account<'info, UserPortfolio>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ============================================================================
+// Insurance Fund Account Contexts
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitializeInsuranceFund<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + 32 + 32 + 32 + 2 + 8 + 8 + 1,
+        seeds = [b"insurance_fund"],
+        bump
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"insurance_vault"],
+        bump,
+        token::mint = usdc_mint,
+        token::authority = insurance_fund,
+        token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FundInsurance<'info> {
+    #[account(
+        mut,
+        seeds = [b"insurance_fund"],
+        bump = insurance_fund.bump,
+        has_one = vault,
+        has_one = usdc_mint
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+    #[account(mut)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, token::mint = usdc_mint, token::authority = funder)]
+    pub funder_usdc: InterfaceAccount<'info, TokenAccount>,
+    pub funder: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimShortfall<'info> {
+    pub batch: Account<'info, Batch>,
+    #[account(mut, has_one = batch)]
+    pub distribution: Account<'info, Distribution>,
+    #[account(
+        mut,
+        seeds = [b"insurance_fund"],
+        bump = insurance_fund.bump,
+        has_one = vault,
+        has_one = usdc_mint
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+    #[account(mut)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, token::mint = usdc_mint, token::authority = distribution.wallet)]
+    pub recipient_usdc: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeCreditLedger<'info> {
+    #[account(seeds = [b"config"], bump, has_one = admin)]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + 32 + 8 + 8 + 1,
+        seeds = [b"credit_ledger"],
+        bump
+    )]
+    pub credit_ledger: Account<'info, CreditLedger>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"credit_vault"],
+        bump,
+        token::mint = usdc_mint,
+        token::authority = credit_ledger,
+        token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(owner: Pubkey)]
+pub struct RecordCredit<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.settler == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+    #[account(
+        mut,
+        seeds = [b"credit_ledger"],
+        bump = credit_ledger.bump,
+        has_one = vault,
+        has_one = usdc_mint
+    )]
+    pub credit_ledger: Account<'info, CreditLedger>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + 32 + 8 + 8 + 8 + 8,
+        seeds = [b"credit", owner.as_ref()],
+        bump
+    )]
+    pub user_credit: Account<'info, UserCredit>,
+    #[account(mut)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, token::mint = usdc_mint, token::authority = authority)]
+    pub settler_usdc: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ClaimCredit<'info> {
+    #[account(
+        mut,
+        seeds = [b"credit", owner.key().as_ref()],
+        bump,
+        has_one = owner
+    )]
+    pub user_credit: Account<'info, UserCredit>,
+    #[account(
+        mut,
+        seeds = [b"credit_ledger"],
+        bump = credit_ledger.bump,
+        has_one = vault,
+        has_one = usdc_mint
+    )]
+    pub credit_ledger: Account<'info, CreditLedger>,
+    #[account(mut)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    #[account(mut, token::mint = usdc_mint)]
+    pub recipient_usdc: InterfaceAccount<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// ============================================================================
+// Session Key Account Contexts
+// ============================================================================
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CreateSessionKey<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + SessionKey::SIZE,
+        seeds = [b"session", user.key().as_ref(), session_key.key().as_ref()],
+        bump
+    )]
+    pub session: Account<'info, SessionKey>,
     #[account(mut)]
-    pub comp_def_account: UncheckedAccount<'info>,
-    pub arcium_program: Program<'info, Arcium>,
+    pub user: Signer<'info>,
+    /// CHECK: any key; it gains only the user's scoped authority
+    pub session_key: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
 
-#[init_computation_definition_accounts("compute_distribution", payer)]
+#[event_cpi]
 #[derive(Accounts)]
-pub struct InitComputeDistributionCompDef<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-    #[account(mut, address = derive_mxe_pda!())]
-    pub mxe_account: Box<Account<'info, MXEAccount>>,
-    /// CHECK: Initialized via CPI
+pub struct RevokeSessionKey<'info> {
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"session", user.key().as_ref(), session.session_key.as_ref()],
+        bump,
+        has_one = user
+    )]
+    pub session: Account<'info, SessionKey>,
     #[account(mut)]
-    pub comp_def_account: UncheckedAccount<'info>,
-    pub arcium_program: Program<'info, Arcium>,
-    pub system_program: Program<'info, System>,
+    pub user: Signer<'info>,
 }
 
 // ============================================================================
-// Batch Management Account Contexts
+// Subscription Account Contexts
 // ============================================================================
 
+#[event_cpi]
 #[derive(Accounts)]
-#[instruction(market_id: String)]
-pub struct CreateBatch<'info> {
+#[instruction(id: u64)]
+pub struct CreateSubscription<'info> {
     #[account(
         init,
-        payer = authority,
-        space = 8 + 32 + 68 + 1 + 1 + 1 + 8 + 8 + 8 + 1,
-        seeds = [b"batch", authority.key().as_ref(), market_id.as_bytes()],
+        payer = owner,
+        space = 8 + Subscription::SIZE,
+        seeds = [b"subscription", owner.key().as_ref(), &id.to_le_bytes()],
         bump
     )]
-    pub batch: Account<'info, Batch>,
+    pub subscription: Account<'info, Subscription>,
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub owner: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-pub struct RecordOrder<'info> {
-    #[account(mut, has_one = authority)]
-    pub batch: Account<'info, Batch>,
-    pub authority: Signer<'info>,
-}
-
-#[derive(Accounts)]
-pub struct CloseBatch<'info> {
-    #[account(mut, has_one = authority)]
-    pub batch: Account<'info, Batch>,
-    pub authority: Signer<'info>,
+pub struct CancelSubscription<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"subscription", owner.key().as_ref(), &subscription.id.to_le_bytes()],
+        bump,
+        has_one = owner
+    )]
+    pub subscription: Account<'info, Subscription>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-pub struct RecordExecution<'info> {
-    #[account(mut, has_one = authority)]
-    pub batch: Account<'info, Batch>,
+pub struct StoreDcaSchedule<'info> {
+    #[account(
+        mut,
+        seeds = [b"subscription", subscription.owner.as_ref(), &subscription.id.to_le_bytes()],
+        bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.operator == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
     pub authority: Signer<'info>,
 }
 
+#[event_cpi]
 #[derive(Accounts)]
-#[instruction(order_index: u8)]
-pub struct RecordDistribution<'info> {
-    #[account(mut, has_one = authority)]
+pub struct EnrollSubscription<'info> {
+    #[account(mut)]
     pub batch: Account<'info, Batch>,
+    #[account(
+        mut,
+        seeds = [b"subscription", subscription.owner.as_ref(), &subscription.id.to_le_bytes()],
+        bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+    #[account(
+        seeds = [b"config"],
+        bump,
+        constraint = config.operator == authority.key() @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 1 + 8 + 32 + 1,
-        seeds = [b"dist", batch.key().as_ref(), &[order_index]],
+        space = 8 + 32 + 1 + 1,
+        seeds = [b"order_slot", batch.key().as_ref(), &[batch.order_count]],
         bump
     )]
-    pub distribution: Account<'info, Distribution>,
+    pub order_slot: Account<'info, OrderSlot>,
     #[account(mut)]
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
-#[derive(Accounts)]
-pub struct MarkDistributed<'info> {
-    #[account(mut, has_one = authority)]
-    pub batch: Account<'info, Batch>,
-    #[account(mut, has_one = batch)]
-    pub distribution: Account<'info, Distribution>,
-    pub authority: Signer<'info>,
-}
-
 // ============================================================================
 // Events
 // ============================================================================
 
+/// A batch opened for orders.
 #[event]
 pub struct BatchCreated {
     pub batch: Pubkey,
     pub market_id: String,
     pub side: u8,
+    pub cluster_offset: u32,
+    pub circuit_version: u16,
+    pub label: String,
+    pub intent_hash: Option<[u8; 32]>,
+    /// Creation order across all batches; the other batch events repeat it
+    pub sequence: u64,
+}
+
+/// A protocol role was handed from `previous` to `key`.
+#[event]
+pub struct RoleUpdated {
+    pub role: Role,
+    pub previous: Pubkey,
+    pub key: Pubkey,
+}
+
+/// A circuit's computation definition was registered.
+#[event]
+pub struct CompDefRegistered {
+    pub circuit: String,
+    pub offset: u32,
+    pub comp_def: Pubkey,
+    pub version: u16,
+}
+
+/// New batches use circuit version `circuit_version`.
+#[event]
+pub struct CircuitVersionUpdated {
+    pub previous_version: u16,
+    pub circuit_version: u16,
+}
+
+/// New batches queue on `cluster_offset`.
+#[event]
+pub struct ClusterUpdated {
+    pub previous_cluster_offset: u32,
+    pub cluster_offset: u32,
+    pub fallback_cluster_offset: u32,
+}
+
+/// Orders in `market_id` are now sized in multiples of `lot_size`.
+#[event]
+pub struct MarketLotSizeUpdated {
+    pub market_id: String,
+    pub lot_size: u64,
+}
+
+/// The YES and NO outcome mints of `market_id` were set.
+#[event]
+pub struct MarketOutcomeMintsUpdated {
+    pub market_id: String,
+    pub yes_mint: Pubkey,
+    pub no_mint: Pubkey,
+}
+
+/// The order size thresholds splitting `market_id` into lanes changed.
+#[event]
+pub struct MarketLanesUpdated {
+    pub market_id: String,
+    pub thresholds: Vec<u64>,
+}
+
+/// An open batch moved to another MPC cluster.
+#[event]
+pub struct BatchClusterMigrated {
+    pub batch: Pubkey,
+    pub previous_cluster_offset: u32,
+    pub cluster_offset: u32,
+    pub sequence: u64,
 }
 
+/// An order was added to the batch, now holding `order_count`.
 #[event]
 pub struct OrderRecorded {
     pub batch: Pubkey,
     pub order_count: u8,
+    pub sequence: u64,
+}
+
+/// An order was routed to the batch of its size lane.
+#[event]
+pub struct OrderRouted {
+    pub batch: Pubkey,
+    pub lane: u8,
+    pub order_index: u8,
+    pub sequence: u64,
+}
+
+/// The revealed order count of an open batch was posted.
+#[event]
+pub struct OrderCountPosted {
+    pub batch: Pubkey,
+    pub order_count: u8,
+    pub posted_at: i64,
+    pub sequence: u64,
 }
 
+/// A relayer submitted an order signed by `user`.
+#[event]
+pub struct DelegatedOrderRecorded {
+    pub batch: Pubkey,
+    pub order_index: u8,
+    pub user: Pubkey,
+    pub relayer: Pubkey,
+    pub order_commitment: [u8; 32],
+    pub sequence: u64,
+}
+
+/// The batch closed with its revealed total and order count.
 #[event]
 pub struct BatchClosed {
     pub batch: Pubkey,
     pub total_usdc: u64,
     pub order_count: u8,
+    pub sequence: u64,
 }
 
+/// The batch's trade executed for `total_shares`.
 #[event]
 pub struct ExecutionRecorded {
     pub batch: Pubkey,
     pub total_shares: u64,
+    pub filled_usdc: u64,
     pub tx_signature: String,
+    pub intent_hash: Option<[u8; 32]>,
+    pub sequence: u64,
+}
+
+/// Slots between the reveal of the batch total (close_batch) and
+/// record_execution: how long the total was public before the trade was
+/// recorded. Emitted with every ExecutionRecorded.
+#[event]
+pub struct RevealExecutionGap {
+    pub batch: Pubkey,
+    pub closed_slot: u64,
+    pub executed_slot: u64,
+    pub gap_slots: u64,
+    pub sequence: u64,
+}
+
+/// The batch's revealed total went unexecuted past the configured gap;
+/// expire_reveal reopened it for a fresh close.
+#[event]
+pub struct RevealExpired {
+    pub batch: Pubkey,
+    pub closed_slot: u64,
+    pub slot: u64,
+    pub max_reveal_exec_gap: u64,
+    pub sequence: u64,
+}
+
+/// The yield adapter for idle escrow was changed or toggled.
+#[event]
+pub struct YieldAdapterUpdated {
+    pub adapter: Option<Pubkey>,
+    pub yield_recipient: Pubkey,
+    pub enabled: bool,
+}
+
+/// Idle batch escrow was deposited into the yield adapter.
+#[event]
+pub struct EscrowParked {
+    pub batch: Pubkey,
+    pub adapter: Pubkey,
+    pub amount: u64,
+    pub sequence: u64,
+}
+
+/// Parked escrow came back from the yield adapter with its earnings.
+#[event]
+pub struct EscrowRecalled {
+    pub batch: Pubkey,
+    pub adapter: Pubkey,
+    pub principal: u64,
+    pub earnings: u64,
+    pub sequence: u64,
 }
 
+/// The approvers and threshold for large executions changed.
+#[event]
+pub struct ExecutionApproversUpdated {
+    pub approvers: Vec<Pubkey>,
+    pub threshold: u8,
+    pub large_batch_threshold: u64,
+}
+
+/// Which callers record_order accepts changed; see set_cpi_guard.
+#[event]
+pub struct CpiGuardUpdated {
+    pub enabled: bool,
+    pub allowed_programs: Vec<Pubkey>,
+}
+
+/// An approver signed off on a large batch's execution.
+#[event]
+pub struct ExecutionApproved {
+    pub batch: Pubkey,
+    pub approver: Pubkey,
+    pub total_shares: u64,
+    pub filled_usdc: u64,
+    pub approvals: u8,
+    pub sequence: u64,
+}
+
+/// The execution and completion timeouts changed.
+#[event]
+pub struct BatchTimeoutsUpdated {
+    pub execution_timeout: i64,
+    pub completion_timeout: i64,
+}
+
+/// The adaptive batch window bounds and order target changed.
+#[event]
+pub struct BatchWindowUpdated {
+    pub min_batch_window: i64,
+    pub max_batch_window: i64,
+    pub target_batch_orders: u8,
+}
+
+/// The express lane's window or fee changed; see set_express_lane.
+#[event]
+pub struct ExpressLaneUpdated {
+    pub batch_window: i64,
+    pub fee_bps: u16,
+}
+
+/// The limit on slots between reveal and execution changed; see
+/// set_max_reveal_exec_gap.
+#[event]
+pub struct RevealExecGapUpdated {
+    pub max_reveal_exec_gap: u64,
+}
+
+/// The batch escrow held less than the batch accounts for; the batch
+/// froze.
+#[event]
+pub struct ReconciliationMismatch {
+    pub batch: Pubkey,
+    pub previous_status: BatchStatus,
+    pub expected: u64,
+    pub actual: u64,
+    pub sequence: u64,
+}
+
+/// The batch was failed and its orders become refundable.
+#[event]
+pub struct BatchFailed {
+    pub batch: Pubkey,
+    pub previous_status: BatchStatus,
+    pub order_count: u8,
+    pub total_usdc: u64,
+    pub sequence: u64,
+}
+
+/// The merkle root of the batch's allocations was committed, with the
+/// number of distributions to record against it.
+#[event]
+pub struct DistributionsStaged {
+    pub batch: Pubkey,
+    pub root: [u8; 32],
+    pub count: u8,
+    pub shares: u64,
+    pub sequence: u64,
+}
+
+/// Every staged distribution was recorded; payouts may start.
+#[event]
+pub struct DistributionsFinalized {
+    pub batch: Pubkey,
+    pub count: u8,
+    pub sequence: u64,
+}
+
+/// An order's allocation was recorded against the committed root.
 #[event]
 pub struct DistributionRecorded {
     pub batch: Pubkey,
     pub order_index: u8,
     pub shares: u64,
+    pub usdc_refund: u64,
+    pub wallet: Pubkey,
+    pub unlock_at: i64,
+    pub sequence: u64,
+}
+
+/// An order's allocation was recorded encrypted to its owner.
+#[event]
+pub struct EncryptedDistributionRecorded {
+    pub batch: Pubkey,
+    pub order_index: u8,
+    pub wallet: Pubkey,
+    pub sequence: u64,
+}
+
+/// An order's allocation was recorded for a stealth claim.
+#[event]
+pub struct StealthDistributionRecorded {
+    pub batch: Pubkey,
+    pub order_index: u8,
+    pub shares: u64,
+    pub sequence: u64,
+}
+
+/// A stealth allocation was claimed by proving its commitment.
+#[event]
+pub struct StealthDistributionClaimed {
+    pub batch: Pubkey,
+    pub order_index: u8,
+    pub commitment: [u8; 32],
+    pub sequence: u64,
+}
+
+/// An order's wallet pulled its shares and refund with claim_shares.
+#[event]
+pub struct SharesClaimed {
+    pub batch: Pubkey,
+    pub order_index: u8,
     pub wallet: Pubkey,
+    pub shares: u64,
+    pub usdc_refund: u64,
+    pub unlock_at: i64,
+    pub sequence: u64,
 }
 
+/// An order's shares and refund were sent.
 #[event]
 pub struct DistributionExecuted {
     pub batch: Pubkey,
     pub order_index: u8,
     pub tx_signature: String,
+    pub sequence: u64,
+}
+
+/// A user's encrypted portfolio was replaced.
+#[event]
+pub struct PortfolioUpdated {
+    pub owner: Pubkey,
+    pub updates: u64,
+}
+
+/// USDC was added to the insurance fund.
+#[event]
+pub struct InsuranceFunded {
+    pub funder: Pubkey,
+    pub amount: u64,
+    pub total_funded: u64,
+}
+
+/// USDC was credited to `owner` for a later claim.
+#[event]
+pub struct CreditRecorded {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub reason: CreditReason,
+    /// Batch the credit arose from
+    pub batch: Pubkey,
+    pub balance: u64,
+}
+
+/// A user delegated order signing to a session key.
+#[event]
+pub struct SessionKeyCreated {
+    pub user: Pubkey,
+    pub session_key: Pubkey,
+    pub expires_at: i64,
+    pub scope: u8,
+}
+
+/// A session key was revoked before it expired.
+#[event]
+pub struct SessionKeyRevoked {
+    pub user: Pubkey,
+    pub session_key: Pubkey,
+}
+
+/// An owner subscribed to recurring orders in a market.
+#[event]
+pub struct SubscriptionCreated {
+    pub subscription: Pubkey,
+    pub owner: Pubkey,
+    pub market_id: String,
+    pub side: u8,
+    pub cadence: i64,
+    pub orders: u32,
+    pub jitter_bps: u16,
+}
+
+/// A jittered subscription's encrypted schedule was replaced.
+#[event]
+pub struct DcaScheduleStored {
+    pub subscription: Pubkey,
+    pub remaining: u32,
+}
+
+/// A subscription was cancelled with `remaining` enrollments unused.
+#[event]
+pub struct SubscriptionCancelled {
+    pub subscription: Pubkey,
+    pub owner: Pubkey,
+    pub remaining: u32,
+}
+
+/// A subscription's order was recorded in `batch` at `order_index`; the
+/// relay submits the ciphertext behind `ciphertext_ref` for it.
+#[event]
+pub struct SubscriptionEnrolled {
+    pub subscription: Pubkey,
+    pub batch: Pubkey,
+    pub order_index: u8,
+    pub ciphertext_ref: [u8; 32],
+    pub remaining: u32,
+    pub sequence: u64,
+}
+
+/// `owner` withdrew credited USDC.
+#[event]
+pub struct CreditClaimed {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub balance: u64,
+}
+
+/// An order short of executed shares was compensated from the insurance
+/// fund.
+#[event]
+pub struct ShortfallClaimed {
+    pub batch: Pubkey,
+    pub order_index: u8,
+    pub wallet: Pubkey,
+    pub amount: u64,
+    pub sequence: u64,
+}
+
+/// A completed batch was added to its market's running stats.
+#[event]
+pub struct MarketStatsUpdated {
+    pub market_id: String,
+    pub completed_batches: u64,
+    pub total_orders: u64,
+    pub total_usdc: u64,
+    pub total_shares: u64,
+}
+
+// ============================================================================
+// Views
+// ============================================================================
+
+/// Return data for `get_batch_status`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BatchStatusView {
+    pub status: BatchStatus,
+    pub side: u8,
+    pub order_count: u8,
+    pub distributions_completed: u8,
+    pub total_usdc: u64,
+    pub total_shares: u64,
+    pub created_at: i64,
+}
+
+/// A relay-driven instruction failed a check (see `require_reported!`).
+/// Logged from failed transactions, so it is only visible in their logs.
+#[event]
+pub struct ProtocolError {
+    /// Anchor error code (ErrorCode discriminant + 6000)
+    pub code: u32,
+    pub error: String,
+    /// Batch the failing instruction acted on
+    pub subject: Pubkey,
+    /// Signer that submitted it, normally the relay
+    pub actor: Pubkey,
+    /// Instruction name and the values that failed the check
+    pub context: String,
+    pub timestamp: i64,
 }
 
 // ============================================================================
@@ -442,4 +5643,146 @@ pub enum ErrorCode {
     AlreadyDistributed,
     #[msg("Order count mismatch")]
     CountMismatch,
+    #[msg("Batch window has not elapsed")]
+    BatchWindowNotElapsed,
+    #[msg("Fee share must be at most 10000 bps")]
+    InvalidFeeShare,
+    #[msg("Batch is not completed")]
+    BatchNotCompleted,
+    #[msg("Batch has no share shortfall")]
+    NoShortfall,
+    #[msg("Shortfall already claimed")]
+    ShortfallAlreadyClaimed,
+    #[msg("Distribution is still locked")]
+    DistributionLocked,
+    #[msg("Too many batch totals revealed for this market recently")]
+    RevealBudgetExhausted,
+    #[msg("Batch is already completed")]
+    BatchAlreadyCompleted,
+    #[msg("Batch is already on the configured cluster")]
+    ClusterUnchanged,
+    #[msg("Computation definition is not initialized")]
+    CompDefNotInitialized,
+    #[msg("Computation definition is not registered")]
+    CompDefNotRegistered,
+    #[msg("MPC registry is full")]
+    RegistryFull,
+    #[msg("Computation definition version does not match the batch")]
+    CircuitVersionMismatch,
+    #[msg("Batch label is too long")]
+    LabelTooLong,
+    #[msg("Execution intent does not match the batch")]
+    IntentMismatch,
+    #[msg("Signer does not hold the required role")]
+    Unauthorized,
+    #[msg("Relay nonce already used or out of order")]
+    StaleRelayNonce,
+    #[msg("Credit amount must be positive")]
+    ZeroCredit,
+    #[msg("Claim exceeds the available credit")]
+    InsufficientCredit,
+    #[msg("Filled USDC exceeds the batch total")]
+    FillExceedsBatch,
+    #[msg("Refunds exceed the unfilled USDC")]
+    RefundExceedsUnfilled,
+    #[msg("Distributions already staged")]
+    DistributionRootCommitted,
+    #[msg("Distribution is not in the committed distribution root")]
+    InvalidDistributionProof,
+    #[msg("Order index is not a recorded order of the batch")]
+    OrderIndexOutOfRange,
+    #[msg("Order already has a distribution")]
+    OrderAlreadyDistributed,
+    #[msg("Timeouts cannot be negative")]
+    InvalidTimeout,
+    #[msg("Batch has not exceeded its timeout")]
+    BatchNotStuck,
+    #[msg("Batch has failed")]
+    BatchFailed,
+    #[msg("Batch is frozen pending reconciliation")]
+    BatchFrozen,
+    #[msg("Batch escrow is only reconciled while Closed or Executed")]
+    BatchNotReconcilable,
+    #[msg("Approvers must be distinct, at most MAX_EXECUTION_APPROVERS, and cover the threshold")]
+    InvalidExecutionApprovers,
+    #[msg("Large batch execution lacks enough matching approvals")]
+    ExecutionNotApproved,
+    #[msg("Approval does not match the execution already approved")]
+    ExecutionApprovalMismatch,
+    #[msg("Approver already approved this execution")]
+    AlreadyApproved,
+    #[msg("Yield adapter is not set or disabled")]
+    YieldDisabled,
+    #[msg("Batch escrow is parked in a yield adapter")]
+    EscrowParked,
+    #[msg("Batch has no escrow parked")]
+    NothingParked,
+    #[msg("Escrow did not move by the parked amount")]
+    AdapterAccountingMismatch,
+    #[msg("Yield adapter returned less than was parked")]
+    AdapterShortfall,
+    #[msg("Order intent has expired")]
+    OrderIntentExpired,
+    #[msg("Order intent is not signed by its user for this batch")]
+    InvalidOrderIntentSignature,
+    #[msg("Session scope must be a non-empty set of known scopes")]
+    InvalidSessionScope,
+    #[msg("Session expiry must be in the future and within the maximum duration")]
+    InvalidSessionExpiry,
+    #[msg("Session key is expired or not scoped for this instruction")]
+    SessionKeyNotAuthorized,
+    #[msg("Batch status cannot move from its current status to the requested one")]
+    InvalidTransition,
+    #[msg("Another program trades the batch's market earlier in the transaction")]
+    MarketTradedInTransaction,
+    #[msg("Batch holds the maximum number of orders")]
+    BatchFull,
+    #[msg("Lane thresholds must be non-zero, ascending and fewer than MAX_MARKET_LANES")]
+    InvalidLaneThresholds,
+    #[msg("Lane is not configured for this market or batch")]
+    InvalidLane,
+    #[msg("Batch window bounds must satisfy 0 <= min <= max, with a target order count exactly when max > 0")]
+    InvalidBatchWindow,
+    #[msg("Posted order count exceeds the orders recorded in the batch")]
+    OrderCountMismatch,
+    #[msg("Instruction was called through a program the CPI guard does not allow")]
+    CpiCallerNotAllowed,
+    #[msg("Too many programs for the CPI guard")]
+    TooManyCpiCallers,
+    #[msg("Subscription needs a market id of at most 64 bytes, a cadence of at least the batch duration, at least one order and jitter within MAX_DCA_JITTER_BPS")]
+    InvalidSubscription,
+    #[msg("Batch is not in the subscription's market and side")]
+    SubscriptionMarketMismatch,
+    #[msg("Subscription has no enrollments left, is not due yet, or is already in this batch")]
+    SubscriptionNotDue,
+    #[msg("Express lane is not enabled")]
+    ExpressLaneDisabled,
+    #[msg("Express window must be non-negative and fee at most 10000 bps")]
+    InvalidExpressLane,
+    #[msg("Staged distribution count must equal the batch's order count")]
+    DistributionCountMismatch,
+    #[msg("Distributions are not staged, or every staged distribution is recorded")]
+    DistributionsNotStaged,
+    #[msg("Not every staged distribution is recorded")]
+    DistributionsIncomplete,
+    #[msg("Distributions are already finalized")]
+    DistributionsAlreadyFinalized,
+    #[msg("Distributions must be finalized before payout")]
+    DistributionsNotFinalized,
+    #[msg("USDC mint has a Token-2022 extension the protocol does not support")]
+    UnsupportedMintExtension,
+    #[msg("Distribution count must be between 1 and MAX_DISTRIBUTIONS_PER_IX")]
+    DistributionCountOutOfRange,
+    #[msg("Distribution account does not match the batch and order index")]
+    DistributionAccountMismatch,
+    #[msg("Revealed total has been public longer than max_reveal_exec_gap")]
+    RevealExpired,
+    #[msg("Revealed total is still within max_reveal_exec_gap")]
+    RevealNotExpired,
+    #[msg("Share mint is not the outcome mint of the batch's side")]
+    ShareMintMismatch,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("Recorded shares exceed the staged share total")]
+    SharesExceedStaged,
 }
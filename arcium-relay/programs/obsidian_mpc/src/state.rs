@@ -0,0 +1,292 @@
+//! Batch lifecycle.
+//!
+//! A batch moves Open -> Closed -> Executed -> Distributing -> Completed.
+//! reconcile_batch freezes a Closed or Executed batch whose escrow
+//! falls short of its accounting, force_fail_batch fails a batch stuck
+//! in Closed, Executed, Distributing or Frozen, and expire_reveal reopens a
+//! Closed batch whose revealed total went unexecuted too long.
+//! `Batch::transition` is the only way to change a batch's status and
+//! rejects every other move.
+
+use anchor_lang::prelude::*;
+
+use crate::{
+    distribution_leaf, distribution_proof_root, BatchResult, ErrorCode, ShareReceipt, EXPRESS_LANE,
+    MAX_BATCH_LABEL_LEN,
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum BatchStatus {
+    #[default]
+    Open,
+    Closed,
+    Executed,
+    Distributing,
+    Completed,
+    /// Timed out in Closed, Executed, Distributing or Frozen; see force_fail_batch
+    Failed,
+    /// Escrow balance fell short of the accounting; see reconcile_batch
+    Frozen,
+}
+
+impl BatchStatus {
+    /// Whether a batch in this status may move to `to`
+    pub fn can_transition_to(self, to: BatchStatus) -> bool {
+        use BatchStatus::*;
+        matches!(
+            (self, to),
+            (Open, Closed)
+                | (Closed, Executed)
+                | (Closed, Open)
+                | (Executed, Distributing)
+                | (Distributing, Completed)
+                | (Closed | Executed, Frozen)
+                | (Closed | Executed | Distributing | Frozen, Failed)
+        )
+    }
+}
+
+/// Priority lane create_batch opens a batch in
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum BatchLane {
+    #[default]
+    Standard,
+    /// Shorter window, higher fee; see set_express_lane
+    Express,
+}
+
+impl BatchLane {
+    /// `Batch::lane` of a batch in this lane
+    pub fn index(self) -> u8 {
+        match self {
+            BatchLane::Standard => 0,
+            BatchLane::Express => EXPRESS_LANE,
+        }
+    }
+
+    /// Last seed of the batch PDA; see [`Batch::lane_seed`].
+    pub fn seed(self) -> &'static [u8] {
+        match self {
+            BatchLane::Standard => &[],
+            BatchLane::Express => &[EXPRESS_LANE],
+        }
+    }
+}
+
+#[account]
+#[derive(Default)]
+pub struct Batch {
+    pub authority: Pubkey,
+    pub market_id: String,
+    pub side: u8,
+    /// Changed only through [`Batch::transition`]
+    status: BatchStatus,
+    pub order_count: u8,
+    pub total_usdc: u64,
+    pub total_shares: u64,
+    pub created_at: i64,
+    pub distributions_completed: u8,
+    /// Sum of shares across recorded distributions
+    pub recorded_shares: u64,
+    /// Arcium cluster the batch's computations run on
+    pub cluster_offset: u32,
+    /// Circuit version pinned at creation
+    pub circuit_version: u16,
+    /// Client-supplied tag, empty if none
+    pub label: String,
+    /// Hash of the off-chain execution intent, quoted by record_execution
+    pub intent_hash: Option<[u8; 32]>,
+    /// USDC spent at DFlow; below total_usdc on a partial fill
+    pub filled_usdc: u64,
+    /// Sum of usdc_refund across recorded distributions
+    pub recorded_refunds: u64,
+    /// Root over the revealed distributions; see stage_distributions
+    pub distribution_root: Option<[u8; 32]>,
+    /// When the batch entered its current status; force_fail_batch
+    /// timeouts run from here
+    pub transitioned_at: i64,
+    /// Escrow USDC parked in a yield adapter; must be 0 to execute
+    pub parked_usdc: u64,
+    /// Adapter holding parked_usdc (default = none)
+    pub parked_adapter: Pubkey,
+    /// Lane of the market the batch serves; 0 for standard create_batch
+    /// batches, EXPRESS_LANE for express ones
+    pub lane: u8,
+    /// Fee the relay charges on the batch's fills, pinned at creation:
+    /// config.express_fee_bps for express batches, 0 otherwise
+    pub fee_bps: u16,
+    /// Creation order across all batches; see BatchSequence
+    pub sequence: u64,
+    /// Distributions stage_distributions committed to recording
+    pub staged_distributions: u8,
+    /// Distributions recorded so far, of staged_distributions
+    pub recorded_distributions: u8,
+    /// Set by finalize_distributions once every staged distribution is
+    /// recorded; payouts wait for it
+    pub distributions_finalized: bool,
+    /// Slot of the last close_batch, i.e. when the total was revealed
+    pub closed_slot: u64,
+    /// Slot of record_execution (0 until executed)
+    pub executed_slot: u64,
+    /// Sum of shares over the staged plaintext and stealth leaves; bounds
+    /// recorded_shares
+    pub staged_shares: u64,
+}
+
+impl Batch {
+    pub const SIZE: usize = 32
+        + 68
+        + 1
+        + 1
+        + 1
+        + 8
+        + 8
+        + 8
+        + 1
+        + 8
+        + 4
+        + 2
+        + (4 + MAX_BATCH_LABEL_LEN)
+        + 33
+        + 8
+        + 8
+        + 33
+        + 8
+        + 8
+        + 32
+        + 1
+        + 2
+        + 8
+        + 1
+        + 1
+        + 1
+        + 8
+        + 8
+        + 8;
+
+    pub fn status(&self) -> BatchStatus {
+        self.status
+    }
+
+    /// Move to `to`, failing with InvalidTransition unless the lifecycle
+    /// allows it. Callers keep transitioned_at up to date themselves: it
+    /// is not reset on entering Distributing or Completed, so the
+    /// completion timeout runs from execution.
+    pub fn transition(&mut self, to: BatchStatus) -> Result<()> {
+        require!(
+            self.status.can_transition_to(to),
+            ErrorCode::InvalidTransition
+        );
+        self.status = to;
+        Ok(())
+    }
+
+    /// recorded_shares after adding `shares`, which callers check against
+    /// staged_shares before storing.
+    pub fn recorded_shares_with(&self, shares: u64) -> Result<u64> {
+        self.recorded_shares
+            .checked_add(shares)
+            .ok_or_else(|| ErrorCode::Overflow.into())
+    }
+
+    /// Check a claim_shares receipt for the order at `order_index` paying
+    /// `wallet`: its leaf must be under distribution_root and unlocked at
+    /// `now`, and with it the batch must stay within the unfilled USDC and
+    /// staged_shares. Returns recorded_refunds and recorded_shares with the
+    /// claim added.
+    pub fn check_claim(
+        &self,
+        order_index: u8,
+        wallet: &Pubkey,
+        receipt: &ShareReceipt,
+        now: i64,
+    ) -> Result<(u64, u64)> {
+        require!(
+            self.status == BatchStatus::Executed || self.status == BatchStatus::Distributing,
+            ErrorCode::BatchNotExecuted
+        );
+        require!(
+            self.recorded_distributions < self.staged_distributions,
+            ErrorCode::DistributionsNotStaged
+        );
+        let leaf = distribution_leaf(
+            order_index,
+            receipt.shares,
+            receipt.usdc_refund,
+            receipt.unlock_at,
+            wallet,
+        );
+        require!(
+            self.distribution_root
+                == Some(distribution_proof_root(leaf, order_index, &receipt.proof)),
+            ErrorCode::InvalidDistributionProof
+        );
+        require!(now >= receipt.unlock_at, ErrorCode::DistributionLocked);
+
+        let recorded_refunds = self
+            .recorded_refunds
+            .checked_add(receipt.usdc_refund)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(
+            recorded_refunds <= self.total_usdc - self.filled_usdc,
+            ErrorCode::RefundExceedsUnfilled
+        );
+        let recorded_shares = self.recorded_shares_with(receipt.shares)?;
+        require!(
+            recorded_shares <= self.staged_shares,
+            ErrorCode::SharesExceedStaged
+        );
+        Ok((recorded_refunds, recorded_shares))
+    }
+
+    /// Last seed of the batch PDA: empty for lane 0, so those batches
+    /// keep the address create_batch always gave them.
+    pub fn lane_seed(&self) -> &[u8] {
+        lane_seed(&self.lane)
+    }
+
+    pub fn is_express(&self) -> bool {
+        self.lane == EXPRESS_LANE
+    }
+
+    /// Whether the total revealed at closed_slot has been public for more
+    /// than `max_gap` slots at `slot` (0 = no limit); see
+    /// set_max_reveal_exec_gap.
+    pub fn reveal_expired(&self, max_gap: u64, slot: u64) -> bool {
+        max_gap > 0 && slot.saturating_sub(self.closed_slot) > max_gap
+    }
+
+    /// The balance the escrow must hold, if `escrow` is short of it:
+    /// total_usdc less parked_usdc once Closed, less filled_usdc once
+    /// Executed. A surplus is not a shortfall: anyone can send the escrow
+    /// USDC, and orders that expired out of the revealed total leave
+    /// theirs behind until refunded.
+    pub fn escrow_shortfall(&self, escrow: u64) -> Result<Option<u64>> {
+        let expected = match self.status {
+            BatchStatus::Closed => self.total_usdc - self.parked_usdc,
+            BatchStatus::Executed => self.total_usdc - self.filled_usdc,
+            _ => return err!(ErrorCode::BatchNotReconcilable),
+        };
+        Ok((escrow < expected).then_some(expected))
+    }
+
+    pub fn result(&self, batch: Pubkey) -> BatchResult {
+        BatchResult {
+            batch,
+            status: self.status,
+            order_count: self.order_count,
+            total_usdc: self.total_usdc,
+            total_shares: self.total_shares,
+            filled_usdc: self.filled_usdc,
+        }
+    }
+}
+
+/// Batch PDA seed for `lane`; see [`Batch::lane_seed`].
+pub fn lane_seed(lane: &u8) -> &[u8] {
+    if *lane == 0 {
+        &[]
+    } else {
+        std::slice::from_ref(lane)
+    }
+}
@@ -0,0 +1,93 @@
+use anchor_lang::error::Error;
+use anchor_spl::token_interface::spl_token_2022::extension::interest_bearing_mint::InterestBearingConfig;
+use anchor_spl::token_interface::spl_token_2022::extension::non_transferable::NonTransferable;
+use anchor_spl::token_interface::spl_token_2022::extension::transfer_fee::{
+    TransferFee, TransferFeeConfig,
+};
+use anchor_spl::token_interface::spl_token_2022::extension::{
+    BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut,
+};
+use anchor_spl::token_interface::spl_token_2022::state::Mint;
+use obsidian_mpc::{check_mint_extensions, mint_received_amount, ErrorCode};
+
+/// Token-2022 USDC mint data with `extensions`; a 1% fee capped at 5 USDC
+/// from epoch 10 when it has a transfer fee
+fn mint(extensions: &[ExtensionType]) -> Vec<u8> {
+    let len = ExtensionType::try_calculate_account_len::<Mint>(extensions).unwrap();
+    let mut data = vec![0; len];
+    let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+    for extension in extensions {
+        match extension {
+            ExtensionType::TransferFeeConfig => {
+                let config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+                config.newer_transfer_fee = TransferFee {
+                    epoch: 10.into(),
+                    maximum_fee: 5_000_000.into(),
+                    transfer_fee_basis_points: 100.into(),
+                };
+            }
+            ExtensionType::InterestBearingConfig => {
+                let config = state.init_extension::<InterestBearingConfig>(true).unwrap();
+                config.current_rate = 500.into();
+            }
+            ExtensionType::NonTransferable => {
+                state.init_extension::<NonTransferable>(true).unwrap();
+            }
+            other => panic!("no fixture for {other:?}"),
+        }
+    }
+    state.base = Mint {
+        decimals: 6,
+        is_initialized: true,
+        ..Default::default()
+    };
+    state.pack_base();
+    state.init_account_type().unwrap();
+    data
+}
+
+#[test]
+fn legacy_and_supported_mints_pass() {
+    assert!(check_mint_extensions(&mint(&[])).is_ok());
+    assert!(check_mint_extensions(&mint(&[
+        ExtensionType::TransferFeeConfig,
+        ExtensionType::InterestBearingConfig,
+    ]))
+    .is_ok());
+
+    let mut legacy = vec![0; 82];
+    legacy[44] = 6;
+    legacy[45] = 1;
+    assert!(check_mint_extensions(&legacy).is_ok());
+}
+
+#[test]
+fn other_extensions_are_rejected() {
+    let err = check_mint_extensions(&mint(&[ExtensionType::NonTransferable])).unwrap_err();
+    assert_eq!(err, Error::from(ErrorCode::UnsupportedMintExtension));
+}
+
+#[test]
+fn transfer_fees_come_off_the_received_amount() {
+    let data = mint(&[ExtensionType::TransferFeeConfig]);
+    // Before the fee's epoch, then 1%, then capped
+    assert_eq!(
+        mint_received_amount(&data, 100_000_000, 9).unwrap(),
+        100_000_000
+    );
+    assert_eq!(
+        mint_received_amount(&data, 100_000_000, 10).unwrap(),
+        99_000_000
+    );
+    assert_eq!(
+        mint_received_amount(&data, 1_000_000_000, 10).unwrap(),
+        995_000_000
+    );
+
+    // Interest accrues in the displayed balance only
+    let interest = mint(&[ExtensionType::InterestBearingConfig]);
+    assert_eq!(
+        mint_received_amount(&interest, 100_000_000, 10).unwrap(),
+        100_000_000
+    );
+}
@@ -0,0 +1,373 @@
+use anchor_lang::error::Error;
+use anchor_lang::prelude::Pubkey;
+use obsidian_mpc::{
+    distribution_leaf, distribution_proof_root, lane_seed, Batch, BatchLane, BatchSequence,
+    BatchStatus, ErrorCode, MarketOutcomeMints, OrderSlot, ProtocolConfig, ShareReceipt,
+    Subscription, DISTRIBUTION_TREE_DEPTH, MAX_CPI_CALLERS,
+};
+
+use BatchStatus::*;
+
+const ALL: [BatchStatus; 7] = [
+    Open,
+    Closed,
+    Executed,
+    Distributing,
+    Completed,
+    Failed,
+    Frozen,
+];
+
+/// Every legal move; everything else must be rejected
+const LEGAL: [(BatchStatus, BatchStatus); 11] = [
+    (Open, Closed),
+    (Closed, Open),
+    (Closed, Executed),
+    (Closed, Frozen),
+    (Closed, Failed),
+    (Executed, Distributing),
+    (Executed, Frozen),
+    (Executed, Failed),
+    (Distributing, Completed),
+    (Distributing, Failed),
+    (Frozen, Failed),
+];
+
+/// A fresh batch walked along legal moves to `status`
+fn batch_in(status: BatchStatus) -> Batch {
+    let path: &[BatchStatus] = match status {
+        Open => &[],
+        Closed => &[Closed],
+        Executed => &[Closed, Executed],
+        Distributing => &[Closed, Executed, Distributing],
+        Completed => &[Closed, Executed, Distributing, Completed],
+        Failed => &[Closed, Failed],
+        Frozen => &[Closed, Frozen],
+    };
+    let mut batch = Batch::default();
+    for step in path {
+        batch.transition(*step).unwrap();
+    }
+    assert_eq!(batch.status(), status);
+    batch
+}
+
+#[test]
+fn new_batches_are_open() {
+    assert_eq!(Batch::default().status(), Open);
+}
+
+#[test]
+fn transitions_follow_the_lifecycle() {
+    for from in ALL {
+        for to in ALL {
+            let legal = LEGAL.contains(&(from, to));
+            assert_eq!(from.can_transition_to(to), legal, "{from:?} -> {to:?}");
+
+            let mut batch = batch_in(from);
+            assert_eq!(batch.transition(to).is_ok(), legal, "{from:?} -> {to:?}");
+            // A rejected move leaves the status alone
+            assert_eq!(batch.status(), if legal { to } else { from });
+        }
+    }
+}
+
+#[test]
+fn completed_and_failed_are_terminal() {
+    for terminal in [Completed, Failed] {
+        for to in ALL {
+            assert!(
+                batch_in(terminal).transition(to).is_err(),
+                "{terminal:?} -> {to:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn failed_batches_report_their_status() {
+    let batch = batch_in(Failed);
+    assert_eq!(batch.result(Default::default()).status, Failed);
+}
+
+#[test]
+fn reveals_expire_past_the_configured_gap() {
+    let mut batch = batch_in(Closed);
+    batch.closed_slot = 1_000;
+
+    assert!(!batch.reveal_expired(0, u64::MAX));
+    assert!(!batch.reveal_expired(150, 1_150));
+    assert!(batch.reveal_expired(150, 1_151));
+
+    // Reopened, the batch closes again with a fresh reveal
+    batch.transition(Open).unwrap();
+    batch.transition(Closed).unwrap();
+}
+
+#[test]
+fn escrow_shortfalls_freeze_but_surpluses_do_not() {
+    let mut batch = batch_in(Closed);
+    batch.total_usdc = 10_000_000;
+    batch.parked_usdc = 4_000_000;
+
+    assert_eq!(batch.escrow_shortfall(6_000_000).unwrap(), None);
+    assert_eq!(batch.escrow_shortfall(5_999_999).unwrap(), Some(6_000_000));
+    // One unit sent to the escrow ATA by anyone
+    assert_eq!(batch.escrow_shortfall(6_000_001).unwrap(), None);
+
+    batch.transition(Executed).unwrap();
+    batch.filled_usdc = 7_000_000;
+    assert_eq!(batch.escrow_shortfall(3_000_000).unwrap(), None);
+    assert_eq!(batch.escrow_shortfall(2_999_999).unwrap(), Some(3_000_000));
+
+    assert!(batch_in(Open).escrow_shortfall(0).is_err());
+    assert!(batch_in(Distributing).escrow_shortfall(0).is_err());
+}
+
+#[test]
+fn expired_orders_left_in_escrow_do_not_freeze_the_batch() {
+    // close_batch sets total_usdc to the revealed total, which leaves out
+    // a 2 USDC order that expired; its USDC stays until refunded
+    let mut batch = batch_in(Closed);
+    batch.total_usdc = 8_000_000;
+
+    assert_eq!(batch.escrow_shortfall(10_000_000).unwrap(), None);
+
+    batch.transition(Executed).unwrap();
+    batch.filled_usdc = 8_000_000;
+    assert_eq!(batch.escrow_shortfall(2_000_000).unwrap(), None);
+}
+
+#[test]
+fn lane_zero_keeps_the_original_batch_address() {
+    let authority = Pubkey::new_unique();
+    let seeds = |lane: &u8| -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"batch", authority.as_ref(), b"BTC-100K", lane_seed(lane)],
+            &obsidian_mpc::ID,
+        )
+        .0
+    };
+    let original = Pubkey::find_program_address(
+        &[b"batch", authority.as_ref(), b"BTC-100K"],
+        &obsidian_mpc::ID,
+    )
+    .0;
+
+    assert_eq!(seeds(&0), original);
+    assert_ne!(seeds(&1), original);
+    assert_ne!(seeds(&1), seeds(&2));
+}
+
+#[test]
+fn batch_sequence_numbers_start_at_one_and_never_repeat() {
+    let mut sequence = BatchSequence { last: 0 };
+    assert_eq!(sequence.claim(), 1);
+    assert_eq!(sequence.claim(), 2);
+    assert_eq!(sequence.last, 2);
+}
+
+#[test]
+fn claims_pay_out_the_outcome_mint_of_the_batch_side() {
+    let mints = MarketOutcomeMints {
+        market_id: "BTC-100K".into(),
+        yes_mint: Pubkey::new_unique(),
+        no_mint: Pubkey::new_unique(),
+        updated_at: 0,
+    };
+    assert_eq!(mints.outcome_mint(1), mints.yes_mint);
+    assert_eq!(mints.outcome_mint(0), mints.no_mint);
+}
+
+/// An executed batch of 10 USDC, 7 filled for 700 shares, staged with the
+/// single receipt returned for order 1 paying `wallet`
+fn staged_batch(wallet: &Pubkey, unlock_at: i64) -> (Batch, ShareReceipt) {
+    let receipt = ShareReceipt {
+        shares: 700,
+        usdc_refund: 3_000_000,
+        unlock_at,
+        proof: [[5; 32]; DISTRIBUTION_TREE_DEPTH],
+    };
+    let leaf = distribution_leaf(1, 700, 3_000_000, unlock_at, wallet);
+    let mut batch = batch_in(Executed);
+    batch.order_count = 2;
+    batch.total_usdc = 10_000_000;
+    batch.filled_usdc = 7_000_000;
+    batch.distribution_root = Some(distribution_proof_root(leaf, 1, &receipt.proof));
+    batch.staged_distributions = 2;
+    batch.staged_shares = 700;
+    (batch, receipt)
+}
+
+#[test]
+fn claims_must_prove_their_leaf() {
+    let wallet = Pubkey::new_unique();
+    let (batch, receipt) = staged_batch(&wallet, 0);
+    assert_eq!(
+        batch.check_claim(1, &wallet, &receipt, 0).unwrap(),
+        (3_000_000, 700)
+    );
+
+    let err = |order_index, claimant: &Pubkey, receipt: &ShareReceipt| {
+        batch
+            .check_claim(order_index, claimant, receipt, 0)
+            .unwrap_err()
+    };
+    let invalid = Error::from(ErrorCode::InvalidDistributionProof);
+    assert_eq!(err(1, &Pubkey::new_unique(), &receipt), invalid);
+    assert_eq!(err(0, &wallet, &receipt), invalid);
+    let inflated = ShareReceipt {
+        shares: 701,
+        ..receipt.clone()
+    };
+    assert_eq!(err(1, &wallet, &inflated), invalid);
+    let mut proof = receipt.proof;
+    proof[3][0] ^= 1;
+    assert_eq!(err(1, &wallet, &ShareReceipt { proof, ..receipt }), invalid);
+}
+
+#[test]
+fn locked_distributions_cannot_be_claimed_early() {
+    let wallet = Pubkey::new_unique();
+    let (batch, receipt) = staged_batch(&wallet, 1_000);
+    assert_eq!(
+        batch.check_claim(1, &wallet, &receipt, 999).unwrap_err(),
+        Error::from(ErrorCode::DistributionLocked)
+    );
+    assert!(batch.check_claim(1, &wallet, &receipt, 1_000).is_ok());
+
+    // Claiming it as unlocked misses the leaf
+    let unlocked = ShareReceipt {
+        unlock_at: 0,
+        ..receipt
+    };
+    assert_eq!(
+        batch.check_claim(1, &wallet, &unlocked, 999).unwrap_err(),
+        Error::from(ErrorCode::InvalidDistributionProof)
+    );
+}
+
+#[test]
+fn claim_refunds_stay_within_the_unfilled_usdc() {
+    let wallet = Pubkey::new_unique();
+    let (mut batch, receipt) = staged_batch(&wallet, 0);
+    // Another order already took 1 of the 3 unfilled USDC
+    batch.recorded_refunds = 1_000_000;
+    assert_eq!(
+        batch.check_claim(1, &wallet, &receipt, 0).unwrap_err(),
+        Error::from(ErrorCode::RefundExceedsUnfilled)
+    );
+
+    batch.recorded_refunds = u64::MAX;
+    assert_eq!(
+        batch.check_claim(1, &wallet, &receipt, 0).unwrap_err(),
+        Error::from(ErrorCode::Overflow)
+    );
+}
+
+#[test]
+fn claim_shares_stay_within_the_staged_total() {
+    let wallet = Pubkey::new_unique();
+    let (mut batch, receipt) = staged_batch(&wallet, 0);
+    batch.recorded_shares = 1;
+    assert_eq!(
+        batch.check_claim(1, &wallet, &receipt, 0).unwrap_err(),
+        Error::from(ErrorCode::SharesExceedStaged)
+    );
+}
+
+#[test]
+fn orders_are_paid_once_pushed_or_pulled() {
+    let wallet = Pubkey::new_unique();
+    let (batch, receipt) = staged_batch(&wallet, 0);
+    let mut slot = OrderSlot {
+        batch: Pubkey::new_unique(),
+        order_index: 1,
+        distributed: false,
+    };
+
+    // record_distribution pushes it ...
+    slot.distribute().unwrap();
+    // ... so the wallet's valid claim of the same order is refused
+    assert!(batch.check_claim(1, &wallet, &receipt, 0).is_ok());
+    assert_eq!(
+        slot.distribute().unwrap_err(),
+        Error::from(ErrorCode::OrderAlreadyDistributed)
+    );
+
+    // Every staged distribution recorded: nothing is left to claim
+    let mut batch = batch;
+    batch.recorded_distributions = 2;
+    assert_eq!(
+        batch.check_claim(1, &wallet, &receipt, 0).unwrap_err(),
+        Error::from(ErrorCode::DistributionsNotStaged)
+    );
+}
+
+#[test]
+fn express_batches_sit_beside_every_size_lane() {
+    assert_eq!(BatchLane::Standard.seed(), lane_seed(&0));
+    assert_eq!(
+        BatchLane::Express.seed(),
+        lane_seed(&BatchLane::Express.index())
+    );
+    assert!((BatchLane::Express.index() as usize) >= obsidian_mpc::MAX_MARKET_LANES);
+
+    let mut batch = Batch::default();
+    batch.lane = BatchLane::Express.index();
+    assert!(batch.is_express());
+    assert_eq!(batch.lane_seed(), BatchLane::Express.seed());
+    assert!(!Batch::default().is_express());
+}
+
+#[test]
+fn cpi_guard_admits_top_level_and_allowed_callers() {
+    let allowed = Pubkey::new_unique();
+    let mut cpi_callers = [Pubkey::default(); MAX_CPI_CALLERS];
+    cpi_callers[0] = allowed;
+    let mut config = ProtocolConfig {
+        cpi_guard_enabled: true,
+        cpi_caller_count: 1,
+        cpi_callers,
+        ..Default::default()
+    };
+
+    assert!(config.allows_caller(&obsidian_mpc::ID, 1));
+    assert!(config.allows_caller(&allowed, 2));
+    assert!(!config.allows_caller(&allowed, 3));
+    assert!(!config.allows_caller(&Pubkey::new_unique(), 2));
+    assert!(!config.allows_caller(&Pubkey::default(), 2));
+
+    config.cpi_guard_enabled = false;
+    assert!(config.allows_caller(&Pubkey::new_unique(), 3));
+}
+
+#[test]
+fn subscriptions_enroll_once_per_batch_at_their_cadence() {
+    let mut subscription = Subscription {
+        cadence: 3_600,
+        next_enroll_at: 1_000,
+        remaining: 2,
+        ..Default::default()
+    };
+    let (first, second, third) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+
+    assert!(!subscription.is_due(&first, 999));
+    assert!(subscription.is_due(&first, 1_000));
+    subscription.enroll(first, 1_000);
+    assert_eq!(subscription.next_enroll_at, 4_600);
+
+    // Not again in the same batch, nor in the next one before the cadence
+    assert!(!subscription.is_due(&first, 10_000));
+    assert!(!subscription.is_due(&second, 4_599));
+    assert!(subscription.is_due(&second, 4_600));
+    subscription.enroll(second, 5_000);
+
+    // Exhausted
+    assert!(!subscription.is_due(&third, 100_000));
+    assert_eq!((subscription.remaining, subscription.enrolled), (0, 2));
+}
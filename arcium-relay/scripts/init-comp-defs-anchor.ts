@@ -17,12 +17,34 @@ const PROGRAM_ID = new PublicKey('8postM9mUCTKTu6a1vkrhfg8erso2g8eHo8bmc9JZjZc')
 const ARCIUM_PROGRAM_ID = new PublicKey('F3G6Q9tRicyznCqcZLydJ6RxkwDSBeHWM458J7V6aeyk');
 const RPC_URL = 'https://api.devnet.solana.com';
 
+// Circuit version registered for these comp defs (see set_circuit_version).
+// Versions above 1 are deployed under a `_v<version>` name suffix.
+const CIRCUIT_VERSION = parseInt(process.env.CIRCUIT_VERSION || '1');
+
+function deployedName(circuitName: string): string {
+  return CIRCUIT_VERSION > 1 ? `${circuitName}_v${CIRCUIT_VERSION}` : circuitName;
+}
+
 // Circuits to initialize
 const CIRCUITS = [
   'init_batch',
   'add_to_batch',
+  'add_to_batch_normalized',
   'reveal_batch_total',
   'compute_distribution',
+  'reveal_bucketed_total',
+  'compute_distribution_from_stats',
+  'compute_encrypted_distribution',
+  'rekey_batch_stats',
+  'import_batch_stats',
+  'init_portfolio',
+  'accumulate_position',
+  'reveal_my_position',
+  'route_order',
+  'peek_order_count',
+  'init_dca_schedule',
+  'add_dca_to_batch',
+  'sum_my_notes',
 ];
 
 // Compute comp_def_offset from circuit name (sha256 first 4 bytes as little-endian u32)
@@ -98,44 +120,75 @@ async function main() {
 
   const program = new anchor.Program(idl, provider);
 
+  // Registry of initialized comp defs, checked by verify_comp_defs
+  const [registryPda] = PublicKey.findProgramAddressSync([Buffer.from('mpc_registry')], PROGRAM_ID);
+  if (!(await connection.getAccountInfo(registryPda))) {
+    console.log(`Creating MPC registry: ${registryPda.toBase58()}`);
+    await (program.methods as any).initializeMpcRegistry()
+      .accounts({ registry: registryPda, authority: keypair.publicKey })
+      .rpc();
+  }
+
   // Initialize each computation definition
   const initMethods = [
     { name: 'init_batch', method: 'initInitBatchCompDef' },
     { name: 'add_to_batch', method: 'initAddToBatchCompDef' },
+    { name: 'add_to_batch_normalized', method: 'initAddToBatchNormalizedCompDef' },
     { name: 'reveal_batch_total', method: 'initRevealBatchTotalCompDef' },
     { name: 'compute_distribution', method: 'initComputeDistributionCompDef' },
+    { name: 'reveal_bucketed_total', method: 'initRevealBucketedTotalCompDef' },
+    { name: 'compute_distribution_from_stats', method: 'initComputeDistributionFromStatsCompDef' },
+    { name: 'compute_encrypted_distribution', method: 'initComputeEncryptedDistributionCompDef' },
+    { name: 'rekey_batch_stats', method: 'initRekeyBatchStatsCompDef' },
+    { name: 'import_batch_stats', method: 'initImportBatchStatsCompDef' },
+    { name: 'init_portfolio', method: 'initInitPortfolioCompDef' },
+    { name: 'accumulate_position', method: 'initAccumulatePositionCompDef' },
+    { name: 'reveal_my_position', method: 'initRevealMyPositionCompDef' },
+    { name: 'route_order', method: 'initRouteOrderCompDef' },
+    { name: 'peek_order_count', method: 'initPeekOrderCountCompDef' },
+    { name: 'init_dca_schedule', method: 'initInitDcaScheduleCompDef' },
+    { name: 'add_dca_to_batch', method: 'initAddDcaToBatchCompDef' },
+    { name: 'sum_my_notes', method: 'initSumMyNotesCompDef' },
   ];
 
   for (const { name, method } of initMethods) {
     console.log(`\n=== Initializing: ${name} ===`);
 
-    const offset = computeCompDefOffset(name);
+    const offset = computeCompDefOffset(deployedName(name));
     const compDefPda = getCompDefPda(offset);
 
     console.log(`  Offset: ${offset}`);
     console.log(`  Comp Def PDA: ${compDefPda.toBase58()}`);
 
-    // Check if already initialized
-    const existingAccount = await connection.getAccountInfo(compDefPda);
-    if (existingAccount) {
-      console.log(`  Already initialized, skipping.`);
-      continue;
-    }
-
-    console.log(`  Calling: program.methods.${method}()`);
-
     try {
-      const tx = await (program.methods as any)[method]()
+      // Check if already initialized
+      const existingAccount = await connection.getAccountInfo(compDefPda);
+      if (existingAccount) {
+        console.log(`  Already initialized, skipping.`);
+      } else {
+        console.log(`  Calling: program.methods.${method}()`);
+
+        const tx = await (program.methods as any)[method]()
+          .accounts({
+            payer: keypair.publicKey,
+            mxeAccount: mxeAddress,
+            compDefAccount: compDefPda,
+            arciumProgram: ARCIUM_PROGRAM_ID,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .rpc();
+
+        console.log(`  Success: ${tx}`);
+      }
+
+      const registerTx = await (program.methods as any).registerCompDef(deployedName(name), CIRCUIT_VERSION)
         .accounts({
-          payer: keypair.publicKey,
-          mxeAccount: mxeAddress,
+          registry: registryPda,
           compDefAccount: compDefPda,
-          arciumProgram: ARCIUM_PROGRAM_ID,
-          systemProgram: anchor.web3.SystemProgram.programId,
+          authority: keypair.publicKey,
         })
         .rpc();
-
-      console.log(`  Success: ${tx}`);
+      console.log(`  Registered: ${registerTx}`);
     } catch (error: any) {
       console.error(`  Error: ${error.message || error}`);
       if (error.logs) {
@@ -144,6 +197,13 @@ async function main() {
     }
   }
 
+  // Fail fast if anything above didn't land
+  console.log('\n=== Verifying registry ===');
+  await (program.methods as any).verifyCompDefs(initMethods.map(({ name }) => deployedName(name)))
+    .accounts({ registry: registryPda })
+    .simulate();
+  console.log('  All computation definitions registered.');
+
   console.log('\n=== Done ===');
   console.log('\nNote: Full initialization requires calling the program instructions.');
   console.log('Use Anchor client or web3.js to invoke init_*_comp_def instructions.');
@@ -25,8 +25,22 @@ const RPC_URL = 'https://api.devnet.solana.com';
 const CIRCUITS = [
   'init_batch',
   'add_to_batch',
+  'add_to_batch_normalized',
   'reveal_batch_total',
   'compute_distribution',
+  'reveal_bucketed_total',
+  'compute_distribution_from_stats',
+  'compute_encrypted_distribution',
+  'rekey_batch_stats',
+  'import_batch_stats',
+  'init_portfolio',
+  'accumulate_position',
+  'reveal_my_position',
+  'route_order',
+  'peek_order_count',
+  'init_dca_schedule',
+  'add_dca_to_batch',
+  'sum_my_notes',
 ];
 
 async function loadKeypair(): Promise<Keypair> {
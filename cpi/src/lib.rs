@@ -0,0 +1,51 @@
+//! Obsidian CPI interface
+//!
+//! Instruction builders, PDAs and event types for calling the privacy pool
+//! and blind batch programs from other on-chain programs. Depends on
+//! `anchor-lang` only - not on the program crates, Arcium or SPL - so it
+//! builds for SBF next to any Anchor program.
+//!
+//! Build an [`Instruction`] here and pass it to `invoke` or `invoke_signed`
+//! together with the `AccountInfo`s in the same order as its metas.
+//!
+//! [`Instruction`]: anchor_lang::solana_program::instruction::Instruction
+
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::Discriminator;
+
+pub mod mpc;
+pub mod pool;
+
+/// Decode an event emitted with `emit_cpi!` from the data of the program's
+/// self-invocation, as found in a transaction's inner instructions.
+pub fn decode_event<E: AnchorDeserialize + Discriminator>(data: &[u8]) -> Option<E> {
+    let mut body = data
+        .strip_prefix(EVENT_IX_TAG_LE)?
+        .strip_prefix(E::DISCRIMINATOR)?;
+    E::deserialize(&mut body).ok()
+}
+
+/// Anchor instruction: discriminator, Borsh args, and for `#[event_cpi]`
+/// instructions the program's event authority and itself appended.
+fn instruction(
+    program_id: Pubkey,
+    discriminator: [u8; 8],
+    args: impl AnchorSerialize,
+    mut accounts: Vec<AccountMeta>,
+    event_cpi: bool,
+) -> Instruction {
+    let mut data = discriminator.to_vec();
+    args.serialize(&mut data).expect("in-memory serialization");
+    if event_cpi {
+        let event_authority = Pubkey::find_program_address(&[b"__event_authority"], &program_id).0;
+        accounts.push(AccountMeta::new_readonly(event_authority, false));
+        accounts.push(AccountMeta::new_readonly(program_id, false));
+    }
+    Instruction {
+        program_id,
+        accounts,
+        data,
+    }
+}
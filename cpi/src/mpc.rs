@@ -0,0 +1,143 @@
+//! Blind batch program (`obsidian_mpc`): batch creation and results.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::get_return_data;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::Discriminator;
+
+pub const ID: Pubkey = pubkey!("8postM9mUCTKTu6a1vkrhfg8erso2g8eHo8bmc9JZjZc");
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BatchStatus {
+    Open,
+    Closed,
+    Executed,
+    Distributing,
+    Completed,
+    Failed,
+    Frozen,
+}
+
+/// Priority lane `create_batch` opens a batch in.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BatchLane {
+    Standard,
+    /// Shorter window, higher fee
+    Express,
+}
+
+/// Return data of `close_batch` and `record_execution`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BatchResult {
+    pub batch: Pubkey,
+    pub status: BatchStatus,
+    pub order_count: u8,
+    pub total_usdc: u64,
+    pub total_shares: u64,
+    pub filled_usdc: u64,
+}
+
+impl BatchResult {
+    /// Read the result of a `close_batch` or `record_execution` CPI made
+    /// just before; `None` if the last return data is not from this program.
+    pub fn from_cpi() -> Option<Self> {
+        let (program_id, data) = get_return_data()?;
+        if program_id != ID {
+            return None;
+        }
+        Self::try_from_slice(&data).ok()
+    }
+}
+
+// ============================================================================
+// PDAs
+// ============================================================================
+
+pub fn config_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &ID).0
+}
+
+pub fn batch_address(authority: &Pubkey, market_id: &str) -> Pubkey {
+    Pubkey::find_program_address(&[b"batch", authority.as_ref(), market_id.as_bytes()], &ID).0
+}
+
+/// Counter `create_batch` takes each batch's sequence number from
+pub fn batch_sequence_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"batch_sequence"], &ID).0
+}
+
+/// Batch `create_batch` opens for `market_id` in `lane`
+pub fn priority_batch_address(authority: &Pubkey, market_id: &str, lane: BatchLane) -> Pubkey {
+    match lane {
+        BatchLane::Standard => batch_address(authority, market_id),
+        BatchLane::Express => {
+            Pubkey::find_program_address(
+                &[
+                    b"batch",
+                    authority.as_ref(),
+                    market_id.as_bytes(),
+                    &[u8::MAX],
+                ],
+                &ID,
+            )
+            .0
+        }
+    }
+}
+
+// ============================================================================
+// Instructions
+// ============================================================================
+
+/// Open a batch for `market_id` in `lane`. `authority` must be the
+/// config's operator and pays for the batch account.
+pub fn create_batch(
+    authority: &Pubkey,
+    market_id: &str,
+    side: u8,
+    lane: BatchLane,
+    label: Option<&str>,
+    intent_hash: Option<[u8; 32]>,
+) -> Instruction {
+    crate::instruction(
+        ID,
+        [159, 198, 248, 43, 248, 31, 235, 86],
+        (
+            market_id.to_string(),
+            side,
+            lane,
+            label.map(str::to_string),
+            intent_hash,
+        ),
+        vec![
+            AccountMeta::new(priority_batch_address(authority, market_id, lane), false),
+            AccountMeta::new(batch_sequence_address(), false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        true,
+    )
+}
+
+// ============================================================================
+// Events
+// ============================================================================
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BatchCreated {
+    pub batch: Pubkey,
+    pub market_id: String,
+    pub side: u8,
+    pub cluster_offset: u32,
+    pub circuit_version: u16,
+    pub label: String,
+    pub intent_hash: Option<[u8; 32]>,
+    /// Creation order across all batches
+    pub sequence: u64,
+}
+
+impl Discriminator for BatchCreated {
+    const DISCRIMINATOR: &'static [u8] = &[231, 92, 210, 203, 2, 59, 109, 234];
+}
@@ -0,0 +1,120 @@
+//! Privacy pool: deposits.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::sysvar::instructions as instructions_sysvar;
+use anchor_lang::Discriminator;
+
+pub const ID: Pubkey = pubkey!("AfTSjfnT7M88XipRjPGLgDCcqcVfnrePrtuvNBF74hhP");
+
+/// USDC mint accepted by `deposit` (Circle devnet USDC)
+#[cfg(not(feature = "mainnet"))]
+pub const USDC_MINT: Pubkey = pubkey!("4zMMC9srt5Ri5X14GAgXhaHii3GnPAEERYPJgZJDncDU");
+
+/// USDC mint accepted by `deposit`
+#[cfg(feature = "mainnet")]
+pub const USDC_MINT: Pubkey = pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+
+/// Commitment scheme `deposit` accepts: hash(1, secret, amount, asset),
+/// asset being the mint with its top byte zeroed
+pub const COMMITMENT_VERSION: u8 = 1;
+
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+// ============================================================================
+// PDAs
+// ============================================================================
+
+pub fn pool_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"privacy_pool"], &ID).0
+}
+
+pub fn limits_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"limits", pool_address().as_ref()], &ID).0
+}
+
+pub fn leaf_times_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"leaf_times", pool_address().as_ref()], &ID).0
+}
+
+pub fn stats_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"stats", pool_address().as_ref()], &ID).0
+}
+
+/// Deposits fail once this exists (the pool is moving to a v2 tree)
+pub fn migration_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"migration", pool_address().as_ref()], &ID).0
+}
+
+/// Programs allowed to call deposit; the pool must list the calling
+/// program once its guard is enabled (see `set_cpi_guard`)
+pub fn cpi_guard_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"cpi_guard", pool_address().as_ref()], &ID).0
+}
+
+/// SPL Token associated account of `owner` for `mint`
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), TOKEN_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+// ============================================================================
+// Instructions
+// ============================================================================
+
+/// Deposit `amount` USDC from `user`'s associated token account as a note
+/// with `commitment`. `user` signs; from a program, pass a PDA and
+/// `invoke_signed`. No deposit receipt is minted. If the pool screens
+/// deposits, append the screening program and its accounts to the
+/// instruction's accounts.
+pub fn deposit(user: &Pubkey, commitment: [u8; 32], amount: u64) -> Instruction {
+    let pool = pool_address();
+    crate::instruction(
+        ID,
+        [242, 35, 198, 137, 82, 225, 242, 182],
+        (commitment, amount, COMMITMENT_VERSION),
+        vec![
+            AccountMeta::new(pool, false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new(associated_token_address(user, &USDC_MINT), false),
+            AccountMeta::new(associated_token_address(&pool, &USDC_MINT), false),
+            AccountMeta::new_readonly(USDC_MINT, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            AccountMeta::new_readonly(limits_address(), false),
+            AccountMeta::new(leaf_times_address(), false),
+            AccountMeta::new(stats_address(), false),
+            AccountMeta::new_readonly(migration_address(), false),
+            AccountMeta::new_readonly(cpi_guard_address(), false),
+            AccountMeta::new_readonly(instructions_sysvar::ID, false),
+            // Receipt accounts left out: Anchor reads the program ID as None
+            AccountMeta::new_readonly(ID, false),
+            AccountMeta::new_readonly(ID, false),
+            AccountMeta::new_readonly(ID, false),
+            AccountMeta::new_readonly(ID, false),
+            AccountMeta::new_readonly(ID, false),
+        ],
+        true,
+    )
+}
+
+// ============================================================================
+// Events
+// ============================================================================
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DepositEvent {
+    pub leaf_index: u32,
+    pub commitment: [u8; 32],
+    pub timestamp: i64,
+    pub version: u8,
+}
+
+impl Discriminator for DepositEvent {
+    const DISCRIMINATOR: &'static [u8] = &[120, 248, 61, 83, 31, 142, 107, 144];
+}
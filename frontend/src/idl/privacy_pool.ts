@@ -0,0 +1,1148 @@
+// Generated by `cargo xtask codegen` from idls/privacy_pool.json. Do not edit.
+
+/** Privacy Pool - ZK Balance Proof Verification for Obsidian Relay */
+export const PROGRAM_ID = 'AfTSjfnT7M88XipRjPGLgDCcqcVfnrePrtuvNBF74hhP';
+
+export const INSTRUCTIONS = {
+  /** Complete a relay rotation started by set_relay; signed by the new relay */
+  acceptRelay: [89, 118, 179, 188, 234, 227, 217, 205],
+  /**
+   * Add a new commitment (for change notes after partial spend)
+   *
+   * relay_nonce: must exceed the relay's last used nonce (see RelayNonce).
+   * version: commitment scheme, must be COMMITMENT_VERSION.
+   */
+  addCommitment: [90, 28, 2, 181, 128, 126, 28, 25],
+  /**
+   * Publish proof-of-reserve evidence
+   *
+   * The indexer builds a Merkle tree over the unspent notes it sees
+   * (spent notes are only known off-chain, by their nullifiers) and the
+   * authority posts its root and total. The vault balance, tree state and
+   * recorded liabilities are read on-chain, so auditors can check the
+   * total against the vault and users can ask the indexer for an
+   * inclusion proof of their note against the root.
+   */
+  attestReserves: [68, 20, 40, 240, 165, 2, 146, 10],
+  /**
+   * Abandon a migration, unfreezing the tree
+   *
+   * Deposits and add_commitment work again. Notes already moved stay in
+   * the v2 pool; their nullifiers stay spent here.
+   */
+  cancelMigration: [88, 255, 119, 245, 65, 15, 188, 223],
+  /**
+   * Stop accepting notes from the source pool
+   *
+   * Finishes an import once the source pool has moved everything (or
+   * abandons it); migrate_notes fails from then on.
+   */
+  closeImport: [35, 13, 66, 202, 209, 182, 165, 45],
+  /**
+   * Deposit USDC and add commitment to Merkle tree
+   *
+   * User provides:
+   * - commitment: hash(secret, amount) - computed client-side
+   * - amount: USDC to deposit (this IS visible on-chain)
+   *
+   * The commitment hides the link between deposit and future spends
+   *
+   * Optionally pass the receipt accounts to also mint a non-transferable
+   * Token-2022 receipt. Its mint address encodes only the leaf index, so
+   * holders can prove participation without revealing the note.
+   *
+   * For Token-2022 mints with a transfer hook, pass the hook program, its
+   * validation account and extra accounts as remaining_accounts. When a
+   * screening program is set, it and its accounts come first.
+   *
+   * The vault must grow by exactly `amount`: a mint that takes a transfer
+   * fee would leave the note worth more than its backing, so the deposit
+   * fails with DepositShortfall instead.
+   */
+  deposit: [242, 35, 198, 137, 82, 225, 242, 182],
+  /**
+   * Return a snapshot of the pool state (view function)
+   *
+   * Clients call this through simulateTransaction and decode the
+   * return data, so no account scanning is required.
+   */
+  getPoolState: [101, 112, 27, 99, 82, 23, 72, 17],
+  /**
+   * Make room for `additional` more nullifiers in a set
+   *
+   * A set starts with room for MAX_LEAVES; inserts fail with
+   * NullifierStorageFull once it is full. The realloc constraint on
+   * GrowNullifierSet does the growth, paid by whoever signs as payer;
+   * `additional` must be 1 to MAX_NULLIFIER_GROWTH. The handler only
+   * sorts sets recorded before nullifiers were kept sorted, so grow each
+   * existing set once after upgrading.
+   */
+  growNullifierSet: [77, 251, 21, 193, 110, 83, 100, 135],
+  /**
+   * Insert notes moved by the source pool's migrate_notes
+   *
+   * Only callable through that CPI, signed by the source PoolMigration,
+   * which verified each note's proof and has already paid `amount` into
+   * this pool's vault.
+   */
+  importNotes: [194, 75, 126, 96, 121, 109, 145, 138],
+  /**
+   * Initialize the privacy pool
+   *
+   * relay: the only signer accepted on record_nullifier,
+   * add_commitment and migrate_notes, stored in the pool's PoolRelay and
+   * rotated with set_relay / accept_relay.
+   */
+  initialize: [175, 175, 109, 31, 13, 152, 155, 237],
+  /**
+   * Create the nullifier set for one purpose (spend or batch)
+   *
+   * Each purpose gets its own set so a nullifier recorded by the batch
+   * system can never collide with, or be replayed as, a pool spend.
+   */
+  initializeNullifierSet: [4, 69, 182, 124, 72, 234, 7, 140],
+  /**
+   * Create the PoolStats account that deposit and withdrawals update
+   *
+   * Totals count from here on, so the vault of an existing pool already
+   * holds more than the recorded liabilities.
+   */
+  initializePoolStats: [56, 225, 69, 186, 188, 223, 34, 193],
+  /** Check if a nullifier has been used (view function) */
+  isNullifierUsed: [121, 52, 201, 172, 230, 35, 253, 72],
+  /**
+   * Move notes of a migrating pool into the v2 tree
+   *
+   * Each move is a withdrawal paid to the new pool's vault instead of a
+   * recipient and without fees. proofs[i] is a WITHDRAWAL_CIRCUIT_ID
+   * proof of moves[i] against the frozen root (see
+   * migration_public_inputs), verified on-chain; it binds the
+   * new_commitment, which the v2 pool's import_notes then inserts in
+   * the same call.
+   *
+   * relay_nonce: must exceed the relay's last used nonce (see RelayNonce).
+   */
+  migrateNotes: [13, 115, 223, 45, 59, 140, 133, 165],
+  /**
+   * Start moving the pool to the v2 tree of the deployment at `new_program`
+   *
+   * Freezes the tree: deposit and add_commitment fail from now on, so
+   * every note is proven against the root recorded here. Notes then move
+   * with migrate_notes while withdrawals keep working; the migration is
+   * done once PoolStats::liabilities reaches 0.
+   *
+   * The v2 pool must be initialized and have opened an import of this
+   * migration (see open_import), so moved notes can only land there.
+   */
+  migratePool: [55, 170, 171, 123, 210, 69, 39, 172],
+  /**
+   * Accept notes migrating from the pool of the deployment at
+   * `source_program`
+   *
+   * Run on the v2 pool before the old one calls migrate_pool. Only that
+   * pool's PoolMigration may then sign import_notes.
+   */
+  openImport: [21, 39, 46, 42, 24, 78, 247, 134],
+  /**
+   * Record a nullifier as spent
+   * Called by the relay after verifying a ZK proof
+   *
+   * The proof binds the nullifier to (pool_id, purpose); the set's PDA
+   * seeds bind it to the same pool and purpose on-chain.
+   *
+   * legacy_nullifier: hash(secret, leaf_index, 1) from the proof's public
+   * inputs. Recording it too means a note already spent under the legacy
+   * scheme cannot be spent again under the domain-separated one.
+   *
+   * relay_nonce: must exceed the relay's last used nonce (see RelayNonce).
+   */
+  recordNullifier: [161, 203, 52, 16, 2, 39, 207, 80],
+  /**
+   * Record a nullifier after verifying its balance proof on-chain
+   *
+   * Permissionless counterpart to record_nullifier: the proof is checked
+   * by CPI into the configured verifier, then its public inputs are bound
+   * to this pool's root, id and the nullifier set's purpose.
+   *
+   * The proof's change note (new_commitment) is inserted into the tree in
+   * the same call, so whoever submits the proof cannot burn the note
+   * without returning its change. Fails while the tree is frozen for a
+   * migration.
+   */
+  recordNullifierWithProof: [108, 135, 133, 240, 244, 0, 84, 197],
+  /** Start the timelock for resuming withdrawals after a circuit-breaker trip */
+  requestUnpause: [102, 114, 36, 202, 204, 124, 59, 137],
+  /**
+   * Require deposit, withdraw_many, withdraw_aggregated and
+   * withdraw_split to be top-level instructions, or called directly by
+   * one of `allowed_programs`
+   *
+   * Keeps wrapper programs from bundling pool instructions into
+   * transactions users sign without seeing what else they do. Off until
+   * first set; `enabled = false` turns it off again.
+   */
+  setCpiGuard: [33, 139, 2, 19, 201, 116, 153, 144],
+  /**
+   * Set the withdrawal fee tiers and the token account fees are paid to
+   *
+   * A withdrawal pays the fee_bps of the last tier whose min_age its note
+   * has reached, so tiers must start at age 0, with ages increasing and
+   * fees not increasing. Also creates the LeafTimes account that deposit
+   * and add_commitment require; leaves inserted before it count as aged.
+   */
+  setFeeTiers: [162, 35, 72, 250, 39, 183, 30, 7],
+  /**
+   * Set the pool's exposure limits
+   *
+   * A new pool starts with small caps that governance raises as it gains
+   * confidence; 0 disables a cap. Deposits and withdrawals fail until
+   * limits exist.
+   *
+   * max_hourly_withdrawal_bps: share of TVL that may leave the pool per
+   * WITHDRAWAL_WINDOW_SECS before withdrawals pause automatically.
+   */
+  setLimits: [207, 50, 250, 67, 211, 33, 70, 91],
+  /**
+   * Propose a new relay signer for record_nullifier, add_commitment and
+   * migrate_notes
+   *
+   * Takes effect once the new key signs accept_relay, so a mistyped key
+   * cannot lock the relay out. Until the first rotation the pool
+   * authority is the relay. Proposing again replaces the pending key.
+   */
+  setRelay: [65, 214, 135, 125, 62, 101, 78, 3],
+  /**
+   * Set the program deposits are screened with, or None to stop
+   * screening
+   *
+   * The program receives the depositor and amount (see
+   * SCREEN_DEPOSIT_DISCRIMINATOR) plus `screening_accounts` accounts of
+   * its own, which depositors pass at the front of remaining_accounts
+   * after the program itself. The pool holds no policy of its own.
+   */
+  setScreeningProgram: [38, 152, 255, 33, 236, 80, 71, 181],
+  /**
+   * Point proof verification at a verifier program and circuit
+   *
+   * The pool never parses proofs itself; swapping proving systems is a
+   * new verifier, and key rotation happens in the verifier against the
+   * circuit's active key, so neither needs a pool redeploy.
+   */
+  setVerifier: [186, 247, 191, 131, 148, 158, 213, 63],
+  /** Resume withdrawals once UNPAUSE_TIMELOCK_SECS have passed since request_unpause */
+  unpause: [169, 144, 4, 38, 10, 141, 188, 255],
+  /**
+   * Pay out a batch of withdrawals backed by one aggregated proof
+   *
+   * The relay folds the individual balance proofs into a single proof
+   * off-chain; the pool verifies only that, then walks its public inputs.
+   * One pairing check replaces one per withdrawal, and no trusted relay
+   * signature is needed. Every nullifier is recorded in the spend set
+   * before any transfer, so a duplicate anywhere in the batch fails the
+   * whole call.
+   *
+   * Public inputs: merkle_root, pool_id, then per withdrawal
+   * (nullifier, legacy_nullifier, recipient_field(recipient), amount,
+   * leaf_bound, recipient_field(reference) or zero). The aggregate key's
+   * input count fixes the batch size it accepts.
+   *
+   * If the batch would push the hour's volume past the circuit breaker,
+   * withdrawals are paused instead and nothing is paid or recorded. Each
+   * withdrawal pays the fee tier for its leaf_bound (see FeeSchedule);
+   * recipients get amount minus fee.
+   *
+   * remaining_accounts: each withdrawal's recipient token account, in
+   * order, then the reference of each withdrawal that has one, in order.
+   */
+  withdrawAggregated: [121, 236, 166, 66, 247, 215, 251, 107],
+  /**
+   * Pay out a batch of withdrawals, each backed by its own proof
+   *
+   * proofs[i] is a WITHDRAWAL_CIRCUIT_ID proof for withdrawals[i],
+   * verified on-chain against the circuit's active key before anything
+   * is recorded, so no relay signature is needed. Costs one pairing
+   * check per withdrawal; withdraw_aggregated pays for one.
+   *
+   * Public inputs of each proof: see withdrawal_public_inputs, after
+   * merkle_root and pool_id. Volume, nullifiers, fees and
+   * remaining_accounts are handled as in withdraw_aggregated.
+   */
+  withdrawMany: [75, 157, 179, 58, 143, 102, 113, 57],
+  /**
+   * Pay one note out to up to MAX_SPLIT_OUTPUTS recipients
+   *
+   * The split_withdrawal proof shows the spent note holds exactly the
+   * sum of the outputs, so a payroll or split spends one nullifier in
+   * one transaction instead of several withdrawals that could be linked
+   * to each other. The proof is verified on-chain against the active
+   * key of SPLIT_WITHDRAWAL_CIRCUIT_ID; no relay signature is needed.
+   *
+   * Public inputs: see split_public_inputs. Each output pays the fee
+   * tier for leaf_bound and emits its own WithdrawalEvent; the circuit
+   * breaker counts the outputs together.
+   *
+   * remaining_accounts: each output's recipient token account, in order.
+   */
+  withdrawSplit: [35, 5, 144, 59, 211, 3, 145, 101],
+} as const;
+
+export const ACCOUNTS = {
+  CpiGuard: [201, 154, 53, 39, 35, 138, 219, 95],
+  FeeSchedule: [250, 80, 88, 27, 206, 216, 50, 199],
+  LeafTimes: [52, 213, 107, 132, 11, 169, 74, 91],
+  NullifierSet: [251, 219, 17, 100, 208, 102, 127, 25],
+  PoolImport: [78, 23, 147, 237, 25, 151, 26, 34],
+  PoolLimits: [78, 105, 39, 122, 186, 43, 13, 156],
+  PoolMigration: [144, 142, 18, 96, 179, 193, 27, 10],
+  PoolRelay: [69, 254, 110, 131, 202, 155, 188, 232],
+  PoolStats: [24, 180, 162, 52, 37, 122, 196, 98],
+  PrivacyPool: [133, 184, 191, 79, 252, 142, 190, 150],
+  RelayNonce: [8, 4, 238, 138, 204, 40, 192, 8],
+  ReserveAttestation: [105, 212, 95, 216, 140, 42, 205, 75],
+  VerifierConfig: [176, 103, 248, 36, 138, 167, 176, 220],
+} as const;
+
+export const EVENTS = {
+  CircuitBreakerTripped: [188, 9, 111, 118, 136, 206, 199, 65],
+  CommitmentAddedEvent: [3, 170, 9, 59, 141, 135, 137, 16],
+  CpiGuardUpdatedEvent: [243, 121, 199, 158, 120, 39, 184, 222],
+  DepositEvent: [120, 248, 61, 83, 31, 142, 107, 144],
+  DepositReceiptEvent: [35, 148, 57, 191, 30, 44, 164, 227],
+  FeeTiersUpdatedEvent: [60, 23, 248, 104, 80, 137, 173, 41],
+  LimitsUpdatedEvent: [244, 137, 204, 15, 213, 180, 183, 234],
+  NoteMigratedEvent: [116, 179, 90, 60, 193, 36, 162, 75],
+  PoolMigrationCancelledEvent: [171, 79, 234, 133, 99, 180, 166, 122],
+  PoolMigrationStartedEvent: [219, 165, 55, 193, 251, 217, 16, 166],
+  RelayProposedEvent: [246, 211, 192, 157, 75, 249, 63, 53],
+  RelayRotatedEvent: [51, 245, 117, 242, 104, 71, 108, 155],
+  ReservesAttestedEvent: [209, 245, 191, 68, 49, 162, 89, 245],
+  ScreeningUpdatedEvent: [97, 254, 55, 33, 225, 129, 194, 231],
+  UnpauseRequestedEvent: [180, 118, 64, 138, 32, 232, 90, 51],
+  VerifierUpdatedEvent: [238, 23, 0, 102, 24, 244, 15, 70],
+  WithdrawalEvent: [161, 53, 185, 18, 98, 254, 54, 165],
+  WithdrawalsUnpausedEvent: [34, 39, 129, 174, 152, 225, 24, 42],
+} as const;
+
+export enum ErrorCode {
+  /** Merkle tree is full */
+  TreeFull = 6000,
+  /** Nullifier has already been used */
+  NullifierAlreadyUsed = 6001,
+  /** Nullifier storage is full */
+  NullifierStorageFull = 6002,
+  /** Invalid nullifier purpose */
+  InvalidNullifierPurpose = 6003,
+  /** Deposit receipt needs all receipt accounts */
+  IncompleteReceiptAccounts = 6004,
+  /** Invalid number of withdrawals or recipient accounts */
+  InvalidWithdrawalCount = 6005,
+  /** Recipient account does not match the withdrawal */
+  RecipientMismatch = 6006,
+  /** Proof public inputs do not match this pool */
+  InvalidPublicInputs = 6007,
+  /** Deposit exceeds the per-deposit limit */
+  DepositTooLarge = 6008,
+  /** Deposit would exceed the pool's TVL cap */
+  TvlCapExceeded = 6009,
+  /** Pool vault is not the pool's USDC token account */
+  InvalidPoolVault = 6010,
+  /** Basis points must be at most 10000 */
+  InvalidBps = 6011,
+  /** Withdrawals are paused by the circuit breaker */
+  WithdrawalsPaused = 6012,
+  /** Withdrawals are not paused */
+  WithdrawalsNotPaused = 6013,
+  /** Unpause has not been requested */
+  UnpauseNotRequested = 6014,
+  /** Unpause timelock has not elapsed */
+  UnpauseTimelocked = 6015,
+  /** Arithmetic overflow */
+  Overflow = 6016,
+  /** Relay nonce already used or out of order */
+  StaleRelayNonce = 6017,
+  /** Fee tiers must start at age 0 with rising ages and non-increasing fees */
+  InvalidFeeTiers = 6018,
+  /** Leaf bound must be between 1 and the number of leaves */
+  InvalidLeafBound = 6019,
+  /** Screening program and its accounts must lead remaining_accounts */
+  MissingScreeningAccounts = 6020,
+  /** Screening program does not match the pool's */
+  InvalidScreeningProgram = 6021,
+  /** Deposit rejected by the screening program */
+  DepositScreened = 6022,
+  /** Vault balance is below recorded liabilities */
+  VaultBelowLiabilities = 6023,
+  /** Signer is not the pool's relay authority */
+  Unauthorized = 6024,
+  /** Attestation counts more notes than the tree holds */
+  InvalidAttestation = 6025,
+  /** Pool is migrating; its tree is frozen */
+  PoolMigrating = 6026,
+  /** Migration target must be another deployment's pool importing this one */
+  InvalidMigrationTarget = 6027,
+  /** Commitment scheme version is not supported */
+  UnsupportedCommitmentVersion = 6028,
+  /** Verifying key is not the circuit's finalized active key */
+  InvalidVerifyingKey = 6029,
+  /** Relay must be a new, non-default key */
+  InvalidRelay = 6030,
+  /** Signer is not the pending relay */
+  NotPendingRelay = 6031,
+  /** Instruction was called through a program the CPI guard does not allow */
+  CpiCallerNotAllowed = 6032,
+  /** Too many programs for the CPI guard */
+  TooManyCpiCallers = 6033,
+  /** Reference accounts do not match the withdrawals' references */
+  ReferenceMismatch = 6034,
+  /** Pool vault received less than the deposited amount; fee-bearing mints are not supported */
+  DepositShortfall = 6035,
+  /** A split withdrawal needs 1 to 4 non-zero outputs, each with its recipient account */
+  InvalidSplitOutputs = 6036,
+  /** A nullifier set grows by 1 to MAX_NULLIFIER_GROWTH slots per call */
+  InvalidNullifierGrowth = 6037,
+}
+
+export const ERROR_MESSAGES: Record<ErrorCode, string> = {
+  [ErrorCode.TreeFull]: "Merkle tree is full",
+  [ErrorCode.NullifierAlreadyUsed]: "Nullifier has already been used",
+  [ErrorCode.NullifierStorageFull]: "Nullifier storage is full",
+  [ErrorCode.InvalidNullifierPurpose]: "Invalid nullifier purpose",
+  [ErrorCode.IncompleteReceiptAccounts]: "Deposit receipt needs all receipt accounts",
+  [ErrorCode.InvalidWithdrawalCount]: "Invalid number of withdrawals or recipient accounts",
+  [ErrorCode.RecipientMismatch]: "Recipient account does not match the withdrawal",
+  [ErrorCode.InvalidPublicInputs]: "Proof public inputs do not match this pool",
+  [ErrorCode.DepositTooLarge]: "Deposit exceeds the per-deposit limit",
+  [ErrorCode.TvlCapExceeded]: "Deposit would exceed the pool's TVL cap",
+  [ErrorCode.InvalidPoolVault]: "Pool vault is not the pool's USDC token account",
+  [ErrorCode.InvalidBps]: "Basis points must be at most 10000",
+  [ErrorCode.WithdrawalsPaused]: "Withdrawals are paused by the circuit breaker",
+  [ErrorCode.WithdrawalsNotPaused]: "Withdrawals are not paused",
+  [ErrorCode.UnpauseNotRequested]: "Unpause has not been requested",
+  [ErrorCode.UnpauseTimelocked]: "Unpause timelock has not elapsed",
+  [ErrorCode.Overflow]: "Arithmetic overflow",
+  [ErrorCode.StaleRelayNonce]: "Relay nonce already used or out of order",
+  [ErrorCode.InvalidFeeTiers]: "Fee tiers must start at age 0 with rising ages and non-increasing fees",
+  [ErrorCode.InvalidLeafBound]: "Leaf bound must be between 1 and the number of leaves",
+  [ErrorCode.MissingScreeningAccounts]: "Screening program and its accounts must lead remaining_accounts",
+  [ErrorCode.InvalidScreeningProgram]: "Screening program does not match the pool's",
+  [ErrorCode.DepositScreened]: "Deposit rejected by the screening program",
+  [ErrorCode.VaultBelowLiabilities]: "Vault balance is below recorded liabilities",
+  [ErrorCode.Unauthorized]: "Signer is not the pool's relay authority",
+  [ErrorCode.InvalidAttestation]: "Attestation counts more notes than the tree holds",
+  [ErrorCode.PoolMigrating]: "Pool is migrating; its tree is frozen",
+  [ErrorCode.InvalidMigrationTarget]: "Migration target must be another deployment's pool importing this one",
+  [ErrorCode.UnsupportedCommitmentVersion]: "Commitment scheme version is not supported",
+  [ErrorCode.InvalidVerifyingKey]: "Verifying key is not the circuit's finalized active key",
+  [ErrorCode.InvalidRelay]: "Relay must be a new, non-default key",
+  [ErrorCode.NotPendingRelay]: "Signer is not the pending relay",
+  [ErrorCode.CpiCallerNotAllowed]: "Instruction was called through a program the CPI guard does not allow",
+  [ErrorCode.TooManyCpiCallers]: "Too many programs for the CPI guard",
+  [ErrorCode.ReferenceMismatch]: "Reference accounts do not match the withdrawals' references",
+  [ErrorCode.DepositShortfall]: "Pool vault received less than the deposited amount; fee-bearing mints are not supported",
+  [ErrorCode.InvalidSplitOutputs]: "A split withdrawal needs 1 to 4 non-zero outputs, each with its recipient account",
+  [ErrorCode.InvalidNullifierGrowth]: "A nullifier set grows by 1 to MAX_NULLIFIER_GROWTH slots per call",
+};
+
+/**
+ * Add a new commitment (for change notes after partial spend)
+ *
+ * relay_nonce: must exceed the relay's last used nonce (see RelayNonce).
+ * version: commitment scheme, must be COMMITMENT_VERSION.
+ */
+export interface AddCommitmentArgs {
+  commitment: number[];
+  relayNonce: bigint;
+  version: number;
+}
+
+/**
+ * Publish proof-of-reserve evidence
+ *
+ * The indexer builds a Merkle tree over the unspent notes it sees
+ * (spent notes are only known off-chain, by their nullifiers) and the
+ * authority posts its root and total. The vault balance, tree state and
+ * recorded liabilities are read on-chain, so auditors can check the
+ * total against the vault and users can ask the indexer for an
+ * inclusion proof of their note against the root.
+ */
+export interface AttestReservesArgs {
+  liabilitiesRoot: number[];
+  liabilities: bigint;
+  noteCount: number;
+}
+
+/**
+ * Deposit USDC and add commitment to Merkle tree
+ *
+ * User provides:
+ * - commitment: hash(secret, amount) - computed client-side
+ * - amount: USDC to deposit (this IS visible on-chain)
+ *
+ * The commitment hides the link between deposit and future spends
+ *
+ * Optionally pass the receipt accounts to also mint a non-transferable
+ * Token-2022 receipt. Its mint address encodes only the leaf index, so
+ * holders can prove participation without revealing the note.
+ *
+ * For Token-2022 mints with a transfer hook, pass the hook program, its
+ * validation account and extra accounts as remaining_accounts. When a
+ * screening program is set, it and its accounts come first.
+ *
+ * The vault must grow by exactly `amount`: a mint that takes a transfer
+ * fee would leave the note worth more than its backing, so the deposit
+ * fails with DepositShortfall instead.
+ */
+export interface DepositArgs {
+  commitment: number[];
+  amount: bigint;
+  version: number;
+}
+
+/**
+ * Make room for `additional` more nullifiers in a set
+ *
+ * A set starts with room for MAX_LEAVES; inserts fail with
+ * NullifierStorageFull once it is full. The realloc constraint on
+ * GrowNullifierSet does the growth, paid by whoever signs as payer;
+ * `additional` must be 1 to MAX_NULLIFIER_GROWTH. The handler only
+ * sorts sets recorded before nullifiers were kept sorted, so grow each
+ * existing set once after upgrading.
+ */
+export interface GrowNullifierSetArgs {
+  purpose: number;
+  additional: number;
+}
+
+/**
+ * Insert notes moved by the source pool's migrate_notes
+ *
+ * Only callable through that CPI, signed by the source PoolMigration,
+ * which verified each note's proof and has already paid `amount` into
+ * this pool's vault.
+ */
+export interface ImportNotesArgs {
+  commitments: number[][];
+  amount: bigint;
+}
+
+/**
+ * Initialize the privacy pool
+ *
+ * relay: the only signer accepted on record_nullifier,
+ * add_commitment and migrate_notes, stored in the pool's PoolRelay and
+ * rotated with set_relay / accept_relay.
+ */
+export interface InitializeArgs {
+  relay: string;
+}
+
+/**
+ * Create the nullifier set for one purpose (spend or batch)
+ *
+ * Each purpose gets its own set so a nullifier recorded by the batch
+ * system can never collide with, or be replayed as, a pool spend.
+ */
+export interface InitializeNullifierSetArgs {
+  purpose: number;
+}
+
+/** Check if a nullifier has been used (view function) */
+export interface IsNullifierUsedArgs {
+  nullifier: number[];
+}
+
+/**
+ * Move notes of a migrating pool into the v2 tree
+ *
+ * Each move is a withdrawal paid to the new pool's vault instead of a
+ * recipient and without fees. proofs[i] is a WITHDRAWAL_CIRCUIT_ID
+ * proof of moves[i] against the frozen root (see
+ * migration_public_inputs), verified on-chain; it binds the
+ * new_commitment, which the v2 pool's import_notes then inserts in
+ * the same call.
+ *
+ * relay_nonce: must exceed the relay's last used nonce (see RelayNonce).
+ */
+export interface MigrateNotesArgs {
+  proofs: Uint8Array[];
+  moves: NoteMigration[];
+  relayNonce: bigint;
+}
+
+/**
+ * Accept notes migrating from the pool of the deployment at
+ * `source_program`
+ *
+ * Run on the v2 pool before the old one calls migrate_pool. Only that
+ * pool's PoolMigration may then sign import_notes.
+ */
+export interface OpenImportArgs {
+  sourceProgram: string;
+}
+
+/**
+ * Record a nullifier as spent
+ * Called by the relay after verifying a ZK proof
+ *
+ * The proof binds the nullifier to (pool_id, purpose); the set's PDA
+ * seeds bind it to the same pool and purpose on-chain.
+ *
+ * legacy_nullifier: hash(secret, leaf_index, 1) from the proof's public
+ * inputs. Recording it too means a note already spent under the legacy
+ * scheme cannot be spent again under the domain-separated one.
+ *
+ * relay_nonce: must exceed the relay's last used nonce (see RelayNonce).
+ */
+export interface RecordNullifierArgs {
+  nullifier: number[];
+  legacyNullifier: number[] | null;
+  relayNonce: bigint;
+}
+
+/**
+ * Record a nullifier after verifying its balance proof on-chain
+ *
+ * Permissionless counterpart to record_nullifier: the proof is checked
+ * by CPI into the configured verifier, then its public inputs are bound
+ * to this pool's root, id and the nullifier set's purpose.
+ *
+ * The proof's change note (new_commitment) is inserted into the tree in
+ * the same call, so whoever submits the proof cannot burn the note
+ * without returning its change. Fails while the tree is frozen for a
+ * migration.
+ */
+export interface RecordNullifierWithProofArgs {
+  proof: Uint8Array;
+  publicInputs: number[][];
+}
+
+/**
+ * Require deposit, withdraw_many, withdraw_aggregated and
+ * withdraw_split to be top-level instructions, or called directly by
+ * one of `allowed_programs`
+ *
+ * Keeps wrapper programs from bundling pool instructions into
+ * transactions users sign without seeing what else they do. Off until
+ * first set; `enabled = false` turns it off again.
+ */
+export interface SetCpiGuardArgs {
+  enabled: boolean;
+  allowedPrograms: string[];
+}
+
+/**
+ * Set the withdrawal fee tiers and the token account fees are paid to
+ *
+ * A withdrawal pays the fee_bps of the last tier whose min_age its note
+ * has reached, so tiers must start at age 0, with ages increasing and
+ * fees not increasing. Also creates the LeafTimes account that deposit
+ * and add_commitment require; leaves inserted before it count as aged.
+ */
+export interface SetFeeTiersArgs {
+  tiers: FeeTier[];
+  feeRecipient: string;
+}
+
+/**
+ * Set the pool's exposure limits
+ *
+ * A new pool starts with small caps that governance raises as it gains
+ * confidence; 0 disables a cap. Deposits and withdrawals fail until
+ * limits exist.
+ *
+ * max_hourly_withdrawal_bps: share of TVL that may leave the pool per
+ * WITHDRAWAL_WINDOW_SECS before withdrawals pause automatically.
+ */
+export interface SetLimitsArgs {
+  maxTvl: bigint;
+  maxDeposit: bigint;
+  maxHourlyWithdrawalBps: number;
+}
+
+/**
+ * Propose a new relay signer for record_nullifier, add_commitment and
+ * migrate_notes
+ *
+ * Takes effect once the new key signs accept_relay, so a mistyped key
+ * cannot lock the relay out. Until the first rotation the pool
+ * authority is the relay. Proposing again replaces the pending key.
+ */
+export interface SetRelayArgs {
+  newRelay: string;
+}
+
+/**
+ * Set the program deposits are screened with, or None to stop
+ * screening
+ *
+ * The program receives the depositor and amount (see
+ * SCREEN_DEPOSIT_DISCRIMINATOR) plus `screening_accounts` accounts of
+ * its own, which depositors pass at the front of remaining_accounts
+ * after the program itself. The pool holds no policy of its own.
+ */
+export interface SetScreeningProgramArgs {
+  screeningProgram: string | null;
+  screeningAccounts: number;
+}
+
+/**
+ * Point proof verification at a verifier program and circuit
+ *
+ * The pool never parses proofs itself; swapping proving systems is a
+ * new verifier, and key rotation happens in the verifier against the
+ * circuit's active key, so neither needs a pool redeploy.
+ */
+export interface SetVerifierArgs {
+  verifierProgram: string;
+  circuitId: number[];
+  aggregateCircuitId: number[];
+}
+
+/**
+ * Pay out a batch of withdrawals backed by one aggregated proof
+ *
+ * The relay folds the individual balance proofs into a single proof
+ * off-chain; the pool verifies only that, then walks its public inputs.
+ * One pairing check replaces one per withdrawal, and no trusted relay
+ * signature is needed. Every nullifier is recorded in the spend set
+ * before any transfer, so a duplicate anywhere in the batch fails the
+ * whole call.
+ *
+ * Public inputs: merkle_root, pool_id, then per withdrawal
+ * (nullifier, legacy_nullifier, recipient_field(recipient), amount,
+ * leaf_bound, recipient_field(reference) or zero). The aggregate key's
+ * input count fixes the batch size it accepts.
+ *
+ * If the batch would push the hour's volume past the circuit breaker,
+ * withdrawals are paused instead and nothing is paid or recorded. Each
+ * withdrawal pays the fee tier for its leaf_bound (see FeeSchedule);
+ * recipients get amount minus fee.
+ *
+ * remaining_accounts: each withdrawal's recipient token account, in
+ * order, then the reference of each withdrawal that has one, in order.
+ */
+export interface WithdrawAggregatedArgs {
+  proof: Uint8Array;
+  withdrawals: Withdrawal[];
+}
+
+/**
+ * Pay out a batch of withdrawals, each backed by its own proof
+ *
+ * proofs[i] is a WITHDRAWAL_CIRCUIT_ID proof for withdrawals[i],
+ * verified on-chain against the circuit's active key before anything
+ * is recorded, so no relay signature is needed. Costs one pairing
+ * check per withdrawal; withdraw_aggregated pays for one.
+ *
+ * Public inputs of each proof: see withdrawal_public_inputs, after
+ * merkle_root and pool_id. Volume, nullifiers, fees and
+ * remaining_accounts are handled as in withdraw_aggregated.
+ */
+export interface WithdrawManyArgs {
+  proofs: Uint8Array[];
+  withdrawals: Withdrawal[];
+}
+
+/**
+ * Pay one note out to up to MAX_SPLIT_OUTPUTS recipients
+ *
+ * The split_withdrawal proof shows the spent note holds exactly the
+ * sum of the outputs, so a payroll or split spends one nullifier in
+ * one transaction instead of several withdrawals that could be linked
+ * to each other. The proof is verified on-chain against the active
+ * key of SPLIT_WITHDRAWAL_CIRCUIT_ID; no relay signature is needed.
+ *
+ * Public inputs: see split_public_inputs. Each output pays the fee
+ * tier for leaf_bound and emits its own WithdrawalEvent; the circuit
+ * breaker counts the outputs together.
+ *
+ * remaining_accounts: each output's recipient token account, in order.
+ */
+export interface WithdrawSplitArgs {
+  proof: Uint8Array;
+  split: SplitWithdrawal;
+}
+
+/**
+ * A withdrawal would have pushed the hourly volume past its limit and
+ * paused withdrawals.
+ */
+export interface CircuitBreakerTripped {
+  windowStart: bigint;
+  windowVolume: bigint;
+  attempted: bigint;
+  tvl: bigint;
+  maxHourlyWithdrawalBps: number;
+}
+
+/** The relay appended a commitment without a deposit, e.g. a change note. */
+export interface CommitmentAddedEvent {
+  leafIndex: number;
+  commitment: number[];
+  timestamp: bigint;
+  version: number;
+}
+
+/**
+ * Callers allowed to reach deposit and the withdrawals through CPI
+ *
+ * Created by set_cpi_guard; without one, or while disabled, any caller
+ * may.
+ */
+export interface CpiGuard {
+  pool: string;
+  enabled: boolean;
+  programCount: number;
+  /** Only the first program_count are set */
+  allowedPrograms: string[];
+}
+
+/**
+ * Which callers deposit and the withdrawals accept changed; see
+ * set_cpi_guard.
+ */
+export interface CpiGuardUpdatedEvent {
+  enabled: boolean;
+  allowedPrograms: string[];
+}
+
+/** A note commitment was appended to the tree by `deposit`. */
+export interface DepositEvent {
+  leafIndex: number;
+  commitment: number[];
+  timestamp: bigint;
+  /** Commitment scheme of the leaf */
+  version: number;
+}
+
+/** A soul-bound receipt `mint` was minted for the deposit at `leaf_index`. */
+export interface DepositReceiptEvent {
+  leafIndex: number;
+  mint: string;
+}
+
+/**
+ * Withdrawal fees by note age
+ *
+ * Note age is measured from the insertion of leaf `leaf_bound - 1`, the
+ * newest leaf the note can be, so it never overstates the age and only
+ * reveals that the note is among the first leaf_bound leaves.
+ */
+export interface FeeSchedule {
+  pool: string;
+  /** USDC token account fees are paid to */
+  feeRecipient: string;
+  tierCount: number;
+  /** Ascending min_age; only the first tier_count are set */
+  tiers: FeeTier[];
+}
+
+export interface FeeTier {
+  /** Seconds since insertion from which this tier applies */
+  minAge: bigint;
+  feeBps: number;
+}
+
+/** The withdrawal fee schedule was replaced. */
+export interface FeeTiersUpdatedEvent {
+  tiers: FeeTier[];
+  feeRecipient: string;
+}
+
+/** Insertion time of each leaf, for withdrawal fee tiers */
+export interface LeafTimes {
+  pool: string;
+  /** Unix timestamp per leaf index (0 = inserted before tracking began) */
+  insertedAt: bigint[];
+}
+
+/** TVL, per-deposit and hourly withdrawal limits changed. */
+export interface LimitsUpdatedEvent {
+  maxTvl: bigint;
+  maxDeposit: bigint;
+  maxHourlyWithdrawalBps: number;
+}
+
+/** A note was spent here and re-issued in the new pool as `new_commitment`. */
+export interface NoteMigratedEvent {
+  nullifier: number[];
+  newCommitment: number[];
+  amount: bigint;
+}
+
+/** One note moved by `migrate_notes` */
+export interface NoteMigration {
+  nullifier: number[];
+  legacyNullifier: number[] | null;
+  amount: bigint;
+  /** The note's COMMITMENT_VERSION commitment in the v2 tree */
+  newCommitment: number[];
+}
+
+/**
+ * Header of a nullifier set account, one per purpose
+ *
+ * Nullifier slots fill the rest of the account; the first `count` hold
+ * the spent nullifiers, sorted, so lookups are a binary search. Access
+ * them through NullifierSlots. The account grows with grow_nullifier_set.
+ */
+export interface NullifierSet {
+  pool: string;
+  purpose: number;
+  /** Little-endian u32, kept as bytes so the header has no padding */
+  count: number[];
+}
+
+/** A pool migrating into this one; import_notes inserts its moved notes */
+export interface PoolImport {
+  pool: string;
+  /** The source pool's PoolMigration, the only signer import_notes accepts */
+  sourceMigration: string;
+  importedNotes: number;
+  importedAmount: bigint;
+  openedAt: bigint;
+  updatedAt: bigint;
+}
+
+/** Exposure limits enforced by `deposit` */
+export interface PoolLimits {
+  pool: string;
+  /** Maximum USDC held by the pool vault (0 = no cap) */
+  maxTvl: bigint;
+  /** Maximum single deposit (0 = no cap) */
+  maxDeposit: bigint;
+  updatedAt: bigint;
+  /** Circuit breaker: share of TVL withdrawable per window (0 = off) */
+  maxHourlyWithdrawalBps: number;
+  windowStart: bigint;
+  windowVolume: bigint;
+  /** Set when the breaker trips; cleared only by the timelocked unpause */
+  paused: boolean;
+  unpauseRequestedAt: bigint;
+  /** Program every deposit is screened with (default = none) */
+  screeningProgram: string;
+  /** Accounts the screening program takes after the depositor */
+  screeningAccounts: number;
+}
+
+/** Progress of moving the pool to a v2 tree; its existence freezes deposits */
+export interface PoolMigration {
+  pool: string;
+  /** The v2 deployment; its pool is new_pool */
+  newProgram: string;
+  newPool: string;
+  /** Root every migrating note is proven against */
+  frozenRoot: number[];
+  frozenLeaves: number;
+  /** PoolStats::liabilities when the migration started */
+  liabilitiesAtStart: bigint;
+  migratedNotes: number;
+  migratedAmount: bigint;
+  startedAt: bigint;
+  updatedAt: bigint;
+}
+
+/** A migration was abandoned and the tree unfrozen. */
+export interface PoolMigrationCancelledEvent {
+  newPool: string;
+  migratedNotes: number;
+  migratedAmount: bigint;
+}
+
+/** Deposits froze at `frozen_root` and migration to `new_pool` began. */
+export interface PoolMigrationStartedEvent {
+  newPool: string;
+  frozenRoot: number[];
+  frozenLeaves: number;
+  liabilities: bigint;
+}
+
+/**
+ * Signer of record_nullifier, add_commitment and migrate_notes
+ *
+ * Created by initialize; pools initialized before it existed get one from
+ * their first set_relay and use their authority until then. Rotation is
+ * two-step: the authority proposes `pending_relay` and it
+ * becomes `relay` when that key signs accept_relay.
+ */
+export interface PoolRelay {
+  pool: string;
+  relay: string;
+  pendingRelay: string | null;
+  updatedAt: bigint;
+}
+
+/** Return data for `get_pool_state` */
+export interface PoolStateView {
+  authority: string;
+  merkleRoot: number[];
+  nextIndex: number;
+  nullifierCount: number;
+  maxLeaves: number;
+  /** Pool id public input for nullifier derivation */
+  poolId: number[];
+}
+
+/**
+ * Running deposit and withdrawal totals, checked against the vault
+ *
+ * Every note is owed out of the vault, so its balance must cover what
+ * was deposited minus what was withdrawn (fees included); a shortfall
+ * means an accounting bug and fails the instruction that exposed it.
+ */
+export interface PoolStats {
+  pool: string;
+  totalDeposited: bigint;
+  /** Gross withdrawal amounts, fees included */
+  totalWithdrawn: bigint;
+  depositCount: bigint;
+  updatedAt: bigint;
+}
+
+/**
+ * Zero-copy: instructions read and write the tree in place instead of
+ * deserializing every leaf onto the stack.
+ */
+export interface PrivacyPool {
+  authority: string;
+  merkleRoot: number[];
+  nextIndex: number;
+  nullifierCount: number;
+  leaves: number[][];
+}
+
+/**
+ * Last nonce used by a relay key on relay-gated instructions
+ *
+ * RPC retries can land a relay's transactions late or twice; requiring a
+ * strictly increasing nonce rejects the stale and replayed copies.
+ */
+export interface RelayNonce {
+  relay: string;
+  nonce: bigint;
+}
+
+/** A new relay was proposed; it takes over once it signs accept_relay. */
+export interface RelayProposedEvent {
+  relay: string;
+  pendingRelay: string;
+}
+
+/** The pool's relay signer changed from `previous` to `relay`. */
+export interface RelayRotatedEvent {
+  previous: string;
+  relay: string;
+}
+
+/** Latest `attest_reserves`; earlier ones remain in its events */
+export interface ReserveAttestation {
+  pool: string;
+  /** Root of the indexer's tree of unspent notes */
+  liabilitiesRoot: number[];
+  /** Sum of the unspent notes under liabilities_root */
+  liabilities: bigint;
+  noteCount: number;
+  /** Read from the pool vault when attesting */
+  vaultBalance: bigint;
+  /** PoolStats::liabilities when attesting */
+  recordedLiabilities: bigint;
+  /** Deposit tree the indexer's view was taken against */
+  merkleRoot: number[];
+  nextIndex: number;
+  attestedAt: bigint;
+}
+
+/** A proof-of-reserves snapshot of the vault against note liabilities. */
+export interface ReservesAttestedEvent {
+  liabilitiesRoot: number[];
+  liabilities: bigint;
+  noteCount: number;
+  vaultBalance: bigint;
+  recordedLiabilities: bigint;
+  merkleRoot: number[];
+  nextIndex: number;
+  timestamp: bigint;
+}
+
+/** The deposit screening program was set or cleared. */
+export interface ScreeningUpdatedEvent {
+  screeningProgram: string | null;
+  screeningAccounts: number;
+}
+
+/** One recipient of a `withdraw_split` */
+export interface SplitOutput {
+  /** Recipient USDC token account */
+  recipient: string;
+  /** Gross amount; the recipient receives it minus the fee */
+  amount: bigint;
+}
+
+/** A note spent to several recipients by `withdraw_split` */
+export interface SplitWithdrawal {
+  nullifier: number[];
+  legacyNullifier: number[];
+  /** Commitment scheme of the spent note */
+  noteVersion: number;
+  /** As for Withdrawal */
+  leafBound: number;
+  /** Add up to the note's amount */
+  outputs: SplitOutput[];
+}
+
+/** Unpausing withdrawals was requested; it can run from `executable_at`. */
+export interface UnpauseRequestedEvent {
+  executableAt: bigint;
+}
+
+/** Verifier program and circuit used by `record_nullifier_with_proof` */
+export interface VerifierConfig {
+  pool: string;
+  verifierProgram: string;
+  /** Zero-padded circuit name; the verifier resolves its active key */
+  circuitId: number[];
+  /** Circuit of the relay's aggregated withdrawal proofs */
+  aggregateCircuitId: number[];
+}
+
+/** Proof checks now go to `verifier_program` for `circuit_id`. */
+export interface VerifierUpdatedEvent {
+  verifierProgram: string;
+  circuitId: number[];
+}
+
+/** One entry of `withdraw_many` or `withdraw_aggregated` */
+export interface Withdrawal {
+  nullifier: number[];
+  legacyNullifier: number[] | null;
+  /** Recipient USDC token account */
+  recipient: string;
+  /** Gross amount; the recipient receives it minus the fee */
+  amount: bigint;
+  /**
+   * The spend proof shows the note's leaf index is below this; sets
+   * the fee tier (see FeeSchedule)
+   */
+  leafBound: number;
+  /**
+   * Solana Pay reference a merchant reconciles the payment by: a fresh
+   * key per invoice, passed as a read-only account so the transaction
+   * can be found by it
+   */
+  reference: string | null;
+}
+
+/** A nullifier was spent and `amount` less `fee` paid to `recipient`. */
+export interface WithdrawalEvent {
+  nullifier: number[];
+  recipient: string;
+  amount: bigint;
+  fee: bigint;
+  /** Invoice reference, if the withdrawal pays one */
+  reference: string | null;
+}
+
+/** Withdrawals resumed after the unpause timelock. */
+export interface WithdrawalsUnpausedEvent {
+  timestamp: bigint;
+}
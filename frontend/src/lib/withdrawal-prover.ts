@@ -0,0 +1,135 @@
+/**
+ * Client-side Withdrawal Prover
+ *
+ * Generates balance_proof proofs entirely in the browser, in a Web Worker,
+ * so the witness (note secret, amount, Merkle path) never reaches a prover
+ * service. Slower than the hosted prover on low-end devices; progress
+ * callbacks let the UI say so.
+ *
+ * Multithreaded proving needs a cross-origin isolated page (COOP/COEP
+ * headers); otherwise the worker proves on one thread.
+ */
+
+// Compiled circuit served from public/circuits
+const BALANCE_PROOF_CIRCUIT_URL = '/circuits/balance_proof.json';
+
+// Must match MERKLE_DEPTH in circuits/balance_proof
+export const WITHDRAWAL_MERKLE_DEPTH = 5;
+
+/**
+ * balance_proof inputs; Field values as decimal or 0x-hex strings
+ */
+export interface WithdrawalWitness {
+  // Public
+  merkle_root: string;
+  nullifier: string;
+  pool_id: string;
+  purpose: string;
+  legacy_nullifier: string;
+  new_commitment: string;
+  order_commitment: string;
+  // Commitment scheme of the spent note: 0 = legacy, 1 = current
+  note_version: string;
+
+  // Private
+  old_secret: string;
+  old_amount: string;
+  leaf_index: string;
+  // Mint address with the top byte zeroed; change notes keep it
+  asset: string;
+  merkle_path: string[];
+  order_amount: string;
+  order_salt: string;
+  new_secret: string;
+}
+
+export type ProverStage = 'download' | 'witness' | 'proving';
+
+export interface ProverProgress {
+  stage: ProverStage;
+  // 0..1 while downloading, when the size is known
+  fraction?: number;
+  // bb.js threads used for proving
+  threads?: number;
+}
+
+export interface WithdrawalProof {
+  proof: Uint8Array;
+  publicInputs: string[];
+}
+
+// Worker protocol
+export interface ProverRequest {
+  circuitUrl: string;
+  inputs: Record<string, string | string[]>;
+}
+
+export type ProverMessage =
+  | ({ type: 'progress' } & ProverProgress)
+  | ({ type: 'result' } & WithdrawalProof)
+  | { type: 'error'; message: string };
+
+export interface ProveOptions {
+  onProgress?: (progress: ProverProgress) => void;
+  // Aborting terminates the worker and frees its memory immediately
+  signal?: AbortSignal;
+}
+
+/**
+ * Prove a withdrawal locally. Each call gets a fresh worker, which is
+ * terminated afterwards so the proving heap is returned to the OS.
+ */
+export function proveWithdrawal(
+  witness: WithdrawalWitness,
+  options: ProveOptions = {}
+): Promise<WithdrawalProof> {
+  if (witness.merkle_path.length !== WITHDRAWAL_MERKLE_DEPTH) {
+    return Promise.reject(
+      new Error(`merkle_path must have ${WITHDRAWAL_MERKLE_DEPTH} siblings, got ${witness.merkle_path.length}`)
+    );
+  }
+  if (options.signal?.aborted) {
+    return Promise.reject(new DOMException('Proof generation aborted', 'AbortError'));
+  }
+
+  const worker = new Worker(new URL('./withdrawal-prover.worker.ts', import.meta.url), { type: 'module' });
+
+  return new Promise<WithdrawalProof>((resolve, reject) => {
+    const onAbort = () => {
+      finish();
+      reject(new DOMException('Proof generation aborted', 'AbortError'));
+    };
+    const finish = () => {
+      options.signal?.removeEventListener('abort', onAbort);
+      worker.terminate();
+    };
+    options.signal?.addEventListener('abort', onAbort);
+
+    worker.onmessage = (event: MessageEvent<ProverMessage>) => {
+      const message = event.data;
+      switch (message.type) {
+        case 'progress':
+          options.onProgress?.({ stage: message.stage, fraction: message.fraction, threads: message.threads });
+          break;
+        case 'result':
+          finish();
+          resolve({ proof: message.proof, publicInputs: message.publicInputs });
+          break;
+        case 'error':
+          finish();
+          reject(new Error(message.message));
+          break;
+      }
+    };
+    worker.onerror = event => {
+      finish();
+      reject(new Error(event.message || 'Prover worker crashed'));
+    };
+
+    const request: ProverRequest = {
+      circuitUrl: BALANCE_PROOF_CIRCUIT_URL,
+      inputs: { ...witness },
+    };
+    worker.postMessage(request);
+  });
+}
@@ -0,0 +1,93 @@
+/**
+ * Withdrawal Prover Worker
+ *
+ * Proves the balance_proof circuit with noir_js + bb.js (Barretenberg WASM)
+ * off the main thread. The witness is posted in and the proof posted out;
+ * nothing else leaves this worker.
+ */
+
+import { Noir } from '@noir-lang/noir_js';
+import { UltraHonkBackend } from '@aztec/bb.js';
+import type { ProverRequest, ProverMessage } from './withdrawal-prover';
+
+// WebAssembly memory is counted in 64 KiB pages
+const WASM_PAGE = 64 * 1024;
+
+// 32-bit WASM cannot address more than 4 GiB
+const MAX_WASM_PAGES = (4 * 1024 * 1024 * 1024) / WASM_PAGE;
+
+function post(message: ProverMessage, transfer: Transferable[] = []): void {
+  (self as unknown as Worker).postMessage(message, transfer);
+}
+
+/**
+ * Stream the compiled circuit in chunks so progress can be reported and the
+ * buffer is allocated once instead of grown on every chunk
+ */
+async function fetchCircuit(url: string): Promise<{ bytecode: string; abi: unknown }> {
+  const response = await fetch(url);
+  if (!response.ok || !response.body) {
+    throw new Error(`Failed to load circuit: HTTP ${response.status}`);
+  }
+
+  const total = Number(response.headers.get('Content-Length')) || 0;
+  const reader = response.body.getReader();
+  let buffer = new Uint8Array(total || 1 << 20);
+  let received = 0;
+
+  for (;;) {
+    const { done, value } = await reader.read();
+    if (done) break;
+    if (received + value.length > buffer.length) {
+      // Unknown or wrong Content-Length: grow geometrically
+      const grown = new Uint8Array(Math.max(buffer.length * 2, received + value.length));
+      grown.set(buffer.subarray(0, received));
+      buffer = grown;
+    }
+    buffer.set(value, received);
+    received += value.length;
+    post({ type: 'progress', stage: 'download', fraction: total ? received / total : undefined });
+  }
+
+  return JSON.parse(new TextDecoder().decode(buffer.subarray(0, received)));
+}
+
+/**
+ * Cap WASM memory at half the device's RAM so low-end phones fail fast
+ * with a clear error instead of being killed by the OS mid-proof
+ */
+function maxMemoryPages(): number {
+  const deviceGiB = (navigator as Navigator & { deviceMemory?: number }).deviceMemory;
+  if (!deviceGiB) return MAX_WASM_PAGES;
+  return Math.min(MAX_WASM_PAGES, Math.floor((deviceGiB * 1024 * 1024 * 1024) / 2 / WASM_PAGE));
+}
+
+self.onmessage = async (event: MessageEvent<ProverRequest>) => {
+  const { circuitUrl, inputs } = event.data;
+  let backend: UltraHonkBackend | undefined;
+
+  try {
+    const circuit = await fetchCircuit(circuitUrl);
+
+    post({ type: 'progress', stage: 'witness' });
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    const noir = new Noir(circuit as any);
+    const { witness } = await noir.execute(inputs);
+
+    // Threads need SharedArrayBuffer, i.e. a cross-origin isolated page
+    const threads = self.crossOriginIsolated ? Math.min(navigator.hardwareConcurrency || 1, 8) : 1;
+    post({ type: 'progress', stage: 'proving', threads });
+    backend = new UltraHonkBackend(circuit.bytecode, {
+      threads,
+      memory: { maximum: maxMemoryPages() },
+    });
+    const proof = await backend.generateProof(witness);
+
+    post({ type: 'result', proof: proof.proof, publicInputs: proof.publicInputs }, [proof.proof.buffer]);
+  } catch (error) {
+    post({ type: 'error', message: error instanceof Error ? error.message : 'Proof generation failed' });
+  } finally {
+    // Release the WASM heap now rather than whenever the worker is collected
+    await backend?.destroy().catch(() => undefined);
+  }
+};
@@ -14,6 +14,14 @@ export default defineConfig({
       },
     }),
   ],
+  // Barretenberg and the ACVM ship their own WASM; pre-bundling breaks their asset URLs
+  optimizeDeps: {
+    exclude: ['@aztec/bb.js', '@noir-lang/noir_js'],
+  },
+  // The withdrawal prover worker imports ES modules
+  worker: {
+    format: 'es',
+  },
   server: {
     port: 5173,
     proxy: {
@@ -0,0 +1,5 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|ops: Vec<obsidian_fuzz::Op>| obsidian_fuzz::run(&ops));
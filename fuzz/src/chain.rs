@@ -0,0 +1,290 @@
+//! LiteSVM with both programs deployed and initialized, plus the actors
+//! and USDC accounts the sequences run with.
+
+use std::path::PathBuf;
+
+use anchor_lang::prelude::{Clock, Pubkey};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::AccountDeserialize;
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use arbitrary::Arbitrary;
+use litesvm::LiteSVM;
+use obsidian_sdk::mpc::{self, MpcAccount};
+use obsidian_sdk::pool;
+use privacy_pool::{NullifierSet, NULLIFIER_PURPOSE_BATCH, NULLIFIER_PURPOSE_SPEND, USDC_MINT};
+use solana_account::Account;
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+/// Arbitrary amounts are counted in cents
+pub const UNIT: u64 = 10_000;
+
+/// USDC every actor starts with
+pub const STARTING_USDC: u64 = 1_000_000 * 1_000_000;
+
+const STARTING_LAMPORTS: u64 = 1_000 * 1_000_000_000;
+const GENESIS_TIMESTAMP: i64 = 1_700_000_000;
+
+/// Who signs (and pays for) a transaction
+#[derive(Arbitrary, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Actor {
+    /// Pool authority, and admin, operator and settler of the MPC program
+    Relay,
+    User,
+    Attacker,
+}
+
+impl Actor {
+    pub const ALL: [Actor; 3] = [Actor::Relay, Actor::User, Actor::Attacker];
+}
+
+/// Markets batches are created in. Batch PDAs are seeded by authority and
+/// market, so the relay has at most one batch per market.
+#[derive(Arbitrary, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Market {
+    Buf,
+    Den,
+    Sea,
+}
+
+impl Market {
+    pub const ALL: [Market; 3] = [Market::Buf, Market::Den, Market::Sea];
+
+    pub fn id(self) -> &'static str {
+        match self {
+            Market::Buf => "KXSB-26-BUF",
+            Market::Den => "KXSB-26-DEN",
+            Market::Sea => "KXSB-26-SEA",
+        }
+    }
+}
+
+fn deploy_path(relative: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join(relative)
+}
+
+pub struct Chain {
+    svm: LiteSVM,
+    keys: [Keypair; 3],
+    /// Owner of the pool's fee recipient token account
+    fee_owner: Pubkey,
+}
+
+impl Chain {
+    pub fn new() -> Self {
+        let mut svm = LiteSVM::new();
+        for (program_id, path) in [
+            (privacy_pool::ID, "target/deploy/privacy_pool.so"),
+            (mpc::ID, "arcium-relay/target/deploy/obsidian_mpc.so"),
+        ] {
+            svm.add_program_from_file(program_id, deploy_path(path))
+                .unwrap_or_else(|e| panic!("{path}: {e:?}; build the programs first"));
+        }
+        let mut clock = svm.get_sysvar::<Clock>();
+        clock.unix_timestamp = GENESIS_TIMESTAMP;
+        svm.set_sysvar(&clock);
+
+        let mut chain = Self {
+            svm,
+            keys: [1, 2, 3].map(|seed| Keypair::new_from_array([seed; 32])),
+            fee_owner: Pubkey::new_from_array([4; 32]),
+        };
+
+        let mut mint = vec![0; spl_token::state::Mint::LEN];
+        spl_token::state::Mint {
+            mint_authority: COption::None,
+            supply: STARTING_USDC * Actor::ALL.len() as u64,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        }
+        .pack_into_slice(&mut mint);
+        chain.put(USDC_MINT, mint, spl_token::ID);
+
+        for actor in Actor::ALL {
+            let key = chain.key(actor);
+            chain.put(key, vec![], system_program::ID);
+            chain.put_usdc(&key, STARTING_USDC);
+        }
+        chain.put_usdc(&chain.fee_owner.clone(), 0);
+        chain.put_usdc(&pool::pool_address(), 0);
+        for market in Market::ALL {
+            chain.put_usdc(&chain.batch(market), 0);
+        }
+
+        let relay = chain.key(Actor::Relay);
+        for ix in [
+            pool::initialize_instruction(&relay, &relay),
+            pool::initialize_nullifier_set_instruction(&relay, NULLIFIER_PURPOSE_SPEND),
+            pool::initialize_nullifier_set_instruction(&relay, NULLIFIER_PURPOSE_BATCH),
+            pool::initialize_pool_stats_instruction(&relay),
+            pool::set_limits_instruction(&relay, 0, 0, 0),
+            pool::set_fee_tiers_instruction(&relay, vec![], &chain.fee_recipient()),
+            mpc::initialize_config(&relay, 0, 0),
+            mpc::initialize_credit_ledger(&relay, &USDC_MINT, &spl_token::ID),
+        ] {
+            chain
+                .send(ix, Actor::Relay)
+                .unwrap_or_else(|e| panic!("setup failed: {e}"));
+        }
+        chain
+    }
+
+    fn put(&mut self, address: Pubkey, data: Vec<u8>, owner: Pubkey) {
+        let lamports = if data.is_empty() {
+            STARTING_LAMPORTS
+        } else {
+            self.svm.minimum_balance_for_rent_exemption(data.len())
+        };
+        self.svm
+            .set_account(
+                address,
+                Account {
+                    lamports,
+                    data,
+                    owner,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            )
+            .expect("set_account");
+    }
+
+    /// Create `owner`'s USDC associated token account holding `amount`
+    fn put_usdc(&mut self, owner: &Pubkey, amount: u64) {
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            mint: USDC_MINT,
+            owner: *owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        }
+        .pack_into_slice(&mut data);
+        self.put(
+            get_associated_token_address(owner, &USDC_MINT),
+            data,
+            spl_token::ID,
+        );
+    }
+
+    pub fn key(&self, actor: Actor) -> Pubkey {
+        self.keys[actor as usize].pubkey()
+    }
+
+    pub fn usdc(&self, actor: Actor) -> Pubkey {
+        get_associated_token_address(&self.key(actor), &USDC_MINT)
+    }
+
+    pub fn fee_recipient(&self) -> Pubkey {
+        get_associated_token_address(&self.fee_owner, &USDC_MINT)
+    }
+
+    /// The relay's batch in `market`
+    pub fn batch(&self, market: Market) -> Pubkey {
+        mpc::batch_address(&self.key(Actor::Relay), market.id())
+    }
+
+    pub fn escrow(&self, market: Market) -> Pubkey {
+        mpc::batch_escrow_address(&self.batch(market), &USDC_MINT, &spl_token::ID)
+    }
+
+    /// Every USDC token account in play; the mint has no authority, so
+    /// their balances always sum to the supply
+    pub fn usdc_accounts(&self) -> Vec<Pubkey> {
+        let mut accounts: Vec<Pubkey> = Actor::ALL.iter().map(|a| self.usdc(*a)).collect();
+        accounts.push(self.fee_recipient());
+        accounts.push(get_associated_token_address(
+            &pool::pool_address(),
+            &USDC_MINT,
+        ));
+        accounts.push(mpc::credit_vault_address());
+        accounts.extend(Market::ALL.iter().map(|m| self.escrow(*m)));
+        accounts
+    }
+
+    /// Run `ix` in its own transaction, signed and paid for by `signer`.
+    ///
+    /// Accounts the instruction expects to sign that `signer` does not hold
+    /// are passed unsigned, as an attacker substituting them would.
+    pub fn send(&mut self, mut ix: Instruction, signer: Actor) -> Result<(), String> {
+        let keypair = &self.keys[signer as usize];
+        for meta in &mut ix.accounts {
+            if meta.pubkey != keypair.pubkey() {
+                meta.is_signer = false;
+            }
+        }
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&keypair.pubkey()),
+            &[keypair],
+            self.svm.latest_blockhash(),
+        );
+        let result = self
+            .svm
+            .send_transaction(tx)
+            .map(|_| ())
+            .map_err(|failed| format!("{:?}", failed.err));
+        // An identical retry would otherwise be rejected as already processed
+        self.svm.expire_blockhash();
+        result
+    }
+
+    pub fn now(&self) -> i64 {
+        self.svm.get_sysvar::<Clock>().unix_timestamp
+    }
+
+    pub fn warp(&mut self, seconds: i64) {
+        let mut clock = self.svm.get_sysvar::<Clock>();
+        clock.unix_timestamp += seconds;
+        clock.slot += seconds.max(0) as u64 * 5 / 2;
+        self.svm.set_sysvar(&clock);
+    }
+
+    fn data(&self, address: &Pubkey) -> Option<Vec<u8>> {
+        self.svm
+            .get_account(address)
+            .filter(|account| account.lamports > 0)
+            .map(|account| account.data)
+    }
+
+    pub fn exists(&self, address: &Pubkey) -> bool {
+        self.data(address).is_some()
+    }
+
+    /// A privacy_pool account
+    pub fn pool_account<T: AccountDeserialize>(&self, address: &Pubkey) -> Option<T> {
+        self.data(address)
+            .and_then(|data| T::try_deserialize(&mut data.as_slice()).ok())
+    }
+
+    /// Spent nullifiers of a privacy_pool nullifier set, in set order
+    pub fn nullifiers(&self, address: &Pubkey) -> Option<Vec<[u8; 32]>> {
+        let data = self.data(address)?;
+        NullifierSet::decode(&data).map(|(_, spent)| spent.to_vec())
+    }
+
+    /// An obsidian_mpc account
+    pub fn mpc_account<T: MpcAccount>(&self, address: &Pubkey) -> Option<T> {
+        self.data(address).and_then(|data| T::decode(&data).ok())
+    }
+
+    pub fn usdc_balance(&self, address: &Pubkey) -> u64 {
+        self.data(address)
+            .and_then(|data| spl_token::state::Account::unpack(&data).ok())
+            .map_or(0, |account| account.amount)
+    }
+}
+
+impl Default for Chain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,642 @@
+//! obsidian_mpc operations and the invariants checked after each.
+//!
+//! Only the relay's batches are tracked: batch PDAs are seeded by the
+//! authority, so one created by anyone else would be a bug caught by the
+//! privileged-instruction check.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::token::spl_token;
+use arbitrary::Arbitrary;
+use obsidian_sdk::mpc::{
+    self, Batch, BatchLane, BatchStatus, CreditLedger, CreditReason, DistributionTree, OrderSlot,
+    RelayNonce, UserCredit,
+};
+use privacy_pool::USDC_MINT;
+
+use crate::chain::{Actor, Chain, Market, UNIT};
+use crate::ensure;
+
+const INTENT_HASH: [u8; 32] = [7; 32];
+
+#[derive(Arbitrary, Debug)]
+pub enum MpcOp {
+    CreateBatch {
+        signer: Actor,
+        market: Market,
+        intent: bool,
+    },
+    /// Repeated `1 + repeat` times, each checked on its own
+    RecordOrder {
+        signer: Actor,
+        market: Market,
+        repeat: u8,
+    },
+    CloseBatch {
+        signer: Actor,
+        market: Market,
+        revealed_total: u32,
+        /// None reveals the batch's actual order count
+        revealed_count: Option<u8>,
+    },
+    RecordExecution {
+        signer: Actor,
+        market: Market,
+        total_shares: u32,
+        /// None fills the whole batch
+        filled: Option<u32>,
+        intent: bool,
+    },
+    /// Stages one distribution per allocation
+    StageDistributions {
+        signer: Actor,
+        market: Market,
+        allocations: Vec<Allocation>,
+    },
+    RecordDistribution {
+        signer: Actor,
+        market: Market,
+        order_index: u8,
+        /// Claim one more share than the committed leaf
+        forged: bool,
+        nonce: Option<u8>,
+    },
+    FinalizeDistributions {
+        signer: Actor,
+        market: Market,
+    },
+    MarkDistributed {
+        signer: Actor,
+        market: Market,
+        order_index: u8,
+    },
+    ForceFailBatch {
+        signer: Actor,
+        market: Market,
+    },
+    ReconcileBatch {
+        signer: Actor,
+        market: Market,
+    },
+    SetBatchTimeouts {
+        signer: Actor,
+        execution_minutes: u16,
+        completion_minutes: u16,
+    },
+    /// A plain token transfer into a batch escrow
+    FundEscrow {
+        source: Actor,
+        market: Market,
+        amount: u16,
+    },
+    RecordCredit {
+        signer: Actor,
+        owner: Actor,
+        amount: u16,
+    },
+    ClaimCredit {
+        signer: Actor,
+        owner: Actor,
+        recipient: Actor,
+        amount: u16,
+    },
+}
+
+#[derive(Arbitrary, Clone, Copy, Debug)]
+pub struct Allocation {
+    shares: u16,
+    refund: u16,
+    wallet: Actor,
+}
+
+impl MpcOp {
+    fn signer(&self) -> Actor {
+        match self {
+            MpcOp::CreateBatch { signer, .. }
+            | MpcOp::RecordOrder { signer, .. }
+            | MpcOp::CloseBatch { signer, .. }
+            | MpcOp::RecordExecution { signer, .. }
+            | MpcOp::StageDistributions { signer, .. }
+            | MpcOp::RecordDistribution { signer, .. }
+            | MpcOp::FinalizeDistributions { signer, .. }
+            | MpcOp::MarkDistributed { signer, .. }
+            | MpcOp::ForceFailBatch { signer, .. }
+            | MpcOp::ReconcileBatch { signer, .. }
+            | MpcOp::SetBatchTimeouts { signer, .. }
+            | MpcOp::RecordCredit { signer, .. }
+            | MpcOp::ClaimCredit { signer, .. } => *signer,
+            MpcOp::FundEscrow { source, .. } => *source,
+        }
+    }
+
+    /// Needs the operator, settler or admin role, all held by the relay
+    fn privileged(&self) -> bool {
+        !matches!(
+            self,
+            MpcOp::ForceFailBatch { .. }
+                | MpcOp::ReconcileBatch { .. }
+                | MpcOp::FundEscrow { .. }
+                | MpcOp::ClaimCredit { .. }
+        )
+    }
+
+    pub fn repeat(&self) -> usize {
+        match self {
+            MpcOp::RecordOrder { repeat, .. } => 1 + *repeat as usize,
+            _ => 1,
+        }
+    }
+}
+
+/// Everything the invariants compare across an operation
+struct Snapshot {
+    /// Per market
+    batches: [Option<Batch>; 3],
+    escrows: [u64; 3],
+    /// Per market, whether the slot at order_count-1 and order_count exist
+    slots: [(bool, bool); 3],
+    ledger: CreditLedger,
+    credit_vault: u64,
+    /// Per actor
+    credits: [Option<UserCredit>; 3],
+    relay_nonces: [u64; 3],
+}
+
+impl Snapshot {
+    fn read(chain: &Chain) -> Self {
+        let batches = Market::ALL.map(|market| chain.mpc_account::<Batch>(&chain.batch(market)));
+        let slots = std::array::from_fn(|i| {
+            let batch = chain.batch(Market::ALL[i]);
+            let count = batches[i].as_ref().map_or(0, |b| b.order_count);
+            let slot = |index: u8| {
+                chain
+                    .mpc_account::<OrderSlot>(&mpc::order_slot_address(&batch, index))
+                    .is_some()
+            };
+            (count > 0 && slot(count - 1), count < u8::MAX && slot(count))
+        });
+        Self {
+            batches,
+            escrows: Market::ALL.map(|market| chain.usdc_balance(&chain.escrow(market))),
+            slots,
+            ledger: chain
+                .mpc_account(&mpc::credit_ledger_address())
+                .expect("credit ledger"),
+            credit_vault: chain.usdc_balance(&mpc::credit_vault_address()),
+            credits: Actor::ALL
+                .map(|actor| chain.mpc_account(&mpc::user_credit_address(&chain.key(actor)))),
+            relay_nonces: Actor::ALL.map(|actor| {
+                chain
+                    .mpc_account::<RelayNonce>(&mpc::relay_nonce_address(&chain.key(actor)))
+                    .map_or(0, |n| n.nonce)
+            }),
+        }
+    }
+}
+
+fn allowed(from: BatchStatus, to: BatchStatus) -> bool {
+    use BatchStatus::*;
+    from == to
+        || matches!(
+            (from, to),
+            (Open, Closed)
+                | (Closed, Executed)
+                | (Executed, Distributing)
+                | (Distributing, Completed)
+                | (Closed | Executed | Distributing | Frozen, Failed)
+                | (Closed | Executed, Frozen)
+        )
+}
+
+/// What the batches and credit ledger should hold, from the operations
+/// that succeeded
+pub struct MpcModel {
+    /// USDC sent to each market's escrow
+    funded: [u64; 3],
+    /// Allocations behind each market's committed distribution root
+    committed: [Option<(Vec<Allocation>, DistributionTree)>; 3],
+    last: Snapshot,
+}
+
+impl MpcModel {
+    pub fn new(chain: &Chain) -> Self {
+        Self {
+            funded: [0; 3],
+            committed: [None, None, None],
+            last: Snapshot::read(chain),
+        }
+    }
+
+    pub fn execute(&self, chain: &mut Chain, op: &MpcOp) -> Result<(), String> {
+        let signer = op.signer();
+        let key = chain.key(signer);
+        let batch_of = |market: &Market| chain.batch(*market);
+        let order_count = |market: &Market| {
+            self.last.batches[*market as usize]
+                .as_ref()
+                .map_or(0, |b| b.order_count)
+        };
+        let ix = match op {
+            MpcOp::CreateBatch { market, intent, .. } => mpc::create_batch(
+                &key,
+                market.id(),
+                0,
+                BatchLane::Standard,
+                None,
+                intent.then_some(INTENT_HASH),
+            ),
+            MpcOp::RecordOrder { market, .. } => {
+                mpc::record_order(&batch_of(market), &key, order_count(market))
+            }
+            MpcOp::CloseBatch {
+                market,
+                revealed_total,
+                revealed_count,
+                ..
+            } => mpc::close_batch(
+                &batch_of(market),
+                market.id(),
+                &key,
+                *revealed_total as u64 * UNIT,
+                revealed_count.unwrap_or(order_count(market)),
+            ),
+            MpcOp::RecordExecution {
+                market,
+                total_shares,
+                filled,
+                intent,
+                ..
+            } => {
+                let total = self.last.batches[*market as usize]
+                    .as_ref()
+                    .map_or(0, |b| b.total_usdc);
+                mpc::record_execution(
+                    &batch_of(market),
+                    market.id(),
+                    &key,
+                    *total_shares as u64,
+                    filled.map_or(total, |f| f as u64 * UNIT),
+                    "fuzz",
+                    intent.then_some(INTENT_HASH),
+                    false,
+                )
+            }
+            MpcOp::StageDistributions {
+                market,
+                allocations,
+                ..
+            } => mpc::stage_distributions(
+                &batch_of(market),
+                &key,
+                self.tree(chain, allocations).root(),
+                allocations.len().min(u8::MAX as usize) as u8,
+                allocations
+                    .iter()
+                    .take(1 << mpc::DISTRIBUTION_TREE_DEPTH)
+                    .map(|a| a.shares as u64)
+                    .sum(),
+            ),
+            MpcOp::RecordDistribution {
+                market,
+                order_index,
+                forged,
+                nonce,
+                ..
+            } => {
+                let (allocation, proof) = match &self.committed[*market as usize] {
+                    Some((allocations, tree)) => (
+                        allocations.get(*order_index as usize).copied(),
+                        tree.proof(*order_index),
+                    ),
+                    None => (None, Default::default()),
+                };
+                let allocation = allocation.unwrap_or(Allocation {
+                    shares: 0,
+                    refund: 0,
+                    wallet: Actor::User,
+                });
+                mpc::record_distribution(
+                    &batch_of(market),
+                    &key,
+                    *order_index,
+                    allocation.shares as u64 + *forged as u64,
+                    allocation.refund as u64 * UNIT,
+                    &chain.key(allocation.wallet),
+                    None,
+                    proof,
+                    match nonce {
+                        Some(nonce) => *nonce as u64,
+                        None => self.last.relay_nonces[signer as usize] + 1,
+                    },
+                )
+            }
+            MpcOp::MarkDistributed {
+                market,
+                order_index,
+                ..
+            } => mpc::mark_distributed(&batch_of(market), market.id(), &key, *order_index, "fuzz"),
+            MpcOp::FinalizeDistributions { market, .. } => {
+                mpc::finalize_distributions(&batch_of(market), &key)
+            }
+            MpcOp::ForceFailBatch { market, .. } => mpc::force_fail_batch(&batch_of(market)),
+            MpcOp::ReconcileBatch { market, .. } => {
+                mpc::reconcile_batch(&batch_of(market), &USDC_MINT, &spl_token::ID)
+            }
+            MpcOp::SetBatchTimeouts {
+                execution_minutes,
+                completion_minutes,
+                ..
+            } => mpc::set_batch_timeouts(
+                &key,
+                *execution_minutes as i64 * 60,
+                *completion_minutes as i64 * 60,
+            ),
+            MpcOp::FundEscrow { market, amount, .. } => spl_token::instruction::transfer(
+                &spl_token::ID,
+                &chain.usdc(signer),
+                &chain.escrow(*market),
+                &key,
+                &[],
+                *amount as u64 * UNIT,
+            )
+            .expect("transfer instruction"),
+            MpcOp::RecordCredit { owner, amount, .. } => mpc::record_credit(
+                &key,
+                &chain.usdc(signer),
+                &USDC_MINT,
+                &spl_token::ID,
+                &chain.key(*owner),
+                *amount as u64 * UNIT,
+                CreditReason::Refund,
+                &Pubkey::default(),
+            ),
+            MpcOp::ClaimCredit {
+                owner,
+                recipient,
+                amount,
+                ..
+            } => mpc::claim_credit(
+                &chain.key(*owner),
+                &chain.usdc(*recipient),
+                &USDC_MINT,
+                &spl_token::ID,
+                *amount as u64 * UNIT,
+            ),
+        };
+        chain.send(ix, signer)
+    }
+
+    fn tree(&self, chain: &Chain, allocations: &[Allocation]) -> DistributionTree {
+        let leaves: Vec<[u8; 32]> = allocations
+            .iter()
+            .take(1 << mpc::DISTRIBUTION_TREE_DEPTH)
+            .enumerate()
+            .map(|(i, a)| {
+                mpc::distribution_leaf(
+                    i as u8,
+                    a.shares as u64,
+                    a.refund as u64 * UNIT,
+                    0,
+                    &chain.key(a.wallet),
+                )
+            })
+            .collect();
+        DistributionTree::new(&leaves)
+    }
+
+    pub fn check(
+        &mut self,
+        chain: &Chain,
+        op: &MpcOp,
+        result: &Result<(), String>,
+    ) -> Result<(), String> {
+        let now = Snapshot::read(chain);
+
+        if op.privileged() && op.signer() != Actor::Relay {
+            ensure!(
+                result.is_err(),
+                "{:?} ran a role-gated instruction",
+                op.signer()
+            );
+        }
+        if result.is_ok() {
+            match op {
+                MpcOp::FundEscrow { market, amount, .. } => {
+                    self.funded[*market as usize] += *amount as u64 * UNIT;
+                }
+                MpcOp::StageDistributions {
+                    market,
+                    allocations,
+                    ..
+                } => {
+                    self.committed[*market as usize] =
+                        Some((allocations.clone(), self.tree(chain, allocations)));
+                }
+                MpcOp::RecordDistribution { forged, .. } => {
+                    ensure!(
+                        !forged,
+                        "a distribution off its committed leaf was recorded"
+                    );
+                }
+                MpcOp::ClaimCredit { signer, owner, .. } => {
+                    ensure!(signer == owner, "{signer:?} claimed {owner:?}'s credit");
+                }
+                _ => {}
+            }
+        }
+
+        for (i, market) in Market::ALL.into_iter().enumerate() {
+            self.check_batch(op, result, market, &now)
+                .map_err(|e| format!("{}: {e}", market.id()))?;
+            ensure!(
+                now.escrows[i] == self.funded[i],
+                "{} escrow holds {}, {} was sent to it",
+                market.id(),
+                now.escrows[i],
+                self.funded[i]
+            );
+        }
+
+        // Credit ledger: the vault backs every outstanding credit exactly
+        ensure!(
+            now.ledger.total_claimed <= now.ledger.total_credited,
+            "claimed {} of {} credited",
+            now.ledger.total_claimed,
+            now.ledger.total_credited
+        );
+        let outstanding = now.ledger.total_credited - now.ledger.total_claimed;
+        ensure!(
+            now.credit_vault == outstanding,
+            "credit vault holds {}, {outstanding} is outstanding",
+            now.credit_vault
+        );
+        let mut balances = 0;
+        for credit in now.credits.iter().flatten() {
+            ensure!(
+                credit.total_credited.checked_sub(credit.total_claimed) == Some(credit.balance),
+                "credit of {} out of balance",
+                credit.owner
+            );
+            balances += credit.balance;
+        }
+        ensure!(
+            balances == outstanding,
+            "user credits total {balances}, ledger owes {outstanding}"
+        );
+
+        for (actor, (before, after)) in self
+            .last
+            .relay_nonces
+            .iter()
+            .zip(now.relay_nonces)
+            .enumerate()
+        {
+            ensure!(after >= *before, "relay nonce of actor {actor} went back");
+        }
+
+        self.last = now;
+        Ok(())
+    }
+
+    fn check_batch(
+        &self,
+        op: &MpcOp,
+        result: &Result<(), String>,
+        market: Market,
+        now: &Snapshot,
+    ) -> Result<(), String> {
+        let i = market as usize;
+        let Some(batch) = &now.batches[i] else {
+            ensure!(self.last.batches[i].is_none(), "batch disappeared");
+            return Ok(());
+        };
+        let (has_last_slot, has_next_slot) = now.slots[i];
+        ensure!(
+            batch.order_count == 0 || has_last_slot,
+            "no slot for order {}",
+            batch.order_count - 1
+        );
+        ensure!(
+            !has_next_slot,
+            "a slot past order_count {}",
+            batch.order_count
+        );
+        ensure!(
+            batch.order_count <= mpc::MAX_BATCH_ORDERS,
+            "{} orders in one batch",
+            batch.order_count
+        );
+        ensure!(
+            batch.distributions_completed <= batch.order_count,
+            "{} distributions of {} orders",
+            batch.distributions_completed,
+            batch.order_count
+        );
+        ensure!(
+            batch.filled_usdc <= batch.total_usdc,
+            "filled {} of {}",
+            batch.filled_usdc,
+            batch.total_usdc
+        );
+        ensure!(
+            batch.recorded_refunds <= batch.total_usdc - batch.filled_usdc,
+            "refunds {} exceed the unfilled {}",
+            batch.recorded_refunds,
+            batch.total_usdc - batch.filled_usdc
+        );
+        ensure!(
+            batch.recorded_distributions <= batch.staged_distributions,
+            "{} distributions recorded of {} staged",
+            batch.recorded_distributions,
+            batch.staged_distributions
+        );
+        if batch.distributions_finalized {
+            ensure!(
+                batch.recorded_distributions == batch.staged_distributions,
+                "finalized with {} of {} staged distributions recorded",
+                batch.recorded_distributions,
+                batch.staged_distributions
+            );
+        }
+        ensure!(
+            batch.distributions_completed == 0 || batch.distributions_finalized,
+            "{} distributions paid out before finalize",
+            batch.distributions_completed
+        );
+        if batch.status == BatchStatus::Completed {
+            ensure!(
+                batch.distributions_completed == batch.order_count,
+                "completed with {} of {} distributions",
+                batch.distributions_completed,
+                batch.order_count
+            );
+        }
+
+        let Some(prev) = &self.last.batches[i] else {
+            ensure!(
+                batch.status == BatchStatus::Open && batch.order_count == 0,
+                "created as {:?} with {} orders",
+                batch.status,
+                batch.order_count
+            );
+            return Ok(());
+        };
+        ensure!(
+            allowed(prev.status, batch.status),
+            "status went from {:?} to {:?}",
+            prev.status,
+            batch.status
+        );
+        ensure!(
+            batch.authority == prev.authority && batch.market_id == prev.market_id,
+            "batch identity changed"
+        );
+        if batch.order_count != prev.order_count {
+            ensure!(
+                prev.order_count.checked_add(1) == Some(batch.order_count),
+                "order_count went from {} to {}",
+                prev.order_count,
+                batch.order_count
+            );
+            ensure!(
+                prev.status == BatchStatus::Open,
+                "order recorded while {:?}",
+                prev.status
+            );
+        }
+        ensure!(
+            batch.distributions_completed >= prev.distributions_completed,
+            "distributions_completed went back"
+        );
+        if batch.total_usdc != prev.total_usdc {
+            ensure!(
+                prev.status == BatchStatus::Open,
+                "total_usdc changed while {:?}",
+                prev.status
+            );
+        }
+        if prev.distribution_root.is_some() {
+            ensure!(
+                batch.distribution_root == prev.distribution_root,
+                "distribution root replaced"
+            );
+        }
+
+        if let (MpcOp::ReconcileBatch { market: target, .. }, Ok(())) = (op, result) {
+            if *target == market {
+                let expected = match prev.status {
+                    BatchStatus::Closed => prev.total_usdc.saturating_sub(prev.parked_usdc),
+                    BatchStatus::Executed => prev.total_usdc - prev.filled_usdc,
+                    status => return Err(format!("reconciled while {status:?}")),
+                };
+                let mismatch = now.escrows[i] != expected;
+                ensure!(
+                    (batch.status == BatchStatus::Frozen) == mismatch,
+                    "escrow {} against {expected} left the batch {:?}",
+                    now.escrows[i],
+                    batch.status
+                );
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,375 @@
+//! privacy_pool operations and the invariants checked after each.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::associated_token::get_associated_token_address;
+use arbitrary::Arbitrary;
+use obsidian_sdk::pool;
+use privacy_pool::{
+    compute_merkle_root, FeeTier, PoolLimits, PoolStats, PrivacyPool, RelayNonce, MAX_FEE_TIERS,
+    MAX_LEAVES, NULLIFIER_PURPOSE_BATCH, NULLIFIER_PURPOSE_SPEND, UNPAUSE_TIMELOCK_SECS,
+    USDC_MINT,
+};
+
+use crate::chain::{Actor, Chain, UNIT};
+use crate::ensure;
+
+#[derive(Arbitrary, Debug)]
+pub enum PoolOp {
+    /// `signer` deposits from `source`'s token account
+    Deposit {
+        signer: Actor,
+        source: Actor,
+        commitment: u8,
+        amount: u16,
+    },
+    AddCommitment {
+        signer: Actor,
+        commitment: u8,
+        /// None uses the signer's next nonce
+        nonce: Option<u8>,
+    },
+    RecordNullifier {
+        signer: Actor,
+        batch_set: bool,
+        nullifier: u8,
+        legacy: Option<u8>,
+        nonce: Option<u8>,
+    },
+    SetLimits {
+        signer: Actor,
+        max_tvl: u32,
+        max_deposit: u16,
+        max_hourly_withdrawal_bps: u16,
+    },
+    SetFeeTiers {
+        signer: Actor,
+        /// (minimum age in minutes, fee bps)
+        tiers: Vec<(u16, u16)>,
+    },
+    RequestUnpause {
+        signer: Actor,
+    },
+    Unpause {
+        signer: Actor,
+    },
+    AttestReserves {
+        signer: Actor,
+        liabilities: u32,
+        note_count: u8,
+    },
+}
+
+impl PoolOp {
+    fn signer(&self) -> Actor {
+        match self {
+            PoolOp::Deposit { signer, .. }
+            | PoolOp::AddCommitment { signer, .. }
+            | PoolOp::RecordNullifier { signer, .. }
+            | PoolOp::SetLimits { signer, .. }
+            | PoolOp::SetFeeTiers { signer, .. }
+            | PoolOp::RequestUnpause { signer }
+            | PoolOp::Unpause { signer }
+            | PoolOp::AttestReserves { signer, .. } => *signer,
+        }
+    }
+
+    /// Only the pool authority may run it
+    fn privileged(&self) -> bool {
+        !matches!(self, PoolOp::Deposit { .. })
+    }
+}
+
+/// A field element: commitments are Poseidon inputs. 255 stands for one
+/// outside the field, which deposits must reject.
+fn commitment(byte: u8) -> [u8; 32] {
+    if byte == u8::MAX {
+        return [u8::MAX; 32];
+    }
+    let mut commitment = [0; 32];
+    commitment[31] = byte;
+    commitment
+}
+
+/// Everything the invariants compare across an operation
+struct Snapshot {
+    pool: PrivacyPool,
+    /// Spent nullifiers, by purpose
+    nullifier_sets: [Vec<[u8; 32]>; 2],
+    limits: PoolLimits,
+    stats: PoolStats,
+    /// Per actor, 0 before their first relay-gated instruction
+    relay_nonces: [u64; 3],
+    vault: u64,
+}
+
+impl Snapshot {
+    fn read(chain: &Chain) -> Self {
+        Self {
+            pool: chain
+                .pool_account(&pool::pool_address())
+                .expect("pool account"),
+            nullifier_sets: [NULLIFIER_PURPOSE_SPEND, NULLIFIER_PURPOSE_BATCH].map(|purpose| {
+                chain
+                    .nullifiers(&pool::nullifier_set_address(purpose))
+                    .expect("nullifier set")
+            }),
+            limits: chain
+                .pool_account(&pool::limits_address())
+                .expect("pool limits"),
+            stats: chain
+                .pool_account(&pool::stats_address())
+                .expect("pool stats"),
+            relay_nonces: Actor::ALL.map(|actor| {
+                chain
+                    .pool_account::<RelayNonce>(&pool::relay_nonce_address(&chain.key(actor)))
+                    .map_or(0, |n| n.nonce)
+            }),
+            vault: chain.usdc_balance(&vault()),
+        }
+    }
+}
+
+fn vault() -> Pubkey {
+    get_associated_token_address(&pool::pool_address(), &USDC_MINT)
+}
+
+/// What the pool should hold, from the operations that succeeded
+pub struct PoolModel {
+    deposits: u64,
+    deposited: u64,
+    relay_commitments: u64,
+    last: Snapshot,
+}
+
+impl PoolModel {
+    pub fn new(chain: &Chain) -> Self {
+        Self {
+            deposits: 0,
+            deposited: 0,
+            relay_commitments: 0,
+            last: Snapshot::read(chain),
+        }
+    }
+
+    pub fn execute(&self, chain: &mut Chain, op: &PoolOp) -> Result<(), String> {
+        let signer = op.signer();
+        let key = chain.key(signer);
+        let next_nonce = |nonce: &Option<u8>| match nonce {
+            Some(nonce) => *nonce as u64,
+            None => self.last.relay_nonces[signer as usize] + 1,
+        };
+        let ix = match op {
+            PoolOp::Deposit {
+                source,
+                commitment: c,
+                amount,
+                ..
+            } => {
+                let mut ix = pool::deposit_instruction(&key, commitment(*c), *amount as u64 * UNIT);
+                let own = chain.usdc(signer);
+                for meta in &mut ix.accounts {
+                    if meta.pubkey == own {
+                        meta.pubkey = chain.usdc(*source);
+                    }
+                }
+                ix
+            }
+            PoolOp::AddCommitment {
+                commitment: c,
+                nonce,
+                ..
+            } => pool::add_commitment_instruction(&key, commitment(*c), next_nonce(nonce)),
+            PoolOp::RecordNullifier {
+                batch_set,
+                nullifier,
+                legacy,
+                nonce,
+                ..
+            } => pool::record_nullifier_instruction(
+                &key,
+                if *batch_set {
+                    NULLIFIER_PURPOSE_BATCH
+                } else {
+                    NULLIFIER_PURPOSE_SPEND
+                },
+                [*nullifier; 32],
+                legacy.map(|l| [l; 32]),
+                next_nonce(nonce),
+            ),
+            PoolOp::SetLimits {
+                max_tvl,
+                max_deposit,
+                max_hourly_withdrawal_bps,
+                ..
+            } => pool::set_limits_instruction(
+                &key,
+                *max_tvl as u64 * UNIT,
+                *max_deposit as u64 * UNIT,
+                *max_hourly_withdrawal_bps,
+            ),
+            PoolOp::SetFeeTiers { tiers, .. } => pool::set_fee_tiers_instruction(
+                &key,
+                tiers
+                    .iter()
+                    .take(MAX_FEE_TIERS + 1)
+                    .map(|(minutes, fee_bps)| FeeTier {
+                        min_age: *minutes as i64 * 60,
+                        fee_bps: *fee_bps,
+                    })
+                    .collect(),
+                &chain.fee_recipient(),
+            ),
+            PoolOp::RequestUnpause { .. } => pool::request_unpause_instruction(&key),
+            PoolOp::Unpause { .. } => pool::unpause_instruction(&key),
+            PoolOp::AttestReserves {
+                liabilities,
+                note_count,
+                ..
+            } => pool::attest_reserves_instruction(
+                &key,
+                [*note_count; 32],
+                *liabilities as u64 * UNIT,
+                *note_count as u32,
+            ),
+        };
+        chain.send(ix, signer)
+    }
+
+    pub fn check(
+        &mut self,
+        chain: &Chain,
+        op: &PoolOp,
+        result: &Result<(), String>,
+    ) -> Result<(), String> {
+        let now = Snapshot::read(chain);
+        let prev = &self.last;
+
+        if op.privileged() && op.signer() != Actor::Relay {
+            ensure!(
+                result.is_err(),
+                "{:?} ran a relay-only instruction",
+                op.signer()
+            );
+        }
+        if result.is_ok() {
+            match op {
+                PoolOp::Deposit {
+                    signer,
+                    source,
+                    amount,
+                    ..
+                } => {
+                    ensure!(signer == source, "deposited from another actor's account");
+                    self.deposits += 1;
+                    self.deposited += *amount as u64 * UNIT;
+                }
+                PoolOp::AddCommitment { .. } => self.relay_commitments += 1,
+                _ => {}
+            }
+        }
+
+        // Merkle tree: append-only, bounded, root always matches the leaves
+        let (before, after) = (prev.pool.next_index as usize, now.pool.next_index as usize);
+        ensure!(
+            after <= MAX_LEAVES,
+            "next_index {after} past {MAX_LEAVES} leaves"
+        );
+        ensure!(after >= before, "next_index went from {before} to {after}");
+        ensure!(
+            now.pool.leaves[..before] == prev.pool.leaves[..before],
+            "an inserted leaf changed"
+        );
+        ensure!(
+            now.pool.leaves[after..].iter().all(|leaf| *leaf == [0; 32]),
+            "a leaf past next_index is set"
+        );
+        if after != before || now.pool.merkle_root != prev.pool.merkle_root {
+            ensure!(
+                now.pool.merkle_root == compute_merkle_root(&now.pool.leaves, after),
+                "merkle_root does not match the leaves"
+            );
+        }
+        ensure!(
+            after as u64 == self.deposits + self.relay_commitments,
+            "{after} leaves for {} deposits and {} relay commitments",
+            self.deposits,
+            self.relay_commitments
+        );
+
+        // Nullifier sets: only grow, bounded, sorted with no nullifier spent twice
+        let sets = prev.nullifier_sets.iter().zip(&now.nullifier_sets);
+        let purposes = [NULLIFIER_PURPOSE_SPEND, NULLIFIER_PURPOSE_BATCH];
+        for (purpose, (prev_set, set)) in purposes.into_iter().zip(sets) {
+            let (before, after) = (prev_set.len(), set.len());
+            ensure!(
+                after <= MAX_LEAVES,
+                "nullifier count {after} past {MAX_LEAVES}"
+            );
+            ensure!(
+                prev_set
+                    .iter()
+                    .all(|nullifier| set.binary_search(nullifier).is_ok()),
+                "a recorded nullifier went missing ({before} before, {after} after)"
+            );
+            ensure!(
+                set.windows(2).all(|pair| pair[0] < pair[1]),
+                "nullifier set {purpose} out of order or spent twice"
+            );
+        }
+
+        for (actor, (before, after)) in prev.relay_nonces.iter().zip(now.relay_nonces).enumerate() {
+            ensure!(after >= *before, "relay nonce of actor {actor} went back");
+        }
+
+        // Escrow conservation: the vault holds exactly what went in minus
+        // what was paid out, and covers every unspent note
+        ensure!(
+            now.stats.deposit_count == self.deposits && now.stats.total_deposited == self.deposited,
+            "stats count {} deposits of {}, expected {} of {}",
+            now.stats.deposit_count,
+            now.stats.total_deposited,
+            self.deposits,
+            self.deposited
+        );
+        ensure!(
+            now.vault as u128 + now.stats.total_withdrawn as u128
+                == now.stats.total_deposited as u128,
+            "vault {} + withdrawn {} != deposited {}",
+            now.vault,
+            now.stats.total_withdrawn,
+            now.stats.total_deposited
+        );
+        ensure!(
+            now.vault >= now.stats.liabilities(),
+            "vault {} below liabilities {}",
+            now.vault,
+            now.stats.liabilities()
+        );
+
+        // Circuit breaker: nothing leaves a paused pool, and only the
+        // timelocked unpause clears it
+        if prev.limits.paused {
+            ensure!(now.vault >= prev.vault, "paid out while paused");
+        }
+        if prev.limits.paused && !now.limits.paused {
+            ensure!(
+                matches!(op, PoolOp::Unpause { .. }) && result.is_ok(),
+                "pause cleared by {op:?}"
+            );
+            ensure!(
+                prev.limits.unpause_requested_at > 0
+                    && chain.now() >= prev.limits.unpause_requested_at + UNPAUSE_TIMELOCK_SECS,
+                "unpaused before the timelock elapsed"
+            );
+        }
+        if !prev.limits.paused && now.limits.paused {
+            ensure!(
+                now.vault == prev.vault,
+                "the tripping withdrawal still paid out"
+            );
+        }
+
+        self.last = now;
+        Ok(())
+    }
+}
@@ -1,33 +1,226 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::program::invoke;
-use anchor_spl::token_interface::spl_token_2022::instruction::transfer_checked;
+use anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+use anchor_lang::solana_program::instruction::{
+    get_stack_height, Instruction, TRANSACTION_LEVEL_STACK_HEIGHT,
+};
+use anchor_lang::solana_program::program::{get_return_data, invoke, invoke_signed};
+use anchor_lang::solana_program::sysvar::instructions::{
+    self as instructions_sysvar, load_current_index_checked, load_instruction_at_checked,
+};
+use anchor_lang::system_program::{self, CreateAccount};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{self, AssociatedToken};
+use anchor_spl::token_2022::{self, InitializeMint2, MintTo, SetAuthority, Token2022};
+use anchor_spl::token_interface::spl_token_2022::extension::ExtensionType;
+use anchor_spl::token_interface::spl_token_2022::instruction::AuthorityType;
+use anchor_spl::token_interface::spl_token_2022::onchain::invoke_transfer_checked;
+use anchor_spl::token_interface::spl_token_2022::state::Mint;
+use anchor_spl::token_interface::{
+    self, non_transferable_mint_initialize, Mint as TokenMint, NonTransferableMintInitialize,
+    TokenAccount, TokenInterface, TransferChecked,
+};
 use ark_bn254::Fr;
 use light_poseidon::{Poseidon, PoseidonBytesHasher};
 
+#[cfg(all(feature = "devnet", feature = "mainnet"))]
+compile_error!("features `devnet` and `mainnet` are mutually exclusive");
+
+#[cfg(not(feature = "mainnet"))]
 declare_id!("AfTSjfnT7M88XipRjPGLgDCcqcVfnrePrtuvNBF74hhP");
 
+// Not deployed to mainnet yet: its declare_id! goes here, with the same ID
+// under [programs.mainnet] in Anchor.toml
+#[cfg(feature = "mainnet")]
+compile_error!("no mainnet program ID yet; add its declare_id! before building for mainnet");
+
 /// Merkle tree depth - supports 2^5 = 32 deposits for demo
 /// For production: use depth 20+ with off-chain storage
+///
+/// Every cluster uses it: the circuits proving membership are compiled
+/// with the same MERKLE_DEPTH, which tests/vectors.rs checks.
 pub const MERKLE_DEPTH: usize = 5;
 
 /// Maximum leaves we can store on-chain (stack size limited)
 /// For production: use off-chain storage with on-chain root, or multiple accounts
 /// For demo: 32 leaves = 32 deposits supported
-pub const MAX_LEAVES: usize = 32;
+pub const MAX_LEAVES: usize = 1 << MERKLE_DEPTH;
+
+/// USDC mint accepted by `deposit` (Circle devnet USDC)
+#[cfg(not(feature = "mainnet"))]
+pub const USDC_MINT: Pubkey = pubkey!("4zMMC9srt5Ri5X14GAgXhaHii3GnPAEERYPJgZJDncDU");
+
+/// USDC mint accepted by `deposit`
+#[cfg(feature = "mainnet")]
+pub const USDC_MINT: Pubkey = pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+
+/// Nullifier purpose tag for spending a pool note (matches the legacy domain separator)
+pub const NULLIFIER_PURPOSE_SPEND: u8 = 1;
+
+/// Nullifiers one grow_nullifier_set call can add: the runtime lets an
+/// account grow by at most MAX_PERMITTED_DATA_INCREASE (10 KiB) per
+/// instruction
+pub const MAX_NULLIFIER_GROWTH: u32 = (MAX_PERMITTED_DATA_INCREASE / 32) as u32;
+
+/// Nullifier purpose tag for notes committed to a batch order
+pub const NULLIFIER_PURPOSE_BATCH: u8 = 2;
+
+/// Withdrawals per `withdraw_many` or `withdraw_aggregated` call (bounded by
+/// transaction size)
+pub const MAX_WITHDRAWALS_PER_CALL: usize = 8;
+
+/// Rolling window for the withdrawal circuit breaker
+pub const WITHDRAWAL_WINDOW_SECS: i64 = 60 * 60;
+
+/// Delay between requesting and applying an unpause of withdrawals
+pub const UNPAUSE_TIMELOCK_SECS: i64 = 24 * 60 * 60;
+
+/// Withdrawal fee tiers in a FeeSchedule
+pub const MAX_FEE_TIERS: usize = 4;
+
+/// Instruction a screening program must implement: sha256("global:screen_deposit")[..8],
+/// then Borsh (depositor: Pubkey, amount: u64). It allows the deposit by
+/// setting return data to [SCREENING_ALLOW].
+pub const SCREEN_DEPOSIT_DISCRIMINATOR: [u8; 8] = [106, 181, 63, 212, 205, 245, 91, 18];
+
+/// Screening return data allowing a deposit
+pub const SCREENING_ALLOW: u8 = 1;
+
+/// Commitment scheme of notes created before leaf versioning:
+/// hash(secret, amount). Still spendable, no longer insertable.
+pub const LEGACY_COMMITMENT_VERSION: u8 = 0;
+
+/// Commitment scheme new leaves must use: hash(1, secret, amount, asset),
+/// asset being the mint with the top byte zeroed (see asset_field). The
+/// version is hashed in, so schemes never collide.
+pub const COMMITMENT_VERSION: u8 = 1;
+
+/// Public inputs of the balance_proof circuit, in circuit order:
+/// merkle_root, nullifier, pool_id, purpose, legacy_nullifier,
+/// new_commitment, order_commitment, note_version
+pub const BALANCE_PROOF_PUBLIC_INPUTS: usize = 8;
+
+/// Public inputs of the aggregated withdrawal proof before the per-withdrawal
+/// block: merkle_root, pool_id
+pub const AGGREGATE_PROOF_HEADER_INPUTS: usize = 2;
+
+/// Public inputs per withdrawal in an aggregated proof:
+/// nullifier, legacy_nullifier, recipient, amount, leaf_bound, reference
+pub const AGGREGATE_PROOF_INPUTS_PER_WITHDRAWAL: usize = 6;
+
+/// Verifier circuit id of single-withdrawal proofs (`zk_verifier::circuit_id`),
+/// the statements an aggregated proof folds. Fixed like
+/// SPLIT_WITHDRAWAL_CIRCUIT_ID.
+pub const WITHDRAWAL_CIRCUIT_ID: [u8; 32] = fixed_circuit_id(b"withdrawal");
+
+/// Recipients one `withdraw_split` can pay
+pub const MAX_SPLIT_OUTPUTS: usize = 4;
+
+/// Public inputs of the split_withdrawal circuit, in circuit order:
+/// merkle_root, pool_id, nullifier, legacy_nullifier, note_version,
+/// leaf_bound, then each output's recipient, then each output's amount
+pub const SPLIT_PROOF_PUBLIC_INPUTS: usize = 6 + 2 * MAX_SPLIT_OUTPUTS;
+
+/// Verifier circuit id of split_withdrawal proofs (`zk_verifier::circuit_id`).
+/// Fixed rather than configured: keys still rotate in the verifier.
+pub const SPLIT_WITHDRAWAL_CIRCUIT_ID: [u8; 32] = fixed_circuit_id(b"split_withdrawal");
+
+/// `zk_verifier::circuit_id`, usable in consts: the name zero-padded
+const fn fixed_circuit_id(name: &[u8]) -> [u8; 32] {
+    let mut id = [0u8; 32];
+    let mut i = 0;
+    while i < name.len() {
+        id[i] = name[i];
+        i += 1;
+    }
+    id
+}
+
+/// Programs a CpiGuard may let call deposit and the withdrawals
+pub const MAX_CPI_CALLERS: usize = 4;
 
 #[program]
 pub mod privacy_pool {
     use super::*;
 
     /// Initialize the privacy pool
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
-        let pool = &mut ctx.accounts.pool;
+    ///
+    /// relay: the only signer accepted on record_nullifier,
+    /// add_commitment and migrate_notes, stored in the pool's PoolRelay and
+    /// rotated with set_relay / accept_relay.
+    pub fn initialize(ctx: Context<Initialize>, relay: Pubkey) -> Result<()> {
+        require!(relay != Pubkey::default(), PoolError::InvalidRelay);
+        let mut pool = ctx.accounts.pool.load_init()?;
         pool.authority = ctx.accounts.authority.key();
         pool.merkle_root = [0u8; 32]; // Empty tree root
         pool.next_index = 0;
         pool.nullifier_count = 0;
 
-        msg!("Privacy pool initialized");
+        let pool_relay = &mut ctx.accounts.pool_relay;
+        pool_relay.pool = ctx.accounts.pool.key();
+        pool_relay.relay = relay;
+        pool_relay.pending_relay = None;
+        pool_relay.updated_at = Clock::get()?.unix_timestamp;
+
+        msg!("Privacy pool initialized: relay={}", relay);
+        Ok(())
+    }
+
+    /// Create the nullifier set for one purpose (spend or batch)
+    ///
+    /// Each purpose gets its own set so a nullifier recorded by the batch
+    /// system can never collide with, or be replayed as, a pool spend.
+    pub fn initialize_nullifier_set(
+        ctx: Context<InitializeNullifierSet>,
+        purpose: u8,
+    ) -> Result<()> {
+        require!(
+            purpose == NULLIFIER_PURPOSE_SPEND || purpose == NULLIFIER_PURPOSE_BATCH,
+            PoolError::InvalidNullifierPurpose
+        );
+
+        *ctx.accounts.nullifiers.load_init()? = NullifierSet::new(ctx.accounts.pool.key(), purpose);
+
+        msg!("Nullifier set initialized: purpose={}", purpose);
+        Ok(())
+    }
+
+    /// Make room for `additional` more nullifiers in a set
+    ///
+    /// A set starts with room for MAX_LEAVES; inserts fail with
+    /// NullifierStorageFull once it is full. The realloc constraint on
+    /// GrowNullifierSet does the growth, paid by whoever signs as payer;
+    /// `additional` must be 1 to MAX_NULLIFIER_GROWTH. The handler only
+    /// sorts sets recorded before nullifiers were kept sorted, so grow each
+    /// existing set once after upgrading.
+    pub fn grow_nullifier_set(
+        ctx: Context<GrowNullifierSet>,
+        purpose: u8,
+        additional: u32,
+    ) -> Result<()> {
+        let set = ctx.accounts.nullifiers.to_account_info();
+        let mut data = set.try_borrow_mut_data()?;
+        let mut nullifiers = NullifierSlots::new(&mut data)?;
+        nullifiers.sort();
+
+        msg!(
+            "Nullifier set grown by {}: purpose={}, capacity={}",
+            additional,
+            purpose,
+            nullifiers.capacity()
+        );
+        Ok(())
+    }
+
+    /// Create the PoolStats account that deposit and withdrawals update
+    ///
+    /// Totals count from here on, so the vault of an existing pool already
+    /// holds more than the recorded liabilities.
+    pub fn initialize_pool_stats(ctx: Context<InitializePoolStats>) -> Result<()> {
+        let stats = &mut ctx.accounts.stats;
+        stats.pool = ctx.accounts.pool.key();
+        stats.updated_at = Clock::get()?.unix_timestamp;
+
+        msg!("Pool stats initialized");
         Ok(())
     }
 
@@ -38,233 +231,2939 @@ pub mod privacy_pool {
     /// - amount: USDC to deposit (this IS visible on-chain)
     ///
     /// The commitment hides the link between deposit and future spends
-    pub fn deposit(
-        ctx: Context<Deposit>,
+    ///
+    /// Optionally pass the receipt accounts to also mint a non-transferable
+    /// Token-2022 receipt. Its mint address encodes only the leaf index, so
+    /// holders can prove participation without revealing the note.
+    ///
+    /// For Token-2022 mints with a transfer hook, pass the hook program, its
+    /// validation account and extra accounts as remaining_accounts. When a
+    /// screening program is set, it and its accounts come first.
+    ///
+    /// The vault must grow by exactly `amount`: a mint that takes a transfer
+    /// fee would leave the note worth more than its backing, so the deposit
+    /// fails with DepositShortfall instead.
+    pub fn deposit<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Deposit<'info>>,
         commitment: [u8; 32],
         amount: u64,
+        version: u8,
+    ) -> Result<()> {
+        require!(ctx.accounts.migration.data_is_empty(), PoolError::PoolMigrating);
+        require!(version == COMMITMENT_VERSION, PoolError::UnsupportedCommitmentVersion);
+        check_cpi_caller(&ctx.accounts.cpi_guard, &ctx.accounts.instructions)?;
+        let pool_key = ctx.accounts.pool.key();
+
+        require!(
+            ctx.accounts.pool.load()?.next_index < MAX_LEAVES as u32,
+            PoolError::TreeFull
+        );
+
+        // Exposure limits (0 = no cap)
+        let limits = &ctx.accounts.limits;
+        require!(
+            limits.max_deposit == 0 || amount <= limits.max_deposit,
+            PoolError::DepositTooLarge
+        );
+        require_keys_eq!(
+            ctx.accounts.pool_usdc.key(),
+            associated_token::get_associated_token_address_with_program_id(
+                &pool_key,
+                &USDC_MINT,
+                &ctx.accounts.token_program.key(),
+            ),
+            PoolError::InvalidPoolVault
+        );
+        let vault_before = token_account_amount(&ctx.accounts.pool_usdc.to_account_info())?;
+        if limits.max_tvl > 0 {
+            require!(
+                vault_before
+                    .checked_add(amount)
+                    .is_some_and(|total| total <= limits.max_tvl),
+                PoolError::TvlCapExceeded
+            );
+        }
+
+        let transfer_accounts = screen_deposit(
+            limits,
+            &ctx.accounts.user.to_account_info(),
+            amount,
+            ctx.remaining_accounts,
+        )?;
+
+        // Transfer USDC from user to pool using transfer_checked CPI.
+        // Resolves transfer-hook extra accounts from remaining_accounts.
+        invoke_transfer_checked(
+            &ctx.accounts.token_program.key(),
+            ctx.accounts.user_usdc.to_account_info(),
+            ctx.accounts.usdc_mint.to_account_info(),
+            ctx.accounts.pool_usdc.to_account_info(),
+            ctx.accounts.user.to_account_info(),
+            transfer_accounts,
+            amount,
+            6, // USDC has 6 decimals
+            &[],
+        )?;
+        let vault_after = token_account_amount(&ctx.accounts.pool_usdc.to_account_info())?;
+        check_deposit_received(vault_before, vault_after, amount)?;
+
+        // Add commitment to tree and recompute the Merkle root
+        let leaf_index = insert_leaf(&ctx.accounts.pool, commitment)?;
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.leaf_times.inserted_at[leaf_index as usize] = now;
+
+        let stats = &mut ctx.accounts.stats;
+        stats.total_deposited = stats
+            .total_deposited
+            .checked_add(amount)
+            .ok_or(PoolError::Overflow)?;
+        stats.deposit_count += 1;
+        stats.updated_at = now;
+        stats.check_solvency(vault_after)?;
+
+        msg!("Deposit: index={}, commitment={:?}", leaf_index, &commitment[..8]);
+
+        if let Some(mint) = mint_deposit_receipt(&ctx, leaf_index)? {
+            emit_cpi!(DepositReceiptEvent { leaf_index, mint });
+        }
+
+        // Emit event for indexers via self-CPI so it survives log truncation
+        emit_cpi!(DepositEvent {
+            leaf_index,
+            commitment,
+            timestamp: now,
+            version,
+        });
+
+        Ok(())
+    }
+
+    /// Return a snapshot of the pool state (view function)
+    ///
+    /// Clients call this through simulateTransaction and decode the
+    /// return data, so no account scanning is required.
+    pub fn get_pool_state(ctx: Context<GetPoolState>) -> Result<PoolStateView> {
+        let pool = ctx.accounts.pool.load()?;
+
+        Ok(PoolStateView {
+            authority: pool.authority,
+            merkle_root: pool.merkle_root,
+            next_index: pool.next_index,
+            nullifier_count: pool.nullifier_count,
+            max_leaves: MAX_LEAVES as u32,
+            pool_id: nullifier_pool_id(&ctx.accounts.pool.key()),
+        })
+    }
+
+    /// Check if a nullifier has been used (view function)
+    pub fn is_nullifier_used(ctx: Context<CheckNullifier>, nullifier: [u8; 32]) -> Result<bool> {
+        let set = ctx.accounts.nullifiers.to_account_info();
+        let mut data = set.try_borrow_mut_data()?;
+        Ok(NullifierSlots::new(&mut data)?.contains(&nullifier))
+    }
+
+    /// Record a nullifier as spent
+    /// Called by the relay after verifying a ZK proof
+    ///
+    /// The proof binds the nullifier to (pool_id, purpose); the set's PDA
+    /// seeds bind it to the same pool and purpose on-chain.
+    ///
+    /// legacy_nullifier: hash(secret, leaf_index, 1) from the proof's public
+    /// inputs. Recording it too means a note already spent under the legacy
+    /// scheme cannot be spent again under the domain-separated one.
+    ///
+    /// relay_nonce: must exceed the relay's last used nonce (see RelayNonce).
+    pub fn record_nullifier(
+        ctx: Context<RecordNullifier>,
+        nullifier: [u8; 32],
+        legacy_nullifier: Option<[u8; 32]>,
+        relay_nonce: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .relay_nonce
+            .advance(ctx.accounts.relay.key(), relay_nonce)?;
+
+        let set = ctx.accounts.nullifiers.to_account_info();
+        let mut data = set.try_borrow_mut_data()?;
+        let mut nullifiers = NullifierSlots::new(&mut data)?;
+        nullifiers.insert(nullifier, legacy_nullifier)?;
+
+        msg!(
+            "Nullifier recorded: purpose={}, nullifier={:?}",
+            nullifiers.purpose(),
+            &nullifier[..8]
+        );
+
+        Ok(())
+    }
+
+    /// Set the pool's exposure limits
+    ///
+    /// A new pool starts with small caps that governance raises as it gains
+    /// confidence; 0 disables a cap. Deposits and withdrawals fail until
+    /// limits exist.
+    ///
+    /// max_hourly_withdrawal_bps: share of TVL that may leave the pool per
+    /// WITHDRAWAL_WINDOW_SECS before withdrawals pause automatically.
+    pub fn set_limits(
+        ctx: Context<SetLimits>,
+        max_tvl: u64,
+        max_deposit: u64,
+        max_hourly_withdrawal_bps: u16,
+    ) -> Result<()> {
+        require!(max_hourly_withdrawal_bps <= 10_000, PoolError::InvalidBps);
+
+        let limits = &mut ctx.accounts.limits;
+        limits.pool = ctx.accounts.pool.key();
+        limits.max_tvl = max_tvl;
+        limits.max_deposit = max_deposit;
+        limits.max_hourly_withdrawal_bps = max_hourly_withdrawal_bps;
+        limits.updated_at = Clock::get()?.unix_timestamp;
+
+        emit_cpi!(LimitsUpdatedEvent {
+            max_tvl,
+            max_deposit,
+            max_hourly_withdrawal_bps,
+        });
+
+        msg!(
+            "Limits set: max_tvl={}, max_deposit={}, max_hourly_withdrawal_bps={}",
+            max_tvl,
+            max_deposit,
+            max_hourly_withdrawal_bps
+        );
+        Ok(())
+    }
+
+    /// Start the timelock for resuming withdrawals after a circuit-breaker trip
+    pub fn request_unpause(ctx: Context<UpdateLimits>) -> Result<()> {
+        let limits = &mut ctx.accounts.limits;
+        require!(limits.paused, PoolError::WithdrawalsNotPaused);
+
+        let now = Clock::get()?.unix_timestamp;
+        limits.unpause_requested_at = now;
+
+        emit_cpi!(UnpauseRequestedEvent {
+            executable_at: now + UNPAUSE_TIMELOCK_SECS,
+        });
+
+        msg!("Unpause requested: executable_at={}", now + UNPAUSE_TIMELOCK_SECS);
+        Ok(())
+    }
+
+    /// Resume withdrawals once UNPAUSE_TIMELOCK_SECS have passed since request_unpause
+    pub fn unpause(ctx: Context<UpdateLimits>) -> Result<()> {
+        let limits = &mut ctx.accounts.limits;
+        require!(limits.paused, PoolError::WithdrawalsNotPaused);
+        require!(limits.unpause_requested_at > 0, PoolError::UnpauseNotRequested);
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now >= limits.unpause_requested_at + UNPAUSE_TIMELOCK_SECS,
+            PoolError::UnpauseTimelocked
+        );
+
+        limits.paused = false;
+        limits.unpause_requested_at = 0;
+        limits.window_start = now;
+        limits.window_volume = 0;
+
+        emit_cpi!(WithdrawalsUnpausedEvent { timestamp: now });
+
+        msg!("Withdrawals unpaused");
+        Ok(())
+    }
+
+    /// Set the program deposits are screened with, or None to stop
+    /// screening
+    ///
+    /// The program receives the depositor and amount (see
+    /// SCREEN_DEPOSIT_DISCRIMINATOR) plus `screening_accounts` accounts of
+    /// its own, which depositors pass at the front of remaining_accounts
+    /// after the program itself. The pool holds no policy of its own.
+    pub fn set_screening_program(
+        ctx: Context<UpdateLimits>,
+        screening_program: Option<Pubkey>,
+        screening_accounts: u8,
+    ) -> Result<()> {
+        let limits = &mut ctx.accounts.limits;
+        limits.screening_program = screening_program.unwrap_or_default();
+        limits.screening_accounts = if screening_program.is_some() {
+            screening_accounts
+        } else {
+            0
+        };
+        limits.updated_at = Clock::get()?.unix_timestamp;
+
+        emit_cpi!(ScreeningUpdatedEvent {
+            screening_program,
+            screening_accounts: limits.screening_accounts,
+        });
+
+        msg!("Screening program: {:?}", screening_program);
+        Ok(())
+    }
+
+    /// Set the withdrawal fee tiers and the token account fees are paid to
+    ///
+    /// A withdrawal pays the fee_bps of the last tier whose min_age its note
+    /// has reached, so tiers must start at age 0, with ages increasing and
+    /// fees not increasing. Also creates the LeafTimes account that deposit
+    /// and add_commitment require; leaves inserted before it count as aged.
+    pub fn set_fee_tiers(
+        ctx: Context<SetFeeTiers>,
+        tiers: Vec<FeeTier>,
+        fee_recipient: Pubkey,
+    ) -> Result<()> {
+        require!(tiers.len() <= MAX_FEE_TIERS, PoolError::InvalidFeeTiers);
+        if let Some(first) = tiers.first() {
+            require!(first.min_age == 0, PoolError::InvalidFeeTiers);
+        }
+        for tier in &tiers {
+            require!(tier.fee_bps <= 10_000, PoolError::InvalidBps);
+        }
+        for pair in tiers.windows(2) {
+            require!(
+                pair[1].min_age > pair[0].min_age && pair[1].fee_bps <= pair[0].fee_bps,
+                PoolError::InvalidFeeTiers
+            );
+        }
+
+        let fees = &mut ctx.accounts.fees;
+        fees.pool = ctx.accounts.pool.key();
+        fees.fee_recipient = fee_recipient;
+        fees.tier_count = tiers.len() as u8;
+        fees.tiers = [FeeTier::default(); MAX_FEE_TIERS];
+        fees.tiers[..tiers.len()].copy_from_slice(&tiers);
+        ctx.accounts.leaf_times.pool = ctx.accounts.pool.key();
+
+        msg!("Fee tiers updated: count={}", tiers.len());
+
+        emit_cpi!(FeeTiersUpdatedEvent {
+            tiers,
+            fee_recipient,
+        });
+
+        Ok(())
+    }
+
+    /// Point proof verification at a verifier program and circuit
+    ///
+    /// The pool never parses proofs itself; swapping proving systems is a
+    /// new verifier, and key rotation happens in the verifier against the
+    /// circuit's active key, so neither needs a pool redeploy.
+    pub fn set_verifier(
+        ctx: Context<SetVerifier>,
+        verifier_program: Pubkey,
+        circuit_id: [u8; 32],
+        aggregate_circuit_id: [u8; 32],
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.verifier_config;
+        config.pool = ctx.accounts.pool.key();
+        config.verifier_program = verifier_program;
+        config.circuit_id = circuit_id;
+        config.aggregate_circuit_id = aggregate_circuit_id;
+
+        emit_cpi!(VerifierUpdatedEvent {
+            verifier_program,
+            circuit_id,
+        });
+
+        msg!("Verifier set: program={}", verifier_program);
+        Ok(())
+    }
+
+    /// Record a nullifier after verifying its balance proof on-chain
+    ///
+    /// Permissionless counterpart to record_nullifier: the proof is checked
+    /// by CPI into the configured verifier, then its public inputs are bound
+    /// to this pool's root, id and the nullifier set's purpose.
+    ///
+    /// The proof's change note (new_commitment) is inserted into the tree in
+    /// the same call, so whoever submits the proof cannot burn the note
+    /// without returning its change. Fails while the tree is frozen for a
+    /// migration.
+    pub fn record_nullifier_with_proof(
+        ctx: Context<RecordNullifierWithProof>,
+        proof: Vec<u8>,
+        public_inputs: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        require!(ctx.accounts.migration.data_is_empty(), PoolError::PoolMigrating);
+        require!(
+            public_inputs.len() == BALANCE_PROOF_PUBLIC_INPUTS,
+            PoolError::InvalidPublicInputs
+        );
+
+        let mut purpose = [0u8; 32];
+        purpose[31] = ctx.accounts.nullifiers.load()?.purpose;
+
+        require!(
+            public_inputs[0] == ctx.accounts.pool.load()?.merkle_root,
+            PoolError::InvalidPublicInputs
+        );
+        require!(
+            public_inputs[2] == nullifier_pool_id(&ctx.accounts.pool.key()),
+            PoolError::InvalidPublicInputs
+        );
+        require!(public_inputs[3] == purpose, PoolError::InvalidPublicInputs);
+        require!(
+            is_known_commitment_version(&public_inputs[7]),
+            PoolError::UnsupportedCommitmentVersion
+        );
+
+        let nullifier = public_inputs[1];
+        let legacy_nullifier = public_inputs[4];
+        let new_commitment = public_inputs[5];
+        invoke_verifier(
+            &ctx.accounts.verifier_program.to_account_info(),
+            &ctx.accounts.verifying_key.to_account_info(),
+            &ctx.accounts.active_key.to_account_info(),
+            public_inputs,
+            proof,
+        )?;
+
+        {
+            let set = ctx.accounts.nullifiers.to_account_info();
+            let mut data = set.try_borrow_mut_data()?;
+            let mut nullifiers = NullifierSlots::new(&mut data)?;
+            nullifiers.insert(nullifier, Some(legacy_nullifier))?;
+
+            msg!(
+                "Nullifier recorded with proof: purpose={}, nullifier={:?}",
+                nullifiers.purpose(),
+                &nullifier[..8]
+            );
+        }
+
+        let leaf_index = insert_leaf(&ctx.accounts.pool, new_commitment)?;
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.leaf_times.inserted_at[leaf_index as usize] = now;
+
+        emit_cpi!(CommitmentAddedEvent {
+            leaf_index,
+            commitment: new_commitment,
+            timestamp: now,
+            version: COMMITMENT_VERSION,
+        });
+
+        Ok(())
+    }
+
+    /// Pay out a batch of withdrawals, each backed by its own proof
+    ///
+    /// proofs[i] is a WITHDRAWAL_CIRCUIT_ID proof for withdrawals[i],
+    /// verified on-chain against the circuit's active key before anything
+    /// is recorded, so no relay signature is needed. Costs one pairing
+    /// check per withdrawal; withdraw_aggregated pays for one.
+    ///
+    /// Public inputs of each proof: see withdrawal_public_inputs, after
+    /// merkle_root and pool_id. Volume, nullifiers, fees and
+    /// remaining_accounts are handled as in withdraw_aggregated.
+    pub fn withdraw_many<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WithdrawMany<'info>>,
+        proofs: Vec<Vec<u8>>,
+        withdrawals: Vec<Withdrawal>,
+    ) -> Result<()> {
+        require!(
+            !withdrawals.is_empty() && withdrawals.len() <= MAX_WITHDRAWALS_PER_CALL,
+            PoolError::InvalidWithdrawalCount
+        );
+        require!(
+            proofs.len() == withdrawals.len(),
+            PoolError::InvalidWithdrawalCount
+        );
+        require!(
+            ctx.remaining_accounts.len() >= withdrawals.len(),
+            PoolError::InvalidWithdrawalCount
+        );
+        check_references(&withdrawals, &ctx.remaining_accounts[withdrawals.len()..])?;
+        check_cpi_caller(&ctx.accounts.cpi_guard, &ctx.accounts.instructions)?;
+
+        let merkle_root = ctx.accounts.pool.load()?.merkle_root;
+        let pool_id = nullifier_pool_id(&ctx.accounts.pool.key());
+        for (proof, withdrawal) in proofs.into_iter().zip(&withdrawals) {
+            let mut public_inputs = Vec::with_capacity(
+                AGGREGATE_PROOF_HEADER_INPUTS + AGGREGATE_PROOF_INPUTS_PER_WITHDRAWAL,
+            );
+            public_inputs.push(merkle_root);
+            public_inputs.push(pool_id);
+            public_inputs.extend(withdrawal_public_inputs(withdrawal));
+
+            invoke_verifier(
+                &ctx.accounts.verifier_program.to_account_info(),
+                &ctx.accounts.verifying_key.to_account_info(),
+                &ctx.accounts.active_key.to_account_info(),
+                public_inputs,
+                proof,
+            )?;
+        }
+
+        if let Some(tripped) = record_withdrawal_volume(
+            &mut ctx.accounts.limits,
+            ctx.accounts.pool_usdc.amount,
+            &withdrawals,
+        )? {
+            emit_cpi!(tripped);
+            return Ok(());
+        }
+
+        {
+            let set = ctx.accounts.nullifiers.to_account_info();
+            let mut data = set.try_borrow_mut_data()?;
+            let mut nullifiers = NullifierSlots::new(&mut data)?;
+            for withdrawal in &withdrawals {
+                nullifiers.insert(withdrawal.nullifier, withdrawal.legacy_nullifier)?;
+            }
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let (events, total_withdrawn) = pay_withdrawals(
+            &ctx.accounts.token_program,
+            &ctx.accounts.pool_usdc,
+            &ctx.accounts.usdc_mint,
+            &ctx.accounts.pool,
+            ctx.bumps.pool,
+            &ctx.accounts.fees,
+            &ctx.accounts.leaf_times,
+            &ctx.accounts.fee_recipient.to_account_info(),
+            &withdrawals,
+            ctx.remaining_accounts,
+            now,
+        )?;
+        for event in events {
+            emit_cpi!(event);
+        }
+
+        ctx.accounts.pool_usdc.reload()?;
+        ctx.accounts
+            .stats
+            .record_withdrawals(total_withdrawn, ctx.accounts.pool_usdc.amount, now)?;
+
+        msg!("Withdrawals paid: count={}", withdrawals.len());
+
+        Ok(())
+    }
+
+    /// Pay out a batch of withdrawals backed by one aggregated proof
+    ///
+    /// The relay folds the individual balance proofs into a single proof
+    /// off-chain; the pool verifies only that, then walks its public inputs.
+    /// One pairing check replaces one per withdrawal, and no trusted relay
+    /// signature is needed. Every nullifier is recorded in the spend set
+    /// before any transfer, so a duplicate anywhere in the batch fails the
+    /// whole call.
+    ///
+    /// Public inputs: merkle_root, pool_id, then per withdrawal
+    /// (nullifier, legacy_nullifier, recipient_field(recipient), amount,
+    /// leaf_bound, recipient_field(reference) or zero). The aggregate key's
+    /// input count fixes the batch size it accepts.
+    ///
+    /// If the batch would push the hour's volume past the circuit breaker,
+    /// withdrawals are paused instead and nothing is paid or recorded. Each
+    /// withdrawal pays the fee tier for its leaf_bound (see FeeSchedule);
+    /// recipients get amount minus fee.
+    ///
+    /// remaining_accounts: each withdrawal's recipient token account, in
+    /// order, then the reference of each withdrawal that has one, in order.
+    pub fn withdraw_aggregated<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WithdrawAggregated<'info>>,
+        proof: Vec<u8>,
+        withdrawals: Vec<Withdrawal>,
+    ) -> Result<()> {
+        require!(
+            !withdrawals.is_empty() && withdrawals.len() <= MAX_WITHDRAWALS_PER_CALL,
+            PoolError::InvalidWithdrawalCount
+        );
+        require!(
+            ctx.remaining_accounts.len() >= withdrawals.len(),
+            PoolError::InvalidWithdrawalCount
+        );
+        check_references(&withdrawals, &ctx.remaining_accounts[withdrawals.len()..])?;
+        check_cpi_caller(&ctx.accounts.cpi_guard, &ctx.accounts.instructions)?;
+
+        let mut public_inputs = Vec::with_capacity(
+            AGGREGATE_PROOF_HEADER_INPUTS
+                + AGGREGATE_PROOF_INPUTS_PER_WITHDRAWAL * withdrawals.len(),
+        );
+        public_inputs.push(ctx.accounts.pool.load()?.merkle_root);
+        public_inputs.push(nullifier_pool_id(&ctx.accounts.pool.key()));
+        for withdrawal in &withdrawals {
+            public_inputs.extend(withdrawal_public_inputs(withdrawal));
+        }
+
+        invoke_verifier(
+            &ctx.accounts.verifier_program.to_account_info(),
+            &ctx.accounts.verifying_key.to_account_info(),
+            &ctx.accounts.active_key.to_account_info(),
+            public_inputs,
+            proof,
+        )?;
+
+        if let Some(tripped) = record_withdrawal_volume(
+            &mut ctx.accounts.limits,
+            ctx.accounts.pool_usdc.amount,
+            &withdrawals,
+        )? {
+            emit_cpi!(tripped);
+            return Ok(());
+        }
+
+        {
+            let set = ctx.accounts.nullifiers.to_account_info();
+            let mut data = set.try_borrow_mut_data()?;
+            let mut nullifiers = NullifierSlots::new(&mut data)?;
+            for withdrawal in &withdrawals {
+                nullifiers.insert(withdrawal.nullifier, withdrawal.legacy_nullifier)?;
+            }
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let (events, total_withdrawn) = pay_withdrawals(
+            &ctx.accounts.token_program,
+            &ctx.accounts.pool_usdc,
+            &ctx.accounts.usdc_mint,
+            &ctx.accounts.pool,
+            ctx.bumps.pool,
+            &ctx.accounts.fees,
+            &ctx.accounts.leaf_times,
+            &ctx.accounts.fee_recipient.to_account_info(),
+            &withdrawals,
+            ctx.remaining_accounts,
+            now,
+        )?;
+        for event in events {
+            emit_cpi!(event);
+        }
+
+        ctx.accounts.pool_usdc.reload()?;
+        ctx.accounts
+            .stats
+            .record_withdrawals(total_withdrawn, ctx.accounts.pool_usdc.amount, now)?;
+
+        msg!("Aggregated withdrawals paid: count={}", withdrawals.len());
+
+        Ok(())
+    }
+
+    /// Pay one note out to up to MAX_SPLIT_OUTPUTS recipients
+    ///
+    /// The split_withdrawal proof shows the spent note holds exactly the
+    /// sum of the outputs, so a payroll or split spends one nullifier in
+    /// one transaction instead of several withdrawals that could be linked
+    /// to each other. The proof is verified on-chain against the active
+    /// key of SPLIT_WITHDRAWAL_CIRCUIT_ID; no relay signature is needed.
+    ///
+    /// Public inputs: see split_public_inputs. Each output pays the fee
+    /// tier for leaf_bound and emits its own WithdrawalEvent; the circuit
+    /// breaker counts the outputs together.
+    ///
+    /// remaining_accounts: each output's recipient token account, in order.
+    pub fn withdraw_split<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WithdrawSplit<'info>>,
+        proof: Vec<u8>,
+        split: SplitWithdrawal,
+    ) -> Result<()> {
+        require!(
+            !split.outputs.is_empty()
+                && split.outputs.len() <= MAX_SPLIT_OUTPUTS
+                && split.outputs.iter().all(|output| output.amount > 0),
+            PoolError::InvalidSplitOutputs
+        );
+        require!(
+            ctx.remaining_accounts.len() == split.outputs.len(),
+            PoolError::InvalidSplitOutputs
+        );
+        require!(
+            split.note_version <= COMMITMENT_VERSION,
+            PoolError::UnsupportedCommitmentVersion
+        );
+        check_cpi_caller(&ctx.accounts.cpi_guard, &ctx.accounts.instructions)?;
+
+        let public_inputs = split_public_inputs(
+            ctx.accounts.pool.load()?.merkle_root,
+            nullifier_pool_id(&ctx.accounts.pool.key()),
+            &split,
+        );
+        invoke_verifier(
+            &ctx.accounts.verifier_program.to_account_info(),
+            &ctx.accounts.verifying_key.to_account_info(),
+            &ctx.accounts.active_key.to_account_info(),
+            public_inputs,
+            proof,
+        )?;
+
+        let withdrawals = split.withdrawals();
+        if let Some(tripped) = record_withdrawal_volume(
+            &mut ctx.accounts.limits,
+            ctx.accounts.pool_usdc.amount,
+            &withdrawals,
+        )? {
+            emit_cpi!(tripped);
+            return Ok(());
+        }
+
+        {
+            let set = ctx.accounts.nullifiers.to_account_info();
+            let mut data = set.try_borrow_mut_data()?;
+            let mut nullifiers = NullifierSlots::new(&mut data)?;
+            nullifiers.insert(split.nullifier, Some(split.legacy_nullifier))?;
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let (events, total_withdrawn) = pay_withdrawals(
+            &ctx.accounts.token_program,
+            &ctx.accounts.pool_usdc,
+            &ctx.accounts.usdc_mint,
+            &ctx.accounts.pool,
+            ctx.bumps.pool,
+            &ctx.accounts.fees,
+            &ctx.accounts.leaf_times,
+            &ctx.accounts.fee_recipient.to_account_info(),
+            &withdrawals,
+            ctx.remaining_accounts,
+            now,
+        )?;
+        for event in events {
+            emit_cpi!(event);
+        }
+
+        ctx.accounts.pool_usdc.reload()?;
+        ctx.accounts
+            .stats
+            .record_withdrawals(total_withdrawn, ctx.accounts.pool_usdc.amount, now)?;
+
+        msg!("Split withdrawal paid: outputs={}", withdrawals.len());
+
+        Ok(())
+    }
+
+    /// Add a new commitment (for change notes after partial spend)
+    ///
+    /// relay_nonce: must exceed the relay's last used nonce (see RelayNonce).
+    /// version: commitment scheme, must be COMMITMENT_VERSION.
+    pub fn add_commitment(
+        ctx: Context<AddCommitment>,
+        commitment: [u8; 32],
+        relay_nonce: u64,
+        version: u8,
+    ) -> Result<()> {
+        require!(ctx.accounts.migration.data_is_empty(), PoolError::PoolMigrating);
+        require!(version == COMMITMENT_VERSION, PoolError::UnsupportedCommitmentVersion);
+        ctx.accounts
+            .relay_nonce
+            .advance(ctx.accounts.relay.key(), relay_nonce)?;
+
+        let leaf_index = insert_leaf(&ctx.accounts.pool, commitment)?;
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.leaf_times.inserted_at[leaf_index as usize] = now;
+
+        msg!("New commitment added: index={}", leaf_index);
+
+        emit_cpi!(CommitmentAddedEvent {
+            leaf_index,
+            commitment,
+            timestamp: now,
+            version,
+        });
+
+        Ok(())
+    }
+
+    /// Publish proof-of-reserve evidence
+    ///
+    /// The indexer builds a Merkle tree over the unspent notes it sees
+    /// (spent notes are only known off-chain, by their nullifiers) and the
+    /// authority posts its root and total. The vault balance, tree state and
+    /// recorded liabilities are read on-chain, so auditors can check the
+    /// total against the vault and users can ask the indexer for an
+    /// inclusion proof of their note against the root.
+    pub fn attest_reserves(
+        ctx: Context<AttestReserves>,
+        liabilities_root: [u8; 32],
+        liabilities: u64,
+        note_count: u32,
     ) -> Result<()> {
-        let pool = &mut ctx.accounts.pool;
+        let pool = ctx.accounts.pool.load()?;
+        require!(note_count <= pool.next_index, PoolError::InvalidAttestation);
+
+        let attestation = &mut ctx.accounts.attestation;
+        attestation.pool = ctx.accounts.pool.key();
+        attestation.liabilities_root = liabilities_root;
+        attestation.liabilities = liabilities;
+        attestation.note_count = note_count;
+        attestation.vault_balance = ctx.accounts.pool_usdc.amount;
+        attestation.recorded_liabilities = ctx.accounts.stats.liabilities();
+        attestation.merkle_root = pool.merkle_root;
+        attestation.next_index = pool.next_index;
+        attestation.attested_at = Clock::get()?.unix_timestamp;
+
+        emit_cpi!(ReservesAttestedEvent {
+            liabilities_root,
+            liabilities,
+            note_count,
+            vault_balance: attestation.vault_balance,
+            recorded_liabilities: attestation.recorded_liabilities,
+            merkle_root: attestation.merkle_root,
+            next_index: attestation.next_index,
+            timestamp: attestation.attested_at,
+        });
 
-        require!(pool.next_index < MAX_LEAVES as u32, PoolError::TreeFull);
+        msg!(
+            "Reserves attested: liabilities={}, vault={}, notes={}",
+            liabilities,
+            attestation.vault_balance,
+            note_count
+        );
+        Ok(())
+    }
+
+    /// Start moving the pool to the v2 tree of the deployment at `new_program`
+    ///
+    /// Freezes the tree: deposit and add_commitment fail from now on, so
+    /// every note is proven against the root recorded here. Notes then move
+    /// with migrate_notes while withdrawals keep working; the migration is
+    /// done once PoolStats::liabilities reaches 0.
+    ///
+    /// The v2 pool must be initialized and have opened an import of this
+    /// migration (see open_import), so moved notes can only land there.
+    pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+        require_keys_neq!(
+            ctx.accounts.new_program.key(),
+            ID,
+            PoolError::InvalidMigrationTarget
+        );
+        require!(
+            ctx.accounts
+                .new_pool
+                .try_borrow_data()?
+                .starts_with(PrivacyPool::DISCRIMINATOR),
+            PoolError::InvalidMigrationTarget
+        );
+        let import =
+            PoolImport::try_deserialize(&mut &ctx.accounts.new_import.try_borrow_data()?[..])?;
+        require_keys_eq!(
+            import.source_migration,
+            ctx.accounts.migration.key(),
+            PoolError::InvalidMigrationTarget
+        );
+        let pool = ctx.accounts.pool.load()?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let new_pool = ctx.accounts.new_pool.key();
+        let migration = &mut ctx.accounts.migration;
+        migration.pool = ctx.accounts.pool.key();
+        migration.new_program = ctx.accounts.new_program.key();
+        migration.new_pool = new_pool;
+        migration.frozen_root = pool.merkle_root;
+        migration.frozen_leaves = pool.next_index;
+        migration.liabilities_at_start = ctx.accounts.stats.liabilities();
+        migration.started_at = now;
+        migration.updated_at = now;
+
+        emit_cpi!(PoolMigrationStartedEvent {
+            new_pool,
+            frozen_root: migration.frozen_root,
+            frozen_leaves: migration.frozen_leaves,
+            liabilities: migration.liabilities_at_start,
+        });
+
+        msg!(
+            "Pool migration started: new_pool={}, leaves={}",
+            new_pool,
+            migration.frozen_leaves
+        );
+        Ok(())
+    }
+
+    /// Move notes of a migrating pool into the v2 tree
+    ///
+    /// Each move is a withdrawal paid to the new pool's vault instead of a
+    /// recipient and without fees. proofs[i] is a WITHDRAWAL_CIRCUIT_ID
+    /// proof of moves[i] against the frozen root (see
+    /// migration_public_inputs), verified on-chain; it binds the
+    /// new_commitment, which the v2 pool's import_notes then inserts in
+    /// the same call.
+    ///
+    /// relay_nonce: must exceed the relay's last used nonce (see RelayNonce).
+    pub fn migrate_notes(
+        ctx: Context<MigrateNotes>,
+        proofs: Vec<Vec<u8>>,
+        moves: Vec<NoteMigration>,
+        relay_nonce: u64,
+    ) -> Result<()> {
+        require!(
+            !moves.is_empty() && moves.len() <= MAX_WITHDRAWALS_PER_CALL,
+            PoolError::InvalidWithdrawalCount
+        );
+        require!(
+            proofs.len() == moves.len(),
+            PoolError::InvalidWithdrawalCount
+        );
+        require!(!ctx.accounts.limits.paused, PoolError::WithdrawalsPaused);
+        ctx.accounts
+            .relay_nonce
+            .advance(ctx.accounts.relay.key(), relay_nonce)?;
+
+        let pool_id = nullifier_pool_id(&ctx.accounts.pool.key());
+        for (proof, note) in proofs.into_iter().zip(&moves) {
+            invoke_verifier(
+                &ctx.accounts.verifier_program.to_account_info(),
+                &ctx.accounts.verifying_key.to_account_info(),
+                &ctx.accounts.active_key.to_account_info(),
+                migration_public_inputs(
+                    &ctx.accounts.migration,
+                    pool_id,
+                    &ctx.accounts.new_pool_usdc.key(),
+                    note,
+                ),
+                proof,
+            )?;
+        }
+
+        let mut total = 0u64;
+        {
+            let set = ctx.accounts.nullifiers.to_account_info();
+            let mut data = set.try_borrow_mut_data()?;
+            let mut nullifiers = NullifierSlots::new(&mut data)?;
+            for note in &moves {
+                nullifiers.insert(note.nullifier, note.legacy_nullifier)?;
+                total = total.checked_add(note.amount).ok_or(PoolError::Overflow)?;
+            }
+        }
+
+        transfer_from_pool(
+            &ctx.accounts.token_program,
+            &ctx.accounts.pool_usdc,
+            &ctx.accounts.usdc_mint,
+            &ctx.accounts.pool,
+            ctx.bumps.pool,
+            &ctx.accounts.new_pool_usdc.to_account_info(),
+            total,
+        )?;
+
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.pool_usdc.reload()?;
+        ctx.accounts
+            .stats
+            .record_withdrawals(total, ctx.accounts.pool_usdc.amount, now)?;
+        ctx.accounts
+            .migration
+            .record(moves.len() as u32, total, now)?;
+        import_into_new_pool(
+            ctx.accounts,
+            ctx.bumps.migration,
+            moves.iter().map(|note| note.new_commitment).collect(),
+            total,
+        )?;
+
+        let migration = &ctx.accounts.migration;
+        for note in &moves {
+            emit_cpi!(NoteMigratedEvent {
+                nullifier: note.nullifier,
+                new_commitment: note.new_commitment,
+                amount: note.amount,
+            });
+        }
+
+        msg!(
+            "Notes migrated: count={}, total_notes={}, total_amount={}",
+            moves.len(),
+            migration.migrated_notes,
+            migration.migrated_amount
+        );
+        Ok(())
+    }
+
+    /// Abandon a migration, unfreezing the tree
+    ///
+    /// Deposits and add_commitment work again. Notes already moved stay in
+    /// the v2 pool; their nullifiers stay spent here.
+    pub fn cancel_migration(ctx: Context<CancelMigration>) -> Result<()> {
+        let migration = &ctx.accounts.migration;
+        emit_cpi!(PoolMigrationCancelledEvent {
+            new_pool: migration.new_pool,
+            migrated_notes: migration.migrated_notes,
+            migrated_amount: migration.migrated_amount,
+        });
+
+        msg!(
+            "Pool migration cancelled: migrated_notes={}",
+            migration.migrated_notes
+        );
+        Ok(())
+    }
+
+    /// Accept notes migrating from the pool of the deployment at
+    /// `source_program`
+    ///
+    /// Run on the v2 pool before the old one calls migrate_pool. Only that
+    /// pool's PoolMigration may then sign import_notes.
+    pub fn open_import(ctx: Context<OpenImport>, source_program: Pubkey) -> Result<()> {
+        require_keys_neq!(source_program, ID, PoolError::InvalidMigrationTarget);
+        let (source_pool, _) = Pubkey::find_program_address(&[b"privacy_pool"], &source_program);
+        let (source_migration, _) =
+            Pubkey::find_program_address(&[b"migration", source_pool.as_ref()], &source_program);
+
+        let now = Clock::get()?.unix_timestamp;
+        let pool_import = &mut ctx.accounts.pool_import;
+        pool_import.pool = ctx.accounts.pool.key();
+        pool_import.source_migration = source_migration;
+        pool_import.opened_at = now;
+        pool_import.updated_at = now;
+
+        msg!("Import opened: source_pool={}", source_pool);
+        Ok(())
+    }
+
+    /// Insert notes moved by the source pool's migrate_notes
+    ///
+    /// Only callable through that CPI, signed by the source PoolMigration,
+    /// which verified each note's proof and has already paid `amount` into
+    /// this pool's vault.
+    pub fn import_notes(
+        ctx: Context<ImportNotes>,
+        commitments: Vec<[u8; 32]>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(
+            !commitments.is_empty() && commitments.len() <= MAX_WITHDRAWALS_PER_CALL,
+            PoolError::InvalidWithdrawalCount
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        for &commitment in &commitments {
+            let leaf_index = insert_leaf(&ctx.accounts.pool, commitment)?;
+            ctx.accounts.leaf_times.inserted_at[leaf_index as usize] = now;
+
+            emit_cpi!(CommitmentAddedEvent {
+                leaf_index,
+                commitment,
+                timestamp: now,
+                version: COMMITMENT_VERSION,
+            });
+        }
+
+        let stats = &mut ctx.accounts.stats;
+        stats.total_deposited = stats
+            .total_deposited
+            .checked_add(amount)
+            .ok_or(PoolError::Overflow)?;
+        stats.updated_at = now;
+        stats.check_solvency(ctx.accounts.pool_usdc.amount)?;
+
+        ctx.accounts
+            .pool_import
+            .record(commitments.len() as u32, amount, now)?;
+
+        msg!("Notes imported: count={}", commitments.len());
+        Ok(())
+    }
+
+    /// Stop accepting notes from the source pool
+    ///
+    /// Finishes an import once the source pool has moved everything (or
+    /// abandons it); migrate_notes fails from then on.
+    pub fn close_import(ctx: Context<CloseImport>) -> Result<()> {
+        msg!(
+            "Import closed: notes={}, amount={}",
+            ctx.accounts.pool_import.imported_notes,
+            ctx.accounts.pool_import.imported_amount
+        );
+        Ok(())
+    }
+
+    /// Propose a new relay signer for record_nullifier, add_commitment and
+    /// migrate_notes
+    ///
+    /// Takes effect once the new key signs accept_relay, so a mistyped key
+    /// cannot lock the relay out. Until the first rotation the pool
+    /// authority is the relay. Proposing again replaces the pending key.
+    pub fn set_relay(ctx: Context<SetRelay>, new_relay: Pubkey) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let pool_relay = &mut ctx.accounts.pool_relay;
+        if pool_relay.pool == Pubkey::default() {
+            pool_relay.pool = ctx.accounts.pool.key();
+            pool_relay.relay = ctx.accounts.pool.load()?.authority;
+        }
+        pool_relay.propose(new_relay, now)?;
+
+        emit_cpi!(RelayProposedEvent {
+            relay: pool_relay.relay,
+            pending_relay: new_relay,
+        });
+
+        msg!("Relay proposed: {}", new_relay);
+        Ok(())
+    }
+
+    /// Complete a relay rotation started by set_relay; signed by the new relay
+    pub fn accept_relay(ctx: Context<AcceptRelay>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let relay = ctx.accounts.new_relay.key();
+        let previous = ctx.accounts.pool_relay.accept(relay, now)?;
+
+        emit_cpi!(RelayRotatedEvent { previous, relay });
+
+        msg!("Relay rotated: {} -> {}", previous, relay);
+        Ok(())
+    }
+
+    /// Require deposit, withdraw_many, withdraw_aggregated and
+    /// withdraw_split to be top-level instructions, or called directly by
+    /// one of `allowed_programs`
+    ///
+    /// Keeps wrapper programs from bundling pool instructions into
+    /// transactions users sign without seeing what else they do. Off until
+    /// first set; `enabled = false` turns it off again.
+    pub fn set_cpi_guard(
+        ctx: Context<SetCpiGuard>,
+        enabled: bool,
+        allowed_programs: Vec<Pubkey>,
+    ) -> Result<()> {
+        require!(
+            allowed_programs.len() <= MAX_CPI_CALLERS,
+            PoolError::TooManyCpiCallers
+        );
+        let guard = &mut ctx.accounts.cpi_guard;
+        guard.pool = ctx.accounts.pool.key();
+        guard.enabled = enabled;
+        guard.program_count = allowed_programs.len() as u8;
+        guard.allowed_programs = [Pubkey::default(); MAX_CPI_CALLERS];
+        guard.allowed_programs[..allowed_programs.len()].copy_from_slice(&allowed_programs);
+
+        emit_cpi!(CpiGuardUpdatedEvent {
+            enabled,
+            allowed_programs,
+        });
+
+        msg!("CPI guard: enabled={}", enabled);
+        Ok(())
+    }
+}
+
+// ============================================
+// ACCOUNTS
+// ============================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PrivacyPool::SIZE,
+        seeds = [b"privacy_pool"],
+        bump
+    )]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PoolRelay::SIZE,
+        seeds = [b"pool_relay", pool.key().as_ref()],
+        bump
+    )]
+    pub pool_relay: Account<'info, PoolRelay>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePoolStats<'info> {
+    #[account(seeds = [b"privacy_pool"], bump, has_one = authority)]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PoolStats::SIZE,
+        seeds = [b"stats", pool.key().as_ref()],
+        bump
+    )]
+    pub stats: Account<'info, PoolStats>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(purpose: u8)]
+pub struct InitializeNullifierSet<'info> {
+    #[account(seeds = [b"privacy_pool"], bump, has_one = authority)]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + NullifierSet::SIZE,
+        seeds = [b"nullifiers", pool.key().as_ref(), &[purpose]],
+        bump
+    )]
+    pub nullifiers: AccountLoader<'info, NullifierSet>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(purpose: u8, additional: u32)]
+pub struct GrowNullifierSet<'info> {
+    /// Checks `additional` here so it is rejected before the realloc below
+    #[account(
+        seeds = [b"privacy_pool"],
+        bump,
+        constraint = additional > 0 && additional <= MAX_NULLIFIER_GROWTH
+            @ PoolError::InvalidNullifierGrowth
+    )]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(
+        mut,
+        seeds = [b"nullifiers", pool.key().as_ref(), &[purpose]],
+        bump,
+        has_one = pool,
+        realloc = nullifiers.to_account_info().data_len() + 32 * additional as usize,
+        realloc::payer = payer,
+        realloc::zero = false
+    )]
+    pub nullifiers: AccountLoader<'info, NullifierSet>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"privacy_pool"],
+        bump
+    )]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: User's USDC token account - validated by token program during transfer
+    #[cfg(not(feature = "strict"))]
+    #[account(mut)]
+    pub user_usdc: UncheckedAccount<'info>,
+
+    #[cfg(feature = "strict")]
+    #[account(
+        mut,
+        token::mint = usdc_mint,
+        token::authority = user,
+        token::token_program = token_program
+    )]
+    pub user_usdc: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Pool's USDC token account - validated by token program during transfer
+    #[cfg(not(feature = "strict"))]
+    #[account(mut)]
+    pub pool_usdc: UncheckedAccount<'info>,
+
+    #[cfg(feature = "strict")]
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program
+    )]
+    pub pool_usdc: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: USDC mint for transfer_checked - pinned to the network's USDC mint
+    #[cfg(not(feature = "strict"))]
+    #[account(address = USDC_MINT)]
+    pub usdc_mint: UncheckedAccount<'info>,
+
+    #[cfg(feature = "strict")]
+    #[account(address = USDC_MINT)]
+    pub usdc_mint: Box<InterfaceAccount<'info, TokenMint>>,
+
+    /// CHECK: Token program for CPI - verified below
+    #[cfg(not(feature = "strict"))]
+    pub token_program: UncheckedAccount<'info>,
+
+    #[cfg(feature = "strict")]
+    pub token_program: Interface<'info, TokenInterface>,
+
+    #[account(seeds = [b"limits", pool.key().as_ref()], bump, has_one = pool)]
+    pub limits: Box<Account<'info, PoolLimits>>,
+
+    #[account(mut, seeds = [b"leaf_times", pool.key().as_ref()], bump, has_one = pool)]
+    pub leaf_times: Box<Account<'info, LeafTimes>>,
+
+    #[account(mut, seeds = [b"stats", pool.key().as_ref()], bump, has_one = pool)]
+    pub stats: Box<Account<'info, PoolStats>>,
+
+    /// CHECK: Must not exist - migrate_pool creating it freezes the tree
+    #[account(seeds = [b"migration", pool.key().as_ref()], bump)]
+    pub migration: UncheckedAccount<'info>,
+
+    /// CHECK: The pool's CpiGuard, if set_cpi_guard has created it (see check_cpi_caller)
+    #[account(seeds = [b"cpi_guard", pool.key().as_ref()], bump)]
+    pub cpi_guard: UncheckedAccount<'info>,
+
+    /// CHECK: The instructions sysvar, checked by address
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    // Optional deposit receipt: pass all five accounts or none.
+
+    /// CHECK: Created in `deposit` as a non-transferable Token-2022 mint
+    #[account(
+        mut,
+        seeds = [b"receipt", pool.key().as_ref(), &pool.load()?.next_index.to_le_bytes()],
+        bump
+    )]
+    pub receipt_mint: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: User's receipt ATA - derivation checked by the associated token program
+    #[account(mut)]
+    pub receipt_account: Option<UncheckedAccount<'info>>,
+
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+
+    pub system_program: Option<Program<'info, System>>,
+}
+
+#[derive(Accounts)]
+pub struct GetPoolState<'info> {
+    #[account(seeds = [b"privacy_pool"], bump)]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+}
+
+#[derive(Accounts)]
+pub struct CheckNullifier<'info> {
+    pub nullifiers: AccountLoader<'info, NullifierSet>,
+}
+
+#[derive(Accounts)]
+pub struct RecordNullifier<'info> {
+    #[account(seeds = [b"privacy_pool"], bump)]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(
+        mut,
+        seeds = [b"nullifiers", pool.key().as_ref(), &[nullifiers.load()?.purpose]],
+        bump,
+        has_one = pool
+    )]
+    pub nullifiers: AccountLoader<'info, NullifierSet>,
+
+    #[account(
+        init_if_needed,
+        payer = relay,
+        space = 8 + RelayNonce::SIZE,
+        seeds = [b"relay_nonce", relay.key().as_ref()],
+        bump
+    )]
+    pub relay_nonce: Box<Account<'info, RelayNonce>>,
+
+    /// Only relay can record nullifiers (after verifying ZK proof)
+    #[account(mut, address = pool_relay_key(&*pool.load()?, &pool_relay) @ PoolError::Unauthorized)]
+    pub relay: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: The pool's PoolRelay; absent on older pools (see pool_relay_key)
+    #[account(seeds = [b"pool_relay", pool.key().as_ref()], bump)]
+    pub pool_relay: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetLimits<'info> {
+    #[account(seeds = [b"privacy_pool"], bump, has_one = authority)]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + PoolLimits::SIZE,
+        seeds = [b"limits", pool.key().as_ref()],
+        bump
+    )]
+    pub limits: Account<'info, PoolLimits>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct UpdateLimits<'info> {
+    #[account(seeds = [b"privacy_pool"], bump, has_one = authority)]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(mut, seeds = [b"limits", pool.key().as_ref()], bump, has_one = pool)]
+    pub limits: Account<'info, PoolLimits>,
+
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetFeeTiers<'info> {
+    #[account(seeds = [b"privacy_pool"], bump, has_one = authority)]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + FeeSchedule::SIZE,
+        seeds = [b"fees", pool.key().as_ref()],
+        bump
+    )]
+    pub fees: Box<Account<'info, FeeSchedule>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + LeafTimes::SIZE,
+        seeds = [b"leaf_times", pool.key().as_ref()],
+        bump
+    )]
+    pub leaf_times: Box<Account<'info, LeafTimes>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetVerifier<'info> {
+    #[account(seeds = [b"privacy_pool"], bump, has_one = authority)]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + VerifierConfig::SIZE,
+        seeds = [b"verifier", pool.key().as_ref()],
+        bump
+    )]
+    pub verifier_config: Account<'info, VerifierConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RecordNullifierWithProof<'info> {
+    #[account(mut, seeds = [b"privacy_pool"], bump)]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(mut, seeds = [b"leaf_times", pool.key().as_ref()], bump, has_one = pool)]
+    pub leaf_times: Box<Account<'info, LeafTimes>>,
+
+    /// CHECK: Must not exist - migrate_pool creating it freezes the tree
+    #[account(seeds = [b"migration", pool.key().as_ref()], bump)]
+    pub migration: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"nullifiers", pool.key().as_ref(), &[nullifiers.load()?.purpose]],
+        bump,
+        has_one = pool
+    )]
+    pub nullifiers: AccountLoader<'info, NullifierSet>,
+
+    #[account(
+        seeds = [b"verifier", pool.key().as_ref()],
+        bump,
+        has_one = pool,
+        has_one = verifier_program
+    )]
+    pub verifier_config: Account<'info, VerifierConfig>,
+
+    /// CHECK: Owned and validated by the verifier program
+    #[cfg(not(feature = "strict"))]
+    pub verifying_key: UncheckedAccount<'info>,
+
+    #[cfg(feature = "strict")]
+    #[account(constraint = verifying_key.finalized @ PoolError::InvalidVerifyingKey)]
+    pub verifying_key: Box<Account<'info, zk_verifier::VerifyingKey>>,
+
+    /// CHECK: The configured circuit's active-key pointer; the verifier
+    /// checks it points at verifying_key
+    #[cfg(not(feature = "strict"))]
+    #[account(
+        seeds = [b"active_vk", verifier_config.circuit_id.as_ref()],
+        bump,
+        seeds::program = verifier_program.key()
+    )]
+    pub active_key: UncheckedAccount<'info>,
+
+    #[cfg(feature = "strict")]
+    #[account(
+        seeds = [b"active_vk", verifier_config.circuit_id.as_ref()],
+        bump,
+        seeds::program = verifier_program.key(),
+        has_one = verifying_key @ PoolError::InvalidVerifyingKey
+    )]
+    pub active_key: Box<Account<'info, zk_verifier::ActiveVerifyingKey>>,
+
+    /// CHECK: Pinned by verifier_config
+    #[cfg(not(feature = "strict"))]
+    #[account(executable)]
+    pub verifier_program: UncheckedAccount<'info>,
+
+    /// Strict builds only verify with the zk_verifier program
+    #[cfg(feature = "strict")]
+    pub verifier_program: Program<'info, zk_verifier::program::ZkVerifier>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawMany<'info> {
+    #[account(seeds = [b"privacy_pool"], bump)]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(
+        mut,
+        seeds = [b"nullifiers", pool.key().as_ref(), &[NULLIFIER_PURPOSE_SPEND]],
+        bump,
+        has_one = pool
+    )]
+    pub nullifiers: AccountLoader<'info, NullifierSet>,
+
+    #[account(
+        seeds = [b"verifier", pool.key().as_ref()],
+        bump,
+        has_one = pool,
+        has_one = verifier_program
+    )]
+    pub verifier_config: Box<Account<'info, VerifierConfig>>,
+
+    /// CHECK: Owned and validated by the verifier program
+    #[cfg(not(feature = "strict"))]
+    pub verifying_key: UncheckedAccount<'info>,
+
+    #[cfg(feature = "strict")]
+    #[account(constraint = verifying_key.finalized @ PoolError::InvalidVerifyingKey)]
+    pub verifying_key: Box<Account<'info, zk_verifier::VerifyingKey>>,
+
+    /// CHECK: The withdrawal circuit's active-key pointer; the verifier
+    /// checks it points at verifying_key
+    #[cfg(not(feature = "strict"))]
+    #[account(
+        seeds = [b"active_vk", WITHDRAWAL_CIRCUIT_ID.as_ref()],
+        bump,
+        seeds::program = verifier_program.key()
+    )]
+    pub active_key: UncheckedAccount<'info>,
+
+    #[cfg(feature = "strict")]
+    #[account(
+        seeds = [b"active_vk", WITHDRAWAL_CIRCUIT_ID.as_ref()],
+        bump,
+        seeds::program = verifier_program.key(),
+        has_one = verifying_key @ PoolError::InvalidVerifyingKey
+    )]
+    pub active_key: Box<Account<'info, zk_verifier::ActiveVerifyingKey>>,
+
+    /// CHECK: Pinned by verifier_config
+    #[cfg(not(feature = "strict"))]
+    #[account(executable)]
+    pub verifier_program: UncheckedAccount<'info>,
+
+    /// Strict builds only verify with the zk_verifier program
+    #[cfg(feature = "strict")]
+    pub verifier_program: Program<'info, zk_verifier::program::ZkVerifier>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program
+    )]
+    pub pool_usdc: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = USDC_MINT)]
+    pub usdc_mint: Box<InterfaceAccount<'info, TokenMint>>,
+
+    #[account(mut, seeds = [b"limits", pool.key().as_ref()], bump, has_one = pool)]
+    pub limits: Box<Account<'info, PoolLimits>>,
+
+    #[account(seeds = [b"fees", pool.key().as_ref()], bump, has_one = pool, has_one = fee_recipient)]
+    pub fees: Box<Account<'info, FeeSchedule>>,
+
+    #[account(seeds = [b"leaf_times", pool.key().as_ref()], bump, has_one = pool)]
+    pub leaf_times: Box<Account<'info, LeafTimes>>,
+
+    #[account(mut, seeds = [b"stats", pool.key().as_ref()], bump, has_one = pool)]
+    pub stats: Box<Account<'info, PoolStats>>,
+
+    /// CHECK: Pinned by fees; validated by the token program during transfer
+    #[cfg(not(feature = "strict"))]
+    #[account(mut)]
+    pub fee_recipient: UncheckedAccount<'info>,
+
+    #[cfg(feature = "strict")]
+    #[account(mut, token::mint = usdc_mint)]
+    pub fee_recipient: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: The pool's CpiGuard, if set_cpi_guard has created it (see check_cpi_caller)
+    #[account(seeds = [b"cpi_guard", pool.key().as_ref()], bump)]
+    pub cpi_guard: UncheckedAccount<'info>,
+
+    /// CHECK: The instructions sysvar, checked by address
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawAggregated<'info> {
+    #[account(seeds = [b"privacy_pool"], bump)]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(
+        mut,
+        seeds = [b"nullifiers", pool.key().as_ref(), &[NULLIFIER_PURPOSE_SPEND]],
+        bump,
+        has_one = pool
+    )]
+    pub nullifiers: AccountLoader<'info, NullifierSet>,
+
+    #[account(
+        seeds = [b"verifier", pool.key().as_ref()],
+        bump,
+        has_one = pool,
+        has_one = verifier_program
+    )]
+    pub verifier_config: Box<Account<'info, VerifierConfig>>,
+
+    /// CHECK: Owned and validated by the verifier program
+    #[cfg(not(feature = "strict"))]
+    pub verifying_key: UncheckedAccount<'info>,
+
+    #[cfg(feature = "strict")]
+    #[account(constraint = verifying_key.finalized @ PoolError::InvalidVerifyingKey)]
+    pub verifying_key: Box<Account<'info, zk_verifier::VerifyingKey>>,
+
+    /// CHECK: The aggregate circuit's active-key pointer; the verifier
+    /// checks it points at verifying_key
+    #[cfg(not(feature = "strict"))]
+    #[account(
+        seeds = [b"active_vk", verifier_config.aggregate_circuit_id.as_ref()],
+        bump,
+        seeds::program = verifier_program.key()
+    )]
+    pub active_key: UncheckedAccount<'info>,
+
+    #[cfg(feature = "strict")]
+    #[account(
+        seeds = [b"active_vk", verifier_config.aggregate_circuit_id.as_ref()],
+        bump,
+        seeds::program = verifier_program.key(),
+        has_one = verifying_key @ PoolError::InvalidVerifyingKey
+    )]
+    pub active_key: Box<Account<'info, zk_verifier::ActiveVerifyingKey>>,
+
+    /// CHECK: Pinned by verifier_config
+    #[cfg(not(feature = "strict"))]
+    #[account(executable)]
+    pub verifier_program: UncheckedAccount<'info>,
+
+    /// Strict builds only verify with the zk_verifier program
+    #[cfg(feature = "strict")]
+    pub verifier_program: Program<'info, zk_verifier::program::ZkVerifier>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program
+    )]
+    pub pool_usdc: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = USDC_MINT)]
+    pub usdc_mint: Box<InterfaceAccount<'info, TokenMint>>,
+
+    #[account(mut, seeds = [b"limits", pool.key().as_ref()], bump, has_one = pool)]
+    pub limits: Box<Account<'info, PoolLimits>>,
+
+    #[account(seeds = [b"fees", pool.key().as_ref()], bump, has_one = pool, has_one = fee_recipient)]
+    pub fees: Box<Account<'info, FeeSchedule>>,
+
+    #[account(seeds = [b"leaf_times", pool.key().as_ref()], bump, has_one = pool)]
+    pub leaf_times: Box<Account<'info, LeafTimes>>,
+
+    #[account(mut, seeds = [b"stats", pool.key().as_ref()], bump, has_one = pool)]
+    pub stats: Box<Account<'info, PoolStats>>,
+
+    /// CHECK: Pinned by fees; validated by the token program during transfer
+    #[cfg(not(feature = "strict"))]
+    #[account(mut)]
+    pub fee_recipient: UncheckedAccount<'info>,
+
+    #[cfg(feature = "strict")]
+    #[account(mut, token::mint = usdc_mint)]
+    pub fee_recipient: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: The pool's CpiGuard, if set_cpi_guard has created it (see check_cpi_caller)
+    #[account(seeds = [b"cpi_guard", pool.key().as_ref()], bump)]
+    pub cpi_guard: UncheckedAccount<'info>,
+
+    /// CHECK: The instructions sysvar, checked by address
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawSplit<'info> {
+    #[account(seeds = [b"privacy_pool"], bump)]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(
+        mut,
+        seeds = [b"nullifiers", pool.key().as_ref(), &[NULLIFIER_PURPOSE_SPEND]],
+        bump,
+        has_one = pool
+    )]
+    pub nullifiers: AccountLoader<'info, NullifierSet>,
+
+    #[account(
+        seeds = [b"verifier", pool.key().as_ref()],
+        bump,
+        has_one = pool,
+        has_one = verifier_program
+    )]
+    pub verifier_config: Box<Account<'info, VerifierConfig>>,
+
+    /// CHECK: Owned and validated by the verifier program
+    #[cfg(not(feature = "strict"))]
+    pub verifying_key: UncheckedAccount<'info>,
+
+    #[cfg(feature = "strict")]
+    #[account(constraint = verifying_key.finalized @ PoolError::InvalidVerifyingKey)]
+    pub verifying_key: Box<Account<'info, zk_verifier::VerifyingKey>>,
+
+    /// CHECK: The split circuit's active-key pointer; the verifier
+    /// checks it points at verifying_key
+    #[cfg(not(feature = "strict"))]
+    #[account(
+        seeds = [b"active_vk", SPLIT_WITHDRAWAL_CIRCUIT_ID.as_ref()],
+        bump,
+        seeds::program = verifier_program.key()
+    )]
+    pub active_key: UncheckedAccount<'info>,
+
+    #[cfg(feature = "strict")]
+    #[account(
+        seeds = [b"active_vk", SPLIT_WITHDRAWAL_CIRCUIT_ID.as_ref()],
+        bump,
+        seeds::program = verifier_program.key(),
+        has_one = verifying_key @ PoolError::InvalidVerifyingKey
+    )]
+    pub active_key: Box<Account<'info, zk_verifier::ActiveVerifyingKey>>,
+
+    /// CHECK: Pinned by verifier_config
+    #[cfg(not(feature = "strict"))]
+    #[account(executable)]
+    pub verifier_program: UncheckedAccount<'info>,
+
+    /// Strict builds only verify with the zk_verifier program
+    #[cfg(feature = "strict")]
+    pub verifier_program: Program<'info, zk_verifier::program::ZkVerifier>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program
+    )]
+    pub pool_usdc: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = USDC_MINT)]
+    pub usdc_mint: Box<InterfaceAccount<'info, TokenMint>>,
+
+    #[account(mut, seeds = [b"limits", pool.key().as_ref()], bump, has_one = pool)]
+    pub limits: Box<Account<'info, PoolLimits>>,
+
+    #[account(seeds = [b"fees", pool.key().as_ref()], bump, has_one = pool, has_one = fee_recipient)]
+    pub fees: Box<Account<'info, FeeSchedule>>,
+
+    #[account(seeds = [b"leaf_times", pool.key().as_ref()], bump, has_one = pool)]
+    pub leaf_times: Box<Account<'info, LeafTimes>>,
+
+    #[account(mut, seeds = [b"stats", pool.key().as_ref()], bump, has_one = pool)]
+    pub stats: Box<Account<'info, PoolStats>>,
+
+    /// CHECK: Pinned by fees; validated by the token program during transfer
+    #[cfg(not(feature = "strict"))]
+    #[account(mut)]
+    pub fee_recipient: UncheckedAccount<'info>,
+
+    #[cfg(feature = "strict")]
+    #[account(mut, token::mint = usdc_mint)]
+    pub fee_recipient: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: The pool's CpiGuard, if set_cpi_guard has created it (see check_cpi_caller)
+    #[account(seeds = [b"cpi_guard", pool.key().as_ref()], bump)]
+    pub cpi_guard: UncheckedAccount<'info>,
+
+    /// CHECK: The instructions sysvar, checked by address
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AddCommitment<'info> {
+    #[account(mut, seeds = [b"privacy_pool"], bump)]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(mut, seeds = [b"leaf_times", pool.key().as_ref()], bump, has_one = pool)]
+    pub leaf_times: Box<Account<'info, LeafTimes>>,
+
+    /// CHECK: Must not exist - migrate_pool creating it freezes the tree
+    #[account(seeds = [b"migration", pool.key().as_ref()], bump)]
+    pub migration: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = relay,
+        space = 8 + RelayNonce::SIZE,
+        seeds = [b"relay_nonce", relay.key().as_ref()],
+        bump
+    )]
+    pub relay_nonce: Box<Account<'info, RelayNonce>>,
+
+    /// Only relay can add commitments (for change notes)
+    #[account(mut, address = pool_relay_key(&*pool.load()?, &pool_relay) @ PoolError::Unauthorized)]
+    pub relay: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: The pool's PoolRelay; absent on older pools (see pool_relay_key)
+    #[account(seeds = [b"pool_relay", pool.key().as_ref()], bump)]
+    pub pool_relay: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AttestReserves<'info> {
+    #[account(seeds = [b"privacy_pool"], bump, has_one = authority)]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(
+        associated_token::mint = usdc_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program
+    )]
+    pub pool_usdc: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = USDC_MINT)]
+    pub usdc_mint: Box<InterfaceAccount<'info, TokenMint>>,
+
+    #[account(seeds = [b"stats", pool.key().as_ref()], bump, has_one = pool)]
+    pub stats: Box<Account<'info, PoolStats>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ReserveAttestation::SIZE,
+        seeds = [b"reserves", pool.key().as_ref()],
+        bump
+    )]
+    pub attestation: Box<Account<'info, ReserveAttestation>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MigratePool<'info> {
+    #[account(seeds = [b"privacy_pool"], bump, has_one = authority)]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(seeds = [b"stats", pool.key().as_ref()], bump, has_one = pool)]
+    pub stats: Box<Account<'info, PoolStats>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PoolMigration::SIZE,
+        seeds = [b"migration", pool.key().as_ref()],
+        bump
+    )]
+    pub migration: Box<Account<'info, PoolMigration>>,
+
+    /// CHECK: The v2 deployment of this program
+    #[account(executable)]
+    pub new_program: UncheckedAccount<'info>,
+
+    /// CHECK: The v2 deployment's pool; checked to be initialized
+    #[account(
+        seeds = [b"privacy_pool"],
+        bump,
+        seeds::program = new_program.key(),
+        owner = new_program.key()
+    )]
+    pub new_pool: UncheckedAccount<'info>,
+
+    /// CHECK: The v2 pool's PoolImport; checked to name migration
+    #[account(
+        seeds = [b"import", new_pool.key().as_ref()],
+        bump,
+        seeds::program = new_program.key(),
+        owner = new_program.key()
+    )]
+    pub new_import: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct MigrateNotes<'info> {
+    #[account(seeds = [b"privacy_pool"], bump)]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(
+        mut,
+        seeds = [b"migration", pool.key().as_ref()],
+        bump,
+        has_one = pool,
+        has_one = new_program,
+        has_one = new_pool
+    )]
+    pub migration: Box<Account<'info, PoolMigration>>,
+
+    #[account(
+        mut,
+        seeds = [b"nullifiers", pool.key().as_ref(), &[NULLIFIER_PURPOSE_SPEND]],
+        bump,
+        has_one = pool
+    )]
+    pub nullifiers: AccountLoader<'info, NullifierSet>,
+
+    #[account(
+        seeds = [b"verifier", pool.key().as_ref()],
+        bump,
+        has_one = pool,
+        has_one = verifier_program
+    )]
+    pub verifier_config: Box<Account<'info, VerifierConfig>>,
+
+    /// CHECK: Owned and validated by the verifier program
+    #[cfg(not(feature = "strict"))]
+    pub verifying_key: UncheckedAccount<'info>,
+
+    #[cfg(feature = "strict")]
+    #[account(constraint = verifying_key.finalized @ PoolError::InvalidVerifyingKey)]
+    pub verifying_key: Box<Account<'info, zk_verifier::VerifyingKey>>,
+
+    /// CHECK: The withdrawal circuit's active-key pointer; the verifier
+    /// checks it points at verifying_key
+    #[cfg(not(feature = "strict"))]
+    #[account(
+        seeds = [b"active_vk", WITHDRAWAL_CIRCUIT_ID.as_ref()],
+        bump,
+        seeds::program = verifier_program.key()
+    )]
+    pub active_key: UncheckedAccount<'info>,
+
+    #[cfg(feature = "strict")]
+    #[account(
+        seeds = [b"active_vk", WITHDRAWAL_CIRCUIT_ID.as_ref()],
+        bump,
+        seeds::program = verifier_program.key(),
+        has_one = verifying_key @ PoolError::InvalidVerifyingKey
+    )]
+    pub active_key: Box<Account<'info, zk_verifier::ActiveVerifyingKey>>,
+
+    /// CHECK: Pinned by verifier_config
+    #[cfg(not(feature = "strict"))]
+    #[account(executable)]
+    pub verifier_program: UncheckedAccount<'info>,
+
+    /// Strict builds only verify with the zk_verifier program
+    #[cfg(feature = "strict")]
+    pub verifier_program: Program<'info, zk_verifier::program::ZkVerifier>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program
+    )]
+    pub pool_usdc: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Pinned by migration
+    pub new_program: UncheckedAccount<'info>,
+
+    /// CHECK: Pinned by migration; new_program's import_notes writes it
+    #[account(mut)]
+    pub new_pool: UncheckedAccount<'info>,
+
+    /// CHECK: new_program's import_notes validates this and the v2
+    /// accounts below
+    #[account(mut)]
+    pub new_import: UncheckedAccount<'info>,
+
+    /// CHECK: See new_import
+    #[account(mut)]
+    pub new_leaf_times: UncheckedAccount<'info>,
+
+    /// CHECK: See new_import
+    #[account(mut)]
+    pub new_stats: UncheckedAccount<'info>,
+
+    /// CHECK: See new_import
+    pub new_event_authority: UncheckedAccount<'info>,
+
+    /// The v2 pool's vault
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = migration.new_pool,
+        associated_token::token_program = token_program
+    )]
+    pub new_pool_usdc: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = USDC_MINT)]
+    pub usdc_mint: Box<InterfaceAccount<'info, TokenMint>>,
+
+    #[account(seeds = [b"limits", pool.key().as_ref()], bump, has_one = pool)]
+    pub limits: Box<Account<'info, PoolLimits>>,
+
+    #[account(mut, seeds = [b"stats", pool.key().as_ref()], bump, has_one = pool)]
+    pub stats: Box<Account<'info, PoolStats>>,
+
+    #[account(
+        init_if_needed,
+        payer = relay,
+        space = 8 + RelayNonce::SIZE,
+        seeds = [b"relay_nonce", relay.key().as_ref()],
+        bump
+    )]
+    pub relay_nonce: Box<Account<'info, RelayNonce>>,
+
+    /// Only relay can migrate notes
+    #[account(mut, address = pool_relay_key(&*pool.load()?, &pool_relay) @ PoolError::Unauthorized)]
+    pub relay: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: The pool's PoolRelay; absent on older pools (see pool_relay_key)
+    #[account(seeds = [b"pool_relay", pool.key().as_ref()], bump)]
+    pub pool_relay: UncheckedAccount<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CancelMigration<'info> {
+    #[account(seeds = [b"privacy_pool"], bump, has_one = authority)]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(
+        mut,
+        seeds = [b"migration", pool.key().as_ref()],
+        bump,
+        has_one = pool,
+        close = authority
+    )]
+    pub migration: Box<Account<'info, PoolMigration>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenImport<'info> {
+    #[account(seeds = [b"privacy_pool"], bump, has_one = authority)]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PoolImport::SIZE,
+        seeds = [b"import", pool.key().as_ref()],
+        bump
+    )]
+    pub pool_import: Box<Account<'info, PoolImport>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ImportNotes<'info> {
+    #[account(mut, seeds = [b"privacy_pool"], bump)]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(
+        mut,
+        seeds = [b"import", pool.key().as_ref()],
+        bump,
+        has_one = pool,
+        has_one = source_migration
+    )]
+    pub pool_import: Box<Account<'info, PoolImport>>,
+
+    #[account(mut, seeds = [b"leaf_times", pool.key().as_ref()], bump, has_one = pool)]
+    pub leaf_times: Box<Account<'info, LeafTimes>>,
+
+    #[account(mut, seeds = [b"stats", pool.key().as_ref()], bump, has_one = pool)]
+    pub stats: Box<Account<'info, PoolStats>>,
+
+    #[account(
+        associated_token::mint = usdc_mint,
+        associated_token::authority = pool,
+        associated_token::token_program = token_program
+    )]
+    pub pool_usdc: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = USDC_MINT)]
+    pub usdc_mint: Box<InterfaceAccount<'info, TokenMint>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// The source pool's PoolMigration, signing through migrate_notes
+    pub source_migration: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseImport<'info> {
+    #[account(seeds = [b"privacy_pool"], bump, has_one = authority)]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(
+        mut,
+        seeds = [b"import", pool.key().as_ref()],
+        bump,
+        has_one = pool,
+        close = authority
+    )]
+    pub pool_import: Box<Account<'info, PoolImport>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetRelay<'info> {
+    #[account(seeds = [b"privacy_pool"], bump, has_one = authority)]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + PoolRelay::SIZE,
+        seeds = [b"pool_relay", pool.key().as_ref()],
+        bump
+    )]
+    pub pool_relay: Account<'info, PoolRelay>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct AcceptRelay<'info> {
+    #[account(seeds = [b"privacy_pool"], bump)]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(mut, seeds = [b"pool_relay", pool.key().as_ref()], bump, has_one = pool)]
+    pub pool_relay: Account<'info, PoolRelay>,
+
+    pub new_relay: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetCpiGuard<'info> {
+    #[account(seeds = [b"privacy_pool"], bump, has_one = authority)]
+    pub pool: AccountLoader<'info, PrivacyPool>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + CpiGuard::SIZE,
+        seeds = [b"cpi_guard", pool.key().as_ref()],
+        bump
+    )]
+    pub cpi_guard: Account<'info, CpiGuard>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// ============================================
+// STATE
+// ============================================
+
+/// Zero-copy: instructions read and write the tree in place instead of
+/// deserializing every leaf onto the stack.
+#[account(zero_copy)]
+pub struct PrivacyPool {
+    pub authority: Pubkey,
+    pub merkle_root: [u8; 32],
+    pub next_index: u32,
+    pub nullifier_count: u32,
+    pub leaves: [[u8; 32]; MAX_LEAVES],
+}
+
+impl PrivacyPool {
+    pub const SIZE: usize = 32 + 32 + 4 + 4 + (32 * MAX_LEAVES);
+}
+
+/// Header of a nullifier set account, one per purpose
+///
+/// Nullifier slots fill the rest of the account; the first `count` hold
+/// the spent nullifiers, sorted, so lookups are a binary search. Access
+/// them through NullifierSlots. The account grows with grow_nullifier_set.
+#[account(zero_copy)]
+pub struct NullifierSet {
+    pub pool: Pubkey,
+    pub purpose: u8,
+    /// Little-endian u32, kept as bytes so the header has no padding
+    count: [u8; 4],
+}
+
+impl NullifierSet {
+    /// Initial size, with room for MAX_LEAVES nullifiers
+    pub const SIZE: usize = std::mem::size_of::<NullifierSet>() + (32 * MAX_LEAVES);
+
+    /// An empty set
+    pub fn new(pool: Pubkey, purpose: u8) -> Self {
+        Self {
+            pool,
+            purpose,
+            count: [0; 4],
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        u32::from_le_bytes(self.count) as usize
+    }
+
+    /// Header and spent nullifiers of a nullifier set account's data
+    /// (discriminator included), or None if it is not one
+    pub fn decode(data: &[u8]) -> Option<(&NullifierSet, &[[u8; 32]])> {
+        let body = data.strip_prefix(Self::DISCRIMINATOR)?;
+        let header_size = std::mem::size_of::<NullifierSet>();
+        let header: &NullifierSet = bytemuck::try_from_bytes(body.get(..header_size)?).ok()?;
+        let recorded = body.get(header_size..header_size + 32 * header.count())?;
+        Some((header, bytemuck::cast_slice(recorded)))
+    }
+}
+
+/// A nullifier set account's header and slots, borrowed from its data
+pub struct NullifierSlots<'a> {
+    header: &'a mut NullifierSet,
+    slots: &'a mut [[u8; 32]],
+}
+
+impl<'a> NullifierSlots<'a> {
+    /// `data`: the account's data, discriminator included
+    pub fn new(data: &'a mut [u8]) -> Result<Self> {
+        let header_end = 8 + std::mem::size_of::<NullifierSet>();
+        require!(
+            data.len() >= header_end,
+            ErrorCode::AccountDidNotDeserialize
+        );
+        let (header, slots) = data[8..].split_at_mut(header_end - 8);
+        let slot_bytes = slots.len() / 32 * 32;
+        Ok(Self {
+            header: bytemuck::from_bytes_mut(header),
+            slots: bytemuck::cast_slice_mut(&mut slots[..slot_bytes]),
+        })
+    }
+
+    pub fn purpose(&self) -> u8 {
+        self.header.purpose
+    }
+
+    pub fn recorded(&self) -> &[[u8; 32]] {
+        &self.slots[..self.header.count()]
+    }
+
+    /// Nullifiers the account has room for
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn contains(&self, nullifier: &[u8; 32]) -> bool {
+        self.recorded().binary_search(nullifier).is_ok()
+    }
+
+    /// Record a nullifier (and its legacy form during migration)
+    /// Fails if either is already in the set, or the set is full
+    pub fn insert(&mut self, nullifier: [u8; 32], legacy_nullifier: Option<[u8; 32]>) -> Result<()> {
+        require!(!self.contains(&nullifier), PoolError::NullifierAlreadyUsed);
+        if let Some(legacy) = legacy_nullifier {
+            require!(
+                legacy != nullifier && !self.contains(&legacy),
+                PoolError::NullifierAlreadyUsed
+            );
+        }
+        let count = self.header.count();
+        let added = 1 + legacy_nullifier.is_some() as usize;
+        require!(
+            count + added <= self.capacity(),
+            PoolError::NullifierStorageFull
+        );
+
+        let mut count = count;
+        for spent in std::iter::once(nullifier).chain(legacy_nullifier) {
+            let at = self.slots[..count].partition_point(|recorded| *recorded < spent);
+            self.slots.copy_within(at..count, at + 1);
+            self.slots[at] = spent;
+            count += 1;
+        }
+        self.header.count = (count as u32).to_le_bytes();
+        Ok(())
+    }
+
+    /// Sort the recorded nullifiers; see grow_nullifier_set
+    pub fn sort(&mut self) {
+        let count = self.header.count();
+        self.slots[..count].sort_unstable();
+    }
+}
+
+/// Last nonce used by a relay key on relay-gated instructions
+///
+/// RPC retries can land a relay's transactions late or twice; requiring a
+/// strictly increasing nonce rejects the stale and replayed copies.
+#[account]
+pub struct RelayNonce {
+    pub relay: Pubkey,
+    pub nonce: u64,
+}
+
+impl RelayNonce {
+    pub const SIZE: usize = 32 + 8;
+
+    /// Accept `nonce` if it is newer than the last one used
+    pub fn advance(&mut self, relay: Pubkey, nonce: u64) -> Result<()> {
+        require!(nonce > self.nonce, PoolError::StaleRelayNonce);
+        self.relay = relay;
+        self.nonce = nonce;
+        Ok(())
+    }
+}
+
+/// Signer of record_nullifier, add_commitment and migrate_notes
+///
+/// Created by initialize; pools initialized before it existed get one from
+/// their first set_relay and use their authority until then. Rotation is
+/// two-step: the authority proposes `pending_relay` and it
+/// becomes `relay` when that key signs accept_relay.
+#[account]
+pub struct PoolRelay {
+    pub pool: Pubkey,
+    pub relay: Pubkey,
+    pub pending_relay: Option<Pubkey>,
+    pub updated_at: i64,
+}
+
+impl PoolRelay {
+    pub const SIZE: usize = 32 + 32 + 33 + 8;
+
+    pub fn propose(&mut self, new_relay: Pubkey, now: i64) -> Result<()> {
+        require!(
+            new_relay != self.relay && new_relay != Pubkey::default(),
+            PoolError::InvalidRelay
+        );
+        self.pending_relay = Some(new_relay);
+        self.updated_at = now;
+        Ok(())
+    }
+
+    /// Make the pending relay current; returns the relay it replaces
+    pub fn accept(&mut self, signer: Pubkey, now: i64) -> Result<Pubkey> {
+        require!(
+            self.pending_relay == Some(signer),
+            PoolError::NotPendingRelay
+        );
+        let previous = std::mem::replace(&mut self.relay, signer);
+        self.pending_relay = None;
+        self.updated_at = now;
+        Ok(previous)
+    }
+}
+
+/// The key allowed to sign as the pool's relay: the PoolRelay's, or the
+/// pool authority for older pools that have none yet
+pub fn pool_relay_key(pool: &PrivacyPool, pool_relay: &AccountInfo) -> Pubkey {
+    if pool_relay.data_is_empty() {
+        return pool.authority;
+    }
+    let data = pool_relay.try_borrow_data();
+    data.ok()
+        .and_then(|data| PoolRelay::try_deserialize(&mut &data[..]).ok())
+        .map_or(Pubkey::default(), |pool_relay| pool_relay.relay)
+}
+
+/// Callers allowed to reach deposit and the withdrawals through CPI
+///
+/// Created by set_cpi_guard; without one, or while disabled, any caller
+/// may.
+#[account]
+pub struct CpiGuard {
+    pub pool: Pubkey,
+    pub enabled: bool,
+    pub program_count: u8,
+    /// Only the first program_count are set
+    pub allowed_programs: [Pubkey; MAX_CPI_CALLERS],
+}
+
+impl CpiGuard {
+    pub const SIZE: usize = 32 + 1 + 1 + (32 * MAX_CPI_CALLERS);
+
+    pub fn allowed_programs(&self) -> &[Pubkey] {
+        &self.allowed_programs[..self.program_count as usize]
+    }
+
+    /// Whether a guarded instruction may run at `stack_height` inside the
+    /// transaction instruction of `top_level`: as that instruction itself,
+    /// or one CPI below an allowed program
+    pub fn allows(&self, top_level: &Pubkey, stack_height: usize) -> bool {
+        !self.enabled
+            || (stack_height <= TRANSACTION_LEVEL_STACK_HEIGHT && *top_level == ID)
+            || (stack_height == TRANSACTION_LEVEL_STACK_HEIGHT + 1
+                && self.allowed_programs().contains(top_level))
+    }
+}
+
+/// Reject calls the pool's CpiGuard doesn't allow; pools without one
+/// accept every caller
+pub fn check_cpi_caller(cpi_guard: &AccountInfo, instructions: &AccountInfo) -> Result<()> {
+    if cpi_guard.data_is_empty() {
+        return Ok(());
+    }
+    let guard = CpiGuard::try_deserialize(&mut &cpi_guard.try_borrow_data()?[..])?;
+    let current = load_current_index_checked(instructions)?;
+    let top_level = load_instruction_at_checked(current as usize, instructions)?.program_id;
+    require!(
+        guard.allows(&top_level, get_stack_height()),
+        PoolError::CpiCallerNotAllowed
+    );
+    Ok(())
+}
+
+/// Exposure limits enforced by `deposit`
+#[account]
+pub struct PoolLimits {
+    pub pool: Pubkey,
+    /// Maximum USDC held by the pool vault (0 = no cap)
+    pub max_tvl: u64,
+    /// Maximum single deposit (0 = no cap)
+    pub max_deposit: u64,
+    pub updated_at: i64,
+    /// Circuit breaker: share of TVL withdrawable per window (0 = off)
+    pub max_hourly_withdrawal_bps: u16,
+    pub window_start: i64,
+    pub window_volume: u64,
+    /// Set when the breaker trips; cleared only by the timelocked unpause
+    pub paused: bool,
+    pub unpause_requested_at: i64,
+    /// Program every deposit is screened with (default = none)
+    pub screening_program: Pubkey,
+    /// Accounts the screening program takes after the depositor
+    pub screening_accounts: u8,
+}
+
+impl PoolLimits {
+    pub const SIZE: usize = 32 + 8 + 8 + 8 + 2 + 8 + 8 + 1 + 8 + 32 + 1;
+}
+
+/// Withdrawal fees by note age
+///
+/// Note age is measured from the insertion of leaf `leaf_bound - 1`, the
+/// newest leaf the note can be, so it never overstates the age and only
+/// reveals that the note is among the first leaf_bound leaves.
+#[account]
+pub struct FeeSchedule {
+    pub pool: Pubkey,
+    /// USDC token account fees are paid to
+    pub fee_recipient: Pubkey,
+    pub tier_count: u8,
+    /// Ascending min_age; only the first tier_count are set
+    pub tiers: [FeeTier; MAX_FEE_TIERS],
+}
+
+impl FeeSchedule {
+    pub const SIZE: usize = 32 + 32 + 1 + (FeeTier::SIZE * MAX_FEE_TIERS);
+
+    /// Fee of the oldest tier `age` has reached (0 with no tiers)
+    pub fn fee_bps(&self, age: i64) -> u16 {
+        self.tiers[..self.tier_count as usize]
+            .iter()
+            .rev()
+            .find(|tier| age >= tier.min_age)
+            .map_or(0, |tier| tier.fee_bps)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct FeeTier {
+    /// Seconds since insertion from which this tier applies
+    pub min_age: i64,
+    pub fee_bps: u16,
+}
 
-        // Transfer USDC from user to pool using transfer_checked CPI
-        let ix = transfer_checked(
-            ctx.accounts.token_program.key,
-            ctx.accounts.user_usdc.key,
-            ctx.accounts.usdc_mint.key,
-            ctx.accounts.pool_usdc.key,
-            ctx.accounts.user.key,
-            &[],
-            amount,
-            6, // USDC has 6 decimals
-        )?;
+impl FeeTier {
+    pub const SIZE: usize = 8 + 2;
+}
 
-        invoke(
-            &ix,
-            &[
-                ctx.accounts.user_usdc.to_account_info(),
-                ctx.accounts.usdc_mint.to_account_info(),
-                ctx.accounts.pool_usdc.to_account_info(),
-                ctx.accounts.user.to_account_info(),
-                ctx.accounts.token_program.to_account_info(),
-            ],
-        )?;
+/// Insertion time of each leaf, for withdrawal fee tiers
+#[account]
+pub struct LeafTimes {
+    pub pool: Pubkey,
+    /// Unix timestamp per leaf index (0 = inserted before tracking began)
+    pub inserted_at: [i64; MAX_LEAVES],
+}
 
-        // Add commitment to tree
-        let leaf_index = pool.next_index;
-        pool.leaves[leaf_index as usize] = commitment;
-        pool.next_index += 1;
+impl LeafTimes {
+    pub const SIZE: usize = 32 + (8 * MAX_LEAVES);
+}
 
-        // Recompute Merkle root
-        pool.merkle_root = compute_merkle_root(&pool.leaves, pool.next_index as usize);
+/// Running deposit and withdrawal totals, checked against the vault
+///
+/// Every note is owed out of the vault, so its balance must cover what
+/// was deposited minus what was withdrawn (fees included); a shortfall
+/// means an accounting bug and fails the instruction that exposed it.
+#[account]
+pub struct PoolStats {
+    pub pool: Pubkey,
+    pub total_deposited: u64,
+    /// Gross withdrawal amounts, fees included
+    pub total_withdrawn: u64,
+    pub deposit_count: u64,
+    pub updated_at: i64,
+}
 
-        msg!("Deposit: index={}, commitment={:?}", leaf_index, &commitment[..8]);
+impl PoolStats {
+    pub const SIZE: usize = 32 + 8 + 8 + 8 + 8;
 
-        // Emit event for indexers
-        emit!(DepositEvent {
-            leaf_index,
-            commitment,
-            timestamp: Clock::get()?.unix_timestamp,
-        });
+    /// USDC the vault owes to unspent notes
+    pub fn liabilities(&self) -> u64 {
+        self.total_deposited.saturating_sub(self.total_withdrawn)
+    }
 
+    pub fn check_solvency(&self, vault_balance: u64) -> Result<()> {
+        require!(
+            vault_balance >= self.liabilities(),
+            PoolError::VaultBelowLiabilities
+        );
         Ok(())
     }
 
-    /// Check if a nullifier has been used (view function)
-    pub fn is_nullifier_used(ctx: Context<CheckNullifier>, nullifier: [u8; 32]) -> Result<bool> {
-        let nullifiers = &ctx.accounts.nullifiers;
+    fn record_withdrawals(&mut self, amount: u64, vault_balance: u64, now: i64) -> Result<()> {
+        self.total_withdrawn = self
+            .total_withdrawn
+            .checked_add(amount)
+            .ok_or(PoolError::Overflow)?;
+        self.updated_at = now;
+        self.check_solvency(vault_balance)
+    }
+}
 
-        for i in 0..nullifiers.count as usize {
-            if nullifiers.data[i] == nullifier {
-                return Ok(true);
-            }
-        }
+/// Progress of moving the pool to a v2 tree; its existence freezes deposits
+#[account]
+pub struct PoolMigration {
+    pub pool: Pubkey,
+    /// The v2 deployment; its pool is new_pool
+    pub new_program: Pubkey,
+    pub new_pool: Pubkey,
+    /// Root every migrating note is proven against
+    pub frozen_root: [u8; 32],
+    pub frozen_leaves: u32,
+    /// PoolStats::liabilities when the migration started
+    pub liabilities_at_start: u64,
+    pub migrated_notes: u32,
+    pub migrated_amount: u64,
+    pub started_at: i64,
+    pub updated_at: i64,
+}
 
-        Ok(false)
-    }
+impl PoolMigration {
+    pub const SIZE: usize = 32 + 32 + 32 + 32 + 4 + 8 + 4 + 8 + 8 + 8;
 
-    /// Record a nullifier as spent
-    /// Called by the relay after verifying a ZK proof
-    pub fn record_nullifier(
-        ctx: Context<RecordNullifier>,
-        nullifier: [u8; 32],
-    ) -> Result<()> {
-        let nullifiers = &mut ctx.accounts.nullifiers;
+    pub fn record(&mut self, notes: u32, amount: u64, now: i64) -> Result<()> {
+        self.migrated_notes = self
+            .migrated_notes
+            .checked_add(notes)
+            .ok_or(PoolError::Overflow)?;
+        self.migrated_amount = self
+            .migrated_amount
+            .checked_add(amount)
+            .ok_or(PoolError::Overflow)?;
+        self.updated_at = now;
+        Ok(())
+    }
 
-        // Check not already used
-        for i in 0..nullifiers.count as usize {
-            require!(nullifiers.data[i] != nullifier, PoolError::NullifierAlreadyUsed);
+    /// Share of the notes outstanding at the start that moved, in bps;
+    /// withdrawals during the migration count as gone too
+    pub fn progress_bps(&self, stats: &PoolStats) -> u16 {
+        if self.liabilities_at_start == 0 {
+            return 10_000;
         }
+        let gone = self.liabilities_at_start.saturating_sub(stats.liabilities());
+        (gone as u128 * 10_000 / self.liabilities_at_start as u128) as u16
+    }
+}
 
-        // Add nullifier
-        let count = nullifiers.count as usize;
-        require!(count < MAX_LEAVES, PoolError::NullifierStorageFull);
-        nullifiers.data[count] = nullifier;
-        nullifiers.count += 1;
+/// A pool migrating into this one; import_notes inserts its moved notes
+#[account]
+pub struct PoolImport {
+    pub pool: Pubkey,
+    /// The source pool's PoolMigration, the only signer import_notes accepts
+    pub source_migration: Pubkey,
+    pub imported_notes: u32,
+    pub imported_amount: u64,
+    pub opened_at: i64,
+    pub updated_at: i64,
+}
 
-        msg!("Nullifier recorded: {:?}", &nullifier[..8]);
+impl PoolImport {
+    pub const SIZE: usize = 32 + 32 + 4 + 8 + 8 + 8;
 
+    pub fn record(&mut self, notes: u32, amount: u64, now: i64) -> Result<()> {
+        self.imported_notes = self
+            .imported_notes
+            .checked_add(notes)
+            .ok_or(PoolError::Overflow)?;
+        self.imported_amount = self
+            .imported_amount
+            .checked_add(amount)
+            .ok_or(PoolError::Overflow)?;
+        self.updated_at = now;
         Ok(())
     }
+}
 
-    /// Add a new commitment (for change notes after partial spend)
-    pub fn add_commitment(
-        ctx: Context<AddCommitment>,
-        commitment: [u8; 32],
-    ) -> Result<()> {
-        let pool = &mut ctx.accounts.pool;
+/// One note moved by `migrate_notes`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct NoteMigration {
+    pub nullifier: [u8; 32],
+    pub legacy_nullifier: Option<[u8; 32]>,
+    pub amount: u64,
+    /// The note's COMMITMENT_VERSION commitment in the v2 tree
+    pub new_commitment: [u8; 32],
+}
+
+/// Latest `attest_reserves`; earlier ones remain in its events
+#[account]
+pub struct ReserveAttestation {
+    pub pool: Pubkey,
+    /// Root of the indexer's tree of unspent notes
+    pub liabilities_root: [u8; 32],
+    /// Sum of the unspent notes under liabilities_root
+    pub liabilities: u64,
+    pub note_count: u32,
+    /// Read from the pool vault when attesting
+    pub vault_balance: u64,
+    /// PoolStats::liabilities when attesting
+    pub recorded_liabilities: u64,
+    /// Deposit tree the indexer's view was taken against
+    pub merkle_root: [u8; 32],
+    pub next_index: u32,
+    pub attested_at: i64,
+}
 
-        require!(pool.next_index < MAX_LEAVES as u32, PoolError::TreeFull);
+impl ReserveAttestation {
+    pub const SIZE: usize = 32 + 32 + 8 + 4 + 8 + 8 + 32 + 4 + 8;
 
-        let leaf_index = pool.next_index;
-        pool.leaves[leaf_index as usize] = commitment;
-        pool.next_index += 1;
+    /// Whether the vault covered the attested liabilities
+    pub fn is_solvent(&self) -> bool {
+        self.vault_balance >= self.liabilities
+    }
+}
 
-        // Recompute Merkle root
-        pool.merkle_root = compute_merkle_root(&pool.leaves, pool.next_index as usize);
+/// Verifier program and circuit used by `record_nullifier_with_proof`
+#[account]
+pub struct VerifierConfig {
+    pub pool: Pubkey,
+    pub verifier_program: Pubkey,
+    /// Zero-padded circuit name; the verifier resolves its active key
+    pub circuit_id: [u8; 32],
+    /// Circuit of the relay's aggregated withdrawal proofs
+    pub aggregate_circuit_id: [u8; 32],
+}
 
-        msg!("New commitment added: index={}", leaf_index);
+impl VerifierConfig {
+    pub const SIZE: usize = 32 + 32 + 32 + 32;
+}
 
-        emit!(CommitmentAddedEvent {
-            leaf_index,
-            commitment,
-            timestamp: Clock::get()?.unix_timestamp,
-        });
+/// One entry of `withdraw_many` or `withdraw_aggregated`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Withdrawal {
+    pub nullifier: [u8; 32],
+    pub legacy_nullifier: Option<[u8; 32]>,
+    /// Recipient USDC token account
+    pub recipient: Pubkey,
+    /// Gross amount; the recipient receives it minus the fee
+    pub amount: u64,
+    /// The spend proof shows the note's leaf index is below this; sets
+    /// the fee tier (see FeeSchedule)
+    pub leaf_bound: u32,
+    /// Solana Pay reference a merchant reconciles the payment by: a fresh
+    /// key per invoice, passed as a read-only account so the transaction
+    /// can be found by it
+    pub reference: Option<Pubkey>,
+}
 
-        Ok(())
+/// One recipient of a `withdraw_split`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SplitOutput {
+    /// Recipient USDC token account
+    pub recipient: Pubkey,
+    /// Gross amount; the recipient receives it minus the fee
+    pub amount: u64,
+}
+
+/// A note spent to several recipients by `withdraw_split`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SplitWithdrawal {
+    pub nullifier: [u8; 32],
+    pub legacy_nullifier: [u8; 32],
+    /// Commitment scheme of the spent note
+    pub note_version: u8,
+    /// As for Withdrawal
+    pub leaf_bound: u32,
+    /// Add up to the note's amount
+    pub outputs: Vec<SplitOutput>,
+}
+
+impl SplitWithdrawal {
+    /// The outputs as withdrawals of the same note, for fees, limits and
+    /// events
+    pub fn withdrawals(&self) -> Vec<Withdrawal> {
+        self.outputs
+            .iter()
+            .map(|output| Withdrawal {
+                nullifier: self.nullifier,
+                legacy_nullifier: Some(self.legacy_nullifier),
+                recipient: output.recipient,
+                amount: output.amount,
+                leaf_bound: self.leaf_bound,
+                reference: None,
+            })
+            .collect()
     }
 }
 
 // ============================================
-// ACCOUNTS
+// EVENTS
 // ============================================
 
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + PrivacyPool::SIZE,
-        seeds = [b"privacy_pool"],
-        bump
-    )]
-    pub pool: Account<'info, PrivacyPool>,
-
-    #[account(mut)]
-    pub authority: Signer<'info>,
+/// A note commitment was appended to the tree by `deposit`.
+#[event]
+pub struct DepositEvent {
+    pub leaf_index: u32,
+    pub commitment: [u8; 32],
+    pub timestamp: i64,
+    /// Commitment scheme of the leaf
+    pub version: u8,
+}
 
-    pub system_program: Program<'info, System>,
+/// A soul-bound receipt `mint` was minted for the deposit at `leaf_index`.
+#[event]
+pub struct DepositReceiptEvent {
+    pub leaf_index: u32,
+    pub mint: Pubkey,
 }
 
-#[derive(Accounts)]
-pub struct Deposit<'info> {
-    #[account(
-        mut,
-        seeds = [b"privacy_pool"],
-        bump
-    )]
-    pub pool: Account<'info, PrivacyPool>,
+/// The deposit screening program was set or cleared.
+#[event]
+pub struct ScreeningUpdatedEvent {
+    pub screening_program: Option<Pubkey>,
+    pub screening_accounts: u8,
+}
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+/// TVL, per-deposit and hourly withdrawal limits changed.
+#[event]
+pub struct LimitsUpdatedEvent {
+    pub max_tvl: u64,
+    pub max_deposit: u64,
+    pub max_hourly_withdrawal_bps: u16,
+}
 
-    /// CHECK: User's USDC token account - validated by token program during transfer
-    #[account(mut)]
-    pub user_usdc: UncheckedAccount<'info>,
+/// A withdrawal would have pushed the hourly volume past its limit and
+/// paused withdrawals.
+#[event]
+pub struct CircuitBreakerTripped {
+    pub window_start: i64,
+    pub window_volume: u64,
+    pub attempted: u64,
+    pub tvl: u64,
+    pub max_hourly_withdrawal_bps: u16,
+}
 
-    /// CHECK: Pool's USDC token account - validated by token program during transfer
-    #[account(mut)]
-    pub pool_usdc: UncheckedAccount<'info>,
+/// Unpausing withdrawals was requested; it can run from `executable_at`.
+#[event]
+pub struct UnpauseRequestedEvent {
+    pub executable_at: i64,
+}
 
-    /// CHECK: USDC mint for transfer_checked - validated by token program
-    pub usdc_mint: UncheckedAccount<'info>,
+/// Withdrawals resumed after the unpause timelock.
+#[event]
+pub struct WithdrawalsUnpausedEvent {
+    pub timestamp: i64,
+}
 
-    /// CHECK: Token program for CPI - verified below
-    pub token_program: UncheckedAccount<'info>,
+/// Proof checks now go to `verifier_program` for `circuit_id`.
+#[event]
+pub struct VerifierUpdatedEvent {
+    pub verifier_program: Pubkey,
+    pub circuit_id: [u8; 32],
 }
 
-#[derive(Accounts)]
-pub struct CheckNullifier<'info> {
-    pub nullifiers: Account<'info, NullifierSet>,
+/// The withdrawal fee schedule was replaced.
+#[event]
+pub struct FeeTiersUpdatedEvent {
+    pub tiers: Vec<FeeTier>,
+    pub fee_recipient: Pubkey,
 }
 
-#[derive(Accounts)]
-pub struct RecordNullifier<'info> {
-    #[account(mut)]
-    pub nullifiers: Account<'info, NullifierSet>,
+/// A nullifier was spent and `amount` less `fee` paid to `recipient`.
+#[event]
+pub struct WithdrawalEvent {
+    pub nullifier: [u8; 32],
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    /// Invoice reference, if the withdrawal pays one
+    pub reference: Option<Pubkey>,
+}
 
-    /// Only relay can record nullifiers (after verifying ZK proof)
-    pub relay: Signer<'info>,
+/// The relay appended a commitment without a deposit, e.g. a change note.
+#[event]
+pub struct CommitmentAddedEvent {
+    pub leaf_index: u32,
+    pub commitment: [u8; 32],
+    pub timestamp: i64,
+    pub version: u8,
 }
 
-#[derive(Accounts)]
-pub struct AddCommitment<'info> {
-    #[account(mut, seeds = [b"privacy_pool"], bump)]
-    pub pool: Account<'info, PrivacyPool>,
+/// Deposits froze at `frozen_root` and migration to `new_pool` began.
+#[event]
+pub struct PoolMigrationStartedEvent {
+    pub new_pool: Pubkey,
+    pub frozen_root: [u8; 32],
+    pub frozen_leaves: u32,
+    pub liabilities: u64,
+}
 
-    /// Only relay can add commitments (for change notes)
-    pub relay: Signer<'info>,
+/// A migration was abandoned and the tree unfrozen.
+#[event]
+pub struct PoolMigrationCancelledEvent {
+    pub new_pool: Pubkey,
+    pub migrated_notes: u32,
+    pub migrated_amount: u64,
 }
 
-// ============================================
-// STATE
-// ============================================
+/// A note was spent here and re-issued in the new pool as `new_commitment`.
+#[event]
+pub struct NoteMigratedEvent {
+    pub nullifier: [u8; 32],
+    pub new_commitment: [u8; 32],
+    pub amount: u64,
+}
 
-#[account]
-pub struct PrivacyPool {
-    pub authority: Pubkey,
+/// A proof-of-reserves snapshot of the vault against note liabilities.
+#[event]
+pub struct ReservesAttestedEvent {
+    pub liabilities_root: [u8; 32],
+    pub liabilities: u64,
+    pub note_count: u32,
+    pub vault_balance: u64,
+    pub recorded_liabilities: u64,
     pub merkle_root: [u8; 32],
     pub next_index: u32,
-    pub nullifier_count: u32,
-    pub leaves: [[u8; 32]; MAX_LEAVES],
+    pub timestamp: i64,
 }
 
-impl PrivacyPool {
-    pub const SIZE: usize = 32 + 32 + 4 + 4 + (32 * MAX_LEAVES);
+/// A new relay was proposed; it takes over once it signs accept_relay.
+#[event]
+pub struct RelayProposedEvent {
+    pub relay: Pubkey,
+    pub pending_relay: Pubkey,
 }
 
-#[account]
-pub struct NullifierSet {
-    pub count: u32,
-    pub data: [[u8; 32]; MAX_LEAVES],
+/// The pool's relay signer changed from `previous` to `relay`.
+#[event]
+pub struct RelayRotatedEvent {
+    pub previous: Pubkey,
+    pub relay: Pubkey,
 }
 
-// ============================================
-// EVENTS
-// ============================================
-
+/// Which callers deposit and the withdrawals accept changed; see
+/// set_cpi_guard.
 #[event]
-pub struct DepositEvent {
-    pub leaf_index: u32,
-    pub commitment: [u8; 32],
-    pub timestamp: i64,
+pub struct CpiGuardUpdatedEvent {
+    pub enabled: bool,
+    pub allowed_programs: Vec<Pubkey>,
 }
 
-#[event]
-pub struct CommitmentAddedEvent {
-    pub leaf_index: u32,
-    pub commitment: [u8; 32],
-    pub timestamp: i64,
+// ============================================
+// VIEWS
+// ============================================
+
+/// Return data for `get_pool_state`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PoolStateView {
+    pub authority: Pubkey,
+    pub merkle_root: [u8; 32],
+    pub next_index: u32,
+    pub nullifier_count: u32,
+    pub max_leaves: u32,
+    /// Pool id public input for nullifier derivation
+    pub pool_id: [u8; 32],
 }
 
 // ============================================
@@ -279,6 +3178,76 @@ pub enum PoolError {
     NullifierAlreadyUsed,
     #[msg("Nullifier storage is full")]
     NullifierStorageFull,
+    #[msg("Invalid nullifier purpose")]
+    InvalidNullifierPurpose,
+    #[msg("Deposit receipt needs all receipt accounts")]
+    IncompleteReceiptAccounts,
+    #[msg("Invalid number of withdrawals or recipient accounts")]
+    InvalidWithdrawalCount,
+    #[msg("Recipient account does not match the withdrawal")]
+    RecipientMismatch,
+    #[msg("Proof public inputs do not match this pool")]
+    InvalidPublicInputs,
+    #[msg("Deposit exceeds the per-deposit limit")]
+    DepositTooLarge,
+    #[msg("Deposit would exceed the pool's TVL cap")]
+    TvlCapExceeded,
+    #[msg("Pool vault is not the pool's USDC token account")]
+    InvalidPoolVault,
+    #[msg("Basis points must be at most 10000")]
+    InvalidBps,
+    #[msg("Withdrawals are paused by the circuit breaker")]
+    WithdrawalsPaused,
+    #[msg("Withdrawals are not paused")]
+    WithdrawalsNotPaused,
+    #[msg("Unpause has not been requested")]
+    UnpauseNotRequested,
+    #[msg("Unpause timelock has not elapsed")]
+    UnpauseTimelocked,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("Relay nonce already used or out of order")]
+    StaleRelayNonce,
+    #[msg("Fee tiers must start at age 0 with rising ages and non-increasing fees")]
+    InvalidFeeTiers,
+    #[msg("Leaf bound must be between 1 and the number of leaves")]
+    InvalidLeafBound,
+    #[msg("Screening program and its accounts must lead remaining_accounts")]
+    MissingScreeningAccounts,
+    #[msg("Screening program does not match the pool's")]
+    InvalidScreeningProgram,
+    #[msg("Deposit rejected by the screening program")]
+    DepositScreened,
+    #[msg("Vault balance is below recorded liabilities")]
+    VaultBelowLiabilities,
+    #[msg("Signer is not the pool's relay authority")]
+    Unauthorized,
+    #[msg("Attestation counts more notes than the tree holds")]
+    InvalidAttestation,
+    #[msg("Pool is migrating; its tree is frozen")]
+    PoolMigrating,
+    #[msg("Migration target must be another deployment's pool importing this one")]
+    InvalidMigrationTarget,
+    #[msg("Commitment scheme version is not supported")]
+    UnsupportedCommitmentVersion,
+    #[msg("Verifying key is not the circuit's finalized active key")]
+    InvalidVerifyingKey,
+    #[msg("Relay must be a new, non-default key")]
+    InvalidRelay,
+    #[msg("Signer is not the pending relay")]
+    NotPendingRelay,
+    #[msg("Instruction was called through a program the CPI guard does not allow")]
+    CpiCallerNotAllowed,
+    #[msg("Too many programs for the CPI guard")]
+    TooManyCpiCallers,
+    #[msg("Reference accounts do not match the withdrawals' references")]
+    ReferenceMismatch,
+    #[msg("Pool vault received less than the deposited amount; fee-bearing mints are not supported")]
+    DepositShortfall,
+    #[msg("A split withdrawal needs 1 to 4 non-zero outputs, each with its recipient account")]
+    InvalidSplitOutputs,
+    #[msg("A nullifier set grows by 1 to MAX_NULLIFIER_GROWTH slots per call")]
+    InvalidNullifierGrowth,
 }
 
 // ============================================
@@ -287,7 +3256,7 @@ pub enum PoolError {
 
 /// Compute Merkle root from leaves
 /// Uses Poseidon hash (must match the Noir circuit!)
-fn compute_merkle_root(leaves: &[[u8; 32]; MAX_LEAVES], count: usize) -> [u8; 32] {
+pub fn compute_merkle_root(leaves: &[[u8; 32]; MAX_LEAVES], count: usize) -> [u8; 32] {
     if count == 0 {
         return [0u8; 32];
     }
@@ -315,6 +3284,550 @@ fn compute_merkle_root(leaves: &[[u8; 32]; MAX_LEAVES], count: usize) -> [u8; 32
     current_level[0]
 }
 
+/// Mint a soul-bound receipt for `leaf_index` if the receipt accounts were
+/// passed to `deposit`. Returns the receipt mint.
+///
+/// The mint is a PDA of the leaf index with the NonTransferable extension,
+/// supply fixed at one and no metadata, so it records nothing else.
+fn mint_deposit_receipt(ctx: &Context<Deposit>, leaf_index: u32) -> Result<Option<Pubkey>> {
+    let accounts = &ctx.accounts;
+    let (mint, receipt_account, token_program, ata_program, system) = match (
+        &accounts.receipt_mint,
+        &accounts.receipt_account,
+        &accounts.token_2022_program,
+        &accounts.associated_token_program,
+        &accounts.system_program,
+    ) {
+        (None, None, None, None, None) => return Ok(None),
+        (Some(m), Some(r), Some(t), Some(a), Some(s)) => (m, r, t, a, s),
+        _ => return err!(PoolError::IncompleteReceiptAccounts),
+    };
+
+    let pool_key = accounts.pool.key();
+    let leaf_bytes = leaf_index.to_le_bytes();
+    let mint_bump = ctx.bumps.receipt_mint.expect("receipt_mint is present");
+    let mint_seeds: &[&[u8]] = &[b"receipt", pool_key.as_ref(), &leaf_bytes, &[mint_bump]];
+    let pool_seeds: &[&[u8]] = &[b"privacy_pool", &[ctx.bumps.pool]];
+
+    let space = ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::NonTransferable])?;
+    system_program::create_account(
+        CpiContext::new_with_signer(
+            system.to_account_info(),
+            CreateAccount {
+                from: accounts.user.to_account_info(),
+                to: mint.to_account_info(),
+            },
+            &[mint_seeds],
+        ),
+        Rent::get()?.minimum_balance(space),
+        space as u64,
+        &token_2022::ID,
+    )?;
+
+    non_transferable_mint_initialize(CpiContext::new(
+        token_program.to_account_info(),
+        NonTransferableMintInitialize {
+            token_program_id: token_program.to_account_info(),
+            mint: mint.to_account_info(),
+        },
+    ))?;
+
+    token_2022::initialize_mint2(
+        CpiContext::new(
+            token_program.to_account_info(),
+            InitializeMint2 {
+                mint: mint.to_account_info(),
+            },
+        ),
+        0,
+        &pool_key,
+        None,
+    )?;
+
+    associated_token::create(CpiContext::new(
+        ata_program.to_account_info(),
+        associated_token::Create {
+            payer: accounts.user.to_account_info(),
+            associated_token: receipt_account.to_account_info(),
+            authority: accounts.user.to_account_info(),
+            mint: mint.to_account_info(),
+            system_program: system.to_account_info(),
+            token_program: token_program.to_account_info(),
+        },
+    ))?;
+
+    token_2022::mint_to(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            MintTo {
+                mint: mint.to_account_info(),
+                to: receipt_account.to_account_info(),
+                authority: accounts.pool.to_account_info(),
+            },
+            &[pool_seeds],
+        ),
+        1,
+    )?;
+
+    // Fix the supply at one
+    token_2022::set_authority(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            SetAuthority {
+                current_authority: accounts.pool.to_account_info(),
+                account_or_mint: mint.to_account_info(),
+            },
+            &[pool_seeds],
+        ),
+        AuthorityType::MintTokens,
+        None,
+    )?;
+
+    Ok(Some(mint.key()))
+}
+
+/// Pool id used as a nullifier domain tag in the balance proof circuit
+/// The top byte is zeroed so the value always fits in a BN254 field element
+pub fn nullifier_pool_id(pool: &Pubkey) -> [u8; 32] {
+    let mut id = pool.to_bytes();
+    id[0] = 0;
+    id
+}
+
+/// Recipient token account as a public input, truncated like nullifier_pool_id
+pub fn recipient_field(recipient: &Pubkey) -> [u8; 32] {
+    let mut field = recipient.to_bytes();
+    field[0] = 0;
+    field
+}
+
+/// Public inputs `withdrawal` contributes to its proof, after merkle_root
+/// and pool_id: nullifier, legacy_nullifier or zero,
+/// recipient_field(recipient), amount, leaf_bound, recipient_field(reference)
+/// or zero. The same block per withdrawal in an aggregated proof.
+pub fn withdrawal_public_inputs(
+    withdrawal: &Withdrawal,
+) -> [[u8; 32]; AGGREGATE_PROOF_INPUTS_PER_WITHDRAWAL] {
+    let mut amount = [0u8; 32];
+    amount[24..].copy_from_slice(&withdrawal.amount.to_be_bytes());
+    let mut leaf_bound = [0u8; 32];
+    leaf_bound[28..].copy_from_slice(&withdrawal.leaf_bound.to_be_bytes());
+
+    [
+        withdrawal.nullifier,
+        withdrawal.legacy_nullifier.unwrap_or_default(),
+        recipient_field(&withdrawal.recipient),
+        amount,
+        leaf_bound,
+        withdrawal.reference.as_ref().map(recipient_field).unwrap_or_default(),
+    ]
+}
+
+/// Public inputs of the proof moving `note` in `migration`: those of a
+/// withdrawal of its amount to the new pool's vault against the frozen
+/// root, with frozen_leaves as leaf_bound and the reference slot holding
+/// new_commitment
+pub fn migration_public_inputs(
+    migration: &PoolMigration,
+    pool_id: [u8; 32],
+    new_pool_usdc: &Pubkey,
+    note: &NoteMigration,
+) -> Vec<[u8; 32]> {
+    let withdrawal = Withdrawal {
+        nullifier: note.nullifier,
+        legacy_nullifier: note.legacy_nullifier,
+        recipient: *new_pool_usdc,
+        amount: note.amount,
+        leaf_bound: migration.frozen_leaves,
+        reference: None,
+    };
+    let mut inputs = vec![migration.frozen_root, pool_id];
+    inputs.extend(withdrawal_public_inputs(&withdrawal));
+    inputs[AGGREGATE_PROOF_HEADER_INPUTS + AGGREGATE_PROOF_INPUTS_PER_WITHDRAWAL - 1] =
+        note.new_commitment;
+    inputs
+}
+
+/// Public inputs of a split_withdrawal proof for `split`: merkle_root,
+/// pool_id, nullifier, legacy_nullifier, note_version, leaf_bound, then
+/// recipient_field(recipient) of each output and then each amount, both
+/// padded with zeros to MAX_SPLIT_OUTPUTS
+pub fn split_public_inputs(
+    merkle_root: [u8; 32],
+    pool_id: [u8; 32],
+    split: &SplitWithdrawal,
+) -> Vec<[u8; 32]> {
+    let mut note_version = [0u8; 32];
+    note_version[31] = split.note_version;
+    let mut leaf_bound = [0u8; 32];
+    leaf_bound[28..].copy_from_slice(&split.leaf_bound.to_be_bytes());
+
+    let mut inputs = Vec::with_capacity(SPLIT_PROOF_PUBLIC_INPUTS);
+    inputs.extend([
+        merkle_root,
+        pool_id,
+        split.nullifier,
+        split.legacy_nullifier,
+        note_version,
+        leaf_bound,
+    ]);
+    for i in 0..MAX_SPLIT_OUTPUTS {
+        let recipient = split.outputs.get(i).map(|output| &output.recipient);
+        inputs.push(recipient.map(recipient_field).unwrap_or_default());
+    }
+    for i in 0..MAX_SPLIT_OUTPUTS {
+        let mut amount = [0u8; 32];
+        if let Some(output) = split.outputs.get(i) {
+            amount[24..].copy_from_slice(&output.amount.to_be_bytes());
+        }
+        inputs.push(amount);
+    }
+    inputs
+}
+
+/// The remaining accounts after the recipients must be the withdrawals'
+/// references, in order, so the transaction is indexed under each
+fn check_references(withdrawals: &[Withdrawal], accounts: &[AccountInfo]) -> Result<()> {
+    let mut references = withdrawals.iter().filter_map(|w| w.reference);
+    for account in accounts {
+        require!(
+            references.next() == Some(account.key()),
+            PoolError::ReferenceMismatch
+        );
+    }
+    require!(references.next().is_none(), PoolError::ReferenceMismatch);
+    Ok(())
+}
+
+/// Asset id hashed into versioned commitments: the mint, truncated like
+/// recipient_field
+pub fn asset_field(mint: &Pubkey) -> [u8; 32] {
+    recipient_field(mint)
+}
+
+/// Whether a note_version public input names a scheme the pool has used
+pub fn is_known_commitment_version(version: &[u8; 32]) -> bool {
+    version[..31].iter().all(|byte| *byte == 0)
+        && (LEGACY_COMMITMENT_VERSION..=COMMITMENT_VERSION).contains(&version[31])
+}
+
+/// Add a withdrawal batch to the rolling window
+///
+/// Returns the trip event, with withdrawals now paused, if the batch would
+/// take the window past max_hourly_withdrawal_bps of `tvl`. The caller must
+/// then stop without paying out so the pause persists.
+fn record_withdrawal_volume(
+    limits: &mut PoolLimits,
+    tvl: u64,
+    withdrawals: &[Withdrawal],
+) -> Result<Option<CircuitBreakerTripped>> {
+    require!(!limits.paused, PoolError::WithdrawalsPaused);
+
+    let attempted = withdrawals
+        .iter()
+        .try_fold(0u64, |total, w| total.checked_add(w.amount))
+        .ok_or(PoolError::Overflow)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    if now - limits.window_start >= WITHDRAWAL_WINDOW_SECS {
+        limits.window_start = now;
+        limits.window_volume = 0;
+    }
+
+    let volume = limits
+        .window_volume
+        .checked_add(attempted)
+        .ok_or(PoolError::Overflow)?;
+    let cap = (tvl as u128 * limits.max_hourly_withdrawal_bps as u128 / 10_000) as u64;
+
+    if limits.max_hourly_withdrawal_bps > 0 && volume > cap {
+        limits.paused = true;
+        limits.unpause_requested_at = 0;
+        msg!("Circuit breaker tripped: volume={}, cap={}", volume, cap);
+        return Ok(Some(CircuitBreakerTripped {
+            window_start: limits.window_start,
+            window_volume: limits.window_volume,
+            attempted,
+            tvl,
+            max_hourly_withdrawal_bps: limits.max_hourly_withdrawal_bps,
+        }));
+    }
+
+    limits.window_volume = volume;
+    Ok(None)
+}
+
+/// Fee owed by `withdrawal` under the pool's fee tiers
+fn withdrawal_fee(
+    fees: &FeeSchedule,
+    leaf_times: &LeafTimes,
+    next_index: u32,
+    withdrawal: &Withdrawal,
+    now: i64,
+) -> Result<u64> {
+    require!(
+        withdrawal.leaf_bound > 0 && withdrawal.leaf_bound <= next_index,
+        PoolError::InvalidLeafBound
+    );
+    let inserted_at = leaf_times.inserted_at[withdrawal.leaf_bound as usize - 1];
+    let fee_bps = fees.fee_bps(now - inserted_at);
+    Ok((withdrawal.amount as u128 * fee_bps as u128 / 10_000) as u64)
+}
+
+/// Pay each withdrawal to its recipient less its fee, then the fees to
+/// `fee_recipient`
+///
+/// Returns the events to emit and the gross amount withdrawn.
+#[allow(clippy::too_many_arguments)]
+fn pay_withdrawals<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    pool_usdc: &InterfaceAccount<'info, TokenAccount>,
+    usdc_mint: &InterfaceAccount<'info, TokenMint>,
+    pool: &AccountLoader<'info, PrivacyPool>,
+    pool_bump: u8,
+    fees: &FeeSchedule,
+    leaf_times: &LeafTimes,
+    fee_recipient: &AccountInfo<'info>,
+    withdrawals: &[Withdrawal],
+    recipients: &[AccountInfo<'info>],
+    now: i64,
+) -> Result<(Vec<WithdrawalEvent>, u64)> {
+    let next_index = pool.load()?.next_index;
+    let mut events = Vec::with_capacity(withdrawals.len());
+    let mut total_fees = 0u64;
+    let mut total_withdrawn = 0u64;
+    for (withdrawal, recipient) in withdrawals.iter().zip(recipients) {
+        require_keys_eq!(recipient.key(), withdrawal.recipient, PoolError::RecipientMismatch);
+
+        let fee = withdrawal_fee(fees, leaf_times, next_index, withdrawal, now)?;
+        total_fees = total_fees.checked_add(fee).ok_or(PoolError::Overflow)?;
+        total_withdrawn = total_withdrawn
+            .checked_add(withdrawal.amount)
+            .ok_or(PoolError::Overflow)?;
+
+        transfer_from_pool(
+            token_program,
+            pool_usdc,
+            usdc_mint,
+            pool,
+            pool_bump,
+            recipient,
+            withdrawal.amount - fee,
+        )?;
+
+        events.push(WithdrawalEvent {
+            nullifier: withdrawal.nullifier,
+            recipient: withdrawal.recipient,
+            amount: withdrawal.amount,
+            fee,
+            reference: withdrawal.reference,
+        });
+    }
+
+    if total_fees > 0 {
+        transfer_from_pool(
+            token_program,
+            pool_usdc,
+            usdc_mint,
+            pool,
+            pool_bump,
+            fee_recipient,
+            total_fees,
+        )?;
+    }
+
+    Ok((events, total_withdrawn))
+}
+
+/// Reject a deposit unless the vault grew by exactly `amount`, e.g. when
+/// the mint withheld a transfer fee
+pub fn check_deposit_received(vault_before: u64, vault_after: u64, amount: u64) -> Result<()> {
+    require!(
+        vault_after.checked_sub(vault_before) == Some(amount),
+        PoolError::DepositShortfall
+    );
+    Ok(())
+}
+
+/// Token balance of an SPL Token or Token-2022 account
+fn token_account_amount(account: &AccountInfo) -> Result<u64> {
+    let data = account.try_borrow_data()?;
+    require!(data.len() >= 72, PoolError::InvalidPoolVault);
+    Ok(u64::from_le_bytes(data[64..72].try_into().unwrap()))
+}
+
+/// Screen a deposit with the pool's screening program, if one is set
+///
+/// Takes the program and its accounts off the front of `remaining` and
+/// returns the rest for the token transfer. The depositor is passed
+/// without its signature, and only return data of [SCREENING_ALLOW] from
+/// the screening program lets the deposit through.
+fn screen_deposit<'a, 'info>(
+    limits: &PoolLimits,
+    user: &AccountInfo<'info>,
+    amount: u64,
+    remaining: &'a [AccountInfo<'info>],
+) -> Result<&'a [AccountInfo<'info>]> {
+    if limits.screening_program == Pubkey::default() {
+        return Ok(remaining);
+    }
+    let count = 1 + limits.screening_accounts as usize;
+    require!(remaining.len() >= count, PoolError::MissingScreeningAccounts);
+    let (screening, rest) = remaining.split_at(count);
+    let (program, accounts) = screening.split_first().unwrap();
+    require_keys_eq!(
+        program.key(),
+        limits.screening_program,
+        PoolError::InvalidScreeningProgram
+    );
+
+    let mut data = SCREEN_DEPOSIT_DISCRIMINATOR.to_vec();
+    (user.key(), amount).serialize(&mut data)?;
+    let ix = Instruction {
+        program_id: program.key(),
+        accounts: std::iter::once(AccountMeta::new_readonly(user.key(), false))
+            .chain(accounts.iter().map(|a| {
+                if a.is_writable {
+                    AccountMeta::new(a.key(), false)
+                } else {
+                    AccountMeta::new_readonly(a.key(), false)
+                }
+            }))
+            .collect(),
+        data,
+    };
+    let mut infos = vec![user.clone()];
+    infos.extend_from_slice(accounts);
+    infos.push(program.clone());
+    invoke(&ix, &infos)?;
+
+    let allowed = matches!(
+        get_return_data(),
+        Some((from, result)) if from == limits.screening_program && result == [SCREENING_ALLOW]
+    );
+    require!(allowed, PoolError::DepositScreened);
+    Ok(rest)
+}
+
+/// CPI into the configured verifier's `verify` instruction
+fn invoke_verifier<'info>(
+    verifier_program: &AccountInfo<'info>,
+    verifying_key: &AccountInfo<'info>,
+    active_key: &AccountInfo<'info>,
+    public_inputs: Vec<[u8; 32]>,
+    proof: Vec<u8>,
+) -> Result<()> {
+    let ix = Instruction {
+        program_id: verifier_program.key(),
+        accounts: vec![
+            AccountMeta::new_readonly(verifying_key.key(), false),
+            AccountMeta::new_readonly(active_key.key(), false),
+        ],
+        data: zk_verifier::instruction::Verify {
+            public_inputs,
+            proof,
+        }
+        .data(),
+    };
+    invoke(
+        &ix,
+        &[
+            verifying_key.clone(),
+            active_key.clone(),
+            verifier_program.clone(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Append `commitment` to the pool's tree and recompute the root; returns
+/// its leaf index
+fn insert_leaf(pool: &AccountLoader<PrivacyPool>, commitment: [u8; 32]) -> Result<u32> {
+    let mut pool = pool.load_mut()?;
+    require!(pool.next_index < MAX_LEAVES as u32, PoolError::TreeFull);
+
+    let leaf_index = pool.next_index;
+    pool.leaves[leaf_index as usize] = commitment;
+    pool.next_index += 1;
+    pool.merkle_root = compute_merkle_root(&pool.leaves, pool.next_index as usize);
+    Ok(leaf_index)
+}
+
+/// Have the v2 pool insert `commitments` through its import_notes, signed
+/// by the PoolMigration
+fn import_into_new_pool(
+    accounts: &MigrateNotes,
+    migration_bump: u8,
+    commitments: Vec<[u8; 32]>,
+    amount: u64,
+) -> Result<()> {
+    let ix = Instruction {
+        program_id: accounts.new_program.key(),
+        accounts: crate::accounts::ImportNotes {
+            pool: accounts.new_pool.key(),
+            pool_import: accounts.new_import.key(),
+            leaf_times: accounts.new_leaf_times.key(),
+            stats: accounts.new_stats.key(),
+            pool_usdc: accounts.new_pool_usdc.key(),
+            usdc_mint: accounts.usdc_mint.key(),
+            token_program: accounts.token_program.key(),
+            source_migration: accounts.migration.key(),
+            event_authority: accounts.new_event_authority.key(),
+            program: accounts.new_program.key(),
+        }
+        .to_account_metas(None),
+        data: crate::instruction::ImportNotes {
+            commitments,
+            amount,
+        }
+        .data(),
+    };
+    let pool = accounts.pool.key();
+    invoke_signed(
+        &ix,
+        &[
+            accounts.new_pool.to_account_info(),
+            accounts.new_import.to_account_info(),
+            accounts.new_leaf_times.to_account_info(),
+            accounts.new_stats.to_account_info(),
+            accounts.new_pool_usdc.to_account_info(),
+            accounts.usdc_mint.to_account_info(),
+            accounts.token_program.to_account_info(),
+            accounts.migration.to_account_info(),
+            accounts.new_event_authority.to_account_info(),
+            accounts.new_program.to_account_info(),
+        ],
+        &[&[b"migration", pool.as_ref(), &[migration_bump]]],
+    )?;
+    Ok(())
+}
+
+/// Transfer USDC out of the pool vault, signed by the pool PDA
+fn transfer_from_pool<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    pool_usdc: &InterfaceAccount<'info, TokenAccount>,
+    usdc_mint: &InterfaceAccount<'info, TokenMint>,
+    pool: &AccountLoader<'info, PrivacyPool>,
+    pool_bump: u8,
+    to: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: pool_usdc.to_account_info(),
+                mint: usdc_mint.to_account_info(),
+                to: to.clone(),
+                authority: pool.to_account_info(),
+            },
+            &[&[b"privacy_pool", &[pool_bump]]],
+        ),
+        amount,
+        usdc_mint.decimals,
+    )
+}
+
 /// Hash two nodes together using Poseidon
 /// Uses light-poseidon with BN254 parameters to match Noir circuit's poseidon::bn254::hash_2
 fn hash_pair(left: [u8; 32], right: [u8; 32]) -> [u8; 32] {
@@ -322,7 +3835,5 @@ fn hash_pair(left: [u8; 32], right: [u8; 32]) -> [u8; 32] {
     let mut poseidon = Poseidon::<Fr>::new_circom(2).expect("poseidon init");
 
     // Convert bytes to field elements and hash
-    let result = poseidon.hash_bytes_be(&[&left, &right]).expect("poseidon hash");
-
-    result
+    poseidon.hash_bytes_be(&[&left, &right]).expect("poseidon hash")
 }
@@ -0,0 +1,48 @@
+use anchor_lang::prelude::Pubkey;
+use privacy_pool::{CpiGuard, MAX_CPI_CALLERS};
+
+fn guard(enabled: bool, allowed: &[Pubkey]) -> CpiGuard {
+    let mut allowed_programs = [Pubkey::default(); MAX_CPI_CALLERS];
+    allowed_programs[..allowed.len()].copy_from_slice(allowed);
+    CpiGuard {
+        pool: Pubkey::new_unique(),
+        enabled,
+        program_count: allowed.len() as u8,
+        allowed_programs,
+    }
+}
+
+#[test]
+fn top_level_calls_always_pass() {
+    let guard = guard(true, &[]);
+    assert!(guard.allows(&privacy_pool::ID, 1));
+    // Host builds report a stack height of 0
+    assert!(guard.allows(&privacy_pool::ID, 0));
+}
+
+#[test]
+fn only_allowed_programs_may_call_directly() {
+    let wrapper = Pubkey::new_unique();
+    let allowed = Pubkey::new_unique();
+    let guard = guard(true, &[allowed]);
+
+    assert!(guard.allows(&allowed, 2));
+    assert!(!guard.allows(&wrapper, 2));
+    // An allowed program must not relay another program's call
+    assert!(!guard.allows(&allowed, 3));
+    // Nor may the pool be reached through itself at depth
+    assert!(!guard.allows(&privacy_pool::ID, 2));
+}
+
+#[test]
+fn disabled_guard_allows_any_caller() {
+    let wrapper = Pubkey::new_unique();
+    assert!(guard(false, &[]).allows(&wrapper, 4));
+}
+
+#[test]
+fn unset_slots_are_not_allowed() {
+    let guard = guard(true, &[Pubkey::new_unique()]);
+    assert_eq!(guard.allowed_programs().len(), 1);
+    assert!(!guard.allows(&Pubkey::default(), 2));
+}
@@ -0,0 +1,15 @@
+use anchor_lang::error::Error;
+use privacy_pool::{check_deposit_received, PoolError};
+
+#[test]
+fn deposits_must_arrive_in_full() {
+    assert!(check_deposit_received(4_000_000, 5_000_000, 1_000_000).is_ok());
+
+    // A 1% transfer fee withheld by the mint
+    let err = check_deposit_received(4_000_000, 4_990_000, 1_000_000).unwrap_err();
+    assert_eq!(err, Error::from(PoolError::DepositShortfall));
+
+    // Nor may the vault shrink or grow by more than was sent
+    assert!(check_deposit_received(4_000_000, 3_000_000, 1_000_000).is_err());
+    assert!(check_deposit_received(4_000_000, 5_000_001, 1_000_000).is_err());
+}
@@ -0,0 +1,26 @@
+use anchor_lang::prelude::Pubkey;
+use privacy_pool::{FeeSchedule, FeeTier, MAX_FEE_TIERS};
+
+const DAY: i64 = 24 * 60 * 60;
+
+#[test]
+fn fee_drops_as_notes_age() {
+    let mut tiers = [FeeTier::default(); MAX_FEE_TIERS];
+    tiers[0] = FeeTier { min_age: 0, fee_bps: 30 };
+    tiers[1] = FeeTier { min_age: DAY, fee_bps: 15 };
+    tiers[2] = FeeTier { min_age: 7 * DAY, fee_bps: 5 };
+    let fees = FeeSchedule {
+        pool: Pubkey::new_unique(),
+        fee_recipient: Pubkey::new_unique(),
+        tier_count: 3,
+        tiers,
+    };
+
+    assert_eq!(fees.fee_bps(0), 30);
+    assert_eq!(fees.fee_bps(DAY - 1), 30);
+    assert_eq!(fees.fee_bps(DAY), 15);
+    assert_eq!(fees.fee_bps(7 * DAY + 1), 5);
+
+    let free = FeeSchedule { tier_count: 0, ..fees };
+    assert_eq!(free.fee_bps(0), 0);
+}
@@ -0,0 +1,114 @@
+use anchor_lang::prelude::Pubkey;
+use privacy_pool::{
+    migration_public_inputs, recipient_field, NoteMigration, PoolImport, PoolMigration, PoolStats,
+    AGGREGATE_PROOF_HEADER_INPUTS,
+};
+
+fn stats(total_withdrawn: u64) -> PoolStats {
+    PoolStats {
+        pool: Pubkey::new_unique(),
+        total_deposited: 10_000_000,
+        total_withdrawn,
+        deposit_count: 4,
+        updated_at: 0,
+    }
+}
+
+#[test]
+fn progress_counts_moved_and_withdrawn_notes() {
+    let mut migration = PoolMigration {
+        pool: Pubkey::new_unique(),
+        new_program: Pubkey::new_unique(),
+        new_pool: Pubkey::new_unique(),
+        frozen_root: [3; 32],
+        frozen_leaves: 4,
+        liabilities_at_start: 8_000_000,
+        migrated_notes: 0,
+        migrated_amount: 0,
+        started_at: 100,
+        updated_at: 100,
+    };
+    assert_eq!(migration.progress_bps(&stats(2_000_000)), 0);
+
+    // One note moved, another withdrawn directly
+    migration.record(1, 3_000_000, 200).unwrap();
+    assert_eq!(
+        (migration.migrated_notes, migration.migrated_amount),
+        (1, 3_000_000)
+    );
+    assert_eq!(migration.updated_at, 200);
+    assert_eq!(migration.progress_bps(&stats(6_000_000)), 5_000);
+
+    assert_eq!(migration.progress_bps(&stats(10_000_000)), 10_000);
+    assert!(migration.record(0, u64::MAX, 300).is_err());
+}
+
+#[test]
+fn moves_are_proven_against_the_frozen_root_and_bind_the_new_commitment() {
+    let migration = PoolMigration {
+        pool: Pubkey::new_unique(),
+        new_program: Pubkey::new_unique(),
+        new_pool: Pubkey::new_unique(),
+        frozen_root: [3; 32],
+        frozen_leaves: 4,
+        liabilities_at_start: 8_000_000,
+        migrated_notes: 0,
+        migrated_amount: 0,
+        started_at: 100,
+        updated_at: 100,
+    };
+    let note = NoteMigration {
+        nullifier: [7; 32],
+        legacy_nullifier: None,
+        amount: 3_000_000,
+        // Field elements may use the top byte recipient_field drops
+        new_commitment: [0x21; 32],
+    };
+    let new_pool_usdc = Pubkey::new_unique();
+    let inputs = migration_public_inputs(&migration, [1; 32], &new_pool_usdc, &note);
+
+    assert_eq!(inputs[0], [3; 32]);
+    assert_eq!(inputs[1], [1; 32]);
+    let withdrawal = &inputs[AGGREGATE_PROOF_HEADER_INPUTS..];
+    assert_eq!(withdrawal[0], [7; 32]);
+    assert_eq!(withdrawal[2], recipient_field(&new_pool_usdc));
+    assert_eq!(withdrawal[3][24..], 3_000_000u64.to_be_bytes());
+    assert_eq!(withdrawal[4][28..], 4u32.to_be_bytes());
+    assert_eq!(withdrawal[5], [0x21; 32]);
+}
+
+#[test]
+fn imports_count_notes_and_amounts() {
+    let mut import = PoolImport {
+        pool: Pubkey::new_unique(),
+        source_migration: Pubkey::new_unique(),
+        imported_notes: 0,
+        imported_amount: 0,
+        opened_at: 100,
+        updated_at: 100,
+    };
+    import.record(2, 5_000_000, 200).unwrap();
+    assert_eq!(
+        (import.imported_notes, import.imported_amount),
+        (2, 5_000_000)
+    );
+    assert_eq!(import.updated_at, 200);
+    assert!(import.record(0, u64::MAX, 300).is_err());
+}
+
+#[test]
+fn empty_pools_are_migrated_immediately() {
+    let migration = PoolMigration {
+        pool: Pubkey::new_unique(),
+        new_program: Pubkey::new_unique(),
+        new_pool: Pubkey::new_unique(),
+        frozen_root: [0; 32],
+        frozen_leaves: 0,
+        liabilities_at_start: 0,
+        migrated_notes: 0,
+        migrated_amount: 0,
+        started_at: 0,
+        updated_at: 0,
+    };
+    assert_eq!(migration.progress_bps(&stats(10_000_000)), 10_000);
+}
@@ -0,0 +1,79 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::Discriminator;
+use privacy_pool::{NullifierSet, NullifierSlots, MAX_LEAVES, MAX_NULLIFIER_GROWTH};
+
+/// Data of an empty set account with room for `capacity` nullifiers
+fn account(pool: Pubkey, capacity: usize) -> Vec<u8> {
+    let header = NullifierSet::new(pool, 0);
+    let mut data = [NullifierSet::DISCRIMINATOR, bytemuck::bytes_of(&header)].concat();
+    data.resize(data.len() + 32 * capacity, 0);
+    data
+}
+
+#[test]
+fn nullifiers_stay_sorted_and_are_spent_once() {
+    let mut data = account(Pubkey::new_unique(), 4);
+    let mut nullifiers = NullifierSlots::new(&mut data).unwrap();
+    nullifiers.insert([5; 32], Some([1; 32])).unwrap();
+    nullifiers.insert([3; 32], None).unwrap();
+    assert_eq!(nullifiers.recorded(), [[1; 32], [3; 32], [5; 32]]);
+    assert!(nullifiers.contains(&[3; 32]));
+    assert!(!nullifiers.contains(&[4; 32]));
+
+    // Spent directly, or as the legacy form of another spend
+    assert!(nullifiers.insert([3; 32], None).is_err());
+    assert!(nullifiers.insert([9; 32], Some([1; 32])).is_err());
+
+    // The last free slot, then full
+    nullifiers.insert([9; 32], None).unwrap();
+    assert!(nullifiers.insert([2; 32], None).is_err());
+
+    let (header, spent) = NullifierSet::decode(&data).unwrap();
+    assert_eq!(header.count(), 4);
+    assert_eq!(spent, [[1; 32], [3; 32], [5; 32], [9; 32]]);
+}
+
+#[test]
+fn capacity_follows_account_size() {
+    let mut initial = account(Pubkey::new_unique(), MAX_LEAVES);
+    assert_eq!(initial.len(), 8 + NullifierSet::SIZE);
+    assert_eq!(
+        NullifierSlots::new(&mut initial).unwrap().capacity(),
+        MAX_LEAVES
+    );
+
+    // One full grow_nullifier_set call; a partial slot left by a resize is
+    // not usable
+    let growth = MAX_NULLIFIER_GROWTH as usize;
+    assert_eq!(growth * 32, 10 * 1024);
+    let mut grown = account(Pubkey::new_unique(), MAX_LEAVES + growth);
+    grown.push(0);
+    assert_eq!(
+        NullifierSlots::new(&mut grown).unwrap().capacity(),
+        MAX_LEAVES + growth
+    );
+
+    assert!(NullifierSlots::new(&mut [0u8; 40]).is_err());
+}
+
+#[test]
+fn layout_matches_the_borsh_set_it_replaced() {
+    // pool, purpose, count, then the recorded nullifiers and free slots
+    let pool = Pubkey::new_unique();
+    let mut legacy = NullifierSet::DISCRIMINATOR.to_vec();
+    legacy.extend_from_slice(pool.as_ref());
+    legacy.push(1);
+    legacy.extend_from_slice(&2u32.to_le_bytes());
+    legacy.extend_from_slice(&[[7u8; 32], [6u8; 32]].concat());
+    legacy.resize(8 + NullifierSet::SIZE, 0);
+
+    let (header, spent) = NullifierSet::decode(&legacy).unwrap();
+    assert_eq!((header.pool, header.purpose), (pool, 1));
+    assert_eq!(spent, [[7; 32], [6; 32]]);
+
+    // Sets recorded before sorting get sorted when grown
+    let mut nullifiers = NullifierSlots::new(&mut legacy).unwrap();
+    nullifiers.sort();
+    assert_eq!(nullifiers.recorded(), [[6; 32], [7; 32]]);
+    assert!(nullifiers.contains(&[7; 32]));
+}
@@ -0,0 +1,101 @@
+use anchor_lang::prelude::{AccountInfo, AccountSerialize, Pubkey};
+use privacy_pool::{pool_relay_key, PoolRelay, PrivacyPool, MAX_LEAVES};
+
+fn pool(authority: Pubkey) -> PrivacyPool {
+    PrivacyPool {
+        authority,
+        merkle_root: [0; 32],
+        next_index: 0,
+        nullifier_count: 0,
+        leaves: [[0; 32]; MAX_LEAVES],
+    }
+}
+
+fn pool_relay(authority: Pubkey) -> PoolRelay {
+    PoolRelay {
+        pool: Pubkey::new_unique(),
+        relay: authority,
+        pending_relay: None,
+        updated_at: 0,
+    }
+}
+
+#[test]
+fn rotation_takes_effect_when_the_new_relay_accepts() {
+    let authority = Pubkey::new_unique();
+    let new_relay = Pubkey::new_unique();
+    let mut relay = pool_relay(authority);
+
+    relay.propose(new_relay, 100).unwrap();
+    assert_eq!(relay.relay, authority);
+    assert_eq!(relay.pending_relay, Some(new_relay));
+
+    // Only the proposed key can accept
+    assert!(relay.accept(Pubkey::new_unique(), 150).is_err());
+    assert!(relay.accept(authority, 150).is_err());
+
+    assert_eq!(relay.accept(new_relay, 200).unwrap(), authority);
+    assert_eq!(relay.relay, new_relay);
+    assert_eq!(relay.pending_relay, None);
+    assert_eq!(relay.updated_at, 200);
+
+    // Nothing left to accept
+    assert!(relay.accept(new_relay, 300).is_err());
+}
+
+#[test]
+fn proposals_must_change_the_relay() {
+    let authority = Pubkey::new_unique();
+    let mut relay = pool_relay(authority);
+    assert!(relay.propose(authority, 100).is_err());
+    assert!(relay.propose(Pubkey::default(), 100).is_err());
+
+    // A second proposal replaces the first
+    let first = Pubkey::new_unique();
+    let second = Pubkey::new_unique();
+    relay.propose(first, 100).unwrap();
+    relay.propose(second, 110).unwrap();
+    assert!(relay.accept(first, 120).is_err());
+    assert!(relay.accept(second, 120).is_ok());
+}
+
+#[test]
+fn the_authority_is_the_relay_until_the_first_rotation() {
+    let authority = Pubkey::new_unique();
+    let new_relay = Pubkey::new_unique();
+    let pool = pool(authority);
+    let key = Pubkey::new_unique();
+    let owner = privacy_pool::ID;
+    let mut lamports = 0;
+
+    let mut empty: Vec<u8> = Vec::new();
+    let missing = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut empty,
+        &owner,
+        false,
+        0,
+    );
+    assert_eq!(pool_relay_key(&pool, &missing), authority);
+
+    let mut relay = pool_relay(authority);
+    relay.propose(new_relay, 100).unwrap();
+    relay.accept(new_relay, 200).unwrap();
+    let mut data = Vec::new();
+    relay.try_serialize(&mut data).unwrap();
+    let mut lamports = 0;
+    let rotated = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+    assert_eq!(pool_relay_key(&pool, &rotated), new_relay);
+}
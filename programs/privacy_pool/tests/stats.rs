@@ -0,0 +1,48 @@
+use anchor_lang::prelude::Pubkey;
+use privacy_pool::{PoolStats, ReserveAttestation};
+
+#[test]
+fn vault_must_cover_unspent_deposits() {
+    let stats = PoolStats {
+        pool: Pubkey::new_unique(),
+        total_deposited: 5_000_000,
+        total_withdrawn: 2_000_000,
+        deposit_count: 3,
+        updated_at: 0,
+    };
+
+    assert_eq!(stats.liabilities(), 3_000_000);
+    assert!(stats.check_solvency(3_000_000).is_ok());
+    assert!(stats.check_solvency(3_500_000).is_ok());
+    assert!(stats.check_solvency(2_999_999).is_err());
+
+    // Notes deposited before stats began can withdraw past the recorded total
+    let late = PoolStats {
+        total_withdrawn: 6_000_000,
+        ..stats
+    };
+    assert_eq!(late.liabilities(), 0);
+    assert!(late.check_solvency(0).is_ok());
+}
+
+#[test]
+fn attestations_compare_liabilities_with_the_vault() {
+    let attestation = ReserveAttestation {
+        pool: Pubkey::new_unique(),
+        liabilities_root: [7; 32],
+        liabilities: 3_000_000,
+        note_count: 2,
+        vault_balance: 3_000_000,
+        recorded_liabilities: 3_000_000,
+        merkle_root: [1; 32],
+        next_index: 3,
+        attested_at: 0,
+    };
+    assert!(attestation.is_solvent());
+
+    let short = ReserveAttestation {
+        vault_balance: 2_999_999,
+        ..attestation
+    };
+    assert!(!short.is_solvent());
+}
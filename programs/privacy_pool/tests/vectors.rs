@@ -0,0 +1,97 @@
+use anchor_lang::prelude::Pubkey;
+use ark_bn254::Fr;
+use light_poseidon::{Poseidon, PoseidonBytesHasher};
+use privacy_pool::{
+    asset_field, compute_merkle_root, is_known_commitment_version, nullifier_pool_id, MAX_LEAVES,
+    MERKLE_DEPTH,
+};
+use test_vectors::bytes32;
+
+#[test]
+fn merkle_roots_match_vectors() {
+    let trees: Vec<_> = test_vectors::merkle()
+        .trees
+        .into_iter()
+        .filter(|tree| tree.depth == MERKLE_DEPTH)
+        .collect();
+    assert!(!trees.is_empty(), "no vectors for depth {MERKLE_DEPTH}");
+
+    for tree in trees {
+        let mut leaves = [[0u8; 32]; MAX_LEAVES];
+        for (slot, leaf) in leaves.iter_mut().zip(&tree.leaves) {
+            *slot = bytes32(leaf);
+        }
+        assert_eq!(
+            compute_merkle_root(&leaves, tree.leaves.len()),
+            bytes32(&tree.root)
+        );
+    }
+}
+
+/// The Noir tests generated by noir-vectors fold the same paths with the
+/// circuits' compute_merkle_root and expect these roots.
+#[test]
+fn merkle_paths_match_vectors() {
+    let hash = |left: &[u8; 32], right: &[u8; 32]| {
+        Poseidon::<Fr>::new_circom(2)
+            .unwrap()
+            .hash_bytes_be(&[left, right])
+            .unwrap()
+    };
+    for tree in test_vectors::merkle().trees {
+        assert_eq!(tree.path.len(), tree.depth);
+        let mut node = bytes32(&tree.leaves[tree.path_index as usize]);
+        for (level, sibling) in tree.path.iter().enumerate() {
+            let sibling = bytes32(sibling);
+            node = if (tree.path_index >> level) & 1 == 1 {
+                hash(&sibling, &node)
+            } else {
+                hash(&node, &sibling)
+            };
+        }
+        assert_eq!(node, bytes32(&tree.root));
+    }
+}
+
+#[test]
+fn circuits_use_the_pool_depth() {
+    for circuit in ["balance_proof", "split_withdrawal", "obsidian_batch_verifier"] {
+        assert_eq!(
+            test_vectors::circuit_merkle_depth(circuit),
+            MERKLE_DEPTH,
+            "{circuit}"
+        );
+    }
+}
+
+#[test]
+fn pool_id_matches_vectors() {
+    let vectors = test_vectors::notes();
+    let pool: Pubkey = vectors.pool.parse().unwrap();
+    assert_eq!(nullifier_pool_id(&pool), bytes32(&vectors.pool_id));
+}
+
+#[cfg(not(feature = "mainnet"))]
+#[test]
+fn asset_matches_vectors() {
+    assert_eq!(
+        asset_field(&privacy_pool::USDC_MINT),
+        bytes32(&test_vectors::notes().asset)
+    );
+}
+
+#[test]
+fn only_known_commitment_versions_are_accepted() {
+    let version = |byte: u8| {
+        let mut field = [0u8; 32];
+        field[31] = byte;
+        field
+    };
+    assert!(is_known_commitment_version(&version(0)));
+    assert!(is_known_commitment_version(&version(1)));
+    assert!(!is_known_commitment_version(&version(2)));
+
+    let mut wide = version(1);
+    wide[0] = 1;
+    assert!(!is_known_commitment_version(&wide));
+}
@@ -0,0 +1,560 @@
+use anchor_lang::prelude::*;
+
+declare_id!("8WvUQPQrAXckyF9aNYp3SLRsv2vzdMHTmfXpP1132Gpz");
+
+/// Groth16 over BN254, checked with the alt_bn128 syscalls
+pub const PROVING_SYSTEM_GROTH16: u8 = 0;
+
+/// UltraHonk (Noir) - reserved until a Honk backend lands
+pub const PROVING_SYSTEM_HONK: u8 = 1;
+
+/// Groth16 key header: alpha (G1) | beta, gamma, delta (G2)
+pub const GROTH16_VK_HEADER_SIZE: usize = 64 + 128 * 3;
+
+/// Groth16 proof: A (G1) | B (G2) | C (G1)
+pub const GROTH16_PROOF_SIZE: usize = 64 + 128 + 64;
+
+/// Largest verifying key accepted (keys are uploaded in chunks)
+pub const MAX_VK_SIZE: usize = 8192;
+
+#[program]
+pub mod zk_verifier {
+    use super::*;
+
+    /// Allocate a verifying key for one circuit version
+    ///
+    /// The key lives in its own PDA per (circuit, version), so a new
+    /// circuit or proving system is a new account rather than a redeploy
+    /// of every program that verifies proofs. Keys are too large for one
+    /// transaction: write them with upload_verifying_key, then finalize.
+    pub fn initialize_verifying_key(
+        ctx: Context<InitializeVerifyingKey>,
+        circuit_id: [u8; 32],
+        version: u16,
+        proving_system: u8,
+        data_len: u32,
+    ) -> Result<()> {
+        require!(
+            data_len as usize <= MAX_VK_SIZE,
+            VerifierError::VerifyingKeyTooLarge
+        );
+
+        let vk = &mut ctx.accounts.verifying_key;
+        vk.authority = ctx.accounts.authority.key();
+        vk.circuit_id = circuit_id;
+        vk.version = version;
+        vk.proving_system = proving_system;
+        vk.finalized = false;
+        vk.data = vec![0u8; data_len as usize];
+
+        msg!(
+            "Verifying key allocated: version={}, proving_system={}, len={}",
+            version,
+            proving_system,
+            data_len
+        );
+        Ok(())
+    }
+
+    /// Write one chunk of key data at `offset`
+    pub fn upload_verifying_key(
+        ctx: Context<UploadVerifyingKey>,
+        offset: u32,
+        chunk: Vec<u8>,
+    ) -> Result<()> {
+        let vk = &mut ctx.accounts.verifying_key;
+        require!(!vk.finalized, VerifierError::VerifyingKeyFinalized);
+
+        let start = offset as usize;
+        let end = start
+            .checked_add(chunk.len())
+            .ok_or(VerifierError::ChunkOutOfBounds)?;
+        require!(end <= vk.data.len(), VerifierError::ChunkOutOfBounds);
+        vk.data[start..end].copy_from_slice(&chunk);
+
+        msg!(
+            "Verifying key chunk written: offset={}, len={}",
+            offset,
+            chunk.len()
+        );
+        Ok(())
+    }
+
+    /// Validate the uploaded key and freeze it
+    ///
+    /// A finalized key can no longer be written; it becomes usable once
+    /// rotate_verifying_key makes it the circuit's active version.
+    pub fn finalize_verifying_key(ctx: Context<FinalizeVerifyingKey>) -> Result<()> {
+        let vk = &mut ctx.accounts.verifying_key;
+        require!(!vk.finalized, VerifierError::VerifyingKeyFinalized);
+        validate_verifying_key(vk.proving_system, &vk.data)?;
+        vk.finalized = true;
+
+        emit_cpi!(VerifyingKeyFinalized {
+            verifying_key: vk.key(),
+            circuit_id: vk.circuit_id,
+            version: vk.version,
+            proving_system: vk.proving_system,
+        });
+
+        msg!("Verifying key finalized: version={}", vk.version);
+        Ok(())
+    }
+
+    /// Make a finalized key the active version for its circuit
+    ///
+    /// Versions only move forward, so a retired key cannot be re-activated.
+    /// Callers that verify through the active pointer pick up the new key
+    /// without any change on their side.
+    pub fn rotate_verifying_key(ctx: Context<RotateVerifyingKey>) -> Result<()> {
+        let vk = &ctx.accounts.verifying_key;
+        require!(vk.finalized, VerifierError::VerifyingKeyNotFinalized);
+
+        let active = &mut ctx.accounts.active_key;
+        if active.verifying_key != Pubkey::default() {
+            require_keys_eq!(
+                active.authority,
+                ctx.accounts.authority.key(),
+                VerifierError::Unauthorized
+            );
+            require!(vk.version > active.version, VerifierError::StaleVersion);
+        }
+
+        let previous_version = active.version;
+        active.authority = ctx.accounts.authority.key();
+        active.circuit_id = vk.circuit_id;
+        active.verifying_key = vk.key();
+        active.version = vk.version;
+        active.rotated_at = Clock::get()?.unix_timestamp;
+
+        emit_cpi!(VerifyingKeyRotated {
+            circuit_id: vk.circuit_id,
+            verifying_key: vk.key(),
+            previous_version,
+            version: vk.version,
+        });
+
+        msg!(
+            "Verifying key rotated: version {} -> {}",
+            previous_version,
+            vk.version
+        );
+        Ok(())
+    }
+
+    /// Verify a proof against a stored verifying key
+    ///
+    /// Callers CPI into this instruction; it fails unless the proof is valid
+    /// for `public_inputs` (32-byte big-endian field elements) under the
+    /// circuit's currently active key.
+    pub fn verify(
+        ctx: Context<Verify>,
+        public_inputs: Vec<[u8; 32]>,
+        proof: Vec<u8>,
+    ) -> Result<()> {
+        let vk = &ctx.accounts.verifying_key;
+
+        match vk.proving_system {
+            PROVING_SYSTEM_GROTH16 => groth16::verify(&vk.data, &proof, &public_inputs)?,
+            _ => return err!(VerifierError::UnsupportedProvingSystem),
+        }
+
+        msg!("Proof verified: version={}", vk.version);
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Account Structures
+// ============================================================================
+
+#[derive(Accounts)]
+#[instruction(circuit_id: [u8; 32], version: u16, proving_system: u8, data_len: u32)]
+pub struct InitializeVerifyingKey<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + VerifyingKey::space(data_len as usize),
+        seeds = [b"vk", circuit_id.as_ref(), &version.to_le_bytes()],
+        bump
+    )]
+    pub verifying_key: Account<'info, VerifyingKey>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UploadVerifyingKey<'info> {
+    #[account(mut, has_one = authority)]
+    pub verifying_key: Account<'info, VerifyingKey>,
+
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FinalizeVerifyingKey<'info> {
+    #[account(mut, has_one = authority)]
+    pub verifying_key: Account<'info, VerifyingKey>,
+
+    pub authority: Signer<'info>,
+}
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RotateVerifyingKey<'info> {
+    #[account(has_one = authority)]
+    pub verifying_key: Account<'info, VerifyingKey>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ActiveVerifyingKey::SIZE,
+        seeds = [b"active_vk", verifying_key.circuit_id.as_ref()],
+        bump
+    )]
+    pub active_key: Account<'info, ActiveVerifyingKey>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Verify<'info> {
+    #[account(constraint = verifying_key.finalized @ VerifierError::VerifyingKeyNotFinalized)]
+    pub verifying_key: Account<'info, VerifyingKey>,
+
+    /// Only the circuit's active version verifies
+    #[account(
+        seeds = [b"active_vk", verifying_key.circuit_id.as_ref()],
+        bump,
+        has_one = verifying_key @ VerifierError::StaleVersion
+    )]
+    pub active_key: Account<'info, ActiveVerifyingKey>,
+}
+
+// ============================================================================
+// State
+// ============================================================================
+
+#[account]
+pub struct VerifyingKey {
+    pub authority: Pubkey,
+    /// Circuit name, zero-padded (e.g. "balance_proof")
+    pub circuit_id: [u8; 32],
+    pub version: u16,
+    pub proving_system: u8,
+    /// Set once the uploaded data has been validated; no writes after
+    pub finalized: bool,
+    /// Serialized key, layout depends on `proving_system`
+    pub data: Vec<u8>,
+}
+
+impl VerifyingKey {
+    pub fn space(data_len: usize) -> usize {
+        32 + 32 + 2 + 1 + 1 + 4 + data_len
+    }
+}
+
+/// Active verifying key for a circuit, moved forward by rotate_verifying_key
+#[account]
+pub struct ActiveVerifyingKey {
+    pub authority: Pubkey,
+    pub circuit_id: [u8; 32],
+    pub verifying_key: Pubkey,
+    pub version: u16,
+    pub rotated_at: i64,
+}
+
+impl ActiveVerifyingKey {
+    pub const SIZE: usize = 32 + 32 + 32 + 2 + 8;
+}
+
+/// Zero-padded circuit id for a circuit name
+pub fn circuit_id(name: &str) -> [u8; 32] {
+    let mut id = [0u8; 32];
+    let len = name.len().min(32);
+    id[..len].copy_from_slice(&name.as_bytes()[..len]);
+    id
+}
+
+fn validate_verifying_key(proving_system: u8, data: &[u8]) -> Result<()> {
+    match proving_system {
+        PROVING_SYSTEM_GROTH16 => {
+            // Header plus at least IC[0], one G1 point per public input after that
+            require!(
+                data.len() >= GROTH16_VK_HEADER_SIZE + 64
+                    && (data.len() - GROTH16_VK_HEADER_SIZE).is_multiple_of(64),
+                VerifierError::InvalidVerifyingKey
+            );
+            Ok(())
+        }
+        _ => err!(VerifierError::UnsupportedProvingSystem),
+    }
+}
+
+// ============================================================================
+// Groth16
+// ============================================================================
+
+/// Groth16 verification over BN254
+///
+/// Points use the EIP-197 big-endian encoding expected by the alt_bn128
+/// syscalls: G1 = x | y, G2 = x.c1 | x.c0 | y.c1 | y.c0.
+///
+/// Key layout:   alpha_g1 | beta_g2 | gamma_g2 | delta_g2 | ic[0..=n]
+/// Proof layout: a_g1 | b_g2 | c_g1
+pub mod groth16 {
+    use super::*;
+
+    pub fn verify(vk: &[u8], proof: &[u8], public_inputs: &[[u8; 32]]) -> Result<()> {
+        require!(
+            proof.len() == GROTH16_PROOF_SIZE,
+            VerifierError::InvalidProof
+        );
+        let ic = &vk[GROTH16_VK_HEADER_SIZE..];
+        require!(
+            ic.len() == (public_inputs.len() + 1) * 64,
+            VerifierError::PublicInputCountMismatch
+        );
+
+        // vk_x = ic[0] + sum(input_i * ic[i + 1])
+        let mut vk_x: [u8; 64] = ic[..64].try_into().unwrap();
+        for (input, point) in public_inputs.iter().zip(ic[64..].chunks(64)) {
+            require!(
+                input < &SCALAR_MODULUS,
+                VerifierError::PublicInputOutOfRange
+            );
+            let term = alt_bn128::g1_mul(point.try_into().unwrap(), input)?;
+            vk_x = alt_bn128::g1_add(&vk_x, &term)?;
+        }
+
+        // e(-A, B) * e(alpha, beta) * e(vk_x, gamma) * e(C, delta) == 1
+        let mut pairs = Vec::with_capacity(4 * 192);
+        pairs.extend_from_slice(&negate_g1(proof[..64].try_into().unwrap()));
+        pairs.extend_from_slice(&proof[64..192]);
+        pairs.extend_from_slice(&vk[..64]);
+        pairs.extend_from_slice(&vk[64..192]);
+        pairs.extend_from_slice(&vk_x);
+        pairs.extend_from_slice(&vk[192..320]);
+        pairs.extend_from_slice(&proof[192..256]);
+        pairs.extend_from_slice(&vk[320..448]);
+
+        require!(alt_bn128::pairing(&pairs)?, VerifierError::InvalidProof);
+        Ok(())
+    }
+
+    /// BN254 scalar field modulus r
+    const SCALAR_MODULUS: [u8; 32] = [
+        0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58,
+        0x5d, 0x28, 0x33, 0xe8, 0x48, 0x79, 0xb9, 0x70, 0x91, 0x43, 0xe1, 0xf5, 0x93, 0xf0, 0x00,
+        0x00, 0x01,
+    ];
+
+    /// BN254 base field modulus p
+    const BASE_MODULUS: [u8; 32] = [
+        0x30, 0x64, 0x4e, 0x72, 0xe1, 0x31, 0xa0, 0x29, 0xb8, 0x50, 0x45, 0xb6, 0x81, 0x81, 0x58,
+        0x5d, 0x97, 0x81, 0x6a, 0x91, 0x68, 0x71, 0xca, 0x8d, 0x3c, 0x20, 0x8c, 0x16, 0xd8, 0x7c,
+        0xfd, 0x47,
+    ];
+
+    /// -P = (x, p - y); the point at infinity is its own negation
+    fn negate_g1(point: &[u8; 64]) -> [u8; 64] {
+        let mut out = *point;
+        if point.iter().all(|b| *b == 0) {
+            return out;
+        }
+        let mut borrow = 0i16;
+        for i in (0..32).rev() {
+            let mut diff = BASE_MODULUS[i] as i16 - point[32 + i] as i16 - borrow;
+            borrow = (diff < 0) as i16;
+            if diff < 0 {
+                diff += 256;
+            }
+            out[32 + i] = diff as u8;
+        }
+        out
+    }
+}
+
+/// BN254 curve operations: syscalls on-chain, arkworks on the host
+mod alt_bn128 {
+    use super::*;
+
+    #[cfg(target_os = "solana")]
+    mod backend {
+        use super::*;
+        #[allow(deprecated)]
+        use anchor_lang::solana_program::syscalls::sol_alt_bn128_group_op;
+
+        const ADD: u64 = 0;
+        const MUL: u64 = 2;
+        const PAIRING: u64 = 3;
+
+        fn group_op(op: u64, input: &[u8], output: &mut [u8]) -> Result<()> {
+            let status = unsafe {
+                sol_alt_bn128_group_op(op, input.as_ptr(), input.len() as u64, output.as_mut_ptr())
+            };
+            require!(status == 0, VerifierError::InvalidCurvePoint);
+            Ok(())
+        }
+
+        pub fn g1_add(a: &[u8; 64], b: &[u8; 64]) -> Result<[u8; 64]> {
+            let mut input = [0u8; 128];
+            input[..64].copy_from_slice(a);
+            input[64..].copy_from_slice(b);
+            let mut out = [0u8; 64];
+            group_op(ADD, &input, &mut out)?;
+            Ok(out)
+        }
+
+        pub fn g1_mul(point: &[u8; 64], scalar: &[u8; 32]) -> Result<[u8; 64]> {
+            let mut input = [0u8; 96];
+            input[..64].copy_from_slice(point);
+            input[64..].copy_from_slice(scalar);
+            let mut out = [0u8; 64];
+            group_op(MUL, &input, &mut out)?;
+            Ok(out)
+        }
+
+        pub fn pairing(input: &[u8]) -> Result<bool> {
+            let mut out = [0u8; 32];
+            group_op(PAIRING, input, &mut out)?;
+            Ok(out[31] == 1)
+        }
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    mod backend {
+        use super::*;
+        use ark_bn254::{Bn254, Fq, Fq2, Fr, G1Affine, G2Affine};
+        use ark_ec::pairing::Pairing;
+        use ark_ec::{AffineRepr, CurveGroup};
+        use ark_ff::{BigInteger, One, PrimeField};
+
+        fn fq(bytes: &[u8]) -> Result<Fq> {
+            let value = Fq::from_be_bytes_mod_order(bytes);
+            require!(
+                value.into_bigint().to_bytes_be() == bytes,
+                VerifierError::InvalidCurvePoint
+            );
+            Ok(value)
+        }
+
+        fn g1(bytes: &[u8]) -> Result<G1Affine> {
+            if bytes.iter().all(|b| *b == 0) {
+                return Ok(G1Affine::zero());
+            }
+            let point = G1Affine::new_unchecked(fq(&bytes[..32])?, fq(&bytes[32..64])?);
+            require!(point.is_on_curve(), VerifierError::InvalidCurvePoint);
+            Ok(point)
+        }
+
+        fn g2(bytes: &[u8]) -> Result<G2Affine> {
+            if bytes.iter().all(|b| *b == 0) {
+                return Ok(G2Affine::zero());
+            }
+            let x = Fq2::new(fq(&bytes[32..64])?, fq(&bytes[..32])?);
+            let y = Fq2::new(fq(&bytes[96..128])?, fq(&bytes[64..96])?);
+            let point = G2Affine::new_unchecked(x, y);
+            require!(
+                point.is_on_curve() && point.is_in_correct_subgroup_assuming_on_curve(),
+                VerifierError::InvalidCurvePoint
+            );
+            Ok(point)
+        }
+
+        fn encode_g1(point: G1Affine) -> [u8; 64] {
+            let mut out = [0u8; 64];
+            if let Some((x, y)) = point.xy() {
+                out[..32].copy_from_slice(&x.into_bigint().to_bytes_be());
+                out[32..].copy_from_slice(&y.into_bigint().to_bytes_be());
+            }
+            out
+        }
+
+        pub fn g1_add(a: &[u8; 64], b: &[u8; 64]) -> Result<[u8; 64]> {
+            Ok(encode_g1((g1(a)? + g1(b)?).into_affine()))
+        }
+
+        pub fn g1_mul(point: &[u8; 64], scalar: &[u8; 32]) -> Result<[u8; 64]> {
+            let scalar = Fr::from_be_bytes_mod_order(scalar);
+            Ok(encode_g1((g1(point)? * scalar).into_affine()))
+        }
+
+        pub fn pairing(input: &[u8]) -> Result<bool> {
+            require!(
+                input.len().is_multiple_of(192),
+                VerifierError::InvalidCurvePoint
+            );
+            let mut g1s = Vec::new();
+            let mut g2s = Vec::new();
+            for pair in input.chunks(192) {
+                g1s.push(g1(&pair[..64])?);
+                g2s.push(g2(&pair[64..])?);
+            }
+            Ok(Bn254::multi_pairing(g1s, g2s).0.is_one())
+        }
+    }
+
+    pub use backend::*;
+}
+
+// ============================================================================
+// Events
+// ============================================================================
+
+/// A verifying key was fully uploaded and can verify proofs.
+#[event]
+pub struct VerifyingKeyFinalized {
+    pub verifying_key: Pubkey,
+    pub circuit_id: [u8; 32],
+    pub version: u16,
+    pub proving_system: u8,
+}
+
+/// `circuit_id` moved to a new verifying key version.
+#[event]
+pub struct VerifyingKeyRotated {
+    pub circuit_id: [u8; 32],
+    pub verifying_key: Pubkey,
+    pub previous_version: u16,
+    pub version: u16,
+}
+
+// ============================================================================
+// Errors
+// ============================================================================
+
+#[error_code]
+pub enum VerifierError {
+    #[msg("Proving system not supported by this verifier")]
+    UnsupportedProvingSystem,
+    #[msg("Verifying key is malformed")]
+    InvalidVerifyingKey,
+    #[msg("Verifying key exceeds the maximum size")]
+    VerifyingKeyTooLarge,
+    #[msg("Proof is invalid")]
+    InvalidProof,
+    #[msg("Number of public inputs does not match the verifying key")]
+    PublicInputCountMismatch,
+    #[msg("Public input is not a canonical field element")]
+    PublicInputOutOfRange,
+    #[msg("Invalid curve point")]
+    InvalidCurvePoint,
+    #[msg("Verifying key is finalized and can no longer be written")]
+    VerifyingKeyFinalized,
+    #[msg("Verifying key has not been finalized")]
+    VerifyingKeyNotFinalized,
+    #[msg("Chunk extends past the end of the verifying key")]
+    ChunkOutOfBounds,
+    #[msg("Verifying key is not the circuit's active version")]
+    StaleVersion,
+    #[msg("Unauthorized")]
+    Unauthorized,
+}
@@ -0,0 +1,104 @@
+use anchor_lang::error::Error;
+use ark_bn254::{Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{BigInteger, PrimeField};
+use zk_verifier::{groth16, VerifierError};
+
+fn g1(scalar: Fr) -> [u8; 64] {
+    let point: G1Affine = (G1Projective::from(G1Affine::generator()) * scalar).into_affine();
+    let (x, y) = point.xy().unwrap();
+    let mut out = [0u8; 64];
+    out[..32].copy_from_slice(&x.into_bigint().to_bytes_be());
+    out[32..].copy_from_slice(&y.into_bigint().to_bytes_be());
+    out
+}
+
+fn g2(scalar: Fr) -> [u8; 128] {
+    let point: G2Affine = (G2Projective::from(G2Affine::generator()) * scalar).into_affine();
+    let (x, y) = point.xy().unwrap();
+    let mut out = [0u8; 128];
+    out[..32].copy_from_slice(&x.c1.into_bigint().to_bytes_be());
+    out[32..64].copy_from_slice(&x.c0.into_bigint().to_bytes_be());
+    out[64..96].copy_from_slice(&y.c1.into_bigint().to_bytes_be());
+    out[96..].copy_from_slice(&y.c0.into_bigint().to_bytes_be());
+    out
+}
+
+fn scalar(value: Fr) -> [u8; 32] {
+    value.into_bigint().to_bytes_be().try_into().unwrap()
+}
+
+/// Key and proof built from known discrete logs, so the pairing equation
+/// e(A, B) = e(alpha, beta) * e(vk_x, gamma) * e(C, delta) holds by construction
+fn fixture(inputs: &[Fr]) -> (Vec<u8>, Vec<u8>) {
+    let (alpha, beta, gamma, delta) = (
+        Fr::from(3u64),
+        Fr::from(5u64),
+        Fr::from(7u64),
+        Fr::from(11u64),
+    );
+    let ic: Vec<Fr> = (0..=inputs.len() as u64)
+        .map(|i| Fr::from(13 + i))
+        .collect();
+    let c = Fr::from(17u64);
+
+    let vk_x = ic[0] + inputs.iter().zip(&ic[1..]).map(|(x, s)| *x * s).sum::<Fr>();
+    let a = alpha * beta + vk_x * gamma + c * delta;
+
+    let mut vk = Vec::new();
+    vk.extend_from_slice(&g1(alpha));
+    vk.extend_from_slice(&g2(beta));
+    vk.extend_from_slice(&g2(gamma));
+    vk.extend_from_slice(&g2(delta));
+    for s in &ic {
+        vk.extend_from_slice(&g1(*s));
+    }
+
+    let mut proof = Vec::new();
+    proof.extend_from_slice(&g1(a));
+    proof.extend_from_slice(&g2(Fr::from(1u64)));
+    proof.extend_from_slice(&g1(c));
+    (vk, proof)
+}
+
+fn public_inputs(inputs: &[Fr]) -> Vec<[u8; 32]> {
+    inputs.iter().map(|x| scalar(*x)).collect()
+}
+
+#[test]
+fn valid_proof_verifies() {
+    let inputs = [Fr::from(42u64), Fr::from(7u64)];
+    let (vk, proof) = fixture(&inputs);
+    groth16::verify(&vk, &proof, &public_inputs(&inputs)).unwrap();
+}
+
+#[test]
+fn wrong_public_input_is_rejected() {
+    let inputs = [Fr::from(42u64), Fr::from(7u64)];
+    let (vk, proof) = fixture(&inputs);
+    let err = groth16::verify(
+        &vk,
+        &proof,
+        &public_inputs(&[Fr::from(43u64), Fr::from(7u64)]),
+    )
+    .unwrap_err();
+    assert_eq!(err, Error::from(VerifierError::InvalidProof));
+}
+
+#[test]
+fn public_input_count_must_match_key() {
+    let inputs = [Fr::from(42u64), Fr::from(7u64)];
+    let (vk, proof) = fixture(&inputs);
+    let err = groth16::verify(&vk, &proof, &public_inputs(&inputs[..1])).unwrap_err();
+    assert_eq!(err, Error::from(VerifierError::PublicInputCountMismatch));
+}
+
+#[test]
+fn non_canonical_public_input_is_rejected() {
+    let inputs = [Fr::from(42u64), Fr::from(7u64)];
+    let (vk, proof) = fixture(&inputs);
+    let mut public = public_inputs(&inputs);
+    public[0] = [0xff; 32];
+    let err = groth16::verify(&vk, &proof, &public).unwrap_err();
+    assert_eq!(err, Error::from(VerifierError::PublicInputOutOfRange));
+}
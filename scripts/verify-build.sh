@@ -0,0 +1,120 @@
+#!/usr/bin/env bash
+# Verify that deployed programs match this source.
+#
+#   scripts/verify-build.sh [--cluster devnet|mainnet] [--url RPC] [--skip-build] [program...]
+#
+# For each program (default: all):
+#   1. cross-checks declare_id! (for the cluster's feature) against Anchor.toml
+#   2. builds it reproducibly with `solana-verify build` (docker)
+#   3. compares the executable hash with the deployed program's hash
+#
+# Exits non-zero if any check fails. Needs solana-verify
+# (cargo install solana-verify) and docker.
+
+set -euo pipefail
+
+ROOT="$(cd "$(dirname "${BASH_SOURCE[0]}")/.." && pwd)"
+
+# name | workspace | program dir (relative to workspace) | Anchor.toml
+PROGRAMS=(
+  "privacy_pool|.|programs/privacy_pool|Anchor.toml"
+  "zk_verifier|.|programs/zk_verifier|Anchor.toml"
+  "obsidian_mpc|arcium-relay/programs|obsidian_mpc|arcium-relay/Anchor.toml"
+)
+
+CLUSTER=devnet
+URL=""
+SKIP_BUILD=0
+SELECTED=()
+
+while [[ $# -gt 0 ]]; do
+  case "$1" in
+    --cluster) CLUSTER="$2"; shift 2 ;;
+    --url) URL="$2"; shift 2 ;;
+    --skip-build) SKIP_BUILD=1; shift ;;
+    -h|--help) sed -n '2,13p' "$0" | sed 's/^# \{0,1\}//'; exit 0 ;;
+    *) SELECTED+=("$1"); shift ;;
+  esac
+done
+
+case "$CLUSTER" in
+  devnet) URL="${URL:-https://api.devnet.solana.com}"; FEATURE_ARGS=() ;;
+  mainnet) URL="${URL:-https://api.mainnet-beta.solana.com}"; FEATURE_ARGS=(-- --no-default-features --features mainnet) ;;
+  *) echo "unknown cluster: $CLUSTER (devnet|mainnet)" >&2; exit 2 ;;
+esac
+
+if [[ $SKIP_BUILD -eq 0 ]] && ! command -v solana-verify >/dev/null; then
+  echo "solana-verify not found: cargo install solana-verify" >&2
+  exit 2
+fi
+
+# declare_id! compiled for the cluster: the mainnet-gated one for mainnet,
+# otherwise the other one. Programs without cluster gates have only one;
+# a gated program with no mainnet ID (a compile_error! instead) has none.
+declared_id() {
+  local lib="$1"
+  local gate='#\[cfg\(not\(feature = "mainnet"\)\)\]'
+  [[ "$CLUSTER" == mainnet ]] && gate='#\[cfg\(feature = "mainnet"\)\]'
+  local id
+  if grep -qE "^$gate" "$lib"; then
+    id=$(grep -EA1 "^$gate" "$lib" | grep -oE 'declare_id!\("[1-9A-HJ-NP-Za-km-z]+"\)' | head -1 || true)
+  else
+    id=$(grep -oE 'declare_id!\("[1-9A-HJ-NP-Za-km-z]+"\)' "$lib" | head -1 || true)
+  fi
+  sed -E 's/declare_id!\("(.*)"\)/\1/' <<<"$id"
+}
+
+# Program ID from [programs.<cluster>] in an Anchor.toml
+anchor_id() {
+  local toml="$1" name="$2"
+  awk -v section="[programs.$CLUSTER]" -v name="$name" '
+    /^\[/ { in_section = ($0 == section) }
+    in_section && $1 == name { gsub(/"/, "", $3); print $3 }
+  ' "$toml"
+}
+
+FAILED=0
+fail() { echo "  FAIL: $*"; FAILED=1; }
+
+for entry in "${PROGRAMS[@]}"; do
+  IFS='|' read -r name workspace dir toml <<<"$entry"
+  if [[ ${#SELECTED[@]} -gt 0 && ! " ${SELECTED[*]} " =~ " $name " ]]; then
+    continue
+  fi
+  echo "== $name ($CLUSTER)"
+
+  declared=$(declared_id "$ROOT/$workspace/$dir/src/lib.rs")
+  configured=$(anchor_id "$ROOT/$toml" "$name")
+  echo "  declare_id!: ${declared:-missing}"
+  echo "  $toml: ${configured:-missing}"
+  if [[ -z "$configured" ]]; then
+    fail "no [programs.$CLUSTER] entry in $toml"
+    continue
+  fi
+  if [[ "$declared" != "$configured" ]]; then
+    fail "declare_id! does not match $toml"
+    continue
+  fi
+
+  if [[ $SKIP_BUILD -eq 1 ]]; then
+    continue
+  fi
+
+  if [[ ! -f "$ROOT/$workspace/Cargo.lock" ]]; then
+    fail "$workspace/Cargo.lock missing; reproducible builds need the lockfile"
+    continue
+  fi
+
+  (cd "$ROOT/$workspace" && solana-verify build --library-name "$name" "${FEATURE_ARGS[@]}") >/dev/null
+  built=$(solana-verify get-executable-hash "$ROOT/$workspace/target/deploy/$name.so")
+  deployed=$(solana-verify get-program-hash --url "$URL" "$declared" 2>/dev/null || true)
+  echo "  built:    $built"
+  echo "  deployed: ${deployed:-not found}"
+  if [[ "$built" != "$deployed" ]]; then
+    fail "deployed program does not match this source"
+  else
+    echo "  OK"
+  fi
+done
+
+exit $FAILED
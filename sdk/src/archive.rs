@@ -0,0 +1,240 @@
+//! Signed, content-addressed pool archives for third-party audits.
+//!
+//! An archive holds the full commitment tree, every spent nullifier and the
+//! program events they were derived from, as of one slot. It is addressed
+//! by the sha256 of its borsh encoding and signed with the exporter's
+//! ed25519 key, so an auditor can pin exactly what they checked and who
+//! vouched for it.
+//!
+//! [`SignedArchive::verify`] needs nothing but the archive: it checks the
+//! signature, replays the archived events into leaves and withdrawals,
+//! and recomputes the Merkle root the pool account held at export.
+//! [`SignedArchive::verify_against`] additionally compares with a live
+//! pool account. See the `obsidian-archive` binary.
+
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anchor_lang::prelude::*;
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
+use sha2::{Digest, Sha256};
+
+use crate::mpc;
+use crate::pool;
+use crate::replay::{AccountSnapshot, LoggedTransaction, Replay};
+use crate::{Result, SdkError};
+
+/// Bumped whenever the archive layout changes
+pub const ARCHIVE_VERSION: u8 = 1;
+
+/// Event-carrying instructions of one transaction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ArchivedTransaction {
+    pub signature: String,
+    pub slot: u64,
+    /// Program and `emit_cpi!` data, in execution order
+    pub events: Vec<(Pubkey, Vec<u8>)>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PoolArchive {
+    pub version: u8,
+    pub pool: Pubkey,
+    /// Latest slot in the archived history
+    pub slot: u64,
+    /// Root held by the pool account at export
+    pub merkle_root: [u8; 32],
+    pub leaves: Vec<[u8; 32]>,
+    /// Spent nullifiers of every nullifier set, by purpose
+    pub nullifiers: Vec<(u8, Vec<[u8; 32]>)>,
+    pub transactions: Vec<ArchivedTransaction>,
+}
+
+impl PoolArchive {
+    /// Build an archive from the pool's successful transactions and a
+    /// snapshot of the pool and its nullifier sets.
+    pub fn export(transactions: &[LoggedTransaction], snapshot: &AccountSnapshot) -> Result<Self> {
+        let pool_address = pool::pool_address();
+        let (_, data) = snapshot
+            .accounts
+            .get(&pool_address)
+            .ok_or(SdkError::AccountNotFound(pool_address))?;
+        let on_chain = privacy_pool::PrivacyPool::try_deserialize(&mut data.as_slice())
+            .map_err(|_| SdkError::InvalidAccountData)?;
+
+        let mut nullifiers = Vec::new();
+        for (owner, data) in snapshot.accounts.values() {
+            if *owner != privacy_pool::ID {
+                continue;
+            }
+            if let Some((set, spent)) = privacy_pool::NullifierSet::decode(data) {
+                nullifiers.push((set.purpose, spent.to_vec()));
+            }
+        }
+        nullifiers.sort_by_key(|(purpose, _)| *purpose);
+
+        let mut archived = Vec::new();
+        for transaction in transactions.iter().filter(|tx| !tx.failed) {
+            let events: Vec<(Pubkey, Vec<u8>)> = transaction
+                .instructions
+                .iter()
+                .filter(|(program, data)| {
+                    (*program == privacy_pool::ID || *program == mpc::ID)
+                        && data.starts_with(EVENT_IX_TAG_LE)
+                })
+                .cloned()
+                .collect();
+            if !events.is_empty() {
+                archived.push(ArchivedTransaction {
+                    signature: transaction.signature.clone(),
+                    slot: transaction.slot,
+                    events,
+                });
+            }
+        }
+        archived.sort_by_key(|tx| tx.slot);
+
+        Ok(Self {
+            version: ARCHIVE_VERSION,
+            pool: pool_address,
+            slot: archived.last().map_or(0, |tx| tx.slot),
+            merkle_root: on_chain.merkle_root,
+            leaves: on_chain.leaves[..on_chain.next_index as usize].to_vec(),
+            nullifiers,
+            transactions: archived,
+        })
+    }
+
+    /// Content address: sha256 of the borsh encoding
+    pub fn digest(&self) -> [u8; 32] {
+        Sha256::digest(self.try_to_vec().expect("archive serializes")).into()
+    }
+
+    pub fn sign(self, keypair: &Keypair) -> SignedArchive {
+        let signature = keypair.sign(&self.digest()).to_bytes();
+        SignedArchive {
+            archive: self,
+            signer: keypair.public.to_bytes(),
+            signature,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SignedArchive {
+    pub archive: PoolArchive,
+    /// ed25519 public key of the exporter
+    pub signer: [u8; 32],
+    /// Signature over [`PoolArchive::digest`]
+    pub signature: [u8; 64],
+}
+
+impl SignedArchive {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.try_to_vec().expect("archive serializes")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Self::try_from_slice(bytes).map_err(|_| invalid("undecodable archive"))
+    }
+
+    /// Check the signature and that the tree, root and nullifiers follow
+    /// from the archived events. Returns the content digest.
+    pub fn verify(&self) -> Result<[u8; 32]> {
+        let archive = &self.archive;
+        if archive.version != ARCHIVE_VERSION {
+            return Err(invalid(format!("unsupported version {}", archive.version)));
+        }
+
+        let digest = archive.digest();
+        let signer = PublicKey::from_bytes(&self.signer).map_err(|_| invalid("bad signer key"))?;
+        let signature =
+            Signature::from_bytes(&self.signature).map_err(|_| invalid("bad signature"))?;
+        signer
+            .verify(&digest, &signature)
+            .map_err(|_| invalid("signature does not match content"))?;
+
+        let mut replay = Replay::default();
+        for transaction in &archive.transactions {
+            replay.apply(&LoggedTransaction {
+                signature: transaction.signature.clone(),
+                slot: transaction.slot,
+                failed: false,
+                instructions: transaction.events.clone(),
+            });
+        }
+
+        let replayed: Vec<[u8; 32]> = replay.leaves.values().copied().collect();
+        if replay.leaves.keys().copied().ne(0..replayed.len() as u32) {
+            return Err(invalid("events skip leaf indices"));
+        }
+        if replayed != archive.leaves {
+            return Err(invalid(format!(
+                "events yield {} leaves, archive holds {}",
+                replayed.len(),
+                archive.leaves.len()
+            )));
+        }
+
+        let root = merkle_root(&archive.leaves)?;
+        if root != archive.merkle_root {
+            return Err(invalid("recomputed root differs from the archived root"));
+        }
+
+        let spent = archive
+            .nullifiers
+            .iter()
+            .find(|(purpose, _)| *purpose == privacy_pool::NULLIFIER_PURPOSE_SPEND)
+            .map_or(&[][..], |(_, set)| set.as_slice());
+        if let Some(missing) = replay.withdrawn.iter().find(|n| !spent.contains(n)) {
+            return Err(invalid(format!(
+                "withdrawn nullifier {} missing from the spend set",
+                hex(missing)
+            )));
+        }
+
+        Ok(digest)
+    }
+
+    /// [`Self::verify`], then check the live pool still extends the
+    /// archived tree: every archived leaf is unchanged.
+    pub fn verify_against(&self, snapshot: &AccountSnapshot) -> Result<[u8; 32]> {
+        let digest = self.verify()?;
+        let pool_address = pool::pool_address();
+        let (_, data) = snapshot
+            .accounts
+            .get(&pool_address)
+            .ok_or(SdkError::AccountNotFound(pool_address))?;
+        let on_chain = privacy_pool::PrivacyPool::try_deserialize(&mut data.as_slice())
+            .map_err(|_| SdkError::InvalidAccountData)?;
+
+        let archived = &self.archive.leaves;
+        if (on_chain.next_index as usize) < archived.len()
+            || on_chain.leaves[..archived.len()] != archived[..]
+        {
+            return Err(invalid("live pool does not extend the archived tree"));
+        }
+        if on_chain.next_index as usize == archived.len()
+            && on_chain.merkle_root != self.archive.merkle_root
+        {
+            return Err(invalid("live root differs from the archived root"));
+        }
+        Ok(digest)
+    }
+}
+
+/// Root the pool program computes over `leaves`
+pub fn merkle_root(leaves: &[[u8; 32]]) -> Result<[u8; 32]> {
+    if leaves.len() > privacy_pool::MAX_LEAVES {
+        return Err(invalid("more leaves than the tree holds"));
+    }
+    let mut padded = Box::new([[0u8; 32]; privacy_pool::MAX_LEAVES]);
+    padded[..leaves.len()].copy_from_slice(leaves);
+    Ok(privacy_pool::compute_merkle_root(&padded, leaves.len()))
+}
+
+fn invalid(reason: impl Into<String>) -> SdkError {
+    SdkError::InvalidArchive(reason.into())
+}
+
+fn hex(bytes: &[u8; 32]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
@@ -0,0 +1,122 @@
+//! Packing circuit arguments for computation queue instructions.
+//!
+//! A queued computation carries its arguments in the queue instruction,
+//! in `ArgBuilder` order, so they share one transaction with the
+//! instruction's accounts. Encrypted structs too large for that are split
+//! across several `Enc<Shared, _>` parameters with
+//! [`MxeSession::encrypt_chunked`](crate::encryption::MxeSession::encrypt_chunked);
+//! the circuit declares one parameter per chunk.
+//!
+//! Queues are retry-safe as long as the same [`PackedArgs`] is resent: its
+//! computation offset is derived from the arguments, so a retry of a
+//! queue that already landed targets the existing computation account and
+//! fails instead of queuing the computation twice. Re-encrypting for a
+//! retry draws new nonces and therefore a new offset.
+
+use anchor_lang::prelude::*;
+use sha2::{Digest, Sha256};
+
+use crate::encryption::SharedCiphertext;
+use crate::{Result, SdkError};
+
+/// Argument bytes that fit beside a queue instruction's accounts in one
+/// 1232-byte transaction.
+pub const MAX_QUEUE_ARGS_BYTES: usize = 480;
+
+/// Ciphertexts per `Enc<Shared, _>` chunk that keep an order-sized
+/// argument list within [`MAX_QUEUE_ARGS_BYTES`].
+pub const DEFAULT_CHUNK_CIPHERTEXTS: usize = 4;
+
+/// One circuit argument, as appended by Arcium's `ArgBuilder`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Argument {
+    X25519Pubkey([u8; 32]),
+    PlaintextU8(u8),
+    PlaintextU64(u64),
+    PlaintextU128(u128),
+    Ciphertext([u8; 32]),
+}
+
+/// Arguments of one queued computation, checked against
+/// [`MAX_QUEUE_ARGS_BYTES`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PackedArgs {
+    args: Vec<Argument>,
+}
+
+impl PackedArgs {
+    pub fn args(&self) -> &[Argument] {
+        &self.args
+    }
+
+    /// Borsh encoding as carried in the queue instruction.
+    pub fn encode(&self) -> Vec<u8> {
+        self.args.try_to_vec().expect("arguments serialize")
+    }
+
+    /// Computation offset for these arguments: the first 8 bytes of
+    /// sha256 over their encoding, little-endian.
+    pub fn computation_offset(&self) -> u64 {
+        let hash = Sha256::digest(self.encode());
+        u64::from_le_bytes(hash[..8].try_into().expect("8 bytes"))
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ArgBuilder {
+    args: Vec<Argument>,
+}
+
+impl ArgBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn plaintext_u8(mut self, value: u8) -> Self {
+        self.args.push(Argument::PlaintextU8(value));
+        self
+    }
+
+    pub fn plaintext_u64(mut self, value: u64) -> Self {
+        self.args.push(Argument::PlaintextU64(value));
+        self
+    }
+
+    pub fn plaintext_u128(mut self, value: u128) -> Self {
+        self.args.push(Argument::PlaintextU128(value));
+        self
+    }
+
+    /// One `Enc<Shared, T>` parameter: public key, nonce, ciphertexts.
+    pub fn shared(mut self, ciphertext: &SharedCiphertext) -> Self {
+        self.args
+            .push(Argument::X25519Pubkey(ciphertext.public_key));
+        self.args.push(Argument::PlaintextU128(ciphertext.nonce));
+        self.args.extend(
+            ciphertext
+                .ciphertexts
+                .iter()
+                .copied()
+                .map(Argument::Ciphertext),
+        );
+        self
+    }
+
+    /// Consecutive `Enc<Shared, _>` parameters, e.g. the chunks of one
+    /// struct.
+    pub fn shared_all(self, ciphertexts: &[SharedCiphertext]) -> Self {
+        ciphertexts.iter().fold(self, Self::shared)
+    }
+
+    pub fn build(self) -> Result<PackedArgs> {
+        let packed = PackedArgs { args: self.args };
+        let size = packed.encode().len();
+        if size > MAX_QUEUE_ARGS_BYTES {
+            return Err(SdkError::ArgumentsTooLarge {
+                size,
+                max: MAX_QUEUE_ARGS_BYTES,
+            });
+        }
+        Ok(packed)
+    }
+}
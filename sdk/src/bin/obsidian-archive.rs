@@ -0,0 +1,114 @@
+//! Export and verify signed pool archives for auditors.
+//!
+//! cargo run -p obsidian-sdk --bin obsidian-archive -- export transactions.json accounts.json keypair.json out/
+//! cargo run -p obsidian-sdk --bin obsidian-archive -- verify out/<digest>.archive [accounts.json]
+//!
+//! Inputs are the same as `obsidian-replay`; keypair.json is a Solana CLI
+//! keypair (64-byte JSON array) that signs the archive. `export` writes
+//! `<sha256 hex>.archive` into the output directory. `verify` exits with
+//! status 1 if the archive does not check out, or if the live pool in
+//! accounts.json does not extend it.
+
+use std::error::Error;
+use std::path::Path;
+use std::process::ExitCode;
+
+use ed25519_dalek::Keypair;
+use obsidian_sdk::archive::{PoolArchive, SignedArchive};
+use obsidian_sdk::replay::{AccountSnapshot, LoggedTransaction};
+use serde_json::Value;
+
+fn main() -> Result<ExitCode, Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["export", transactions, accounts, keypair, out] => {
+            export(transactions, accounts, keypair, out)
+        }
+        ["verify", archive] => verify(archive, None),
+        ["verify", archive, accounts] => verify(archive, Some(accounts)),
+        _ => {
+            eprintln!(
+                "usage: obsidian-archive export <transactions.json> <accounts.json> <keypair.json> <out-dir>\n       obsidian-archive verify <archive> [accounts.json]"
+            );
+            Ok(ExitCode::from(2))
+        }
+    }
+}
+
+fn export(
+    transactions: &str,
+    accounts: &str,
+    keypair: &str,
+    out: &str,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let transactions: Value = serde_json::from_str(&std::fs::read_to_string(transactions)?)?;
+    let transactions = transactions
+        .as_array()
+        .ok_or("transactions.json must be an array")?
+        .iter()
+        .map(LoggedTransaction::from_rpc_json)
+        .collect::<Result<Vec<_>, _>>()?;
+    let snapshot = read_snapshot(accounts)?;
+
+    let keypair: Vec<u8> = serde_json::from_str(&std::fs::read_to_string(keypair)?)?;
+    let keypair = Keypair::from_bytes(&keypair)?;
+
+    let archive = PoolArchive::export(&transactions, &snapshot)?;
+    let (leaves, slot) = (archive.leaves.len(), archive.slot);
+    let signed = archive.sign(&keypair);
+    let digest = signed.verify()?;
+
+    let path = Path::new(out).join(format!("{}.archive", hex(&digest)));
+    std::fs::write(&path, signed.to_bytes())?;
+    println!(
+        "Archived {leaves} leaves through slot {slot} to {}",
+        path.display()
+    );
+    Ok(ExitCode::SUCCESS)
+}
+
+fn verify(archive: &str, accounts: Option<&str>) -> Result<ExitCode, Box<dyn Error>> {
+    let signed = SignedArchive::from_bytes(&std::fs::read(archive)?)?;
+    let result = match accounts {
+        Some(accounts) => signed.verify_against(&read_snapshot(accounts)?),
+        None => signed.verify(),
+    };
+    let digest = match result {
+        Ok(digest) => digest,
+        Err(e) => {
+            println!("{e}");
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+
+    // A renamed file still verifies; say so rather than trusting the name
+    let name = Path::new(archive).file_stem().and_then(|s| s.to_str());
+    if name != Some(hex(&digest).as_str()) {
+        println!(
+            "warning: file name does not match content digest {}",
+            hex(&digest)
+        );
+    }
+    println!(
+        "Verified {} leaves through slot {}, root {}, signed by {}",
+        signed.archive.leaves.len(),
+        signed.archive.slot,
+        hex(&signed.archive.merkle_root),
+        bs58::encode(signed.signer).into_string()
+    );
+    Ok(ExitCode::SUCCESS)
+}
+
+fn read_snapshot(path: &str) -> Result<AccountSnapshot, Box<dyn Error>> {
+    let accounts: Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    Ok(AccountSnapshot::from_json(&accounts)?)
+}
+
+fn hex(bytes: &[u8; 32]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
@@ -0,0 +1,165 @@
+//! Serve per-user order history rebuilt from transaction history.
+//!
+//! cargo run -p obsidian-sdk --bin obsidian-indexer -- transactions.json accounts.json [addr]
+//!
+//! Inputs are the same as `obsidian-replay`; accounts.json only needs the
+//! `EncryptedDistribution` accounts, to reveal encrypted shares. Listens on
+//! 127.0.0.1:8787 unless `addr` is given.
+//!
+//! GET /users/{wallet}/orders
+//!   Orders the wallet placed through a delegated intent or was paid out to.
+//!   Encrypted shares are `null` unless the request carries the order's
+//!   viewing key as `X-Viewing-Key: <hex x25519 secret>`. The key is only
+//!   used for that request; keep the indexer local or behind TLS.
+
+use std::error::Error;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use obsidian_sdk::encryption::MXE_PUBLIC_KEY_DEVNET;
+use obsidian_sdk::replay::{AccountSnapshot, LoggedTransaction, Replay};
+use serde_json::{json, Value};
+use x25519_dalek::StaticSecret;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (transactions, accounts, addr) = match args.as_slice() {
+        [t, a] => (t, a, "127.0.0.1:8787"),
+        [t, a, addr] => (t, a, addr.as_str()),
+        _ => {
+            eprintln!("usage: obsidian-indexer <transactions.json> <accounts.json> [addr]");
+            std::process::exit(2);
+        }
+    };
+
+    let transactions: Value = serde_json::from_str(&std::fs::read_to_string(transactions)?)?;
+    let mut transactions = transactions
+        .as_array()
+        .ok_or("transactions.json must be an array")?
+        .iter()
+        .map(LoggedTransaction::from_rpc_json)
+        .collect::<Result<Vec<_>, _>>()?;
+    transactions.sort_by_key(|tx| tx.slot);
+
+    let accounts: Value = serde_json::from_str(&std::fs::read_to_string(accounts)?)?;
+    let snapshot = AccountSnapshot::from_json(&accounts)?;
+
+    let mut replay = Replay::default();
+    for transaction in &transactions {
+        replay.apply(transaction);
+    }
+    println!(
+        "Indexed {} transactions: {} batches, {} orders; listening on {addr}",
+        replay.applied,
+        replay.batches.len(),
+        replay.orders.len()
+    );
+
+    for stream in TcpListener::bind(addr)?.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("accept failed: {e}");
+                continue;
+            }
+        };
+        if let Err(e) = serve(stream, &replay, &snapshot) {
+            eprintln!("request failed: {e}");
+        }
+    }
+    Ok(())
+}
+
+fn serve(
+    mut stream: TcpStream,
+    replay: &Replay,
+    snapshot: &AccountSnapshot,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    let mut viewing_key = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("x-viewing-key") {
+                viewing_key = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    let (status, body) = route(&request_line, viewing_key.as_deref(), replay, snapshot);
+    let body = body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+fn route(
+    request_line: &str,
+    viewing_key: Option<&str>,
+    replay: &Replay,
+    snapshot: &AccountSnapshot,
+) -> (&'static str, Value) {
+    let mut parts = request_line.split_whitespace();
+    let (Some("GET"), Some(path)) = (parts.next(), parts.next()) else {
+        return (
+            "405 Method Not Allowed",
+            json!({ "error": "only GET is supported" }),
+        );
+    };
+    let path = path.split('?').next().unwrap_or_default();
+    let Some(wallet) = path
+        .strip_prefix("/users/")
+        .and_then(|rest| rest.strip_suffix("/orders"))
+    else {
+        return ("404 Not Found", json!({ "error": "not found" }));
+    };
+    let Ok(wallet) = Pubkey::from_str(wallet) else {
+        return ("400 Bad Request", json!({ "error": "invalid wallet" }));
+    };
+    let viewing_key = match viewing_key.map(parse_key) {
+        None => None,
+        Some(Some(key)) => Some(StaticSecret::from(key)),
+        Some(None) => {
+            return (
+                "400 Bad Request",
+                json!({ "error": "invalid X-Viewing-Key" }),
+            );
+        }
+    };
+
+    let mut orders = replay.user_orders(&wallet);
+    if let Some(viewing_key) = &viewing_key {
+        for order in &mut orders {
+            // Orders encrypted under another key stay hidden
+            let _ = order.reveal_shares(snapshot, viewing_key, &MXE_PUBLIC_KEY_DEVNET);
+        }
+    }
+    (
+        "200 OK",
+        json!({
+            "wallet": wallet.to_string(),
+            "orders": orders.iter().map(|order| order.to_json()).collect::<Vec<_>>(),
+        }),
+    )
+}
+
+fn parse_key(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 {
+        return None;
+    }
+    let mut key = [0u8; 32];
+    for (i, byte) in key.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(2 * i..2 * i + 2)?, 16).ok()?;
+    }
+    Some(key)
+}
@@ -0,0 +1,68 @@
+//! Print per-epoch USDC netting between the privacy pool and batch escrows.
+//!
+//! cargo run -p obsidian-sdk --bin obsidian-netting -- transactions.json [--epoch-slots N] [--csv]
+//!
+//! transactions.json: array of `getTransaction` results (`"encoding": "json"`)
+//! covering every transaction of both programs, as for obsidian-replay.
+//! Prints JSON, or CSV with `--csv`; conservation breaks go to stderr in
+//! CSV mode.
+//!
+//! Exits with status 1 if any batch's flows do not conserve USDC.
+
+use std::error::Error;
+use std::process::ExitCode;
+
+use obsidian_sdk::netting::{NettingReport, DEFAULT_EPOCH_SLOTS};
+use obsidian_sdk::replay::LoggedTransaction;
+use serde_json::Value;
+
+const USAGE: &str = "usage: obsidian-netting <transactions.json> [--epoch-slots N] [--csv]";
+
+fn main() -> Result<ExitCode, Box<dyn Error>> {
+    let mut path = None;
+    let mut epoch_slots = DEFAULT_EPOCH_SLOTS;
+    let mut csv = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--csv" => csv = true,
+            "--epoch-slots" => {
+                epoch_slots = args.next().ok_or(USAGE)?.parse()?;
+            }
+            _ if path.is_none() => path = Some(arg),
+            _ => {
+                eprintln!("{USAGE}");
+                return Ok(ExitCode::from(2));
+            }
+        }
+    }
+    let Some(path) = path else {
+        eprintln!("{USAGE}");
+        return Ok(ExitCode::from(2));
+    };
+
+    let transactions: Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    let mut transactions = transactions
+        .as_array()
+        .ok_or("transactions.json must be an array")?
+        .iter()
+        .map(LoggedTransaction::from_rpc_json)
+        .collect::<Result<Vec<_>, _>>()?;
+    transactions.sort_by_key(|tx| tx.slot);
+
+    let report = NettingReport::build(&transactions, epoch_slots);
+    if csv {
+        print!("{}", report.to_csv());
+        for conservation_break in &report.breaks {
+            eprintln!("conservation break: {conservation_break}");
+        }
+    } else {
+        println!("{}", serde_json::to_string_pretty(&report.to_json())?);
+    }
+
+    Ok(if report.breaks.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
@@ -0,0 +1,66 @@
+//! Rebuild batch and pool state from transaction history and print where
+//! the current accounts diverge from it.
+//!
+//! cargo run -p obsidian-sdk --bin obsidian-replay -- transactions.json accounts.json
+//!
+//! transactions.json: array of `getTransaction` results (`"encoding": "json"`)
+//! covering every transaction of both programs; replayed in slot order, so
+//! transactions within one slot must already be in execution order.
+//! accounts.json: array of `solana account --output json` objects for the
+//! pool, its nullifier sets and the batches.
+//!
+//! Exits with status 1 if anything diverges.
+
+use std::error::Error;
+use std::process::ExitCode;
+
+use obsidian_sdk::replay::{AccountSnapshot, LoggedTransaction, Replay};
+use serde_json::Value;
+
+fn main() -> Result<ExitCode, Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [transactions, accounts] = args.as_slice() else {
+        eprintln!("usage: obsidian-replay <transactions.json> <accounts.json>");
+        return Ok(ExitCode::from(2));
+    };
+
+    let transactions: Value = serde_json::from_str(&std::fs::read_to_string(transactions)?)?;
+    let mut transactions = transactions
+        .as_array()
+        .ok_or("transactions.json must be an array")?
+        .iter()
+        .map(LoggedTransaction::from_rpc_json)
+        .collect::<Result<Vec<_>, _>>()?;
+    transactions.sort_by_key(|tx| tx.slot);
+
+    let accounts: Value = serde_json::from_str(&std::fs::read_to_string(accounts)?)?;
+    let snapshot = AccountSnapshot::from_json(&accounts)?;
+
+    let mut replay = Replay::default();
+    for transaction in &transactions {
+        replay.apply(transaction);
+    }
+
+    println!(
+        "Replayed {} transactions ({} failed, skipped): {} batches, {} leaves, {} withdrawals",
+        replay.applied,
+        replay.skipped_failed,
+        replay.batches.len(),
+        replay.leaves.len(),
+        replay.withdrawn.len()
+    );
+    for (batch, signature) in &replay.orphaned {
+        println!("warning: {batch} has events before its creation (first in {signature}); history is incomplete");
+    }
+
+    let divergences = replay.diff(&snapshot);
+    if divergences.is_empty() {
+        println!("No divergence across {} accounts", snapshot.accounts.len());
+        return Ok(ExitCode::SUCCESS);
+    }
+    for divergence in &divergences {
+        println!("{divergence}");
+    }
+    println!("{} divergences", divergences.len());
+    Ok(ExitCode::FAILURE)
+}
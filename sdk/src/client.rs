@@ -0,0 +1,1618 @@
+//! Typed async client over both Obsidian programs.
+//!
+//! `ObsidianClient` derives every PDA itself and exposes one method per
+//! instruction. Network access goes through [`Transport`], so the same
+//! client runs over an anchor-client `Program`, a nonblocking RPC client,
+//! or a bank in tests.
+
+use std::future::Future;
+use std::time::Duration;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::AccountDeserialize;
+use anchor_spl::associated_token::{
+    get_associated_token_address, get_associated_token_address_with_program_id,
+};
+use anchor_spl::token::spl_token;
+use futures_util::stream::{self, Stream};
+use solana_signature::Signature;
+
+use crate::lookup_table::{self, AddressLookupTableAccount};
+use crate::mpc::{
+    self, Batch, BatchLane, BatchStatus, CreditReason, Distribution, EncryptedDistribution,
+    InsuranceFund, MarketOutcomeMints, MpcAccount, MpcRegistry, ProtocolConfig, ShareReceipt,
+    StealthDistribution, UserCredit, UserPortfolio, PORTFOLIO_CIPHERTEXTS,
+};
+use crate::pool;
+use crate::verifier;
+use crate::{Result, SdkError};
+
+/// Default interval between account polls in [`ObsidianClient::watch_batch`].
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// RPC access needed by the client.
+pub trait Transport: Send + Sync {
+    /// Fee payer and signer for every instruction the client sends.
+    fn payer(&self) -> Pubkey;
+
+    /// Raw account data, or `None` if the account does not exist.
+    fn get_account_data(
+        &self,
+        address: &Pubkey,
+    ) -> impl Future<Output = Result<Option<Vec<u8>>>> + Send;
+
+    /// Sign with the payer, send and confirm one transaction.
+    fn send_instructions(
+        &self,
+        instructions: Vec<Instruction>,
+    ) -> impl Future<Output = Result<Signature>> + Send;
+
+    /// As [`Self::send_instructions`], as a v0 transaction resolving
+    /// accounts through `lookup_tables`. Transports without v0 support
+    /// send a legacy transaction.
+    fn send_versioned_instructions(
+        &self,
+        instructions: Vec<Instruction>,
+        lookup_tables: Vec<AddressLookupTableAccount>,
+    ) -> impl Future<Output = Result<Signature>> + Send {
+        let _ = lookup_tables;
+        self.send_instructions(instructions)
+    }
+}
+
+pub struct ObsidianClient<T> {
+    transport: T,
+    poll_interval: Duration,
+}
+
+impl<T: Transport> ObsidianClient<T> {
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    fn payer(&self) -> Pubkey {
+        self.transport.payer()
+    }
+
+    async fn send(&self, instruction: Instruction) -> Result<Signature> {
+        self.transport.send_instructions(vec![instruction]).await
+    }
+
+    async fn account_data(&self, address: &Pubkey) -> Result<Vec<u8>> {
+        self.transport
+            .get_account_data(address)
+            .await?
+            .ok_or(SdkError::AccountNotFound(*address))
+    }
+
+    async fn fetch_mpc<A: MpcAccount>(&self, address: &Pubkey) -> Result<A> {
+        A::decode(&self.account_data(address).await?)
+    }
+
+    /// Nonce after the payer's last one on the pool's relay-gated instructions.
+    async fn next_pool_relay_nonce(&self) -> Result<u64> {
+        let address = pool::relay_nonce_address(&self.payer());
+        let last = match self.transport.get_account_data(&address).await? {
+            Some(data) => {
+                privacy_pool::RelayNonce::try_deserialize(&mut data.as_slice())
+                    .map_err(|_| SdkError::InvalidAccountData)?
+                    .nonce
+            }
+            None => 0,
+        };
+        Ok(last + 1)
+    }
+
+    /// Nonce after the payer's last one on MPC distribution recording.
+    async fn next_mpc_relay_nonce(&self) -> Result<u64> {
+        let address = mpc::relay_nonce_address(&self.payer());
+        let last = match self.transport.get_account_data(&address).await? {
+            Some(data) => mpc::RelayNonce::decode(&data)?.nonce,
+            None => 0,
+        };
+        Ok(last + 1)
+    }
+
+    // ========================================================================
+    // Privacy pool
+    // ========================================================================
+
+    pub async fn initialize_pool(&self, relay: &Pubkey) -> Result<Signature> {
+        self.send(pool::initialize_instruction(&self.payer(), relay))
+            .await
+    }
+
+    pub async fn initialize_nullifier_set(&self, purpose: u8) -> Result<Signature> {
+        self.send(pool::initialize_nullifier_set_instruction(
+            &self.payer(),
+            purpose,
+        ))
+        .await
+    }
+
+    pub async fn grow_nullifier_set(&self, purpose: u8, additional: u32) -> Result<Signature> {
+        self.send(pool::grow_nullifier_set_instruction(
+            &self.payer(),
+            purpose,
+            additional,
+        ))
+        .await
+    }
+
+    pub async fn deposit(&self, commitment: [u8; 32], amount: u64) -> Result<Signature> {
+        self.send(pool::deposit_instruction(&self.payer(), commitment, amount))
+            .await
+    }
+
+    /// Deposit and mint a soul-bound receipt for the new leaf; returns its
+    /// leaf index.
+    pub async fn deposit_with_receipt(
+        &self,
+        commitment: [u8; 32],
+        amount: u64,
+    ) -> Result<(u32, Signature)> {
+        let leaf_index = self.pool().await?.next_index;
+        let signature = self
+            .send(pool::deposit_with_receipt_instruction(
+                &self.payer(),
+                commitment,
+                amount,
+                leaf_index,
+            ))
+            .await?;
+        Ok((leaf_index, signature))
+    }
+
+    pub async fn record_nullifier(
+        &self,
+        purpose: u8,
+        nullifier: [u8; 32],
+        legacy_nullifier: Option<[u8; 32]>,
+    ) -> Result<Signature> {
+        self.send(pool::record_nullifier_instruction(
+            &self.payer(),
+            purpose,
+            nullifier,
+            legacy_nullifier,
+            self.next_pool_relay_nonce().await?,
+        ))
+        .await
+    }
+
+    pub async fn set_limits(
+        &self,
+        max_tvl: u64,
+        max_deposit: u64,
+        max_hourly_withdrawal_bps: u16,
+    ) -> Result<Signature> {
+        self.send(pool::set_limits_instruction(
+            &self.payer(),
+            max_tvl,
+            max_deposit,
+            max_hourly_withdrawal_bps,
+        ))
+        .await
+    }
+
+    pub async fn set_screening_program(
+        &self,
+        screening_program: Option<Pubkey>,
+        screening_accounts: u8,
+    ) -> Result<Signature> {
+        self.send(pool::set_screening_program_instruction(
+            &self.payer(),
+            screening_program,
+            screening_accounts,
+        ))
+        .await
+    }
+
+    pub async fn request_unpause(&self) -> Result<Signature> {
+        self.send(pool::request_unpause_instruction(&self.payer()))
+            .await
+    }
+
+    pub async fn unpause(&self) -> Result<Signature> {
+        self.send(pool::unpause_instruction(&self.payer())).await
+    }
+
+    pub async fn initialize_pool_stats(&self) -> Result<Signature> {
+        self.send(pool::initialize_pool_stats_instruction(&self.payer()))
+            .await
+    }
+
+    pub async fn pool_stats(&self) -> Result<privacy_pool::PoolStats> {
+        let data = self.account_data(&pool::stats_address()).await?;
+        privacy_pool::PoolStats::try_deserialize(&mut data.as_slice())
+            .map_err(|_| SdkError::InvalidAccountData)
+    }
+
+    pub async fn attest_reserves(
+        &self,
+        liabilities_root: [u8; 32],
+        liabilities: u64,
+        note_count: u32,
+    ) -> Result<Signature> {
+        self.send(pool::attest_reserves_instruction(
+            &self.payer(),
+            liabilities_root,
+            liabilities,
+            note_count,
+        ))
+        .await
+    }
+
+    pub async fn reserve_attestation(&self) -> Result<privacy_pool::ReserveAttestation> {
+        let data = self.account_data(&pool::reserves_address()).await?;
+        privacy_pool::ReserveAttestation::try_deserialize(&mut data.as_slice())
+            .map_err(|_| SdkError::InvalidAccountData)
+    }
+
+    pub async fn migrate_pool(&self, new_program: &Pubkey) -> Result<Signature> {
+        self.send(pool::migrate_pool_instruction(&self.payer(), new_program))
+            .await
+    }
+
+    /// Move notes with one withdrawal proof each to the migration's v2
+    /// pool.
+    pub async fn migrate_notes(
+        &self,
+        proofs: Vec<Vec<u8>>,
+        moves: Vec<privacy_pool::NoteMigration>,
+    ) -> Result<Signature> {
+        let migration = self.pool_migration().await?;
+        let config = self.verifier_config().await?;
+        let active = self
+            .decode_verifier_account::<zk_verifier::ActiveVerifyingKey>(
+                &pool::verifier_active_key_address(
+                    &config.verifier_program,
+                    &privacy_pool::WITHDRAWAL_CIRCUIT_ID,
+                ),
+            )
+            .await?;
+        self.send(pool::migrate_notes_instruction(
+            &self.payer(),
+            &config.verifier_program,
+            &active.verifying_key,
+            &migration.new_program,
+            proofs,
+            moves,
+            self.next_pool_relay_nonce().await?,
+        ))
+        .await
+    }
+
+    pub async fn cancel_migration(&self) -> Result<Signature> {
+        self.send(pool::cancel_migration_instruction(&self.payer()))
+            .await
+    }
+
+    pub async fn open_import(&self, source_program: &Pubkey) -> Result<Signature> {
+        self.send(pool::open_import_instruction(&self.payer(), source_program))
+            .await
+    }
+
+    pub async fn close_import(&self) -> Result<Signature> {
+        self.send(pool::close_import_instruction(&self.payer()))
+            .await
+    }
+
+    pub async fn pool_import(&self) -> Result<privacy_pool::PoolImport> {
+        let data = self
+            .account_data(&pool::import_address_in(&privacy_pool::ID))
+            .await?;
+        privacy_pool::PoolImport::try_deserialize(&mut data.as_slice())
+            .map_err(|_| SdkError::InvalidAccountData)
+    }
+
+    pub async fn pool_migration(&self) -> Result<privacy_pool::PoolMigration> {
+        let data = self.account_data(&pool::migration_address()).await?;
+        privacy_pool::PoolMigration::try_deserialize(&mut data.as_slice())
+            .map_err(|_| SdkError::InvalidAccountData)
+    }
+
+    pub async fn set_relay(&self, new_relay: &Pubkey) -> Result<Signature> {
+        self.send(pool::set_relay_instruction(&self.payer(), new_relay))
+            .await
+    }
+
+    /// Signed by the payer, which must be the proposed relay
+    pub async fn accept_relay(&self) -> Result<Signature> {
+        self.send(pool::accept_relay_instruction(&self.payer()))
+            .await
+    }
+
+    pub async fn set_cpi_guard(
+        &self,
+        enabled: bool,
+        allowed_programs: Vec<Pubkey>,
+    ) -> Result<Signature> {
+        self.send(pool::set_cpi_guard_instruction(
+            &self.payer(),
+            enabled,
+            allowed_programs,
+        ))
+        .await
+    }
+
+    pub async fn pool_relay(&self) -> Result<privacy_pool::PoolRelay> {
+        let data = self.account_data(&pool::pool_relay_address()).await?;
+        privacy_pool::PoolRelay::try_deserialize(&mut data.as_slice())
+            .map_err(|_| SdkError::InvalidAccountData)
+    }
+
+    pub async fn limits(&self) -> Result<privacy_pool::PoolLimits> {
+        let data = self.account_data(&pool::limits_address()).await?;
+        privacy_pool::PoolLimits::try_deserialize(&mut data.as_slice())
+            .map_err(|_| SdkError::InvalidAccountData)
+    }
+
+    pub async fn set_verifier(
+        &self,
+        verifier_program: Pubkey,
+        circuit: &str,
+        aggregate_circuit: &str,
+    ) -> Result<Signature> {
+        self.send(pool::set_verifier_instruction(
+            &self.payer(),
+            verifier_program,
+            circuit,
+            aggregate_circuit,
+        ))
+        .await
+    }
+
+    /// Record a nullifier using the pool's configured verifier.
+    pub async fn record_nullifier_with_proof(
+        &self,
+        purpose: u8,
+        proof: Vec<u8>,
+        public_inputs: Vec<[u8; 32]>,
+    ) -> Result<Signature> {
+        let config = self.verifier_config().await?;
+        let active = self
+            .decode_verifier_account::<zk_verifier::ActiveVerifyingKey>(
+                &pool::verifier_active_key_address(&config.verifier_program, &config.circuit_id),
+            )
+            .await?;
+        self.send(pool::record_nullifier_with_proof_instruction(
+            purpose,
+            &config.verifier_program,
+            &config.circuit_id,
+            &active.verifying_key,
+            proof,
+            public_inputs,
+        ))
+        .await
+    }
+
+    /// Pay out withdrawals with one withdrawal proof each.
+    pub async fn withdraw_many(
+        &self,
+        proofs: Vec<Vec<u8>>,
+        withdrawals: Vec<privacy_pool::Withdrawal>,
+    ) -> Result<Signature> {
+        let config = self.verifier_config().await?;
+        let active = self
+            .decode_verifier_account::<zk_verifier::ActiveVerifyingKey>(
+                &pool::verifier_active_key_address(
+                    &config.verifier_program,
+                    &privacy_pool::WITHDRAWAL_CIRCUIT_ID,
+                ),
+            )
+            .await?;
+        let fees = self.fee_schedule().await?;
+        self.send(pool::withdraw_many_instruction(
+            &config.verifier_program,
+            &active.verifying_key,
+            &fees.fee_recipient,
+            proofs,
+            withdrawals,
+        ))
+        .await
+    }
+
+    /// Pay out withdrawals with one aggregated proof, using the pool's
+    /// configured aggregate circuit.
+    pub async fn withdraw_aggregated(
+        &self,
+        proof: Vec<u8>,
+        withdrawals: Vec<privacy_pool::Withdrawal>,
+    ) -> Result<Signature> {
+        let config = self.verifier_config().await?;
+        let active = self
+            .decode_verifier_account::<zk_verifier::ActiveVerifyingKey>(
+                &pool::verifier_active_key_address(
+                    &config.verifier_program,
+                    &config.aggregate_circuit_id,
+                ),
+            )
+            .await?;
+        let fees = self.fee_schedule().await?;
+        self.send(pool::withdraw_aggregated_instruction(
+            &config.verifier_program,
+            &config.aggregate_circuit_id,
+            &active.verifying_key,
+            &fees.fee_recipient,
+            proof,
+            withdrawals,
+        ))
+        .await
+    }
+
+    /// Pay one note out to several recipients with a split_withdrawal
+    /// proof.
+    pub async fn withdraw_split(
+        &self,
+        proof: Vec<u8>,
+        split: privacy_pool::SplitWithdrawal,
+    ) -> Result<Signature> {
+        let config = self.verifier_config().await?;
+        let active = self
+            .decode_verifier_account::<zk_verifier::ActiveVerifyingKey>(
+                &pool::verifier_active_key_address(
+                    &config.verifier_program,
+                    &privacy_pool::SPLIT_WITHDRAWAL_CIRCUIT_ID,
+                ),
+            )
+            .await?;
+        let fees = self.fee_schedule().await?;
+        self.send(pool::withdraw_split_instruction(
+            &config.verifier_program,
+            &active.verifying_key,
+            &fees.fee_recipient,
+            proof,
+            split,
+        ))
+        .await
+    }
+
+    pub async fn verifier_config(&self) -> Result<privacy_pool::VerifierConfig> {
+        let data = self.account_data(&pool::verifier_config_address()).await?;
+        privacy_pool::VerifierConfig::try_deserialize(&mut data.as_slice())
+            .map_err(|_| SdkError::InvalidAccountData)
+    }
+
+    pub async fn set_fee_tiers(
+        &self,
+        tiers: Vec<privacy_pool::FeeTier>,
+        fee_recipient: &Pubkey,
+    ) -> Result<Signature> {
+        self.send(pool::set_fee_tiers_instruction(
+            &self.payer(),
+            tiers,
+            fee_recipient,
+        ))
+        .await
+    }
+
+    pub async fn fee_schedule(&self) -> Result<privacy_pool::FeeSchedule> {
+        let data = self.account_data(&pool::fees_address()).await?;
+        privacy_pool::FeeSchedule::try_deserialize(&mut data.as_slice())
+            .map_err(|_| SdkError::InvalidAccountData)
+    }
+
+    pub async fn add_commitment(&self, commitment: [u8; 32]) -> Result<Signature> {
+        self.send(pool::add_commitment_instruction(
+            &self.payer(),
+            commitment,
+            self.next_pool_relay_nonce().await?,
+        ))
+        .await
+    }
+
+    pub async fn pool(&self) -> Result<privacy_pool::PrivacyPool> {
+        let data = self.account_data(&pool::pool_address()).await?;
+        privacy_pool::PrivacyPool::try_deserialize(&mut data.as_slice())
+            .map_err(|_| SdkError::InvalidAccountData)
+    }
+
+    /// Spent nullifiers of the set for `purpose`, sorted
+    pub async fn nullifier_set(&self, purpose: u8) -> Result<Vec<[u8; 32]>> {
+        let data = self
+            .account_data(&pool::nullifier_set_address(purpose))
+            .await?;
+        privacy_pool::NullifierSet::decode(&data)
+            .map(|(_, spent)| spent.to_vec())
+            .ok_or(SdkError::InvalidAccountData)
+    }
+
+    // ========================================================================
+    // ZK verifier
+    // ========================================================================
+
+    /// Allocate, upload in chunks and finalize a verifying key.
+    ///
+    /// Returns the finalize signature. The key is not used for verification
+    /// until [`Self::rotate_verifying_key`] makes it the active version.
+    pub async fn upload_verifying_key(
+        &self,
+        circuit: &str,
+        version: u16,
+        proving_system: u8,
+        data: &[u8],
+    ) -> Result<Signature> {
+        let authority = self.payer();
+        self.send(verifier::initialize_verifying_key_instruction(
+            &authority,
+            circuit,
+            version,
+            proving_system,
+            data.len() as u32,
+        ))
+        .await?;
+        for ix in verifier::upload_verifying_key_instructions(&authority, circuit, version, data) {
+            self.send(ix).await?;
+        }
+        self.send(verifier::finalize_verifying_key_instruction(
+            &authority, circuit, version,
+        ))
+        .await
+    }
+
+    pub async fn rotate_verifying_key(&self, circuit: &str, version: u16) -> Result<Signature> {
+        self.send(verifier::rotate_verifying_key_instruction(
+            &self.payer(),
+            circuit,
+            version,
+        ))
+        .await
+    }
+
+    pub async fn verifying_key(
+        &self,
+        circuit: &str,
+        version: u16,
+    ) -> Result<zk_verifier::VerifyingKey> {
+        self.decode_verifier_account(&verifier::verifying_key_address(circuit, version))
+            .await
+    }
+
+    pub async fn active_verifying_key(
+        &self,
+        circuit: &str,
+    ) -> Result<zk_verifier::ActiveVerifyingKey> {
+        self.decode_verifier_account(&verifier::active_key_address(circuit))
+            .await
+    }
+
+    async fn decode_verifier_account<A: AccountDeserialize>(&self, address: &Pubkey) -> Result<A> {
+        let data = self.account_data(address).await?;
+        A::try_deserialize(&mut data.as_slice()).map_err(|_| SdkError::InvalidAccountData)
+    }
+
+    // ========================================================================
+    // MPC registry
+    // ========================================================================
+
+    pub async fn initialize_mpc_registry(&self) -> Result<Signature> {
+        self.send(mpc::initialize_mpc_registry(&self.payer())).await
+    }
+
+    pub async fn register_comp_def(&self, circuit: &str, version: u16) -> Result<Signature> {
+        self.send(mpc::register_comp_def(&self.payer(), circuit, version))
+            .await
+    }
+
+    pub async fn mpc_registry(&self) -> Result<MpcRegistry> {
+        self.fetch_mpc(&mpc::mpc_registry_address()).await
+    }
+
+    /// Fail fast with [`SdkError::CompDefsNotRegistered`] if any circuit in
+    /// [`mpc::CIRCUITS`] is not registered at the configured circuit version.
+    pub async fn verify_comp_defs(&self) -> Result<()> {
+        let version = self.config().await?.circuit_version;
+        self.verify_comp_defs_at(version).await
+    }
+
+    /// As [`Self::verify_comp_defs`], for the version `batch` is pinned to.
+    pub async fn verify_batch_comp_defs(&self, batch: &Pubkey) -> Result<()> {
+        let version = self.batch(batch).await?.circuit_version;
+        self.verify_comp_defs_at(version).await
+    }
+
+    async fn verify_comp_defs_at(&self, version: u16) -> Result<()> {
+        let missing = self.mpc_registry().await?.missing(version);
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(SdkError::CompDefsNotRegistered(missing.join(", ")))
+        }
+    }
+
+    // ========================================================================
+    // Protocol config
+    // ========================================================================
+
+    pub async fn initialize_config(
+        &self,
+        cluster_offset: u32,
+        fallback_cluster_offset: u32,
+    ) -> Result<Signature> {
+        self.send(mpc::initialize_config(
+            &self.payer(),
+            cluster_offset,
+            fallback_cluster_offset,
+        ))
+        .await
+    }
+
+    pub async fn set_cluster(
+        &self,
+        cluster_offset: u32,
+        fallback_cluster_offset: u32,
+    ) -> Result<Signature> {
+        self.send(mpc::set_cluster(
+            &self.payer(),
+            cluster_offset,
+            fallback_cluster_offset,
+        ))
+        .await
+    }
+
+    pub async fn set_circuit_version(&self, circuit_version: u16) -> Result<Signature> {
+        self.send(mpc::set_circuit_version(&self.payer(), circuit_version))
+            .await
+    }
+
+    pub async fn set_batch_timeouts(
+        &self,
+        execution_timeout: i64,
+        completion_timeout: i64,
+    ) -> Result<Signature> {
+        self.send(mpc::set_batch_timeouts(
+            &self.payer(),
+            execution_timeout,
+            completion_timeout,
+        ))
+        .await
+    }
+
+    pub async fn set_batch_window(
+        &self,
+        min_batch_window: i64,
+        max_batch_window: i64,
+        target_batch_orders: u8,
+    ) -> Result<Signature> {
+        self.send(mpc::set_batch_window(
+            &self.payer(),
+            min_batch_window,
+            max_batch_window,
+            target_batch_orders,
+        ))
+        .await
+    }
+
+    pub async fn set_express_lane(&self, batch_window: i64, fee_bps: u16) -> Result<Signature> {
+        self.send(mpc::set_express_lane(&self.payer(), batch_window, fee_bps))
+            .await
+    }
+
+    pub async fn set_max_reveal_exec_gap(&self, max_reveal_exec_gap: u64) -> Result<Signature> {
+        self.send(mpc::set_max_reveal_exec_gap(
+            &self.payer(),
+            max_reveal_exec_gap,
+        ))
+        .await
+    }
+
+    pub async fn set_execution_approvers(
+        &self,
+        approvers: Vec<Pubkey>,
+        threshold: u8,
+        large_batch_threshold: u64,
+    ) -> Result<Signature> {
+        self.send(mpc::set_execution_approvers(
+            &self.payer(),
+            approvers,
+            threshold,
+            large_batch_threshold,
+        ))
+        .await
+    }
+
+    pub async fn set_mpc_cpi_guard(
+        &self,
+        enabled: bool,
+        allowed_programs: Vec<Pubkey>,
+    ) -> Result<Signature> {
+        self.send(mpc::set_cpi_guard(&self.payer(), enabled, allowed_programs))
+            .await
+    }
+
+    pub async fn set_yield_adapter(
+        &self,
+        adapter: Option<Pubkey>,
+        yield_recipient: &Pubkey,
+    ) -> Result<Signature> {
+        self.send(mpc::set_yield_adapter(
+            &self.payer(),
+            adapter,
+            yield_recipient,
+        ))
+        .await
+    }
+
+    pub async fn set_yield_enabled(&self, enabled: bool) -> Result<Signature> {
+        self.send(mpc::set_yield_enabled(&self.payer(), enabled))
+            .await
+    }
+
+    pub async fn set_role(&self, role: mpc::Role, key: &Pubkey) -> Result<Signature> {
+        self.send(mpc::set_role(&self.payer(), role, key)).await
+    }
+
+    pub async fn set_market_lot_size(&self, market_id: &str, lot_size: u64) -> Result<Signature> {
+        self.send(mpc::set_market_lot_size(&self.payer(), market_id, lot_size))
+            .await
+    }
+
+    pub async fn set_market_outcome_mints(
+        &self,
+        market_id: &str,
+        yes_mint: &Pubkey,
+        no_mint: &Pubkey,
+    ) -> Result<Signature> {
+        self.send(mpc::set_market_outcome_mints(
+            &self.payer(),
+            market_id,
+            yes_mint,
+            no_mint,
+        ))
+        .await
+    }
+
+    pub async fn set_market_lanes(
+        &self,
+        market_id: &str,
+        thresholds: Vec<u64>,
+    ) -> Result<Signature> {
+        self.send(mpc::set_market_lanes(&self.payer(), market_id, thresholds))
+            .await
+    }
+
+    /// Lane thresholds of `market_id`; `None` for a single-lane market.
+    pub async fn market_lanes(&self, market_id: &str) -> Result<Option<mpc::MarketLanes>> {
+        let address = mpc::market_lanes_address(market_id);
+        match self.transport.get_account_data(&address).await? {
+            Some(data) => Ok(Some(mpc::MarketLanes::decode(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Lot size to pass to `add_to_batch_normalized`; 0 if none is set.
+    pub async fn market_lot_size(&self, market_id: &str) -> Result<u64> {
+        let address = mpc::market_lot_size_address(market_id);
+        match self.transport.get_account_data(&address).await? {
+            Some(data) => Ok(mpc::MarketLotSize::decode(&data)?.lot_size),
+            None => Ok(0),
+        }
+    }
+
+    /// Make the configured fallback cluster active, keeping the degraded
+    /// one as the new fallback.
+    pub async fn fail_over_cluster(&self) -> Result<Signature> {
+        let config = self.config().await?;
+        self.set_cluster(config.fallback_cluster_offset, config.cluster_offset)
+            .await
+    }
+
+    pub async fn config(&self) -> Result<ProtocolConfig> {
+        self.fetch_mpc(&mpc::config_address()).await
+    }
+
+    // ========================================================================
+    // Batches
+    // ========================================================================
+
+    /// Create a standard batch owned by the payer; returns its address.
+    pub async fn create_batch(&self, market_id: &str, side: u8) -> Result<(Pubkey, Signature)> {
+        self.create_batch_with_metadata(market_id, side, BatchLane::Standard, None, None)
+            .await
+    }
+
+    /// Create the payer's express batch for `market_id`, next to its
+    /// standard one.
+    pub async fn create_express_batch(
+        &self,
+        market_id: &str,
+        side: u8,
+    ) -> Result<(Pubkey, Signature)> {
+        self.create_batch_with_metadata(market_id, side, BatchLane::Express, None, None)
+            .await
+    }
+
+    /// [`Self::create_batch`] in `lane`, with a label and/or execution
+    /// intent hash.
+    pub async fn create_batch_with_metadata(
+        &self,
+        market_id: &str,
+        side: u8,
+        lane: BatchLane,
+        label: Option<&str>,
+        intent_hash: Option<[u8; 32]>,
+    ) -> Result<(Pubkey, Signature)> {
+        let signature = self
+            .send(mpc::create_batch(
+                &self.payer(),
+                market_id,
+                side,
+                lane,
+                label,
+                intent_hash,
+            ))
+            .await?;
+        Ok((
+            mpc::priority_batch_address(&self.payer(), market_id, lane),
+            signature,
+        ))
+    }
+
+    /// Create the payer's batch for `lane` of `market_id`; lane 0 is the
+    /// [`Self::create_batch`] batch.
+    pub async fn create_lane_batch(
+        &self,
+        market_id: &str,
+        side: u8,
+        lane: u8,
+    ) -> Result<(Pubkey, Signature)> {
+        let signature = self
+            .send(mpc::create_lane_batch(
+                &self.payer(),
+                market_id,
+                side,
+                lane,
+                None,
+                None,
+            ))
+            .await?;
+        Ok((
+            mpc::lane_batch_address(&self.payer(), market_id, lane),
+            signature,
+        ))
+    }
+
+    pub async fn migrate_batch_cluster(&self, batch: &Pubkey) -> Result<Signature> {
+        self.send(mpc::migrate_batch_cluster(batch, &self.payer()))
+            .await
+    }
+
+    pub async fn record_order(&self, batch: &Pubkey) -> Result<Signature> {
+        let order_index = self.batch(batch).await?.order_count;
+        self.send(mpc::record_order(batch, &self.payer(), order_index))
+            .await
+    }
+
+    /// Record an order in the payer's batch for `lane`, the lane the
+    /// `route_order` circuit revealed for it.
+    pub async fn route_order(&self, market_id: &str, lane: u8) -> Result<Signature> {
+        let batch = mpc::lane_batch_address(&self.payer(), market_id, lane);
+        let order_index = self.batch(&batch).await?.order_count;
+        self.send(mpc::route_order(
+            &batch,
+            market_id,
+            &self.payer(),
+            order_index,
+            lane,
+        ))
+        .await
+    }
+
+    pub async fn post_order_count(&self, batch: &Pubkey, order_count: u8) -> Result<Signature> {
+        self.send(mpc::post_order_count(batch, &self.payer(), order_count))
+            .await
+    }
+
+    /// Relay a user's signed order intent; the payer covers fees and rent.
+    /// `session_key` is the user's session key that signed, if not the
+    /// user.
+    pub async fn record_delegated_order(
+        &self,
+        intent: &mpc::OrderIntent,
+        signature: &[u8; 64],
+        session_key: Option<&Pubkey>,
+    ) -> Result<Signature> {
+        let order_index = self.batch(&intent.batch).await?.order_count;
+        self.transport
+            .send_instructions(
+                mpc::record_delegated_order(
+                    &self.payer(),
+                    intent,
+                    signature,
+                    session_key,
+                    order_index,
+                )
+                .to_vec(),
+            )
+            .await
+    }
+
+    /// Next nonce for a new order intent by `user`.
+    pub async fn next_intent_nonce(&self, user: &Pubkey) -> Result<u64> {
+        let address = mpc::intent_nonce_address(user);
+        let last = match self.transport.get_account_data(&address).await? {
+            Some(data) => mpc::RelayNonce::decode(&data)?.nonce,
+            None => 0,
+        };
+        Ok(last + 1)
+    }
+
+    /// Market aggregates and arrival counters; default until the market's
+    /// first batch closes.
+    pub async fn market_stats(&self, market_id: &str) -> Result<mpc::MarketStats> {
+        let address = mpc::market_stats_address(market_id);
+        match self.transport.get_account_data(&address).await? {
+            Some(data) => Ok(mpc::MarketStats::decode(&data)?),
+            None => Ok(mpc::MarketStats::default()),
+        }
+    }
+
+    /// Whether `close_batch` would accept `batch` at `now` under the
+    /// adaptive batch window; the relay polls this to decide when to close.
+    pub async fn batch_window_elapsed(&self, batch: &Pubkey, now: i64) -> Result<bool> {
+        let batch = self.batch(batch).await?;
+        let stats = self.market_stats(&batch.market_id).await?;
+        let config = self.config().await?;
+        Ok(stats.can_close(&config, &batch, now))
+    }
+
+    pub async fn close_batch(
+        &self,
+        batch: &Pubkey,
+        revealed_total: u64,
+        revealed_count: u8,
+    ) -> Result<Signature> {
+        let market_id = self.batch(batch).await?.market_id;
+        self.send(mpc::close_batch(
+            batch,
+            &market_id,
+            &self.payer(),
+            revealed_total,
+            revealed_count,
+        ))
+        .await
+    }
+
+    pub async fn record_execution(
+        &self,
+        batch: &Pubkey,
+        total_shares: u64,
+        filled_usdc: u64,
+        tx_signature: &str,
+        intent_hash: Option<[u8; 32]>,
+    ) -> Result<Signature> {
+        let market_id = self.batch(batch).await?.market_id;
+        let approved = self
+            .transport
+            .get_account_data(&mpc::execution_approval_address(batch))
+            .await?
+            .is_some();
+        self.send(mpc::record_execution(
+            batch,
+            &market_id,
+            &self.payer(),
+            total_shares,
+            filled_usdc,
+            tx_signature,
+            intent_hash,
+            approved,
+        ))
+        .await
+    }
+
+    pub async fn approve_execution(
+        &self,
+        batch: &Pubkey,
+        total_shares: u64,
+        filled_usdc: u64,
+    ) -> Result<Signature> {
+        self.send(mpc::approve_execution(
+            batch,
+            &self.payer(),
+            total_shares,
+            filled_usdc,
+        ))
+        .await
+    }
+
+    /// Stage the root of a [`mpc::DistributionTree`] over every revealed
+    /// distribution of `batch`, their count and the shares they pay out;
+    /// required before any distribution is recorded.
+    pub async fn stage_distributions(
+        &self,
+        batch: &Pubkey,
+        root: [u8; 32],
+        count: u8,
+        shares: u64,
+    ) -> Result<Signature> {
+        self.send(mpc::stage_distributions(
+            batch,
+            &self.payer(),
+            root,
+            count,
+            shares,
+        ))
+        .await
+    }
+
+    /// Close recording once every staged distribution is recorded;
+    /// required before any payout.
+    pub async fn finalize_distributions(&self, batch: &Pubkey) -> Result<Signature> {
+        self.send(mpc::finalize_distributions(batch, &self.payer()))
+            .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn record_distribution(
+        &self,
+        batch: &Pubkey,
+        order_index: u8,
+        shares: u64,
+        usdc_refund: u64,
+        wallet: &Pubkey,
+        unlock_at: Option<i64>,
+        proof: [[u8; 32]; mpc::DISTRIBUTION_TREE_DEPTH],
+    ) -> Result<Signature> {
+        self.send(mpc::record_distribution(
+            batch,
+            &self.payer(),
+            order_index,
+            shares,
+            usdc_refund,
+            wallet,
+            unlock_at,
+            proof,
+            self.next_mpc_relay_nonce().await?,
+        ))
+        .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn record_encrypted_distribution(
+        &self,
+        batch: &Pubkey,
+        order_index: u8,
+        encryption_key: [u8; 32],
+        nonce: u128,
+        encrypted_shares: [u8; 32],
+        wallet: &Pubkey,
+        proof: [[u8; 32]; mpc::DISTRIBUTION_TREE_DEPTH],
+    ) -> Result<Signature> {
+        self.send(mpc::record_encrypted_distribution(
+            batch,
+            &self.payer(),
+            order_index,
+            encryption_key,
+            nonce,
+            encrypted_shares,
+            wallet,
+            proof,
+            self.next_mpc_relay_nonce().await?,
+        ))
+        .await
+    }
+
+    pub async fn record_stealth_distribution(
+        &self,
+        batch: &Pubkey,
+        order_index: u8,
+        shares: u64,
+        claim_key: &Pubkey,
+        proof: [[u8; 32]; mpc::DISTRIBUTION_TREE_DEPTH],
+    ) -> Result<Signature> {
+        self.send(mpc::record_stealth_distribution(
+            batch,
+            &self.payer(),
+            order_index,
+            shares,
+            claim_key,
+            proof,
+            self.next_mpc_relay_nonce().await?,
+        ))
+        .await
+    }
+
+    /// Pull the payer's distribution for `order_index` into its associated
+    /// token accounts, which must exist. `share_token_program` owns the
+    /// market's outcome mints.
+    pub async fn claim_shares(
+        &self,
+        batch: &Pubkey,
+        order_index: u8,
+        receipt: ShareReceipt,
+        share_token_program: &Pubkey,
+    ) -> Result<Signature> {
+        let state = self.batch(batch).await?;
+        let mints: MarketOutcomeMints = self
+            .fetch_mpc(&mpc::market_mints_address(&state.market_id))
+            .await?;
+        let share_mint = mints.outcome_mint(state.side);
+        let claimant = self.payer();
+        self.send(mpc::claim_shares(
+            batch,
+            &state.market_id,
+            &claimant,
+            order_index,
+            receipt,
+            &share_mint,
+            share_token_program,
+            &get_associated_token_address_with_program_id(
+                &claimant,
+                &share_mint,
+                share_token_program,
+            ),
+            &privacy_pool::USDC_MINT,
+            &spl_token::ID,
+            &get_associated_token_address(&claimant, &privacy_pool::USDC_MINT),
+        ))
+        .await
+    }
+
+    pub async fn mark_distributed(
+        &self,
+        batch: &Pubkey,
+        order_index: u8,
+        tx_signature: &str,
+    ) -> Result<Signature> {
+        let market_id = self.batch(batch).await?.market_id;
+        self.send(mpc::mark_distributed(
+            batch,
+            &market_id,
+            &self.payer(),
+            order_index,
+            tx_signature,
+        ))
+        .await
+    }
+
+    /// Mark `order_indices` distributed, MAX_DISTRIBUTIONS_PER_IX per
+    /// transaction, all paid out by `tx_signature`. Returns one signature
+    /// per transaction.
+    pub async fn mark_distributed_many(
+        &self,
+        batch: &Pubkey,
+        order_indices: &[u8],
+        tx_signature: &str,
+    ) -> Result<Vec<Signature>> {
+        let market_id = self.batch(batch).await?.market_id;
+        let mut signatures = Vec::new();
+        for chunk in order_indices.chunks(mpc::MAX_DISTRIBUTIONS_PER_IX) {
+            signatures.push(
+                self.send(mpc::mark_distributed_many(
+                    batch,
+                    &market_id,
+                    &self.payer(),
+                    chunk,
+                    tx_signature,
+                ))
+                .await?,
+            );
+        }
+        Ok(signatures)
+    }
+
+    pub async fn batch(&self, batch: &Pubkey) -> Result<Batch> {
+        self.fetch_mpc(batch).await
+    }
+
+    pub async fn distribution(&self, batch: &Pubkey, order_index: u8) -> Result<Distribution> {
+        self.fetch_mpc(&mpc::distribution_address(batch, order_index))
+            .await
+    }
+
+    pub async fn encrypted_distribution(
+        &self,
+        batch: &Pubkey,
+        order_index: u8,
+    ) -> Result<EncryptedDistribution> {
+        self.fetch_mpc(&mpc::encrypted_distribution_address(batch, order_index))
+            .await
+    }
+
+    pub async fn stealth_distribution(
+        &self,
+        batch: &Pubkey,
+        order_index: u8,
+    ) -> Result<StealthDistribution> {
+        self.fetch_mpc(&mpc::stealth_distribution_address(batch, order_index))
+            .await
+    }
+
+    /// Fail `batch` if it is stuck past its timeout; anyone may call this.
+    pub async fn force_fail_batch(&self, batch: &Pubkey) -> Result<Signature> {
+        self.send(mpc::force_fail_batch(batch)).await
+    }
+
+    /// Reopen `batch` if its revealed total went unexecuted too long; anyone
+    /// may call this.
+    pub async fn expire_reveal(&self, batch: &Pubkey) -> Result<Signature> {
+        self.send(mpc::expire_reveal(batch)).await
+    }
+
+    /// Check `batch`'s escrow against its accounting; a shortfall freezes it.
+    pub async fn reconcile_batch(&self, batch: &Pubkey) -> Result<Signature> {
+        self.send(mpc::reconcile_batch(
+            batch,
+            &privacy_pool::USDC_MINT,
+            &spl_token::ID,
+        ))
+        .await
+    }
+
+    /// Park `amount` of `batch`'s escrow in the config's yield adapter.
+    pub async fn park_escrow(
+        &self,
+        batch: &Pubkey,
+        amount: u64,
+        adapter_accounts: &[AccountMeta],
+    ) -> Result<Signature> {
+        let adapter = self.config().await?.yield_adapter;
+        self.send(mpc::park_escrow(
+            batch,
+            &self.payer(),
+            &privacy_pool::USDC_MINT,
+            &spl_token::ID,
+            &adapter,
+            amount,
+            adapter_accounts,
+        ))
+        .await
+    }
+
+    /// Recall `batch`'s parked escrow; anyone may call this.
+    pub async fn recall_escrow(
+        &self,
+        batch: &Pubkey,
+        adapter_accounts: &[AccountMeta],
+    ) -> Result<Signature> {
+        let adapter = self.batch(batch).await?.parked_adapter;
+        let yield_recipient = self.config().await?.yield_recipient;
+        self.send(mpc::recall_escrow(
+            batch,
+            &privacy_pool::USDC_MINT,
+            &spl_token::ID,
+            &adapter,
+            &yield_recipient,
+            adapter_accounts,
+        ))
+        .await
+    }
+
+    /// Stream of status transitions for `batch`, starting with its current
+    /// status and ending after `Completed` or `Failed`.
+    pub fn watch_batch(&self, batch: Pubkey) -> impl Stream<Item = Result<BatchStatus>> + '_ {
+        stream::unfold(Some((None, false)), move |state| async move {
+            let (last, polled) = state?;
+            if polled {
+                tokio::time::sleep(self.poll_interval).await;
+            }
+            loop {
+                match self.batch(&batch).await {
+                    Err(err) => return Some((Err(err), Some((last, true)))),
+                    Ok(account) if last != Some(account.status) => {
+                        let status = account.status;
+                        let next =
+                            (!matches!(status, BatchStatus::Completed | BatchStatus::Failed))
+                                .then_some((Some(status), true));
+                        return Some((Ok(status), next));
+                    }
+                    Ok(_) => tokio::time::sleep(self.poll_interval).await,
+                }
+            }
+        })
+    }
+
+    // ========================================================================
+    // Lookup tables
+    // ========================================================================
+
+    /// Create a lookup table owned by the payer. `recent_slot` must be a
+    /// recent rooted slot; it also sets the table's address.
+    pub async fn create_lookup_table(&self, recent_slot: u64) -> Result<(Pubkey, Signature)> {
+        let (ix, table) =
+            lookup_table::create_lookup_table(&self.payer(), &self.payer(), recent_slot);
+        Ok((table, self.send(ix).await?))
+    }
+
+    pub async fn lookup_table(&self, table: &Pubkey) -> Result<AddressLookupTableAccount> {
+        lookup_table::decode_lookup_table(table, &self.account_data(table).await?)
+    }
+
+    /// Add the distribution accounts of `batch`, and `extra` such as payout
+    /// token accounts, to `table`; addresses already in it are skipped.
+    pub async fn extend_distribution_lookup_table(
+        &self,
+        table: &Pubkey,
+        batch: &Pubkey,
+        extra: &[Pubkey],
+    ) -> Result<Vec<Signature>> {
+        let state = self.batch(batch).await?;
+        let existing = self.lookup_table(table).await?.addresses;
+        let mut addresses = lookup_table::distribution_accounts(
+            batch,
+            &self.payer(),
+            &state.market_id,
+            state.order_count,
+        );
+        addresses.extend_from_slice(extra);
+
+        let mut signatures = Vec::new();
+        for ix in lookup_table::extend_instructions(
+            table,
+            &self.payer(),
+            &self.payer(),
+            &existing,
+            &addresses,
+        )? {
+            signatures.push(self.send(ix).await?);
+        }
+        Ok(signatures)
+    }
+
+    pub async fn deactivate_lookup_table(&self, table: &Pubkey) -> Result<Signature> {
+        self.send(lookup_table::deactivate_lookup_table(table, &self.payer()))
+            .await
+    }
+
+    /// Close a table deactivated long enough ago, returning its rent to the payer.
+    pub async fn close_lookup_table(&self, table: &Pubkey) -> Result<Signature> {
+        self.send(lookup_table::close_lookup_table(
+            table,
+            &self.payer(),
+            &self.payer(),
+        ))
+        .await
+    }
+
+    /// Send `instructions` as one v0 transaction resolving accounts
+    /// through `tables`.
+    pub async fn send_with_lookup_tables(
+        &self,
+        instructions: Vec<Instruction>,
+        tables: &[Pubkey],
+    ) -> Result<Signature> {
+        let mut accounts = Vec::with_capacity(tables.len());
+        for table in tables {
+            accounts.push(self.lookup_table(table).await?);
+        }
+        self.transport
+            .send_versioned_instructions(instructions, accounts)
+            .await
+    }
+
+    // ========================================================================
+    // Portfolios
+    // ========================================================================
+
+    pub async fn store_portfolio(
+        &self,
+        owner: &Pubkey,
+        nonce: u128,
+        encrypted_positions: [[u8; 32]; PORTFOLIO_CIPHERTEXTS],
+    ) -> Result<Signature> {
+        self.send(mpc::store_portfolio(
+            &self.payer(),
+            owner,
+            nonce,
+            encrypted_positions,
+        ))
+        .await
+    }
+
+    /// Portfolio of `owner` stored by the payer.
+    pub async fn portfolio(&self, owner: &Pubkey) -> Result<UserPortfolio> {
+        self.fetch_mpc(&mpc::portfolio_address(&self.payer(), owner))
+            .await
+    }
+
+    // ========================================================================
+    // Insurance fund
+    // ========================================================================
+
+    pub async fn initialize_insurance_fund(&self, fee_share_bps: u16) -> Result<Signature> {
+        self.send(mpc::initialize_insurance_fund(
+            &self.payer(),
+            &privacy_pool::USDC_MINT,
+            &spl_token::ID,
+            fee_share_bps,
+        ))
+        .await
+    }
+
+    /// Move `amount` USDC from the payer's token account into the fund.
+    pub async fn fund_insurance(&self, amount: u64) -> Result<Signature> {
+        let funder = self.payer();
+        self.send(mpc::fund_insurance(
+            &funder,
+            &get_associated_token_address(&funder, &privacy_pool::USDC_MINT),
+            &privacy_pool::USDC_MINT,
+            &spl_token::ID,
+            amount,
+        ))
+        .await
+    }
+
+    /// Pay a distribution's shortfall to its wallet's USDC account.
+    pub async fn claim_shortfall(&self, batch: &Pubkey, order_index: u8) -> Result<Signature> {
+        let wallet = self.distribution(batch, order_index).await?.wallet;
+        self.send(mpc::claim_shortfall(
+            batch,
+            order_index,
+            &get_associated_token_address(&wallet, &privacy_pool::USDC_MINT),
+            &privacy_pool::USDC_MINT,
+            &spl_token::ID,
+        ))
+        .await
+    }
+
+    pub async fn insurance_fund(&self) -> Result<InsuranceFund> {
+        self.fetch_mpc(&mpc::insurance_fund_address()).await
+    }
+
+    // ========================================================================
+    // User credits
+    // ========================================================================
+
+    pub async fn initialize_credit_ledger(&self) -> Result<Signature> {
+        self.send(mpc::initialize_credit_ledger(
+            &self.payer(),
+            &privacy_pool::USDC_MINT,
+            &spl_token::ID,
+        ))
+        .await
+    }
+
+    /// Credit `owner` with `amount` USDC, paid from the payer's (settler's)
+    /// token account into the credit vault.
+    pub async fn record_credit(
+        &self,
+        owner: &Pubkey,
+        amount: u64,
+        reason: CreditReason,
+        batch: &Pubkey,
+    ) -> Result<Signature> {
+        let settler = self.payer();
+        self.send(mpc::record_credit(
+            &settler,
+            &get_associated_token_address(&settler, &privacy_pool::USDC_MINT),
+            &privacy_pool::USDC_MINT,
+            &spl_token::ID,
+            owner,
+            amount,
+            reason,
+            batch,
+        ))
+        .await
+    }
+
+    /// Withdraw `amount` of the payer's credit to their USDC account.
+    pub async fn claim_credit(&self, amount: u64) -> Result<Signature> {
+        let owner = self.payer();
+        self.send(mpc::claim_credit(
+            &owner,
+            &get_associated_token_address(&owner, &privacy_pool::USDC_MINT),
+            &privacy_pool::USDC_MINT,
+            &spl_token::ID,
+            amount,
+        ))
+        .await
+    }
+
+    pub async fn user_credit(&self, owner: &Pubkey) -> Result<UserCredit> {
+        self.fetch_mpc(&mpc::user_credit_address(owner)).await
+    }
+
+    // ========================================================================
+    // Session keys
+    // ========================================================================
+
+    pub async fn create_session_key(
+        &self,
+        session_key: &Pubkey,
+        expiry: i64,
+        scope: u8,
+    ) -> Result<Signature> {
+        self.send(mpc::create_session_key(
+            &self.payer(),
+            session_key,
+            expiry,
+            scope,
+        ))
+        .await
+    }
+
+    pub async fn revoke_session_key(&self, session_key: &Pubkey) -> Result<Signature> {
+        self.send(mpc::revoke_session_key(&self.payer(), session_key))
+            .await
+    }
+
+    pub async fn session_key(
+        &self,
+        user: &Pubkey,
+        session_key: &Pubkey,
+    ) -> Result<mpc::SessionKey> {
+        self.fetch_mpc(&mpc::session_key_address(user, session_key))
+            .await
+    }
+
+    // ========================================================================
+    // Subscriptions
+    // ========================================================================
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_subscription(
+        &self,
+        id: u64,
+        market_id: &str,
+        side: u8,
+        cadence: i64,
+        orders: u32,
+        ciphertext_ref: [u8; 32],
+        jitter_bps: u16,
+    ) -> Result<Signature> {
+        self.send(mpc::create_subscription(
+            &self.payer(),
+            id,
+            market_id,
+            side,
+            cadence,
+            orders,
+            ciphertext_ref,
+            jitter_bps,
+        ))
+        .await
+    }
+
+    pub async fn store_dca_schedule(
+        &self,
+        subscription: &Pubkey,
+        nonce: u128,
+        encrypted_schedule: [[u8; 32]; mpc::DCA_SCHEDULE_CIPHERTEXTS],
+    ) -> Result<Signature> {
+        self.send(mpc::store_dca_schedule(
+            subscription,
+            &self.payer(),
+            nonce,
+            encrypted_schedule,
+        ))
+        .await
+    }
+
+    pub async fn cancel_subscription(&self, id: u64) -> Result<Signature> {
+        self.send(mpc::cancel_subscription(&self.payer(), id)).await
+    }
+
+    /// Enroll a due subscription in `batch`, reading the order index the
+    /// batch is at.
+    pub async fn enroll_subscription(
+        &self,
+        batch: &Pubkey,
+        subscription: &Pubkey,
+    ) -> Result<Signature> {
+        let order_index = self.batch(batch).await?.order_count;
+        self.send(mpc::enroll_subscription(
+            batch,
+            subscription,
+            &self.payer(),
+            order_index,
+        ))
+        .await
+    }
+
+    pub async fn subscription(&self, owner: &Pubkey, id: u64) -> Result<mpc::Subscription> {
+        self.fetch_mpc(&mpc::subscription_address(owner, id)).await
+    }
+}
@@ -0,0 +1,343 @@
+//! Client-side order encryption for the Arcium MXE.
+//!
+//! The client generates an ephemeral x25519 key, derives a Rescue key from
+//! the shared secret with the MXE cluster key, and encrypts the order in CTR
+//! mode. The relay only ever sees the ciphertext, the ephemeral public key
+//! and the nonce; the MXE recomputes the shared secret inside the cluster.
+//!
+//! Every `Enc<Shared, T>` circuit parameter is passed as its own
+//! `(public key, nonce, ciphertext)` triple with the CTR counter starting
+//! at zero, so each parameter encrypted under one key needs a distinct
+//! nonce. [`MxeSession`] hands those out.
+
+use anchor_lang::prelude::Pubkey;
+use ark_ff::PrimeField;
+use sha2::{Digest, Sha256};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::error::{Result, SdkError};
+use crate::mpc::{PORTFOLIO_CIPHERTEXTS, PORTFOLIO_MARKETS, SUMMARY_NOTES};
+use crate::rescue::{self, Fq, RescueCipher, RescuePrimeHash};
+use crate::solana_pay::EncryptedOrder;
+
+/// MXE cluster key for devnet cluster 123, from MXE account
+/// CUx5EJ6PtgWTHfiqmYbMgeDepaiqj1xu3Y2C6Q11Nqkb.
+pub const MXE_PUBLIC_KEY_DEVNET: [u8; 32] = [
+    0x55, 0x91, 0x2e, 0xe0, 0x36, 0x7b, 0xbb, 0xf2, 0x0e, 0xb4, 0x97, 0xb7, 0xb1, 0x68, 0x01, 0x36,
+    0x7c, 0x18, 0xc3, 0xb1, 0x07, 0x10, 0xff, 0x37, 0x71, 0xe5, 0x55, 0x1f, 0x8b, 0xc9, 0x5b, 0xaa,
+];
+
+/// Rescue cipher keyed by the x25519 shared secret between `secret` and
+/// `peer`. The MXE derives the same cipher from its own secret and the
+/// client's public key.
+pub fn shared_cipher(secret: &StaticSecret, peer: &[u8; 32]) -> RescueCipher {
+    let shared = secret.diffie_hellman(&PublicKey::from(*peer));
+    // Single-step KDF: H(counter = 1 || Z).
+    let key = RescuePrimeHash::default().digest(&[
+        Fq::from(1u64),
+        Fq::from_le_bytes_mod_order(shared.as_bytes()),
+    ]);
+    RescueCipher::new(&key)
+}
+
+/// Mirror of `circuits::OrderData` in `encrypted-ixs`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OrderData {
+    /// USDC amount in atomic units
+    pub usdc_amount: u64,
+    /// Destination wallet bytes 0..16, little-endian
+    pub wallet_lo: u128,
+    /// Destination wallet bytes 16..32, little-endian
+    pub wallet_hi: u128,
+    /// Unix seconds after which the MPC drops the order from the batch
+    /// total and refunds it; `u64::MAX` never expires
+    pub expires_at: u64,
+}
+
+impl OrderData {
+    /// An order that never expires.
+    pub fn new(usdc_amount: u64, wallet: &Pubkey) -> Self {
+        let bytes = wallet.to_bytes();
+        Self {
+            usdc_amount,
+            wallet_lo: u128::from_le_bytes(bytes[..16].try_into().expect("16 bytes")),
+            wallet_hi: u128::from_le_bytes(bytes[16..].try_into().expect("16 bytes")),
+            expires_at: u64::MAX,
+        }
+    }
+
+    /// The same order, expiring at `expires_at` (unix seconds).
+    pub fn expiring_at(self, expires_at: u64) -> Self {
+        Self { expires_at, ..self }
+    }
+
+    /// Whether the MPC treats the order as expired at `now`.
+    pub fn is_expired(&self, now: u64) -> bool {
+        now > self.expires_at
+    }
+
+    pub fn wallet(&self) -> Pubkey {
+        let mut bytes = [0u8; 32];
+        bytes[..16].copy_from_slice(&self.wallet_lo.to_le_bytes());
+        bytes[16..].copy_from_slice(&self.wallet_hi.to_le_bytes());
+        Pubkey::new_from_array(bytes)
+    }
+
+    /// Field elements in struct field order.
+    pub fn to_fields(&self) -> [Fq; 4] {
+        [
+            Fq::from(self.usdc_amount),
+            Fq::from(self.wallet_lo),
+            Fq::from(self.wallet_hi),
+            Fq::from(self.expires_at),
+        ]
+    }
+
+    pub fn from_fields(fields: &[Fq]) -> Result<Self> {
+        let [amount, lo, hi, expires_at] = fields else {
+            return Err(SdkError::InvalidCiphertext);
+        };
+        Ok(Self {
+            usdc_amount: field_to_u64(amount)?,
+            wallet_lo: field_to_u128(lo)?,
+            wallet_hi: field_to_u128(hi)?,
+            expires_at: field_to_u64(expires_at)?,
+        })
+    }
+}
+
+fn field_to_u64(x: &Fq) -> Result<u64> {
+    field_to_u128(x)?
+        .try_into()
+        .map_err(|_| SdkError::InvalidCiphertext)
+}
+
+fn field_to_u128(x: &Fq) -> Result<u128> {
+    let bytes = rescue::to_bytes(x);
+    if bytes[16..].iter().any(|b| *b != 0) {
+        return Err(SdkError::InvalidCiphertext);
+    }
+    Ok(u128::from_le_bytes(
+        bytes[..16].try_into().expect("16 bytes"),
+    ))
+}
+
+/// One `Enc<Shared, T>` circuit argument, in `ArgBuilder` order:
+/// `x25519_pubkey`, `plaintext_u128(nonce)`, then one 32-byte ciphertext
+/// per field element of `T`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SharedCiphertext {
+    pub public_key: [u8; 32],
+    pub nonce: u128,
+    pub ciphertexts: Vec<[u8; 32]>,
+}
+
+impl SharedCiphertext {
+    /// Parse ciphertext elements back into field elements.
+    pub fn fields(&self) -> Result<Vec<Fq>> {
+        self.ciphertexts
+            .iter()
+            .map(|c| rescue::from_bytes(c).ok_or(SdkError::InvalidCiphertext))
+            .collect()
+    }
+
+    /// Concatenated ciphertext bytes, as carried in the order memo.
+    pub fn ciphertext_bytes(&self) -> Vec<u8> {
+        self.ciphertexts.concat()
+    }
+}
+
+/// Encryption context between one ephemeral client key and the MXE.
+pub struct MxeSession {
+    public_key: [u8; 32],
+    cipher: RescueCipher,
+    next_nonce: u128,
+}
+
+impl MxeSession {
+    /// New session under a fresh ephemeral key and random starting nonce.
+    pub fn new(mxe_public_key: &[u8; 32]) -> Result<Self> {
+        let mut secret = [0u8; 32];
+        let mut nonce = [0u8; 16];
+        getrandom::getrandom(&mut secret).map_err(|_| SdkError::Randomness)?;
+        getrandom::getrandom(&mut nonce).map_err(|_| SdkError::Randomness)?;
+        Ok(Self::from_parts(
+            StaticSecret::from(secret),
+            mxe_public_key,
+            u128::from_le_bytes(nonce),
+        ))
+    }
+
+    /// Session from an explicit key and starting nonce.
+    pub fn from_parts(secret: StaticSecret, mxe_public_key: &[u8; 32], nonce: u128) -> Self {
+        Self {
+            public_key: PublicKey::from(&secret).to_bytes(),
+            cipher: shared_cipher(&secret, mxe_public_key),
+            next_nonce: nonce,
+        }
+    }
+
+    pub fn public_key(&self) -> [u8; 32] {
+        self.public_key
+    }
+
+    /// Encrypt one circuit argument under the next unused nonce.
+    pub fn encrypt(&mut self, plaintext: &[Fq]) -> SharedCiphertext {
+        let nonce = self.next_nonce;
+        self.next_nonce = nonce.wrapping_add(1);
+        SharedCiphertext {
+            public_key: self.public_key,
+            nonce,
+            ciphertexts: self
+                .cipher
+                .encrypt(plaintext, &nonce.to_le_bytes())
+                .iter()
+                .map(rescue::to_bytes)
+                .collect(),
+        }
+    }
+
+    /// `plaintext` split into consecutive `Enc<Shared, _>` arguments of at
+    /// most `chunk` field elements, each under its own nonce, for structs
+    /// too large for one queue instruction (see [`crate::args`]).
+    pub fn encrypt_chunked(&mut self, plaintext: &[Fq], chunk: usize) -> Vec<SharedCiphertext> {
+        plaintext
+            .chunks(chunk.max(1))
+            .map(|fields| self.encrypt(fields))
+            .collect()
+    }
+
+    /// `usdc_amount` and `expires_at` for `add_to_batch`, each its own
+    /// `Enc<Shared, u64>` argument.
+    pub fn add_to_batch_args(&mut self, order: &OrderData) -> [SharedCiphertext; 2] {
+        [order.usdc_amount, order.expires_at].map(|field| self.encrypt(&[Fq::from(field)]))
+    }
+
+    /// `order_amount`, `wallet_lo`, `wallet_hi`, `expires_at` for
+    /// `compute_distribution`, each its own `Enc<Shared, _>` argument.
+    pub fn compute_distribution_args(&mut self, order: &OrderData) -> [SharedCiphertext; 4] {
+        order.to_fields().map(|field| self.encrypt(&[field]))
+    }
+
+    /// `NoteRefs` for `sum_my_notes`: the leaf index and amount of each
+    /// note, at most [`SUMMARY_NOTES`] of them. Unused slots are padded
+    /// with zero amounts.
+    pub fn sum_my_notes_args(&mut self, notes: &[(u32, u64)]) -> Result<SharedCiphertext> {
+        if notes.len() > SUMMARY_NOTES {
+            return Err(SdkError::TooManyNotes {
+                count: notes.len(),
+                max: SUMMARY_NOTES,
+            });
+        }
+        let mut leaf_indices = [Fq::from(0u64); SUMMARY_NOTES];
+        let mut amounts = [Fq::from(0u64); SUMMARY_NOTES];
+        for (i, (leaf_index, amount)) in notes.iter().enumerate() {
+            leaf_indices[i] = Fq::from(*leaf_index as u64);
+            amounts[i] = Fq::from(*amount);
+        }
+        Ok(self.encrypt(&[leaf_indices, amounts].concat()))
+    }
+
+    /// The whole `OrderData` as one argument, as sent in order memos.
+    pub fn order_data_args(&mut self, order: &OrderData) -> SharedCiphertext {
+        self.encrypt(&order.to_fields())
+    }
+}
+
+/// Decrypt a shared ciphertext with the peer's secret, i.e. what the MXE
+/// does with its cluster key. Used for tests and local simulation.
+pub fn decrypt(secret: &StaticSecret, ciphertext: &SharedCiphertext) -> Result<Vec<Fq>> {
+    Ok(shared_cipher(secret, &ciphertext.public_key)
+        .decrypt(&ciphertext.fields()?, &ciphertext.nonce.to_le_bytes()))
+}
+
+/// Read the share amount of an `EncryptedDistribution` with the secret
+/// the order was encrypted under.
+pub fn decrypt_shares(
+    secret: &StaticSecret,
+    mxe_public_key: &[u8; 32],
+    nonce: u128,
+    encrypted_shares: &[u8; 32],
+) -> Result<u64> {
+    let ciphertext = rescue::from_bytes(encrypted_shares).ok_or(SdkError::InvalidCiphertext)?;
+    let plaintext =
+        shared_cipher(secret, mxe_public_key).decrypt(&[ciphertext], &nonce.to_le_bytes());
+    field_to_u64(&plaintext[0])
+}
+
+/// Read the `sum_my_notes` output with the secret the notes were
+/// encrypted under.
+pub fn decrypt_balance(
+    secret: &StaticSecret,
+    mxe_public_key: &[u8; 32],
+    nonce: u128,
+    encrypted_total: &[u8; 32],
+) -> Result<u64> {
+    decrypt_shares(secret, mxe_public_key, nonce, encrypted_total)
+}
+
+/// Key identifying `market_id` inside an encrypted portfolio: the first
+/// 8 bytes of sha256(market_id), little-endian. Never zero, since zero
+/// marks an empty slot.
+pub fn portfolio_market_key(market_id: &str) -> u64 {
+    let digest = Sha256::digest(market_id.as_bytes());
+    u64::from_le_bytes(digest[..8].try_into().expect("8 bytes")).max(1)
+}
+
+/// One non-empty slot of a decrypted portfolio.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PortfolioPosition {
+    /// [`portfolio_market_key`] of the market
+    pub market: u64,
+    pub shares: u64,
+}
+
+/// Read the `reveal_my_position` output with the secret the request was
+/// encrypted under. Empty slots are skipped.
+pub fn decrypt_portfolio(
+    secret: &StaticSecret,
+    mxe_public_key: &[u8; 32],
+    nonce: u128,
+    ciphertexts: &[[u8; 32]; PORTFOLIO_CIPHERTEXTS],
+) -> Result<Vec<PortfolioPosition>> {
+    let ciphertexts = ciphertexts
+        .iter()
+        .map(|c| rescue::from_bytes(c).ok_or(SdkError::InvalidCiphertext))
+        .collect::<Result<Vec<_>>>()?;
+    let plaintext = shared_cipher(secret, mxe_public_key)
+        .decrypt(&ciphertexts, &nonce.to_le_bytes())
+        .iter()
+        .map(field_to_u64)
+        .collect::<Result<Vec<u64>>>()?;
+    let (markets, shares) = plaintext.split_at(PORTFOLIO_MARKETS);
+    Ok(markets
+        .iter()
+        .zip(shares)
+        .filter(|(market, _)| **market != 0)
+        .map(|(&market, &shares)| PortfolioPosition { market, shares })
+        .collect())
+}
+
+/// Encrypt an order for the MXE under a fresh ephemeral key and nonce.
+/// With `expires_at`, the order is dropped and refunded if its batch is
+/// still open by then.
+pub fn encrypt_order(
+    mxe_public_key: &[u8; 32],
+    market_id: &str,
+    side: u8,
+    usdc_amount: u64,
+    wallet: &Pubkey,
+    expires_at: Option<u64>,
+) -> Result<EncryptedOrder> {
+    let order = OrderData::new(usdc_amount, wallet);
+    let order = expires_at.map_or(order, |expires_at| order.expiring_at(expires_at));
+    let args = MxeSession::new(mxe_public_key)?.order_data_args(&order);
+
+    Ok(EncryptedOrder {
+        market_id: market_id.to_string(),
+        side,
+        ciphertext: args.ciphertext_bytes(),
+        public_key: args.public_key,
+        nonce: args.nonce.to_le_bytes(),
+    })
+}
@@ -0,0 +1,47 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SdkError {
+    #[error("Transaction serialization failed: {0}")]
+    Serialization(#[from] bincode::Error),
+    #[error("Transaction is {size} bytes, over the {max} byte packet limit")]
+    TransactionTooLarge { size: usize, max: usize },
+    #[error("v0 message does not compile: {0}")]
+    MessageCompile(String),
+    #[error("{count} addresses are more than the {max} a lookup table holds")]
+    TooManyAddresses { count: usize, max: usize },
+    #[error("Circuit arguments are {size} bytes, over the {max} bytes a queue instruction fits")]
+    ArgumentsTooLarge { size: usize, max: usize },
+    #[error("{count} notes are more than the {max} one balance summary covers")]
+    TooManyNotes { count: usize, max: usize },
+    #[error("System randomness unavailable")]
+    Randomness,
+    #[error("Poseidon hash failed")]
+    Hash,
+    #[error("Invalid nullifier purpose: {0}")]
+    InvalidNullifierPurpose(u8),
+    #[error("Unsupported commitment version: {0}")]
+    UnsupportedCommitmentVersion(u8),
+    #[error("Split outputs add up to {outputs}, not the note's {note}")]
+    SplitMismatch { outputs: u128, note: u64 },
+    #[error("Ciphertext is malformed or does not decode to the expected layout")]
+    InvalidCiphertext,
+    #[error("Account {0} not found")]
+    AccountNotFound(anchor_lang::prelude::Pubkey),
+    #[error("Account data does not match the expected layout")]
+    InvalidAccountData,
+    #[error("Transaction JSON is missing or has a malformed {0}")]
+    InvalidTransaction(String),
+    #[error("Revealed distribution for order {order_index} does not match the simulation")]
+    DistributionMismatch { order_index: u8 },
+    #[error("Computation definitions not registered: {0}")]
+    CompDefsNotRegistered(String),
+    #[error("Archive verification failed: {0}")]
+    InvalidArchive(String),
+    #[error("Inconsistent batch observation: {0}")]
+    InvalidObservation(String),
+    #[error("Transport error: {0}")]
+    Transport(String),
+}
+
+pub type Result<T> = std::result::Result<T, SdkError>;
@@ -0,0 +1,249 @@
+//! What revealed batch totals give away about individual orders.
+//!
+//! A closed batch reveals its order count and its total, exactly
+//! (`reveal_batch_total`) or rounded up to a bucket
+//! (`reveal_bucketed_total`). An observer who also knows the public order
+//! size limits, and possibly some of the orders (their own, or those of
+//! colluding users), can bound any other order in the batch. This module
+//! computes those bounds so integrators can pick a minimum order count and
+//! a bucket size from a target, rather than by feel.
+//!
+//! For each batch the estimate gives:
+//!
+//! - the interval every unknown order must lie in
+//! - `entropy_bits`: log2 of the amounts left in that interval at the
+//!   model's resolution, an upper bound on the observer's uncertainty
+//! - `rms_error`: the error of the observer's best guess (an even split of
+//!   the unknown remainder) under a uniform prior over splits
+//!
+//! Expired orders count towards the order count but not the total. The
+//! estimates treat every counted order as live, which is the observer's
+//! best case.
+
+use crate::mpc::MAX_BATCH_ORDERS;
+use crate::simulation::REVEAL_BUCKET;
+use crate::{Result, SdkError};
+
+/// What an observer sees of one closed batch, plus what they already
+/// know about it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BatchObservation {
+    /// Revealed total; the bucket's upper edge when bucketed
+    pub revealed_total: u64,
+    pub order_count: u8,
+    /// Width of the reveal bucket; 1 for an exact total
+    pub bucket: u64,
+    /// Orders in the batch the observer already knows
+    pub known_orders: u8,
+    /// Sum of those orders
+    pub known_total: u64,
+}
+
+impl BatchObservation {
+    /// A `reveal_batch_total` output.
+    pub fn exact(total: u64, order_count: u8) -> Self {
+        Self {
+            revealed_total: total,
+            order_count,
+            bucket: 1,
+            known_orders: 0,
+            known_total: 0,
+        }
+    }
+
+    /// A `reveal_bucketed_total` output.
+    pub fn bucketed(bucketed_total: u64, order_count: u8) -> Self {
+        Self {
+            bucket: REVEAL_BUCKET,
+            ..Self::exact(bucketed_total, order_count)
+        }
+    }
+
+    /// The observer placed or learned `orders` orders summing to `total`.
+    pub fn with_known(mut self, orders: u8, total: u64) -> Self {
+        self.known_orders = orders;
+        self.known_total = total;
+        self
+    }
+
+    /// Range the exact batch total lies in.
+    pub fn total_range(&self) -> (u64, u64) {
+        if self.revealed_total == 0 {
+            return (0, 0);
+        }
+        let low = self
+            .revealed_total
+            .saturating_sub(self.bucket.max(1) - 1)
+            .max(1);
+        (low, self.revealed_total)
+    }
+}
+
+/// Public limits on order sizes, and how finely amounts are told apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ObserverModel {
+    pub min_order: u64,
+    pub max_order: u64,
+    /// Smallest amount difference that matters, in atomic units
+    pub resolution: u64,
+}
+
+impl Default for ObserverModel {
+    /// Any order size, told apart to the cent.
+    fn default() -> Self {
+        Self {
+            min_order: 1,
+            max_order: u64::MAX,
+            resolution: 10_000,
+        }
+    }
+}
+
+/// Bounds on one unknown order of a batch.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LeakageEstimate {
+    /// Orders the observer does not know
+    pub unknown_orders: u8,
+    pub min_amount: u64,
+    pub max_amount: u64,
+    /// The observer's best guess: an even split of the unknown remainder
+    pub best_guess: u64,
+    pub rms_error: f64,
+    pub entropy_bits: f64,
+}
+
+impl LeakageEstimate {
+    /// Whether the order is pinned down to within the model's resolution.
+    pub fn is_exposed(&self) -> bool {
+        self.unknown_orders > 0 && self.entropy_bits == 0.0
+    }
+}
+
+/// Bound what `batch` reveals about each order the observer doesn't know.
+pub fn estimate(batch: &BatchObservation, model: &ObserverModel) -> Result<LeakageEstimate> {
+    let invalid = |reason: &str| Err(SdkError::InvalidObservation(reason.to_string()));
+    if batch.known_orders > batch.order_count {
+        return invalid("more known orders than the batch holds");
+    }
+    if model.min_order > model.max_order || model.resolution == 0 {
+        return invalid("order size limits are inverted or resolution is zero");
+    }
+
+    let unknown = batch.order_count - batch.known_orders;
+    let (total_low, total_high) = batch.total_range();
+    if batch.known_total > total_high {
+        return invalid("known orders exceed the revealed total");
+    }
+    if unknown == 0 {
+        return Ok(LeakageEstimate {
+            unknown_orders: 0,
+            min_amount: 0,
+            max_amount: 0,
+            best_guess: 0,
+            rms_error: 0.0,
+            entropy_bits: 0.0,
+        });
+    }
+
+    // What the unknown orders sum to
+    let rest_low = total_low.saturating_sub(batch.known_total) as u128;
+    let rest_high = (total_high - batch.known_total) as u128;
+    let others = (unknown - 1) as u128;
+    let (min_order, max_order) = (model.min_order as u128, model.max_order as u128);
+
+    let max_amount = rest_high.saturating_sub(others * min_order).min(max_order);
+    let min_amount = rest_low
+        .saturating_sub(others * max_order)
+        .max(min_order)
+        .min(max_amount);
+
+    let rest_mid = (rest_low + rest_high) as f64 / 2.0;
+    let best_guess = ((rest_mid / unknown as f64) as u128).clamp(min_amount, max_amount);
+
+    // Orders are min_order plus a share of the slack; for n shares of a
+    // uniform split of s, each has variance s²(n-1)/(n²(n+1)). The bucket
+    // adds the variance of a uniform total over its width.
+    let n = unknown as f64;
+    let slack = (rest_mid - n * model.min_order as f64).max(0.0);
+    let split_variance = slack * slack * (n - 1.0) / (n * n * (n + 1.0));
+    let width = (rest_high - rest_low) as f64;
+    let bucket_variance = width * width / (12.0 * n * n);
+
+    let amounts = (max_amount - min_amount) / model.resolution as u128 + 1;
+    Ok(LeakageEstimate {
+        unknown_orders: unknown,
+        min_amount: min_amount as u64,
+        max_amount: max_amount as u64,
+        best_guess: best_guess as u64,
+        rms_error: (split_variance + bucket_variance).sqrt(),
+        entropy_bits: (amounts as f64).log2(),
+    })
+}
+
+/// Leakage across a sequence of batches.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LeakageSummary {
+    pub estimates: Vec<LeakageEstimate>,
+    /// Index of the batch with the least entropy left, among those with
+    /// unknown orders
+    pub worst: Option<usize>,
+    pub mean_entropy_bits: f64,
+    /// Batches with an order pinned down to the model's resolution
+    pub exposed: usize,
+}
+
+/// [`estimate`] every batch in `batches`.
+pub fn summarize(batches: &[BatchObservation], model: &ObserverModel) -> Result<LeakageSummary> {
+    let estimates = batches
+        .iter()
+        .map(|batch| estimate(batch, model))
+        .collect::<Result<Vec<_>>>()?;
+    let counted: Vec<(usize, f64)> = estimates
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.unknown_orders > 0)
+        .map(|(index, e)| (index, e.entropy_bits))
+        .collect();
+    let worst = counted
+        .iter()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(index, _)| *index);
+    let mean_entropy_bits = if counted.is_empty() {
+        0.0
+    } else {
+        counted.iter().map(|(_, bits)| bits).sum::<f64>() / counted.len() as f64
+    };
+    let exposed = estimates.iter().filter(|e| e.is_exposed()).count();
+    Ok(LeakageSummary {
+        estimates,
+        worst,
+        mean_entropy_bits,
+        exposed,
+    })
+}
+
+/// Smallest order count at which a batch of `average_order`-sized orders,
+/// revealed with `bucket`, leaves at least `target_bits` of entropy on
+/// each order when the observer knows `known_orders` of them. `None` if
+/// no batch up to `MAX_BATCH_ORDERS` gets there.
+pub fn min_order_count(
+    average_order: u64,
+    bucket: u64,
+    known_orders: u8,
+    target_bits: f64,
+    model: &ObserverModel,
+) -> Option<u8> {
+    (known_orders.saturating_add(1)..=MAX_BATCH_ORDERS).find(|&count| {
+        let total = average_order.saturating_mul(count as u64);
+        let revealed = total.div_ceil(bucket.max(1)) * bucket.max(1);
+        let batch = BatchObservation {
+            bucket,
+            ..BatchObservation::exact(revealed, count)
+        }
+        .with_known(
+            known_orders,
+            average_order.saturating_mul(known_orders as u64),
+        );
+        estimate(&batch, model).is_ok_and(|e| e.entropy_bits >= target_bits)
+    })
+}
@@ -0,0 +1,28 @@
+//! Obsidian SDK
+//!
+//! Client-side helpers for the privacy pool and blind batch programs.
+//! Shared by the relay, bots, and any frontend that needs to build
+//! Obsidian transactions without going through the relay API.
+
+pub mod archive;
+pub mod args;
+#[cfg(feature = "client")]
+pub mod client;
+pub mod encryption;
+pub mod error;
+pub mod leakage;
+pub mod lookup_table;
+pub mod mpc;
+pub mod netting;
+pub mod note;
+pub mod pool;
+pub mod recovery;
+pub mod replay;
+pub mod rescue;
+pub mod simulation;
+pub mod solana_pay;
+pub mod verifier;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use error::{Result, SdkError};
@@ -0,0 +1,152 @@
+//! Address lookup tables and v0 transactions for distribution cranks.
+//!
+//! Every distribution crank names the batch, the config, the relay's nonce
+//! account, the system program and the event authority, plus a distribution PDA and an order
+//! slot for its order. Listed in a lookup table, each of those costs one
+//! byte in a v0 transaction instead of 32, so several distributions fit in
+//! one packet.
+//!
+//! The relay keeps one table per batch: [`create_lookup_table`] once the
+//! batch closes, [`extend_instructions`] with [`distribution_accounts`] and
+//! whatever token accounts the payouts touch, then [`compile_v0`] each
+//! crank transaction against it. Once the batch is settled the table is
+//! deactivated and, after the deactivation cooldown, closed for its rent.
+
+use std::collections::HashSet;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::system_program;
+use solana_address_lookup_table_interface::instruction as alt;
+use solana_address_lookup_table_interface::state::{
+    AddressLookupTable, LOOKUP_TABLE_MAX_ADDRESSES,
+};
+use solana_hash::Hash;
+use solana_message::{v0, VersionedMessage};
+use solana_signature::Signature;
+use solana_transaction::versioned::VersionedTransaction;
+
+use crate::mpc;
+use crate::solana_pay::PACKET_DATA_SIZE;
+use crate::{Result, SdkError};
+
+pub use solana_address_lookup_table_interface::program::ID as LOOKUP_TABLE_PROGRAM_ID;
+pub use solana_message::AddressLookupTableAccount;
+
+/// Addresses per `extend` instruction, so the instruction fits one
+/// transaction alongside its signer
+pub const MAX_EXTEND_ADDRESSES: usize = 20;
+
+/// Accounts shared by the distribution cranks of `batch`, then each order's
+/// distribution PDA and order slot.
+pub fn distribution_accounts(
+    batch: &Pubkey,
+    authority: &Pubkey,
+    market_id: &str,
+    order_count: u8,
+) -> Vec<Pubkey> {
+    let mut accounts = vec![
+        *batch,
+        mpc::config_address(),
+        mpc::relay_nonce_address(authority),
+        mpc::market_stats_address(market_id),
+        system_program::ID,
+        mpc::event_authority_address(),
+    ];
+    for order_index in 0..order_count {
+        accounts.push(mpc::distribution_address(batch, order_index));
+        accounts.push(mpc::order_slot_address(batch, order_index));
+    }
+    accounts
+}
+
+/// Create a table owned by `authority`, returning it with its address.
+/// `recent_slot` must be a recent rooted slot.
+pub fn create_lookup_table(
+    authority: &Pubkey,
+    payer: &Pubkey,
+    recent_slot: u64,
+) -> (Instruction, Pubkey) {
+    alt::create_lookup_table(*authority, *payer, recent_slot)
+}
+
+/// Instructions adding the `addresses` missing from `table`, whose current
+/// entries are `existing`; one transaction each.
+pub fn extend_instructions(
+    table: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    existing: &[Pubkey],
+    addresses: &[Pubkey],
+) -> Result<Vec<Instruction>> {
+    let mut seen: HashSet<Pubkey> = existing.iter().copied().collect();
+    let missing: Vec<Pubkey> = addresses
+        .iter()
+        .filter(|address| seen.insert(**address))
+        .copied()
+        .collect();
+
+    if existing.len() + missing.len() > LOOKUP_TABLE_MAX_ADDRESSES {
+        return Err(SdkError::TooManyAddresses {
+            count: existing.len() + missing.len(),
+            max: LOOKUP_TABLE_MAX_ADDRESSES,
+        });
+    }
+
+    Ok(missing
+        .chunks(MAX_EXTEND_ADDRESSES)
+        .map(|chunk| alt::extend_lookup_table(*table, *authority, Some(*payer), chunk.to_vec()))
+        .collect())
+}
+
+/// Stop `table` from being extended or used; it can be closed once the
+/// deactivation slot leaves the slot hashes, about 513 slots later.
+pub fn deactivate_lookup_table(table: &Pubkey, authority: &Pubkey) -> Instruction {
+    alt::deactivate_lookup_table(*table, *authority)
+}
+
+/// Close a deactivated `table`, paying its rent to `recipient`.
+pub fn close_lookup_table(table: &Pubkey, authority: &Pubkey, recipient: &Pubkey) -> Instruction {
+    alt::close_lookup_table(*table, *authority, *recipient)
+}
+
+/// Decode the table account at `address`.
+pub fn decode_lookup_table(address: &Pubkey, data: &[u8]) -> Result<AddressLookupTableAccount> {
+    let table = AddressLookupTable::deserialize(data).map_err(|_| SdkError::InvalidAccountData)?;
+    Ok(AddressLookupTableAccount {
+        key: *address,
+        addresses: table.addresses.to_vec(),
+    })
+}
+
+/// Compile `instructions` into a v0 message resolving accounts through
+/// `tables`, failing if the signed transaction would not fit one packet.
+pub fn compile_v0(
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    tables: &[AddressLookupTableAccount],
+    recent_blockhash: Hash,
+) -> Result<VersionedMessage> {
+    let message = v0::Message::try_compile(payer, instructions, tables, recent_blockhash)
+        .map_err(|e| SdkError::MessageCompile(e.to_string()))?;
+    let message = VersionedMessage::V0(message);
+
+    let size = transaction_size(&message)?;
+    if size > PACKET_DATA_SIZE {
+        return Err(SdkError::TransactionTooLarge {
+            size,
+            max: PACKET_DATA_SIZE,
+        });
+    }
+    Ok(message)
+}
+
+/// Serialized size of `message` once signed by all its signers.
+pub fn transaction_size(message: &VersionedMessage) -> Result<usize> {
+    let signers = message.header().num_required_signatures as usize;
+    let tx = VersionedTransaction {
+        signatures: vec![Signature::default(); signers],
+        message: message.clone(),
+    };
+    Ok(bincode::serialized_size(&tx)? as usize)
+}
@@ -0,0 +1,2624 @@
+//! Client-side interface for the `obsidian_mpc` program.
+//!
+//! The program depends on `arcium-anchor` and cannot be linked into client
+//! builds, so its accounts and batch instructions are mirrored here by hand.
+//! Keep discriminators, field order and account lists in step with
+//! `arcium-relay/programs/obsidian_mpc/src/lib.rs`.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::system_program;
+use sha2::{Digest, Sha256};
+
+use crate::{Result, SdkError};
+
+pub const ID: Pubkey = pubkey!("8postM9mUCTKTu6a1vkrhfg8erso2g8eHo8bmc9JZjZc");
+
+/// Arcium program matching arcium-anchor 0.5.4.
+pub const ARCIUM_PROGRAM_ID: Pubkey = pubkey!("F3G6Q9tRicyznCqcZLydJ6RxkwDSBeHWM458J7V6aeyk");
+
+/// Every circuit in `encrypted-ixs`, in deploy order.
+pub const CIRCUITS: &[&str] = &[
+    "init_batch",
+    "add_to_batch",
+    "add_to_batch_normalized",
+    "reveal_batch_total",
+    "compute_distribution",
+    "reveal_bucketed_total",
+    "compute_distribution_from_stats",
+    "compute_encrypted_distribution",
+    "rekey_batch_stats",
+    "import_batch_stats",
+    "init_portfolio",
+    "accumulate_position",
+    "reveal_my_position",
+    "route_order",
+    "peek_order_count",
+    "init_dca_schedule",
+    "add_dca_to_batch",
+    "sum_my_notes",
+];
+
+/// Relayer keys that can approve large executions.
+pub const MAX_EXECUTION_APPROVERS: usize = 5;
+
+/// Programs `record_order` can accept calls from while the CPI guard is on.
+pub const MAX_CPI_CALLERS: usize = 4;
+
+/// Minimum seconds a batch stays open on mainnet builds of the program.
+pub const MIN_BATCH_DURATION: i64 = 60;
+
+/// Depth of a batch's distribution tree: one leaf per possible order index.
+pub const DISTRIBUTION_TREE_DEPTH: usize = 8;
+
+/// Prefix of the message a user signs for a delegated order.
+pub const ORDER_INTENT_DOMAIN: &[u8] = b"obsidian:order_intent:v1";
+
+/// Session key scope: sign order intents for [`record_delegated_order`].
+pub const SESSION_SCOPE_RECORD_ORDER: u8 = 1 << 0;
+
+/// Longest a session key may live, in seconds.
+pub const MAX_SESSION_DURATION: i64 = 7 * 24 * 60 * 60;
+
+/// Longest market id a subscription may name.
+pub const MAX_SUBSCRIPTION_MARKET_ID_LEN: usize = 64;
+
+/// Largest per-enrollment jitter of a subscription, in basis points of
+/// the even split.
+pub const MAX_DCA_JITTER_BPS: u16 = 5_000;
+
+/// Ciphertexts in an `Enc<Mxe, DcaSchedule>`.
+pub const DCA_SCHEDULE_CIPHERTEXTS: usize = 2;
+
+pub const ED25519_PROGRAM_ID: Pubkey = pubkey!("Ed25519SigVerify111111111111111111111111111");
+pub const INSTRUCTIONS_SYSVAR_ID: Pubkey = pubkey!("Sysvar1nstructions1111111111111111111111111");
+
+/// Orders a batch can hold: the MPC batch stats keep each order's amount
+/// and expiry.
+pub const MAX_BATCH_ORDERS: u8 = 32;
+
+/// Distributions one `mark_distributed_many` can settle.
+pub const MAX_DISTRIBUTIONS_PER_IX: usize = 8;
+
+/// Parallel batches a market can run, one per lane.
+pub const MAX_MARKET_LANES: usize = 4;
+
+/// `Batch::lane` of express batches, clear of the size lanes.
+pub const EXPRESS_LANE: u8 = u8::MAX;
+
+/// Markets tracked per user portfolio.
+pub const PORTFOLIO_MARKETS: usize = 8;
+/// Ciphertexts in an `Enc<Mxe, UserPortfolio>`: all market keys, then all
+/// share counts.
+pub const PORTFOLIO_CIPHERTEXTS: usize = 2 * PORTFOLIO_MARKETS;
+
+/// Notes one `sum_my_notes` call covers.
+pub const SUMMARY_NOTES: usize = 6;
+
+// ============================================================================
+// Accounts
+// ============================================================================
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BatchStatus {
+    #[default]
+    Open,
+    Closed,
+    Executed,
+    Distributing,
+    Completed,
+    Failed,
+    /// Escrow fell short of the batch accounting; see [`reconcile_batch`]
+    Frozen,
+}
+
+/// Priority lane [`create_batch`] opens a batch in.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BatchLane {
+    #[default]
+    Standard,
+    /// Shorter window, higher fee; see [`set_express_lane`]
+    Express,
+}
+
+impl BatchLane {
+    /// `Batch::lane` of a batch in this lane
+    pub fn index(self) -> u8 {
+        match self {
+            BatchLane::Standard => 0,
+            BatchLane::Express => EXPRESS_LANE,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Batch {
+    pub authority: Pubkey,
+    pub market_id: String,
+    pub side: u8,
+    pub status: BatchStatus,
+    pub order_count: u8,
+    pub total_usdc: u64,
+    pub total_shares: u64,
+    pub created_at: i64,
+    pub distributions_completed: u8,
+    pub recorded_shares: u64,
+    pub cluster_offset: u32,
+    pub circuit_version: u16,
+    pub label: String,
+    pub intent_hash: Option<[u8; 32]>,
+    pub filled_usdc: u64,
+    pub recorded_refunds: u64,
+    pub distribution_root: Option<[u8; 32]>,
+    pub transitioned_at: i64,
+    /// Escrow USDC parked in a yield adapter
+    pub parked_usdc: u64,
+    pub parked_adapter: Pubkey,
+    pub lane: u8,
+    /// Fee charged on fills; the express fee for express batches
+    pub fee_bps: u16,
+    /// Creation order across all batches; see [`BatchSequence`]
+    pub sequence: u64,
+    pub staged_distributions: u8,
+    pub recorded_distributions: u8,
+    pub distributions_finalized: bool,
+    /// Slot the total was revealed at; see [`expire_reveal`]
+    pub closed_slot: u64,
+    pub executed_slot: u64,
+    /// Share total [`stage_distributions`] committed; bounds recorded_shares
+    pub staged_shares: u64,
+}
+
+impl Batch {
+    pub fn is_express(&self) -> bool {
+        self.lane == EXPRESS_LANE
+    }
+
+    /// Whether the revealed total has waited more than `max_gap` slots at
+    /// `slot` (0 = no limit), so [`expire_reveal`] may reopen the batch.
+    pub fn reveal_expired(&self, max_gap: u64, slot: u64) -> bool {
+        max_gap > 0 && slot.saturating_sub(self.closed_slot) > max_gap
+    }
+}
+
+/// Return data of `close_batch` and `record_execution`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BatchResult {
+    pub batch: Pubkey,
+    pub status: BatchStatus,
+    pub order_count: u8,
+    pub total_usdc: u64,
+    pub total_shares: u64,
+    pub filled_usdc: u64,
+}
+
+impl BatchResult {
+    /// Decode a transaction's return data; `None` unless it was set by
+    /// this program.
+    pub fn from_return_data(program_id: &Pubkey, data: &[u8]) -> Option<Self> {
+        if *program_id != ID {
+            return None;
+        }
+        Self::try_from_slice(data).ok()
+    }
+}
+
+/// One recorded order of a batch; distributions require an undistributed slot.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OrderSlot {
+    pub batch: Pubkey,
+    pub order_index: u8,
+    pub distributed: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Distribution {
+    pub batch: Pubkey,
+    pub order_index: u8,
+    pub shares: u64,
+    pub usdc_refund: u64,
+    pub wallet: Pubkey,
+    pub executed: bool,
+    pub shortfall_claimed: bool,
+    pub unlock_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EncryptedDistribution {
+    pub batch: Pubkey,
+    pub order_index: u8,
+    pub encryption_key: [u8; 32],
+    pub nonce: u128,
+    pub encrypted_shares: [u8; 32],
+    pub wallet: Pubkey,
+    pub executed: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct StealthDistribution {
+    pub batch: Pubkey,
+    pub order_index: u8,
+    pub shares: u64,
+    pub claim_key: Pubkey,
+    pub commitment: [u8; 32],
+    pub claimed: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct UserPortfolio {
+    pub authority: Pubkey,
+    pub owner: Pubkey,
+    pub nonce: u128,
+    pub encrypted_positions: [[u8; 32]; PORTFOLIO_CIPHERTEXTS],
+    pub updates: u64,
+    pub updated_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MpcRegistry {
+    pub authority: Pubkey,
+    pub circuits: Vec<RegisteredCompDef>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegisteredCompDef {
+    pub offset: u32,
+    pub comp_def: Pubkey,
+    pub version: u16,
+    pub registered_at: i64,
+}
+
+impl MpcRegistry {
+    /// Whether `circuit` is registered at `version`.
+    pub fn is_registered(&self, circuit: &str, version: u16) -> bool {
+        let offset = comp_def_offset(&versioned_circuit(circuit, version));
+        self.circuits
+            .iter()
+            .any(|c| c.offset == offset && c.version == version)
+    }
+
+    /// Circuits from [`CIRCUITS`] not registered at `version`.
+    pub fn missing(&self, version: u16) -> Vec<&'static str> {
+        CIRCUITS
+            .iter()
+            .copied()
+            .filter(|circuit| !self.is_registered(circuit, version))
+            .collect()
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProtocolConfig {
+    pub admin: Pubkey,
+    pub cluster_offset: u32,
+    pub fallback_cluster_offset: u32,
+    pub circuit_version: u16,
+    pub updated_at: i64,
+    pub operator: Pubkey,
+    pub settler: Pubkey,
+    pub execution_timeout: i64,
+    pub completion_timeout: i64,
+    /// Batches above this total need [`approve_execution`] (0 = off)
+    pub large_batch_threshold: u64,
+    pub approval_threshold: u8,
+    pub approver_count: u8,
+    pub approvers: [Pubkey; MAX_EXECUTION_APPROVERS],
+    /// Adapter [`park_escrow`] deposits into (default = none)
+    pub yield_adapter: Pubkey,
+    pub yield_enabled: bool,
+    pub yield_recipient: Pubkey,
+    /// Adaptive batch windows; see [`set_batch_window`] (max 0 = off)
+    pub min_batch_window: i64,
+    pub max_batch_window: i64,
+    pub target_batch_orders: u8,
+    /// Limits who may call [`record_order`] through CPI; see
+    /// [`set_cpi_guard`]
+    pub cpi_guard_enabled: bool,
+    pub cpi_caller_count: u8,
+    pub cpi_callers: [Pubkey; MAX_CPI_CALLERS],
+    /// Express lane; see [`set_express_lane`] (window 0 = off)
+    pub express_batch_window: i64,
+    pub express_fee_bps: u16,
+    /// Slots a revealed total may wait for execution; see
+    /// [`set_max_reveal_exec_gap`] (0 = off)
+    pub max_reveal_exec_gap: u64,
+}
+
+/// Approvals of one large batch execution.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionApproval {
+    pub batch: Pubkey,
+    pub total_shares: u64,
+    pub filled_usdc: u64,
+    pub approver_count: u8,
+    pub approvers: [Pubkey; MAX_EXECUTION_APPROVERS],
+}
+
+/// Per-market aggregates over completed batches, plus the order arrival
+/// counters adaptive batch windows are computed from.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MarketStats {
+    pub market_id: String,
+    pub completed_batches: u64,
+    pub total_orders: u64,
+    pub total_usdc: u64,
+    pub total_shares: u64,
+    pub last_completed_at: i64,
+    /// Orders of recently closed batches, halved at every close
+    pub recent_orders: u64,
+    /// Seconds those batches were open, halved alongside `recent_orders`
+    pub recent_open_secs: u64,
+}
+
+impl MarketStats {
+    /// Seconds a batch must stay open before `close_batch` accepts it,
+    /// unless it reaches `target_batch_orders` first; mirrors the program.
+    pub fn batch_window(&self, config: &ProtocolConfig) -> i64 {
+        if config.max_batch_window == 0 {
+            return MIN_BATCH_DURATION;
+        }
+        let window = if self.recent_orders == 0 {
+            config.max_batch_window
+        } else {
+            (config.target_batch_orders as u128 * self.recent_open_secs as u128
+                / self.recent_orders as u128)
+                .min(i64::MAX as u128) as i64
+        };
+        window
+            .clamp(config.min_batch_window, config.max_batch_window)
+            .max(MIN_BATCH_DURATION)
+    }
+
+    /// Counters after `close_batch` closes a batch open for `open_secs`.
+    pub fn record_close(&mut self, order_count: u8, open_secs: i64) {
+        self.recent_orders = self.recent_orders / 2 + order_count as u64;
+        self.recent_open_secs = self.recent_open_secs / 2 + open_secs.max(0) as u64;
+    }
+
+    /// Whether `close_batch` accepts `batch` at `now`. Express batches
+    /// wait out the express window instead of the adaptive one.
+    pub fn can_close(&self, config: &ProtocolConfig, batch: &Batch, now: i64) -> bool {
+        let open_secs = now - batch.created_at;
+        let (min_open, window) = if batch.is_express() {
+            let window = config.express_batch_window.max(MIN_BATCH_DURATION);
+            (window, window)
+        } else {
+            (
+                MIN_BATCH_DURATION.max(config.min_batch_window),
+                self.batch_window(config),
+            )
+        };
+        let target_reached =
+            config.target_batch_orders > 0 && batch.order_count >= config.target_batch_orders;
+        open_secs >= min_open && (open_secs >= window || target_reached)
+    }
+}
+
+/// Program-wide batch counter behind `Batch::sequence`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BatchSequence {
+    /// Sequence of the newest batch; 0 before the first
+    pub last: u64,
+}
+
+/// Lot size passed to `add_to_batch_normalized` for one market.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MarketLotSize {
+    pub market_id: String,
+    pub lot_size: u64,
+    pub updated_at: i64,
+}
+
+/// Outcome mints `close_batch` and `record_execution` guard against trades of.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MarketOutcomeMints {
+    pub market_id: String,
+    pub yes_mint: Pubkey,
+    pub no_mint: Pubkey,
+    pub updated_at: i64,
+}
+
+impl MarketOutcomeMints {
+    /// Mint of the shares a batch on `side` buys (1 = YES, 0 = NO).
+    pub fn outcome_mint(&self, side: u8) -> Pubkey {
+        if side == 1 {
+            self.yes_mint
+        } else {
+            self.no_mint
+        }
+    }
+}
+
+/// Amount thresholds splitting a market into lanes.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MarketLanes {
+    pub market_id: String,
+    /// Ascending; lane i takes amounts in [thresholds[i - 1], thresholds[i])
+    pub thresholds: Vec<u64>,
+    pub updated_at: i64,
+}
+
+impl MarketLanes {
+    /// Lane for `usdc_amount`, as the `route_order` circuit computes it.
+    pub fn lane_for(&self, usdc_amount: u64) -> u8 {
+        self.thresholds
+            .iter()
+            .filter(|threshold| usdc_amount >= **threshold)
+            .count() as u8
+    }
+
+    /// `thresholds` argument of `route_order`: padded with `u64::MAX`,
+    /// which no amount reaches.
+    pub fn circuit_thresholds(&self) -> [u64; MAX_MARKET_LANES - 1] {
+        let mut padded = [u64::MAX; MAX_MARKET_LANES - 1];
+        for (slot, threshold) in padded.iter_mut().zip(&self.thresholds) {
+            *slot = *threshold;
+        }
+        padded
+    }
+}
+
+/// Last nonce a relay used on distribution recording, or a user on
+/// delegated order intents.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RelayNonce {
+    pub relay: Pubkey,
+    pub nonce: u64,
+}
+
+/// A temporary key a user lets sign for them within `scope`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SessionKey {
+    pub user: Pubkey,
+    pub session_key: Pubkey,
+    pub expires_at: i64,
+    /// `SESSION_SCOPE_*` bits
+    pub scope: u8,
+    pub created_at: i64,
+}
+
+/// A recurring order [`enroll_subscription`] records in a new batch at
+/// most once every `cadence` seconds.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Subscription {
+    pub owner: Pubkey,
+    pub id: u64,
+    pub market_id: String,
+    pub side: u8,
+    pub cadence: i64,
+    pub next_enroll_at: i64,
+    pub remaining: u32,
+    /// Hash of the encrypted order the relay resubmits at each enrollment
+    pub ciphertext_ref: [u8; 32],
+    /// Batch of the latest enrollment (default = none yet)
+    pub last_batch: Pubkey,
+    pub enrolled: u32,
+    pub created_at: i64,
+    /// Largest deviation from the even split per enrollment, in bps;
+    /// 0 for fixed amounts
+    pub jitter_bps: u16,
+    /// `Enc<Mxe, DcaSchedule>`; see [`store_dca_schedule`]
+    pub schedule_nonce: u128,
+    pub encrypted_schedule: [[u8; 32]; DCA_SCHEDULE_CIPHERTEXTS],
+}
+
+impl Subscription {
+    /// Whether the crank may enroll it in `batch` at `now`.
+    pub fn is_due(&self, batch: &Pubkey, now: i64) -> bool {
+        self.remaining > 0 && now >= self.next_enroll_at && *batch != self.last_batch
+    }
+}
+
+/// An order a user authorizes a relayer to record for them; see
+/// [`record_delegated_order`].
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OrderIntent {
+    pub batch: Pubkey,
+    pub user: Pubkey,
+    /// Binds the intent to one encrypted order, e.g. a hash of its ciphertext
+    pub order_commitment: [u8; 32],
+    /// Must exceed the user's last intent nonce
+    pub nonce: u64,
+    pub expires_at: i64,
+}
+
+impl OrderIntent {
+    /// Bytes the user signs with their wallet key.
+    pub fn message(&self) -> Vec<u8> {
+        let mut message = ORDER_INTENT_DOMAIN.to_vec();
+        self.serialize(&mut message)
+            .expect("in-memory serialization");
+        message
+    }
+}
+
+/// Protocol roles assigned with [`set_role`].
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Admin,
+    /// Batch lifecycle instructions
+    Operator,
+    /// Distribution instructions
+    Settler,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct InsuranceFund {
+    pub authority: Pubkey,
+    pub usdc_mint: Pubkey,
+    pub vault: Pubkey,
+    pub fee_share_bps: u16,
+    pub total_funded: u64,
+    pub total_claimed: u64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CreditLedger {
+    pub usdc_mint: Pubkey,
+    pub vault: Pubkey,
+    pub total_credited: u64,
+    pub total_claimed: u64,
+    pub bump: u8,
+}
+
+/// USDC owed to one user across batches.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct UserCredit {
+    pub owner: Pubkey,
+    pub balance: u64,
+    pub total_credited: u64,
+    pub total_claimed: u64,
+    pub updated_at: i64,
+}
+
+/// Why a user was credited.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CreditReason {
+    Dust,
+    Rounding,
+    Refund,
+}
+
+/// Anchor account discriminator plus Borsh body.
+pub trait MpcAccount: AnchorDeserialize {
+    const DISCRIMINATOR: [u8; 8];
+
+    fn decode(data: &[u8]) -> Result<Self> {
+        match data.split_at_checked(8) {
+            Some((disc, mut body)) if disc == Self::DISCRIMINATOR => {
+                Self::deserialize(&mut body).map_err(|_| SdkError::InvalidAccountData)
+            }
+            _ => Err(SdkError::InvalidAccountData),
+        }
+    }
+}
+
+impl MpcAccount for Batch {
+    const DISCRIMINATOR: [u8; 8] = [156, 194, 70, 44, 22, 88, 137, 44];
+}
+
+impl MpcAccount for OrderSlot {
+    const DISCRIMINATOR: [u8; 8] = [231, 55, 137, 53, 94, 43, 13, 135];
+}
+
+impl MpcAccount for Distribution {
+    const DISCRIMINATOR: [u8; 8] = [176, 85, 17, 11, 13, 194, 18, 1];
+}
+
+impl MpcAccount for EncryptedDistribution {
+    const DISCRIMINATOR: [u8; 8] = [14, 161, 179, 13, 159, 60, 230, 30];
+}
+
+impl MpcAccount for StealthDistribution {
+    const DISCRIMINATOR: [u8; 8] = [36, 250, 54, 116, 54, 118, 176, 207];
+}
+
+impl MpcAccount for UserPortfolio {
+    const DISCRIMINATOR: [u8; 8] = [221, 161, 193, 108, 114, 23, 232, 99];
+}
+
+impl MpcAccount for MpcRegistry {
+    const DISCRIMINATOR: [u8; 8] = [49, 168, 97, 5, 33, 51, 141, 133];
+}
+
+impl MpcAccount for ExecutionApproval {
+    const DISCRIMINATOR: [u8; 8] = [125, 113, 217, 67, 39, 28, 151, 43];
+}
+
+impl MpcAccount for ProtocolConfig {
+    const DISCRIMINATOR: [u8; 8] = [207, 91, 250, 28, 152, 179, 215, 209];
+}
+
+impl MpcAccount for MarketStats {
+    const DISCRIMINATOR: [u8; 8] = [240, 45, 182, 233, 92, 118, 209, 83];
+}
+
+impl MpcAccount for MarketLotSize {
+    const DISCRIMINATOR: [u8; 8] = [115, 170, 165, 93, 49, 65, 243, 22];
+}
+
+impl MpcAccount for MarketOutcomeMints {
+    const DISCRIMINATOR: [u8; 8] = [90, 22, 79, 168, 61, 146, 51, 14];
+}
+
+impl MpcAccount for BatchSequence {
+    const DISCRIMINATOR: [u8; 8] = [148, 106, 237, 11, 16, 41, 144, 76];
+}
+
+impl MpcAccount for MarketLanes {
+    const DISCRIMINATOR: [u8; 8] = [105, 138, 10, 84, 196, 96, 50, 1];
+}
+
+impl MpcAccount for RelayNonce {
+    const DISCRIMINATOR: [u8; 8] = [8, 4, 238, 138, 204, 40, 192, 8];
+}
+
+impl MpcAccount for InsuranceFund {
+    const DISCRIMINATOR: [u8; 8] = [43, 134, 170, 87, 102, 16, 142, 147];
+}
+
+impl MpcAccount for CreditLedger {
+    const DISCRIMINATOR: [u8; 8] = [198, 115, 205, 229, 55, 158, 228, 228];
+}
+
+impl MpcAccount for UserCredit {
+    const DISCRIMINATOR: [u8; 8] = [41, 47, 169, 170, 241, 28, 215, 103];
+}
+
+impl MpcAccount for SessionKey {
+    const DISCRIMINATOR: [u8; 8] = [93, 186, 163, 139, 160, 255, 81, 112];
+}
+
+impl MpcAccount for Subscription {
+    const DISCRIMINATOR: [u8; 8] = [64, 7, 26, 135, 102, 132, 98, 33];
+}
+
+// ============================================================================
+// Events
+// ============================================================================
+
+/// Anchor event discriminator plus Borsh body, as carried by `emit_cpi!`
+/// after the event instruction tag.
+pub trait MpcEvent: AnchorDeserialize {
+    const DISCRIMINATOR: [u8; 8];
+
+    /// Decode if `data` starts with this event's discriminator.
+    fn decode(data: &[u8]) -> Option<Self> {
+        match data.split_at_checked(8) {
+            Some((disc, mut body)) if disc == Self::DISCRIMINATOR => {
+                Self::deserialize(&mut body).ok()
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BatchCreated {
+    pub batch: Pubkey,
+    pub market_id: String,
+    pub side: u8,
+    pub cluster_offset: u32,
+    pub circuit_version: u16,
+    pub label: String,
+    pub intent_hash: Option<[u8; 32]>,
+    /// Creation order across all batches; see [`BatchSequence`]
+    pub sequence: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BatchClusterMigrated {
+    pub batch: Pubkey,
+    pub previous_cluster_offset: u32,
+    pub cluster_offset: u32,
+    pub sequence: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OrderRecorded {
+    pub batch: Pubkey,
+    pub order_count: u8,
+    pub sequence: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OrderRouted {
+    pub batch: Pubkey,
+    pub lane: u8,
+    pub order_index: u8,
+    pub sequence: u64,
+}
+
+/// Order count `peek_order_count` revealed for an open batch.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OrderCountPosted {
+    pub batch: Pubkey,
+    pub order_count: u8,
+    pub posted_at: i64,
+    pub sequence: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SessionKeyCreated {
+    pub user: Pubkey,
+    pub session_key: Pubkey,
+    pub expires_at: i64,
+    pub scope: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SessionKeyRevoked {
+    pub user: Pubkey,
+    pub session_key: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SubscriptionCreated {
+    pub subscription: Pubkey,
+    pub owner: Pubkey,
+    pub market_id: String,
+    pub side: u8,
+    pub cadence: i64,
+    pub orders: u32,
+    pub jitter_bps: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DcaScheduleStored {
+    pub subscription: Pubkey,
+    pub remaining: u32,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SubscriptionCancelled {
+    pub subscription: Pubkey,
+    pub owner: Pubkey,
+    pub remaining: u32,
+}
+
+/// A subscription's order went into `batch`; the relay submits the
+/// ciphertext behind `ciphertext_ref` for `order_index`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SubscriptionEnrolled {
+    pub subscription: Pubkey,
+    pub batch: Pubkey,
+    pub order_index: u8,
+    pub ciphertext_ref: [u8; 32],
+    pub remaining: u32,
+    pub sequence: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DelegatedOrderRecorded {
+    pub batch: Pubkey,
+    pub order_index: u8,
+    pub user: Pubkey,
+    pub relayer: Pubkey,
+    pub order_commitment: [u8; 32],
+    pub sequence: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EscrowParked {
+    pub batch: Pubkey,
+    pub adapter: Pubkey,
+    pub amount: u64,
+    pub sequence: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EscrowRecalled {
+    pub batch: Pubkey,
+    pub adapter: Pubkey,
+    pub principal: u64,
+    pub earnings: u64,
+    pub sequence: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ReconciliationMismatch {
+    pub batch: Pubkey,
+    pub previous_status: BatchStatus,
+    pub expected: u64,
+    pub actual: u64,
+    pub sequence: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BatchFailed {
+    pub batch: Pubkey,
+    pub previous_status: BatchStatus,
+    pub order_count: u8,
+    pub total_usdc: u64,
+    pub sequence: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BatchClosed {
+    pub batch: Pubkey,
+    pub total_usdc: u64,
+    pub order_count: u8,
+    pub sequence: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionRecorded {
+    pub batch: Pubkey,
+    pub total_shares: u64,
+    pub filled_usdc: u64,
+    pub tx_signature: String,
+    pub intent_hash: Option<[u8; 32]>,
+    pub sequence: u64,
+}
+
+/// Slots between a batch's reveal (close) and its execution.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RevealExecutionGap {
+    pub batch: Pubkey,
+    pub closed_slot: u64,
+    pub executed_slot: u64,
+    pub gap_slots: u64,
+    pub sequence: u64,
+}
+
+/// A revealed total went unexecuted too long and the batch reopened.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RevealExpired {
+    pub batch: Pubkey,
+    pub closed_slot: u64,
+    pub slot: u64,
+    pub max_reveal_exec_gap: u64,
+    pub sequence: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DistributionsStaged {
+    pub batch: Pubkey,
+    pub root: [u8; 32],
+    pub count: u8,
+    pub shares: u64,
+    pub sequence: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DistributionsFinalized {
+    pub batch: Pubkey,
+    pub count: u8,
+    pub sequence: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DistributionRecorded {
+    pub batch: Pubkey,
+    pub order_index: u8,
+    pub shares: u64,
+    pub usdc_refund: u64,
+    pub wallet: Pubkey,
+    pub unlock_at: i64,
+    pub sequence: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EncryptedDistributionRecorded {
+    pub batch: Pubkey,
+    pub order_index: u8,
+    pub wallet: Pubkey,
+    pub sequence: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct StealthDistributionRecorded {
+    pub batch: Pubkey,
+    pub order_index: u8,
+    pub shares: u64,
+    pub sequence: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct StealthDistributionClaimed {
+    pub batch: Pubkey,
+    pub order_index: u8,
+    pub commitment: [u8; 32],
+    pub sequence: u64,
+}
+
+/// A wallet pulled its distribution with `claim_shares`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SharesClaimed {
+    pub batch: Pubkey,
+    pub order_index: u8,
+    pub wallet: Pubkey,
+    pub shares: u64,
+    pub usdc_refund: u64,
+    pub unlock_at: i64,
+    pub sequence: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DistributionExecuted {
+    pub batch: Pubkey,
+    pub order_index: u8,
+    pub tx_signature: String,
+    pub sequence: u64,
+}
+
+impl MpcEvent for BatchCreated {
+    const DISCRIMINATOR: [u8; 8] = [231, 92, 210, 203, 2, 59, 109, 234];
+}
+
+impl MpcEvent for BatchClusterMigrated {
+    const DISCRIMINATOR: [u8; 8] = [241, 188, 209, 110, 178, 83, 227, 178];
+}
+
+impl MpcEvent for OrderRecorded {
+    const DISCRIMINATOR: [u8; 8] = [43, 225, 76, 203, 41, 27, 170, 4];
+}
+
+impl MpcEvent for OrderRouted {
+    const DISCRIMINATOR: [u8; 8] = [190, 25, 69, 124, 196, 140, 194, 55];
+}
+
+impl MpcEvent for OrderCountPosted {
+    const DISCRIMINATOR: [u8; 8] = [125, 30, 174, 174, 140, 237, 130, 237];
+}
+
+impl MpcEvent for SessionKeyCreated {
+    const DISCRIMINATOR: [u8; 8] = [242, 227, 114, 120, 164, 30, 58, 114];
+}
+
+impl MpcEvent for SessionKeyRevoked {
+    const DISCRIMINATOR: [u8; 8] = [18, 208, 143, 205, 85, 72, 180, 176];
+}
+
+impl MpcEvent for SubscriptionCreated {
+    const DISCRIMINATOR: [u8; 8] = [215, 63, 169, 25, 179, 200, 180, 105];
+}
+
+impl MpcEvent for SubscriptionCancelled {
+    const DISCRIMINATOR: [u8; 8] = [158, 216, 233, 205, 138, 62, 176, 239];
+}
+
+impl MpcEvent for SubscriptionEnrolled {
+    const DISCRIMINATOR: [u8; 8] = [140, 134, 121, 52, 30, 197, 3, 33];
+}
+
+impl MpcEvent for DcaScheduleStored {
+    const DISCRIMINATOR: [u8; 8] = [51, 132, 40, 154, 253, 147, 31, 216];
+}
+
+impl MpcEvent for DelegatedOrderRecorded {
+    const DISCRIMINATOR: [u8; 8] = [86, 197, 69, 150, 238, 182, 83, 183];
+}
+
+impl MpcEvent for EscrowParked {
+    const DISCRIMINATOR: [u8; 8] = [16, 177, 223, 79, 109, 23, 63, 38];
+}
+
+impl MpcEvent for EscrowRecalled {
+    const DISCRIMINATOR: [u8; 8] = [248, 53, 193, 164, 213, 148, 86, 157];
+}
+
+impl MpcEvent for ReconciliationMismatch {
+    const DISCRIMINATOR: [u8; 8] = [152, 182, 81, 212, 194, 173, 248, 90];
+}
+
+impl MpcEvent for BatchFailed {
+    const DISCRIMINATOR: [u8; 8] = [35, 126, 24, 45, 168, 190, 213, 85];
+}
+
+impl MpcEvent for BatchClosed {
+    const DISCRIMINATOR: [u8; 8] = [192, 76, 201, 211, 10, 212, 139, 232];
+}
+
+impl MpcEvent for ExecutionRecorded {
+    const DISCRIMINATOR: [u8; 8] = [231, 133, 106, 58, 31, 56, 123, 10];
+}
+
+impl MpcEvent for RevealExecutionGap {
+    const DISCRIMINATOR: [u8; 8] = [222, 33, 49, 45, 47, 32, 86, 14];
+}
+
+impl MpcEvent for RevealExpired {
+    const DISCRIMINATOR: [u8; 8] = [208, 85, 70, 41, 238, 240, 64, 136];
+}
+
+impl MpcEvent for DistributionsStaged {
+    const DISCRIMINATOR: [u8; 8] = [123, 98, 171, 237, 163, 231, 40, 134];
+}
+
+impl MpcEvent for DistributionsFinalized {
+    const DISCRIMINATOR: [u8; 8] = [97, 73, 61, 136, 227, 35, 253, 163];
+}
+
+impl MpcEvent for DistributionRecorded {
+    const DISCRIMINATOR: [u8; 8] = [84, 56, 187, 206, 131, 85, 136, 227];
+}
+
+impl MpcEvent for EncryptedDistributionRecorded {
+    const DISCRIMINATOR: [u8; 8] = [40, 177, 82, 25, 84, 105, 176, 139];
+}
+
+impl MpcEvent for StealthDistributionRecorded {
+    const DISCRIMINATOR: [u8; 8] = [201, 97, 180, 197, 24, 33, 11, 148];
+}
+
+impl MpcEvent for StealthDistributionClaimed {
+    const DISCRIMINATOR: [u8; 8] = [2, 154, 67, 52, 231, 159, 17, 11];
+}
+
+impl MpcEvent for SharesClaimed {
+    const DISCRIMINATOR: [u8; 8] = [135, 17, 55, 130, 218, 208, 153, 101];
+}
+
+impl MpcEvent for DistributionExecuted {
+    const DISCRIMINATOR: [u8; 8] = [120, 132, 182, 5, 60, 75, 183, 95];
+}
+
+// ============================================================================
+// Distribution tree
+// ============================================================================
+
+/// What `claim_shares` needs besides the claiming wallet and order index:
+/// the leaf's amounts and unlock time, and its proof against the staged
+/// root.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ShareReceipt {
+    pub shares: u64,
+    pub usdc_refund: u64,
+    /// 0 unless the market settles late; the claim fails before it
+    pub unlock_at: i64,
+    pub proof: [[u8; 32]; DISTRIBUTION_TREE_DEPTH],
+}
+
+/// Leaf committed for a revealed distribution:
+/// sha256(order_index || shares LE || usdc_refund LE || unlock_at LE || wallet),
+/// unlock_at 0 unless the payout is delayed.
+pub fn distribution_leaf(
+    order_index: u8,
+    shares: u64,
+    usdc_refund: u64,
+    unlock_at: i64,
+    wallet: &Pubkey,
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([order_index]);
+    hasher.update(shares.to_le_bytes());
+    hasher.update(usdc_refund.to_le_bytes());
+    hasher.update(unlock_at.to_le_bytes());
+    hasher.update(wallet.as_ref());
+    hasher.finalize().into()
+}
+
+/// Leaf committed for an encrypted distribution:
+/// sha256(order_index || encryption_key || nonce LE || encrypted_shares || wallet).
+pub fn encrypted_distribution_leaf(
+    order_index: u8,
+    encryption_key: &[u8; 32],
+    nonce: u128,
+    encrypted_shares: &[u8; 32],
+    wallet: &Pubkey,
+) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([order_index]);
+    hasher.update(encryption_key);
+    hasher.update(nonce.to_le_bytes());
+    hasher.update(encrypted_shares);
+    hasher.update(wallet.as_ref());
+    hasher.finalize().into()
+}
+
+/// Leaf committed for a stealth distribution:
+/// sha256(order_index || shares LE || claim_key).
+pub fn stealth_distribution_leaf(order_index: u8, shares: u64, claim_key: &Pubkey) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([order_index]);
+    hasher.update(shares.to_le_bytes());
+    hasher.update(claim_key.as_ref());
+    hasher.finalize().into()
+}
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Tree over a batch's revealed distributions, leaf `i` for order index
+/// `i`, zero leaves past the last order. Its root goes to
+/// `stage_distributions` and its proofs to `record_distribution`.
+#[derive(Clone, Debug)]
+pub struct DistributionTree {
+    /// Leaves first, root last
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl DistributionTree {
+    /// `leaves` in order-index order, at most 2^DISTRIBUTION_TREE_DEPTH.
+    pub fn new(leaves: &[[u8; 32]]) -> Self {
+        let mut level = leaves.to_vec();
+        level.resize(1 << DISTRIBUTION_TREE_DEPTH, [0u8; 32]);
+        let mut levels = vec![level];
+        for _ in 0..DISTRIBUTION_TREE_DEPTH {
+            let next = levels
+                .last()
+                .expect("at least one level")
+                .chunks(2)
+                .map(|pair| hash_pair(&pair[0], &pair[1]))
+                .collect();
+            levels.push(next);
+        }
+        Self { levels }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.levels[DISTRIBUTION_TREE_DEPTH][0]
+    }
+
+    /// Sibling hashes from `order_index`'s leaf to the root.
+    pub fn proof(&self, order_index: u8) -> [[u8; 32]; DISTRIBUTION_TREE_DEPTH] {
+        std::array::from_fn(|level| self.levels[level][((order_index as usize) >> level) ^ 1])
+    }
+
+    /// Receipt the relay hands the wallet of `order_index` so it can
+    /// `claim_shares` the `shares` and `usdc_refund` of its leaf, from
+    /// `unlock_at` on.
+    pub fn receipt(
+        &self,
+        order_index: u8,
+        shares: u64,
+        usdc_refund: u64,
+        unlock_at: i64,
+    ) -> ShareReceipt {
+        ShareReceipt {
+            shares,
+            usdc_refund,
+            unlock_at,
+            proof: self.proof(order_index),
+        }
+    }
+}
+
+/// Root the program recomputes from a leaf and its proof.
+pub fn distribution_proof_root(
+    leaf: [u8; 32],
+    order_index: u8,
+    proof: &[[u8; 32]; DISTRIBUTION_TREE_DEPTH],
+) -> [u8; 32] {
+    proof
+        .iter()
+        .enumerate()
+        .fold(leaf, |node, (level, sibling)| {
+            if (order_index >> level) & 1 == 0 {
+                hash_pair(&node, sibling)
+            } else {
+                hash_pair(sibling, &node)
+            }
+        })
+}
+
+// ============================================================================
+// PDAs
+// ============================================================================
+
+/// Name `circuit` is deployed under at `version`: unchanged for version 1,
+/// `{circuit}_v{version}` after that.
+pub fn versioned_circuit(circuit: &str, version: u16) -> String {
+    if version <= 1 {
+        circuit.to_string()
+    } else {
+        format!("{circuit}_v{version}")
+    }
+}
+
+/// Arcium's `comp_def_offset`: the first 4 bytes of sha256(circuit),
+/// little-endian.
+pub fn comp_def_offset(circuit: &str) -> u32 {
+    let digest = Sha256::digest(circuit.as_bytes());
+    u32::from_le_bytes(digest[..4].try_into().expect("4 bytes"))
+}
+
+pub fn comp_def_address(circuit: &str) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"ComputationDefinitionAccount",
+            ID.as_ref(),
+            &comp_def_offset(circuit).to_le_bytes(),
+        ],
+        &ARCIUM_PROGRAM_ID,
+    )
+    .0
+}
+
+pub fn mpc_registry_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"mpc_registry"], &ID).0
+}
+
+pub fn config_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &ID).0
+}
+
+pub fn batch_address(authority: &Pubkey, market_id: &str) -> Pubkey {
+    Pubkey::find_program_address(&[b"batch", authority.as_ref(), market_id.as_bytes()], &ID).0
+}
+
+/// Batch [`create_batch`] opens for `market_id` in priority `lane`.
+pub fn priority_batch_address(authority: &Pubkey, market_id: &str, lane: BatchLane) -> Pubkey {
+    lane_batch_address(authority, market_id, lane.index())
+}
+
+/// Batch of `lane` in `market_id`; lane 0 is the create_batch batch.
+pub fn lane_batch_address(authority: &Pubkey, market_id: &str, lane: u8) -> Pubkey {
+    if lane == 0 {
+        return batch_address(authority, market_id);
+    }
+    Pubkey::find_program_address(
+        &[b"batch", authority.as_ref(), market_id.as_bytes(), &[lane]],
+        &ID,
+    )
+    .0
+}
+
+pub fn order_slot_address(batch: &Pubkey, order_index: u8) -> Pubkey {
+    Pubkey::find_program_address(&[b"order_slot", batch.as_ref(), &[order_index]], &ID).0
+}
+
+pub fn distribution_address(batch: &Pubkey, order_index: u8) -> Pubkey {
+    Pubkey::find_program_address(&[b"dist", batch.as_ref(), &[order_index]], &ID).0
+}
+
+pub fn encrypted_distribution_address(batch: &Pubkey, order_index: u8) -> Pubkey {
+    Pubkey::find_program_address(&[b"enc_dist", batch.as_ref(), &[order_index]], &ID).0
+}
+
+pub fn stealth_distribution_address(batch: &Pubkey, order_index: u8) -> Pubkey {
+    Pubkey::find_program_address(&[b"stealth_dist", batch.as_ref(), &[order_index]], &ID).0
+}
+
+/// Per-relay nonce PDA checked when recording distributions.
+pub fn relay_nonce_address(authority: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"relay_nonce", authority.as_ref()], &ID).0
+}
+
+pub fn intent_nonce_address(user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"intent_nonce", user.as_ref()], &ID).0
+}
+
+pub fn session_key_address(user: &Pubkey, session_key: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"session", user.as_ref(), session_key.as_ref()], &ID).0
+}
+
+pub fn subscription_address(owner: &Pubkey, id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"subscription", owner.as_ref(), &id.to_le_bytes()], &ID).0
+}
+
+pub fn market_lot_size_address(market_id: &str) -> Pubkey {
+    Pubkey::find_program_address(&[b"market_lot", market_id.as_bytes()], &ID).0
+}
+
+pub fn market_mints_address(market_id: &str) -> Pubkey {
+    Pubkey::find_program_address(&[b"market_mints", market_id.as_bytes()], &ID).0
+}
+
+pub fn batch_sequence_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"batch_sequence"], &ID).0
+}
+
+pub fn market_lanes_address(market_id: &str) -> Pubkey {
+    Pubkey::find_program_address(&[b"market_lanes", market_id.as_bytes()], &ID).0
+}
+
+pub fn market_stats_address(market_id: &str) -> Pubkey {
+    Pubkey::find_program_address(&[b"market_stats", market_id.as_bytes()], &ID).0
+}
+
+pub fn reveal_budget_address(market_id: &str) -> Pubkey {
+    Pubkey::find_program_address(&[b"reveal_budget", market_id.as_bytes()], &ID).0
+}
+
+pub fn portfolio_address(authority: &Pubkey, owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"portfolio", authority.as_ref(), owner.as_ref()], &ID).0
+}
+
+pub fn insurance_fund_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"insurance_fund"], &ID).0
+}
+
+pub fn insurance_vault_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"insurance_vault"], &ID).0
+}
+
+/// USDC escrow of a batch: the batch PDA's associated token account.
+pub fn batch_escrow_address(batch: &Pubkey, usdc_mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    anchor_spl::associated_token::get_associated_token_address_with_program_id(
+        batch,
+        usdc_mint,
+        token_program,
+    )
+}
+
+/// Outcome shares `claim_shares` pays from: the batch PDA's associated
+/// token account for the side's outcome mint.
+pub fn share_vault_address(batch: &Pubkey, share_mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    anchor_spl::associated_token::get_associated_token_address_with_program_id(
+        batch,
+        share_mint,
+        token_program,
+    )
+}
+
+pub fn execution_approval_address(batch: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"execution_approval", batch.as_ref()], &ID).0
+}
+
+pub fn credit_ledger_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"credit_ledger"], &ID).0
+}
+
+pub fn credit_vault_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"credit_vault"], &ID).0
+}
+
+pub fn user_credit_address(owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"credit", owner.as_ref()], &ID).0
+}
+
+pub fn event_authority_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"__event_authority"], &ID).0
+}
+
+// ============================================================================
+// Instructions
+// ============================================================================
+
+fn instruction(
+    discriminator: [u8; 8],
+    args: impl AnchorSerialize,
+    mut accounts: Vec<AccountMeta>,
+    event_cpi: bool,
+) -> Instruction {
+    let mut data = discriminator.to_vec();
+    args.serialize(&mut data).expect("in-memory serialization");
+    if event_cpi {
+        accounts.push(AccountMeta::new_readonly(event_authority_address(), false));
+        accounts.push(AccountMeta::new_readonly(ID, false));
+    }
+    Instruction {
+        program_id: ID,
+        accounts,
+        data,
+    }
+}
+
+/// Accounts of the batch-authority-only instructions.
+/// Batch, role-checked config, and the role holder signing.
+fn batch_authority(batch: &Pubkey, authority: &Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(*batch, false),
+        AccountMeta::new_readonly(config_address(), false),
+        AccountMeta::new_readonly(*authority, true),
+    ]
+}
+
+pub fn initialize_mpc_registry(authority: &Pubkey) -> Instruction {
+    instruction(
+        [14, 215, 166, 63, 65, 32, 46, 201],
+        (),
+        vec![
+            AccountMeta::new(mpc_registry_address(), false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        false,
+    )
+}
+
+/// Register `circuit` at `version`, i.e. the comp def deployed under
+/// [`versioned_circuit`].
+pub fn register_comp_def(authority: &Pubkey, circuit: &str, version: u16) -> Instruction {
+    let name = versioned_circuit(circuit, version);
+    instruction(
+        [37, 137, 100, 54, 82, 98, 253, 109],
+        (name.clone(), version),
+        vec![
+            AccountMeta::new(mpc_registry_address(), false),
+            AccountMeta::new_readonly(comp_def_address(&name), false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        true,
+    )
+}
+
+/// Fails unless every circuit is registered. Simulate it; nothing is written.
+pub fn verify_comp_defs(circuits: &[&str]) -> Instruction {
+    instruction(
+        [86, 185, 124, 197, 182, 140, 14, 124],
+        circuits.iter().map(|c| c.to_string()).collect::<Vec<_>>(),
+        vec![AccountMeta::new_readonly(mpc_registry_address(), false)],
+        false,
+    )
+}
+
+/// Fails unless `circuits` are registered at the batch's circuit version.
+/// Bundle ahead of queueing computations for `batch`.
+pub fn verify_batch_comp_defs(batch: &Pubkey, circuits: &[&str], version: u16) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(mpc_registry_address(), false),
+        AccountMeta::new_readonly(*batch, false),
+    ];
+    accounts.extend(circuits.iter().map(|circuit| {
+        AccountMeta::new_readonly(
+            comp_def_address(&versioned_circuit(circuit, version)),
+            false,
+        )
+    }));
+    instruction(
+        [220, 235, 145, 196, 184, 123, 251, 150],
+        (),
+        accounts,
+        false,
+    )
+}
+
+pub fn initialize_config(
+    admin: &Pubkey,
+    cluster_offset: u32,
+    fallback_cluster_offset: u32,
+) -> Instruction {
+    instruction(
+        [208, 127, 21, 1, 194, 190, 196, 70],
+        (cluster_offset, fallback_cluster_offset),
+        vec![
+            AccountMeta::new(config_address(), false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        false,
+    )
+}
+
+pub fn set_cluster(
+    admin: &Pubkey,
+    cluster_offset: u32,
+    fallback_cluster_offset: u32,
+) -> Instruction {
+    instruction(
+        [140, 96, 38, 83, 225, 128, 25, 176],
+        (cluster_offset, fallback_cluster_offset),
+        vec![
+            AccountMeta::new(config_address(), false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        true,
+    )
+}
+
+pub fn set_circuit_version(admin: &Pubkey, circuit_version: u16) -> Instruction {
+    instruction(
+        [57, 127, 76, 56, 130, 239, 134, 230],
+        circuit_version,
+        vec![
+            AccountMeta::new(config_address(), false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        true,
+    )
+}
+
+/// Set the force_fail_batch timeouts in seconds (0 = no timeout).
+pub fn set_batch_timeouts(
+    admin: &Pubkey,
+    execution_timeout: i64,
+    completion_timeout: i64,
+) -> Instruction {
+    instruction(
+        [170, 253, 190, 88, 247, 113, 170, 24],
+        (execution_timeout, completion_timeout),
+        vec![
+            AccountMeta::new(config_address(), false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        true,
+    )
+}
+
+/// Adaptive batch windows: batches close after the time the market's
+/// recent arrival rate takes to bring in `target_batch_orders`, within
+/// `min_batch_window..=max_batch_window`. All zero turns them off.
+pub fn set_batch_window(
+    admin: &Pubkey,
+    min_batch_window: i64,
+    max_batch_window: i64,
+    target_batch_orders: u8,
+) -> Instruction {
+    instruction(
+        [115, 45, 96, 96, 237, 168, 85, 158],
+        (min_batch_window, max_batch_window, target_batch_orders),
+        vec![
+            AccountMeta::new(config_address(), false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        true,
+    )
+}
+
+/// Let express batches close `batch_window` seconds after opening and
+/// charge `fee_bps` on their fills; a zero window turns the lane off.
+pub fn set_express_lane(admin: &Pubkey, batch_window: i64, fee_bps: u16) -> Instruction {
+    instruction(
+        [122, 227, 60, 61, 86, 191, 101, 189],
+        (batch_window, fee_bps),
+        vec![
+            AccountMeta::new(config_address(), false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        true,
+    )
+}
+
+/// Reject executions more than `max_reveal_exec_gap` slots after the
+/// batch's total was revealed, letting [`expire_reveal`] reopen the batch
+/// (0 = no limit).
+pub fn set_max_reveal_exec_gap(admin: &Pubkey, max_reveal_exec_gap: u64) -> Instruction {
+    instruction(
+        [113, 240, 237, 16, 239, 100, 159, 90],
+        max_reveal_exec_gap,
+        vec![
+            AccountMeta::new(config_address(), false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        true,
+    )
+}
+
+/// Require `threshold` of `approvers` to approve executions of batches
+/// above `large_batch_threshold` USDC (0 = never).
+pub fn set_execution_approvers(
+    admin: &Pubkey,
+    approvers: Vec<Pubkey>,
+    threshold: u8,
+    large_batch_threshold: u64,
+) -> Instruction {
+    instruction(
+        [174, 184, 54, 11, 78, 159, 243, 35],
+        (approvers, threshold, large_batch_threshold),
+        vec![
+            AccountMeta::new(config_address(), false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        true,
+    )
+}
+
+/// Only accept `record_order` as a top-level instruction or from one of
+/// `allowed_programs`; `enabled = false` accepts any caller.
+pub fn set_cpi_guard(admin: &Pubkey, enabled: bool, allowed_programs: Vec<Pubkey>) -> Instruction {
+    instruction(
+        [33, 139, 2, 19, 201, 116, 153, 144],
+        (enabled, allowed_programs),
+        vec![
+            AccountMeta::new(config_address(), false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        true,
+    )
+}
+
+/// Whitelist the yield adapter escrow may be parked in (None removes it)
+/// and the USDC token account its earnings go to.
+pub fn set_yield_adapter(
+    admin: &Pubkey,
+    adapter: Option<Pubkey>,
+    yield_recipient: &Pubkey,
+) -> Instruction {
+    instruction(
+        [182, 102, 113, 154, 33, 248, 240, 217],
+        (adapter, *yield_recipient),
+        vec![
+            AccountMeta::new(config_address(), false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        true,
+    )
+}
+
+/// Kill-switch for [`park_escrow`]; recalls keep working.
+pub fn set_yield_enabled(admin: &Pubkey, enabled: bool) -> Instruction {
+    instruction(
+        [110, 239, 153, 83, 80, 53, 223, 16],
+        enabled,
+        vec![
+            AccountMeta::new(config_address(), false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        true,
+    )
+}
+
+/// Assign `role` to `key`; signed by the current admin.
+pub fn set_role(admin: &Pubkey, role: Role, key: &Pubkey) -> Instruction {
+    instruction(
+        [77, 78, 62, 233, 192, 61, 199, 190],
+        (role, *key),
+        vec![
+            AccountMeta::new(config_address(), false),
+            AccountMeta::new_readonly(*admin, true),
+        ],
+        true,
+    )
+}
+
+/// Set the lot size `market_id` orders are normalized to (0 = none).
+pub fn set_market_lot_size(admin: &Pubkey, market_id: &str, lot_size: u64) -> Instruction {
+    instruction(
+        [235, 20, 135, 115, 248, 172, 60, 31],
+        (market_id.to_string(), lot_size),
+        vec![
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new(market_lot_size_address(market_id), false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        true,
+    )
+}
+
+/// Register `market_id`'s outcome mints; close_batch and record_execution
+/// then fail if another program touches them earlier in the transaction.
+pub fn set_market_outcome_mints(
+    admin: &Pubkey,
+    market_id: &str,
+    yes_mint: &Pubkey,
+    no_mint: &Pubkey,
+) -> Instruction {
+    instruction(
+        [153, 236, 182, 95, 152, 67, 212, 205],
+        (market_id.to_string(), *yes_mint, *no_mint),
+        vec![
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new(market_mints_address(market_id), false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        true,
+    )
+}
+
+/// Split `market_id` into lanes at the ascending `thresholds`.
+pub fn set_market_lanes(admin: &Pubkey, market_id: &str, thresholds: Vec<u64>) -> Instruction {
+    instruction(
+        [93, 231, 86, 155, 62, 98, 45, 145],
+        (market_id.to_string(), thresholds),
+        vec![
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new(market_lanes_address(market_id), false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        true,
+    )
+}
+
+/// `label` and `intent_hash` are optional batch metadata; a batch created
+/// with an intent hash can only be executed by quoting the same hash.
+/// The standard and express batches of a market live at different
+/// addresses; see [`priority_batch_address`].
+pub fn create_batch(
+    authority: &Pubkey,
+    market_id: &str,
+    side: u8,
+    lane: BatchLane,
+    label: Option<&str>,
+    intent_hash: Option<[u8; 32]>,
+) -> Instruction {
+    instruction(
+        [159, 198, 248, 43, 248, 31, 235, 86],
+        (
+            market_id.to_string(),
+            side,
+            lane,
+            label.map(str::to_string),
+            intent_hash,
+        ),
+        vec![
+            AccountMeta::new(priority_batch_address(authority, market_id, lane), false),
+            AccountMeta::new(batch_sequence_address(), false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        true,
+    )
+}
+
+/// [`create_batch`] for lane 1 and up of a market with lanes.
+pub fn create_lane_batch(
+    authority: &Pubkey,
+    market_id: &str,
+    side: u8,
+    lane: u8,
+    label: Option<&str>,
+    intent_hash: Option<[u8; 32]>,
+) -> Instruction {
+    instruction(
+        [112, 241, 199, 199, 102, 222, 231, 108],
+        (
+            market_id.to_string(),
+            side,
+            lane,
+            label.map(str::to_string),
+            intent_hash,
+        ),
+        vec![
+            AccountMeta::new(lane_batch_address(authority, market_id, lane), false),
+            AccountMeta::new(batch_sequence_address(), false),
+            AccountMeta::new_readonly(market_lanes_address(market_id), false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        true,
+    )
+}
+
+pub fn get_batch_status(batch: &Pubkey) -> Instruction {
+    instruction(
+        [21, 4, 124, 0, 186, 229, 1, 166],
+        (),
+        vec![AccountMeta::new_readonly(*batch, false)],
+        false,
+    )
+}
+
+/// Move `batch` to the cluster currently set in the config.
+pub fn migrate_batch_cluster(batch: &Pubkey, authority: &Pubkey) -> Instruction {
+    instruction(
+        [152, 23, 92, 174, 229, 201, 39, 94],
+        (),
+        batch_authority(batch, authority),
+        true,
+    )
+}
+
+/// `order_index` is the batch's current order_count: the slot it creates.
+pub fn record_order(batch: &Pubkey, authority: &Pubkey, order_index: u8) -> Instruction {
+    instruction(
+        [142, 220, 120, 190, 0, 153, 119, 120],
+        (),
+        vec![
+            AccountMeta::new(*batch, false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new(order_slot_address(batch, order_index), false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(INSTRUCTIONS_SYSVAR_ID, false),
+        ],
+        true,
+    )
+}
+
+/// Record an order in the batch of the lane `route_order` revealed for it.
+pub fn route_order(
+    batch: &Pubkey,
+    market_id: &str,
+    authority: &Pubkey,
+    order_index: u8,
+    lane: u8,
+) -> Instruction {
+    instruction(
+        [66, 177, 13, 63, 151, 46, 202, 110],
+        lane,
+        vec![
+            AccountMeta::new(*batch, false),
+            AccountMeta::new_readonly(market_lanes_address(market_id), false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new(order_slot_address(batch, order_index), false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        true,
+    )
+}
+
+/// Post the order count `peek_order_count` revealed while `batch` is open.
+pub fn post_order_count(batch: &Pubkey, authority: &Pubkey, order_count: u8) -> Instruction {
+    instruction(
+        [103, 211, 155, 22, 94, 113, 183, 36],
+        order_count,
+        vec![
+            AccountMeta::new_readonly(*batch, false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        true,
+    )
+}
+
+/// Ed25519 program instruction verifying one `signature` by `signer` over
+/// `message`, with all three inline.
+pub fn ed25519_verify(signer: &Pubkey, signature: &[u8; 64], message: &[u8]) -> Instruction {
+    const HEADER: u16 = 2 + 14;
+    let pubkey_offset = HEADER;
+    let signature_offset = pubkey_offset + 32;
+    let message_offset = signature_offset + 64;
+    let mut data = vec![1, 0];
+    for field in [
+        signature_offset,
+        u16::MAX,
+        pubkey_offset,
+        u16::MAX,
+        message_offset,
+        message.len() as u16,
+        u16::MAX,
+    ] {
+        data.extend_from_slice(&field.to_le_bytes());
+    }
+    data.extend_from_slice(signer.as_ref());
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+    Instruction {
+        program_id: ED25519_PROGRAM_ID,
+        accounts: vec![],
+        data,
+    }
+}
+
+/// Record an order for `intent.user`, paid by `relayer`. `signature` is
+/// the ed25519 signature over [`OrderIntent::message`] by the user, or by
+/// `session_key` if one of the user's session keys signed; its
+/// verification must directly precede the record, so send both
+/// instructions in this order. `order_index` is the batch's current
+/// order_count.
+pub fn record_delegated_order(
+    relayer: &Pubkey,
+    intent: &OrderIntent,
+    signature: &[u8; 64],
+    session_key: Option<&Pubkey>,
+    order_index: u8,
+) -> [Instruction; 2] {
+    let signer = session_key.unwrap_or(&intent.user);
+    // A missing optional account is passed as the program ID
+    let session = session_key.map_or(ID, |key| session_key_address(&intent.user, key));
+    [
+        ed25519_verify(signer, signature, &intent.message()),
+        instruction(
+            [75, 8, 50, 217, 141, 196, 156, 30],
+            intent,
+            vec![
+                AccountMeta::new(intent.batch, false),
+                AccountMeta::new(order_slot_address(&intent.batch, order_index), false),
+                AccountMeta::new(intent_nonce_address(&intent.user), false),
+                AccountMeta::new(*relayer, true),
+                AccountMeta::new_readonly(INSTRUCTIONS_SYSVAR_ID, false),
+                AccountMeta::new_readonly(session, false),
+                AccountMeta::new_readonly(system_program::ID, false),
+            ],
+            true,
+        ),
+    ]
+}
+
+pub fn close_batch(
+    batch: &Pubkey,
+    market_id: &str,
+    authority: &Pubkey,
+    revealed_total: u64,
+    revealed_count: u8,
+) -> Instruction {
+    instruction(
+        [166, 174, 35, 253, 209, 211, 181, 28],
+        (revealed_total, revealed_count),
+        vec![
+            AccountMeta::new(*batch, false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new(reveal_budget_address(market_id), false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(market_mints_address(market_id), false),
+            AccountMeta::new_readonly(INSTRUCTIONS_SYSVAR_ID, false),
+            AccountMeta::new(market_stats_address(market_id), false),
+        ],
+        true,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn record_execution(
+    batch: &Pubkey,
+    market_id: &str,
+    authority: &Pubkey,
+    total_shares: u64,
+    filled_usdc: u64,
+    tx_signature: &str,
+    intent_hash: Option<[u8; 32]>,
+    approved: bool,
+) -> Instruction {
+    let mut accounts = batch_authority(batch, authority);
+    // Without approvals the optional account is passed as the program ID
+    accounts.push(AccountMeta::new_readonly(
+        if approved {
+            execution_approval_address(batch)
+        } else {
+            ID
+        },
+        false,
+    ));
+    accounts.push(AccountMeta::new_readonly(
+        market_mints_address(market_id),
+        false,
+    ));
+    accounts.push(AccountMeta::new_readonly(INSTRUCTIONS_SYSVAR_ID, false));
+    instruction(
+        [231, 245, 144, 129, 178, 195, 89, 160],
+        (
+            total_shares,
+            filled_usdc,
+            tx_signature.to_string(),
+            intent_hash,
+        ),
+        accounts,
+        true,
+    )
+}
+
+/// Approve the execution of a large batch as one of the config's
+/// approvers. The first approval fixes the values the rest must match.
+pub fn approve_execution(
+    batch: &Pubkey,
+    approver: &Pubkey,
+    total_shares: u64,
+    filled_usdc: u64,
+) -> Instruction {
+    instruction(
+        [22, 96, 20, 190, 177, 12, 242, 141],
+        (total_shares, filled_usdc),
+        vec![
+            AccountMeta::new_readonly(*batch, false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new(execution_approval_address(batch), false),
+            AccountMeta::new(*approver, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        true,
+    )
+}
+
+pub fn stage_distributions(
+    batch: &Pubkey,
+    authority: &Pubkey,
+    root: [u8; 32],
+    count: u8,
+    shares: u64,
+) -> Instruction {
+    instruction(
+        [39, 0, 102, 37, 101, 108, 126, 80],
+        (root, count, shares),
+        vec![
+            AccountMeta::new(*batch, false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        true,
+    )
+}
+
+pub fn finalize_distributions(batch: &Pubkey, authority: &Pubkey) -> Instruction {
+    instruction(
+        [151, 133, 5, 85, 60, 195, 208, 52],
+        (),
+        vec![
+            AccountMeta::new(*batch, false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        true,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn record_distribution(
+    batch: &Pubkey,
+    authority: &Pubkey,
+    order_index: u8,
+    shares: u64,
+    usdc_refund: u64,
+    wallet: &Pubkey,
+    unlock_at: Option<i64>,
+    proof: [[u8; 32]; DISTRIBUTION_TREE_DEPTH],
+    relay_nonce: u64,
+) -> Instruction {
+    instruction(
+        [35, 239, 115, 184, 162, 108, 209, 36],
+        (
+            order_index,
+            shares,
+            usdc_refund,
+            *wallet,
+            unlock_at,
+            proof,
+            relay_nonce,
+        ),
+        vec![
+            AccountMeta::new(*batch, false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new(distribution_address(batch, order_index), false),
+            AccountMeta::new(relay_nonce_address(authority), false),
+            AccountMeta::new(order_slot_address(batch, order_index), false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        true,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn record_encrypted_distribution(
+    batch: &Pubkey,
+    authority: &Pubkey,
+    order_index: u8,
+    encryption_key: [u8; 32],
+    nonce: u128,
+    encrypted_shares: [u8; 32],
+    wallet: &Pubkey,
+    proof: [[u8; 32]; DISTRIBUTION_TREE_DEPTH],
+    relay_nonce: u64,
+) -> Instruction {
+    instruction(
+        [36, 195, 217, 48, 77, 104, 161, 135],
+        (
+            order_index,
+            encryption_key,
+            nonce,
+            encrypted_shares,
+            *wallet,
+            proof,
+            relay_nonce,
+        ),
+        vec![
+            AccountMeta::new(*batch, false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new(encrypted_distribution_address(batch, order_index), false),
+            AccountMeta::new(relay_nonce_address(authority), false),
+            AccountMeta::new(order_slot_address(batch, order_index), false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        true,
+    )
+}
+
+pub fn record_stealth_distribution(
+    batch: &Pubkey,
+    authority: &Pubkey,
+    order_index: u8,
+    shares: u64,
+    claim_key: &Pubkey,
+    proof: [[u8; 32]; DISTRIBUTION_TREE_DEPTH],
+    relay_nonce: u64,
+) -> Instruction {
+    instruction(
+        [28, 130, 152, 4, 35, 1, 199, 57],
+        (order_index, shares, *claim_key, proof, relay_nonce),
+        vec![
+            AccountMeta::new(*batch, false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new(stealth_distribution_address(batch, order_index), false),
+            AccountMeta::new(relay_nonce_address(authority), false),
+            AccountMeta::new(order_slot_address(batch, order_index), false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        true,
+    )
+}
+
+/// Claim signed by both the one-time claim key and the settler.
+/// Bundle with the pool's `add_commitment` for the same commitment.
+pub fn claim_stealth_distribution(
+    batch: &Pubkey,
+    authority: &Pubkey,
+    order_index: u8,
+    claim_key: &Pubkey,
+    commitment: [u8; 32],
+) -> Instruction {
+    instruction(
+        [140, 203, 200, 126, 52, 143, 45, 189],
+        commitment,
+        vec![
+            AccountMeta::new(*batch, false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new(stealth_distribution_address(batch, order_index), false),
+            AccountMeta::new_readonly(*claim_key, true),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        true,
+    )
+}
+
+/// Pull `claimant`'s distribution for `order_index` out of the batch:
+/// shares from its share vault (the batch's associated account for
+/// `share_mint`) and the refund from its escrow.
+#[allow(clippy::too_many_arguments)]
+pub fn claim_shares(
+    batch: &Pubkey,
+    market_id: &str,
+    claimant: &Pubkey,
+    order_index: u8,
+    receipt: ShareReceipt,
+    share_mint: &Pubkey,
+    share_token_program: &Pubkey,
+    recipient_shares: &Pubkey,
+    usdc_mint: &Pubkey,
+    token_program: &Pubkey,
+    recipient_usdc: &Pubkey,
+) -> Instruction {
+    instruction(
+        [130, 131, 29, 237, 134, 20, 110, 245],
+        (order_index, receipt),
+        vec![
+            AccountMeta::new(*batch, false),
+            AccountMeta::new(distribution_address(batch, order_index), false),
+            AccountMeta::new(order_slot_address(batch, order_index), false),
+            AccountMeta::new(market_stats_address(market_id), false),
+            AccountMeta::new_readonly(market_mints_address(market_id), false),
+            AccountMeta::new_readonly(*share_mint, false),
+            AccountMeta::new(
+                share_vault_address(batch, share_mint, share_token_program),
+                false,
+            ),
+            AccountMeta::new(*recipient_shares, false),
+            AccountMeta::new(batch_escrow_address(batch, usdc_mint, token_program), false),
+            AccountMeta::new_readonly(credit_ledger_address(), false),
+            AccountMeta::new_readonly(*usdc_mint, false),
+            AccountMeta::new(*recipient_usdc, false),
+            AccountMeta::new(*claimant, true),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(*share_token_program, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        true,
+    )
+}
+
+pub fn mark_distributed(
+    batch: &Pubkey,
+    market_id: &str,
+    authority: &Pubkey,
+    order_index: u8,
+    tx_signature: &str,
+) -> Instruction {
+    instruction(
+        [225, 106, 116, 3, 4, 174, 164, 224],
+        tx_signature.to_string(),
+        vec![
+            AccountMeta::new(*batch, false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new(distribution_address(batch, order_index), false),
+            AccountMeta::new(market_stats_address(market_id), false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        true,
+    )
+}
+
+/// Mark up to MAX_DISTRIBUTIONS_PER_IX orders paid out by the transfer
+/// transaction `tx_signature`. Their distribution accounts follow the
+/// program's own, in `order_indices` order.
+pub fn mark_distributed_many(
+    batch: &Pubkey,
+    market_id: &str,
+    authority: &Pubkey,
+    order_indices: &[u8],
+    tx_signature: &str,
+) -> Instruction {
+    let mut ix = instruction(
+        [44, 18, 10, 93, 21, 94, 172, 147],
+        (order_indices.to_vec(), tx_signature.to_string()),
+        vec![
+            AccountMeta::new(*batch, false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new(market_stats_address(market_id), false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        true,
+    );
+    ix.accounts.extend(
+        order_indices
+            .iter()
+            .map(|&order_index| AccountMeta::new(distribution_address(batch, order_index), false)),
+    );
+    ix
+}
+
+/// Fail a batch stuck past its config timeout. Needs no signer beyond
+/// the fee payer.
+pub fn force_fail_batch(batch: &Pubkey) -> Instruction {
+    instruction(
+        [101, 185, 195, 18, 175, 213, 168, 207],
+        (),
+        vec![
+            AccountMeta::new(*batch, false),
+            AccountMeta::new_readonly(config_address(), false),
+        ],
+        true,
+    )
+}
+
+/// Reopen a Closed batch whose revealed total went unexecuted past the
+/// config's gap, clearing its execution approvals if any exist. Needs no
+/// signer beyond the fee payer.
+pub fn expire_reveal(batch: &Pubkey) -> Instruction {
+    instruction(
+        [103, 196, 198, 6, 189, 208, 40, 94],
+        (),
+        vec![
+            AccountMeta::new(*batch, false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new(execution_approval_address(batch), false),
+        ],
+        true,
+    )
+}
+
+/// Compare a Closed or Executed batch's escrow with its accounting,
+/// freezing the batch if the escrow is short. Needs no signer beyond the
+/// fee payer.
+pub fn reconcile_batch(batch: &Pubkey, usdc_mint: &Pubkey, token_program: &Pubkey) -> Instruction {
+    instruction(
+        [124, 246, 224, 76, 37, 147, 26, 225],
+        (),
+        vec![
+            AccountMeta::new(*batch, false),
+            AccountMeta::new_readonly(batch_escrow_address(batch, usdc_mint, token_program), false),
+            AccountMeta::new_readonly(credit_ledger_address(), false),
+            AccountMeta::new_readonly(*usdc_mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+        true,
+    )
+}
+
+/// Park `amount` of a closed batch's escrow in `adapter`, passing it
+/// `adapter_accounts` after the escrow, batch, mint and token program.
+pub fn park_escrow(
+    batch: &Pubkey,
+    authority: &Pubkey,
+    usdc_mint: &Pubkey,
+    token_program: &Pubkey,
+    adapter: &Pubkey,
+    amount: u64,
+    adapter_accounts: &[AccountMeta],
+) -> Instruction {
+    let mut ix = instruction(
+        [119, 233, 114, 83, 107, 233, 216, 148],
+        amount,
+        vec![
+            AccountMeta::new(*batch, false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new(batch_escrow_address(batch, usdc_mint, token_program), false),
+            AccountMeta::new_readonly(credit_ledger_address(), false),
+            AccountMeta::new_readonly(*usdc_mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(*adapter, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        true,
+    );
+    ix.accounts.extend_from_slice(adapter_accounts);
+    ix
+}
+
+/// Recall a batch's parked escrow from `adapter` (the batch's
+/// `parked_adapter`); earnings go to the config's `yield_recipient`.
+/// Needs no signer beyond the fee payer.
+pub fn recall_escrow(
+    batch: &Pubkey,
+    usdc_mint: &Pubkey,
+    token_program: &Pubkey,
+    adapter: &Pubkey,
+    yield_recipient: &Pubkey,
+    adapter_accounts: &[AccountMeta],
+) -> Instruction {
+    let mut ix = instruction(
+        [194, 6, 37, 115, 80, 150, 216, 89],
+        (),
+        vec![
+            AccountMeta::new(*batch, false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new(batch_escrow_address(batch, usdc_mint, token_program), false),
+            AccountMeta::new_readonly(credit_ledger_address(), false),
+            AccountMeta::new_readonly(*usdc_mint, false),
+            AccountMeta::new(*yield_recipient, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(*adapter, false),
+        ],
+        true,
+    );
+    ix.accounts.extend_from_slice(adapter_accounts);
+    ix
+}
+
+/// Store the `Enc<Mxe, UserPortfolio>` output of `init_portfolio` or
+/// `accumulate_position` for `owner`.
+pub fn store_portfolio(
+    authority: &Pubkey,
+    owner: &Pubkey,
+    nonce: u128,
+    encrypted_positions: [[u8; 32]; PORTFOLIO_CIPHERTEXTS],
+) -> Instruction {
+    instruction(
+        [155, 197, 75, 16, 33, 179, 128, 225],
+        (*owner, nonce, encrypted_positions),
+        vec![
+            AccountMeta::new(portfolio_address(authority, owner), false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        true,
+    )
+}
+
+pub fn initialize_insurance_fund(
+    authority: &Pubkey,
+    usdc_mint: &Pubkey,
+    token_program: &Pubkey,
+    fee_share_bps: u16,
+) -> Instruction {
+    instruction(
+        [2, 239, 39, 87, 50, 28, 108, 12],
+        fee_share_bps,
+        vec![
+            AccountMeta::new(insurance_fund_address(), false),
+            AccountMeta::new(insurance_vault_address(), false),
+            AccountMeta::new_readonly(*usdc_mint, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        false,
+    )
+}
+
+pub fn fund_insurance(
+    funder: &Pubkey,
+    funder_usdc: &Pubkey,
+    usdc_mint: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    instruction(
+        [58, 216, 37, 162, 201, 50, 20, 66],
+        amount,
+        vec![
+            AccountMeta::new(insurance_fund_address(), false),
+            AccountMeta::new(insurance_vault_address(), false),
+            AccountMeta::new_readonly(*usdc_mint, false),
+            AccountMeta::new(*funder_usdc, false),
+            AccountMeta::new_readonly(*funder, true),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+        true,
+    )
+}
+
+pub fn claim_shortfall(
+    batch: &Pubkey,
+    order_index: u8,
+    recipient_usdc: &Pubkey,
+    usdc_mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    instruction(
+        [71, 71, 22, 191, 161, 110, 29, 177],
+        (),
+        vec![
+            AccountMeta::new_readonly(*batch, false),
+            AccountMeta::new(distribution_address(batch, order_index), false),
+            AccountMeta::new(insurance_fund_address(), false),
+            AccountMeta::new(insurance_vault_address(), false),
+            AccountMeta::new_readonly(*usdc_mint, false),
+            AccountMeta::new(*recipient_usdc, false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+        true,
+    )
+}
+
+pub fn initialize_credit_ledger(
+    admin: &Pubkey,
+    usdc_mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    instruction(
+        [136, 63, 233, 26, 46, 19, 17, 138],
+        (),
+        vec![
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new(credit_ledger_address(), false),
+            AccountMeta::new(credit_vault_address(), false),
+            AccountMeta::new_readonly(*usdc_mint, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        false,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn record_credit(
+    settler: &Pubkey,
+    settler_usdc: &Pubkey,
+    usdc_mint: &Pubkey,
+    token_program: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+    reason: CreditReason,
+    batch: &Pubkey,
+) -> Instruction {
+    instruction(
+        [206, 97, 199, 72, 22, 174, 68, 238],
+        (*owner, amount, reason, *batch),
+        vec![
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new(credit_ledger_address(), false),
+            AccountMeta::new(user_credit_address(owner), false),
+            AccountMeta::new(credit_vault_address(), false),
+            AccountMeta::new_readonly(*usdc_mint, false),
+            AccountMeta::new(*settler_usdc, false),
+            AccountMeta::new(*settler, true),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        true,
+    )
+}
+
+/// Let `session_key` sign for `user` within `scope` until `expiry`.
+pub fn create_session_key(
+    user: &Pubkey,
+    session_key: &Pubkey,
+    expiry: i64,
+    scope: u8,
+) -> Instruction {
+    instruction(
+        [137, 204, 246, 242, 200, 143, 215, 56],
+        (expiry, scope),
+        vec![
+            AccountMeta::new(session_key_address(user, session_key), false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(*session_key, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        true,
+    )
+}
+
+pub fn revoke_session_key(user: &Pubkey, session_key: &Pubkey) -> Instruction {
+    instruction(
+        [81, 192, 32, 110, 104, 116, 144, 151],
+        (),
+        vec![
+            AccountMeta::new(session_key_address(user, session_key), false),
+            AccountMeta::new(*user, true),
+        ],
+        true,
+    )
+}
+
+/// Subscribe `owner` to `orders` recurring orders on `side` of a market,
+/// one per `cadence` seconds at most. `ciphertext_ref` is the hash of the
+/// encrypted order handed to the relay; with `jitter_bps` > 0, of the
+/// encrypted total to spread over all `orders` (see
+/// [`crate::simulation::simulate_dca_amounts`]).
+#[allow(clippy::too_many_arguments)]
+pub fn create_subscription(
+    owner: &Pubkey,
+    id: u64,
+    market_id: &str,
+    side: u8,
+    cadence: i64,
+    orders: u32,
+    ciphertext_ref: [u8; 32],
+    jitter_bps: u16,
+) -> Instruction {
+    instruction(
+        [65, 71, 10, 60, 249, 82, 197, 12],
+        (
+            id,
+            market_id.to_string(),
+            side,
+            cadence,
+            orders,
+            ciphertext_ref,
+            jitter_bps,
+        ),
+        vec![
+            AccountMeta::new(subscription_address(owner, id), false),
+            AccountMeta::new(*owner, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        true,
+    )
+}
+
+/// Store the `Enc<Mxe, DcaSchedule>` output by `init_dca_schedule` or
+/// `add_dca_to_batch` for a jittered subscription.
+pub fn store_dca_schedule(
+    subscription: &Pubkey,
+    authority: &Pubkey,
+    nonce: u128,
+    encrypted_schedule: [[u8; 32]; DCA_SCHEDULE_CIPHERTEXTS],
+) -> Instruction {
+    instruction(
+        [103, 27, 167, 110, 209, 10, 114, 96],
+        (nonce, encrypted_schedule),
+        vec![
+            AccountMeta::new(*subscription, false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        true,
+    )
+}
+
+pub fn cancel_subscription(owner: &Pubkey, id: u64) -> Instruction {
+    instruction(
+        [60, 139, 189, 242, 191, 208, 143, 18],
+        (),
+        vec![
+            AccountMeta::new(subscription_address(owner, id), false),
+            AccountMeta::new(*owner, true),
+        ],
+        true,
+    )
+}
+
+/// Crank a due subscription into `batch`. `order_index` is the batch's
+/// current order_count, as for [`record_order`].
+pub fn enroll_subscription(
+    batch: &Pubkey,
+    subscription: &Pubkey,
+    authority: &Pubkey,
+    order_index: u8,
+) -> Instruction {
+    instruction(
+        [139, 72, 161, 156, 143, 120, 3, 198],
+        (),
+        vec![
+            AccountMeta::new(*batch, false),
+            AccountMeta::new(*subscription, false),
+            AccountMeta::new_readonly(config_address(), false),
+            AccountMeta::new(order_slot_address(batch, order_index), false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        true,
+    )
+}
+
+pub fn claim_credit(
+    owner: &Pubkey,
+    recipient_usdc: &Pubkey,
+    usdc_mint: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    instruction(
+        [190, 242, 172, 79, 29, 82, 22, 163],
+        amount,
+        vec![
+            AccountMeta::new(user_credit_address(owner), false),
+            AccountMeta::new(credit_ledger_address(), false),
+            AccountMeta::new(credit_vault_address(), false),
+            AccountMeta::new_readonly(*usdc_mint, false),
+            AccountMeta::new(*recipient_usdc, false),
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+        true,
+    )
+}
@@ -0,0 +1,287 @@
+//! Per-epoch netting of USDC between the privacy pool and batch escrows.
+//!
+//! An operator report built from the same transaction history as
+//! [`crate::replay`]: for each epoch of `epoch_slots` slots, what the pool
+//! took in and paid out, how much of that went to batch escrows, how much
+//! came back as refunds, and what the batches escrowed, filled and refunded.
+//! Only totals are reported, never individual notes or orders, so the
+//! report can be handed to auditors as is; see the `obsidian-netting`
+//! binary.
+//!
+//! A withdrawal counts as pool-to-batch when its recipient is the escrow
+//! of a batch created in the history, and a refund as batch-to-pool when
+//! it is paid to the pool or its vault. Each batch's flows must conserve
+//! USDC: it cannot take more from the pool than it escrowed, nor fill and
+//! refund more than it escrowed. Breaks are listed with the report.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::{token, token_2022};
+use serde_json::{json, Value};
+
+use crate::mpc::{self, MpcEvent};
+use crate::pool;
+use crate::recovery::deposit_amounts;
+use crate::replay::{decode_pool_event, LoggedTransaction};
+
+/// Slots per mainnet epoch
+pub const DEFAULT_EPOCH_SLOTS: u64 = 432_000;
+
+/// USDC flows of one epoch, in atomic units.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EpochFlows {
+    pub epoch: u64,
+    /// Amounts passed to `deposit`
+    pub pool_deposits: u64,
+    /// Paid out to anything but a batch escrow, after fees
+    pub pool_withdrawals: u64,
+    /// Paid into batch escrows, after fees
+    pub pool_to_batches: u64,
+    /// Withdrawal fees, whatever the recipient. Deposits less withdrawals,
+    /// transfers to batches and fees is the vault's change
+    pub pool_fees: u64,
+    /// Refunds paid to the pool or its vault
+    pub batches_to_pool: u64,
+    /// Order totals of batches closed in the epoch
+    pub batch_escrowed: u64,
+    /// USDC spent by batches executed in the epoch
+    pub batch_filled: u64,
+    /// Refunds recorded in the epoch, to any wallet
+    pub batch_refunded: u64,
+}
+
+impl EpochFlows {
+    fn is_empty(&self) -> bool {
+        *self
+            == Self {
+                epoch: self.epoch,
+                ..Default::default()
+            }
+    }
+
+    /// What the pool sent to batches less what came back.
+    pub fn net_pool_to_batches(&self) -> i128 {
+        self.pool_to_batches as i128 - self.batches_to_pool as i128
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "epoch": self.epoch,
+            "poolDeposits": self.pool_deposits,
+            "poolWithdrawals": self.pool_withdrawals,
+            "poolToBatches": self.pool_to_batches,
+            "poolFees": self.pool_fees,
+            "batchesToPool": self.batches_to_pool,
+            "netPoolToBatches": self.net_pool_to_batches().to_string(),
+            "batchEscrowed": self.batch_escrowed,
+            "batchFilled": self.batch_filled,
+            "batchRefunded": self.batch_refunded,
+        })
+    }
+}
+
+/// One batch's flows across the whole history.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct BatchFlows {
+    from_pool: u64,
+    escrowed: Option<u64>,
+    filled: u64,
+    refunded: u64,
+}
+
+/// A batch whose flows do not add up.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConservationBreak {
+    pub batch: Pubkey,
+    pub detail: String,
+}
+
+impl fmt::Display for ConservationBreak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.batch, self.detail)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NettingReport {
+    pub epoch_slots: u64,
+    /// Epochs with any flow, in order
+    pub epochs: Vec<EpochFlows>,
+    pub breaks: Vec<ConservationBreak>,
+}
+
+impl NettingReport {
+    /// Fold `transactions`, oldest first, into per-epoch flows.
+    pub fn build(transactions: &[LoggedTransaction], epoch_slots: u64) -> Self {
+        let epoch_slots = epoch_slots.max(1);
+        let transactions: Vec<_> = transactions.iter().filter(|tx| !tx.failed).collect();
+
+        // Escrows of every batch in the history, under either token program
+        let mut escrows = HashMap::new();
+        for event in transactions.iter().flat_map(|tx| events(tx, mpc::ID)) {
+            if let Some(e) = mpc::BatchCreated::decode(event) {
+                for token_program in [token::ID, token_2022::ID] {
+                    let escrow = mpc::batch_escrow_address(
+                        &e.batch,
+                        &privacy_pool::USDC_MINT,
+                        &token_program,
+                    );
+                    escrows.insert(escrow, e.batch);
+                }
+            }
+        }
+        let pool = pool::pool_address();
+        let pool_vault = get_associated_token_address_with_program_id(
+            &pool,
+            &privacy_pool::USDC_MINT,
+            &token::ID,
+        );
+
+        let mut epochs: BTreeMap<u64, EpochFlows> = BTreeMap::new();
+        let mut batches: BTreeMap<Pubkey, BatchFlows> = BTreeMap::new();
+        for transaction in transactions {
+            let epoch = transaction.slot / epoch_slots;
+            let flows = epochs.entry(epoch).or_insert_with(|| EpochFlows {
+                epoch,
+                ..Default::default()
+            });
+
+            for (_, amount) in deposit_amounts(transaction) {
+                flows.pool_deposits += amount;
+            }
+            for event in events(transaction, privacy_pool::ID) {
+                let Some(e) = decode_pool_event::<privacy_pool::WithdrawalEvent>(event) else {
+                    continue;
+                };
+                let paid = e.amount - e.fee;
+                flows.pool_fees += e.fee;
+                match escrows.get(&e.recipient) {
+                    Some(batch) => {
+                        flows.pool_to_batches += paid;
+                        batches.entry(*batch).or_default().from_pool += paid;
+                    }
+                    None => flows.pool_withdrawals += paid,
+                }
+            }
+            for event in events(transaction, mpc::ID) {
+                if let Some(e) = mpc::BatchClosed::decode(event) {
+                    flows.batch_escrowed += e.total_usdc;
+                    batches.entry(e.batch).or_default().escrowed = Some(e.total_usdc);
+                } else if let Some(e) = mpc::ExecutionRecorded::decode(event) {
+                    flows.batch_filled += e.filled_usdc;
+                    batches.entry(e.batch).or_default().filled = e.filled_usdc;
+                } else if let Some(e) = mpc::DistributionRecorded::decode(event) {
+                    flows.batch_refunded += e.usdc_refund;
+                    batches.entry(e.batch).or_default().refunded += e.usdc_refund;
+                    if e.wallet == pool || e.wallet == pool_vault {
+                        flows.batches_to_pool += e.usdc_refund;
+                    }
+                }
+            }
+        }
+
+        Self {
+            epoch_slots,
+            epochs: epochs
+                .into_values()
+                .filter(|flows| !flows.is_empty())
+                .collect(),
+            breaks: batches
+                .iter()
+                .filter_map(|(batch, flows)| conservation_break(batch, flows))
+                .collect(),
+        }
+    }
+
+    /// Totals across every epoch.
+    pub fn totals(&self) -> EpochFlows {
+        self.epochs
+            .iter()
+            .fold(EpochFlows::default(), |total, e| EpochFlows {
+                epoch: 0,
+                pool_deposits: total.pool_deposits + e.pool_deposits,
+                pool_withdrawals: total.pool_withdrawals + e.pool_withdrawals,
+                pool_to_batches: total.pool_to_batches + e.pool_to_batches,
+                pool_fees: total.pool_fees + e.pool_fees,
+                batches_to_pool: total.batches_to_pool + e.batches_to_pool,
+                batch_escrowed: total.batch_escrowed + e.batch_escrowed,
+                batch_filled: total.batch_filled + e.batch_filled,
+                batch_refunded: total.batch_refunded + e.batch_refunded,
+            })
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut totals = self.totals().to_json();
+        totals.as_object_mut().expect("object").remove("epoch");
+        json!({
+            "epochSlots": self.epoch_slots,
+            "epochs": self.epochs.iter().map(EpochFlows::to_json).collect::<Vec<_>>(),
+            "totals": totals,
+            "conservationBreaks": self
+                .breaks
+                .iter()
+                .map(|b| json!({ "batch": b.batch.to_string(), "detail": b.detail }))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    /// One row per epoch, with a header.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "epoch,pool_deposits,pool_withdrawals,pool_to_batches,pool_fees,batches_to_pool,\
+             net_pool_to_batches,batch_escrowed,batch_filled,batch_refunded\n",
+        );
+        for e in &self.epochs {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{}\n",
+                e.epoch,
+                e.pool_deposits,
+                e.pool_withdrawals,
+                e.pool_to_batches,
+                e.pool_fees,
+                e.batches_to_pool,
+                e.net_pool_to_batches(),
+                e.batch_escrowed,
+                e.batch_filled,
+                e.batch_refunded
+            ));
+        }
+        csv
+    }
+}
+
+fn conservation_break(batch: &Pubkey, flows: &BatchFlows) -> Option<ConservationBreak> {
+    let detail = match flows.escrowed {
+        Some(escrowed) if flows.from_pool > escrowed => format!(
+            "received {} from the pool but escrowed {escrowed}",
+            flows.from_pool
+        ),
+        Some(escrowed) if flows.filled as u128 + flows.refunded as u128 > escrowed as u128 => {
+            format!(
+                "filled {} and refunded {} of {escrowed} escrowed",
+                flows.filled, flows.refunded
+            )
+        }
+        None if flows.filled > 0 || flows.refunded > 0 => {
+            "filled or refunded without closing".to_string()
+        }
+        _ => return None,
+    };
+    Some(ConservationBreak {
+        batch: *batch,
+        detail,
+    })
+}
+
+/// `emit_cpi!` events of `program` in `transaction`.
+fn events(transaction: &LoggedTransaction, program: Pubkey) -> impl Iterator<Item = &[u8]> + '_ {
+    transaction
+        .instructions
+        .iter()
+        .filter(move |(id, _)| *id == program)
+        .filter_map(|(_, data)| data.strip_prefix(EVENT_IX_TAG_LE))
+}
@@ -0,0 +1,171 @@
+//! Deposit notes, commitments and nullifiers.
+//!
+//! Hashes match the `balance_proof` circuit and the privacy pool program:
+//! Poseidon (circom parameters) over BN254, 32-byte big-endian encodings.
+//!
+//! - commitment        = hash_4([1, secret, amount, asset])
+//! - legacy commitment = hash_2([secret, amount]), version 0 notes
+//! - nullifier         = hash_4([secret, leaf_index, pool_id, purpose])
+//! - legacy nullifier  = hash_3([secret, leaf_index, 1])
+//!
+//! Secrets are either random or derived from a wallet seed, so a wallet
+//! restored from its seed can find its notes again (see [`crate::recovery`]):
+//!
+//! - secret_i = tagged_hash("obsidian/note", seed || i), top byte cleared
+//!
+//! `tagged_hash` is the BIP-340 construction, SHA-256 over
+//! `SHA-256(tag) || SHA-256(tag) || message`, and `i` is big-endian u32.
+
+use ark_bn254::Fr;
+use light_poseidon::{Poseidon, PoseidonBytesHasher};
+use sha2::{Digest, Sha256};
+
+use crate::error::{Result, SdkError};
+
+/// A private deposit note. Whoever holds the secret can spend the note.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Note {
+    pub secret: [u8; 32],
+    pub amount: u64,
+    /// Commitment scheme; `privacy_pool::COMMITMENT_VERSION` for new notes
+    pub version: u8,
+}
+
+impl Note {
+    /// Create a note with a fresh random secret.
+    pub fn random(amount: u64) -> Result<Self> {
+        let mut secret = [0u8; 32];
+        // Leave the top byte zero so the secret is always below the BN254 modulus.
+        getrandom::getrandom(&mut secret[1..]).map_err(|_| SdkError::Randomness)?;
+        Ok(Self {
+            secret,
+            amount,
+            version: privacy_pool::COMMITMENT_VERSION,
+        })
+    }
+
+    /// Create the note at `index` of the wallet with `seed`.
+    pub fn derive(seed: &[u8], index: u32, amount: u64) -> Self {
+        Self {
+            secret: derive_secret(seed, index),
+            amount,
+            version: privacy_pool::COMMITMENT_VERSION,
+        }
+    }
+
+    /// Leaf value inserted into the pool's Merkle tree. The pool holds only
+    /// USDC, so that is the asset of every versioned note.
+    pub fn commitment(&self) -> Result<[u8; 32]> {
+        match self.version {
+            privacy_pool::LEGACY_COMMITMENT_VERSION => {
+                poseidon(&[&self.secret, &u64_to_field(self.amount)])
+            }
+            privacy_pool::COMMITMENT_VERSION => poseidon(&[
+                &u64_to_field(self.version as u64),
+                &self.secret,
+                &u64_to_field(self.amount),
+                &privacy_pool::asset_field(&privacy_pool::USDC_MINT),
+            ]),
+            version => Err(SdkError::UnsupportedCommitmentVersion(version)),
+        }
+    }
+
+    /// Nullifier published when the note at `leaf_index` is spent.
+    ///
+    /// `pool_id` is `privacy_pool::nullifier_pool_id(&pool)` and `purpose`
+    /// one of the `NULLIFIER_PURPOSE_*` constants.
+    pub fn nullifier(&self, leaf_index: u32, pool_id: &[u8; 32], purpose: u8) -> Result<[u8; 32]> {
+        derive_nullifier(&self.secret, leaf_index, pool_id, purpose)
+    }
+
+    /// Pre-domain-separation nullifier, still recorded alongside the new one.
+    pub fn legacy_nullifier(&self, leaf_index: u32) -> Result<[u8; 32]> {
+        poseidon(&[
+            &self.secret,
+            &u64_to_field(leaf_index as u64),
+            &u64_to_field(1),
+        ])
+    }
+
+    /// Spend of the note at `leaf_index` to `outputs` by `withdraw_split`.
+    ///
+    /// The split_withdrawal circuit only proves outputs that add up to
+    /// exactly the note's amount, so any other split is rejected here
+    /// rather than by the prover.
+    pub fn split(
+        &self,
+        leaf_index: u32,
+        leaf_bound: u32,
+        outputs: Vec<privacy_pool::SplitOutput>,
+    ) -> Result<privacy_pool::SplitWithdrawal> {
+        let total: u128 = outputs.iter().map(|output| output.amount as u128).sum();
+        if total != self.amount as u128 {
+            return Err(SdkError::SplitMismatch {
+                outputs: total,
+                note: self.amount,
+            });
+        }
+        let pool_id = privacy_pool::nullifier_pool_id(&crate::pool::pool_address());
+        Ok(privacy_pool::SplitWithdrawal {
+            nullifier: self.nullifier(
+                leaf_index,
+                &pool_id,
+                privacy_pool::NULLIFIER_PURPOSE_SPEND,
+            )?,
+            legacy_nullifier: self.legacy_nullifier(leaf_index)?,
+            note_version: self.version,
+            leaf_bound,
+            outputs,
+        })
+    }
+}
+
+/// Domain tag of derived note secrets
+const NOTE_SECRET_TAG: &[u8] = b"obsidian/note";
+
+/// Secret of the note at `index` of the wallet with `seed`, e.g. a BIP-39
+/// seed. Indices are used in order, one per note.
+pub fn derive_secret(seed: &[u8], index: u32) -> [u8; 32] {
+    let tag = Sha256::digest(NOTE_SECRET_TAG);
+    let mut secret: [u8; 32] = Sha256::new()
+        .chain_update(tag)
+        .chain_update(tag)
+        .chain_update(seed)
+        .chain_update(index.to_be_bytes())
+        .finalize()
+        .into();
+    // Below the BN254 modulus, as for random secrets
+    secret[0] = 0;
+    secret
+}
+
+/// Nullifier for a note secret, without needing the amount.
+pub fn derive_nullifier(
+    secret: &[u8; 32],
+    leaf_index: u32,
+    pool_id: &[u8; 32],
+    purpose: u8,
+) -> Result<[u8; 32]> {
+    if purpose != privacy_pool::NULLIFIER_PURPOSE_SPEND
+        && purpose != privacy_pool::NULLIFIER_PURPOSE_BATCH
+    {
+        return Err(SdkError::InvalidNullifierPurpose(purpose));
+    }
+    poseidon(&[
+        secret,
+        &u64_to_field(leaf_index as u64),
+        pool_id,
+        &u64_to_field(purpose as u64),
+    ])
+}
+
+fn poseidon(inputs: &[&[u8]]) -> Result<[u8; 32]> {
+    let mut hasher = Poseidon::<Fr>::new_circom(inputs.len()).map_err(|_| SdkError::Hash)?;
+    hasher.hash_bytes_be(inputs).map_err(|_| SdkError::Hash)
+}
+
+fn u64_to_field(value: u64) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes[24..].copy_from_slice(&value.to_be_bytes());
+    bytes
+}
@@ -0,0 +1,910 @@
+//! Instruction builders and PDAs for the privacy pool program.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::solana_program::sysvar::instructions as instructions_sysvar;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{
+    self, get_associated_token_address, get_associated_token_address_with_program_id,
+};
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022;
+
+/// Privacy pool PDA
+pub fn pool_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"privacy_pool"], &privacy_pool::ID).0
+}
+
+/// Nullifier set PDA for one `NULLIFIER_PURPOSE_*`
+pub fn nullifier_set_address(purpose: u8) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"nullifiers", pool_address().as_ref(), &[purpose]],
+        &privacy_pool::ID,
+    )
+    .0
+}
+
+/// Soul-bound deposit receipt mint for the note at `leaf_index`
+pub fn receipt_mint_address(leaf_index: u32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"receipt",
+            pool_address().as_ref(),
+            &leaf_index.to_le_bytes(),
+        ],
+        &privacy_pool::ID,
+    )
+    .0
+}
+
+/// `owner`'s Token-2022 account holding the receipt for `leaf_index`
+pub fn receipt_account_address(owner: &Pubkey, leaf_index: u32) -> Pubkey {
+    get_associated_token_address_with_program_id(
+        owner,
+        &receipt_mint_address(leaf_index),
+        &token_2022::ID,
+    )
+}
+
+/// Exposure limits PDA read by `deposit`
+pub fn limits_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"limits", pool_address().as_ref()], &privacy_pool::ID).0
+}
+
+/// Per-relay nonce PDA checked by `record_nullifier`, `add_commitment` and
+/// `migrate_notes`
+pub fn relay_nonce_address(relay: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"relay_nonce", relay.as_ref()], &privacy_pool::ID).0
+}
+
+/// Withdrawal fee tiers PDA
+pub fn fees_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"fees", pool_address().as_ref()], &privacy_pool::ID).0
+}
+
+/// Leaf insertion times PDA written by `deposit`, `add_commitment` and
+/// `record_nullifier_with_proof`
+pub fn leaf_times_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"leaf_times", pool_address().as_ref()], &privacy_pool::ID).0
+}
+
+/// Deposit and withdrawal totals checked against the vault
+pub fn stats_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"stats", pool_address().as_ref()], &privacy_pool::ID).0
+}
+
+/// Migration progress; deposits fail once it exists
+pub fn migration_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"migration", pool_address().as_ref()], &privacy_pool::ID).0
+}
+
+/// Pool PDA of the privacy_pool deployment at `program`, such as a
+/// migration's v2 pool
+pub fn pool_address_in(program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"privacy_pool"], program).0
+}
+
+/// PoolImport PDA of the pool at `program`, created by `open_import`
+pub fn import_address_in(program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"import", pool_address_in(program).as_ref()], program).0
+}
+
+/// PoolRelay PDA naming the relay signer
+pub fn pool_relay_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"pool_relay", pool_address().as_ref()], &privacy_pool::ID).0
+}
+
+/// CpiGuard PDA limiting which programs may call deposit and the
+/// withdrawals
+pub fn cpi_guard_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"cpi_guard", pool_address().as_ref()], &privacy_pool::ID).0
+}
+
+/// Latest proof-of-reserve attestation
+pub fn reserves_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"reserves", pool_address().as_ref()], &privacy_pool::ID).0
+}
+
+/// Verifier config PDA read by `record_nullifier_with_proof`
+pub fn verifier_config_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"verifier", pool_address().as_ref()], &privacy_pool::ID).0
+}
+
+/// Active key pointer for `circuit_id` in whichever verifier the pool uses
+pub fn verifier_active_key_address(verifier_program: &Pubkey, circuit_id: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[b"active_vk", circuit_id.as_ref()], verifier_program).0
+}
+
+fn event_authority_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"__event_authority"], &privacy_pool::ID).0
+}
+
+fn instruction(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: privacy_pool::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Create the pool; only `relay` may then record nullifiers and add
+/// commitments.
+pub fn initialize_instruction(authority: &Pubkey, relay: &Pubkey) -> Instruction {
+    instruction(
+        privacy_pool::accounts::Initialize {
+            pool: pool_address(),
+            pool_relay: pool_relay_address(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        privacy_pool::instruction::Initialize { relay: *relay },
+    )
+}
+
+pub fn initialize_nullifier_set_instruction(authority: &Pubkey, purpose: u8) -> Instruction {
+    instruction(
+        privacy_pool::accounts::InitializeNullifierSet {
+            pool: pool_address(),
+            nullifiers: nullifier_set_address(purpose),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        privacy_pool::instruction::InitializeNullifierSet { purpose },
+    )
+}
+
+/// Make room for `additional` more nullifiers in a set, 1 to
+/// `MAX_NULLIFIER_GROWTH`; `payer` funds the extra rent.
+pub fn grow_nullifier_set_instruction(payer: &Pubkey, purpose: u8, additional: u32) -> Instruction {
+    instruction(
+        privacy_pool::accounts::GrowNullifierSet {
+            pool: pool_address(),
+            nullifiers: nullifier_set_address(purpose),
+            payer: *payer,
+            system_program: system_program::ID,
+        },
+        privacy_pool::instruction::GrowNullifierSet {
+            purpose,
+            additional,
+        },
+    )
+}
+
+/// Create the pool's stats account; required before deposits and
+/// withdrawals.
+pub fn initialize_pool_stats_instruction(authority: &Pubkey) -> Instruction {
+    instruction(
+        privacy_pool::accounts::InitializePoolStats {
+            pool: pool_address(),
+            stats: stats_address(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        privacy_pool::instruction::InitializePoolStats {},
+    )
+}
+
+/// Build the privacy pool `deposit` instruction for `user`.
+///
+/// `commitment` must use the current scheme (`Note::random` does), as the
+/// pool only inserts `COMMITMENT_VERSION` leaves.
+///
+/// When the pool screens deposits, append the screening program and its
+/// accounts first (see `screening_accounts`). For a mint with a transfer
+/// hook, append the hook's extra accounts (hook program, validation
+/// account, then its extra metas) after them.
+pub fn deposit_instruction(user: &Pubkey, commitment: [u8; 32], amount: u64) -> Instruction {
+    deposit(user, commitment, amount, None)
+}
+
+/// `deposit` that also mints a soul-bound receipt to `user`.
+///
+/// `leaf_index` must be the pool's current `next_index`; if another
+/// deposit lands first the receipt PDA no longer matches and the
+/// transaction fails without side effects.
+pub fn deposit_with_receipt_instruction(
+    user: &Pubkey,
+    commitment: [u8; 32],
+    amount: u64,
+    leaf_index: u32,
+) -> Instruction {
+    deposit(user, commitment, amount, Some(leaf_index))
+}
+
+fn deposit(
+    user: &Pubkey,
+    commitment: [u8; 32],
+    amount: u64,
+    receipt_leaf_index: Option<u32>,
+) -> Instruction {
+    let pool = pool_address();
+
+    instruction(
+        privacy_pool::accounts::Deposit {
+            pool,
+            user: *user,
+            user_usdc: get_associated_token_address(user, &privacy_pool::USDC_MINT),
+            pool_usdc: get_associated_token_address(&pool, &privacy_pool::USDC_MINT),
+            usdc_mint: privacy_pool::USDC_MINT,
+            token_program: spl_token::ID,
+            limits: limits_address(),
+            leaf_times: leaf_times_address(),
+            stats: stats_address(),
+            migration: migration_address(),
+            cpi_guard: cpi_guard_address(),
+            instructions: instructions_sysvar::ID,
+            receipt_mint: receipt_leaf_index.map(receipt_mint_address),
+            receipt_account: receipt_leaf_index.map(|i| receipt_account_address(user, i)),
+            token_2022_program: receipt_leaf_index.map(|_| token_2022::ID),
+            associated_token_program: receipt_leaf_index.map(|_| associated_token::ID),
+            system_program: receipt_leaf_index.map(|_| system_program::ID),
+            event_authority: event_authority_address(),
+            program: privacy_pool::ID,
+        },
+        privacy_pool::instruction::Deposit {
+            commitment,
+            amount,
+            version: privacy_pool::COMMITMENT_VERSION,
+        },
+    )
+}
+
+pub fn get_pool_state_instruction() -> Instruction {
+    instruction(
+        privacy_pool::accounts::GetPoolState {
+            pool: pool_address(),
+        },
+        privacy_pool::instruction::GetPoolState {},
+    )
+}
+
+pub fn is_nullifier_used_instruction(purpose: u8, nullifier: [u8; 32]) -> Instruction {
+    instruction(
+        privacy_pool::accounts::CheckNullifier {
+            nullifiers: nullifier_set_address(purpose),
+        },
+        privacy_pool::instruction::IsNullifierUsed { nullifier },
+    )
+}
+
+pub fn record_nullifier_instruction(
+    relay: &Pubkey,
+    purpose: u8,
+    nullifier: [u8; 32],
+    legacy_nullifier: Option<[u8; 32]>,
+    relay_nonce: u64,
+) -> Instruction {
+    instruction(
+        privacy_pool::accounts::RecordNullifier {
+            pool: pool_address(),
+            nullifiers: nullifier_set_address(purpose),
+            relay_nonce: relay_nonce_address(relay),
+            relay: *relay,
+            system_program: system_program::ID,
+            pool_relay: pool_relay_address(),
+        },
+        privacy_pool::instruction::RecordNullifier {
+            nullifier,
+            legacy_nullifier,
+            relay_nonce,
+        },
+    )
+}
+
+/// Set the pool's TVL, per-deposit and hourly withdrawal caps (0 disables a cap).
+pub fn set_limits_instruction(
+    authority: &Pubkey,
+    max_tvl: u64,
+    max_deposit: u64,
+    max_hourly_withdrawal_bps: u16,
+) -> Instruction {
+    instruction(
+        privacy_pool::accounts::SetLimits {
+            pool: pool_address(),
+            limits: limits_address(),
+            authority: *authority,
+            system_program: system_program::ID,
+            event_authority: event_authority_address(),
+            program: privacy_pool::ID,
+        },
+        privacy_pool::instruction::SetLimits {
+            max_tvl,
+            max_deposit,
+            max_hourly_withdrawal_bps,
+        },
+    )
+}
+
+/// Set withdrawal fee tiers (ascending `min_age`, starting at 0) and the
+/// USDC token account fees go to.
+pub fn set_fee_tiers_instruction(
+    authority: &Pubkey,
+    tiers: Vec<privacy_pool::FeeTier>,
+    fee_recipient: &Pubkey,
+) -> Instruction {
+    instruction(
+        privacy_pool::accounts::SetFeeTiers {
+            pool: pool_address(),
+            fees: fees_address(),
+            leaf_times: leaf_times_address(),
+            authority: *authority,
+            system_program: system_program::ID,
+            event_authority: event_authority_address(),
+            program: privacy_pool::ID,
+        },
+        privacy_pool::instruction::SetFeeTiers {
+            tiers,
+            fee_recipient: *fee_recipient,
+        },
+    )
+}
+
+fn update_limits_accounts(authority: &Pubkey) -> privacy_pool::accounts::UpdateLimits {
+    privacy_pool::accounts::UpdateLimits {
+        pool: pool_address(),
+        limits: limits_address(),
+        authority: *authority,
+        event_authority: event_authority_address(),
+        program: privacy_pool::ID,
+    }
+}
+
+/// Screen every deposit with `screening_program`, which takes
+/// `screening_accounts` accounts after the depositor; None stops screening.
+pub fn set_screening_program_instruction(
+    authority: &Pubkey,
+    screening_program: Option<Pubkey>,
+    screening_accounts: u8,
+) -> Instruction {
+    instruction(
+        update_limits_accounts(authority),
+        privacy_pool::instruction::SetScreeningProgram {
+            screening_program,
+            screening_accounts,
+        },
+    )
+}
+
+/// Remaining accounts a deposit passes to the pool's screening program:
+/// the program, then its own `accounts` (which must number
+/// `PoolLimits::screening_accounts`). Empty when screening is off.
+pub fn screening_accounts(
+    limits: &privacy_pool::PoolLimits,
+    accounts: &[AccountMeta],
+) -> Vec<AccountMeta> {
+    if limits.screening_program == Pubkey::default() {
+        return vec![];
+    }
+    std::iter::once(AccountMeta::new_readonly(limits.screening_program, false))
+        .chain(accounts.iter().cloned())
+        .collect()
+}
+
+/// Start the unpause timelock after the withdrawal circuit breaker tripped.
+pub fn request_unpause_instruction(authority: &Pubkey) -> Instruction {
+    instruction(
+        update_limits_accounts(authority),
+        privacy_pool::instruction::RequestUnpause {},
+    )
+}
+
+/// Resume withdrawals once `privacy_pool::UNPAUSE_TIMELOCK_SECS` have passed.
+pub fn unpause_instruction(authority: &Pubkey) -> Instruction {
+    instruction(
+        update_limits_accounts(authority),
+        privacy_pool::instruction::Unpause {},
+    )
+}
+
+pub fn set_verifier_instruction(
+    authority: &Pubkey,
+    verifier_program: Pubkey,
+    circuit: &str,
+    aggregate_circuit: &str,
+) -> Instruction {
+    instruction(
+        privacy_pool::accounts::SetVerifier {
+            pool: pool_address(),
+            verifier_config: verifier_config_address(),
+            authority: *authority,
+            system_program: system_program::ID,
+            event_authority: event_authority_address(),
+            program: privacy_pool::ID,
+        },
+        privacy_pool::instruction::SetVerifier {
+            verifier_program,
+            circuit_id: zk_verifier::circuit_id(circuit),
+            aggregate_circuit_id: zk_verifier::circuit_id(aggregate_circuit),
+        },
+    )
+}
+
+/// Record a nullifier backed by an on-chain verified balance proof, and
+/// insert the proof's change note.
+///
+/// `verifier_program` and `circuit_id` must match the pool's verifier config,
+/// and `verifying_key` must be the circuit's active key in that verifier.
+pub fn record_nullifier_with_proof_instruction(
+    purpose: u8,
+    verifier_program: &Pubkey,
+    circuit_id: &[u8; 32],
+    verifying_key: &Pubkey,
+    proof: Vec<u8>,
+    public_inputs: Vec<[u8; 32]>,
+) -> Instruction {
+    instruction(
+        privacy_pool::accounts::RecordNullifierWithProof {
+            pool: pool_address(),
+            leaf_times: leaf_times_address(),
+            migration: migration_address(),
+            nullifiers: nullifier_set_address(purpose),
+            verifier_config: verifier_config_address(),
+            verifying_key: *verifying_key,
+            active_key: verifier_active_key_address(verifier_program, circuit_id),
+            verifier_program: *verifier_program,
+            event_authority: event_authority_address(),
+            program: privacy_pool::ID,
+        },
+        privacy_pool::instruction::RecordNullifierWithProof {
+            proof,
+            public_inputs,
+        },
+    )
+}
+
+/// Public inputs the relay's aggregated proof must commit to, in order.
+pub fn aggregate_public_inputs(
+    merkle_root: [u8; 32],
+    withdrawals: &[privacy_pool::Withdrawal],
+) -> Vec<[u8; 32]> {
+    let mut inputs = vec![
+        merkle_root,
+        privacy_pool::nullifier_pool_id(&pool_address()),
+    ];
+    for withdrawal in withdrawals {
+        let mut amount = [0u8; 32];
+        amount[24..].copy_from_slice(&withdrawal.amount.to_be_bytes());
+        let mut leaf_bound = [0u8; 32];
+        leaf_bound[28..].copy_from_slice(&withdrawal.leaf_bound.to_be_bytes());
+        inputs.push(withdrawal.nullifier);
+        inputs.push(withdrawal.legacy_nullifier.unwrap_or_default());
+        inputs.push(privacy_pool::recipient_field(&withdrawal.recipient));
+        inputs.push(amount);
+        inputs.push(leaf_bound);
+        inputs.push(
+            withdrawal
+                .reference
+                .as_ref()
+                .map(privacy_pool::recipient_field)
+                .unwrap_or_default(),
+        );
+    }
+    inputs
+}
+
+/// Public inputs a WITHDRAWAL_CIRCUIT_ID proof for `withdrawal` must commit
+/// to, in order.
+pub fn withdrawal_public_inputs(
+    merkle_root: [u8; 32],
+    withdrawal: &privacy_pool::Withdrawal,
+) -> Vec<[u8; 32]> {
+    let mut inputs = vec![
+        merkle_root,
+        privacy_pool::nullifier_pool_id(&pool_address()),
+    ];
+    inputs.extend(privacy_pool::withdrawal_public_inputs(withdrawal));
+    inputs
+}
+
+/// Pay out withdrawals each covered by its own proof.
+///
+/// `verifier_program` must match the pool's verifier config and
+/// `verifying_key` be the active key of `WITHDRAWAL_CIRCUIT_ID`;
+/// `proofs[i]` proves `withdrawals[i]`. Recipients are appended as for
+/// [`withdraw_aggregated_instruction`].
+pub fn withdraw_many_instruction(
+    verifier_program: &Pubkey,
+    verifying_key: &Pubkey,
+    fee_recipient: &Pubkey,
+    proofs: Vec<Vec<u8>>,
+    withdrawals: Vec<privacy_pool::Withdrawal>,
+) -> Instruction {
+    let pool = pool_address();
+    let remaining = withdrawal_accounts(&withdrawals);
+
+    let mut ix = instruction(
+        privacy_pool::accounts::WithdrawMany {
+            pool,
+            nullifiers: nullifier_set_address(privacy_pool::NULLIFIER_PURPOSE_SPEND),
+            verifier_config: verifier_config_address(),
+            verifying_key: *verifying_key,
+            active_key: verifier_active_key_address(
+                verifier_program,
+                &privacy_pool::WITHDRAWAL_CIRCUIT_ID,
+            ),
+            verifier_program: *verifier_program,
+            pool_usdc: get_associated_token_address(&pool, &privacy_pool::USDC_MINT),
+            usdc_mint: privacy_pool::USDC_MINT,
+            limits: limits_address(),
+            fees: fees_address(),
+            leaf_times: leaf_times_address(),
+            stats: stats_address(),
+            fee_recipient: *fee_recipient,
+            token_program: spl_token::ID,
+            cpi_guard: cpi_guard_address(),
+            instructions: instructions_sysvar::ID,
+            event_authority: event_authority_address(),
+            program: privacy_pool::ID,
+        },
+        privacy_pool::instruction::WithdrawMany {
+            proofs,
+            withdrawals,
+        },
+    );
+    ix.accounts.extend(remaining);
+    ix
+}
+
+/// Pay out withdrawals covered by one aggregated proof.
+///
+/// `aggregate_circuit_id` must match the pool's verifier config and
+/// `verifying_key` be its active key. Each recipient token account is
+/// appended as a writable remaining account, in the same order as
+/// `withdrawals`, followed by any Solana Pay references.
+pub fn withdraw_aggregated_instruction(
+    verifier_program: &Pubkey,
+    aggregate_circuit_id: &[u8; 32],
+    verifying_key: &Pubkey,
+    fee_recipient: &Pubkey,
+    proof: Vec<u8>,
+    withdrawals: Vec<privacy_pool::Withdrawal>,
+) -> Instruction {
+    let pool = pool_address();
+    let remaining = withdrawal_accounts(&withdrawals);
+
+    let mut ix = instruction(
+        privacy_pool::accounts::WithdrawAggregated {
+            pool,
+            nullifiers: nullifier_set_address(privacy_pool::NULLIFIER_PURPOSE_SPEND),
+            verifier_config: verifier_config_address(),
+            verifying_key: *verifying_key,
+            active_key: verifier_active_key_address(verifier_program, aggregate_circuit_id),
+            verifier_program: *verifier_program,
+            pool_usdc: get_associated_token_address(&pool, &privacy_pool::USDC_MINT),
+            usdc_mint: privacy_pool::USDC_MINT,
+            limits: limits_address(),
+            fees: fees_address(),
+            leaf_times: leaf_times_address(),
+            stats: stats_address(),
+            fee_recipient: *fee_recipient,
+            token_program: spl_token::ID,
+            cpi_guard: cpi_guard_address(),
+            instructions: instructions_sysvar::ID,
+            event_authority: event_authority_address(),
+            program: privacy_pool::ID,
+        },
+        privacy_pool::instruction::WithdrawAggregated { proof, withdrawals },
+    );
+    ix.accounts.extend(remaining);
+    ix
+}
+
+/// Public inputs a split_withdrawal proof for `split` must commit to.
+pub fn split_public_inputs(
+    merkle_root: [u8; 32],
+    split: &privacy_pool::SplitWithdrawal,
+) -> Vec<[u8; 32]> {
+    privacy_pool::split_public_inputs(
+        merkle_root,
+        privacy_pool::nullifier_pool_id(&pool_address()),
+        split,
+    )
+}
+
+/// Pay one note out to each of `split`'s outputs.
+///
+/// `verifier_program` must match the pool's verifier config and
+/// `verifying_key` be the active key of `SPLIT_WITHDRAWAL_CIRCUIT_ID`.
+/// Each output's recipient is appended, writable, in output order.
+pub fn withdraw_split_instruction(
+    verifier_program: &Pubkey,
+    verifying_key: &Pubkey,
+    fee_recipient: &Pubkey,
+    proof: Vec<u8>,
+    split: privacy_pool::SplitWithdrawal,
+) -> Instruction {
+    let pool = pool_address();
+    let recipients: Vec<AccountMeta> = split
+        .outputs
+        .iter()
+        .map(|output| AccountMeta::new(output.recipient, false))
+        .collect();
+
+    let mut ix = instruction(
+        privacy_pool::accounts::WithdrawSplit {
+            pool,
+            nullifiers: nullifier_set_address(privacy_pool::NULLIFIER_PURPOSE_SPEND),
+            verifier_config: verifier_config_address(),
+            verifying_key: *verifying_key,
+            active_key: verifier_active_key_address(
+                verifier_program,
+                &privacy_pool::SPLIT_WITHDRAWAL_CIRCUIT_ID,
+            ),
+            verifier_program: *verifier_program,
+            pool_usdc: get_associated_token_address(&pool, &privacy_pool::USDC_MINT),
+            usdc_mint: privacy_pool::USDC_MINT,
+            limits: limits_address(),
+            fees: fees_address(),
+            leaf_times: leaf_times_address(),
+            stats: stats_address(),
+            fee_recipient: *fee_recipient,
+            token_program: spl_token::ID,
+            cpi_guard: cpi_guard_address(),
+            instructions: instructions_sysvar::ID,
+            event_authority: event_authority_address(),
+            program: privacy_pool::ID,
+        },
+        privacy_pool::instruction::WithdrawSplit { proof, split },
+    );
+    ix.accounts.extend(recipients);
+    ix
+}
+
+/// Remaining accounts of the withdraw instructions: each recipient token
+/// account, writable, then each reference, read-only, both in withdrawal
+/// order.
+fn withdrawal_accounts(withdrawals: &[privacy_pool::Withdrawal]) -> Vec<AccountMeta> {
+    let recipients = withdrawals
+        .iter()
+        .map(|w| AccountMeta::new(w.recipient, false));
+    let references = withdrawals
+        .iter()
+        .filter_map(|w| w.reference)
+        .map(|reference| AccountMeta::new_readonly(reference, false));
+    recipients.chain(references).collect()
+}
+
+pub fn add_commitment_instruction(
+    relay: &Pubkey,
+    commitment: [u8; 32],
+    relay_nonce: u64,
+) -> Instruction {
+    instruction(
+        privacy_pool::accounts::AddCommitment {
+            pool: pool_address(),
+            leaf_times: leaf_times_address(),
+            migration: migration_address(),
+            relay_nonce: relay_nonce_address(relay),
+            relay: *relay,
+            system_program: system_program::ID,
+            pool_relay: pool_relay_address(),
+            event_authority: event_authority_address(),
+            program: privacy_pool::ID,
+        },
+        privacy_pool::instruction::AddCommitment {
+            commitment,
+            relay_nonce,
+            version: privacy_pool::COMMITMENT_VERSION,
+        },
+    )
+}
+
+/// Attest that the indexer's tree of unspent notes, rooted at
+/// `liabilities_root`, sums to `liabilities`; the program records the vault
+/// balance next to it.
+pub fn attest_reserves_instruction(
+    authority: &Pubkey,
+    liabilities_root: [u8; 32],
+    liabilities: u64,
+    note_count: u32,
+) -> Instruction {
+    let pool = pool_address();
+    instruction(
+        privacy_pool::accounts::AttestReserves {
+            pool,
+            pool_usdc: get_associated_token_address(&pool, &privacy_pool::USDC_MINT),
+            usdc_mint: privacy_pool::USDC_MINT,
+            stats: stats_address(),
+            attestation: reserves_address(),
+            authority: *authority,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            event_authority: event_authority_address(),
+            program: privacy_pool::ID,
+        },
+        privacy_pool::instruction::AttestReserves {
+            liabilities_root,
+            liabilities,
+            note_count,
+        },
+    )
+}
+
+/// Freeze the pool's tree and start moving its notes to the pool of the
+/// deployment at `new_program`, which must have run `open_import` first.
+pub fn migrate_pool_instruction(authority: &Pubkey, new_program: &Pubkey) -> Instruction {
+    instruction(
+        privacy_pool::accounts::MigratePool {
+            pool: pool_address(),
+            stats: stats_address(),
+            migration: migration_address(),
+            new_program: *new_program,
+            new_pool: pool_address_in(new_program),
+            new_import: import_address_in(new_program),
+            authority: *authority,
+            system_program: system_program::ID,
+            event_authority: event_authority_address(),
+            program: privacy_pool::ID,
+        },
+        privacy_pool::instruction::MigratePool {},
+    )
+}
+
+/// Public inputs the proof moving `note` in `migration` must commit to.
+pub fn migration_public_inputs(
+    migration: &privacy_pool::PoolMigration,
+    note: &privacy_pool::NoteMigration,
+) -> Vec<[u8; 32]> {
+    privacy_pool::migration_public_inputs(
+        migration,
+        privacy_pool::nullifier_pool_id(&pool_address()),
+        &get_associated_token_address(&migration.new_pool, &privacy_pool::USDC_MINT),
+        note,
+    )
+}
+
+/// Move proven notes into the v2 tree of the deployment at `new_program`,
+/// the one given to `migrate_pool`; their USDC goes to its vault.
+///
+/// `verifier_program` must match the pool's verifier config and
+/// `verifying_key` be the active key of `WITHDRAWAL_CIRCUIT_ID`;
+/// `proofs[i]` proves `moves[i]` (see [`migration_public_inputs`]).
+pub fn migrate_notes_instruction(
+    relay: &Pubkey,
+    verifier_program: &Pubkey,
+    verifying_key: &Pubkey,
+    new_program: &Pubkey,
+    proofs: Vec<Vec<u8>>,
+    moves: Vec<privacy_pool::NoteMigration>,
+    relay_nonce: u64,
+) -> Instruction {
+    let pool = pool_address();
+    let new_pool = pool_address_in(new_program);
+    let new_pda =
+        |seed: &[u8]| Pubkey::find_program_address(&[seed, new_pool.as_ref()], new_program).0;
+    instruction(
+        privacy_pool::accounts::MigrateNotes {
+            pool,
+            migration: migration_address(),
+            nullifiers: nullifier_set_address(privacy_pool::NULLIFIER_PURPOSE_SPEND),
+            verifier_config: verifier_config_address(),
+            verifying_key: *verifying_key,
+            active_key: verifier_active_key_address(
+                verifier_program,
+                &privacy_pool::WITHDRAWAL_CIRCUIT_ID,
+            ),
+            verifier_program: *verifier_program,
+            pool_usdc: get_associated_token_address(&pool, &privacy_pool::USDC_MINT),
+            new_program: *new_program,
+            new_pool,
+            new_import: new_pda(b"import"),
+            new_leaf_times: new_pda(b"leaf_times"),
+            new_stats: new_pda(b"stats"),
+            new_event_authority: Pubkey::find_program_address(&[b"__event_authority"], new_program)
+                .0,
+            new_pool_usdc: get_associated_token_address(&new_pool, &privacy_pool::USDC_MINT),
+            usdc_mint: privacy_pool::USDC_MINT,
+            limits: limits_address(),
+            stats: stats_address(),
+            relay_nonce: relay_nonce_address(relay),
+            relay: *relay,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+            pool_relay: pool_relay_address(),
+            event_authority: event_authority_address(),
+            program: privacy_pool::ID,
+        },
+        privacy_pool::instruction::MigrateNotes {
+            proofs,
+            moves,
+            relay_nonce,
+        },
+    )
+}
+
+/// Abandon the pool's migration and unfreeze its tree.
+pub fn cancel_migration_instruction(authority: &Pubkey) -> Instruction {
+    instruction(
+        privacy_pool::accounts::CancelMigration {
+            pool: pool_address(),
+            migration: migration_address(),
+            authority: *authority,
+            event_authority: event_authority_address(),
+            program: privacy_pool::ID,
+        },
+        privacy_pool::instruction::CancelMigration {},
+    )
+}
+
+/// Let the pool of the deployment at `source_program` migrate its notes
+/// into this one.
+pub fn open_import_instruction(authority: &Pubkey, source_program: &Pubkey) -> Instruction {
+    instruction(
+        privacy_pool::accounts::OpenImport {
+            pool: pool_address(),
+            pool_import: import_address_in(&privacy_pool::ID),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        privacy_pool::instruction::OpenImport {
+            source_program: *source_program,
+        },
+    )
+}
+
+/// Stop accepting migrated notes.
+pub fn close_import_instruction(authority: &Pubkey) -> Instruction {
+    instruction(
+        privacy_pool::accounts::CloseImport {
+            pool: pool_address(),
+            pool_import: import_address_in(&privacy_pool::ID),
+            authority: *authority,
+        },
+        privacy_pool::instruction::CloseImport {},
+    )
+}
+
+/// Propose `new_relay` as the pool's relay signer; it takes over once it
+/// signs `accept_relay`.
+pub fn set_relay_instruction(authority: &Pubkey, new_relay: &Pubkey) -> Instruction {
+    instruction(
+        privacy_pool::accounts::SetRelay {
+            pool: pool_address(),
+            pool_relay: pool_relay_address(),
+            authority: *authority,
+            system_program: system_program::ID,
+            event_authority: event_authority_address(),
+            program: privacy_pool::ID,
+        },
+        privacy_pool::instruction::SetRelay {
+            new_relay: *new_relay,
+        },
+    )
+}
+
+pub fn accept_relay_instruction(new_relay: &Pubkey) -> Instruction {
+    instruction(
+        privacy_pool::accounts::AcceptRelay {
+            pool: pool_address(),
+            pool_relay: pool_relay_address(),
+            new_relay: *new_relay,
+            event_authority: event_authority_address(),
+            program: privacy_pool::ID,
+        },
+        privacy_pool::instruction::AcceptRelay {},
+    )
+}
+
+/// Only accept deposit and withdrawals as top-level instructions or from
+/// `allowed_programs`; `enabled = false` accepts any caller again.
+pub fn set_cpi_guard_instruction(
+    authority: &Pubkey,
+    enabled: bool,
+    allowed_programs: Vec<Pubkey>,
+) -> Instruction {
+    instruction(
+        privacy_pool::accounts::SetCpiGuard {
+            pool: pool_address(),
+            cpi_guard: cpi_guard_address(),
+            authority: *authority,
+            system_program: system_program::ID,
+            event_authority: event_authority_address(),
+            program: privacy_pool::ID,
+        },
+        privacy_pool::instruction::SetCpiGuard {
+            enabled,
+            allowed_programs,
+        },
+    )
+}
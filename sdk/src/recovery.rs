@@ -0,0 +1,113 @@
+//! Wallet recovery from a seed.
+//!
+//! Notes created with [`Note::derive`] can be found again from the seed
+//! alone: derive secrets in index order and look for their commitments among
+//! the pool's deposits. Like BIP-44 account discovery, the scan stops after
+//! `gap_limit` consecutive indices with no deposit.
+//!
+//! A commitment also hides the note's amount, so each derived secret is
+//! tried against every distinct deposit amount; `deposit` instructions carry
+//! the amount in the clear. Commitments the relay appends with
+//! `add_commitment`, such as change notes, carry none and are not recovered.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anchor_lang::{AnchorDeserialize, Discriminator};
+
+use crate::note::Note;
+use crate::pool;
+use crate::replay::{LoggedTransaction, Replay};
+use crate::Result;
+
+/// Consecutive unused indices after which [`recover_notes`] stops, as in
+/// BIP-44
+pub const DEFAULT_GAP_LIMIT: u32 = 20;
+
+/// A note of the wallet found in the pool's history.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecoveredNote {
+    /// Derivation index of the note's secret
+    pub index: u32,
+    pub leaf_index: u32,
+    pub note: Note,
+    /// A withdrawal published the note's spend nullifier
+    pub spent: bool,
+}
+
+/// Notes of the wallet with `seed` deposited in `transactions`, in
+/// derivation order.
+///
+/// `transactions` should be the pool's full history; notes deposited in
+/// transactions left out are missed, and may end the scan early.
+pub fn recover_notes(
+    seed: &[u8],
+    transactions: &[LoggedTransaction],
+    gap_limit: u32,
+) -> Result<Vec<RecoveredNote>> {
+    let mut replay = Replay::default();
+    let mut deposited = HashMap::new();
+    for transaction in transactions {
+        replay.apply(transaction);
+        if !transaction.failed {
+            deposited.extend(deposit_amounts(transaction));
+        }
+    }
+
+    // Leaves by the amount deposited into them
+    let mut by_amount: BTreeMap<u64, HashMap<[u8; 32], u32>> = BTreeMap::new();
+    for (leaf_index, commitment) in &replay.leaves {
+        if let Some(amount) = deposited.get(commitment) {
+            by_amount
+                .entry(*amount)
+                .or_default()
+                .insert(*commitment, *leaf_index);
+        }
+    }
+
+    let withdrawn: HashSet<[u8; 32]> = replay.withdrawn.iter().copied().collect();
+    let pool_id = privacy_pool::nullifier_pool_id(&pool::pool_address());
+    let mut notes = Vec::new();
+    let mut unused = 0;
+    let mut index = 0u32;
+    while unused < gap_limit {
+        let mut found = false;
+        for (amount, leaves) in &by_amount {
+            let note = Note::derive(seed, index, *amount);
+            let Some(leaf_index) = leaves.get(&note.commitment()?) else {
+                continue;
+            };
+            let nullifier =
+                note.nullifier(*leaf_index, &pool_id, privacy_pool::NULLIFIER_PURPOSE_SPEND)?;
+            notes.push(RecoveredNote {
+                index,
+                leaf_index: *leaf_index,
+                spent: withdrawn.contains(&nullifier),
+                note,
+            });
+            found = true;
+            break;
+        }
+        unused = if found { 0 } else { unused + 1 };
+        let Some(next) = index.checked_add(1) else {
+            break;
+        };
+        index = next;
+    }
+    Ok(notes)
+}
+
+/// Commitment and amount of every `deposit` instruction in `transaction`,
+/// top-level or invoked by CPI.
+pub(crate) fn deposit_amounts(
+    transaction: &LoggedTransaction,
+) -> impl Iterator<Item = ([u8; 32], u64)> + '_ {
+    transaction
+        .instructions
+        .iter()
+        .filter(|(program, _)| *program == privacy_pool::ID)
+        .filter_map(|(_, data)| {
+            let mut args = data.strip_prefix(privacy_pool::instruction::Deposit::DISCRIMINATOR)?;
+            let deposit = privacy_pool::instruction::Deposit::deserialize(&mut args).ok()?;
+            Some((deposit.commitment, deposit.amount))
+        })
+}
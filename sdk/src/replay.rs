@@ -0,0 +1,943 @@
+//! Event-sourced reconstruction of batch and pool state.
+//!
+//! Folds the `emit_cpi!` events carried by historical transactions into the
+//! state the programs should hold, then diffs it against account data. An
+//! operator audit for corrupted accounts and events an indexer missed; see
+//! the `obsidian-replay` binary.
+//!
+//! Only what the events carry can be checked: `record_nullifier` emits
+//! nothing, so nullifier sets are only checked for withdrawals.
+//!
+//! The same fold backs per-user order history ([`Replay::user_orders`],
+//! served by the `obsidian-indexer` binary).
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::{json, Value};
+use x25519_dalek::StaticSecret;
+
+use crate::encryption;
+use crate::mpc::{self, BatchStatus, MpcAccount, MpcEvent};
+use crate::pool;
+use crate::{Result, SdkError};
+
+// ============================================================================
+// Input
+// ============================================================================
+
+/// A transaction as returned by `getTransaction` with `"encoding": "json"`.
+#[derive(Clone, Debug)]
+pub struct LoggedTransaction {
+    pub signature: String,
+    pub slot: u64,
+    /// Failed transactions changed no state and are skipped by [`Replay`]
+    pub failed: bool,
+    /// Program and data of every instruction, inner ones right after the
+    /// top-level instruction that invoked them
+    pub instructions: Vec<(Pubkey, Vec<u8>)>,
+}
+
+impl LoggedTransaction {
+    pub fn from_rpc_json(value: &Value) -> Result<Self> {
+        let signature = value["transaction"]["signatures"][0]
+            .as_str()
+            .ok_or_else(|| malformed("transaction.signatures"))?
+            .to_string();
+        let slot = value["slot"].as_u64().ok_or_else(|| malformed("slot"))?;
+        let meta = &value["meta"];
+        let failed = !meta["err"].is_null();
+
+        // Static keys, then keys loaded from lookup tables
+        let message = &value["transaction"]["message"];
+        let mut keys = Vec::new();
+        for key in [
+            &message["accountKeys"],
+            &meta["loadedAddresses"]["writable"],
+            &meta["loadedAddresses"]["readonly"],
+        ]
+        .into_iter()
+        .filter_map(Value::as_array)
+        .flatten()
+        {
+            let key = key.as_str().ok_or_else(|| malformed("accountKeys"))?;
+            keys.push(Pubkey::from_str(key).map_err(|_| malformed("accountKeys"))?);
+        }
+
+        let mut inner: BTreeMap<u64, &Vec<Value>> = BTreeMap::new();
+        for set in meta["innerInstructions"].as_array().into_iter().flatten() {
+            let index = set["index"]
+                .as_u64()
+                .ok_or_else(|| malformed("innerInstructions.index"))?;
+            if let Some(instructions) = set["instructions"].as_array() {
+                inner.insert(index, instructions);
+            }
+        }
+
+        let mut instructions = Vec::new();
+        let top_level = message["instructions"]
+            .as_array()
+            .ok_or_else(|| malformed("message.instructions"))?;
+        for (index, instruction) in top_level.iter().enumerate() {
+            instructions.push(decode_instruction(instruction, &keys)?);
+            for instruction in inner.get(&(index as u64)).into_iter().copied().flatten() {
+                instructions.push(decode_instruction(instruction, &keys)?);
+            }
+        }
+
+        Ok(Self {
+            signature,
+            slot,
+            failed,
+            instructions,
+        })
+    }
+}
+
+fn decode_instruction(instruction: &Value, keys: &[Pubkey]) -> Result<(Pubkey, Vec<u8>)> {
+    let program = instruction["programIdIndex"]
+        .as_u64()
+        .and_then(|index| keys.get(index as usize))
+        .ok_or_else(|| malformed("programIdIndex"))?;
+    let data = instruction["data"]
+        .as_str()
+        .and_then(|data| bs58::decode(data).into_vec().ok())
+        .ok_or_else(|| malformed("instruction data"))?;
+    Ok((*program, data))
+}
+
+fn malformed(field: &str) -> SdkError {
+    SdkError::InvalidTransaction(field.to_string())
+}
+
+/// Account data keyed by address, from `solana account --output json`.
+#[derive(Clone, Debug, Default)]
+pub struct AccountSnapshot {
+    /// Owner program and raw data
+    pub accounts: BTreeMap<Pubkey, (Pubkey, Vec<u8>)>,
+}
+
+impl AccountSnapshot {
+    /// Parse an array of `solana account --output json` objects.
+    pub fn from_json(value: &Value) -> Result<Self> {
+        let mut accounts = BTreeMap::new();
+        for entry in value.as_array().ok_or(SdkError::InvalidAccountData)? {
+            let address = entry["pubkey"]
+                .as_str()
+                .and_then(|k| Pubkey::from_str(k).ok());
+            let owner = entry["account"]["owner"]
+                .as_str()
+                .and_then(|k| Pubkey::from_str(k).ok());
+            let data = entry["account"]["data"][0]
+                .as_str()
+                .and_then(|data| BASE64.decode(data).ok());
+            match (address, owner, data) {
+                (Some(address), Some(owner), Some(data)) => {
+                    accounts.insert(address, (owner, data));
+                }
+                _ => return Err(SdkError::InvalidAccountData),
+            }
+        }
+        Ok(Self { accounts })
+    }
+
+    pub fn insert(&mut self, address: Pubkey, owner: Pubkey, data: Vec<u8>) {
+        self.accounts.insert(address, (owner, data));
+    }
+
+    fn owned_by<'a>(&'a self, program: &'a Pubkey) -> impl Iterator<Item = (&'a Pubkey, &'a [u8])> {
+        self.accounts
+            .iter()
+            .filter(move |(_, (owner, _))| owner == program)
+            .map(|(address, (_, data))| (address, data.as_slice()))
+    }
+}
+
+// ============================================================================
+// Replay
+// ============================================================================
+
+/// Batch fields the lifecycle events carry; `authority` and `created_at`
+/// are not in any event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplayedBatch {
+    pub market_id: String,
+    pub side: u8,
+    pub status: BatchStatus,
+    pub order_count: u8,
+    pub total_usdc: u64,
+    pub total_shares: u64,
+    pub distributions_completed: u8,
+    pub recorded_shares: u64,
+    pub cluster_offset: u32,
+    pub circuit_version: u16,
+    pub label: String,
+    pub intent_hash: Option<[u8; 32]>,
+    pub filled_usdc: u64,
+    pub recorded_refunds: u64,
+    pub distribution_root: Option<[u8; 32]>,
+    pub parked_usdc: u64,
+    pub parked_adapter: Pubkey,
+    pub sequence: u64,
+    pub staged_distributions: u8,
+    pub recorded_distributions: u8,
+    pub distributions_finalized: bool,
+    pub staged_shares: u64,
+}
+
+/// What the events reveal about one order. Plain `record_order` names no
+/// user, so an order only shows up here once it was delegated or paid out.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReplayedOrder {
+    /// Signer of a delegated order intent
+    pub user: Option<Pubkey>,
+    pub order_commitment: Option<[u8; 32]>,
+    pub payout: Option<Payout>,
+    /// `DistributionExecuted` or `claim_shares` transaction, or `None`
+    /// until paid out
+    pub paid_out: Option<String>,
+    /// Claimed by the user: a stealth claim or `claim_shares`
+    pub claimed: bool,
+}
+
+/// How an order's distribution was recorded
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Payout {
+    Plain {
+        wallet: Pubkey,
+        shares: u64,
+        usdc_refund: u64,
+        unlock_at: i64,
+    },
+    /// Shares only readable with the order's viewing key
+    Encrypted { wallet: Pubkey },
+    /// Claimed against a commitment; no wallet on-chain
+    Stealth { shares: u64 },
+}
+
+/// State rebuilt from events, applied oldest first.
+#[derive(Clone, Debug, Default)]
+pub struct Replay {
+    pub batches: BTreeMap<Pubkey, ReplayedBatch>,
+    /// Orders of replayed batches, by batch and order index
+    pub orders: BTreeMap<(Pubkey, u8), ReplayedOrder>,
+    /// Pool leaves from `DepositEvent` and `CommitmentAddedEvent`
+    pub leaves: BTreeMap<u32, [u8; 32]>,
+    /// Nullifiers from `WithdrawalEvent`
+    pub withdrawn: Vec<[u8; 32]>,
+    /// Batch events seen before (or without) the batch's `BatchCreated`,
+    /// with the transaction carrying them: the history is incomplete
+    pub orphaned: Vec<(Pubkey, String)>,
+    pub applied: usize,
+    pub skipped_failed: usize,
+}
+
+impl Replay {
+    pub fn apply(&mut self, transaction: &LoggedTransaction) {
+        if transaction.failed {
+            self.skipped_failed += 1;
+            return;
+        }
+        for (program, data) in &transaction.instructions {
+            let Some(event) = data.strip_prefix(EVENT_IX_TAG_LE) else {
+                continue;
+            };
+            if *program == privacy_pool::ID {
+                self.apply_pool_event(event);
+            } else if *program == mpc::ID {
+                self.apply_batch_event(event, &transaction.signature);
+            }
+        }
+        self.applied += 1;
+    }
+
+    fn apply_pool_event(&mut self, event: &[u8]) {
+        if let Some((leaf_index, commitment)) = decode_leaf_event(event) {
+            self.leaves.insert(leaf_index, commitment);
+        } else if let Some(e) = decode_pool_event::<privacy_pool::WithdrawalEvent>(event) {
+            self.withdrawn.push(e.nullifier);
+        }
+    }
+
+    fn apply_batch_event(&mut self, event: &[u8], signature: &str) {
+        if let Some(e) = mpc::BatchCreated::decode(event) {
+            self.batches.insert(
+                e.batch,
+                ReplayedBatch {
+                    market_id: e.market_id,
+                    side: e.side,
+                    status: BatchStatus::Open,
+                    order_count: 0,
+                    total_usdc: 0,
+                    total_shares: 0,
+                    distributions_completed: 0,
+                    recorded_shares: 0,
+                    cluster_offset: e.cluster_offset,
+                    circuit_version: e.circuit_version,
+                    label: e.label,
+                    intent_hash: e.intent_hash,
+                    filled_usdc: 0,
+                    recorded_refunds: 0,
+                    distribution_root: None,
+                    parked_usdc: 0,
+                    parked_adapter: Pubkey::default(),
+                    sequence: e.sequence,
+                    staged_distributions: 0,
+                    recorded_distributions: 0,
+                    distributions_finalized: false,
+                    staged_shares: 0,
+                },
+            );
+            return;
+        }
+
+        if let Some((address, order_index, update)) = order_update(event, signature) {
+            if self.batches.contains_key(&address) {
+                let order = self.orders.entry((address, order_index)).or_default();
+                order.apply(update);
+            }
+        }
+
+        let Some((address, update)) = batch_update(event) else {
+            return;
+        };
+        match self.batches.get_mut(&address) {
+            Some(batch) => batch.apply(update),
+            None => self.orphaned.push((address, signature.to_string())),
+        }
+    }
+
+    /// Batches of `market_id` in creation order, whatever order their
+    /// events were delivered in.
+    pub fn market_batches(&self, market_id: &str) -> Vec<(Pubkey, &ReplayedBatch)> {
+        let mut batches: Vec<_> = self
+            .batches
+            .iter()
+            .filter(|(_, batch)| batch.market_id == market_id)
+            .map(|(address, batch)| (*address, batch))
+            .collect();
+        batches.sort_by_key(|(_, batch)| batch.sequence);
+        batches
+    }
+
+    /// Compare the replayed state with `snapshot`.
+    ///
+    /// Batches are checked both ways: replayed but missing on-chain, and
+    /// on-chain but never created in the history.
+    pub fn diff(&self, snapshot: &AccountSnapshot) -> Vec<Divergence> {
+        let mut divergences = Vec::new();
+
+        let mut on_chain_batches = BTreeMap::new();
+        for (address, data) in snapshot.owned_by(&mpc::ID) {
+            if let Ok(batch) = mpc::Batch::decode(data) {
+                on_chain_batches.insert(*address, batch);
+            }
+        }
+        for (address, replayed) in &self.batches {
+            match on_chain_batches.get(address) {
+                Some(batch) => diff_batch(*address, replayed, batch, &mut divergences),
+                None => {
+                    divergences.push(Divergence::new(*address, "account", "created", "missing"))
+                }
+            }
+        }
+        for (address, batch) in &on_chain_batches {
+            if !self.batches.contains_key(address) {
+                divergences.push(Divergence::new(
+                    *address,
+                    "account",
+                    "never created",
+                    format!("{:?}", batch.status),
+                ));
+            }
+        }
+
+        self.diff_pool(snapshot, &mut divergences);
+        divergences
+    }
+
+    fn diff_pool(&self, snapshot: &AccountSnapshot, divergences: &mut Vec<Divergence>) {
+        let pool_address = pool::pool_address();
+        let Some((_, data)) = snapshot.accounts.get(&pool_address) else {
+            if !self.leaves.is_empty() {
+                divergences.push(Divergence::new(
+                    pool_address,
+                    "account",
+                    "exists",
+                    "missing",
+                ));
+            }
+            return;
+        };
+        let Ok(on_chain) = privacy_pool::PrivacyPool::try_deserialize(&mut data.as_slice()) else {
+            divergences.push(Divergence::new(
+                pool_address,
+                "account",
+                "PrivacyPool",
+                "undecodable",
+            ));
+            return;
+        };
+
+        let next_index = self.leaves.keys().next_back().map_or(0, |i| i + 1);
+        if next_index != on_chain.next_index {
+            divergences.push(Divergence::new(
+                pool_address,
+                "next_index",
+                next_index,
+                on_chain.next_index,
+            ));
+        }
+        for index in 0..next_index.max(on_chain.next_index) {
+            let replayed = self.leaves.get(&index).copied().unwrap_or_default();
+            let actual = on_chain
+                .leaves
+                .get(index as usize)
+                .copied()
+                .unwrap_or_default();
+            if replayed != actual {
+                divergences.push(Divergence::new(
+                    pool_address,
+                    format!("leaves[{index}]"),
+                    hex(&replayed),
+                    hex(&actual),
+                ));
+            }
+        }
+
+        let spent: Vec<[u8; 32]> = snapshot
+            .owned_by(&privacy_pool::ID)
+            .filter_map(|(_, data)| privacy_pool::NullifierSet::decode(data))
+            .flat_map(|(_, spent)| spent.iter().copied())
+            .collect();
+        let spend_set = pool::nullifier_set_address(privacy_pool::NULLIFIER_PURPOSE_SPEND);
+        for nullifier in &self.withdrawn {
+            if !spent.contains(nullifier) {
+                divergences.push(Divergence::new(
+                    spend_set,
+                    "nullifier",
+                    hex(nullifier),
+                    "missing",
+                ));
+            }
+        }
+    }
+
+    /// Orders `wallet` placed through a delegated intent or was paid out
+    /// to, by batch address and order index.
+    pub fn user_orders(&self, wallet: &Pubkey) -> Vec<UserOrder> {
+        self.orders
+            .iter()
+            .filter(|(_, order)| {
+                order.user == Some(*wallet)
+                    || matches!(
+                        order.payout,
+                        Some(Payout::Plain { wallet: w, .. } | Payout::Encrypted { wallet: w })
+                            if w == *wallet
+                    )
+            })
+            .map(|((batch, order_index), order)| {
+                let replayed = &self.batches[batch];
+                let (shares, usdc_refund, unlock_at) = match order.payout {
+                    Some(Payout::Plain {
+                        shares,
+                        usdc_refund,
+                        unlock_at,
+                        ..
+                    }) => (Some(shares), usdc_refund, unlock_at),
+                    Some(Payout::Stealth { shares }) => (Some(shares), 0, 0),
+                    Some(Payout::Encrypted { .. }) | None => (None, 0, 0),
+                };
+                UserOrder {
+                    batch: *batch,
+                    order_index: *order_index,
+                    market_id: replayed.market_id.clone(),
+                    side: replayed.side,
+                    batch_status: replayed.status,
+                    delegated: order.user.is_some(),
+                    encrypted: matches!(order.payout, Some(Payout::Encrypted { .. })),
+                    shares,
+                    usdc_refund,
+                    unlock_at,
+                    paid_out: order.paid_out.clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+// ============================================================================
+// User history
+// ============================================================================
+
+/// One order in a user's history, joined with its batch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserOrder {
+    pub batch: Pubkey,
+    pub order_index: u8,
+    pub market_id: String,
+    pub side: u8,
+    pub batch_status: BatchStatus,
+    /// Placed through `record_delegated_order`
+    pub delegated: bool,
+    /// Shares were recorded encrypted; see [`UserOrder::reveal_shares`]
+    pub encrypted: bool,
+    /// `None` until distributed, or while encrypted and unrevealed
+    pub shares: Option<u64>,
+    pub usdc_refund: u64,
+    pub unlock_at: i64,
+    pub paid_out: Option<String>,
+}
+
+impl UserOrder {
+    /// Decrypt the shares of an encrypted distribution with the viewing
+    /// key the order was encrypted under. The ciphertext is not in any
+    /// event, so it is read from the `EncryptedDistribution` in `snapshot`.
+    pub fn reveal_shares(
+        &mut self,
+        snapshot: &AccountSnapshot,
+        viewing_key: &StaticSecret,
+        mxe_public_key: &[u8; 32],
+    ) -> Result<()> {
+        if !self.encrypted || self.shares.is_some() {
+            return Ok(());
+        }
+        let address = mpc::encrypted_distribution_address(&self.batch, self.order_index);
+        let (_, data) = snapshot
+            .accounts
+            .get(&address)
+            .ok_or(SdkError::InvalidAccountData)?;
+        let distribution = mpc::EncryptedDistribution::decode(data)?;
+        self.shares = Some(encryption::decrypt_shares(
+            viewing_key,
+            mxe_public_key,
+            distribution.nonce,
+            &distribution.encrypted_shares,
+        )?);
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "batch": self.batch.to_string(),
+            "orderIndex": self.order_index,
+            "marketId": self.market_id,
+            "side": self.side,
+            "batchStatus": format!("{:?}", self.batch_status),
+            "delegated": self.delegated,
+            "encrypted": self.encrypted,
+            "shares": self.shares,
+            "usdcRefund": self.usdc_refund,
+            "unlockAt": self.unlock_at,
+            "paidOut": self.paid_out,
+        })
+    }
+}
+
+/// Effect of an event on one order
+enum OrderUpdate {
+    Delegated {
+        user: Pubkey,
+        order_commitment: [u8; 32],
+    },
+    Recorded(Payout),
+    Executed {
+        tx_signature: String,
+    },
+    Claimed,
+    /// `claim_shares` records and pays out in one step
+    PulledOut {
+        payout: Payout,
+        tx_signature: String,
+    },
+}
+
+/// `signature` is the transaction that logged `event`
+fn order_update(event: &[u8], signature: &str) -> Option<(Pubkey, u8, OrderUpdate)> {
+    if let Some(e) = mpc::DelegatedOrderRecorded::decode(event) {
+        let update = OrderUpdate::Delegated {
+            user: e.user,
+            order_commitment: e.order_commitment,
+        };
+        Some((e.batch, e.order_index, update))
+    } else if let Some(e) = mpc::DistributionRecorded::decode(event) {
+        let payout = Payout::Plain {
+            wallet: e.wallet,
+            shares: e.shares,
+            usdc_refund: e.usdc_refund,
+            unlock_at: e.unlock_at,
+        };
+        Some((e.batch, e.order_index, OrderUpdate::Recorded(payout)))
+    } else if let Some(e) = mpc::EncryptedDistributionRecorded::decode(event) {
+        let payout = Payout::Encrypted { wallet: e.wallet };
+        Some((e.batch, e.order_index, OrderUpdate::Recorded(payout)))
+    } else if let Some(e) = mpc::StealthDistributionRecorded::decode(event) {
+        let payout = Payout::Stealth { shares: e.shares };
+        Some((e.batch, e.order_index, OrderUpdate::Recorded(payout)))
+    } else if let Some(e) = mpc::DistributionExecuted::decode(event) {
+        let update = OrderUpdate::Executed {
+            tx_signature: e.tx_signature,
+        };
+        Some((e.batch, e.order_index, update))
+    } else if let Some(e) = mpc::SharesClaimed::decode(event) {
+        let payout = Payout::Plain {
+            wallet: e.wallet,
+            shares: e.shares,
+            usdc_refund: e.usdc_refund,
+            unlock_at: e.unlock_at,
+        };
+        let update = OrderUpdate::PulledOut {
+            payout,
+            tx_signature: signature.to_string(),
+        };
+        Some((e.batch, e.order_index, update))
+    } else {
+        mpc::StealthDistributionClaimed::decode(event)
+            .map(|e| (e.batch, e.order_index, OrderUpdate::Claimed))
+    }
+}
+
+impl ReplayedOrder {
+    fn apply(&mut self, update: OrderUpdate) {
+        match update {
+            OrderUpdate::Delegated {
+                user,
+                order_commitment,
+            } => {
+                self.user = Some(user);
+                self.order_commitment = Some(order_commitment);
+            }
+            OrderUpdate::Recorded(payout) => self.payout = Some(payout),
+            OrderUpdate::Executed { tx_signature } => self.paid_out = Some(tx_signature),
+            OrderUpdate::Claimed => self.claimed = true,
+            OrderUpdate::PulledOut {
+                payout,
+                tx_signature,
+            } => {
+                self.payout = Some(payout);
+                self.paid_out = Some(tx_signature);
+                self.claimed = true;
+            }
+        }
+    }
+}
+
+/// Effect of a batch event after `BatchCreated`
+enum BatchUpdate {
+    Migrated {
+        cluster_offset: u32,
+    },
+    OrderRecorded {
+        order_count: u8,
+    },
+    Closed {
+        total_usdc: u64,
+        order_count: u8,
+    },
+    Executed {
+        total_shares: u64,
+        filled_usdc: u64,
+    },
+    /// `expire_reveal` sent a Closed batch back to Open
+    Reopened,
+    Staged {
+        root: [u8; 32],
+        count: u8,
+        shares: u64,
+    },
+    Finalized,
+    Failed,
+    Frozen,
+    Parked {
+        amount: u64,
+        adapter: Pubkey,
+    },
+    Recalled,
+    /// Encrypted distributions record 0: their shares are not revealed.
+    /// Only plain distributions carry a refund
+    DistributionRecorded {
+        shares: u64,
+        usdc_refund: u64,
+    },
+    DistributionCompleted,
+    /// Recorded and completed at once by `claim_shares`
+    SharesClaimed {
+        shares: u64,
+        usdc_refund: u64,
+    },
+}
+
+fn batch_update(event: &[u8]) -> Option<(Pubkey, BatchUpdate)> {
+    if let Some(e) = mpc::BatchClusterMigrated::decode(event) {
+        let cluster_offset = e.cluster_offset;
+        Some((e.batch, BatchUpdate::Migrated { cluster_offset }))
+    } else if let Some(e) = mpc::OrderRecorded::decode(event) {
+        let order_count = e.order_count;
+        Some((e.batch, BatchUpdate::OrderRecorded { order_count }))
+    } else if let Some(e) = mpc::BatchClosed::decode(event) {
+        Some((
+            e.batch,
+            BatchUpdate::Closed {
+                total_usdc: e.total_usdc,
+                order_count: e.order_count,
+            },
+        ))
+    } else if let Some(e) = mpc::ExecutionRecorded::decode(event) {
+        Some((
+            e.batch,
+            BatchUpdate::Executed {
+                total_shares: e.total_shares,
+                filled_usdc: e.filled_usdc,
+            },
+        ))
+    } else if let Some(e) = mpc::RevealExpired::decode(event) {
+        Some((e.batch, BatchUpdate::Reopened))
+    } else if let Some(e) = mpc::BatchFailed::decode(event) {
+        Some((e.batch, BatchUpdate::Failed))
+    } else if let Some(e) = mpc::EscrowParked::decode(event) {
+        Some((
+            e.batch,
+            BatchUpdate::Parked {
+                amount: e.amount,
+                adapter: e.adapter,
+            },
+        ))
+    } else if let Some(e) = mpc::EscrowRecalled::decode(event) {
+        Some((e.batch, BatchUpdate::Recalled))
+    } else if let Some(e) = mpc::ReconciliationMismatch::decode(event) {
+        Some((e.batch, BatchUpdate::Frozen))
+    } else if let Some(e) = mpc::DistributionsStaged::decode(event) {
+        let (root, count, shares) = (e.root, e.count, e.shares);
+        Some((
+            e.batch,
+            BatchUpdate::Staged {
+                root,
+                count,
+                shares,
+            },
+        ))
+    } else if let Some(e) = mpc::DistributionsFinalized::decode(event) {
+        Some((e.batch, BatchUpdate::Finalized))
+    } else if let Some(e) = mpc::DistributionRecorded::decode(event) {
+        Some((
+            e.batch,
+            BatchUpdate::DistributionRecorded {
+                shares: e.shares,
+                usdc_refund: e.usdc_refund,
+            },
+        ))
+    } else if let Some(e) = mpc::StealthDistributionRecorded::decode(event) {
+        Some((
+            e.batch,
+            BatchUpdate::DistributionRecorded {
+                shares: e.shares,
+                usdc_refund: 0,
+            },
+        ))
+    } else if let Some(e) = mpc::EncryptedDistributionRecorded::decode(event) {
+        Some((
+            e.batch,
+            BatchUpdate::DistributionRecorded {
+                shares: 0,
+                usdc_refund: 0,
+            },
+        ))
+    } else if let Some(e) = mpc::DistributionExecuted::decode(event) {
+        Some((e.batch, BatchUpdate::DistributionCompleted))
+    } else if let Some(e) = mpc::SharesClaimed::decode(event) {
+        Some((
+            e.batch,
+            BatchUpdate::SharesClaimed {
+                shares: e.shares,
+                usdc_refund: e.usdc_refund,
+            },
+        ))
+    } else {
+        mpc::StealthDistributionClaimed::decode(event)
+            .map(|e| (e.batch, BatchUpdate::DistributionCompleted))
+    }
+}
+
+impl ReplayedBatch {
+    /// Same transitions as the matching obsidian_mpc instruction
+    fn apply(&mut self, update: BatchUpdate) {
+        match update {
+            BatchUpdate::Migrated { cluster_offset } => self.cluster_offset = cluster_offset,
+            BatchUpdate::OrderRecorded { order_count } => self.order_count = order_count,
+            BatchUpdate::Closed {
+                total_usdc,
+                order_count,
+            } => {
+                self.status = BatchStatus::Closed;
+                self.total_usdc = total_usdc;
+                self.order_count = order_count;
+            }
+            BatchUpdate::Executed {
+                total_shares,
+                filled_usdc,
+            } => {
+                self.status = BatchStatus::Executed;
+                self.total_shares = total_shares;
+                self.filled_usdc = filled_usdc;
+            }
+            BatchUpdate::Reopened => self.status = BatchStatus::Open,
+            BatchUpdate::Staged {
+                root,
+                count,
+                shares,
+            } => {
+                self.distribution_root = Some(root);
+                self.staged_distributions = count;
+                self.staged_shares = shares;
+            }
+            BatchUpdate::Finalized => self.distributions_finalized = true,
+            BatchUpdate::Failed => self.status = BatchStatus::Failed,
+            BatchUpdate::Frozen => self.status = BatchStatus::Frozen,
+            BatchUpdate::Parked { amount, adapter } => {
+                self.parked_usdc = amount;
+                self.parked_adapter = adapter;
+            }
+            BatchUpdate::Recalled => {
+                self.parked_usdc = 0;
+                self.parked_adapter = Pubkey::default();
+            }
+            BatchUpdate::DistributionRecorded {
+                shares,
+                usdc_refund,
+            } => {
+                if self.status == BatchStatus::Executed {
+                    self.status = BatchStatus::Distributing;
+                }
+                self.recorded_shares += shares;
+                self.recorded_refunds += usdc_refund;
+                self.recorded_distributions += 1;
+            }
+            BatchUpdate::DistributionCompleted => {
+                self.distributions_completed += 1;
+                if self.distributions_completed == self.order_count {
+                    self.status = BatchStatus::Completed;
+                }
+            }
+            BatchUpdate::SharesClaimed {
+                shares,
+                usdc_refund,
+            } => {
+                self.apply(BatchUpdate::DistributionRecorded {
+                    shares,
+                    usdc_refund,
+                });
+                self.apply(BatchUpdate::DistributionCompleted);
+            }
+        }
+    }
+}
+
+fn diff_batch(
+    address: Pubkey,
+    replayed: &ReplayedBatch,
+    on_chain: &mpc::Batch,
+    divergences: &mut Vec<Divergence>,
+) {
+    macro_rules! check {
+        ($($field:ident),*) => {
+            $(
+                if replayed.$field != on_chain.$field {
+                    divergences.push(Divergence::new(
+                        address,
+                        stringify!($field),
+                        format!("{:?}", replayed.$field),
+                        format!("{:?}", on_chain.$field),
+                    ));
+                }
+            )*
+        };
+    }
+    check!(
+        market_id,
+        side,
+        status,
+        order_count,
+        total_usdc,
+        total_shares,
+        distributions_completed,
+        recorded_shares,
+        cluster_offset,
+        circuit_version,
+        label,
+        intent_hash,
+        filled_usdc,
+        recorded_refunds,
+        distribution_root,
+        parked_usdc,
+        parked_adapter,
+        sequence,
+        staged_distributions,
+        recorded_distributions,
+        distributions_finalized,
+        staged_shares
+    );
+}
+
+/// Leaf index and commitment of a `DepositEvent` or `CommitmentAddedEvent`.
+/// Only their common prefix is read, so events logged before leaves were
+/// versioned still decode.
+fn decode_leaf_event(data: &[u8]) -> Option<(u32, [u8; 32])> {
+    let mut body = data
+        .strip_prefix(privacy_pool::DepositEvent::DISCRIMINATOR)
+        .or_else(|| data.strip_prefix(privacy_pool::CommitmentAddedEvent::DISCRIMINATOR))?;
+    <(u32, [u8; 32])>::deserialize(&mut body).ok()
+}
+
+pub(crate) fn decode_pool_event<E: AnchorDeserialize + Discriminator>(data: &[u8]) -> Option<E> {
+    let mut body = data.strip_prefix(E::DISCRIMINATOR)?;
+    E::deserialize(&mut body).ok()
+}
+
+fn hex(bytes: &[u8; 32]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+// ============================================================================
+// Divergences
+// ============================================================================
+
+/// A field whose on-chain value differs from the replayed one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    pub account: Pubkey,
+    pub field: String,
+    pub replayed: String,
+    pub on_chain: String,
+}
+
+impl Divergence {
+    fn new(
+        account: Pubkey,
+        field: impl Into<String>,
+        replayed: impl ToString,
+        on_chain: impl ToString,
+    ) -> Self {
+        Self {
+            account,
+            field: field.into(),
+            replayed: replayed.to_string(),
+            on_chain: on_chain.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}: replayed {}, on-chain {}",
+            self.account, self.field, self.replayed, self.on_chain
+        )
+    }
+}
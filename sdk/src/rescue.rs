@@ -0,0 +1,238 @@
+//! Rescue over the Curve25519 base field (p = 2^255 - 19).
+//!
+//! Arcium encrypts `Enc<Shared, _>` circuit inputs with the Rescue block
+//! cipher in counter mode, keyed by a Rescue-Prime hash of the x25519
+//! shared secret. Both constructions share the permutation below and use
+//! a Cauchy MDS matrix; they differ only in width, round count and how
+//! the round constants are generated.
+
+use ark_ff::fields::{Field, PrimeField};
+use ark_ff::BigInteger;
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::Shake256;
+
+mod field {
+    // The ark-ff 0.4 derive expands to impls inside a const item.
+    #![allow(non_local_definitions)]
+
+    use ark_ff::fields::{Fp256, MontBackend, MontConfig};
+
+    #[derive(MontConfig)]
+    #[modulus = "57896044618658097711785492504343953926634992332820282019728792003956564819949"]
+    #[generator = "2"]
+    pub struct FqConfig;
+
+    /// Element of the Curve25519 base field.
+    pub type Fq = Fp256<MontBackend<FqConfig, 4>>;
+}
+
+pub use field::{Fq, FqConfig};
+
+const ALPHA: [u64; 1] = [5];
+/// 5^-1 mod (p - 1), little-endian limbs.
+const ALPHA_INV: [u64; 4] = [
+    0xccccccccccccccc1,
+    0xcccccccccccccccc,
+    0xcccccccccccccccc,
+    0x4ccccccccccccccc,
+];
+
+/// Field elements per cipher block.
+pub const BLOCK_SIZE: usize = 5;
+const CIPHER_ROUNDS: usize = 10;
+const CIPHER_SEED: &[u8] = b"encrypt everything, compute anything";
+
+const HASH_RATE: usize = 7;
+const HASH_CAPACITY: usize = 5;
+const HASH_SECURITY_LEVEL: usize = 256;
+/// ceil(1.5 * max(5, l1)) with l1 = 5 from the Groebner basis bound for
+/// width 12 at 256-bit security.
+const HASH_ROUNDS: usize = 8;
+
+/// Bytes of SHAKE256 output reduced into each constant; the extra 16
+/// bytes keep the reduction bias negligible.
+const CONSTANT_BYTES: usize = 32 + 16;
+
+type Vector = Vec<Fq>;
+
+fn cauchy_mds(m: usize) -> Vec<Vector> {
+    (1..=m)
+        .map(|i| {
+            (1..=m)
+                .map(|j| Fq::from((i + j) as u64).inverse().expect("nonzero"))
+                .collect()
+        })
+        .collect()
+}
+
+fn mul(matrix: &[Vector], v: &[Fq]) -> Vector {
+    matrix
+        .iter()
+        .map(|row| row.iter().zip(v).map(|(a, b)| *a * b).sum())
+        .collect()
+}
+
+fn add(a: &[Fq], b: &[Fq]) -> Vector {
+    a.iter().zip(b).map(|(x, y)| *x + y).collect()
+}
+
+fn sbox(v: &[Fq], exponent: &[u64]) -> Vector {
+    v.iter().map(|x| x.pow(exponent)).collect()
+}
+
+fn shake_constants(seed: &[u8], count: usize) -> Vector {
+    let mut shake = Shake256::default();
+    shake.update(seed);
+    let mut reader = shake.finalize_xof();
+    (0..count)
+        .map(|_| {
+            let mut chunk = [0u8; CONSTANT_BYTES];
+            reader.read(&mut chunk);
+            Fq::from_le_bytes_mod_order(&chunk)
+        })
+        .collect()
+}
+
+/// Rescue block cipher with a fixed key.
+pub struct RescueCipher {
+    mds: Vec<Vector>,
+    round_keys: Vec<Vector>,
+}
+
+impl RescueCipher {
+    pub fn new(key: &[Fq; BLOCK_SIZE]) -> Self {
+        let m = BLOCK_SIZE;
+        let mds = cauchy_mds(m);
+
+        // Round constants: an affine recurrence seeded from SHAKE256.
+        let raw = shake_constants(CIPHER_SEED, m * m + 2 * m);
+        let matrix: Vec<Vector> = raw[..m * m].chunks(m).map(<[Fq]>::to_vec).collect();
+        let affine = &raw[m * m + m..];
+        let mut constants = vec![raw[m * m..m * m + m].to_vec()];
+        for r in 0..2 * CIPHER_ROUNDS {
+            let next = add(&mul(&matrix, &constants[r]), affine);
+            constants.push(next);
+        }
+
+        // Key schedule: run the key through the permutation, keeping every state.
+        let mut state = add(key, &constants[0]);
+        let mut round_keys = vec![state.clone()];
+        for (r, constant) in constants.iter().enumerate().skip(1) {
+            state = add(&mul(&mds, &sbox(&state, cipher_exponent(r - 1))), constant);
+            round_keys.push(state.clone());
+        }
+
+        Self { mds, round_keys }
+    }
+
+    /// Encrypt a single block.
+    pub fn permute(&self, block: &[Fq; BLOCK_SIZE]) -> [Fq; BLOCK_SIZE] {
+        let mut state = add(block, &self.round_keys[0]);
+        for (r, key) in self.round_keys.iter().enumerate().skip(1) {
+            state = add(&mul(&self.mds, &sbox(&state, cipher_exponent(r - 1))), key);
+        }
+        state.try_into().expect("block size")
+    }
+
+    /// CTR keystream of `len` elements for `nonce`. Counter block `i` is
+    /// `[nonce, i, 0, 0, 0]`.
+    pub fn keystream(&self, nonce: &[u8; 16], len: usize) -> Vector {
+        let nonce = Fq::from_le_bytes_mod_order(nonce);
+        (0..len.div_ceil(BLOCK_SIZE))
+            .flat_map(|i| {
+                let mut counter = [Fq::from(0u64); BLOCK_SIZE];
+                counter[0] = nonce;
+                counter[1] = Fq::from(i as u64);
+                self.permute(&counter)
+            })
+            .take(len)
+            .collect()
+    }
+
+    pub fn encrypt(&self, plaintext: &[Fq], nonce: &[u8; 16]) -> Vector {
+        add(plaintext, &self.keystream(nonce, plaintext.len()))
+    }
+
+    pub fn decrypt(&self, ciphertext: &[Fq], nonce: &[u8; 16]) -> Vector {
+        let stream = self.keystream(nonce, ciphertext.len());
+        ciphertext
+            .iter()
+            .zip(&stream)
+            .map(|(c, k)| *c - k)
+            .collect()
+    }
+}
+
+fn cipher_exponent(round: usize) -> &'static [u64] {
+    if round.is_multiple_of(2) {
+        &ALPHA_INV
+    } else {
+        &ALPHA
+    }
+}
+
+/// Rescue-Prime sponge used to derive cipher keys from shared secrets.
+pub struct RescuePrimeHash {
+    mds: Vec<Vector>,
+    constants: Vector,
+}
+
+impl Default for RescuePrimeHash {
+    fn default() -> Self {
+        let m = HASH_RATE + HASH_CAPACITY;
+        let seed = format!(
+            "Rescue-XLIX({},{},{},{})",
+            Fq::MODULUS,
+            m,
+            HASH_CAPACITY,
+            HASH_SECURITY_LEVEL
+        );
+        Self {
+            mds: cauchy_mds(m),
+            constants: shake_constants(seed.as_bytes(), 2 * m * HASH_ROUNDS),
+        }
+    }
+}
+
+impl RescuePrimeHash {
+    fn permute(&self, mut state: Vector) -> Vector {
+        let m = state.len();
+        for round in self.constants.chunks(2 * m) {
+            state = add(&mul(&self.mds, &sbox(&state, &ALPHA)), &round[..m]);
+            state = add(&mul(&self.mds, &sbox(&state, &ALPHA_INV)), &round[m..]);
+        }
+        state
+    }
+
+    /// Hash `message` to `BLOCK_SIZE` field elements.
+    pub fn digest(&self, message: &[Fq]) -> [Fq; BLOCK_SIZE] {
+        let mut padded = message.to_vec();
+        padded.push(Fq::from(1u64));
+        while !padded.len().is_multiple_of(HASH_RATE) {
+            padded.push(Fq::from(0u64));
+        }
+
+        let mut state = vec![Fq::from(0u64); HASH_RATE + HASH_CAPACITY];
+        for chunk in padded.chunks(HASH_RATE) {
+            for (s, x) in state.iter_mut().zip(chunk) {
+                *s += x;
+            }
+            state = self.permute(state);
+        }
+        state[..BLOCK_SIZE].try_into().expect("digest length")
+    }
+}
+
+/// Little-endian 32-byte encoding of a field element.
+pub fn to_bytes(x: &Fq) -> [u8; 32] {
+    x.into_bigint()
+        .to_bytes_le()
+        .try_into()
+        .expect("32-byte field element")
+}
+
+/// Parse a little-endian 32-byte field element, rejecting non-canonical values.
+pub fn from_bytes(bytes: &[u8; 32]) -> Option<Fq> {
+    let x = Fq::from_le_bytes_mod_order(bytes);
+    (to_bytes(&x) == *bytes).then_some(x)
+}
@@ -0,0 +1,169 @@
+//! Off-chain dry run of the MPC batch circuits.
+//!
+//! Reproduces the integer math of `add_to_batch`, `reveal_batch_total` and
+//! `compute_distribution` in `encrypted-ixs`, so revealed MPC outputs can be
+//! checked before they are written with `record_distribution`. Arcis
+//! integers wrap on overflow, hence the wrapping adds.
+//!
+//! `now` is the plaintext timestamp passed to `reveal_batch_total`; orders
+//! that expired before it count towards the order count but not the total,
+//! and are refunded in full.
+//!
+//! `simulate_dca_amounts` reproduces the amounts `add_dca_to_batch` draws
+//! for a jittered subscription, given the MXE's random draws, and
+//! `simulate_note_balance` the total `sum_my_notes` returns to a wallet.
+
+use anchor_lang::prelude::Pubkey;
+
+use crate::encryption::OrderData;
+use crate::mpc::{MAX_BATCH_ORDERS, MAX_DCA_JITTER_BPS};
+use crate::{Result, SdkError};
+
+/// Revealed `compute_distribution` output for one order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SimulatedDistribution {
+    pub order_index: u8,
+    pub shares: u64,
+    pub usdc_refund: u64,
+    pub wallet: Pubkey,
+}
+
+/// `reveal_batch_total` at `now` after one `add_to_batch` per order.
+/// Only the first `MAX_BATCH_ORDERS` orders find a slot in the stats.
+pub fn simulate_batch_total(orders: &[OrderData], now: u64) -> (u64, u8) {
+    let total = orders
+        .iter()
+        .take(MAX_BATCH_ORDERS as usize)
+        .filter(|order| !order.is_expired(now))
+        .fold(0u64, |total, order| total.wrapping_add(order.usdc_amount));
+    (total, orders.len() as u8)
+}
+
+/// `REVEAL_BUCKET` in `encrypted-ixs`: 1,000 USDC in atomic units.
+pub const REVEAL_BUCKET: u64 = 1_000_000_000;
+
+/// `reveal_bucketed_total` for an exact batch total.
+pub fn simulate_bucketed_total(batch_total: u64) -> u64 {
+    batch_total.wrapping_add(REVEAL_BUCKET - 1) / REVEAL_BUCKET * REVEAL_BUCKET
+}
+
+/// `compute_distribution` for a single order.
+pub fn simulate_shares(order_amount: u64, batch_total: u64, total_shares: u64) -> u64 {
+    if batch_total > 0 {
+        ((order_amount as u128) * (total_shares as u128) / (batch_total as u128)) as u64
+    } else {
+        0
+    }
+}
+
+/// `compute_distribution` refund: the part of the order not covered by
+/// its pro-rata share of `filled_usdc`.
+pub fn simulate_refund(order_amount: u64, batch_total: u64, filled_usdc: u64) -> u64 {
+    if batch_total > 0 {
+        order_amount.wrapping_sub(
+            ((order_amount as u128) * (filled_usdc as u128) / (batch_total as u128)) as u64,
+        )
+    } else {
+        0
+    }
+}
+
+/// Expected distributions for `orders`, in order-index order.
+/// `filled_usdc` is the batch total unless DFlow filled it partially.
+pub fn simulate_distribution(
+    orders: &[OrderData],
+    total_shares: u64,
+    filled_usdc: u64,
+    now: u64,
+) -> Vec<SimulatedDistribution> {
+    let (batch_total, _) = simulate_batch_total(orders, now);
+    orders
+        .iter()
+        .enumerate()
+        .map(|(index, order)| {
+            let (shares, usdc_refund) = if order.is_expired(now) {
+                (0, order.usdc_amount)
+            } else {
+                (
+                    simulate_shares(order.usdc_amount, batch_total, total_shares),
+                    simulate_refund(order.usdc_amount, batch_total, filled_usdc),
+                )
+            };
+            SimulatedDistribution {
+                order_index: index as u8,
+                shares,
+                usdc_refund,
+                wallet: order.wallet(),
+            }
+        })
+        .collect()
+}
+
+/// `dca_amount` in `encrypted-ixs`: the next epoch's amount of a schedule
+/// with `remaining_usdc` left over `remaining_epochs`, for the MXE's
+/// 64-bit `random` draw. Within `jitter_bps` of the even split, except
+/// the last epoch, which takes the remainder.
+pub fn simulate_dca_amount(
+    remaining_usdc: u64,
+    remaining_epochs: u64,
+    jitter_bps: u16,
+    random: u64,
+) -> u64 {
+    match remaining_epochs {
+        0 => 0,
+        1 => remaining_usdc,
+        epochs => {
+            let base = remaining_usdc / epochs;
+            let jitter_bps = jitter_bps.min(MAX_DCA_JITTER_BPS);
+            let span = ((base as u128) * (jitter_bps as u128) / 10_000) as u64;
+            base - span + random % (2 * span + 1)
+        }
+    }
+}
+
+/// Every amount `add_dca_to_batch` draws from `init_dca_schedule(total,
+/// epochs)`, one of `randoms` per epoch. They always add up to `total`.
+pub fn simulate_dca_amounts(
+    total: u64,
+    epochs: u64,
+    jitter_bps: u16,
+    randoms: impl IntoIterator<Item = u64>,
+) -> Vec<u64> {
+    let mut remaining = total;
+    randoms
+        .into_iter()
+        .take(epochs as usize)
+        .enumerate()
+        .map(|(epoch, random)| {
+            let amount = simulate_dca_amount(remaining, epochs - epoch as u64, jitter_bps, random);
+            remaining -= amount;
+            amount
+        })
+        .collect()
+}
+
+/// `sum_my_notes` over `notes`, as (leaf index, amount) pairs, against
+/// the pool's `next_index`. Wallets with more than `SUMMARY_NOTES` notes
+/// add up the totals of several calls, which this sums in one.
+pub fn simulate_note_balance(notes: &[(u32, u64)], next_index: u32) -> u64 {
+    notes
+        .iter()
+        .filter(|(leaf_index, _)| *leaf_index < next_index)
+        .fold(0u64, |total, (_, amount)| total.wrapping_add(*amount))
+}
+
+/// Compare a revealed `compute_distribution` output with the simulation.
+pub fn check_revealed(
+    expected: &[SimulatedDistribution],
+    order_index: u8,
+    shares: u64,
+    usdc_refund: u64,
+    wallet: &Pubkey,
+) -> Result<()> {
+    match expected.get(order_index as usize) {
+        Some(e) if e.shares == shares && e.usdc_refund == usdc_refund && e.wallet == *wallet => {
+            Ok(())
+        }
+        _ => Err(SdkError::DistributionMismatch { order_index }),
+    }
+}
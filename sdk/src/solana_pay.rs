@@ -0,0 +1,222 @@
+//! Solana Pay transaction requests
+//!
+//! A mobile wallet scans a `solana:<https link>` QR code, GETs the link for
+//! a label and icon, then POSTs its account and receives an unsigned
+//! transaction to sign. These helpers build the POST responses for pool
+//! deposits and encrypted order submission, so the relay only serves them.
+//!
+//! A recent blockhash keeps a transaction valid for about a minute, too short
+//! when a mobile wallet signs an order and relaying it waits for the batch
+//! window. Built on a [`TransactionLifetime::DurableNonce`] instead, the
+//! transaction first advances the user's nonce account and stays valid
+//! until that nonce is used.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use solana_hash::Hash;
+use solana_nonce::state::State as NonceState;
+use solana_nonce::versions::Versions as NonceVersions;
+use solana_system_interface::instruction::advance_nonce_account;
+use solana_transaction::Transaction;
+
+use crate::pool::deposit_instruction;
+use crate::{Result, SdkError};
+
+/// Solana Memo program
+pub const MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+/// Memo prefix the relay's deposit monitor uses to recognise encrypted orders
+pub const ENCRYPTED_ORDER_MEMO_PREFIX: &str = "OBSIDIAN_ENC";
+
+/// Maximum serialized transaction size (one network packet)
+pub const PACKET_DATA_SIZE: usize = 1232;
+
+/// USDC decimals
+const USDC_DECIMALS: u8 = 6;
+
+/// GET response: what the wallet shows before the user connects
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionRequestMetadata {
+    pub label: String,
+    pub icon: String,
+}
+
+/// POST body sent by the wallet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionRequestBody {
+    pub account: String,
+}
+
+/// POST response: base64 unsigned transaction plus an optional message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionRequestResponse {
+    pub transaction: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Order encrypted client-side for the MXE (x25519 + Rescue)
+#[derive(Debug, Clone)]
+pub struct EncryptedOrder {
+    pub market_id: String,
+    /// 0 = NO, 1 = YES
+    pub side: u8,
+    pub ciphertext: Vec<u8>,
+    pub public_key: [u8; 32],
+    pub nonce: [u8; 16],
+}
+
+impl EncryptedOrder {
+    /// Memo format: OBSIDIAN_ENC|marketId|side|ciphertext|publicKey|nonce (base64 fields)
+    pub fn to_memo(&self) -> String {
+        [
+            ENCRYPTED_ORDER_MEMO_PREFIX.to_string(),
+            self.market_id.clone(),
+            self.side.to_string(),
+            BASE64.encode(&self.ciphertext),
+            BASE64.encode(self.public_key),
+            BASE64.encode(self.nonce),
+        ]
+        .join("|")
+    }
+}
+
+/// What keeps a built transaction valid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionLifetime {
+    /// Valid for about 150 slots after the blockhash
+    Blockhash(Hash),
+    /// Valid until the nonce stored in `account` is advanced; `authority`
+    /// signs the transaction too
+    DurableNonce {
+        account: Pubkey,
+        authority: Pubkey,
+        nonce: Hash,
+    },
+}
+
+impl TransactionLifetime {
+    /// Lifetime from the data of the nonce account at `account`.
+    pub fn from_nonce_account(account: Pubkey, data: &[u8]) -> Result<Self> {
+        let versions: NonceVersions =
+            bincode::deserialize(data).map_err(|_| SdkError::InvalidAccountData)?;
+        match versions.state() {
+            NonceState::Initialized(state) => Ok(Self::DurableNonce {
+                account,
+                authority: state.authority,
+                nonce: state.blockhash(),
+            }),
+            NonceState::Uninitialized => Err(SdkError::InvalidAccountData),
+        }
+    }
+}
+
+impl From<Hash> for TransactionLifetime {
+    fn from(recent_blockhash: Hash) -> Self {
+        Self::Blockhash(recent_blockhash)
+    }
+}
+
+/// Wrap an HTTPS transaction-request endpoint in a `solana:` link for a QR code.
+pub fn transaction_request_link(endpoint: &str) -> String {
+    format!("solana:{}", urlencoding::encode(endpoint))
+}
+
+/// Build the USDC transfer plus memo that submits an encrypted order to the relay.
+pub fn order_instructions(
+    user: &Pubkey,
+    relay_wallet: &Pubkey,
+    order: &EncryptedOrder,
+    amount: u64,
+) -> Vec<Instruction> {
+    let transfer = spl_token::instruction::transfer_checked(
+        &spl_token::ID,
+        &get_associated_token_address(user, &privacy_pool::USDC_MINT),
+        &privacy_pool::USDC_MINT,
+        &get_associated_token_address(relay_wallet, &privacy_pool::USDC_MINT),
+        user,
+        &[],
+        amount,
+        USDC_DECIMALS,
+    )
+    .expect("static transfer_checked arguments");
+
+    let memo = Instruction {
+        program_id: MEMO_PROGRAM_ID,
+        accounts: vec![AccountMeta::new_readonly(*user, true)],
+        data: order.to_memo().into_bytes(),
+    };
+
+    vec![transfer, memo]
+}
+
+/// Transaction-request response for a privacy pool deposit.
+pub fn deposit_transaction_request(
+    account: &Pubkey,
+    commitment: [u8; 32],
+    amount: u64,
+    lifetime: impl Into<TransactionLifetime>,
+    message: Option<String>,
+) -> Result<TransactionRequestResponse> {
+    let ix = deposit_instruction(account, commitment, amount);
+    build_response(account, &[ix], lifetime.into(), message)
+}
+
+/// Transaction-request response for an encrypted order submission.
+pub fn order_transaction_request(
+    account: &Pubkey,
+    relay_wallet: &Pubkey,
+    order: &EncryptedOrder,
+    amount: u64,
+    lifetime: impl Into<TransactionLifetime>,
+    message: Option<String>,
+) -> Result<TransactionRequestResponse> {
+    let ixs = order_instructions(account, relay_wallet, order, amount);
+    build_response(account, &ixs, lifetime.into(), message)
+}
+
+/// Serialize an unsigned transaction with `account` as fee payer.
+fn build_response(
+    account: &Pubkey,
+    instructions: &[Instruction],
+    lifetime: TransactionLifetime,
+    message: Option<String>,
+) -> Result<TransactionRequestResponse> {
+    let tx = match lifetime {
+        TransactionLifetime::Blockhash(recent_blockhash) => {
+            let mut tx = Transaction::new_with_payer(instructions, Some(account));
+            tx.message.recent_blockhash = recent_blockhash;
+            tx
+        }
+        TransactionLifetime::DurableNonce {
+            account: nonce_account,
+            authority,
+            nonce,
+        } => {
+            // The runtime only honours a nonce advanced by the first instruction
+            let mut nonced = vec![advance_nonce_account(&nonce_account, &authority)];
+            nonced.extend_from_slice(instructions);
+            let mut tx = Transaction::new_with_payer(&nonced, Some(account));
+            tx.message.recent_blockhash = nonce;
+            tx
+        }
+    };
+
+    let bytes = bincode::serialize(&tx)?;
+    if bytes.len() > PACKET_DATA_SIZE {
+        return Err(SdkError::TransactionTooLarge {
+            size: bytes.len(),
+            max: PACKET_DATA_SIZE,
+        });
+    }
+
+    Ok(TransactionRequestResponse {
+        transaction: BASE64.encode(bytes),
+        message,
+    })
+}
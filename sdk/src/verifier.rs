@@ -0,0 +1,156 @@
+//! Instruction builders and PDAs for the ZK verifier program.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+
+/// Verifying key PDA for one circuit version
+pub fn verifying_key_address(circuit: &str, version: u16) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"vk",
+            zk_verifier::circuit_id(circuit).as_ref(),
+            &version.to_le_bytes(),
+        ],
+        &zk_verifier::ID,
+    )
+    .0
+}
+
+/// Active key pointer for a circuit, moved by `rotate_verifying_key`
+pub fn active_key_address(circuit: &str) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"active_vk", zk_verifier::circuit_id(circuit).as_ref()],
+        &zk_verifier::ID,
+    )
+    .0
+}
+
+/// Key bytes per `upload_verifying_key` transaction
+pub const VK_CHUNK_SIZE: usize = 800;
+
+fn event_authority_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"__event_authority"], &zk_verifier::ID).0
+}
+
+fn instruction(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: zk_verifier::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+pub fn initialize_verifying_key_instruction(
+    authority: &Pubkey,
+    circuit: &str,
+    version: u16,
+    proving_system: u8,
+    data_len: u32,
+) -> Instruction {
+    instruction(
+        zk_verifier::accounts::InitializeVerifyingKey {
+            verifying_key: verifying_key_address(circuit, version),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        zk_verifier::instruction::InitializeVerifyingKey {
+            circuit_id: zk_verifier::circuit_id(circuit),
+            version,
+            proving_system,
+            data_len,
+        },
+    )
+}
+
+pub fn upload_verifying_key_instruction(
+    authority: &Pubkey,
+    circuit: &str,
+    version: u16,
+    offset: u32,
+    chunk: Vec<u8>,
+) -> Instruction {
+    instruction(
+        zk_verifier::accounts::UploadVerifyingKey {
+            verifying_key: verifying_key_address(circuit, version),
+            authority: *authority,
+        },
+        zk_verifier::instruction::UploadVerifyingKey { offset, chunk },
+    )
+}
+
+/// One `upload_verifying_key` per `VK_CHUNK_SIZE` bytes of `data`.
+pub fn upload_verifying_key_instructions(
+    authority: &Pubkey,
+    circuit: &str,
+    version: u16,
+    data: &[u8],
+) -> Vec<Instruction> {
+    data.chunks(VK_CHUNK_SIZE)
+        .enumerate()
+        .map(|(i, chunk)| {
+            upload_verifying_key_instruction(
+                authority,
+                circuit,
+                version,
+                (i * VK_CHUNK_SIZE) as u32,
+                chunk.to_vec(),
+            )
+        })
+        .collect()
+}
+
+pub fn finalize_verifying_key_instruction(
+    authority: &Pubkey,
+    circuit: &str,
+    version: u16,
+) -> Instruction {
+    instruction(
+        zk_verifier::accounts::FinalizeVerifyingKey {
+            verifying_key: verifying_key_address(circuit, version),
+            authority: *authority,
+            event_authority: event_authority_address(),
+            program: zk_verifier::ID,
+        },
+        zk_verifier::instruction::FinalizeVerifyingKey {},
+    )
+}
+
+pub fn rotate_verifying_key_instruction(
+    authority: &Pubkey,
+    circuit: &str,
+    version: u16,
+) -> Instruction {
+    instruction(
+        zk_verifier::accounts::RotateVerifyingKey {
+            verifying_key: verifying_key_address(circuit, version),
+            active_key: active_key_address(circuit),
+            authority: *authority,
+            system_program: system_program::ID,
+            event_authority: event_authority_address(),
+            program: zk_verifier::ID,
+        },
+        zk_verifier::instruction::RotateVerifyingKey {},
+    )
+}
+
+/// Standalone proof check against `circuit`'s active key `version`,
+/// e.g. for simulating before submission
+pub fn verify_instruction(
+    circuit: &str,
+    version: u16,
+    public_inputs: Vec<[u8; 32]>,
+    proof: Vec<u8>,
+) -> Instruction {
+    instruction(
+        zk_verifier::accounts::Verify {
+            verifying_key: verifying_key_address(circuit, version),
+            active_key: active_key_address(circuit),
+        },
+        zk_verifier::instruction::Verify {
+            public_inputs,
+            proof,
+        },
+    )
+}
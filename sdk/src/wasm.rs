@@ -0,0 +1,114 @@
+//! wasm-bindgen exports for the web frontend.
+//!
+//! Build with `npm run build:wasm` in `frontend/`.
+
+use anchor_lang::prelude::Pubkey;
+use wasm_bindgen::prelude::*;
+
+use crate::encryption;
+use crate::note::{self, Note};
+
+fn array<const N: usize>(bytes: &[u8], what: &str) -> Result<[u8; N], JsError> {
+    bytes
+        .try_into()
+        .map_err(|_| JsError::new(&format!("{what} must be {N} bytes")))
+}
+
+#[wasm_bindgen(js_name = Note)]
+pub struct WasmNote(Note);
+
+#[wasm_bindgen(js_class = Note)]
+impl WasmNote {
+    #[wasm_bindgen(getter)]
+    pub fn secret(&self) -> Vec<u8> {
+        self.0.secret.to_vec()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn amount(&self) -> u64 {
+        self.0.amount
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn version(&self) -> u8 {
+        self.0.version
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn commitment(&self) -> Result<Vec<u8>, JsError> {
+        Ok(self.0.commitment()?.to_vec())
+    }
+}
+
+#[wasm_bindgen(js_name = createNote)]
+pub fn create_note(amount: u64) -> Result<WasmNote, JsError> {
+    Ok(WasmNote(Note::random(amount)?))
+}
+
+#[wasm_bindgen(js_name = deriveNote)]
+pub fn derive_note(seed: &[u8], index: u32, amount: u64) -> WasmNote {
+    WasmNote(Note::derive(seed, index, amount))
+}
+
+#[wasm_bindgen(js_name = deriveNullifier)]
+pub fn derive_nullifier(
+    secret: &[u8],
+    leaf_index: u32,
+    pool_id: &[u8],
+    purpose: u8,
+) -> Result<Vec<u8>, JsError> {
+    let nullifier = note::derive_nullifier(
+        &array(secret, "secret")?,
+        leaf_index,
+        &array(pool_id, "pool id")?,
+        purpose,
+    )?;
+    Ok(nullifier.to_vec())
+}
+
+#[wasm_bindgen(js_name = EncryptedOrder)]
+pub struct WasmEncryptedOrder(crate::solana_pay::EncryptedOrder);
+
+#[wasm_bindgen(js_class = EncryptedOrder)]
+impl WasmEncryptedOrder {
+    #[wasm_bindgen(getter)]
+    pub fn ciphertext(&self) -> Vec<u8> {
+        self.0.ciphertext.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = publicKey)]
+    pub fn public_key(&self) -> Vec<u8> {
+        self.0.public_key.to_vec()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn nonce(&self) -> Vec<u8> {
+        self.0.nonce.to_vec()
+    }
+
+    /// Memo string for the deposit-with-order transaction.
+    #[wasm_bindgen(js_name = toMemo)]
+    pub fn to_memo(&self) -> String {
+        self.0.to_memo()
+    }
+}
+
+#[wasm_bindgen(js_name = encryptOrder)]
+pub fn encrypt_order(
+    mxe_public_key: &[u8],
+    market_id: &str,
+    side: u8,
+    usdc_amount: u64,
+    wallet: &[u8],
+    expires_at: Option<u64>,
+) -> Result<WasmEncryptedOrder, JsError> {
+    let order = encryption::encrypt_order(
+        &array(mxe_public_key, "MXE public key")?,
+        market_id,
+        side,
+        usdc_amount,
+        &Pubkey::new_from_array(array(wallet, "wallet")?),
+        expires_at,
+    )?;
+    Ok(WasmEncryptedOrder(order))
+}
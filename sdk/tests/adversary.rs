@@ -0,0 +1,212 @@
+//! Simulated adversaries against realistic order flow.
+//!
+//! Orders go through the client side of the pipeline (encryption, the MPC
+//! batch math, the bucketed reveal) and an observer tries to recover them
+//! from what lands on chain. A change that weakens any of these properties
+//! should fail here rather than in review.
+
+use anchor_lang::prelude::Pubkey;
+use obsidian_sdk::encryption::{self, MxeSession, OrderData};
+use obsidian_sdk::leakage::{estimate, BatchObservation, ObserverModel};
+use obsidian_sdk::note::Note;
+use obsidian_sdk::pool;
+use obsidian_sdk::simulation::{simulate_batch_total, simulate_bucketed_total, REVEAL_BUCKET};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+const USDC: u64 = 1_000_000;
+
+/// Smallest batch the relay closes with a bucketed reveal
+const MIN_ORDERS: u8 = 8;
+
+/// No order may be pinned down closer than this
+const PRECISION: u64 = 100 * USDC;
+
+/// Public order size limits
+const MODEL: ObserverModel = ObserverModel {
+    min_order: 10 * USDC,
+    max_order: 5_000 * USDC,
+    resolution: USDC,
+};
+
+/// xorshift64, so every run sees the same flow
+struct Flow(u64);
+
+impl Flow {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Order sizes spread evenly over orders of magnitude, whole USDC
+    fn order(&mut self) -> OrderData {
+        let (low, high) = (
+            (MODEL.min_order / USDC) as f64,
+            (MODEL.max_order / USDC) as f64,
+        );
+        let fraction = (self.next() % 10_000) as f64 / 10_000.0;
+        let amount = (low * (high / low).powf(fraction)) as u64 * USDC;
+        OrderData::new(amount, &Pubkey::new_unique())
+    }
+
+    fn batch(&mut self, count: u8) -> Vec<OrderData> {
+        (0..count).map(|_| self.order()).collect()
+    }
+}
+
+fn mxe_keypair() -> (StaticSecret, [u8; 32]) {
+    let secret = StaticSecret::from([7u8; 32]);
+    let public = PublicKey::from(&secret).to_bytes();
+    (secret, public)
+}
+
+/// Encrypt every order as its user would, decrypt as the MXE does and
+/// reveal the bucketed total.
+fn run_batch(orders: &[OrderData]) -> BatchObservation {
+    let (mxe_secret, mxe_public) = mxe_keypair();
+    let received: Vec<OrderData> = orders
+        .iter()
+        .map(|order| {
+            let ciphertext = MxeSession::new(&mxe_public).unwrap().order_data_args(order);
+            let fields = encryption::decrypt(&mxe_secret, &ciphertext).unwrap();
+            OrderData::from_fields(&fields).unwrap()
+        })
+        .collect();
+    assert_eq!(received, orders);
+
+    let (total, count) = simulate_batch_total(&received, 0);
+    BatchObservation::bucketed(simulate_bucketed_total(total), count)
+}
+
+#[test]
+fn outside_observer_cannot_recover_orders_from_bucketed_totals() {
+    let mut flow = Flow(0x5eed);
+    let mut close_guesses = 0;
+    let mut orders_seen = 0;
+
+    for count in [MIN_ORDERS, 12, 16] {
+        for _ in 0..4 {
+            let orders = flow.batch(count);
+            let e = estimate(&run_batch(&orders), &MODEL).unwrap();
+
+            for order in &orders {
+                let amount = order.usdc_amount;
+                assert!(
+                    (e.min_amount..=e.max_amount).contains(&amount),
+                    "bounds must hold for every order"
+                );
+                assert!(e.max_amount - e.min_amount >= PRECISION, "{e:?}");
+                close_guesses += (e.best_guess.abs_diff(amount) < PRECISION) as usize;
+                orders_seen += 1;
+            }
+        }
+    }
+    // The even-split guess lands within PRECISION for only a small share
+    // of orders
+    assert!(
+        close_guesses * 4 < orders_seen,
+        "{close_guesses} of {orders_seen} guessed"
+    );
+}
+
+#[test]
+fn colluding_users_learn_the_last_order_only_to_a_bucket() {
+    let mut flow = Flow(0xc0ffee);
+    for _ in 0..16 {
+        let orders = flow.batch(MIN_ORDERS);
+        let (target, colluders) = orders.split_last().unwrap();
+        let known: u64 = colluders.iter().map(|order| order.usdc_amount).sum();
+
+        let bucketed = run_batch(&orders).with_known(MIN_ORDERS - 1, known);
+        let e = estimate(&bucketed, &MODEL).unwrap();
+        assert!((e.min_amount..=e.max_amount).contains(&target.usdc_amount));
+        // Only the public size limits can narrow it further
+        let near_limit = target.usdc_amount < MODEL.min_order + PRECISION
+            || target.usdc_amount > MODEL.max_order - PRECISION;
+        assert!(
+            e.max_amount - e.min_amount >= PRECISION || near_limit,
+            "{e:?}"
+        );
+        assert!(!e.is_exposed());
+
+        // The same batch revealed exactly gives the order away
+        let (total, count) = simulate_batch_total(&orders, 0);
+        let exact = BatchObservation::exact(total, count).with_known(MIN_ORDERS - 1, known);
+        let e = estimate(&exact, &MODEL).unwrap();
+        assert!(e.is_exposed());
+        assert_eq!(e.best_guess, target.usdc_amount);
+    }
+}
+
+#[test]
+fn order_ciphertexts_do_not_link_or_size_orders() {
+    let (_, mxe_public) = mxe_keypair();
+    let order = OrderData::new(250 * USDC, &Pubkey::new_unique());
+
+    // Two submissions of the same order share no key or ciphertext
+    let first = MxeSession::new(&mxe_public)
+        .unwrap()
+        .order_data_args(&order);
+    let second = MxeSession::new(&mxe_public)
+        .unwrap()
+        .order_data_args(&order);
+    assert_ne!(first.public_key, second.public_key);
+    assert!(first
+        .ciphertexts
+        .iter()
+        .all(|block| !second.ciphertexts.contains(block)));
+
+    // A resubmission within one session moves to a fresh nonce
+    let mut session = MxeSession::new(&mxe_public).unwrap();
+    let again = [
+        session.order_data_args(&order),
+        session.order_data_args(&order),
+    ];
+    assert_ne!(again[0].nonce, again[1].nonce);
+    assert_ne!(again[0].ciphertexts, again[1].ciphertexts);
+
+    // Ciphertext length says nothing about the amount
+    let small = OrderData::new(MODEL.min_order, &order.wallet());
+    let large = OrderData::new(u64::MAX, &order.wallet());
+    assert_eq!(
+        session.order_data_args(&small).ciphertext_bytes().len(),
+        session.order_data_args(&large).ciphertext_bytes().len()
+    );
+}
+
+#[test]
+fn pool_notes_do_not_link_deposits_to_spends() {
+    let pool_id = privacy_pool::nullifier_pool_id(&pool::pool_address());
+    let notes: Vec<Note> = (0..8)
+        .map(|_| Note::random(REVEAL_BUCKET).unwrap())
+        .collect();
+
+    let commitments: Vec<[u8; 32]> = notes.iter().map(|n| n.commitment().unwrap()).collect();
+    let nullifiers: Vec<[u8; 32]> = notes
+        .iter()
+        .enumerate()
+        .map(|(index, note)| {
+            note.nullifier(
+                index as u32,
+                &pool_id,
+                privacy_pool::NULLIFIER_PURPOSE_SPEND,
+            )
+            .unwrap()
+        })
+        .collect();
+
+    // Equal amounts still give distinct commitments, and no nullifier
+    // repeats a commitment or another note's nullifier
+    for (index, commitment) in commitments.iter().enumerate() {
+        assert!(!commitments[..index].contains(commitment));
+        assert!(!nullifiers.contains(commitment));
+        assert!(!nullifiers[..index].contains(&nullifiers[index]));
+    }
+
+    // Spending for a batch publishes a nullifier unrelated to the spend one
+    let batch = notes[0]
+        .nullifier(0, &pool_id, privacy_pool::NULLIFIER_PURPOSE_BATCH)
+        .unwrap();
+    assert_ne!(batch, nullifiers[0]);
+}
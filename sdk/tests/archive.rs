@@ -0,0 +1,133 @@
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AnchorSerialize, Discriminator};
+use ed25519_dalek::{Keypair, SecretKey};
+use obsidian_sdk::archive::{self, PoolArchive, SignedArchive};
+use obsidian_sdk::pool;
+use obsidian_sdk::replay::{AccountSnapshot, LoggedTransaction};
+use obsidian_sdk::SdkError;
+use privacy_pool::{DepositEvent, NullifierSet, PrivacyPool, MAX_LEAVES};
+
+fn keypair() -> Keypair {
+    let secret = SecretKey::from_bytes(&[9u8; 32]).unwrap();
+    Keypair {
+        public: (&secret).into(),
+        secret,
+    }
+}
+
+fn deposit(slot: u64, leaf_index: u32, commitment: [u8; 32]) -> LoggedTransaction {
+    let mut data = EVENT_IX_TAG_LE.to_vec();
+    data.extend_from_slice(DepositEvent::DISCRIMINATOR);
+    DepositEvent {
+        leaf_index,
+        commitment,
+        timestamp: 0,
+        version: privacy_pool::COMMITMENT_VERSION,
+    }
+    .serialize(&mut data)
+    .unwrap();
+    LoggedTransaction {
+        signature: format!("sig{slot}"),
+        slot,
+        failed: false,
+        instructions: vec![(privacy_pool::ID, data)],
+    }
+}
+
+fn snapshot(leaves: &[[u8; 32]]) -> AccountSnapshot {
+    let mut pool_leaves = [[0u8; 32]; MAX_LEAVES];
+    pool_leaves[..leaves.len()].copy_from_slice(leaves);
+    let pool_account = PrivacyPool {
+        authority: Pubkey::new_unique(),
+        merkle_root: archive::merkle_root(leaves).unwrap(),
+        next_index: leaves.len() as u32,
+        nullifier_count: 0,
+        leaves: pool_leaves,
+    };
+    let data = [
+        PrivacyPool::DISCRIMINATOR,
+        bytemuck::bytes_of(&pool_account),
+    ]
+    .concat();
+
+    let spend_set = NullifierSet::new(pool::pool_address(), privacy_pool::NULLIFIER_PURPOSE_SPEND);
+    let mut set_data = [NullifierSet::DISCRIMINATOR, bytemuck::bytes_of(&spend_set)].concat();
+    set_data.resize(8 + NullifierSet::SIZE, 0);
+
+    let mut snapshot = AccountSnapshot::default();
+    snapshot.insert(pool::pool_address(), privacy_pool::ID, data);
+    snapshot.insert(
+        pool::nullifier_set_address(privacy_pool::NULLIFIER_PURPOSE_SPEND),
+        privacy_pool::ID,
+        set_data,
+    );
+    snapshot
+}
+
+#[test]
+fn exported_archive_verifies_and_roundtrips() {
+    let leaves = [[1u8; 32], [2u8; 32]];
+    let history = [deposit(5, 0, leaves[0]), deposit(6, 1, leaves[1])];
+    let snapshot = snapshot(&leaves);
+
+    let archive = PoolArchive::export(&history, &snapshot).unwrap();
+    assert_eq!(archive.leaves, leaves);
+    assert_eq!(archive.slot, 6);
+    let digest = archive.digest();
+
+    let signed = SignedArchive::from_bytes(&archive.sign(&keypair()).to_bytes()).unwrap();
+    assert_eq!(signed.verify().unwrap(), digest);
+    assert_eq!(signed.verify_against(&snapshot).unwrap(), digest);
+
+    // A pool that grew since export still extends the archive
+    let grown = self::snapshot(&[leaves[0], leaves[1], [3u8; 32]]);
+    assert!(signed.verify_against(&grown).is_ok());
+    let rewritten = self::snapshot(&[[7u8; 32], leaves[1], [3u8; 32]]);
+    assert!(matches!(
+        signed.verify_against(&rewritten),
+        Err(SdkError::InvalidArchive(_))
+    ));
+}
+
+#[test]
+fn tampered_archives_fail() {
+    let leaves = [[1u8; 32], [2u8; 32]];
+    let history = [deposit(5, 0, leaves[0]), deposit(6, 1, leaves[1])];
+    let archive = PoolArchive::export(&history, &snapshot(&leaves)).unwrap();
+
+    // Content changed after signing
+    let mut signed = archive.clone().sign(&keypair());
+    signed.archive.leaves[1] = [3u8; 32];
+    assert!(matches!(signed.verify(), Err(SdkError::InvalidArchive(_))));
+
+    // Re-signed, but the leaves no longer follow from the events
+    let mut forged = archive.clone();
+    forged.leaves[1] = [3u8; 32];
+    assert!(forged.sign(&keypair()).verify().is_err());
+
+    // Events and leaves agree, but the root does not
+    let mut forged = archive;
+    forged.merkle_root = [0u8; 32];
+    assert!(forged.sign(&keypair()).verify().is_err());
+}
+
+#[test]
+fn deposits_logged_before_leaf_versions_replay() {
+    // DepositEvent as logged before it carried the commitment version
+    let mut data = EVENT_IX_TAG_LE.to_vec();
+    data.extend_from_slice(DepositEvent::DISCRIMINATOR);
+    (4u32, [5u8; 32], 0i64).serialize(&mut data).unwrap();
+    let legacy = LoggedTransaction {
+        signature: "legacy".into(),
+        slot: 1,
+        failed: false,
+        instructions: vec![(privacy_pool::ID, data)],
+    };
+
+    let mut replay = obsidian_sdk::replay::Replay::default();
+    replay.apply(&legacy);
+    replay.apply(&deposit(2, 5, [6; 32]));
+    assert_eq!(replay.leaves.get(&4), Some(&[5; 32]));
+    assert_eq!(replay.leaves.get(&5), Some(&[6; 32]));
+}
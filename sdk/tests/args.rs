@@ -0,0 +1,112 @@
+use anchor_lang::prelude::Pubkey;
+use obsidian_sdk::args::{ArgBuilder, Argument, DEFAULT_CHUNK_CIPHERTEXTS, MAX_QUEUE_ARGS_BYTES};
+use obsidian_sdk::encryption::{self, MxeSession, OrderData};
+use obsidian_sdk::rescue::Fq;
+use obsidian_sdk::SdkError;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+fn mxe_keypair() -> (StaticSecret, [u8; 32]) {
+    let secret = StaticSecret::from([7u8; 32]);
+    let public = PublicKey::from(&secret).to_bytes();
+    (secret, public)
+}
+
+fn session(mxe_public: &[u8; 32]) -> MxeSession {
+    MxeSession::from_parts(StaticSecret::from([42u8; 32]), mxe_public, 1000)
+}
+
+#[test]
+fn chunks_decrypt_back_to_the_struct() {
+    let (mxe_secret, mxe_public) = mxe_keypair();
+    let fields: Vec<Fq> = (1..=10u64).map(Fq::from).collect();
+
+    let chunks = session(&mxe_public).encrypt_chunked(&fields, DEFAULT_CHUNK_CIPHERTEXTS);
+    assert_eq!(
+        chunks
+            .iter()
+            .map(|c| c.ciphertexts.len())
+            .collect::<Vec<_>>(),
+        [4, 4, 2]
+    );
+    assert_eq!(
+        chunks.iter().map(|c| c.nonce).collect::<Vec<_>>(),
+        [1000, 1001, 1002]
+    );
+
+    let decrypted: Vec<Fq> = chunks
+        .iter()
+        .flat_map(|chunk| encryption::decrypt(&mxe_secret, chunk).unwrap())
+        .collect();
+    assert_eq!(decrypted, fields);
+}
+
+#[test]
+fn shared_arguments_follow_arg_builder_order() {
+    let (_, mxe_public) = mxe_keypair();
+    let ciphertext = session(&mxe_public).encrypt(&[Fq::from(5u64), Fq::from(6u64)]);
+
+    let packed = ArgBuilder::new()
+        .shared(&ciphertext)
+        .plaintext_u64(1_700_000_000)
+        .build()
+        .unwrap();
+    assert_eq!(
+        packed.args(),
+        [
+            Argument::X25519Pubkey(ciphertext.public_key),
+            Argument::PlaintextU128(1000),
+            Argument::Ciphertext(ciphertext.ciphertexts[0]),
+            Argument::Ciphertext(ciphertext.ciphertexts[1]),
+            Argument::PlaintextU64(1_700_000_000),
+        ]
+    );
+}
+
+#[test]
+fn retries_reuse_the_computation_offset() {
+    let (_, mxe_public) = mxe_keypair();
+    let order = OrderData::new(12_500_000, &Pubkey::new_unique());
+    let build = |session: &mut MxeSession| {
+        ArgBuilder::new()
+            .shared_all(&session.compute_distribution_args(&order))
+            .plaintext_u64(100)
+            .build()
+            .unwrap()
+    };
+
+    // Rebuilding from the same session state is a retry of the same queue
+    let first = build(&mut session(&mxe_public));
+    assert_eq!(build(&mut session(&mxe_public)), first);
+    assert_eq!(
+        build(&mut session(&mxe_public)).computation_offset(),
+        first.computation_offset()
+    );
+
+    // Re-encrypting draws new nonces and a new computation
+    let mut reused = session(&mxe_public);
+    build(&mut reused);
+    assert_ne!(
+        build(&mut reused).computation_offset(),
+        first.computation_offset()
+    );
+}
+
+#[test]
+fn oversized_arguments_are_rejected() {
+    let (_, mxe_public) = mxe_keypair();
+    let fields = vec![Fq::from(1u64); 16];
+
+    let whole = session(&mxe_public).encrypt(&fields);
+    match ArgBuilder::new().shared(&whole).build() {
+        Err(SdkError::ArgumentsTooLarge { size, max }) => {
+            assert!(size > max);
+            assert_eq!(max, MAX_QUEUE_ARGS_BYTES);
+        }
+        other => panic!("expected ArgumentsTooLarge, got {other:?}"),
+    }
+
+    // An order-sized struct fits in chunks
+    let order = OrderData::new(1, &Pubkey::new_unique()).to_fields();
+    let chunks = session(&mxe_public).encrypt_chunked(&order, DEFAULT_CHUNK_CIPHERTEXTS);
+    assert!(ArgBuilder::new().shared_all(&chunks).build().is_ok());
+}
@@ -0,0 +1,437 @@
+//! Compute-unit benchmarks for the instructions closest to their budget.
+//!
+//! Each test runs one instruction against the SBF builds in target/deploy
+//! and fails if it uses more than its budget in compute_units.json. They
+//! need `anchor build` and `arcium build` first, so they are ignored here;
+//! `cargo xtask bench` builds the programs, runs them and, with --update,
+//! rewrites the budgets from the measured usage.
+
+use std::path::PathBuf;
+
+use anchor_lang::prelude::{AnchorSerialize, Pubkey, Rent};
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::{AccountSerialize, Discriminator};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token;
+use obsidian_sdk::mpc::{self, Batch, BatchStatus, DistributionTree, MpcAccount, OrderSlot};
+use obsidian_sdk::pool;
+use privacy_pool::{
+    compute_merkle_root, LeafTimes, NullifierSet, NullifierSlots, PoolLimits, PoolStats,
+    PrivacyPool, VerifierConfig, COMMITMENT_VERSION, MAX_LEAVES, NULLIFIER_PURPOSE_SPEND,
+    USDC_MINT,
+};
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::account::Account;
+use solana_sdk::account_info::AccountInfo;
+use solana_sdk::bpf_loader;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::entrypoint::ProgramResult;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+
+/// Limit every benchmarked transaction runs under, so usage past the
+/// default 200k is still measured rather than cut off
+const BENCH_UNIT_LIMIT: u32 = 1_400_000;
+
+fn deploy_path(relative: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join(relative)
+}
+
+fn budget(name: &str) -> u64 {
+    let budgets: serde_json::Value =
+        serde_json::from_str(include_str!("compute_units.json")).expect("compute_units.json");
+    budgets[name]
+        .as_u64()
+        .unwrap_or_else(|| panic!("no budget for {name} in compute_units.json"))
+}
+
+fn account(owner: Pubkey, data: Vec<u8>) -> Account {
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn anchor_account<T: AccountSerialize>(owner: Pubkey, value: &T, space: usize) -> Account {
+    let mut data = Vec::with_capacity(space);
+    value.try_serialize(&mut data).expect("serialize");
+    data.resize(space, 0);
+    account(owner, data)
+}
+
+fn mpc_account<T: MpcAccount + AnchorSerialize>(value: &T, space: usize) -> Account {
+    let mut data = T::DISCRIMINATOR.to_vec();
+    value.serialize(&mut data).expect("serialize");
+    data.resize(space, 0);
+    account(mpc::ID, data)
+}
+
+/// Load a program from its SBF build
+fn add_program(test: &mut ProgramTest, program_id: Pubkey, path: PathBuf) {
+    let data = std::fs::read(&path).unwrap_or_else(|e| {
+        panic!(
+            "{}: {e}; build the programs first (cargo xtask bench)",
+            path.display()
+        )
+    });
+    test.add_account(
+        program_id,
+        Account {
+            executable: true,
+            ..account(bpf_loader::ID, data)
+        },
+    );
+}
+
+fn pool_program() -> ProgramTest {
+    let mut test = ProgramTest::default();
+    add_program(
+        &mut test,
+        privacy_pool::ID,
+        deploy_path("target/deploy/privacy_pool.so"),
+    );
+    test
+}
+
+/// Pool authored (and relayed) by `authority`, with leaves `0..next_index`
+/// filled
+fn add_pool(test: &mut ProgramTest, authority: Pubkey, next_index: u32) {
+    let pool = pool::pool_address();
+    let mut state: PrivacyPool = bytemuck::Zeroable::zeroed();
+    state.authority = authority;
+    state.next_index = next_index;
+    for (i, leaf) in state.leaves[..next_index as usize].iter_mut().enumerate() {
+        leaf[31] = i as u8 + 1;
+    }
+    let data = [PrivacyPool::DISCRIMINATOR, bytemuck::bytes_of(&state)].concat();
+    test.add_account(pool, account(privacy_pool::ID, data));
+}
+
+fn token_account(mint: Pubkey, owner: Pubkey, amount: u64) -> Account {
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        mint,
+        owner,
+        amount,
+        state: spl_token::state::AccountState::Initialized,
+        ..Default::default()
+    }
+    .pack_into_slice(&mut data);
+    account(spl_token::ID, data)
+}
+
+/// Simulate `instruction` signed by `signer` and return the units it used
+async fn measure(test: ProgramTest, signer: &Keypair, instruction: Instruction) -> u64 {
+    let (banks, payer, blockhash) = test.start().await;
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            ComputeBudgetInstruction::set_compute_unit_limit(BENCH_UNIT_LIMIT),
+            instruction,
+        ],
+        Some(&payer.pubkey()),
+        &[&payer, signer],
+        blockhash,
+    );
+    let simulation = banks
+        .simulate_transaction(transaction)
+        .await
+        .expect("simulate");
+    let details = simulation.simulation_details.expect("simulation details");
+    if let Some(Err(e)) = simulation.result {
+        panic!("{e}\n{}", details.logs.join("\n"));
+    }
+    details.units_consumed
+}
+
+fn check(name: &str, used: u64) {
+    // Parsed by `cargo xtask bench`
+    println!("compute-units {name} {used}");
+    let budget = budget(name);
+    assert!(used <= budget, "{name} used {used} CU, budget {budget}");
+}
+
+#[tokio::test]
+#[ignore = "needs SBF builds; run with cargo xtask bench"]
+async fn deposit_into_the_last_leaf() {
+    let user = Keypair::new();
+    let pool = pool::pool_address();
+    let mut test = pool_program();
+    // The root is rehashed in full on every deposit, so the last leaf is
+    // no worse than the first; fill the tree up to it anyway
+    add_pool(&mut test, Pubkey::new_unique(), MAX_LEAVES as u32 - 1);
+
+    let mut mint = vec![0; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        mint_authority: COption::None,
+        supply: 1_000_000_000,
+        decimals: 6,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }
+    .pack_into_slice(&mut mint);
+    test.add_account(USDC_MINT, account(spl_token::ID, mint));
+    test.add_account(
+        get_associated_token_address(&user.pubkey(), &USDC_MINT),
+        token_account(USDC_MINT, user.pubkey(), 1_000_000_000),
+    );
+    test.add_account(
+        get_associated_token_address(&pool, &USDC_MINT),
+        token_account(USDC_MINT, pool, 0),
+    );
+
+    let limits = PoolLimits {
+        pool,
+        max_tvl: 0,
+        max_deposit: 0,
+        updated_at: 0,
+        max_hourly_withdrawal_bps: 0,
+        window_start: 0,
+        window_volume: 0,
+        paused: false,
+        unpause_requested_at: 0,
+        screening_program: Pubkey::default(),
+        screening_accounts: 0,
+    };
+    test.add_account(
+        pool::limits_address(),
+        anchor_account(privacy_pool::ID, &limits, 8 + PoolLimits::SIZE),
+    );
+    let leaf_times = LeafTimes {
+        pool,
+        inserted_at: [0; MAX_LEAVES],
+    };
+    test.add_account(
+        pool::leaf_times_address(),
+        anchor_account(privacy_pool::ID, &leaf_times, 8 + LeafTimes::SIZE),
+    );
+    let stats = PoolStats {
+        pool,
+        total_deposited: 0,
+        total_withdrawn: 0,
+        deposit_count: 0,
+        updated_at: 0,
+    };
+    test.add_account(
+        pool::stats_address(),
+        anchor_account(privacy_pool::ID, &stats, 8 + PoolStats::SIZE),
+    );
+
+    let used = measure(
+        test,
+        &user,
+        pool::deposit_instruction(&user.pubkey(), [9; 32], 25_000_000),
+    )
+    .await;
+    check("deposit", used);
+}
+
+#[tokio::test]
+#[ignore = "needs SBF builds; run with cargo xtask bench"]
+async fn record_nullifier_into_a_full_set() {
+    let relay = Keypair::new();
+    let mut test = pool_program();
+    add_pool(&mut test, relay.pubkey(), 0);
+
+    // One free slot left, and the new nullifier sorts first, so every
+    // recorded one has to move
+    let header = NullifierSet::new(pool::pool_address(), NULLIFIER_PURPOSE_SPEND);
+    let mut data = [NullifierSet::DISCRIMINATOR, bytemuck::bytes_of(&header)].concat();
+    data.resize(8 + NullifierSet::SIZE, 0);
+    let mut nullifiers = NullifierSlots::new(&mut data).expect("nullifier set");
+    for i in 0..MAX_LEAVES - 1 {
+        nullifiers
+            .insert([i as u8 + 2; 32], None)
+            .expect("free slot");
+    }
+    test.add_account(
+        pool::nullifier_set_address(NULLIFIER_PURPOSE_SPEND),
+        account(privacy_pool::ID, data),
+    );
+
+    let used = measure(
+        test,
+        &relay,
+        pool::record_nullifier_instruction(
+            &relay.pubkey(),
+            NULLIFIER_PURPOSE_SPEND,
+            [1; 32],
+            None,
+            1,
+        ),
+    )
+    .await;
+    check("record_nullifier", used);
+}
+
+/// Verifier standing in for zk_verifier, accepting every proof
+fn accept_any_proof(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _data: &[u8],
+) -> ProgramResult {
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "needs SBF builds; run with cargo xtask bench"]
+async fn record_nullifier_with_proof_inserts_the_change_note() {
+    let pool = pool::pool_address();
+    let verifier = Pubkey::new_unique();
+    let circuit_id = [7; 32];
+    let mut test = pool_program();
+    test.prefer_bpf(false);
+    test.add_program("accept_any_proof", verifier, processor!(accept_any_proof));
+    add_pool(&mut test, Pubkey::new_unique(), 3);
+
+    let header = NullifierSet::new(pool, NULLIFIER_PURPOSE_SPEND);
+    let mut data = [NullifierSet::DISCRIMINATOR, bytemuck::bytes_of(&header)].concat();
+    data.resize(8 + NullifierSet::SIZE, 0);
+    test.add_account(
+        pool::nullifier_set_address(NULLIFIER_PURPOSE_SPEND),
+        account(privacy_pool::ID, data),
+    );
+    let config = VerifierConfig {
+        pool,
+        verifier_program: verifier,
+        circuit_id,
+        aggregate_circuit_id: [8; 32],
+    };
+    test.add_account(
+        pool::verifier_config_address(),
+        anchor_account(privacy_pool::ID, &config, 8 + VerifierConfig::SIZE),
+    );
+    let leaf_times = LeafTimes {
+        pool,
+        inserted_at: [0; MAX_LEAVES],
+    };
+    test.add_account(
+        pool::leaf_times_address(),
+        anchor_account(privacy_pool::ID, &leaf_times, 8 + LeafTimes::SIZE),
+    );
+
+    // add_pool leaves the root unset
+    let mut purpose = [0; 32];
+    purpose[31] = NULLIFIER_PURPOSE_SPEND;
+    let mut version = [0; 32];
+    version[31] = COMMITMENT_VERSION;
+    let change = [9; 32];
+    let public_inputs = vec![
+        [0; 32],
+        [1; 32],
+        privacy_pool::nullifier_pool_id(&pool),
+        purpose,
+        [2; 32],
+        change,
+        [3; 32],
+        version,
+    ];
+
+    let (banks, payer, blockhash) = test.start().await;
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            ComputeBudgetInstruction::set_compute_unit_limit(BENCH_UNIT_LIMIT),
+            pool::record_nullifier_with_proof_instruction(
+                NULLIFIER_PURPOSE_SPEND,
+                &verifier,
+                &circuit_id,
+                &Pubkey::new_unique(),
+                vec![],
+                public_inputs,
+            ),
+        ],
+        Some(&payer.pubkey()),
+        &[&payer],
+        blockhash,
+    );
+    let result = banks
+        .process_transaction_with_metadata(transaction)
+        .await
+        .expect("process");
+    let metadata = result.metadata.expect("metadata");
+    if let Err(e) = result.result {
+        panic!("{e}\n{}", metadata.log_messages.join("\n"));
+    }
+
+    let data = banks
+        .get_account(pool)
+        .await
+        .expect("pool")
+        .expect("pool exists")
+        .data;
+    let state: PrivacyPool = bytemuck::pod_read_unaligned(&data[8..]);
+    assert_eq!(state.next_index, 4);
+    assert_eq!(state.leaves[3], change);
+    assert_eq!(state.merkle_root, compute_merkle_root(&state.leaves, 4));
+
+    // The verifier is stubbed, so this covers the pool's side only
+    check(
+        "record_nullifier_with_proof",
+        metadata.compute_units_consumed,
+    );
+}
+
+#[tokio::test]
+#[ignore = "needs SBF builds; run with cargo xtask bench"]
+async fn record_distribution_with_a_full_proof() {
+    let settler = Keypair::new();
+    let batch = Pubkey::new_unique();
+    let wallet = Pubkey::new_unique();
+    let mut test = ProgramTest::default();
+    add_program(
+        &mut test,
+        mpc::ID,
+        deploy_path("arcium-relay/target/deploy/obsidian_mpc.so"),
+    );
+
+    let leaf = mpc::distribution_leaf(0, 500, 0, 0, &wallet);
+    let tree = DistributionTree::new(&[leaf]);
+    let state = Batch {
+        market_id: "BTC-100K".into(),
+        status: BatchStatus::Executed,
+        order_count: 1,
+        total_usdc: 1_000_000,
+        total_shares: 500,
+        filled_usdc: 1_000_000,
+        distribution_root: Some(tree.root()),
+        staged_distributions: 1,
+        staged_shares: 500,
+        ..Default::default()
+    };
+    test.add_account(batch, mpc_account(&state, 1024));
+    let config = mpc::ProtocolConfig {
+        settler: settler.pubkey(),
+        ..Default::default()
+    };
+    test.add_account(mpc::config_address(), mpc_account(&config, 1024));
+    let slot = OrderSlot {
+        batch,
+        order_index: 0,
+        distributed: false,
+    };
+    test.add_account(mpc::order_slot_address(&batch, 0), mpc_account(&slot, 64));
+
+    let used = measure(
+        test,
+        &settler,
+        mpc::record_distribution(
+            &batch,
+            &settler.pubkey(),
+            0,
+            500,
+            0,
+            &wallet,
+            None,
+            tree.proof(0),
+            1,
+        ),
+    )
+    .await;
+    check("record_distribution", used);
+}
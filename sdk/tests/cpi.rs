@@ -0,0 +1,71 @@
+//! obsidian-cpi keeps its own copy of the builders; they must match the SDK's.
+
+use anchor_lang::prelude::{AnchorSerialize, Pubkey};
+use anchor_lang::Discriminator;
+use obsidian_sdk::mpc::{self, MpcEvent};
+use obsidian_sdk::pool;
+
+#[test]
+fn create_batch_matches_sdk() {
+    let authority = Pubkey::new_unique();
+    assert_eq!(
+        obsidian_cpi::mpc::create_batch(
+            &authority,
+            "BTC-100K",
+            1,
+            obsidian_cpi::mpc::BatchLane::Express,
+            Some("vault"),
+            Some([3; 32])
+        ),
+        mpc::create_batch(
+            &authority,
+            "BTC-100K",
+            1,
+            mpc::BatchLane::Express,
+            Some("vault"),
+            Some([3; 32])
+        )
+    );
+    assert_eq!(obsidian_cpi::mpc::ID, mpc::ID);
+}
+
+#[test]
+fn deposit_matches_sdk() {
+    let user = Pubkey::new_unique();
+    assert_eq!(
+        obsidian_cpi::pool::deposit(&user, [9; 32], 5_000_000),
+        pool::deposit_instruction(&user, [9; 32], 5_000_000)
+    );
+    assert_eq!(obsidian_cpi::pool::USDC_MINT, privacy_pool::USDC_MINT);
+    assert_eq!(
+        obsidian_cpi::pool::COMMITMENT_VERSION,
+        privacy_pool::COMMITMENT_VERSION
+    );
+}
+
+#[test]
+fn types_match_sdk() {
+    assert_eq!(
+        obsidian_cpi::mpc::BatchCreated::DISCRIMINATOR,
+        mpc::BatchCreated::DISCRIMINATOR
+    );
+    assert_eq!(
+        obsidian_cpi::pool::DepositEvent::DISCRIMINATOR,
+        privacy_pool::DepositEvent::DISCRIMINATOR
+    );
+
+    let result = mpc::BatchResult {
+        batch: Pubkey::new_unique(),
+        status: mpc::BatchStatus::Failed,
+        order_count: 2,
+        total_usdc: 10,
+        total_shares: 20,
+        filled_usdc: 5,
+    };
+    let mut data = Vec::new();
+    result.serialize(&mut data).unwrap();
+    let decoded: obsidian_cpi::mpc::BatchResult =
+        anchor_lang::AnchorDeserialize::try_from_slice(&data).unwrap();
+    assert_eq!(decoded.status, obsidian_cpi::mpc::BatchStatus::Failed);
+    assert_eq!(decoded.filled_usdc, 5);
+}
@@ -0,0 +1,191 @@
+use anchor_lang::prelude::Pubkey;
+use obsidian_sdk::args::ArgBuilder;
+use obsidian_sdk::encryption::{self, MxeSession, OrderData, PortfolioPosition};
+use obsidian_sdk::mpc;
+use obsidian_sdk::rescue::{self, Fq};
+use obsidian_sdk::SdkError;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+fn mxe_keypair() -> (StaticSecret, [u8; 32]) {
+    let secret = StaticSecret::from([7u8; 32]);
+    let public = PublicKey::from(&secret).to_bytes();
+    (secret, public)
+}
+
+fn session(mxe_public: &[u8; 32]) -> MxeSession {
+    MxeSession::from_parts(StaticSecret::from([42u8; 32]), mxe_public, 1000)
+}
+
+fn order() -> OrderData {
+    OrderData::new(12_500_000, &Pubkey::new_unique())
+}
+
+#[test]
+fn order_data_wallet_round_trip() {
+    let wallet = Pubkey::new_unique();
+    let order = OrderData::new(1, &wallet);
+    assert_eq!(order.wallet(), wallet);
+    assert_eq!(OrderData::from_fields(&order.to_fields()).unwrap(), order);
+
+    let expiring = order.expiring_at(1_700_000_000);
+    assert_eq!(
+        OrderData::from_fields(&expiring.to_fields()).unwrap(),
+        expiring
+    );
+}
+
+#[test]
+fn orders_expire_after_expires_at() {
+    let order = order();
+    assert!(!order.is_expired(u64::MAX));
+
+    let order = order.expiring_at(100);
+    assert!(!order.is_expired(100));
+    assert!(order.is_expired(101));
+}
+
+#[test]
+fn add_to_batch_args_decrypt_to_amount_and_expiry() {
+    let (mxe_secret, mxe_public) = mxe_keypair();
+    let order = order().expiring_at(1_700_000_000);
+
+    let [amount, expires_at] = session(&mxe_public).add_to_batch_args(&order);
+    assert_eq!(amount.ciphertexts.len(), 1);
+    assert_ne!(amount.nonce, expires_at.nonce);
+
+    assert_eq!(
+        encryption::decrypt(&mxe_secret, &amount).unwrap(),
+        vec![Fq::from(order.usdc_amount)]
+    );
+    assert_eq!(
+        encryption::decrypt(&mxe_secret, &expires_at).unwrap(),
+        vec![Fq::from(1_700_000_000u64)]
+    );
+}
+
+#[test]
+fn compute_distribution_args_use_distinct_nonces() {
+    let (mxe_secret, mxe_public) = mxe_keypair();
+    let order = order();
+
+    let args = session(&mxe_public).compute_distribution_args(&order);
+    let nonces: Vec<u128> = args.iter().map(|a| a.nonce).collect();
+    assert_eq!(nonces, vec![1000, 1001, 1002, 1003]);
+
+    let fields: Vec<Fq> = args
+        .iter()
+        .flat_map(|a| encryption::decrypt(&mxe_secret, a).unwrap())
+        .collect();
+    assert_eq!(OrderData::from_fields(&fields).unwrap(), order);
+}
+
+#[test]
+fn order_data_args_round_trip() {
+    let (mxe_secret, mxe_public) = mxe_keypair();
+    let order = order();
+
+    let mut session = session(&mxe_public);
+    let args = session.order_data_args(&order);
+    assert_eq!(args.public_key, session.public_key());
+    assert_eq!(args.ciphertext_bytes().len(), 4 * 32);
+    assert_ne!(args.fields().unwrap(), order.to_fields().to_vec());
+
+    let plaintext = encryption::decrypt(&mxe_secret, &args).unwrap();
+    assert_eq!(OrderData::from_fields(&plaintext).unwrap(), order);
+}
+
+#[test]
+fn wrong_key_does_not_decrypt() {
+    let (_, mxe_public) = mxe_keypair();
+    let order = order();
+
+    let args = session(&mxe_public).order_data_args(&order);
+    let plaintext = encryption::decrypt(&StaticSecret::from([8u8; 32]), &args).unwrap();
+    assert_ne!(plaintext, order.to_fields().to_vec());
+}
+
+#[test]
+fn non_canonical_ciphertext_is_rejected() {
+    let (mxe_secret, mxe_public) = mxe_keypair();
+
+    let [mut args, _] = session(&mxe_public).add_to_batch_args(&order());
+    args.ciphertexts[0] = [0xff; 32];
+    assert!(encryption::decrypt(&mxe_secret, &args).is_err());
+}
+
+#[test]
+fn revealed_portfolio_decrypts_to_positions() {
+    let (mxe_secret, mxe_public) = mxe_keypair();
+    let user_secret = StaticSecret::from([42u8; 32]);
+    let user_public = PublicKey::from(&user_secret).to_bytes();
+    let market = encryption::portfolio_market_key("KXBTC-25DEC31");
+
+    // What reveal_my_position outputs: markets, then shares, encrypted to the user.
+    let mut plaintext = vec![Fq::from(0u64); mpc::PORTFOLIO_CIPHERTEXTS];
+    plaintext[2] = Fq::from(market);
+    plaintext[mpc::PORTFOLIO_MARKETS + 2] = Fq::from(4_200u64);
+    let nonce = 77u128;
+    let ciphertexts: Vec<[u8; 32]> = encryption::shared_cipher(&mxe_secret, &user_public)
+        .encrypt(&plaintext, &nonce.to_le_bytes())
+        .iter()
+        .map(rescue::to_bytes)
+        .collect();
+
+    let positions = encryption::decrypt_portfolio(
+        &user_secret,
+        &mxe_public,
+        nonce,
+        &ciphertexts.try_into().unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        positions,
+        vec![PortfolioPosition {
+            market,
+            shares: 4_200
+        }]
+    );
+}
+
+#[test]
+fn note_summary_fits_one_queue_and_decrypts_to_the_total() {
+    let (mxe_secret, mxe_public) = mxe_keypair();
+    let notes = [(3, 25_000_000), (9, 10_000_000)];
+
+    let args = session(&mxe_public).sum_my_notes_args(&notes).unwrap();
+    let mut plaintext = vec![Fq::from(0u64); 2 * mpc::SUMMARY_NOTES];
+    plaintext[0] = Fq::from(3u64);
+    plaintext[1] = Fq::from(9u64);
+    plaintext[mpc::SUMMARY_NOTES] = Fq::from(25_000_000u64);
+    plaintext[mpc::SUMMARY_NOTES + 1] = Fq::from(10_000_000u64);
+    assert_eq!(encryption::decrypt(&mxe_secret, &args).unwrap(), plaintext);
+
+    let full = [(0, 1); mpc::SUMMARY_NOTES];
+    let args = session(&mxe_public).sum_my_notes_args(&full).unwrap();
+    assert!(ArgBuilder::new()
+        .shared(&args)
+        .plaintext_u64(u64::MAX)
+        .build()
+        .is_ok());
+    assert!(matches!(
+        session(&mxe_public).sum_my_notes_args(&[(0, 1); mpc::SUMMARY_NOTES + 1]),
+        Err(SdkError::TooManyNotes { .. })
+    ));
+
+    // What sum_my_notes outputs: the total, encrypted back to the user
+    let user_secret = StaticSecret::from([42u8; 32]);
+    let user_public = PublicKey::from(&user_secret).to_bytes();
+    let nonce = 5u128;
+    let total = encryption::shared_cipher(&mxe_secret, &user_public)
+        .encrypt(&[Fq::from(35_000_000u64)], &nonce.to_le_bytes());
+    assert_eq!(
+        encryption::decrypt_balance(
+            &user_secret,
+            &mxe_public,
+            nonce,
+            &rescue::to_bytes(&total[0])
+        )
+        .unwrap(),
+        35_000_000
+    );
+}
@@ -0,0 +1,89 @@
+//! The committed IDLs (idls/, written by `cargo xtask codegen`) must match
+//! the programs: the Rust bindings below are generated from them, and the
+//! relay and frontend TypeScript bindings come from the same files.
+
+use anchor_lang::prelude::{Pubkey, ToAccountMetas};
+use anchor_lang::{Discriminator, InstructionData};
+use obsidian_sdk::{pool, verifier};
+
+anchor_lang::declare_program!(privacy_pool);
+anchor_lang::declare_program!(zk_verifier);
+
+#[test]
+fn deposit_bindings_match_the_program() {
+    let sdk = pool::deposit_instruction(&Pubkey::new_unique(), [9; 32], 5_000_000);
+    let key = |i: usize| sdk.accounts[i].pubkey;
+
+    let accounts = privacy_pool::client::accounts::Deposit {
+        pool: key(0),
+        user: key(1),
+        user_usdc: key(2),
+        pool_usdc: key(3),
+        usdc_mint: key(4),
+        token_program: key(5),
+        limits: key(6),
+        leaf_times: key(7),
+        stats: key(8),
+        migration: key(9),
+        cpi_guard: key(10),
+        instructions: key(11),
+        receipt_mint: None,
+        receipt_account: None,
+        token_2022_program: None,
+        associated_token_program: None,
+        system_program: None,
+        event_authority: key(sdk.accounts.len() - 2),
+        program: privacy_pool::ID,
+    };
+    assert_eq!(accounts.to_account_metas(None), sdk.accounts);
+    assert_eq!(
+        privacy_pool::client::args::Deposit {
+            commitment: [9; 32],
+            amount: 5_000_000,
+            version: ::privacy_pool::COMMITMENT_VERSION,
+        }
+        .data(),
+        sdk.data
+    );
+    assert_eq!(privacy_pool::ID, sdk.program_id);
+}
+
+#[test]
+fn verifier_bindings_match_the_program() {
+    let sdk = verifier::rotate_verifying_key_instruction(&Pubkey::new_unique(), "balance_proof", 2);
+    let key = |i: usize| sdk.accounts[i].pubkey;
+
+    let accounts = zk_verifier::client::accounts::RotateVerifyingKey {
+        verifying_key: key(0),
+        active_key: key(1),
+        authority: key(2),
+        system_program: key(3),
+        event_authority: key(4),
+        program: zk_verifier::ID,
+    };
+    assert_eq!(accounts.to_account_metas(None), sdk.accounts);
+    assert_eq!(
+        zk_verifier::client::args::RotateVerifyingKey {}.data(),
+        sdk.data
+    );
+}
+
+#[test]
+fn event_and_account_discriminators_match() {
+    assert_eq!(
+        privacy_pool::events::DepositEvent::DISCRIMINATOR,
+        ::privacy_pool::DepositEvent::DISCRIMINATOR
+    );
+    assert_eq!(
+        privacy_pool::events::WithdrawalEvent::DISCRIMINATOR,
+        ::privacy_pool::WithdrawalEvent::DISCRIMINATOR
+    );
+    assert_eq!(
+        privacy_pool::accounts::PrivacyPool::DISCRIMINATOR,
+        ::privacy_pool::PrivacyPool::DISCRIMINATOR
+    );
+    assert_eq!(
+        zk_verifier::events::VerifyingKeyRotated::DISCRIMINATOR,
+        ::zk_verifier::VerifyingKeyRotated::DISCRIMINATOR
+    );
+}
@@ -0,0 +1,102 @@
+use obsidian_sdk::leakage::{
+    estimate, min_order_count, summarize, BatchObservation, ObserverModel,
+};
+use obsidian_sdk::simulation::REVEAL_BUCKET;
+
+const USDC: u64 = 1_000_000;
+
+#[test]
+fn a_lone_unknown_order_in_an_exact_batch_is_exposed() {
+    let batch = BatchObservation::exact(300 * USDC, 3).with_known(2, 120 * USDC);
+    let e = estimate(&batch, &ObserverModel::default()).unwrap();
+
+    assert_eq!((e.min_amount, e.max_amount), (180 * USDC, 180 * USDC));
+    assert_eq!(e.best_guess, 180 * USDC);
+    assert!(e.is_exposed());
+    assert_eq!(e.rms_error, 0.0);
+}
+
+#[test]
+fn buckets_keep_a_lone_unknown_order_uncertain() {
+    let batch = BatchObservation::bucketed(3 * REVEAL_BUCKET, 3).with_known(2, 1_200 * USDC);
+    let e = estimate(&batch, &ObserverModel::default()).unwrap();
+
+    assert_eq!(e.max_amount, 1_800 * USDC);
+    assert_eq!(e.min_amount, 800 * USDC + 1);
+    assert!(!e.is_exposed());
+    // 1,000 USDC at cent resolution
+    assert!((e.entropy_bits - 100_000f64.log2()).abs() < 1e-6);
+}
+
+#[test]
+fn order_size_limits_narrow_the_interval() {
+    let model = ObserverModel {
+        min_order: 10 * USDC,
+        max_order: 100 * USDC,
+        ..ObserverModel::default()
+    };
+    let e = estimate(&BatchObservation::exact(250 * USDC, 3), &model).unwrap();
+
+    // Two others fill at most 200 and at least 20
+    assert_eq!((e.min_amount, e.max_amount), (50 * USDC, 100 * USDC));
+    assert!(e.best_guess >= e.min_amount && e.best_guess <= e.max_amount);
+}
+
+#[test]
+fn more_orders_leave_more_uncertainty() {
+    let model = ObserverModel::default();
+    let errors: Vec<f64> = [2u8, 4, 8, 16]
+        .iter()
+        .map(|&count| {
+            let total = 100 * USDC * count as u64;
+            let batch = BatchObservation::exact(total, count);
+            estimate(&batch, &model).unwrap().rms_error / (100 * USDC) as f64
+        })
+        .collect();
+    // Relative error of an even-split guess grows towards 1 with the count
+    assert!(
+        errors.windows(2).all(|pair| pair[0] < pair[1]),
+        "{errors:?}"
+    );
+}
+
+#[test]
+fn summary_points_at_the_weakest_batch() {
+    let batches = [
+        BatchObservation::exact(500 * USDC, 5),
+        BatchObservation::exact(90 * USDC, 2).with_known(1, 40 * USDC),
+        BatchObservation::exact(40 * USDC, 1).with_known(1, 40 * USDC),
+    ];
+    let summary = summarize(&batches, &ObserverModel::default()).unwrap();
+
+    assert_eq!(summary.worst, Some(1));
+    assert_eq!(summary.exposed, 1);
+    assert_eq!(summary.estimates[2].unknown_orders, 0);
+}
+
+#[test]
+fn min_order_count_meets_the_target() {
+    let model = ObserverModel {
+        min_order: USDC,
+        max_order: 10_000 * USDC,
+        resolution: USDC,
+    };
+    // An observer holding one order never learns anything from a 1-order
+    // batch, so the answer needs at least one more
+    let exact = min_order_count(100 * USDC, 1, 1, 6.0, &model).unwrap();
+    assert!(exact >= 2);
+    let batch = BatchObservation::exact(100 * USDC * exact as u64, exact).with_known(1, 100 * USDC);
+    assert!(estimate(&batch, &model).unwrap().entropy_bits >= 6.0);
+
+    // Bucketing reaches the same target with no more orders
+    let bucketed = min_order_count(100 * USDC, REVEAL_BUCKET, 1, 6.0, &model).unwrap();
+    assert!(bucketed <= exact);
+    assert_eq!(min_order_count(100 * USDC, 1, 0, 64.0, &model), None);
+}
+
+#[test]
+fn inconsistent_observations_are_rejected() {
+    let model = ObserverModel::default();
+    assert!(estimate(&BatchObservation::exact(10, 1).with_known(2, 0), &model).is_err());
+    assert!(estimate(&BatchObservation::exact(10, 2).with_known(1, 11), &model).is_err());
+}
@@ -0,0 +1,139 @@
+use std::borrow::Cow;
+
+use anchor_lang::prelude::Pubkey;
+use obsidian_sdk::lookup_table::{
+    compile_v0, decode_lookup_table, distribution_accounts, extend_instructions, transaction_size,
+    AddressLookupTableAccount, MAX_EXTEND_ADDRESSES,
+};
+use obsidian_sdk::mpc::{self, DISTRIBUTION_TREE_DEPTH};
+use obsidian_sdk::SdkError;
+use solana_address_lookup_table_interface::state::{AddressLookupTable, LookupTableMeta};
+use solana_hash::Hash;
+
+fn distributions(
+    batch: &Pubkey,
+    authority: &Pubkey,
+    count: u8,
+) -> Vec<anchor_lang::solana_program::instruction::Instruction> {
+    (0..count)
+        .map(|order_index| {
+            mpc::record_distribution(
+                batch,
+                authority,
+                order_index,
+                1_000_000,
+                0,
+                &Pubkey::new_unique(),
+                None,
+                [[0; 32]; DISTRIBUTION_TREE_DEPTH],
+                order_index as u64 + 1,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn distribution_accounts_cover_every_order() {
+    let batch = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let accounts = distribution_accounts(&batch, &authority, "BTC-100K", 3);
+
+    assert_eq!(accounts.len(), 6 + 2 * 3);
+    assert_eq!(accounts[0], batch);
+    assert!(accounts.contains(&mpc::distribution_address(&batch, 2)));
+    assert!(accounts.contains(&mpc::order_slot_address(&batch, 2)));
+    assert!(accounts.contains(&mpc::relay_nonce_address(&authority)));
+}
+
+#[test]
+fn extend_skips_present_addresses_and_chunks_the_rest() {
+    let table = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let existing: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+    let mut wanted = existing.clone();
+    wanted.extend((0..MAX_EXTEND_ADDRESSES + 1).map(|_| Pubkey::new_unique()));
+    // Duplicates within the request are added once
+    wanted.push(wanted[6]);
+
+    let ixs = extend_instructions(&table, &authority, &authority, &existing, &wanted).unwrap();
+    assert_eq!(ixs.len(), 2);
+    assert!(ixs.iter().all(|ix| ix.accounts[0].pubkey == table));
+
+    assert!(
+        extend_instructions(&table, &authority, &authority, &existing, &existing)
+            .unwrap()
+            .is_empty()
+    );
+
+    let full: Vec<Pubkey> = (0..256).map(|_| Pubkey::new_unique()).collect();
+    assert!(matches!(
+        extend_instructions(
+            &table,
+            &authority,
+            &authority,
+            &full,
+            &[Pubkey::new_unique()]
+        ),
+        Err(SdkError::TooManyAddresses {
+            count: 257,
+            max: 256
+        })
+    ));
+}
+
+#[test]
+fn lookup_table_replaces_account_keys_with_indexes() {
+    let batch = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let ixs = distributions(&batch, &authority, 2);
+
+    let legacy = compile_v0(&authority, &ixs, &[], Hash::new_unique()).unwrap();
+    let table = AddressLookupTableAccount {
+        key: Pubkey::new_unique(),
+        addresses: distribution_accounts(&batch, &authority, "BTC-100K", 2),
+    };
+    let looked_up = compile_v0(&authority, &ixs, &[table], Hash::new_unique()).unwrap();
+
+    // Everything but the signer and the invoked program moves to the table
+    let lookups = looked_up.address_table_lookups().unwrap();
+    assert_eq!(lookups.len(), 1);
+    let indexes = lookups[0].writable_indexes.len() + lookups[0].readonly_indexes.len();
+    assert_eq!(indexes, 9);
+    assert_eq!(looked_up.static_account_keys().len(), 2);
+
+    let saved = transaction_size(&legacy).unwrap() - transaction_size(&looked_up).unwrap();
+    // 32 bytes per key, less the table's key, an index per key and the
+    // lengths of both index lists
+    assert_eq!(saved, 9 * 32 - 32 - 9 - 2);
+
+    assert!(matches!(
+        compile_v0(
+            &authority,
+            &distributions(&batch, &authority, 4),
+            &[],
+            Hash::new_unique()
+        ),
+        Err(SdkError::TransactionTooLarge { .. })
+    ));
+}
+
+#[test]
+fn decodes_lookup_table_accounts() {
+    let address = Pubkey::new_unique();
+    let addresses: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let data = AddressLookupTable {
+        meta: LookupTableMeta::new(Pubkey::new_unique()),
+        addresses: Cow::Owned(addresses.clone()),
+    }
+    .serialize_for_tests()
+    .unwrap();
+
+    let table = decode_lookup_table(&address, &data).unwrap();
+    assert_eq!(table.key, address);
+    assert_eq!(table.addresses, addresses);
+
+    assert!(matches!(
+        decode_lookup_table(&address, &[0; 8]),
+        Err(SdkError::InvalidAccountData)
+    ));
+}
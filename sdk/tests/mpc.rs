@@ -0,0 +1,519 @@
+use anchor_lang::prelude::{AnchorSerialize, Pubkey};
+use obsidian_sdk::mpc::{self, MpcAccount, MpcRegistry, RegisteredCompDef, Subscription};
+
+#[test]
+fn comp_def_offsets_match_deployed() {
+    // Offsets registered on devnet by scripts/init-comp-defs.ts.
+    assert_eq!(mpc::comp_def_offset("init_batch"), 3167146940);
+    assert_eq!(mpc::comp_def_offset("add_to_batch"), 448552201);
+    assert_eq!(mpc::comp_def_offset("reveal_batch_total"), 1072107248);
+    assert_eq!(mpc::comp_def_offset("compute_distribution"), 623176224);
+}
+
+#[test]
+fn registry_reports_missing_circuits() {
+    let mut registry = MpcRegistry {
+        authority: Pubkey::new_unique(),
+        circuits: mpc::CIRCUITS
+            .iter()
+            .map(|circuit| RegisteredCompDef {
+                offset: mpc::comp_def_offset(circuit),
+                comp_def: mpc::comp_def_address(circuit),
+                version: 1,
+                registered_at: 0,
+            })
+            .collect(),
+    };
+    assert!(registry.missing(1).is_empty());
+    assert_eq!(registry.missing(2), mpc::CIRCUITS);
+
+    registry.circuits.remove(1);
+    assert_eq!(registry.missing(1), vec!["add_to_batch"]);
+    assert!(!registry.is_registered("add_to_batch", 1));
+}
+
+#[test]
+fn versioned_circuits_get_their_own_comp_defs() {
+    assert_eq!(mpc::versioned_circuit("add_to_batch", 1), "add_to_batch");
+    assert_eq!(mpc::versioned_circuit("add_to_batch", 2), "add_to_batch_v2");
+    assert_ne!(
+        mpc::comp_def_address(&mpc::versioned_circuit("add_to_batch", 2)),
+        mpc::comp_def_address("add_to_batch")
+    );
+}
+
+#[test]
+fn distribution_proofs_verify_against_tree_root() {
+    let wallets = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+    let leaves: Vec<[u8; 32]> = wallets
+        .iter()
+        .enumerate()
+        .map(|(index, wallet)| {
+            mpc::distribution_leaf(index as u8, 100 * index as u64, 5, 0, wallet)
+        })
+        .collect();
+    let tree = mpc::DistributionTree::new(&leaves);
+
+    for (index, leaf) in leaves.iter().enumerate() {
+        let proof = tree.proof(index as u8);
+        assert_eq!(
+            mpc::distribution_proof_root(*leaf, index as u8, &proof),
+            tree.root()
+        );
+    }
+
+    // A fabricated distribution, or a real one at another index, misses the root
+    let forged = mpc::distribution_leaf(1, 1_000, 5, 0, &wallets[1]);
+    assert_ne!(
+        mpc::distribution_proof_root(forged, 1, &tree.proof(1)),
+        tree.root()
+    );
+    assert_ne!(
+        mpc::distribution_proof_root(leaves[0], 1, &tree.proof(1)),
+        tree.root()
+    );
+}
+
+#[test]
+fn encrypted_and_stealth_distributions_prove_their_own_leaves() {
+    let wallet = Pubkey::new_unique();
+    let claim_key = Pubkey::new_unique();
+    let encryption_key = [4u8; 32];
+    let encrypted_shares = [5u8; 32];
+    let leaves = [
+        mpc::distribution_leaf(0, 100, 5, 0, &wallet),
+        mpc::encrypted_distribution_leaf(1, &encryption_key, 77, &encrypted_shares, &wallet),
+        mpc::stealth_distribution_leaf(2, 300, &claim_key),
+    ];
+    let tree = mpc::DistributionTree::new(&leaves);
+    for (index, leaf) in leaves.iter().enumerate() {
+        assert_eq!(
+            mpc::distribution_proof_root(*leaf, index as u8, &tree.proof(index as u8)),
+            tree.root()
+        );
+    }
+
+    // Another ciphertext, or more shares for the claim key, misses the root
+    let swapped = mpc::encrypted_distribution_leaf(1, &encryption_key, 77, &[6; 32], &wallet);
+    assert_ne!(
+        mpc::distribution_proof_root(swapped, 1, &tree.proof(1)),
+        tree.root()
+    );
+    let inflated = mpc::stealth_distribution_leaf(2, 3_000, &claim_key);
+    assert_ne!(
+        mpc::distribution_proof_root(inflated, 2, &tree.proof(2)),
+        tree.root()
+    );
+
+    let batch = Pubkey::new_unique();
+    let settler = Pubkey::new_unique();
+    let ix =
+        mpc::record_stealth_distribution(&batch, &settler, 2, 300, &claim_key, tree.proof(2), 1);
+    let mut data = Vec::new();
+    (2u8, 300u64, claim_key, tree.proof(2), 1u64)
+        .serialize(&mut data)
+        .unwrap();
+    assert_eq!(ix.data[8..], data);
+}
+
+#[test]
+fn staging_commits_the_root_count_and_shares() {
+    let batch = Pubkey::new_unique();
+    let settler = Pubkey::new_unique();
+    let root = [9u8; 32];
+
+    let stage = mpc::stage_distributions(&batch, &settler, root, 3, 600);
+    assert_eq!(&stage.data[8..40], &root);
+    assert_eq!(stage.data[40], 3);
+    assert_eq!(stage.data[41..], 600u64.to_le_bytes());
+    assert_eq!(stage.accounts[0].pubkey, batch);
+    assert!(stage.accounts[0].is_writable);
+
+    let finalize = mpc::finalize_distributions(&batch, &settler);
+    assert_eq!(finalize.data.len(), 8);
+    assert_ne!(finalize.data[..8], stage.data[..8]);
+    assert!(finalize.accounts[2].is_signer);
+}
+
+#[test]
+fn mark_distributed_many_lists_each_distribution_after_the_program() {
+    let batch = Pubkey::new_unique();
+    let settler = Pubkey::new_unique();
+    let indices = [4u8, 0, 7];
+
+    let ix = mpc::mark_distributed_many(&batch, "BTC-100K", &settler, &indices, "sig");
+    let mut data = Vec::new();
+    (indices.to_vec(), "sig".to_string())
+        .serialize(&mut data)
+        .unwrap();
+    assert_eq!(ix.data[8..], data);
+    assert_ne!(
+        ix.data[..8],
+        mpc::mark_distributed(&batch, "BTC-100K", &settler, 4, "sig").data[..8]
+    );
+
+    // batch, config, market stats, settler, system program, event
+    // authority, program, then the distributions in order
+    assert_eq!(ix.accounts.len(), 7 + indices.len());
+    assert_eq!(ix.accounts[6].pubkey, mpc::ID);
+    for (meta, &order_index) in ix.accounts[7..].iter().zip(&indices) {
+        assert_eq!(meta.pubkey, mpc::distribution_address(&batch, order_index));
+        assert!(meta.is_writable && !meta.is_signer);
+    }
+}
+
+#[test]
+fn claim_receipts_prove_the_claimant_leaf() {
+    let batch = Pubkey::new_unique();
+    let claimant = Pubkey::new_unique();
+    let leaves = [
+        mpc::distribution_leaf(0, 200, 20, 0, &Pubkey::new_unique()),
+        mpc::distribution_leaf(1, 400, 0, 0, &claimant),
+    ];
+    let tree = mpc::DistributionTree::new(&leaves);
+    let receipt = tree.receipt(1, 400, 0, 0);
+    assert_eq!(
+        mpc::distribution_proof_root(leaves[1], 1, &receipt.proof),
+        tree.root()
+    );
+
+    let share_mint = Pubkey::new_unique();
+    let usdc_mint = Pubkey::new_unique();
+    let token_program = Pubkey::new_unique();
+    let ix = mpc::claim_shares(
+        &batch,
+        "BTC-100K",
+        &claimant,
+        1,
+        receipt.clone(),
+        &share_mint,
+        &token_program,
+        &Pubkey::new_unique(),
+        &usdc_mint,
+        &token_program,
+        &Pubkey::new_unique(),
+    );
+    let mut data = Vec::new();
+    (1u8, receipt).serialize(&mut data).unwrap();
+    assert_eq!(ix.data[8..], data);
+
+    // The claimant signs and pays; the relay is not involved
+    let signers: Vec<_> = ix.accounts.iter().filter(|meta| meta.is_signer).collect();
+    assert_eq!(signers.len(), 1);
+    assert_eq!(signers[0].pubkey, claimant);
+    assert_eq!(ix.accounts[1].pubkey, mpc::distribution_address(&batch, 1));
+    assert_eq!(
+        ix.accounts[6].pubkey,
+        mpc::share_vault_address(&batch, &share_mint, &token_program)
+    );
+    assert_eq!(
+        ix.accounts[8].pubkey,
+        mpc::batch_escrow_address(&batch, &usdc_mint, &token_program)
+    );
+}
+
+#[test]
+fn batch_result_decodes_only_obsidian_return_data() {
+    let result = mpc::BatchResult {
+        batch: Pubkey::new_unique(),
+        status: mpc::BatchStatus::Closed,
+        order_count: 3,
+        total_usdc: 6_000_000,
+        total_shares: 0,
+        filled_usdc: 0,
+    };
+    let mut data = Vec::new();
+    result.serialize(&mut data).unwrap();
+
+    assert_eq!(
+        mpc::BatchResult::from_return_data(&mpc::ID, &data),
+        Some(result)
+    );
+    assert_eq!(
+        mpc::BatchResult::from_return_data(&Pubkey::new_unique(), &data),
+        None
+    );
+    assert_eq!(
+        mpc::BatchResult::from_return_data(&mpc::ID, &data[..10]),
+        None
+    );
+}
+
+#[test]
+fn delegated_order_signature_instruction_points_at_intent() {
+    let intent = mpc::OrderIntent {
+        batch: Pubkey::new_unique(),
+        user: Pubkey::new_unique(),
+        order_commitment: [3; 32],
+        nonce: 1,
+        expires_at: 1_700_000_000,
+    };
+    let signature = [9u8; 64];
+    let [verify, record] =
+        mpc::record_delegated_order(&Pubkey::new_unique(), &intent, &signature, None, 4);
+
+    assert_eq!(verify.program_id, mpc::ED25519_PROGRAM_ID);
+    let data = &verify.data;
+    let field = |i: usize| u16::from_le_bytes([data[2 + 2 * i], data[3 + 2 * i]]) as usize;
+    assert_eq!(data[0], 1);
+    assert_eq!(&data[field(0)..field(0) + 64], signature);
+    assert_eq!(&data[field(2)..field(2) + 32], intent.user.as_ref());
+    assert_eq!(&data[field(4)..field(4) + field(5)], intent.message());
+    assert!(intent.message().starts_with(mpc::ORDER_INTENT_DOMAIN));
+
+    assert_eq!(
+        record.accounts[1].pubkey,
+        mpc::order_slot_address(&intent.batch, 4)
+    );
+    assert_eq!(record.accounts[4].pubkey, mpc::INSTRUCTIONS_SYSVAR_ID);
+}
+
+#[test]
+fn session_signed_intent_verifies_the_session_key() {
+    let intent = mpc::OrderIntent {
+        batch: Pubkey::new_unique(),
+        user: Pubkey::new_unique(),
+        order_commitment: [3; 32],
+        nonce: 2,
+        expires_at: 1_700_000_000,
+    };
+    let session_key = Pubkey::new_unique();
+    let [verify, record] = mpc::record_delegated_order(
+        &Pubkey::new_unique(),
+        &intent,
+        &[9; 64],
+        Some(&session_key),
+        0,
+    );
+
+    let pubkey_offset = u16::from_le_bytes([verify.data[6], verify.data[7]]) as usize;
+    assert_eq!(
+        &verify.data[pubkey_offset..pubkey_offset + 32],
+        session_key.as_ref()
+    );
+    assert_eq!(
+        record.accounts[5].pubkey,
+        mpc::session_key_address(&intent.user, &session_key)
+    );
+
+    let [_, record] =
+        mpc::record_delegated_order(&Pubkey::new_unique(), &intent, &[9; 64], None, 0);
+    assert_eq!(record.accounts[5].pubkey, mpc::ID);
+}
+
+#[test]
+fn reveal_and_execution_pass_the_market_trade_guard_accounts() {
+    let batch = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let guard = [
+        mpc::market_mints_address("KXSB-26-BUF"),
+        mpc::INSTRUCTIONS_SYSVAR_ID,
+    ];
+    let close = mpc::close_batch(&batch, "KXSB-26-BUF", &authority, 6_000_000, 3);
+    let execute = mpc::record_execution(
+        &batch,
+        "KXSB-26-BUF",
+        &authority,
+        100,
+        6_000_000,
+        "sig",
+        None,
+        false,
+    );
+
+    // close_batch also updates the market's arrival counters
+    let n = close.accounts.len();
+    assert_eq!(
+        close.accounts[n - 3].pubkey,
+        mpc::market_stats_address("KXSB-26-BUF")
+    );
+    assert!(close.accounts[n - 3].is_writable);
+    let mut close = close;
+    close.accounts.remove(n - 3);
+
+    for ix in [close, execute] {
+        // Followed only by the event CPI accounts
+        let n = ix.accounts.len();
+        let keys: Vec<Pubkey> = ix.accounts[n - 4..n - 2].iter().map(|m| m.pubkey).collect();
+        assert_eq!(keys, guard);
+        assert!(ix.accounts[n - 4..n - 2].iter().all(|m| !m.is_writable));
+    }
+    assert_ne!(guard[0], mpc::market_mints_address("KXSB-26-DEN"));
+}
+
+#[test]
+fn lanes_route_by_amount_thresholds() {
+    let lanes = mpc::MarketLanes {
+        market_id: "KXSB-26-BUF".into(),
+        thresholds: vec![100_000_000, 1_000_000_000],
+        updated_at: 0,
+    };
+    assert_eq!(lanes.lane_for(99_999_999), 0);
+    assert_eq!(lanes.lane_for(100_000_000), 1);
+    assert_eq!(lanes.lane_for(5_000_000_000), 2);
+    assert_eq!(
+        lanes.circuit_thresholds(),
+        [100_000_000, 1_000_000_000, u64::MAX]
+    );
+
+    let authority = Pubkey::new_unique();
+    assert_eq!(
+        mpc::lane_batch_address(&authority, "KXSB-26-BUF", 0),
+        mpc::batch_address(&authority, "KXSB-26-BUF")
+    );
+    let lane_batch = mpc::lane_batch_address(&authority, "KXSB-26-BUF", 2);
+    assert_ne!(lane_batch, mpc::batch_address(&authority, "KXSB-26-BUF"));
+
+    let create = mpc::create_lane_batch(&authority, "KXSB-26-BUF", 1, 2, None, None);
+    assert_eq!(create.accounts[0].pubkey, lane_batch);
+    let route = mpc::route_order(&lane_batch, "KXSB-26-BUF", &authority, 4, 2);
+    assert_eq!(route.data[8..], [2]);
+    assert_eq!(
+        route.accounts[1].pubkey,
+        mpc::market_lanes_address("KXSB-26-BUF")
+    );
+    assert_eq!(
+        route.accounts[3].pubkey,
+        mpc::order_slot_address(&lane_batch, 4)
+    );
+}
+
+#[test]
+fn batch_windows_follow_recent_arrival_rate() {
+    let mut config = mpc::ProtocolConfig::default();
+    let mut stats = mpc::MarketStats::default();
+    let mut batch = mpc::Batch {
+        created_at: 1_000,
+        ..Default::default()
+    };
+
+    // Off: the fixed minimum duration
+    assert_eq!(stats.batch_window(&config), mpc::MIN_BATCH_DURATION);
+    assert!(!stats.can_close(&config, &batch, 1_059));
+    assert!(stats.can_close(&config, &batch, 1_060));
+
+    config.min_batch_window = 120;
+    config.max_batch_window = 900;
+    config.target_batch_orders = 8;
+    // No history yet: the longest window
+    assert_eq!(stats.batch_window(&config), 900);
+
+    // 16 orders in 240s: 8 orders take 120s
+    stats.record_close(16, 240);
+    assert_eq!(stats.batch_window(&config), 120);
+    // Volume dries up: 1 order in 600s pulls the window out to the maximum
+    stats.record_close(1, 600);
+    stats.record_close(1, 600);
+    assert_eq!((stats.recent_orders, stats.recent_open_secs), (5, 960));
+    assert_eq!(stats.batch_window(&config), 900);
+
+    // Reaching the target closes early, but never before the minimum
+    batch.order_count = 8;
+    assert!(!stats.can_close(&config, &batch, 1_119));
+    assert!(stats.can_close(&config, &batch, 1_120));
+    batch.order_count = 7;
+    assert!(!stats.can_close(&config, &batch, 1_899));
+    assert!(stats.can_close(&config, &batch, 1_900));
+}
+
+#[test]
+fn express_batches_close_on_the_express_window() {
+    let authority = Pubkey::new_unique();
+    let express = mpc::priority_batch_address(&authority, "BTC-100K", mpc::BatchLane::Express);
+    assert_eq!(
+        mpc::priority_batch_address(&authority, "BTC-100K", mpc::BatchLane::Standard),
+        mpc::batch_address(&authority, "BTC-100K")
+    );
+    assert_ne!(express, mpc::batch_address(&authority, "BTC-100K"));
+    assert_eq!(
+        mpc::create_batch(
+            &authority,
+            "BTC-100K",
+            1,
+            mpc::BatchLane::Express,
+            None,
+            None
+        )
+        .accounts[0]
+            .pubkey,
+        express
+    );
+
+    let config = mpc::ProtocolConfig {
+        min_batch_window: 120,
+        max_batch_window: 900,
+        target_batch_orders: 8,
+        express_batch_window: 90,
+        express_fee_bps: 50,
+        ..Default::default()
+    };
+    let stats = mpc::MarketStats::default();
+    let batch = mpc::Batch {
+        created_at: 1_000,
+        lane: mpc::EXPRESS_LANE,
+        ..Default::default()
+    };
+
+    // Neither the adaptive window nor its minimum applies
+    assert!(!stats.can_close(&config, &batch, 1_089));
+    assert!(stats.can_close(&config, &batch, 1_090));
+    let standard = mpc::Batch { lane: 0, ..batch };
+    assert!(!stats.can_close(&config, &standard, 1_090));
+}
+
+#[test]
+fn reveals_expire_past_the_configured_gap() {
+    let batch = mpc::Batch {
+        closed_slot: 1_000,
+        ..Default::default()
+    };
+    assert!(!batch.reveal_expired(0, u64::MAX));
+    assert!(!batch.reveal_expired(150, 1_150));
+    assert!(batch.reveal_expired(150, 1_151));
+
+    // The approval PDA is always passed, whether or not approvals exist
+    let key = Pubkey::new_unique();
+    let ix = mpc::expire_reveal(&key);
+    assert_eq!(ix.accounts[2].pubkey, mpc::execution_approval_address(&key));
+    assert!(ix.accounts[2].is_writable);
+}
+
+#[test]
+fn subscriptions_are_per_owner_and_id() {
+    let owner = Pubkey::new_unique();
+    assert_ne!(
+        mpc::subscription_address(&owner, 0),
+        mpc::subscription_address(&owner, 1)
+    );
+    assert_ne!(
+        mpc::subscription_address(&owner, 0),
+        mpc::subscription_address(&Pubkey::new_unique(), 0)
+    );
+
+    let subscription = Subscription {
+        owner,
+        id: 1,
+        market_id: "KXBTC-100K".to_string(),
+        side: 0,
+        cadence: 86_400,
+        next_enroll_at: 1_000,
+        remaining: 30,
+        ciphertext_ref: [9; 32],
+        last_batch: Pubkey::default(),
+        enrolled: 0,
+        created_at: 1_000,
+        jitter_bps: 1_000,
+        schedule_nonce: 0,
+        encrypted_schedule: [[0; 32]; mpc::DCA_SCHEDULE_CIPHERTEXTS],
+    };
+    let mut data = Subscription::DISCRIMINATOR.to_vec();
+    subscription.serialize(&mut data).unwrap();
+    let decoded = Subscription::decode(&data).unwrap();
+    assert_eq!(decoded, subscription);
+    assert!(decoded.is_due(&Pubkey::new_unique(), 1_000));
+    assert!(!decoded.is_due(&Pubkey::new_unique(), 999));
+}
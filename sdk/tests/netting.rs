@@ -0,0 +1,234 @@
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anchor_lang::prelude::{AnchorSerialize, Pubkey};
+use anchor_lang::{Discriminator, InstructionData};
+use anchor_spl::token;
+use obsidian_sdk::mpc::{self, MpcEvent};
+use obsidian_sdk::netting::{EpochFlows, NettingReport};
+use obsidian_sdk::pool;
+use obsidian_sdk::replay::LoggedTransaction;
+use privacy_pool::{WithdrawalEvent, COMMITMENT_VERSION};
+
+const EPOCH_SLOTS: u64 = 100;
+
+fn mpc_event<E: MpcEvent + AnchorSerialize>(event: E) -> (Pubkey, Vec<u8>) {
+    let mut data = EVENT_IX_TAG_LE.to_vec();
+    data.extend_from_slice(&E::DISCRIMINATOR);
+    event.serialize(&mut data).unwrap();
+    (mpc::ID, data)
+}
+
+fn pool_event<E: Discriminator + AnchorSerialize>(event: E) -> (Pubkey, Vec<u8>) {
+    let mut data = EVENT_IX_TAG_LE.to_vec();
+    data.extend_from_slice(E::DISCRIMINATOR);
+    event.serialize(&mut data).unwrap();
+    (privacy_pool::ID, data)
+}
+
+fn transaction(slot: u64, instructions: Vec<(Pubkey, Vec<u8>)>) -> LoggedTransaction {
+    LoggedTransaction {
+        signature: format!("sig{slot}"),
+        slot,
+        failed: false,
+        instructions,
+    }
+}
+
+fn deposit(slot: u64, amount: u64) -> LoggedTransaction {
+    transaction(
+        slot,
+        vec![(
+            privacy_pool::ID,
+            privacy_pool::instruction::Deposit {
+                commitment: [slot as u8; 32],
+                amount,
+                version: COMMITMENT_VERSION,
+            }
+            .data(),
+        )],
+    )
+}
+
+fn withdrawal(slot: u64, recipient: Pubkey, amount: u64, fee: u64) -> LoggedTransaction {
+    transaction(
+        slot,
+        vec![pool_event(WithdrawalEvent {
+            nullifier: [slot as u8; 32],
+            recipient,
+            amount,
+            fee,
+            reference: None,
+        })],
+    )
+}
+
+fn created(slot: u64, batch: Pubkey) -> LoggedTransaction {
+    transaction(
+        slot,
+        vec![mpc_event(mpc::BatchCreated {
+            batch,
+            market_id: "BTC-100K".into(),
+            side: 0,
+            cluster_offset: 7,
+            circuit_version: 2,
+            label: String::new(),
+            intent_hash: None,
+            sequence: 1,
+        })],
+    )
+}
+
+fn closed(slot: u64, batch: Pubkey, total_usdc: u64) -> LoggedTransaction {
+    transaction(
+        slot,
+        vec![mpc_event(mpc::BatchClosed {
+            batch,
+            total_usdc,
+            order_count: 2,
+            sequence: 1,
+        })],
+    )
+}
+
+fn executed(slot: u64, batch: Pubkey, filled_usdc: u64) -> LoggedTransaction {
+    transaction(
+        slot,
+        vec![mpc_event(mpc::ExecutionRecorded {
+            batch,
+            total_shares: 1,
+            filled_usdc,
+            tx_signature: String::new(),
+            intent_hash: None,
+            sequence: 1,
+        })],
+    )
+}
+
+fn refunded(slot: u64, batch: Pubkey, wallet: Pubkey, usdc_refund: u64) -> LoggedTransaction {
+    transaction(
+        slot,
+        vec![mpc_event(mpc::DistributionRecorded {
+            batch,
+            order_index: 0,
+            shares: 0,
+            usdc_refund,
+            wallet,
+            unlock_at: 0,
+            sequence: 1,
+        })],
+    )
+}
+
+fn escrow(batch: &Pubkey) -> Pubkey {
+    mpc::batch_escrow_address(batch, &privacy_pool::USDC_MINT, &token::ID)
+}
+
+#[test]
+fn separates_withdrawals_into_escrows_from_other_withdrawals() {
+    let batch = Pubkey::new_unique();
+    let history = [
+        deposit(1, 50_000_000),
+        created(2, batch),
+        withdrawal(3, escrow(&batch), 20_000_000, 100_000),
+        withdrawal(4, Pubkey::new_unique(), 10_000_000, 50_000),
+        closed(5, batch, 19_900_000),
+    ];
+    let report = NettingReport::build(&history, EPOCH_SLOTS);
+
+    assert_eq!(
+        report.epochs,
+        vec![EpochFlows {
+            epoch: 0,
+            pool_deposits: 50_000_000,
+            pool_withdrawals: 9_950_000,
+            pool_to_batches: 19_900_000,
+            pool_fees: 150_000,
+            batches_to_pool: 0,
+            batch_escrowed: 19_900_000,
+            batch_filled: 0,
+            batch_refunded: 0,
+        }]
+    );
+    assert!(report.breaks.is_empty());
+}
+
+#[test]
+fn refunds_to_the_pool_net_against_transfers_to_batches() {
+    let batch = Pubkey::new_unique();
+    let history = [
+        created(1, batch),
+        withdrawal(2, escrow(&batch), 10_000_000, 0),
+        closed(3, batch, 10_000_000),
+        executed(4, batch, 6_000_000),
+        refunded(5, batch, pool::pool_address(), 3_000_000),
+        refunded(6, batch, Pubkey::new_unique(), 1_000_000),
+    ];
+    let report = NettingReport::build(&history, EPOCH_SLOTS);
+
+    let totals = report.totals();
+    assert_eq!(totals.batches_to_pool, 3_000_000);
+    assert_eq!(totals.batch_refunded, 4_000_000);
+    assert_eq!(totals.net_pool_to_batches(), 7_000_000);
+    assert!(report.breaks.is_empty());
+}
+
+#[test]
+fn buckets_flows_by_epoch_and_skips_failed_transactions() {
+    let mut failed = deposit(150, 5_000_000);
+    failed.failed = true;
+    let history = [
+        deposit(10, 1_000_000),
+        deposit(99, 2_000_000),
+        failed,
+        deposit(250, 4_000_000),
+    ];
+    let report = NettingReport::build(&history, EPOCH_SLOTS);
+
+    let deposits: Vec<_> = report
+        .epochs
+        .iter()
+        .map(|e| (e.epoch, e.pool_deposits))
+        .collect();
+    assert_eq!(deposits, vec![(0, 3_000_000), (2, 4_000_000)]);
+    assert_eq!(report.totals().pool_deposits, 7_000_000);
+}
+
+#[test]
+fn flags_batches_that_do_not_conserve_usdc() {
+    let over_funded = Pubkey::new_unique();
+    let over_spent = Pubkey::new_unique();
+    let never_closed = Pubkey::new_unique();
+    let history = [
+        created(1, over_funded),
+        withdrawal(2, escrow(&over_funded), 10_000_000, 0),
+        closed(3, over_funded, 8_000_000),
+        closed(4, over_spent, 5_000_000),
+        executed(5, over_spent, 4_000_000),
+        refunded(6, over_spent, Pubkey::new_unique(), 2_000_000),
+        executed(7, never_closed, 1_000_000),
+    ];
+    let report = NettingReport::build(&history, EPOCH_SLOTS);
+
+    let mut flagged: Vec<_> = report.breaks.iter().map(|b| b.batch).collect();
+    flagged.sort();
+    let mut expected = vec![over_funded, over_spent, never_closed];
+    expected.sort();
+    assert_eq!(flagged, expected);
+
+    let json = report.to_json();
+    assert_eq!(json["conservationBreaks"].as_array().unwrap().len(), 3);
+}
+
+#[test]
+fn csv_has_a_header_and_a_row_per_epoch() {
+    let report = NettingReport::build(
+        &[deposit(5, 1_000_000), deposit(205, 2_000_000)],
+        EPOCH_SLOTS,
+    );
+    let csv = report.to_csv();
+    let lines: Vec<_> = csv.lines().collect();
+
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("epoch,pool_deposits,"));
+    assert_eq!(lines[1], "0,1000000,0,0,0,0,0,0,0,0");
+    assert_eq!(lines[2], "2,2000000,0,0,0,0,0,0,0,0");
+}
@@ -0,0 +1,135 @@
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anchor_lang::prelude::{AnchorSerialize, Pubkey};
+use anchor_lang::{Discriminator, InstructionData};
+use obsidian_sdk::note::{derive_secret, Note};
+use obsidian_sdk::pool;
+use obsidian_sdk::recovery::{recover_notes, DEFAULT_GAP_LIMIT};
+use obsidian_sdk::replay::LoggedTransaction;
+use privacy_pool::{DepositEvent, WithdrawalEvent, COMMITMENT_VERSION, NULLIFIER_PURPOSE_SPEND};
+
+const SEED: &[u8] = b"restore me from twelve words, please";
+
+fn event<E: Discriminator + AnchorSerialize>(event: E) -> Vec<u8> {
+    let mut data = EVENT_IX_TAG_LE.to_vec();
+    data.extend_from_slice(E::DISCRIMINATOR);
+    event.serialize(&mut data).unwrap();
+    data
+}
+
+/// A `deposit` of `note` landing in `leaf_index`, with its event
+fn deposit(slot: u64, note: &Note, leaf_index: u32) -> LoggedTransaction {
+    let commitment = note.commitment().unwrap();
+    LoggedTransaction {
+        signature: format!("sig{slot}"),
+        slot,
+        failed: false,
+        instructions: vec![
+            (
+                privacy_pool::ID,
+                privacy_pool::instruction::Deposit {
+                    commitment,
+                    amount: note.amount,
+                    version: COMMITMENT_VERSION,
+                }
+                .data(),
+            ),
+            (
+                privacy_pool::ID,
+                event(DepositEvent {
+                    leaf_index,
+                    commitment,
+                    timestamp: 0,
+                    version: COMMITMENT_VERSION,
+                }),
+            ),
+        ],
+    }
+}
+
+fn withdrawal(slot: u64, note: &Note, leaf_index: u32) -> LoggedTransaction {
+    let pool_id = privacy_pool::nullifier_pool_id(&pool::pool_address());
+    LoggedTransaction {
+        signature: format!("sig{slot}"),
+        slot,
+        failed: false,
+        instructions: vec![(
+            privacy_pool::ID,
+            event(WithdrawalEvent {
+                nullifier: note
+                    .nullifier(leaf_index, &pool_id, NULLIFIER_PURPOSE_SPEND)
+                    .unwrap(),
+                recipient: Pubkey::new_unique(),
+                amount: note.amount,
+                fee: 0,
+                reference: None,
+            }),
+        )],
+    }
+}
+
+#[test]
+fn derived_secrets_are_deterministic_field_elements() {
+    assert_eq!(derive_secret(SEED, 0), derive_secret(SEED, 0));
+    assert_ne!(derive_secret(SEED, 0), derive_secret(SEED, 1));
+    assert_ne!(derive_secret(SEED, 0), derive_secret(b"another seed", 0));
+    assert_eq!(derive_secret(SEED, 7)[0], 0);
+
+    let note = Note::derive(SEED, 3, 25_000_000);
+    assert_eq!(note.secret, derive_secret(SEED, 3));
+    assert_eq!(note.version, COMMITMENT_VERSION);
+}
+
+#[test]
+fn recovers_deposits_and_marks_spent_notes() {
+    let first = Note::derive(SEED, 0, 25_000_000);
+    let second = Note::derive(SEED, 1, 10_000_000);
+    // Skipped indices within the gap limit are scanned past
+    let third = Note::derive(SEED, 5, 25_000_000);
+    let stranger = Note::random(10_000_000).unwrap();
+
+    let history = [
+        deposit(1, &first, 0),
+        deposit(2, &stranger, 1),
+        deposit(3, &second, 2),
+        deposit(4, &third, 3),
+        withdrawal(5, &first, 0),
+    ];
+    let notes = recover_notes(SEED, &history, DEFAULT_GAP_LIMIT).unwrap();
+
+    let found: Vec<_> = notes
+        .iter()
+        .map(|n| (n.index, n.leaf_index, n.note.amount, n.spent))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (0, 0, 25_000_000, true),
+            (1, 2, 10_000_000, false),
+            (5, 3, 25_000_000, false),
+        ]
+    );
+    assert_eq!(notes[2].note, third);
+}
+
+#[test]
+fn scan_stops_after_the_gap_limit() {
+    let near = Note::derive(SEED, 0, 1_000_000);
+    let far = Note::derive(SEED, 4, 1_000_000);
+    let history = [deposit(1, &near, 0), deposit(2, &far, 1)];
+
+    let notes = recover_notes(SEED, &history, 3).unwrap();
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0].index, 0);
+
+    assert_eq!(recover_notes(SEED, &history, 4).unwrap().len(), 2);
+}
+
+#[test]
+fn failed_deposits_are_not_recovered() {
+    let note = Note::derive(SEED, 0, 1_000_000);
+    let mut failed = deposit(1, &note, 0);
+    failed.failed = true;
+    assert!(recover_notes(SEED, &[failed], DEFAULT_GAP_LIMIT)
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,451 @@
+use anchor_lang::event::EVENT_IX_TAG_LE;
+use anchor_lang::prelude::{AnchorSerialize, Pubkey};
+use obsidian_sdk::encryption;
+use obsidian_sdk::mpc::{self, Batch, BatchStatus, MpcAccount, MpcEvent};
+use obsidian_sdk::replay::{AccountSnapshot, LoggedTransaction, Replay};
+use obsidian_sdk::rescue::{self, Fq};
+use serde_json::{json, Value};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+fn event<E: MpcEvent + AnchorSerialize>(event: E) -> Vec<u8> {
+    let mut data = EVENT_IX_TAG_LE.to_vec();
+    data.extend_from_slice(&E::DISCRIMINATOR);
+    event.serialize(&mut data).unwrap();
+    data
+}
+
+/// A relay transaction whose instruction emitted `events` by CPI
+fn transaction(slot: u64, events: &[Vec<u8>], failed: bool) -> Value {
+    let relay = Pubkey::new_unique();
+    json!({
+        "slot": slot,
+        "transaction": {
+            "signatures": [format!("sig{slot}")],
+            "message": {
+                "accountKeys": [relay.to_string(), mpc::ID.to_string()],
+                "instructions": [{ "programIdIndex": 1, "accounts": [0], "data": "1" }],
+            },
+        },
+        "meta": {
+            "err": if failed { json!({ "InstructionError": [0, { "Custom": 6000 }] }) } else { Value::Null },
+            "innerInstructions": [{
+                "index": 0,
+                "instructions": events
+                    .iter()
+                    .map(|data| json!({ "programIdIndex": 1, "accounts": [], "data": bs58::encode(data).into_string() }))
+                    .collect::<Vec<_>>(),
+            }],
+        },
+    })
+}
+
+fn batch_account(batch: &Batch) -> Vec<u8> {
+    let mut data = Batch::DISCRIMINATOR.to_vec();
+    batch.serialize(&mut data).unwrap();
+    data
+}
+
+fn lifecycle(batch: Pubkey) -> Vec<Value> {
+    let wallet = Pubkey::new_unique();
+    vec![
+        transaction(
+            10,
+            &[event(mpc::BatchCreated {
+                batch,
+                market_id: "BTC-100K".into(),
+                side: 0,
+                cluster_offset: 7,
+                circuit_version: 2,
+                label: "momentum".into(),
+                intent_hash: None,
+                sequence: 1,
+            })],
+            false,
+        ),
+        transaction(
+            11,
+            &[event(mpc::OrderRecorded {
+                batch,
+                order_count: 1,
+                sequence: 1,
+            })],
+            false,
+        ),
+        transaction(
+            12,
+            &[event(mpc::OrderRecorded {
+                batch,
+                order_count: 2,
+                sequence: 1,
+            })],
+            false,
+        ),
+        transaction(
+            13,
+            &[event(mpc::BatchClosed {
+                batch,
+                total_usdc: 300,
+                order_count: 2,
+                sequence: 1,
+            })],
+            false,
+        ),
+        transaction(
+            14,
+            &[event(mpc::ExecutionRecorded {
+                batch,
+                total_shares: 600,
+                filled_usdc: 240,
+                tx_signature: "dflow".into(),
+                intent_hash: None,
+                sequence: 1,
+            })],
+            false,
+        ),
+        transaction(
+            15,
+            &[
+                event(mpc::DistributionsStaged {
+                    batch,
+                    root: [7; 32],
+                    count: 2,
+                    shares: 600,
+                    sequence: 1,
+                }),
+                event(mpc::DistributionRecorded {
+                    batch,
+                    order_index: 0,
+                    shares: 200,
+                    usdc_refund: 20,
+                    wallet,
+                    unlock_at: 0,
+                    sequence: 1,
+                }),
+                event(mpc::StealthDistributionRecorded {
+                    batch,
+                    order_index: 1,
+                    shares: 400,
+                    sequence: 1,
+                }),
+                event(mpc::DistributionsFinalized {
+                    batch,
+                    count: 2,
+                    sequence: 1,
+                }),
+            ],
+            false,
+        ),
+        transaction(
+            16,
+            &[event(mpc::DistributionExecuted {
+                batch,
+                order_index: 0,
+                tx_signature: "payout".into(),
+                sequence: 1,
+            })],
+            false,
+        ),
+    ]
+}
+
+fn expected_batch() -> Batch {
+    Batch {
+        authority: Pubkey::new_unique(),
+        market_id: "BTC-100K".into(),
+        side: 0,
+        status: BatchStatus::Distributing,
+        order_count: 2,
+        total_usdc: 300,
+        total_shares: 600,
+        created_at: 1_700_000_000,
+        distributions_completed: 1,
+        recorded_shares: 600,
+        cluster_offset: 7,
+        circuit_version: 2,
+        label: "momentum".into(),
+        intent_hash: None,
+        filled_usdc: 240,
+        recorded_refunds: 20,
+        distribution_root: Some([7; 32]),
+        transitioned_at: 1_700_000_100,
+        parked_usdc: 0,
+        parked_adapter: Pubkey::default(),
+        lane: 0,
+        fee_bps: 0,
+        sequence: 1,
+        staged_distributions: 2,
+        recorded_distributions: 2,
+        distributions_finalized: true,
+        closed_slot: 0,
+        executed_slot: 0,
+        staged_shares: 600,
+    }
+}
+
+fn replay(transactions: &[Value]) -> Replay {
+    let mut replay = Replay::default();
+    for transaction in transactions {
+        replay.apply(&LoggedTransaction::from_rpc_json(transaction).unwrap());
+    }
+    replay
+}
+
+#[test]
+fn replayed_batch_matches_account() {
+    let batch = Pubkey::new_unique();
+    let replay = replay(&lifecycle(batch));
+    assert!(replay.orphaned.is_empty());
+
+    let mut snapshot = AccountSnapshot::default();
+    snapshot.insert(batch, mpc::ID, batch_account(&expected_batch()));
+    assert_eq!(replay.diff(&snapshot), vec![]);
+}
+
+#[test]
+fn reports_diverging_fields_and_missing_accounts() {
+    let batch = Pubkey::new_unique();
+    let replay = replay(&lifecycle(batch));
+
+    let mut corrupted = expected_batch();
+    corrupted.recorded_shares = 800;
+    let mut snapshot = AccountSnapshot::default();
+    snapshot.insert(batch, mpc::ID, batch_account(&corrupted));
+    let divergences = replay.diff(&snapshot);
+    assert_eq!(divergences.len(), 1);
+    assert_eq!(divergences[0].field, "recorded_shares");
+    assert_eq!(divergences[0].replayed, "600");
+    assert_eq!(divergences[0].on_chain, "800");
+
+    let divergences = replay.diff(&AccountSnapshot::default());
+    assert_eq!(divergences.len(), 1);
+    assert_eq!(divergences[0].on_chain, "missing");
+}
+
+#[test]
+fn market_batches_follow_creation_order() {
+    let created = |batch: Pubkey, market_id: &str, sequence: u64| {
+        event(mpc::BatchCreated {
+            batch,
+            market_id: market_id.into(),
+            side: 0,
+            cluster_offset: 7,
+            circuit_version: 2,
+            label: String::new(),
+            intent_hash: None,
+            sequence,
+        })
+    };
+    let (first, second, other) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    // Logs delivered newest first
+    let replayed = replay(&[
+        transaction(12, &[created(second, "BTC-100K", 9)], false),
+        transaction(11, &[created(other, "ETH-5K", 8)], false),
+        transaction(10, &[created(first, "BTC-100K", 4)], false),
+    ]);
+
+    let order: Vec<_> = replayed
+        .market_batches("BTC-100K")
+        .into_iter()
+        .map(|(address, batch)| (address, batch.sequence))
+        .collect();
+    assert_eq!(order, vec![(first, 4), (second, 9)]);
+}
+
+#[test]
+fn failed_transactions_and_orphaned_events() {
+    let batch = Pubkey::new_unique();
+    let mut transactions = lifecycle(batch);
+    // A retried record_order that failed on-chain changes nothing
+    transactions.push(transaction(
+        17,
+        &[event(mpc::OrderRecorded {
+            batch,
+            order_count: 3,
+            sequence: 1,
+        })],
+        true,
+    ));
+    let replayed = replay(&transactions);
+    assert_eq!(replayed.skipped_failed, 1);
+    assert_eq!(replayed.batches[&batch].order_count, 2);
+
+    // History that starts after creation
+    let replayed = replay(&transactions[1..]);
+    assert!(replayed.batches.is_empty());
+    assert_eq!(replayed.orphaned.len(), 9);
+    assert_eq!(replayed.orphaned[0], (batch, "sig11".to_string()));
+}
+
+#[test]
+fn expired_reveals_reopen_the_batch() {
+    let batch = Pubkey::new_unique();
+    // Created, two orders, closed
+    let mut transactions = lifecycle(batch)[..4].to_vec();
+    assert_eq!(
+        replay(&transactions).batches[&batch].status,
+        BatchStatus::Closed
+    );
+
+    transactions.push(transaction(
+        200,
+        &[event(mpc::RevealExpired {
+            batch,
+            closed_slot: 13,
+            slot: 200,
+            max_reveal_exec_gap: 150,
+            sequence: 1,
+        })],
+        false,
+    ));
+    assert_eq!(
+        replay(&transactions).batches[&batch].status,
+        BatchStatus::Open
+    );
+}
+
+#[test]
+fn claimed_shares_record_and_complete_distributions() {
+    let batch = Pubkey::new_unique();
+    let user = Pubkey::new_unique();
+    // Created through executed; no relay payouts
+    let mut transactions = lifecycle(batch)[..5].to_vec();
+    transactions.push(transaction(
+        15,
+        &[event(mpc::DistributionsStaged {
+            batch,
+            root: [7; 32],
+            count: 2,
+            shares: 600,
+            sequence: 1,
+        })],
+        false,
+    ));
+    for (slot, order_index, shares, usdc_refund) in [(16, 0, 200, 20), (17, 1, 400, 0)] {
+        transactions.push(transaction(
+            slot,
+            &[event(mpc::SharesClaimed {
+                batch,
+                order_index,
+                wallet: if order_index == 0 {
+                    user
+                } else {
+                    Pubkey::new_unique()
+                },
+                shares,
+                usdc_refund,
+                unlock_at: 0,
+                sequence: 1,
+            })],
+            false,
+        ));
+    }
+    let replay = replay(&transactions);
+
+    let replayed = &replay.batches[&batch];
+    assert_eq!(replayed.status, BatchStatus::Completed);
+    assert_eq!(replayed.recorded_distributions, 2);
+    assert_eq!(replayed.distributions_completed, 2);
+    assert_eq!(replayed.recorded_shares, 600);
+    assert_eq!(replayed.recorded_refunds, 20);
+    // Claims skip finalize_distributions
+    assert!(!replayed.distributions_finalized);
+
+    let orders = replay.user_orders(&user);
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].shares, Some(200));
+    assert_eq!(orders[0].paid_out.as_deref(), Some("sig16"));
+    assert!(replay.orders[&(batch, 0)].claimed);
+}
+
+#[test]
+fn user_order_history() {
+    let batch = Pubkey::new_unique();
+    let user = Pubkey::new_unique();
+    let viewing_key = StaticSecret::from([42u8; 32]);
+    let mxe_secret = StaticSecret::from([7u8; 32]);
+    let mxe_public = PublicKey::from(&mxe_secret).to_bytes();
+
+    let mut transactions = lifecycle(batch);
+    transactions.insert(
+        1,
+        transaction(
+            11,
+            &[event(mpc::DelegatedOrderRecorded {
+                batch,
+                order_index: 2,
+                user,
+                relayer: Pubkey::new_unique(),
+                order_commitment: [3; 32],
+                sequence: 1,
+            })],
+            false,
+        ),
+    );
+    transactions.push(transaction(
+        17,
+        &[event(mpc::EncryptedDistributionRecorded {
+            batch,
+            order_index: 2,
+            wallet: user,
+            sequence: 1,
+        })],
+        false,
+    ));
+    let replay = replay(&transactions);
+
+    let orders = replay.user_orders(&user);
+    assert_eq!(orders.len(), 1);
+    let mut order = orders[0].clone();
+    assert_eq!(order.market_id, "BTC-100K");
+    assert!(order.delegated && order.encrypted);
+    assert_eq!(order.shares, None);
+    assert_eq!(order.paid_out, None);
+
+    // The MXE encrypts the shares to the key the order was placed under
+    let nonce = 99u128;
+    let ciphertext =
+        encryption::shared_cipher(&mxe_secret, PublicKey::from(&viewing_key).as_bytes())
+            .encrypt(&[Fq::from(150u64)], &nonce.to_le_bytes());
+    let distribution = mpc::EncryptedDistribution {
+        batch,
+        order_index: 2,
+        encryption_key: PublicKey::from(&viewing_key).to_bytes(),
+        nonce,
+        encrypted_shares: rescue::to_bytes(&ciphertext[0]),
+        wallet: user,
+        executed: false,
+    };
+    let mut data = mpc::EncryptedDistribution::DISCRIMINATOR.to_vec();
+    distribution.serialize(&mut data).unwrap();
+    let mut snapshot = AccountSnapshot::default();
+    snapshot.insert(
+        mpc::encrypted_distribution_address(&batch, 2),
+        mpc::ID,
+        data,
+    );
+
+    order
+        .reveal_shares(&snapshot, &viewing_key, &mxe_public)
+        .unwrap();
+    assert_eq!(order.shares, Some(150));
+    assert_eq!(order.to_json()["shares"], 150);
+
+    // Plain payouts are attributed through the distribution wallet
+    let paid = replay
+        .orders
+        .iter()
+        .find(|(key, _)| key.1 == 0)
+        .and_then(|(_, order)| order.payout.clone());
+    let Some(obsidian_sdk::replay::Payout::Plain { wallet, .. }) = paid else {
+        panic!("order 0 has no plain payout");
+    };
+    let orders = replay.user_orders(&wallet);
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].shares, Some(200));
+    assert_eq!(orders[0].usdc_refund, 20);
+    assert_eq!(orders[0].paid_out.as_deref(), Some("payout"));
+}
@@ -0,0 +1,134 @@
+use anchor_lang::prelude::Pubkey;
+use obsidian_sdk::encryption::OrderData;
+use obsidian_sdk::mpc;
+use obsidian_sdk::simulation::{
+    check_revealed, simulate_batch_total, simulate_dca_amount, simulate_dca_amounts,
+    simulate_distribution, simulate_note_balance, simulate_refund, simulate_shares,
+};
+
+fn orders(amounts: &[u64]) -> Vec<OrderData> {
+    amounts
+        .iter()
+        .map(|amount| OrderData::new(*amount, &Pubkey::new_unique()))
+        .collect()
+}
+
+#[test]
+fn shares_are_pro_rata_and_round_down() {
+    let orders = orders(&[1_000_000, 2_000_000, 3_000_000]);
+    let dists = simulate_distribution(&orders, 100, 6_000_000, 0);
+
+    let shares: Vec<u64> = dists.iter().map(|d| d.shares).collect();
+    assert_eq!(shares, vec![16, 33, 50]);
+    assert_eq!(dists[1].wallet, orders[1].wallet());
+    assert!(dists.iter().all(|d| d.usdc_refund == 0));
+}
+
+#[test]
+fn partial_fill_refunds_unfilled_usdc() {
+    let orders = orders(&[1_000_000, 2_000_000, 3_000_000]);
+    let dists = simulate_distribution(&orders, 50, 4_000_000, 0);
+
+    let refunds: Vec<u64> = dists.iter().map(|d| d.usdc_refund).collect();
+    assert_eq!(refunds, vec![333_334, 666_667, 1_000_000]);
+    assert!(refunds.iter().sum::<u64>() >= 2_000_000);
+    assert_eq!(simulate_refund(5, 0, 0), 0);
+}
+
+#[test]
+fn empty_batch_total_yields_zero_shares() {
+    assert_eq!(simulate_shares(5, 0, 1_000), 0);
+}
+
+#[test]
+fn large_values_do_not_overflow_intermediate_product() {
+    assert_eq!(simulate_shares(u64::MAX, u64::MAX, u64::MAX), u64::MAX);
+}
+
+#[test]
+fn batch_total_wraps_like_arcis_integers() {
+    assert_eq!(simulate_batch_total(&orders(&[u64::MAX, 2]), 0), (1, 2));
+    assert_eq!(simulate_batch_total(&orders(&[1; 256]), 0).1, 0);
+}
+
+#[test]
+fn expired_orders_are_left_out_and_refunded() {
+    let mut orders = orders(&[1_000_000, 2_000_000, 3_000_000]);
+    orders[1] = orders[1].expiring_at(1_700_000_000);
+
+    // Still live at its expiry second
+    assert_eq!(simulate_batch_total(&orders, 1_700_000_000), (6_000_000, 3));
+    assert_eq!(simulate_batch_total(&orders, 1_700_000_001), (4_000_000, 3));
+
+    let dists = simulate_distribution(&orders, 100, 4_000_000, 1_700_000_001);
+    let shares: Vec<u64> = dists.iter().map(|d| d.shares).collect();
+    let refunds: Vec<u64> = dists.iter().map(|d| d.usdc_refund).collect();
+    assert_eq!(shares, vec![25, 0, 75]);
+    assert_eq!(refunds, vec![0, 2_000_000, 0]);
+}
+
+#[test]
+fn only_max_batch_orders_enter_the_total() {
+    let orders = orders(&[1; mpc::MAX_BATCH_ORDERS as usize + 1]);
+    assert_eq!(
+        simulate_batch_total(&orders, 0),
+        (mpc::MAX_BATCH_ORDERS as u64, mpc::MAX_BATCH_ORDERS + 1)
+    );
+}
+
+#[test]
+fn check_revealed_rejects_mismatches() {
+    let orders = orders(&[10, 30]);
+    let dists = simulate_distribution(&orders, 8, 20, 0);
+
+    assert!(check_revealed(&dists, 1, 6, 15, &orders[1].wallet()).is_ok());
+    assert!(check_revealed(&dists, 1, 7, 15, &orders[1].wallet()).is_err());
+    assert!(check_revealed(&dists, 1, 6, 0, &orders[1].wallet()).is_err());
+    assert!(check_revealed(&dists, 1, 6, 15, &orders[0].wallet()).is_err());
+    assert!(check_revealed(&dists, 2, 0, 0, &orders[0].wallet()).is_err());
+}
+
+#[test]
+fn dca_amounts_conserve_the_total_within_the_jitter() {
+    let total = 1_000_000_007;
+    let mut random = 0x9e37_79b9_7f4a_7c15u64;
+    let draws = std::iter::from_fn(|| {
+        random ^= random << 13;
+        random ^= random >> 7;
+        random ^= random << 17;
+        Some(random)
+    });
+    let amounts = simulate_dca_amounts(total, 10, 2_000, draws);
+
+    assert_eq!(amounts.len(), 10);
+    assert_eq!(amounts.iter().sum::<u64>(), total);
+    let mut remaining = total;
+    for (epoch, amount) in amounts[..9].iter().enumerate() {
+        let base = remaining / (10 - epoch as u64);
+        assert!(amount.abs_diff(base) <= base / 5, "{amount} vs {base}");
+        remaining -= amount;
+    }
+    // Consecutive epochs don't repeat the same amount
+    assert!(amounts.windows(2).all(|pair| pair[0] != pair[1]));
+}
+
+#[test]
+fn dca_jitter_is_capped_and_off_at_zero() {
+    // Without jitter the split is even and the last epoch takes the rest
+    assert_eq!(
+        simulate_dca_amounts(100, 3, 0, [u64::MAX; 3]),
+        vec![33, 33, 34]
+    );
+    // Full jitter still leaves at least half the even split
+    assert_eq!(simulate_dca_amount(100, 2, u16::MAX, 0), 25);
+    assert_eq!(simulate_dca_amount(100, 2, u16::MAX, 50), 75);
+    assert_eq!(simulate_dca_amount(100, 0, 1_000, 7), 0);
+}
+
+#[test]
+fn note_balance_leaves_out_leaves_not_yet_inserted() {
+    let notes = [(0, 25_000_000), (4, 10_000_000), (7, 1_000_000)];
+    assert_eq!(simulate_note_balance(&notes, 7), 35_000_000);
+    assert_eq!(simulate_note_balance(&notes, 8), 36_000_000);
+    assert_eq!(simulate_note_balance(&[], 8), 0);
+}
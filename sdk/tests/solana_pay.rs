@@ -0,0 +1,128 @@
+use anchor_lang::prelude::Pubkey;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use obsidian_sdk::solana_pay::{
+    order_transaction_request, EncryptedOrder, TransactionLifetime, TransactionRequestResponse,
+};
+use obsidian_sdk::SdkError;
+use solana_hash::Hash;
+use solana_nonce::state::{DurableNonce, State};
+use solana_nonce::versions::Versions;
+use solana_transaction::Transaction;
+
+fn order() -> EncryptedOrder {
+    EncryptedOrder {
+        market_id: "BTC-100K".into(),
+        side: 1,
+        ciphertext: vec![7; 64],
+        public_key: [3; 32],
+        nonce: [5; 16],
+    }
+}
+
+fn decode(response: &TransactionRequestResponse) -> Transaction {
+    bincode::deserialize(&BASE64.decode(&response.transaction).unwrap()).unwrap()
+}
+
+fn nonce_account_data(authority: &Pubkey, nonce: &Hash) -> Vec<u8> {
+    let state = State::new_initialized(authority, DurableNonce::from_blockhash(nonce), 5_000);
+    bincode::serialize(&Versions::new(state)).unwrap()
+}
+
+#[test]
+fn blockhash_lifetime_builds_the_order_alone() {
+    let user = Pubkey::new_unique();
+    let blockhash = Hash::new_unique();
+    let response = order_transaction_request(
+        &user,
+        &Pubkey::new_unique(),
+        &order(),
+        1_000_000,
+        blockhash,
+        None,
+    )
+    .unwrap();
+
+    let tx = decode(&response);
+    assert_eq!(tx.message.recent_blockhash, blockhash);
+    assert_eq!(tx.message.instructions.len(), 2);
+    assert_eq!(tx.message.header.num_required_signatures, 1);
+}
+
+#[test]
+fn durable_nonce_lifetime_advances_the_nonce_first() {
+    let user = Pubkey::new_unique();
+    let nonce_account = Pubkey::new_unique();
+    let stored = Hash::new_unique();
+    let lifetime =
+        TransactionLifetime::from_nonce_account(nonce_account, &nonce_account_data(&user, &stored))
+            .unwrap();
+    assert_eq!(
+        lifetime,
+        TransactionLifetime::DurableNonce {
+            account: nonce_account,
+            authority: user,
+            // The stored value is the blockhash hashed with the nonce domain
+            nonce: DurableNonce::from_blockhash(&stored).as_hash().to_owned(),
+        }
+    );
+
+    let response = order_transaction_request(
+        &user,
+        &Pubkey::new_unique(),
+        &order(),
+        1_000_000,
+        lifetime,
+        None,
+    )
+    .unwrap();
+    let tx = decode(&response);
+    let TransactionLifetime::DurableNonce { nonce, .. } = lifetime else {
+        unreachable!()
+    };
+    assert_eq!(tx.message.recent_blockhash, nonce);
+    assert_eq!(tx.message.instructions.len(), 3);
+
+    let advance = &tx.message.instructions[0];
+    let keys = &tx.message.account_keys;
+    assert_eq!(
+        keys[advance.program_id_index as usize],
+        solana_system_interface::program::ID
+    );
+    assert_eq!(keys[advance.accounts[0] as usize], nonce_account);
+    // The user is both fee payer and nonce authority: one signature
+    assert_eq!(tx.message.header.num_required_signatures, 1);
+}
+
+#[test]
+fn nonce_authority_other_than_the_payer_must_sign() {
+    let user = Pubkey::new_unique();
+    let lifetime = TransactionLifetime::DurableNonce {
+        account: Pubkey::new_unique(),
+        authority: Pubkey::new_unique(),
+        nonce: Hash::new_unique(),
+    };
+    let response = order_transaction_request(
+        &user,
+        &Pubkey::new_unique(),
+        &order(),
+        1_000_000,
+        lifetime,
+        None,
+    )
+    .unwrap();
+    assert_eq!(decode(&response).message.header.num_required_signatures, 2);
+}
+
+#[test]
+fn uninitialized_nonce_accounts_are_rejected() {
+    let data = bincode::serialize(&Versions::new(State::Uninitialized)).unwrap();
+    assert!(matches!(
+        TransactionLifetime::from_nonce_account(Pubkey::new_unique(), &data),
+        Err(SdkError::InvalidAccountData)
+    ));
+    assert!(matches!(
+        TransactionLifetime::from_nonce_account(Pubkey::new_unique(), &[1, 2, 3]),
+        Err(SdkError::InvalidAccountData)
+    ));
+}
@@ -0,0 +1,56 @@
+use anchor_lang::prelude::Pubkey;
+use obsidian_sdk::encryption::OrderData;
+use obsidian_sdk::note::Note;
+use obsidian_sdk::pool::pool_address;
+use obsidian_sdk::simulation::{simulate_batch_total, simulate_distribution};
+use test_vectors::bytes32;
+
+#[test]
+fn notes_match_vectors() {
+    let vectors = test_vectors::notes();
+    assert_eq!(pool_address().to_string(), vectors.pool);
+    let pool_id = bytes32(&vectors.pool_id);
+
+    for v in vectors.notes {
+        let mut note = Note {
+            secret: bytes32(&v.secret),
+            amount: v.amount,
+            version: privacy_pool::COMMITMENT_VERSION,
+        };
+        assert_eq!(note.commitment().unwrap(), bytes32(&v.versioned_commitment));
+        note.version = privacy_pool::LEGACY_COMMITMENT_VERSION;
+        assert_eq!(note.commitment().unwrap(), bytes32(&v.commitment));
+        assert_eq!(
+            note.nullifier(v.leaf_index, &pool_id, v.purpose).unwrap(),
+            bytes32(&v.nullifier)
+        );
+        assert_eq!(
+            note.legacy_nullifier(v.leaf_index).unwrap(),
+            bytes32(&v.legacy_nullifier)
+        );
+    }
+}
+
+#[test]
+fn distribution_matches_vectors() {
+    for case in test_vectors::distribution().cases {
+        let orders: Vec<OrderData> = case
+            .order_amounts
+            .iter()
+            .map(|amount| OrderData::new(*amount, &Pubkey::new_unique()))
+            .collect();
+
+        assert_eq!(
+            simulate_batch_total(&orders, 0),
+            (case.batch_total, case.order_count),
+            "{}",
+            case.name
+        );
+        let shares: Vec<u64> =
+            simulate_distribution(&orders, case.total_shares, case.batch_total, 0)
+                .iter()
+                .map(|d| d.shares)
+                .collect();
+        assert_eq!(shares, case.shares, "{}", case.name);
+    }
+}
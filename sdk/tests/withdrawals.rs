@@ -0,0 +1,277 @@
+use anchor_lang::prelude::Pubkey;
+use obsidian_sdk::note::Note;
+use obsidian_sdk::pool;
+use obsidian_sdk::SdkError;
+use privacy_pool::{
+    SplitOutput, SplitWithdrawal, Withdrawal, AGGREGATE_PROOF_HEADER_INPUTS,
+    AGGREGATE_PROOF_INPUTS_PER_WITHDRAWAL, MAX_SPLIT_OUTPUTS, SPLIT_PROOF_PUBLIC_INPUTS,
+    SPLIT_WITHDRAWAL_CIRCUIT_ID, WITHDRAWAL_CIRCUIT_ID,
+};
+
+fn withdrawal(reference: Option<Pubkey>) -> Withdrawal {
+    Withdrawal {
+        nullifier: [7; 32],
+        legacy_nullifier: None,
+        recipient: Pubkey::new_unique(),
+        amount: 25_000_000,
+        leaf_bound: 16,
+        reference,
+    }
+}
+
+#[test]
+fn references_follow_the_recipients() {
+    let reference = Pubkey::new_unique();
+    let withdrawals = vec![withdrawal(None), withdrawal(Some(reference))];
+    let recipients: Vec<Pubkey> = withdrawals.iter().map(|w| w.recipient).collect();
+
+    let ix = pool::withdraw_aggregated_instruction(
+        &Pubkey::new_unique(),
+        &[3; 32],
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        vec![],
+        withdrawals,
+    );
+    let tail = &ix.accounts[ix.accounts.len() - 3..];
+
+    assert_eq!(tail[0].pubkey, recipients[0]);
+    assert_eq!(tail[1].pubkey, recipients[1]);
+    assert!(tail[0].is_writable && tail[1].is_writable);
+    // Read-only and unsigned, as Solana Pay references are
+    assert_eq!(tail[2].pubkey, reference);
+    assert!(!tail[2].is_writable && !tail[2].is_signer);
+}
+
+#[test]
+fn aggregate_inputs_commit_to_the_reference() {
+    let reference = Pubkey::new_unique();
+    let withdrawals = [withdrawal(None), withdrawal(Some(reference))];
+    let inputs = pool::aggregate_public_inputs([1; 32], &withdrawals);
+
+    assert_eq!(
+        inputs.len(),
+        AGGREGATE_PROOF_HEADER_INPUTS + AGGREGATE_PROOF_INPUTS_PER_WITHDRAWAL * 2
+    );
+    let last = |i: usize| {
+        inputs[AGGREGATE_PROOF_HEADER_INPUTS + AGGREGATE_PROOF_INPUTS_PER_WITHDRAWAL * (i + 1) - 1]
+    };
+    assert_eq!(last(0), [0; 32]);
+    assert_eq!(last(1), privacy_pool::recipient_field(&reference));
+}
+
+#[test]
+fn each_withdrawal_proof_commits_to_its_block_of_the_aggregate() {
+    let withdrawals = [withdrawal(None), withdrawal(Some(Pubkey::new_unique()))];
+    let aggregate = pool::aggregate_public_inputs([1; 32], &withdrawals);
+
+    let single = pool::withdrawal_public_inputs([1; 32], &withdrawals[1]);
+    assert_eq!(
+        single.len(),
+        AGGREGATE_PROOF_HEADER_INPUTS + AGGREGATE_PROOF_INPUTS_PER_WITHDRAWAL
+    );
+    assert_eq!(
+        single[..AGGREGATE_PROOF_HEADER_INPUTS],
+        aggregate[..AGGREGATE_PROOF_HEADER_INPUTS]
+    );
+    assert_eq!(
+        single[AGGREGATE_PROOF_HEADER_INPUTS..],
+        aggregate[AGGREGATE_PROOF_HEADER_INPUTS + AGGREGATE_PROOF_INPUTS_PER_WITHDRAWAL..]
+    );
+}
+
+#[test]
+fn withdraw_many_verifies_against_the_withdrawal_circuit() {
+    let verifier = Pubkey::new_unique();
+    let withdrawals = vec![withdrawal(None), withdrawal(None)];
+    let recipients: Vec<Pubkey> = withdrawals.iter().map(|w| w.recipient).collect();
+
+    let ix = pool::withdraw_many_instruction(
+        &verifier,
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        vec![vec![]; 2],
+        withdrawals,
+    );
+    let tail = &ix.accounts[ix.accounts.len() - 2..];
+    assert_eq!(tail[0].pubkey, recipients[0]);
+    assert_eq!(tail[1].pubkey, recipients[1]);
+    assert!(ix
+        .accounts
+        .iter()
+        .any(|meta| meta.pubkey
+            == pool::verifier_active_key_address(&verifier, &WITHDRAWAL_CIRCUIT_ID)));
+    // Proven, so nothing needs the relay's signature
+    assert!(ix.accounts.iter().all(|meta| !meta.is_signer));
+    assert_eq!(WITHDRAWAL_CIRCUIT_ID, zk_verifier::circuit_id("withdrawal"));
+}
+
+fn split(amounts: &[u64]) -> SplitWithdrawal {
+    SplitWithdrawal {
+        nullifier: [7; 32],
+        legacy_nullifier: [8; 32],
+        note_version: privacy_pool::COMMITMENT_VERSION,
+        leaf_bound: 16,
+        outputs: amounts
+            .iter()
+            .map(|&amount| SplitOutput {
+                recipient: Pubkey::new_unique(),
+                amount,
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn split_inputs_list_recipients_then_amounts_padded() {
+    let split = split(&[60_000_000, 40_000_000]);
+    let inputs = pool::split_public_inputs([1; 32], &split);
+
+    assert_eq!(inputs.len(), SPLIT_PROOF_PUBLIC_INPUTS);
+    assert_eq!(inputs[0], [1; 32]);
+    assert_eq!(inputs[2], [7; 32]);
+    assert_eq!(inputs[3], [8; 32]);
+    assert_eq!(inputs[4][31], privacy_pool::COMMITMENT_VERSION);
+    assert_eq!(inputs[5][31], 16);
+
+    let (recipients, amounts) = inputs[6..].split_at(MAX_SPLIT_OUTPUTS);
+    assert_eq!(
+        recipients[1],
+        privacy_pool::recipient_field(&split.outputs[1].recipient)
+    );
+    assert_eq!(recipients[2], [0; 32]);
+    assert_eq!(amounts[0][24..], 60_000_000u64.to_be_bytes());
+    assert_eq!(amounts[3], [0; 32]);
+}
+
+#[test]
+fn split_outputs_are_paid_as_withdrawals_of_one_note() {
+    let split = split(&[60_000_000, 40_000_000]);
+    let withdrawals = split.withdrawals();
+    assert_eq!(withdrawals.len(), 2);
+    assert!(withdrawals
+        .iter()
+        .all(|w| w.nullifier == [7; 32] && w.legacy_nullifier == Some([8; 32])));
+    assert_eq!(withdrawals[1].amount, 40_000_000);
+
+    let verifier = Pubkey::new_unique();
+    let ix = pool::withdraw_split_instruction(
+        &verifier,
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        vec![],
+        split.clone(),
+    );
+    let tail = &ix.accounts[ix.accounts.len() - 2..];
+    assert_eq!(tail[0].pubkey, split.outputs[0].recipient);
+    assert_eq!(tail[1].pubkey, split.outputs[1].recipient);
+    assert!(tail.iter().all(|meta| meta.is_writable));
+    assert!(ix.accounts.iter().any(|meta| meta.pubkey
+        == pool::verifier_active_key_address(&verifier, &SPLIT_WITHDRAWAL_CIRCUIT_ID)));
+    assert_eq!(
+        SPLIT_WITHDRAWAL_CIRCUIT_ID,
+        zk_verifier::circuit_id("split_withdrawal")
+    );
+}
+
+#[test]
+fn splits_must_spend_exactly_the_note() {
+    let note = Note::derive(b"seed", 0, 100_000_000);
+    let outputs = |amounts: &[u64]| split(amounts).outputs;
+
+    let spend = note
+        .split(3, 4, outputs(&[60_000_000, 40_000_000]))
+        .unwrap();
+    assert_eq!(spend.legacy_nullifier, note.legacy_nullifier(3).unwrap());
+    assert_eq!(spend.outputs.len(), 2);
+
+    for amounts in [&[60_000_000, 30_000_000][..], &[60_000_000, 50_000_000]] {
+        assert!(matches!(
+            note.split(3, 4, outputs(amounts)),
+            Err(SdkError::SplitMismatch {
+                note: 100_000_000,
+                ..
+            })
+        ));
+    }
+    // Amounts that would wrap a u64 still do not add up to the note
+    assert!(matches!(
+        note.split(3, 4, outputs(&[u64::MAX, 100_000_001])),
+        Err(SdkError::SplitMismatch { .. })
+    ));
+}
+
+#[test]
+fn note_migrations_are_signed_by_the_relay() {
+    let relay = Pubkey::new_unique();
+    let moves = vec![privacy_pool::NoteMigration {
+        nullifier: [7; 32],
+        legacy_nullifier: None,
+        amount: 25_000_000,
+        new_commitment: [9; 32],
+    }];
+    let ix = pool::migrate_notes_instruction(
+        &relay,
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        vec![vec![]],
+        moves,
+        5,
+    );
+
+    let signers: Vec<Pubkey> = ix
+        .accounts
+        .iter()
+        .filter(|meta| meta.is_signer)
+        .map(|meta| meta.pubkey)
+        .collect();
+    assert_eq!(signers, vec![relay]);
+    assert!(ix
+        .accounts
+        .iter()
+        .any(|meta| meta.pubkey == pool::relay_nonce_address(&relay) && meta.is_writable));
+    assert!(ix
+        .accounts
+        .iter()
+        .any(|meta| meta.pubkey == pool::pool_relay_address()));
+}
+
+#[test]
+fn migrations_target_the_v2_deployment() {
+    let new_program = Pubkey::new_unique();
+    let new_pool = pool::pool_address_in(&new_program);
+    let ix = pool::migrate_notes_instruction(
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &new_program,
+        vec![vec![]],
+        vec![],
+        5,
+    );
+    let has = |key: Pubkey| ix.accounts.iter().any(|meta| meta.pubkey == key);
+
+    assert!(has(new_program));
+    assert!(has(new_pool));
+    assert!(has(pool::import_address_in(&new_program)));
+    assert!(has(Pubkey::find_program_address(
+        &[b"leaf_times", new_pool.as_ref()],
+        &new_program
+    )
+    .0));
+    assert!(has(
+        anchor_spl::associated_token::get_associated_token_address(
+            &new_pool,
+            &privacy_pool::USDC_MINT
+        )
+    ));
+    // Only the v2 pool's own accounts, never this pool's, are imported into
+    assert_ne!(new_pool, pool::pool_address());
+
+    let start = pool::migrate_pool_instruction(&Pubkey::new_unique(), &new_program);
+    assert!(start
+        .accounts
+        .iter()
+        .any(|meta| meta.pubkey == pool::import_address_in(&new_program)));
+}
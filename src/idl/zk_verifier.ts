@@ -0,0 +1,163 @@
+// Generated by `cargo xtask codegen` from idls/zk_verifier.json. Do not edit.
+
+/** ZK Verifier - pluggable on-chain proof verification for the privacy pool */
+export const PROGRAM_ID = '8WvUQPQrAXckyF9aNYp3SLRsv2vzdMHTmfXpP1132Gpz';
+
+export const INSTRUCTIONS = {
+  /**
+   * Validate the uploaded key and freeze it
+   *
+   * A finalized key can no longer be written; it becomes usable once
+   * rotate_verifying_key makes it the circuit's active version.
+   */
+  finalizeVerifyingKey: [104, 73, 112, 226, 147, 60, 154, 200],
+  /**
+   * Allocate a verifying key for one circuit version
+   *
+   * The key lives in its own PDA per (circuit, version), so a new
+   * circuit or proving system is a new account rather than a redeploy
+   * of every program that verifies proofs. Keys are too large for one
+   * transaction: write them with upload_verifying_key, then finalize.
+   */
+  initializeVerifyingKey: [201, 78, 71, 55, 112, 169, 95, 20],
+  /**
+   * Make a finalized key the active version for its circuit
+   *
+   * Versions only move forward, so a retired key cannot be re-activated.
+   * Callers that verify through the active pointer pick up the new key
+   * without any change on their side.
+   */
+  rotateVerifyingKey: [44, 224, 91, 205, 221, 36, 254, 207],
+  /** Write one chunk of key data at `offset` */
+  uploadVerifyingKey: [120, 55, 86, 66, 4, 156, 95, 72],
+  /**
+   * Verify a proof against a stored verifying key
+   *
+   * Callers CPI into this instruction; it fails unless the proof is valid
+   * for `public_inputs` (32-byte big-endian field elements) under the
+   * circuit's currently active key.
+   */
+  verify: [133, 161, 141, 48, 120, 198, 88, 150],
+} as const;
+
+export const ACCOUNTS = {
+  ActiveVerifyingKey: [42, 169, 220, 60, 78, 81, 231, 121],
+  VerifyingKey: [248, 154, 12, 178, 205, 133, 193, 239],
+} as const;
+
+export const EVENTS = {
+  VerifyingKeyFinalized: [24, 27, 217, 90, 14, 22, 137, 135],
+  VerifyingKeyRotated: [193, 118, 199, 130, 111, 250, 4, 91],
+} as const;
+
+export enum ErrorCode {
+  /** Proving system not supported by this verifier */
+  UnsupportedProvingSystem = 6000,
+  /** Verifying key is malformed */
+  InvalidVerifyingKey = 6001,
+  /** Verifying key exceeds the maximum size */
+  VerifyingKeyTooLarge = 6002,
+  /** Proof is invalid */
+  InvalidProof = 6003,
+  /** Number of public inputs does not match the verifying key */
+  PublicInputCountMismatch = 6004,
+  /** Public input is not a canonical field element */
+  PublicInputOutOfRange = 6005,
+  /** Invalid curve point */
+  InvalidCurvePoint = 6006,
+  /** Verifying key is finalized and can no longer be written */
+  VerifyingKeyFinalized = 6007,
+  /** Verifying key has not been finalized */
+  VerifyingKeyNotFinalized = 6008,
+  /** Chunk extends past the end of the verifying key */
+  ChunkOutOfBounds = 6009,
+  /** Verifying key is not the circuit's active version */
+  StaleVersion = 6010,
+  /** Unauthorized */
+  Unauthorized = 6011,
+}
+
+export const ERROR_MESSAGES: Record<ErrorCode, string> = {
+  [ErrorCode.UnsupportedProvingSystem]: "Proving system not supported by this verifier",
+  [ErrorCode.InvalidVerifyingKey]: "Verifying key is malformed",
+  [ErrorCode.VerifyingKeyTooLarge]: "Verifying key exceeds the maximum size",
+  [ErrorCode.InvalidProof]: "Proof is invalid",
+  [ErrorCode.PublicInputCountMismatch]: "Number of public inputs does not match the verifying key",
+  [ErrorCode.PublicInputOutOfRange]: "Public input is not a canonical field element",
+  [ErrorCode.InvalidCurvePoint]: "Invalid curve point",
+  [ErrorCode.VerifyingKeyFinalized]: "Verifying key is finalized and can no longer be written",
+  [ErrorCode.VerifyingKeyNotFinalized]: "Verifying key has not been finalized",
+  [ErrorCode.ChunkOutOfBounds]: "Chunk extends past the end of the verifying key",
+  [ErrorCode.StaleVersion]: "Verifying key is not the circuit's active version",
+  [ErrorCode.Unauthorized]: "Unauthorized",
+};
+
+/**
+ * Allocate a verifying key for one circuit version
+ *
+ * The key lives in its own PDA per (circuit, version), so a new
+ * circuit or proving system is a new account rather than a redeploy
+ * of every program that verifies proofs. Keys are too large for one
+ * transaction: write them with upload_verifying_key, then finalize.
+ */
+export interface InitializeVerifyingKeyArgs {
+  circuitId: number[];
+  version: number;
+  provingSystem: number;
+  dataLen: number;
+}
+
+/** Write one chunk of key data at `offset` */
+export interface UploadVerifyingKeyArgs {
+  offset: number;
+  chunk: Uint8Array;
+}
+
+/**
+ * Verify a proof against a stored verifying key
+ *
+ * Callers CPI into this instruction; it fails unless the proof is valid
+ * for `public_inputs` (32-byte big-endian field elements) under the
+ * circuit's currently active key.
+ */
+export interface VerifyArgs {
+  publicInputs: number[][];
+  proof: Uint8Array;
+}
+
+/** Active verifying key for a circuit, moved forward by rotate_verifying_key */
+export interface ActiveVerifyingKey {
+  authority: string;
+  circuitId: number[];
+  verifyingKey: string;
+  version: number;
+  rotatedAt: bigint;
+}
+
+export interface VerifyingKey {
+  authority: string;
+  /** Circuit name, zero-padded (e.g. "balance_proof") */
+  circuitId: number[];
+  version: number;
+  provingSystem: number;
+  /** Set once the uploaded data has been validated; no writes after */
+  finalized: boolean;
+  /** Serialized key, layout depends on `proving_system` */
+  data: Uint8Array;
+}
+
+/** A verifying key was fully uploaded and can verify proofs. */
+export interface VerifyingKeyFinalized {
+  verifyingKey: string;
+  circuitId: number[];
+  version: number;
+  provingSystem: number;
+}
+
+/** `circuit_id` moved to a new verifying key version. */
+export interface VerifyingKeyRotated {
+  circuitId: number[];
+  verifyingKey: string;
+  previousVersion: number;
+  version: number;
+}
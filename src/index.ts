@@ -8,8 +8,10 @@ import relayRouter from './routes/relay.js';
 import walletsRouter from './routes/wallets.js';
 import marketsRouter from './routes/markets.js';
 import privacyRouter from './routes/privacy.js';
+import actionsRouter, { actionHeaders, actionsJson } from './routes/actions.js';
 import { getRelayWallet } from './services/wallet.js';
 import { startDepositMonitor } from './services/deposit-monitor.js';
+import { startProgressStream } from './services/progress-stream.js';
 import { initDatabase } from './services/database.js';
 
 const app = express();
@@ -52,6 +54,10 @@ app.use('/api/markets', marketsRouter);
 // Privacy Cash API (for unlinkable deposits)
 app.use('/api/privacy', privacyRouter);
 
+// Solana Actions API (Blinks)
+app.get('/actions.json', actionHeaders, actionsJson);
+app.use('/api/actions', actionsRouter);
+
 // Error handling
 app.use(
   (
@@ -86,4 +92,10 @@ app.listen(PORT, async () => {
   } else {
     console.log('SOLANA_RPC_URL not set - deposit monitoring disabled');
   }
+
+  // Fill progress stream for market frontends (gRPC, proto/progress.proto)
+  const progressPort = process.env.PROGRESS_GRPC_PORT;
+  if (progressPort) {
+    startProgressStream(parseInt(progressPort));
+  }
 });
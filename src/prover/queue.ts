@@ -0,0 +1,241 @@
+/**
+ * Proving queue with a pool of worker threads
+ *
+ * Jobs run first-in first-out on PROVER_WORKERS threads. Job IDs are
+ * content hashes of the circuit and inputs: submitting the same inputs
+ * again returns the existing job (or its finished proof) instead of
+ * queueing a duplicate.
+ */
+
+import { Worker } from 'worker_threads';
+import { createHash } from 'crypto';
+import { readFile } from 'fs/promises';
+import { join } from 'path';
+import type { WorkerJob, WorkerResult } from './worker.js';
+
+export type JobStatus = 'queued' | 'proving' | 'done' | 'failed';
+
+export interface ProofJob {
+  id: string;
+  circuit: string;
+  status: JobStatus;
+  proof?: Uint8Array;
+  publicInputs?: string[];
+  error?: string;
+  submittedAt: Date;
+  startedAt?: Date;
+  completedAt?: Date;
+}
+
+// Finished jobs kept for GetJob and deduplication; oldest dropped first
+const MAX_FINISHED_JOBS = 1000;
+
+// Circuit names map straight to paths under circuitsDir
+const CIRCUIT_NAME = /^[a-z0-9_]+$/;
+
+/**
+ * JSON with object keys sorted, so equal inputs hash equally
+ */
+function canonicalJson(value: unknown): string {
+  if (Array.isArray(value)) {
+    return `[${value.map(canonicalJson).join(',')}]`;
+  }
+  if (value && typeof value === 'object') {
+    const entries = Object.keys(value)
+      .sort()
+      .map(key => `${JSON.stringify(key)}:${canonicalJson((value as Record<string, unknown>)[key])}`);
+    return `{${entries.join(',')}}`;
+  }
+  return JSON.stringify(value);
+}
+
+export class ProvingQueue {
+  private jobs: Map<string, ProofJob> = new Map();
+  private inputs: Map<string, Record<string, unknown>> = new Map();
+  private pending: string[] = [];
+  private idle: Worker[] = [];
+  private running: Map<Worker, string> = new Map();
+  private waiters: Map<string, Array<(job: ProofJob) => void>> = new Map();
+  private bytecodeHashes: Map<string, string> = new Map();
+
+  constructor(
+    private circuitsDir: string,
+    workers: number,
+    private threadsPerWorker: number
+  ) {
+    for (let i = 0; i < workers; i++) {
+      this.spawn();
+    }
+    console.log(`[Prover] ${workers} worker(s), ${threadsPerWorker} thread(s) each`);
+  }
+
+  /**
+   * Start a worker; a crashed one fails its job and is replaced
+   */
+  private spawn(): void {
+    // Under tsx the worker is still TypeScript and needs the loader
+    const isTs = import.meta.url.endsWith('.ts');
+    const worker = new Worker(new URL(isTs ? './worker.ts' : './worker.js', import.meta.url), {
+      workerData: { circuitsDir: this.circuitsDir, threads: this.threadsPerWorker },
+      execArgv: isTs ? ['--import', 'tsx'] : undefined,
+    });
+    worker.on('message', (result: WorkerResult) => this.finish(worker, result));
+    worker.on('error', error => {
+      console.error('[Prover] Worker crashed:', error);
+      const id = this.running.get(worker);
+      this.running.delete(worker);
+      this.idle = this.idle.filter(w => w !== worker);
+      this.spawn();
+      if (id) {
+        this.finish(undefined, { id, error: `Worker crashed: ${error.message}` });
+      } else {
+        this.dispatch();
+      }
+    });
+    this.idle.push(worker);
+  }
+
+  /**
+   * Deterministic job ID: sha256 over circuit name, circuit bytecode and canonical inputs
+   */
+  async jobId(circuit: string, inputs: Record<string, unknown>): Promise<string> {
+    let bytecodeHash = this.bytecodeHashes.get(circuit);
+    if (!bytecodeHash) {
+      const path = join(this.circuitsDir, circuit, 'target', `${circuit}.json`);
+      const json = JSON.parse(await readFile(path, 'utf-8'));
+      bytecodeHash = createHash('sha256').update(json.bytecode).digest('hex');
+      this.bytecodeHashes.set(circuit, bytecodeHash);
+    }
+    return createHash('sha256')
+      .update(circuit)
+      .update('\0')
+      .update(bytecodeHash)
+      .update('\0')
+      .update(canonicalJson(inputs))
+      .digest('hex');
+  }
+
+  /**
+   * Queue a proof, or return the existing job for the same inputs
+   */
+  async submit(circuit: string, inputs: Record<string, unknown>): Promise<ProofJob> {
+    if (!CIRCUIT_NAME.test(circuit)) {
+      throw new Error(`Invalid circuit name: ${circuit}`);
+    }
+
+    const id = await this.jobId(circuit, inputs);
+    const existing = this.jobs.get(id);
+    // A failed job is retried on resubmission
+    if (existing && existing.status !== 'failed') {
+      return existing;
+    }
+
+    const job: ProofJob = { id, circuit, status: 'queued', submittedAt: new Date() };
+    this.jobs.delete(id);
+    this.jobs.set(id, job);
+    this.inputs.set(id, inputs);
+    this.pending.push(id);
+    this.dispatch();
+    return job;
+  }
+
+  get(id: string): ProofJob | undefined {
+    return this.jobs.get(id);
+  }
+
+  /**
+   * Resolves once the job is done or failed
+   */
+  wait(id: string): Promise<ProofJob> {
+    const job = this.jobs.get(id);
+    if (!job) {
+      return Promise.reject(new Error(`Unknown job ${id}`));
+    }
+    if (job.status === 'done' || job.status === 'failed') {
+      return Promise.resolve(job);
+    }
+    return new Promise(resolve => {
+      const list = this.waiters.get(id) ?? [];
+      list.push(resolve);
+      this.waiters.set(id, list);
+    });
+  }
+
+  /**
+   * Jobs ahead of `id` in the queue (0 once it is proving)
+   */
+  position(id: string): number {
+    const index = this.pending.indexOf(id);
+    return index < 0 ? 0 : index;
+  }
+
+  stats() {
+    return {
+      queued: this.pending.length,
+      proving: this.running.size,
+      idleWorkers: this.idle.length,
+      jobs: this.jobs.size,
+    };
+  }
+
+  private dispatch(): void {
+    while (this.idle.length > 0 && this.pending.length > 0) {
+      const id = this.pending.shift()!;
+      const job = this.jobs.get(id)!;
+      const worker = this.idle.pop()!;
+
+      job.status = 'proving';
+      job.startedAt = new Date();
+      this.running.set(worker, id);
+
+      const message: WorkerJob = { id, circuit: job.circuit, inputs: this.inputs.get(id)! };
+      worker.postMessage(message);
+    }
+  }
+
+  private finish(worker: Worker | undefined, result: WorkerResult): void {
+    const job = this.jobs.get(result.id);
+    if (worker) {
+      this.running.delete(worker);
+      this.idle.push(worker);
+    }
+    this.inputs.delete(result.id);
+
+    if (job) {
+      job.completedAt = new Date();
+      if (result.error) {
+        job.status = 'failed';
+        job.error = result.error;
+        console.error(`[Prover] Job ${job.id.slice(0, 12)} failed: ${result.error}`);
+      } else {
+        job.status = 'done';
+        job.proof = result.proof;
+        job.publicInputs = result.publicInputs;
+        const seconds = (job.completedAt.getTime() - job.startedAt!.getTime()) / 1000;
+        console.log(`[Prover] Job ${job.id.slice(0, 12)} (${job.circuit}) proved in ${seconds.toFixed(1)}s`);
+      }
+      for (const resolve of this.waiters.get(job.id) ?? []) {
+        resolve(job);
+      }
+      this.waiters.delete(job.id);
+    }
+
+    this.evictFinished();
+    this.dispatch();
+  }
+
+  private evictFinished(): void {
+    let finished = 0;
+    for (const job of this.jobs.values()) {
+      if (job.status === 'done' || job.status === 'failed') finished++;
+    }
+    // Map iteration is insertion order, so this drops the oldest first
+    for (const [id, job] of this.jobs) {
+      if (finished <= MAX_FINISHED_JOBS) break;
+      if (job.status === 'done' || job.status === 'failed') {
+        this.jobs.delete(id);
+        finished--;
+      }
+    }
+  }
+}
@@ -0,0 +1,136 @@
+/**
+ * Obsidian Prover Service
+ *
+ * Standalone gRPC service (proto/prover.proto) that proves Noir circuits
+ * for clients too slow to prove locally, e.g. browsers on depth-20 Merkle
+ * membership circuits. Witness inputs are Noir input maps; proofs are
+ * UltraHonk, identical to what bb.js produces in the browser.
+ *
+ * Proving is parallel across PROVER_WORKERS worker threads, each with
+ * PROVER_THREADS bb.js threads. bb.js proves on the CPU only; scale with
+ * more cores or replicas behind a load balancer.
+ *
+ * Environment:
+ * - PROVER_PORT: gRPC listen port (default 50051)
+ * - PROVER_WORKERS: parallel proofs (default: half the CPU cores)
+ * - PROVER_THREADS: bb.js threads per proof (default 2)
+ * - PROVER_CIRCUITS_DIR: compiled circuits (default ./circuits)
+ *
+ * Start with `npm run prover`.
+ */
+
+import 'dotenv/config';
+import * as grpc from '@grpc/grpc-js';
+import * as protoLoader from '@grpc/proto-loader';
+import { availableParallelism } from 'os';
+import { dirname, join, resolve } from 'path';
+import { fileURLToPath } from 'url';
+import { ProvingQueue, type ProofJob } from './queue.js';
+
+const __dirname = dirname(fileURLToPath(import.meta.url));
+
+const PORT = parseInt(process.env.PROVER_PORT || '50051');
+const WORKERS = parseInt(process.env.PROVER_WORKERS || String(Math.max(1, Math.floor(availableParallelism() / 2))));
+const THREADS = parseInt(process.env.PROVER_THREADS || '2');
+const CIRCUITS_DIR = resolve(process.env.PROVER_CIRCUITS_DIR || join(__dirname, '../../circuits'));
+
+// Both src/prover and dist/prover sit two levels below the repo root
+const PROTO_PATH = join(__dirname, '../../proto/prover.proto');
+
+const queue = new ProvingQueue(CIRCUITS_DIR, WORKERS, THREADS);
+
+function toMessage(job: ProofJob) {
+  return {
+    id: job.id,
+    status: job.status,
+    proof: job.proof ? Buffer.from(job.proof) : Buffer.alloc(0),
+    public_inputs: job.publicInputs ?? [],
+    error: job.error ?? '',
+    queue_position: queue.position(job.id),
+  };
+}
+
+/**
+ * Parse and queue a ProveRequest; rejects with INVALID_ARGUMENT on bad input
+ */
+async function submit(request: { circuit: string; inputs_json: string }): Promise<ProofJob> {
+  let inputs: unknown;
+  try {
+    inputs = JSON.parse(request.inputs_json);
+  } catch {
+    throw Object.assign(new Error('inputs_json is not valid JSON'), { code: grpc.status.INVALID_ARGUMENT });
+  }
+  if (!inputs || typeof inputs !== 'object' || Array.isArray(inputs)) {
+    throw Object.assign(new Error('inputs_json must be a JSON object'), { code: grpc.status.INVALID_ARGUMENT });
+  }
+  try {
+    return await queue.submit(request.circuit, inputs as Record<string, unknown>);
+  } catch (error) {
+    // Unknown or malformed circuit name
+    throw Object.assign(error instanceof Error ? error : new Error(String(error)), {
+      code: grpc.status.INVALID_ARGUMENT,
+    });
+  }
+}
+
+function fail(callback: grpc.sendUnaryData<unknown>, error: unknown): void {
+  const code = (error as { code?: grpc.status }).code ?? grpc.status.INTERNAL;
+  callback({ code, details: error instanceof Error ? error.message : 'Unknown error' }, null);
+}
+
+/* eslint-disable @typescript-eslint/no-explicit-any */
+const handlers: grpc.UntypedServiceImplementation = {
+  Submit: async (call: grpc.ServerUnaryCall<any, any>, callback: grpc.sendUnaryData<any>) => {
+    try {
+      callback(null, toMessage(await submit(call.request)));
+    } catch (error) {
+      fail(callback, error);
+    }
+  },
+
+  GetJob: (call: grpc.ServerUnaryCall<any, any>, callback: grpc.sendUnaryData<any>) => {
+    const job = queue.get(call.request.id);
+    if (!job) {
+      callback({ code: grpc.status.NOT_FOUND, details: `Unknown job ${call.request.id}` }, null);
+      return;
+    }
+    callback(null, toMessage(job));
+  },
+
+  Prove: async (call: grpc.ServerUnaryCall<any, any>, callback: grpc.sendUnaryData<any>) => {
+    try {
+      const job = await submit(call.request);
+      callback(null, toMessage(await queue.wait(job.id)));
+    } catch (error) {
+      fail(callback, error);
+    }
+  },
+};
+/* eslint-enable @typescript-eslint/no-explicit-any */
+
+const definition = protoLoader.loadSync(PROTO_PATH, {
+  keepCase: true,
+  longs: Number,
+  defaults: true,
+});
+// eslint-disable-next-line @typescript-eslint/no-explicit-any
+const proto = grpc.loadPackageDefinition(definition) as any;
+
+const server = new grpc.Server();
+server.addService(proto.obsidian.prover.v1.Prover.service, handlers);
+server.bindAsync(`0.0.0.0:${PORT}`, grpc.ServerCredentials.createInsecure(), (error, port) => {
+  if (error) {
+    console.error('[Prover] Failed to bind:', error);
+    process.exit(1);
+  }
+  console.log(`[Prover] Obsidian prover listening on port ${port}`);
+  console.log(`[Prover] Circuits: ${CIRCUITS_DIR}`);
+});
+
+// Queue depth for operators
+setInterval(() => {
+  const stats = queue.stats();
+  if (stats.queued > 0 || stats.proving > 0) {
+    console.log(`[Prover] ${stats.proving} proving, ${stats.queued} queued`);
+  }
+}, 30_000).unref();
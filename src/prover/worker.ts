@@ -0,0 +1,63 @@
+/**
+ * Proving worker (one per worker thread)
+ *
+ * Loads each circuit and its UltraHonk backend on first use and keeps
+ * them for the life of the thread, so repeated jobs for the same circuit
+ * skip bytecode parsing and backend setup.
+ */
+
+import { parentPort, workerData } from 'worker_threads';
+import { Noir } from '@noir-lang/noir_js';
+import { UltraHonkBackend } from '@aztec/bb.js';
+import { readFile } from 'fs/promises';
+import { join } from 'path';
+import { getWasmPath } from '../services/prover.js';
+
+export interface WorkerJob {
+  id: string;
+  circuit: string;
+  inputs: Record<string, unknown>;
+}
+
+export interface WorkerResult {
+  id: string;
+  proof?: Uint8Array;
+  publicInputs?: string[];
+  error?: string;
+}
+
+const { circuitsDir, threads } = workerData as { circuitsDir: string; threads: number };
+
+const loaded: Map<string, Promise<{ noir: Noir; backend: UltraHonkBackend }>> = new Map();
+
+function loadCircuit(name: string): Promise<{ noir: Noir; backend: UltraHonkBackend }> {
+  let circuit = loaded.get(name);
+  if (!circuit) {
+    circuit = (async () => {
+      const path = join(circuitsDir, name, 'target', `${name}.json`);
+      const json = JSON.parse(await readFile(path, 'utf-8'));
+      return {
+        noir: new Noir(json),
+        backend: new UltraHonkBackend(json.bytecode, { threads, wasmPath: getWasmPath() }),
+      };
+    })();
+    // Don't cache a failed load; the next job retries it
+    circuit.catch(() => loaded.delete(name));
+    loaded.set(name, circuit);
+  }
+  return circuit;
+}
+
+parentPort!.on('message', async (job: WorkerJob) => {
+  let result: WorkerResult;
+  try {
+    const { noir, backend } = await loadCircuit(job.circuit);
+    // eslint-disable-next-line @typescript-eslint/no-explicit-any
+    const { witness } = await noir.execute(job.inputs as any);
+    const proof = await backend.generateProof(witness);
+    result = { id: job.id, proof: proof.proof, publicInputs: proof.publicInputs };
+  } catch (error) {
+    result = { id: job.id, error: error instanceof Error ? error.message : 'Unknown proving error' };
+  }
+  parentPort!.postMessage(result);
+});
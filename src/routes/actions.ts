@@ -0,0 +1,435 @@
+/**
+ * Solana Actions (Blinks) API
+ *
+ * Lets any Blink-enabled surface (wallets, X, dial.to) deposit into the
+ * privacy pool or submit an order without the frontend:
+ * - GET returns the action's metadata and the inputs it takes
+ * - POST with the user's { account } returns an unsigned transaction for
+ *   the wallet to sign and send
+ *
+ * Orders are encrypted either client-side, by passing the encrypted blob
+ * (the relay stays blind, as with /relay/order/encrypted), or server-side,
+ * by passing only the amount (the relay records and commits to the order,
+ * as with /relay/order, and so sees the amount).
+ *
+ * Spec: https://solana.com/docs/advanced/actions
+ */
+
+import { Router } from 'express';
+import type { Request, Response, NextFunction } from 'express';
+import {
+  Connection,
+  PublicKey,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  Transaction,
+  TransactionInstruction,
+} from '@solana/web3.js';
+import {
+  TOKEN_PROGRAM_ID,
+  createTransferCheckedInstruction,
+  getAssociatedTokenAddressSync,
+} from '@solana/spl-token';
+import { submitOrder } from '../services/batch.js';
+import { getMcpWalletAddress } from '../services/dflow.js';
+import { getRelayWallet } from '../services/wallet.js';
+import { INSTRUCTIONS, PROGRAM_ID as PRIVACY_POOL_PROGRAM_ID } from '../idl/privacy_pool.js';
+
+const router = Router();
+
+// Solana Memo Program ID
+const MEMO_PROGRAM_ID = new PublicKey('MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr');
+
+// USDC mint orders are paid in (mainnet, as watched by the deposit monitor)
+const ORDER_USDC_MINT = new PublicKey('EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v');
+
+// USDC mint the privacy pool accepts (Circle devnet USDC, privacy_pool::USDC_MINT)
+const POOL_USDC_MINT = new PublicKey(
+  process.env.POOL_USDC_MINT || '4zMMC9srt5Ri5X14GAgXhaHii3GnPAEERYPJgZJDncDU'
+);
+
+const POOL_PROGRAM = new PublicKey(process.env.PRIVACY_POOL_PROGRAM_ID || PRIVACY_POOL_PROGRAM_ID);
+
+const USDC_DECIMALS = 6;
+
+// privacy_pool::COMMITMENT_VERSION
+const COMMITMENT_VERSION = 1;
+
+// Memo prefix the deposit monitor recognises encrypted orders by
+const ENCRYPTED_ORDER_MEMO_PREFIX = 'OBSIDIAN_ENC';
+
+// CAIP-2 ids of the chains the actions build transactions for
+const SOLANA_MAINNET = 'solana:5eykt4UsFv8P8NJdTREpY1vzqKqZKvdp';
+const SOLANA_DEVNET = 'solana:EtWTRABZaYq6iMfeYKouRu166VU2xqa1';
+
+const ACTION_VERSION = '2.4';
+
+const ICON_URL = process.env.ACTIONS_ICON_URL || 'https://quantish.live/logo.svg';
+
+const mainnet = new Connection(
+  process.env.SOLANA_RPC_URL || 'https://api.mainnet-beta.solana.com',
+  'confirmed'
+);
+const devnet = new Connection(
+  process.env.SOLANA_RPC_URL_DEVNET || 'https://api.devnet.solana.com',
+  'confirmed'
+);
+
+/**
+ * Headers every Actions response must carry so Blink clients on any
+ * origin can call the endpoints
+ */
+export function actionHeaders(_req: Request, res: Response, next: NextFunction): void {
+  res.set({
+    'Access-Control-Allow-Origin': '*',
+    'Access-Control-Allow-Methods': 'GET,POST,PUT,OPTIONS',
+    'Access-Control-Allow-Headers':
+      'Content-Type, Authorization, Content-Encoding, Accept-Encoding, X-Action-Version, X-Blockchain-Ids',
+    'Access-Control-Expose-Headers': 'X-Action-Version, X-Blockchain-Ids',
+    'X-Action-Version': ACTION_VERSION,
+  });
+  next();
+}
+
+/**
+ * GET /actions.json
+ * Maps this host's action URLs to the API, for Blink clients that unfurl
+ * links to the relay
+ */
+export function actionsJson(_req: Request, res: Response): void {
+  res.json({
+    rules: [
+      { pathPattern: '/api/actions/**', apiPath: '/api/actions/**' },
+    ],
+  });
+}
+
+router.use(actionHeaders);
+
+router.options('*', (_req: Request, res: Response) => {
+  res.sendStatus(204);
+});
+
+/** Actions error body: a message the client shows the user */
+function actionError(res: Response, status: number, message: string): void {
+  res.status(status).json({ message });
+}
+
+/** Decimal USDC ("12.5") to atomic units; null unless positive with at most 6 decimals */
+function parseUsdc(value: unknown): bigint | null {
+  if (typeof value !== 'string' || !/^\d+(\.\d{1,6})?$/.test(value)) return null;
+  const [whole, fraction = ''] = value.split('.');
+  const atomic = BigInt(whole) * 1_000_000n + BigInt(fraction.padEnd(USDC_DECIMALS, '0'));
+  return atomic > 0n ? atomic : null;
+}
+
+function parseAccount(value: unknown): PublicKey | null {
+  try {
+    return typeof value === 'string' ? new PublicKey(value) : null;
+  } catch {
+    return null;
+  }
+}
+
+/** A form input: from the query string, or the POST body's data */
+function input(req: Request, name: string): string | undefined {
+  const value = req.query[name] ?? req.body?.data?.[name];
+  return typeof value === 'string' && value.length > 0 ? value : undefined;
+}
+
+/**
+ * Client-side encrypted order blob: base64 JSON of the encryptedData of an
+ * EncryptedOrderSubmission ({ ciphertext, publicKey, nonce }, each base64)
+ */
+function parseEncryptedBlob(
+  blob: string
+): { ciphertext: string; publicKey: string; nonce: string } | null {
+  try {
+    const data = JSON.parse(Buffer.from(blob, 'base64').toString('utf-8'));
+    const fields = [data.ciphertext, data.publicKey, data.nonce];
+    if (!fields.every(f => typeof f === 'string' && f.length > 0 && !f.includes('|'))) {
+      return null;
+    }
+    return { ciphertext: data.ciphertext, publicKey: data.publicKey, nonce: data.nonce };
+  } catch {
+    return null;
+  }
+}
+
+function memoInstruction(signer: PublicKey, memo: string): TransactionInstruction {
+  return new TransactionInstruction({
+    programId: MEMO_PROGRAM_ID,
+    keys: [{ pubkey: signer, isSigner: true, isWritable: false }],
+    data: Buffer.from(memo, 'utf-8'),
+  });
+}
+
+/** Unsigned transaction paid by `account`, base64 for the Actions response */
+async function serialize(
+  connection: Connection,
+  account: PublicKey,
+  instructions: TransactionInstruction[]
+): Promise<string> {
+  const { blockhash, lastValidBlockHeight } = await connection.getLatestBlockhash();
+  const tx = new Transaction({ feePayer: account, blockhash, lastValidBlockHeight });
+  tx.add(...instructions);
+  return tx.serialize({ requireAllSignatures: false, verifySignatures: false }).toString('base64');
+}
+
+/**
+ * privacy_pool `deposit` without a receipt. Accounts in the order of the
+ * program's Deposit context; absent optional accounts are the program id.
+ */
+function poolDepositInstruction(
+  user: PublicKey,
+  commitment: Buffer,
+  amount: bigint
+): TransactionInstruction {
+  const pda = (...seeds: Buffer[]) => PublicKey.findProgramAddressSync(seeds, POOL_PROGRAM)[0];
+  const pool = pda(Buffer.from('privacy_pool'));
+  const none = { pubkey: POOL_PROGRAM, isSigner: false, isWritable: false };
+
+  const data = Buffer.alloc(8 + 32 + 8 + 1);
+  Buffer.from(INSTRUCTIONS.deposit).copy(data, 0);
+  commitment.copy(data, 8);
+  data.writeBigUInt64LE(amount, 40);
+  data.writeUInt8(COMMITMENT_VERSION, 48);
+
+  return new TransactionInstruction({
+    programId: POOL_PROGRAM,
+    keys: [
+      { pubkey: pool, isSigner: false, isWritable: true },
+      { pubkey: user, isSigner: true, isWritable: true },
+      { pubkey: getAssociatedTokenAddressSync(POOL_USDC_MINT, user), isSigner: false, isWritable: true },
+      { pubkey: getAssociatedTokenAddressSync(POOL_USDC_MINT, pool, true), isSigner: false, isWritable: true },
+      { pubkey: POOL_USDC_MINT, isSigner: false, isWritable: false },
+      { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+      { pubkey: pda(Buffer.from('limits'), pool.toBuffer()), isSigner: false, isWritable: false },
+      { pubkey: pda(Buffer.from('leaf_times'), pool.toBuffer()), isSigner: false, isWritable: true },
+      { pubkey: pda(Buffer.from('stats'), pool.toBuffer()), isSigner: false, isWritable: true },
+      { pubkey: pda(Buffer.from('migration'), pool.toBuffer()), isSigner: false, isWritable: false },
+      { pubkey: pda(Buffer.from('cpi_guard'), pool.toBuffer()), isSigner: false, isWritable: false },
+      { pubkey: SYSVAR_INSTRUCTIONS_PUBKEY, isSigner: false, isWritable: false },
+      // No receipt: receipt_mint, receipt_account, token_2022_program,
+      // associated_token_program, system_program
+      none,
+      none,
+      none,
+      none,
+      none,
+      { pubkey: pda(Buffer.from('__event_authority')), isSigner: false, isWritable: false },
+      { pubkey: POOL_PROGRAM, isSigner: false, isWritable: false },
+    ],
+    data,
+  });
+}
+
+/** Wallet order deposits go to: the MCP trading wallet, else the relay wallet */
+async function orderDepositAddress(): Promise<PublicKey> {
+  const mcpAddress = await getMcpWalletAddress();
+  if (mcpAddress) return new PublicKey(mcpAddress);
+  const wallet = await getRelayWallet();
+  return new PublicKey(wallet.getAddress());
+}
+
+/**
+ * GET /api/actions/deposit
+ * Deposit USDC into the privacy pool under a note commitment the user
+ * computed client-side (e.g. createNote in the SDK's wasm build); the
+ * relay never sees the note.
+ */
+router.get('/deposit', (_req: Request, res: Response) => {
+  res.set('X-Blockchain-Ids', SOLANA_DEVNET);
+  res.json({
+    type: 'action',
+    icon: ICON_URL,
+    title: 'Obsidian privacy pool deposit',
+    description:
+      'Deposit USDC into the Obsidian privacy pool. Withdrawals are unlinkable to this deposit. Keep the note your commitment came from: it is the only way to spend it.',
+    label: 'Deposit',
+    links: {
+      actions: [
+        {
+          type: 'transaction',
+          label: 'Deposit',
+          href: '/api/actions/deposit?amount={amount}&commitment={commitment}',
+          parameters: [
+            { type: 'number', name: 'amount', label: 'USDC amount', required: true, min: 0.000001 },
+            {
+              type: 'text',
+              name: 'commitment',
+              label: 'Note commitment (hex)',
+              required: true,
+              pattern: '^[0-9a-fA-F]{64}$',
+              patternDescription: '32-byte note commitment, hex encoded',
+            },
+          ],
+        },
+      ],
+    },
+  });
+});
+
+/**
+ * POST /api/actions/deposit?amount=<usdc>&commitment=<hex>
+ * Body: { account }
+ */
+router.post('/deposit', async (req: Request, res: Response) => {
+  try {
+    const account = parseAccount(req.body?.account);
+    if (!account) {
+      actionError(res, 400, 'Invalid account');
+      return;
+    }
+    const amount = parseUsdc(input(req, 'amount'));
+    if (amount === null) {
+      actionError(res, 400, 'amount must be a positive USDC amount with at most 6 decimals');
+      return;
+    }
+    const commitment = input(req, 'commitment');
+    if (!commitment || !/^[0-9a-fA-F]{64}$/.test(commitment)) {
+      actionError(res, 400, 'commitment must be 32 bytes, hex encoded');
+      return;
+    }
+
+    const ix = poolDepositInstruction(account, Buffer.from(commitment, 'hex'), amount);
+    res.set('X-Blockchain-Ids', SOLANA_DEVNET);
+    res.json({
+      type: 'transaction',
+      transaction: await serialize(devnet, account, [ix]),
+      message: 'Deposit submitted to the privacy pool. Keep your note safe.',
+    });
+  } catch (error) {
+    actionError(res, 500, error instanceof Error ? error.message : 'Failed to build deposit');
+  }
+});
+
+/**
+ * GET /api/actions/order?marketId=<id>&side=YES|NO
+ * Buy one side of a market through the relay's next batch
+ */
+router.get('/order', (req: Request, res: Response) => {
+  const marketId = input(req, 'marketId');
+  const side = input(req, 'side');
+  if (!marketId || (side !== 'YES' && side !== 'NO')) {
+    actionError(res, 400, 'marketId and side (YES or NO) are required');
+    return;
+  }
+
+  const base = `/api/actions/order?marketId=${encodeURIComponent(marketId)}&side=${side}`;
+  res.set('X-Blockchain-Ids', SOLANA_MAINNET);
+  res.json({
+    type: 'action',
+    icon: ICON_URL,
+    title: `Buy ${side} on ${marketId}`,
+    description:
+      'Order through an Obsidian batch: it executes together with other orders, and the shares are sent to your wallet. Pass an encrypted order to keep the amount hidden from the relay.',
+    label: `Buy ${side}`,
+    links: {
+      actions: [
+        {
+          type: 'transaction',
+          label: `Buy ${side}`,
+          href: `${base}&amount={amount}`,
+          parameters: [
+            { type: 'number', name: 'amount', label: 'USDC amount', required: true, min: 0.000001 },
+          ],
+        },
+        {
+          type: 'transaction',
+          label: `Buy ${side} (encrypted)`,
+          href: `${base}&amount={amount}&encrypted={encrypted}`,
+          parameters: [
+            { type: 'number', name: 'amount', label: 'USDC amount', required: true, min: 0.000001 },
+            {
+              type: 'text',
+              name: 'encrypted',
+              label: 'Encrypted order',
+              required: true,
+              patternDescription: 'base64 JSON { ciphertext, publicKey, nonce } from the Obsidian SDK',
+            },
+          ],
+        },
+      ],
+    },
+  });
+});
+
+/**
+ * POST /api/actions/order?marketId=<id>&side=YES|NO&amount=<usdc>[&encrypted=<blob>]
+ * Body: { account }
+ *
+ * With `encrypted`, the memo carries the encrypted order and the deposit
+ * monitor submits it to the MPC when the transfer lands. Without it, the
+ * relay records the order now, with the shares going to `account`.
+ */
+router.post('/order', async (req: Request, res: Response) => {
+  try {
+    const account = parseAccount(req.body?.account);
+    if (!account) {
+      actionError(res, 400, 'Invalid account');
+      return;
+    }
+    const marketId = input(req, 'marketId');
+    const side = input(req, 'side');
+    if (!marketId || marketId.includes('|') || (side !== 'YES' && side !== 'NO')) {
+      actionError(res, 400, 'marketId and side (YES or NO) are required');
+      return;
+    }
+    const amountInput = input(req, 'amount');
+    const amount = parseUsdc(amountInput);
+    if (amount === null) {
+      actionError(res, 400, 'amount must be a positive USDC amount with at most 6 decimals');
+      return;
+    }
+
+    let memo: string;
+    let message: string;
+    const blob = input(req, 'encrypted');
+    if (blob) {
+      const encrypted = parseEncryptedBlob(blob);
+      if (!encrypted) {
+        actionError(res, 400, 'encrypted must be base64 JSON with ciphertext, publicKey and nonce');
+        return;
+      }
+      memo = [
+        ENCRYPTED_ORDER_MEMO_PREFIX,
+        marketId,
+        side === 'YES' ? '1' : '0',
+        encrypted.ciphertext,
+        encrypted.publicKey,
+        encrypted.nonce,
+      ].join('|');
+      message = 'Encrypted order submitted. The relay cannot see its amount.';
+    } else {
+      const order = await submitOrder({
+        marketId,
+        side,
+        usdcAmount: amountInput!,
+        distribution: [{ wallet: account.toBase58(), percentage: 10000 }],
+      });
+      memo = order.id;
+      message = `Order ${order.id} submitted to batch ${order.batchId}.`;
+    }
+
+    const depositAddress = await orderDepositAddress();
+    const transfer = createTransferCheckedInstruction(
+      getAssociatedTokenAddressSync(ORDER_USDC_MINT, account),
+      ORDER_USDC_MINT,
+      getAssociatedTokenAddressSync(ORDER_USDC_MINT, depositAddress, true),
+      account,
+      amount,
+      USDC_DECIMALS
+    );
+
+    res.set('X-Blockchain-Ids', SOLANA_MAINNET);
+    res.json({
+      type: 'transaction',
+      transaction: await serialize(mainnet, account, [transfer, memoInstruction(account, memo)]),
+      message,
+    });
+  } catch (error) {
+    actionError(res, 500, error instanceof Error ? error.message : 'Failed to build order');
+  }
+});
+
+export default router;
@@ -9,6 +9,7 @@ import {
   getBatchOrders,
   markBatchReady,
   executeBatch,
+  executeBatchInBundle,
   getAllBatches,
   getAllOrders,
   getReadyBatches,
@@ -20,8 +21,12 @@ import {
   refundUnmatchedDeposit,
 } from '../services/deposit-monitor.js';
 import { getRelayWallet, isWalletInitialized } from '../services/wallet.js';
-import { executeDFlowTrade, getMarketInfo, estimateShares, getMcpWalletAddress, distributeTokensViaMcp } from '../services/dflow.js';
+import { executeDFlowTrade, executeDFlowTradeBundled, getMarketInfo, estimateShares, getMcpWalletAddress, distributeTokensViaMcp } from '../services/dflow.js';
 import { isMpcEnabled, getArciumMpcService } from '../services/arcium-mpc.js';
+import { getWebhookStatus } from '../services/webhooks.js';
+import { getSenderStatus } from '../services/sender.js';
+import { getLookupTableStatus } from '../services/lookup-tables.js';
+import { getJitoStatus, jitoEnabled } from '../services/jito.js';
 import type { OrderSubmission, EncryptedOrderSubmission } from '../types/relay.js';
 import { DEFAULT_RELAY_CONFIG } from '../types/relay.js';
 
@@ -418,6 +423,32 @@ router.get('/mpc/status', (_req: Request, res: Response) => {
   });
 });
 
+/**
+ * GET /relay/webhooks
+ * Configured webhook endpoints and delivery counters
+ * Events: BatchCreated, BatchClosed, BatchExecuted, BatchCompleted, BatchFailed, DistributionExecuted
+ */
+router.get('/webhooks', (_req: Request, res: Response) => {
+  res.json({
+    success: true,
+    ...getWebhookStatus(),
+  });
+});
+
+/**
+ * GET /relay/sender
+ * Transaction sender settings and landing counters, lookup table and
+ * Jito bundle counters
+ */
+router.get('/sender', (_req: Request, res: Response) => {
+  res.json({
+    success: true,
+    ...getSenderStatus(),
+    lookupTables: getLookupTableStatus(),
+    jito: getJitoStatus(),
+  });
+});
+
 /**
  * GET /relay/order/:orderId
  * Get order status
@@ -493,8 +524,9 @@ router.post('/batch/:batchId/execute', async (req: Request, res: Response) => {
     console.log(`[MPC] Executing encrypted batch ${batchId} via MPC...`);
 
     const result = await executeEncryptedBatch(batchId, async (b, totalUsdc) => {
-      // DFlow executor receives the MPC-revealed total
-      return executeDFlowTrade(b);
+      // DFlow executor receives the MPC-revealed total; with Jito on, the
+      // trade is bundled so the revealed total can't be traded against
+      return jitoEnabled() ? executeDFlowTradeBundled(b, totalUsdc) : executeDFlowTrade(b);
     });
 
     if (!result.success) {
@@ -520,8 +552,10 @@ router.post('/batch/:batchId/execute', async (req: Request, res: Response) => {
     return;
   }
 
-  // Regular (non-encrypted) execution
-  const result = await executeBatch(batchId, executeDFlowTrade);
+  // Regular (non-encrypted) execution, as one bundle when Jito is on
+  const result = jitoEnabled()
+    ? await executeBatchInBundle(batchId)
+    : await executeBatch(batchId, executeDFlowTrade);
 
   if (!result.success) {
     res.status(500).json({
@@ -584,7 +618,9 @@ router.post('/execute-ready', async (_req: Request, res: Response) => {
   const results = [];
   for (const batch of ready) {
     markBatchReady(batch.id);
-    const result = await executeBatch(batch.id, executeDFlowTrade);
+    const result = jitoEnabled()
+      ? await executeBatchInBundle(batch.id)
+      : await executeBatch(batch.id, executeDFlowTrade);
     results.push({
       batchId: batch.id,
       success: result.success,
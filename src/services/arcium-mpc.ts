@@ -30,6 +30,12 @@ const OBSIDIAN_MPC_PROGRAM = new PublicKey(
 // Arcium devnet cluster offset - Cluster 1 has active nodes!
 const ARCIUM_CLUSTER_OFFSET = parseInt(process.env.ARCIUM_CLUSTER_OFFSET || '1');
 
+// Cluster to fail over to when the active one stops answering.
+// Overridden by the on-chain ProtocolConfig once loaded.
+const ARCIUM_FALLBACK_CLUSTER_OFFSET = parseInt(
+  process.env.ARCIUM_FALLBACK_CLUSTER_OFFSET || String(ARCIUM_CLUSTER_OFFSET)
+);
+
 // RPC endpoints - Arcium MPC ALWAYS uses devnet (separate from mainnet trading)
 const SOLANA_RPC_URL_DEVNET = process.env.SOLANA_RPC_URL_DEVNET || 'https://api.devnet.solana.com';
 
@@ -39,6 +45,10 @@ const MXE_ACCOUNT_ADDRESS = new PublicKey(
   process.env.MXE_ACCOUNT_ADDRESS || '2EYXHVLZGSTGmPN3VFdHb6DroZBfpir6mgYZuFvpxfJG'
 );
 
+// Comp def offsets the relay queues (first 4 bytes of sha256(circuit name), LE):
+// init_batch, add_to_batch, reveal_batch_total, compute_distribution
+const REQUIRED_COMP_DEF_OFFSETS = [3167146940, 448552201, 1072107248, 623176224];
+
 /**
  * Encrypted order data that user submits
  * The relay cannot decrypt this - only MXE nodes can
@@ -88,16 +98,19 @@ class ArciumMpcService {
   private connection: Connection;
   private batches: Map<string, MpcBatchState> = new Map();
   private clusterOffset: number;
+  private fallbackClusterOffset: number;
 
   constructor() {
     this.connection = new Connection(SOLANA_RPC_URL_DEVNET, 'confirmed');
     this.clusterOffset = ARCIUM_CLUSTER_OFFSET;
+    this.fallbackClusterOffset = ARCIUM_FALLBACK_CLUSTER_OFFSET;
 
     console.log(`[ArciumMPC] === Arcium MPC Service ===`);
     console.log(`[ArciumMPC] Solana RPC: ${SOLANA_RPC_URL_DEVNET}`);
     console.log(`[ArciumMPC] MPC Program: ${OBSIDIAN_MPC_PROGRAM.toBase58()}`);
     console.log(`[ArciumMPC] Arcium Program: ${ARCIUM_PROGRAM_ID.toBase58()}`);
     console.log(`[ArciumMPC] Cluster Offset: ${this.clusterOffset}`);
+    console.log(`[ArciumMPC] Fallback Cluster Offset: ${this.fallbackClusterOffset}`);
     console.log(`[ArciumMPC] MXE Account: ${MXE_ACCOUNT_ADDRESS.toBase58()}`);
     console.log(`[ArciumMPC] =============================`);
   }
@@ -274,6 +287,10 @@ class ArciumMpcService {
         };
       }
 
+      // A reveal that never arrives means the cluster is degraded.
+      // Switch clusters so the next attempt (after migrate_batch_cluster
+      // and rekey_batch_stats) runs on the fallback.
+      this.failOverCluster(mpcResult.error || 'reveal timed out');
       return { success: false, error: mpcResult.error };
     } catch (error) {
       const errorMsg = error instanceof Error ? error.message : 'Unknown error';
@@ -368,6 +385,46 @@ class ArciumMpcService {
     return this.batches.get(batchId);
   }
 
+  /**
+   * Load the active and fallback clusters from the on-chain ProtocolConfig
+   * Layout: 8 (discriminator) + 32 (admin) + 4 (cluster_offset) + 4 (fallback_cluster_offset) + 8 (updated_at)
+   */
+  async loadClusterConfig(): Promise<void> {
+    const [configAddress] = PublicKey.findProgramAddressSync(
+      [Buffer.from('config')],
+      OBSIDIAN_MPC_PROGRAM
+    );
+
+    try {
+      const accountInfo = await this.connection.getAccountInfo(configAddress);
+      if (accountInfo && accountInfo.data.length >= 48) {
+        this.clusterOffset = accountInfo.data.readUInt32LE(40);
+        this.fallbackClusterOffset = accountInfo.data.readUInt32LE(44);
+        console.log(
+          `[ArciumMPC] Cluster config loaded: active ${this.clusterOffset}, fallback ${this.fallbackClusterOffset}`
+        );
+      }
+    } catch (error) {
+      console.log(`[ArciumMPC] No cluster config on-chain, using environment offsets`);
+    }
+  }
+
+  /**
+   * Swap the active and fallback clusters
+   * The admin mirrors this on-chain with set_cluster
+   */
+  failOverCluster(reason: string): void {
+    if (this.fallbackClusterOffset === this.clusterOffset) {
+      console.error(`[ArciumMPC] Cluster ${this.clusterOffset} degraded (${reason}), no fallback configured`);
+      return;
+    }
+
+    console.warn(
+      `[ArciumMPC] Cluster ${this.clusterOffset} degraded (${reason}), failing over to ${this.fallbackClusterOffset}`
+    );
+    [this.clusterOffset, this.fallbackClusterOffset] = [this.fallbackClusterOffset, this.clusterOffset];
+  }
+
   // --- Private helpers ---
 
   private encodeCreateBatch(marketId: string, side: 'YES' | 'NO'): Buffer {
@@ -511,6 +568,7 @@ class ArciumMpcService {
     mpcProgram: string;
     arciumProgram: string;
     clusterOffset: number;
+    fallbackClusterOffset: number;
     mxeAccount: string;
     mxeInitialized: boolean;
     compDefsAvailable: number[];
@@ -554,15 +612,22 @@ class ArciumMpcService {
       status = 'partial';
     }
 
-    // Check computation definitions - they should be initialized on cluster 123
-    // Expected offsets: [1, 3167146940, 448552201, 1072107248, 623176224]
-    compDefsAvailable = [1, 3167146940, 448552201, 1072107248, 623176224];
+    // Check computation definitions against the on-chain MpcRegistry
+    compDefsAvailable = await this.getRegisteredCompDefs();
+    const missing = REQUIRED_COMP_DEF_OFFSETS.filter(offset => !compDefsAvailable.includes(offset));
+    if (missing.length > 0) {
+      issues.push(`Computation definitions not registered: ${missing.join(', ')}`);
+      if (status === 'ready') {
+        status = 'partial';
+      }
+    }
 
     return {
       solanaRpc: SOLANA_RPC_URL_DEVNET,
       mpcProgram: OBSIDIAN_MPC_PROGRAM.toBase58(),
       arciumProgram: ARCIUM_PROGRAM_ID.toBase58(),
       clusterOffset: this.clusterOffset,
+      fallbackClusterOffset: this.fallbackClusterOffset,
       mxeAccount: MXE_ACCOUNT_ADDRESS.toBase58(),
       mxeInitialized,
       compDefsAvailable,
@@ -571,6 +636,33 @@ class ArciumMpcService {
     };
   }
 
+  /**
+   * Offsets recorded in the MpcRegistry PDA
+   * Layout: 8 (discriminator) + 32 (authority) + 4 (len) + len * (4 offset + 32 comp_def + 2 version + 8 registered_at)
+   */
+  async getRegisteredCompDefs(): Promise<number[]> {
+    const [registryAddress] = PublicKey.findProgramAddressSync(
+      [Buffer.from('mpc_registry')],
+      OBSIDIAN_MPC_PROGRAM
+    );
+
+    try {
+      const accountInfo = await this.connection.getAccountInfo(registryAddress);
+      if (!accountInfo || accountInfo.data.length < 44) {
+        return [];
+      }
+      const data = accountInfo.data;
+      const count = data.readUInt32LE(40);
+      const offsets: number[] = [];
+      for (let i = 0; i < count; i++) {
+        offsets.push(data.readUInt32LE(44 + i * 46));
+      }
+      return offsets;
+    } catch (error) {
+      return [];
+    }
+  }
+
   /**
    * Get cluster offset being used
    */
@@ -588,6 +680,7 @@ let mpcService: ArciumMpcService | null = null;
 export function getArciumMpcService(): ArciumMpcService {
   if (!mpcService) {
     mpcService = new ArciumMpcService();
+    void mpcService.loadClusterConfig();
   }
   return mpcService;
 }
@@ -17,10 +17,12 @@ import { buildMerkleTree } from './merkle.js';
 import { computeCommitmentHash } from './commitment.js';
 import { poseidonHash2, poseidonHash5, poseidonHashN } from './poseidon.js';
 import { generateProof } from './prover.js';
-import { getRelayWallet } from './wallet.js';
+import { getRelayWallet, type TokenTransfer } from './wallet.js';
 import { decimalToField, pubkeyToField, sideToField } from '../utils/field.js';
 import type { DistributionEntry } from '../types/index.js';
 import { getArciumMpcService, isMpcEnabled, type EncryptedOrderData } from './arcium-mpc.js';
+import { emitWebhook } from './webhooks.js';
+import { publishProgress, type ProgressFields } from './progress.js';
 
 // In-memory storage (use database in production)
 const orders: Map<string, RelayOrder> = new Map();
@@ -29,6 +31,128 @@ const batches: Map<string, RelayBatch> = new Map();
 // Current collecting batch per market+side
 const collectingBatches: Map<string, string> = new Map(); // "marketId:side" -> batchId
 
+// Recent arrivals per market, halved at every close like MarketStats on-chain
+const marketArrivals: Map<string, { recentOrders: number; recentOpenSecs: number }> = new Map();
+
+/**
+ * Common webhook fields for a batch lifecycle event
+ */
+function batchEventData(batch: RelayBatch): Record<string, unknown> {
+  return {
+    batchId: batch.id,
+    status: batch.status,
+    isEncrypted: batch.isEncrypted,
+    marketId: batch.marketId,
+    side: batch.side,
+    orderCount: batch.orderIds.length,
+  };
+}
+
+/**
+ * Stage-dependent progress fields of a batch, for updates and stream snapshots
+ */
+export function batchProgress(batch: RelayBatch): ProgressFields {
+  return {
+    closesAt: batch.status === 'collecting'
+      ? Math.floor(batch.createdAt.getTime() / 1000) + getBatchWindowSeconds(batch.marketId)
+      : undefined,
+    distributionsDone: batch.distributionsDone,
+    distributionsTotal: batch.distributionsTotal,
+  };
+}
+
+/**
+ * Publish fill progress for the batch's current stage
+ */
+function progress(batch: RelayBatch, fields: ProgressFields = {}): void {
+  publishProgress(batch, { ...batchProgress(batch), ...fields });
+}
+
+/**
+ * Move batch to the next execution stage and publish progress
+ */
+function advanceBatch(batch: RelayBatch, status: BatchStatus): void {
+  batch.status = status;
+  progress(batch);
+}
+
+/**
+ * Mark batch failed and notify webhooks
+ */
+function failBatch(batch: RelayBatch, error?: string): void {
+  batch.status = 'failed';
+  emitWebhook('BatchFailed', { ...batchEventData(batch), error });
+  progress(batch, { error });
+}
+
+/**
+ * Close batch to new orders and notify webhooks
+ */
+function closeBatch(batch: RelayBatch): void {
+  batch.status = 'ready';
+
+  const arrivals = marketArrivals.get(batch.marketId) ?? { recentOrders: 0, recentOpenSecs: 0 };
+  const openSecs = Math.max(0, Math.floor((Date.now() - batch.createdAt.getTime()) / 1000));
+  marketArrivals.set(batch.marketId, {
+    recentOrders: Math.floor(arrivals.recentOrders / 2) + batch.orderIds.length,
+    recentOpenSecs: Math.floor(arrivals.recentOpenSecs / 2) + openSecs,
+  });
+  emitWebhook('BatchClosed', { ...batchEventData(batch), totalUsdcCommitted: batch.totalUsdcCommitted });
+  progress(batch);
+}
+
+/**
+ * Record execution results and notify webhooks
+ */
+function recordExecution(batch: RelayBatch, executionResult: DFlowExecutionResult): void {
+  batch.actualUsdcSpent = executionResult.usdcSpent;
+  batch.actualSharesReceived = executionResult.sharesReceived;
+  batch.fillPercentage = executionResult.fillPercentage;
+  batch.executionPrice = executionResult.averagePrice;
+  batch.dflowOrderId = executionResult.orderId;
+  batch.dflowTxSignature = executionResult.txSignature;
+  batch.executionCompletedAt = new Date();
+
+  emitWebhook('BatchExecuted', {
+    ...batchEventData(batch),
+    usdcSpent: batch.actualUsdcSpent,
+    sharesReceived: batch.actualSharesReceived,
+    fillPercentage: batch.fillPercentage,
+    executionPrice: batch.executionPrice,
+    txSignature: batch.dflowTxSignature,
+  });
+}
+
+/**
+ * Notify webhooks subscribed to `wallet` that its shares were sent, and
+ * count the transfer towards the batch's distribution progress
+ */
+function distributionExecuted(
+  batch: RelayBatch,
+  orderId: string,
+  wallet: string,
+  sharesAmount: string,
+  txSignature: string | undefined
+): void {
+  emitWebhook(
+    'DistributionExecuted',
+    { batchId: batch.id, marketId: batch.marketId, side: batch.side, orderId, wallet, sharesAmount, txSignature },
+    wallet
+  );
+  batch.distributionsDone = (batch.distributionsDone ?? 0) + 1;
+  progress(batch, { orderId });
+}
+
+/**
+ * Mark batch completed and notify webhooks
+ */
+function completeBatch(batch: RelayBatch): void {
+  batch.status = 'completed';
+  batch.distributionCompletedAt = new Date();
+  emitWebhook('BatchCompleted', { ...batchEventData(batch), sharesReceived: batch.actualSharesReceived });
+  progress(batch);
+}
+
 /**
  * Get or create a collecting batch for a market+side
  */
@@ -65,6 +189,7 @@ function getOrCreateCollectingBatch(
   collectingBatches.set(key, batch.id);
 
   console.log(`Created new ${isEncrypted ? 'ENCRYPTED' : 'regular'} batch ${batch.id} for ${marketId} ${side}`);
+  emitWebhook('BatchCreated', batchEventData(batch));
   return batch;
 }
 
@@ -198,10 +323,11 @@ export async function submitOrder(
   console.log(`  Distribution: ${distribution.map(d => `${d.wallet.slice(0, 8)}...(${d.percentage / 100}%)`).join(', ')}`);
   if (batch.yesTokenMint) console.log(`  YES mint: ${batch.yesTokenMint}`);
   if (batch.noTokenMint) console.log(`  NO mint: ${batch.noTokenMint}`);
+  progress(batch, { orderId: order.id });
 
   // Check if batch is ready
   if (batch.orderIds.length >= config.maxBatchSize) {
-    batch.status = 'ready';
+    closeBatch(batch);
     console.log(`Batch ${batch.id} is full and ready for execution`);
   }
 
@@ -287,10 +413,11 @@ export async function submitEncryptedOrder(
   console.log(`[MPC] Encrypted order ${order.id} added to batch ${batch.id} (index ${order.mpcOrderIndex})`);
   console.log(`[MPC] Relay CANNOT see: usdcAmount, distribution, salt`);
   console.log(`[MPC] Batch now has ${batch.orderIds.length} encrypted orders`);
+  progress(batch, { orderId: order.id });
 
   // Check if batch is ready
   if (batch.orderIds.length >= config.maxBatchSize) {
-    batch.status = 'ready';
+    closeBatch(batch);
     console.log(`[MPC] Batch ${batch.id} is full and ready for MPC execution`);
   }
 
@@ -359,7 +486,7 @@ export function getBatchOrders(batchId: string): RelayOrder[] {
 export function markBatchReady(batchId: string): RelayBatch | undefined {
   const batch = batches.get(batchId);
   if (batch && batch.status === 'collecting') {
-    batch.status = 'ready';
+    closeBatch(batch);
   }
   return batch;
 }
@@ -571,28 +698,22 @@ export async function executeBatch(
   try {
     // 1. Execute on DFlow
     console.log(`Executing batch ${batchId} on DFlow...`);
-    batch.status = 'executing';
+    advanceBatch(batch, 'executing');
     batch.executionStartedAt = new Date();
 
     const executionResult = await dflowExecutor(batch);
 
     if (!executionResult.success) {
-      batch.status = 'failed';
+      failBatch(batch, executionResult.error);
       return { success: false, batch, error: executionResult.error };
     }
 
     // Store execution results
-    batch.actualUsdcSpent = executionResult.usdcSpent;
-    batch.actualSharesReceived = executionResult.sharesReceived;
-    batch.fillPercentage = executionResult.fillPercentage;
-    batch.executionPrice = executionResult.averagePrice;
-    batch.dflowOrderId = executionResult.orderId;
-    batch.dflowTxSignature = executionResult.txSignature;
-    batch.executionCompletedAt = new Date();
+    recordExecution(batch, executionResult);
 
     // 2. Generate ZK proof
     console.log(`Generating ZK proof for batch ${batchId}...`);
-    batch.status = 'proving';
+    advanceBatch(batch, 'proving');
 
     const proofResult = await generateBatchProof(batch, executionResult);
     batch.proof = proofResult.proof;
@@ -605,7 +726,9 @@ export async function executeBatch(
 
     // 4. Distribute shares and refunds
     console.log(`Distributing shares for batch ${batchId}...`);
-    batch.status = 'distributing';
+    batch.distributionsDone = 0;
+    batch.distributionsTotal = distribution.walletAllocations.filter(wa => parseFloat(wa.sharesAmount) > 0).length;
+    advanceBatch(batch, 'distributing');
 
     // First, update order info from allocations
     for (const allocation of distribution.allocations) {
@@ -649,34 +772,45 @@ export async function executeBatch(
             sharesAmount: walletAlloc.sharesAmount,
             txSignature: result.success ? result.txSignature : undefined,
           });
-          if (!result.success) {
+          if (result.success) {
+            distributionExecuted(batch, order.id, result.wallet, walletAlloc.sharesAmount, result.txSignature);
+          } else {
             console.error(`MCP distribution failed for ${result.wallet}: ${result.error}`);
           }
         }
       }
     } else {
-      // Legacy wallet distribution
+      // Legacy wallet distribution, packed into as few transactions as fit
+      // through the batch's lookup table
       const wallet = await getRelayWallet();
+      const shareAllocations = distribution.walletAllocations.filter(wa => parseFloat(wa.sharesAmount) > 0);
+      for (const walletAlloc of shareAllocations) {
+        console.log(`Transferring ${walletAlloc.sharesAmount} shares (${walletAlloc.percentage / 100}%) to ${walletAlloc.destinationWallet}`);
+      }
 
-      for (const walletAlloc of distribution.walletAllocations) {
-        const order = orders.get(walletAlloc.orderId)!;
-
-        if (parseFloat(walletAlloc.sharesAmount) > 0) {
-          console.log(`Transferring ${walletAlloc.sharesAmount} shares (${walletAlloc.percentage / 100}%) to ${walletAlloc.destinationWallet}`);
-          const shareResult = await wallet.transferToken(
-            executionResult.shareTokenMint,
-            walletAlloc.destinationWallet,
-            parseFloat(walletAlloc.sharesAmount),
-            6
-          );
+      const shareResults = await wallet.transferTokenMany(
+        executionResult.shareTokenMint,
+        shareAllocations.map(wa => ({ recipient: wa.destinationWallet, amount: parseFloat(wa.sharesAmount) })),
+        6,
+        batchId
+      );
+      await wallet.releaseLookupTable(batchId);
 
-          order.distributionResults!.push({
-            wallet: walletAlloc.destinationWallet,
-            sharesAmount: walletAlloc.sharesAmount,
-            txSignature: shareResult.success ? shareResult.signature : undefined,
-          });
+      shareAllocations.forEach((walletAlloc, i) => {
+        const order = orders.get(walletAlloc.orderId)!;
+        const shareResult = shareResults[i];
+
+        order.distributionResults!.push({
+          wallet: walletAlloc.destinationWallet,
+          sharesAmount: walletAlloc.sharesAmount,
+          txSignature: shareResult.success ? shareResult.signature : undefined,
+        });
+        if (shareResult.success) {
+          distributionExecuted(batch, order.id, walletAlloc.destinationWallet, walletAlloc.sharesAmount, shareResult.signature);
+        } else {
+          console.error(`Share transfer to ${walletAlloc.destinationWallet} failed: ${shareResult.error}`);
         }
-      }
+      });
     }
 
     // Handle refunds (sent to primary wallet only) - use MCP if available
@@ -718,20 +852,199 @@ export async function executeBatch(
     }
 
     // 5. Mark batch complete
-    batch.status = 'completed';
-    batch.distributionCompletedAt = new Date();
+    completeBatch(batch);
 
     console.log(`Batch ${batchId} completed successfully!`);
 
     return { success: true, batch, distribution };
 
   } catch (error) {
-    batch.status = 'failed';
+    const message = error instanceof Error ? error.message : 'Unknown error';
+    failBatch(batch, message);
     return {
       success: false,
       batch,
-      error: error instanceof Error ? error.message : 'Unknown error',
+      error: message,
+    };
+  }
+}
+
+/**
+ * Execute a regular batch as one Jito bundle: the DFlow swap from the relay
+ * wallet, the share transfers, then a tip. Nothing can land between the
+ * trade and its payouts, so the window between close and execution can't
+ * be exploited by interleaved transactions.
+ *
+ * Shares in the bundle are split from the least the swap may return (its
+ * slippage bound), so a worse fill can't fail a transfer and with it the
+ * bundle. What the fill returned above that, and transfers that didn't fit
+ * in the bundle, are sent once it lands.
+ */
+export async function executeBatchInBundle(batchId: string): Promise<{
+  success: boolean;
+  batch: RelayBatch;
+  distribution?: DistributionPlan;
+  error?: string;
+}> {
+  const batch = batches.get(batchId);
+  if (!batch) {
+    return { success: false, batch: batch!, error: 'Batch not found' };
+  }
+
+  if (batch.status !== 'ready') {
+    return { success: false, batch, error: `Batch status is ${batch.status}, not ready` };
+  }
+
+  const fundedOrders = getBatchOrders(batch.id).filter(o => o.status === 'pending');
+  if (fundedOrders.length === 0) {
+    return { success: false, batch, error: 'No funded orders in batch' };
+  }
+  const fundedUsdcTotal = fundedOrders.reduce((sum, o) => sum + parseFloat(o.usdcAmount), 0);
+  batch.totalUsdcCommitted = fundedUsdcTotal.toString();
+
+  try {
+    // 1. Build the swap and the payouts of its guaranteed minimum
+    console.log(`[Jito] Executing batch ${batchId} as a bundle...`);
+    advanceBatch(batch, 'executing');
+    batch.executionStartedAt = new Date();
+
+    // Imported dynamically to avoid circular deps, as for MCP distribution
+    const { prepareDFlowSwap } = await import('./dflow.js');
+    const swap = await prepareDFlowSwap(batch, fundedUsdcTotal);
+    const minimum: DFlowExecutionResult = {
+      success: true,
+      usdcSpent: swap.usdcIn.toString(),
+      sharesReceived: swap.minShares.toString(),
+      averagePrice: '0',
+      fillPercentage: 100,
+      partialFill: false,
+      shareTokenMint: swap.shareTokenMint,
     };
+    const guaranteed = calculateDistribution(batch, minimum).walletAllocations;
+    const bundledTransfers: TokenTransfer[] = guaranteed
+      .filter(wa => parseFloat(wa.sharesAmount) > 0)
+      .map(wa => ({ recipient: wa.destinationWallet, amount: parseFloat(wa.sharesAmount) }));
+
+    // 2. Send them as one bundle
+    const wallet = await getRelayWallet();
+    const { bundle, included } = await wallet.bundleWithTokenTransfers(
+      swap.transaction,
+      swap.shareTokenMint,
+      bundledTransfers,
+      6,
+      batchId
+    );
+    if (!bundle.success) {
+      failBatch(batch, `Bundle failed: ${bundle.error}`);
+      return { success: false, batch, error: `Bundle failed: ${bundle.error}` };
+    }
+    batch.jitoBundleId = bundle.bundleId;
+
+    const txSignature = bundle.signatures[0];
+    const sharesReceived = Number(await wallet.tokenReceived(txSignature, swap.shareTokenMint)) / 1e6;
+    const executionResult: DFlowExecutionResult = {
+      ...minimum,
+      orderId: `jito-${bundle.bundleId}`,
+      txSignature,
+      sharesReceived: sharesReceived.toString(),
+      averagePrice: sharesReceived > 0 ? (swap.usdcIn / sharesReceived).toString() : '0',
+    };
+    recordExecution(batch, executionResult);
+    console.log(`[Jito] Bundle ${bundle.bundleId} landed with ${included.length}/${bundledTransfers.length} transfers`);
+
+    // 3. Generate ZK proof over the actual fill
+    advanceBatch(batch, 'proving');
+    const proofResult = await generateBatchProof(batch, executionResult);
+    batch.proof = proofResult.proof;
+    batch.publicInputs = proofResult.publicInputs;
+    batch.proofVerified = proofResult.verified;
+
+    // 4. Send what the bundle didn't: transfers that didn't fit, and every
+    // wallet's share of the fill above the minimum
+    const distribution = calculateDistribution(batch, executionResult);
+    const payouts = distribution.walletAllocations
+      .map((wa, i) => ({ wa, bundled: parseFloat(guaranteed[i].sharesAmount) }))
+      .filter(({ wa }) => parseFloat(wa.sharesAmount) > 0);
+    batch.distributionsDone = 0;
+    batch.distributionsTotal = payouts.length;
+    advanceBatch(batch, 'distributing');
+
+    let bundledSeen = 0;
+    const followUps = payouts.map(({ wa, bundled }) => {
+      const bundledTransfer = bundled > 0 ? included[bundledSeen++] : undefined;
+      const remaining = parseFloat(wa.sharesAmount) - (bundledTransfer ? bundled : 0);
+      return {
+        wa,
+        bundledSignature: bundledTransfer?.signature,
+        // Below one base unit there is nothing left to send
+        transfer: remaining >= 1e-6 ? { recipient: wa.destinationWallet, amount: remaining } : undefined,
+      };
+    });
+    const sent = await wallet.transferTokenMany(
+      swap.shareTokenMint,
+      followUps.flatMap(f => (f.transfer ? [f.transfer] : [])),
+      6,
+      batchId
+    );
+    await wallet.releaseLookupTable(batchId);
+
+    for (const allocation of distribution.allocations) {
+      const order = orders.get(allocation.orderId)!;
+      order.effectiveUsdcSpent = (
+        parseFloat(order.usdcAmount) - parseFloat(allocation.refundAmount)
+      ).toString();
+      order.sharesReceived = allocation.sharesAmount;
+      order.refundAmount = allocation.refundAmount;
+      order.distributionResults = [];
+    }
+
+    let sentIndex = 0;
+    for (const { wa, bundledSignature, transfer } of followUps) {
+      const order = orders.get(wa.orderId)!;
+      const followUp = transfer ? sent[sentIndex++] : undefined;
+      // The bundle paid the bulk of it; a follow-up carries the rest
+      const signature = bundledSignature ?? followUp?.signature;
+      const paid = followUp ? followUp.success : bundledSignature !== undefined;
+
+      order.distributionResults!.push({
+        wallet: wa.destinationWallet,
+        sharesAmount: wa.sharesAmount,
+        txSignature: paid ? signature : undefined,
+      });
+      if (paid) {
+        distributionExecuted(batch, order.id, wa.destinationWallet, wa.sharesAmount, signature);
+      } else {
+        console.error(`Share transfer to ${wa.destinationWallet} failed: ${followUp?.error}`);
+      }
+    }
+
+    // Exact-in swaps spend the whole total, so refunds are rounding at most
+    for (const allocation of distribution.allocations) {
+      const order = orders.get(allocation.orderId)!;
+      if (parseFloat(allocation.refundAmount) >= 1e-6) {
+        const refundResult = await wallet.transferUsdc(
+          allocation.destinationWallet,
+          parseFloat(allocation.refundAmount)
+        );
+        if (refundResult.success) {
+          order.refundTxSignature = refundResult.signature;
+          order.refundWallet = allocation.destinationWallet;
+        }
+      }
+      order.status = 'completed';
+      order.executedAt = batch.executionCompletedAt;
+      order.completedAt = new Date();
+    }
+
+    // 5. Mark batch complete
+    completeBatch(batch);
+    console.log(`Batch ${batchId} completed successfully via bundle ${bundle.bundleId}!`);
+
+    return { success: true, batch, distribution };
+  } catch (error) {
+    const message = error instanceof Error ? error.message : 'Unknown error';
+    failBatch(batch, message);
+    return { success: false, batch, error: message };
   }
 }
 
@@ -777,7 +1090,7 @@ export async function executeEncryptedBatch(
   try {
     // 1. Queue all funded orders to MPC
     console.log(`[MPC] Queueing encrypted orders to MPC for batch ${batchId}...`);
-    batch.status = 'mpc_computing';
+    advanceBatch(batch, 'mpc_computing');
 
     const fundedOrders = getBatchOrders(batch.id).filter(o => o.status === 'pending');
 
@@ -799,7 +1112,7 @@ export async function executeEncryptedBatch(
     const revealResult = await mpcService.closeBatchAndRevealTotal(batchId);
 
     if (!revealResult.success || !revealResult.totalUsdc) {
-      batch.status = 'failed';
+      failBatch(batch, revealResult.error || 'MPC reveal failed');
       return { success: false, batch, error: revealResult.error || 'MPC reveal failed' };
     }
 
@@ -814,28 +1127,24 @@ export async function executeEncryptedBatch(
 
     // 3. Execute on DFlow with revealed total
     console.log(`[MPC] Executing batch on DFlow with revealed total...`);
-    batch.status = 'executing';
+    advanceBatch(batch, 'executing');
     batch.executionStartedAt = new Date();
 
     const executionResult = await dflowExecutor(batch, totalUsdc);
 
     if (!executionResult.success) {
-      batch.status = 'failed';
+      failBatch(batch, executionResult.error);
       return { success: false, batch, error: executionResult.error };
     }
 
     // Store execution results
-    batch.actualUsdcSpent = executionResult.usdcSpent;
-    batch.actualSharesReceived = executionResult.sharesReceived;
-    batch.fillPercentage = executionResult.fillPercentage;
-    batch.executionPrice = executionResult.averagePrice;
-    batch.dflowOrderId = executionResult.orderId;
-    batch.dflowTxSignature = executionResult.txSignature;
-    batch.executionCompletedAt = new Date();
+    recordExecution(batch, executionResult);
 
     // 4. Use MPC to compute and reveal distributions (one at a time)
     console.log(`[MPC] Requesting MPC distribution instructions...`);
-    batch.status = 'mpc_distributing';
+    batch.distributionsDone = 0;
+    batch.distributionsTotal = fundedOrders.length;
+    advanceBatch(batch, 'mpc_distributing');
 
     const totalShares = parseFloat(executionResult.sharesReceived);
 
@@ -854,20 +1163,26 @@ export async function executeEncryptedBatch(
         console.log(`[MPC] Order ${i}: sending ${sharesAmount} shares to ${destinationWallet.toBase58()}`);
 
         // Execute the transfer
+        let txSignature: string | undefined;
         if (executionResult.mcpWallet) {
           const { distributeTokensViaMcp } = await import('./dflow.js');
-          await distributeTokensViaMcp(executionResult.shareTokenMint, [{
+          const [result] = await distributeTokensViaMcp(executionResult.shareTokenMint, [{
             wallet: destinationWallet.toBase58(),
             amount: sharesAmount,
           }]);
+          txSignature = result?.success ? result.txSignature : undefined;
         } else {
           const wallet = await getRelayWallet();
-          await wallet.transferToken(
+          const result = await wallet.transferToken(
             executionResult.shareTokenMint,
             destinationWallet.toBase58(),
             sharesAmount,
             6
           );
+          txSignature = result.success ? result.signature : undefined;
+        }
+        if (txSignature) {
+          distributionExecuted(batch, order.id, destinationWallet.toBase58(), sharesAmount.toString(), txSignature);
         }
 
         // Update order (with MPC-revealed values)
@@ -882,8 +1197,7 @@ export async function executeEncryptedBatch(
 
     // 5. Mark batch complete
     await mpcService.completeBatch(batchId);
-    batch.status = 'completed';
-    batch.distributionCompletedAt = new Date();
+    completeBatch(batch);
 
     console.log(`[MPC] Encrypted batch ${batchId} completed successfully!`);
     console.log(`[MPC] Relay NEVER learned individual order amounts`);
@@ -891,11 +1205,12 @@ export async function executeEncryptedBatch(
     return { success: true, batch };
 
   } catch (error) {
-    batch.status = 'failed';
+    const message = error instanceof Error ? error.message : 'Unknown error';
+    failBatch(batch, message);
     return {
       success: false,
       batch,
-      error: error instanceof Error ? error.message : 'Unknown error',
+      error: message,
     };
   }
 }
@@ -914,6 +1229,23 @@ export function getAllOrders(): RelayOrder[] {
   return Array.from(orders.values());
 }
 
+/**
+ * Seconds a batch in `marketId` collects orders before it times out: the time
+ * the market's recent arrival rate takes to bring in targetBatchOrders, within
+ * the configured bounds. Same formula as MarketStats.batch_window on-chain.
+ */
+export function getBatchWindowSeconds(marketId: string, config: RelayConfig = { ...DEFAULT_RELAY_CONFIG }): number {
+  const min = config.minBatchWindowSeconds ?? 0;
+  const max = config.maxBatchWindowSeconds ?? 0;
+  if (max === 0) return config.batchTimeoutSeconds;
+
+  const arrivals = marketArrivals.get(marketId);
+  const window = arrivals && arrivals.recentOrders > 0
+    ? Math.floor(((config.targetBatchOrders ?? 0) * arrivals.recentOpenSecs) / arrivals.recentOrders)
+    : max;
+  return Math.max(Math.min(Math.max(window, min), max), config.batchTimeoutSeconds);
+}
+
 /**
  * Get collecting batches that are ready or timed out
  */
@@ -925,10 +1257,15 @@ export function getReadyBatches(config: RelayConfig = { ...DEFAULT_RELAY_CONFIG
     if (batch.status !== 'collecting') continue;
 
     const age = (now.getTime() - batch.createdAt.getTime()) / 1000;
-    const isTimedOut = age >= config.batchTimeoutSeconds;
+    const isTimedOut = age >= getBatchWindowSeconds(batch.marketId, config);
     const hasMinOrders = batch.orderIds.length >= config.minBatchSize;
+    // A batch that collected its target closes early, once the shortest window passed
+    const target = config.targetBatchOrders ?? 0;
+    const reachedTarget = target > 0
+      && batch.orderIds.length >= target
+      && age >= Math.max(config.minBatchWindowSeconds ?? 0, config.batchTimeoutSeconds);
 
-    if (batch.orderIds.length >= config.maxBatchSize || (isTimedOut && hasMinOrders)) {
+    if (batch.orderIds.length >= config.maxBatchSize || (isTimedOut && hasMinOrders) || reachedTarget) {
       ready.push(batch);
     }
   }
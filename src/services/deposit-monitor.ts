@@ -6,7 +6,7 @@ import {
 } from '@solana/web3.js';
 import { getRelayWallet } from './wallet.js';
 import { getMcpWalletAddress } from './dflow.js';
-import { getOrder, activateOrder, refundOrder } from './batch.js';
+import { getOrder, activateOrder, refundOrder, submitEncryptedOrder } from './batch.js';
 
 // USDC mint on mainnet
 const USDC_MINT = new PublicKey('EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v');
@@ -41,6 +41,14 @@ interface ParsedOrderMemo {
   amount?: number;
   slippageBps?: number;
   destinationWallets?: string[]; // Multiple wallets separated by ; in memo
+  // Encrypted order (Solana Pay / SDK flow): OBSIDIAN_ENC|marketId|side|ciphertext|publicKey|nonce
+  encrypted?: {
+    marketId: string;
+    side: 'YES' | 'NO';
+    ciphertext: string;
+    publicKey: string;
+    nonce: string;
+  };
 }
 
 /**
@@ -115,6 +123,23 @@ function parseMemo(tx: ParsedTransactionWithMeta): ParsedOrderMemo | undefined {
     }
   }
 
+  // Encrypted order submitted directly on-chain (e.g. scanned Solana Pay QR)
+  if (memoContent.startsWith('OBSIDIAN_ENC|')) {
+    const parts = memoContent.split('|');
+    if (parts.length >= 6) {
+      return {
+        raw: memoContent,
+        encrypted: {
+          marketId: parts[1],
+          side: parts[2] === '1' ? 'YES' : 'NO',
+          ciphertext: parts[3],
+          publicKey: parts[4],
+          nonce: parts[5],
+        },
+      };
+    }
+  }
+
   // Legacy UUID format
   if (/^[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}$/i.test(memoContent)) {
     return { raw: memoContent };
@@ -310,6 +335,30 @@ async function processDeposit(
     return;
   }
 
+  // Encrypted order - register it with the MPC batch and activate in one step
+  if (memo?.encrypted) {
+    const { marketId, side, ciphertext, publicKey, nonce } = memo.encrypted;
+    try {
+      const order = await submitEncryptedOrder({
+        marketId,
+        side,
+        encryptedData: { ciphertext, publicKey, nonce },
+      });
+      await activateOrder(order.id, signature, transfer.sender);
+      console.log(`[Deposit Monitor] Encrypted order ${order.id} registered from on-chain memo`);
+    } catch (error) {
+      console.error(`[Deposit Monitor] Encrypted order failed:`, error);
+      unmatchedDeposits.set(signature, {
+        signature,
+        amount: transfer.amount,
+        sender: transfer.sender,
+        memo: memo.raw,
+        timestamp: new Date(),
+      });
+    }
+    return;
+  }
+
   // Legacy format - try to match to an existing order
   if (memo?.raw) {
     const order = getOrder(memo.raw);
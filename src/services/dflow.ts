@@ -15,10 +15,10 @@ import {
 import type { RelayBatch, DFlowExecutionResult } from '../types/relay.js';
 import { getRelayWallet } from './wallet.js';
 
-// API endpoints
-const KALSHI_API = 'https://api.elections.kalshi.com/trade-api/v2';
-const DFLOW_QUOTE_API = 'https://quote-api.dflow.net';
-const DFLOW_DEV_API = 'https://dev-prediction-markets-api.dflow.net/api/v1';
+// API endpoints (overridable to point at the localnet mock, `cargo xtask mock-dflow`)
+const KALSHI_API = process.env.KALSHI_API || 'https://api.elections.kalshi.com/trade-api/v2';
+const DFLOW_QUOTE_API = process.env.DFLOW_QUOTE_API || 'https://quote-api.dflow.net';
+const DFLOW_DEV_API = process.env.DFLOW_DEV_API || 'https://dev-prediction-markets-api.dflow.net/api/v1';
 
 // MCP Server for trading (has working DFlow credentials)
 const MCP_ENDPOINT = process.env.MCP_ENDPOINT || 'https://kalshi-mcp-production-7c2c.up.railway.app/mcp';
@@ -551,6 +551,104 @@ export async function executeDFlowTrade(batch: RelayBatch): Promise<DFlowExecuti
   };
 }
 
+/**
+ * A DFlow swap of a batch's USDC, built for and signed by the relay wallet
+ * but not yet sent
+ */
+export interface PreparedSwap {
+  transaction: VersionedTransaction;
+  shareTokenMint: string;
+  usdcIn: number;
+  /** Shares the quote expects, and the least the swap accepts (slippage) */
+  expectedShares: number;
+  minShares: number;
+}
+
+/**
+ * Quote and build the swap for `batch` paid from the relay wallet, to be
+ * sent in a bundle rather than through MCP. Throws if the market can't be
+ * traded or DFlow returns no swap.
+ */
+export async function prepareDFlowSwap(batch: RelayBatch, usdcAmount: number): Promise<PreparedSwap> {
+  const market = await getMarketInfo(batch.marketId);
+  if (!market) {
+    throw new Error(`Market ${batch.marketId} not found`);
+  }
+  if (market.status !== 'active') {
+    throw new Error(`Market is ${market.status}, not active`);
+  }
+  const outputMint = batch.side === 'YES'
+    ? batch.yesTokenMint || market.yesTokenMint
+    : batch.noTokenMint || market.noTokenMint;
+  if (!outputMint) {
+    throw new Error(`No token mint found for ${batch.marketId} ${batch.side}`);
+  }
+
+  const quote = await getQuote(USDC_MINT, outputMint, usdcAmount, 300);
+  if (!quote) {
+    throw new Error(`No quote for ${usdcAmount} USDC of ${batch.marketId} ${batch.side}`);
+  }
+  const wallet = await getRelayWallet();
+  const swap = await createSwap(quote, wallet.getPublicKey().toBase58());
+  if (!swap) {
+    throw new Error('DFlow returned no swap transaction');
+  }
+
+  return {
+    transaction: VersionedTransaction.deserialize(Buffer.from(swap.swapTransaction, 'base64')),
+    shareTokenMint: outputMint,
+    usdcIn: parseFloat(quote.inAmount) / 1e6,
+    expectedShares: parseFloat(quote.outAmount) / 1e6,
+    minShares: parseFloat(quote.minOutAmount || quote.otherAmountThreshold) / 1e6,
+  };
+}
+
+/**
+ * Execute a batch trade from the relay wallet as a Jito bundle (swap and
+ * tip only), so the trade can't be sandwiched between the reveal and its
+ * execution. Used for encrypted batches, whose distributions depend on the
+ * MPC and can't join the bundle; see executeBatchInBundle for the rest.
+ */
+export async function executeDFlowTradeBundled(batch: RelayBatch, totalUsdc: number): Promise<DFlowExecutionResult> {
+  const failure = (error: string): DFlowExecutionResult => ({
+    success: false,
+    usdcSpent: '0',
+    sharesReceived: '0',
+    averagePrice: '0',
+    fillPercentage: 0,
+    partialFill: false,
+    shareTokenMint: '',
+    error,
+  });
+
+  try {
+    const prepared = await prepareDFlowSwap(batch, totalUsdc);
+    const wallet = await getRelayWallet();
+    const { bundle } = await wallet.bundleWithTokenTransfers(prepared.transaction, prepared.shareTokenMint, []);
+    if (!bundle.success) {
+      return failure(`Bundle failed: ${bundle.error}`);
+    }
+
+    const txSignature = bundle.signatures[0];
+    const sharesReceived = Number(await wallet.tokenReceived(txSignature, prepared.shareTokenMint)) / 1e6;
+    console.log(`[Jito] Bundle ${bundle.bundleId} landed: ${sharesReceived} shares for $${prepared.usdcIn}`);
+
+    return {
+      success: true,
+      orderId: `jito-${bundle.bundleId}`,
+      txSignature,
+      usdcSpent: prepared.usdcIn.toString(),
+      sharesReceived: sharesReceived.toString(),
+      averagePrice: sharesReceived > 0 ? (prepared.usdcIn / sharesReceived).toString() : '0',
+      fillPercentage: 100,
+      partialFill: false,
+      shareTokenMint: prepared.shareTokenMint,
+    };
+  } catch (error) {
+    return failure(error instanceof Error ? error.message : 'Bundled execution failed');
+  }
+}
+
 /**
  * Get MCP wallet address for deposits
  */
@@ -0,0 +1,165 @@
+/**
+ * Jito Block Engine Client
+ *
+ * Submits transaction bundles to a Jito block engine. A bundle runs in
+ * order, all-or-nothing, in one slot, and never enters the public
+ * forwarding path, so nothing can be placed between its transactions.
+ * The relay sends a batch's execution and its distributions this way when
+ * JITO_BLOCK_ENGINE_URL is set (see TransactionSender.sendBundle).
+ *
+ * - The last transaction of every bundle tips a Jito tip account; bundles
+ *   without a tip are dropped
+ * - Bundles hold at most MAX_BUNDLE_TRANSACTIONS transactions
+ *
+ * Settings: JITO_BLOCK_ENGINE_URL, JITO_AUTH_UUID (optional),
+ * JITO_TIP_LAMPORTS, JITO_MAX_TIP_LAMPORTS.
+ */
+
+import { PublicKey } from '@solana/web3.js';
+
+export const MAX_BUNDLE_TRANSACTIONS = 5;
+// Tip accounts rarely change; refetch them this often
+const TIP_ACCOUNTS_TTL_MS = 600_000;
+// The block engine rejects tips below this
+const MIN_TIP_LAMPORTS = 1_000;
+
+export interface JitoConfig {
+  blockEngineUrl: string;
+  authUuid?: string;
+  /** Tip of a bundle's first attempt */
+  tipLamports: number;
+  /** Cap on the tip as retries escalate it */
+  maxTipLamports: number;
+}
+
+/**
+ * Status of a submitted bundle: Pending until it lands, fails, or the
+ * block engine forgets it (Invalid)
+ */
+export type BundleStatus = 'Pending' | 'Landed' | 'Failed' | 'Invalid';
+
+const stats = {
+  submitted: 0,
+  landed: 0,
+  failed: 0,
+  lastTipLamports: 0,
+  lastError: undefined as string | undefined,
+};
+
+function envNumber(name: string, fallback: number): number {
+  const value = Number(process.env[name]);
+  return process.env[name] && Number.isFinite(value) ? value : fallback;
+}
+
+/**
+ * Bundle settings from JITO_*; undefined unless JITO_BLOCK_ENGINE_URL is set
+ */
+export function jitoConfigFromEnv(): JitoConfig | undefined {
+  const blockEngineUrl = process.env.JITO_BLOCK_ENGINE_URL;
+  if (!blockEngineUrl) {
+    return undefined;
+  }
+  const tipLamports = Math.max(MIN_TIP_LAMPORTS, Math.floor(envNumber('JITO_TIP_LAMPORTS', 10_000)));
+  return {
+    blockEngineUrl: blockEngineUrl.replace(/\/+$/, ''),
+    authUuid: process.env.JITO_AUTH_UUID || undefined,
+    tipLamports,
+    maxTipLamports: Math.max(tipLamports, Math.floor(envNumber('JITO_MAX_TIP_LAMPORTS', 1_000_000))),
+  };
+}
+
+export function jitoEnabled(): boolean {
+  return Boolean(process.env.JITO_BLOCK_ENGINE_URL);
+}
+
+export class JitoClient {
+  private tipAccounts?: { accounts: PublicKey[]; fetchedAt: number };
+
+  constructor(readonly config: JitoConfig) {}
+
+  /**
+   * One of the block engine's tip accounts, picked at random so concurrent
+   * bundles don't all write the same account
+   */
+  async tipAccount(): Promise<PublicKey> {
+    const cached = this.tipAccounts;
+    if (!cached || Date.now() - cached.fetchedAt > TIP_ACCOUNTS_TTL_MS) {
+      const accounts = await this.call<string[]>('getTipAccounts', []);
+      if (accounts.length === 0) {
+        throw new Error('block engine returned no tip accounts');
+      }
+      this.tipAccounts = { accounts: accounts.map(a => new PublicKey(a)), fetchedAt: Date.now() };
+    }
+    const { accounts } = this.tipAccounts!;
+    return accounts[Math.floor(Math.random() * accounts.length)];
+  }
+
+  /**
+   * Submit signed, serialized `transactions` as one bundle; returns its id
+   */
+  async sendBundle(transactions: Uint8Array[], tipLamports: number): Promise<string> {
+    if (transactions.length === 0 || transactions.length > MAX_BUNDLE_TRANSACTIONS) {
+      throw new Error(`bundle has ${transactions.length} transactions, must be 1-${MAX_BUNDLE_TRANSACTIONS}`);
+    }
+    const encoded = transactions.map(tx => Buffer.from(tx).toString('base64'));
+    const bundleId = await this.call<string>('sendBundle', [encoded, { encoding: 'base64' }]);
+    stats.submitted++;
+    stats.lastTipLamports = tipLamports;
+    return bundleId;
+  }
+
+  /**
+   * Status of a bundle submitted in the last few minutes
+   */
+  async bundleStatus(bundleId: string): Promise<BundleStatus> {
+    const result = await this.call<{ value: { status: BundleStatus }[] }>('getInflightBundleStatuses', [[bundleId]]);
+    return result.value[0]?.status ?? 'Invalid';
+  }
+
+  /**
+   * Record how a bundle ended, for getJitoStatus
+   */
+  recordOutcome(landed: boolean, error?: string): void {
+    if (landed) {
+      stats.landed++;
+    } else {
+      stats.failed++;
+      stats.lastError = error;
+    }
+  }
+
+  private async call<T>(method: string, params: unknown[]): Promise<T> {
+    const headers: Record<string, string> = { 'Content-Type': 'application/json' };
+    if (this.config.authUuid) {
+      headers['x-jito-auth'] = this.config.authUuid;
+    }
+
+    const response = await fetch(`${this.config.blockEngineUrl}/api/v1/bundles`, {
+      method: 'POST',
+      headers,
+      body: JSON.stringify({ jsonrpc: '2.0', id: 1, method, params }),
+    });
+    if (!response.ok) {
+      throw new Error(`block engine ${method} failed: ${response.status} ${await response.text()}`);
+    }
+    const body = await response.json();
+    if (body.error) {
+      throw new Error(`block engine ${method} failed: ${body.error.message ?? JSON.stringify(body.error)}`);
+    }
+    return body.result as T;
+  }
+}
+
+/**
+ * Whether bundles are on, the tip settings and bundle counters
+ */
+export function getJitoStatus() {
+  const config = jitoConfigFromEnv();
+  return {
+    enabled: Boolean(config),
+    blockEngineUrl: config?.blockEngineUrl ?? null,
+    tipLamports: config?.tipLamports ?? null,
+    maxTipLamports: config?.maxTipLamports ?? null,
+    ...stats,
+  };
+}
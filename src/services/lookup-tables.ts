@@ -0,0 +1,195 @@
+/**
+ * Address Lookup Table Manager
+ *
+ * Keeps one address lookup table per batch so distribution transactions can
+ * be sent as v0 and name each repeated account (mint, relay token account,
+ * token programs, recipients) with a one-byte index instead of 32 bytes.
+ * That is what lets several transfers share one transaction.
+ *
+ * - A batch's table is created the first time it is needed and extended
+ *   with whatever addresses later transfers add
+ * - Addresses are only usable the slot after they are added, so extending
+ *   waits for the next slot before returning the table
+ * - Once the batch is settled the table is deactivated, then closed to
+ *   reclaim its rent after the deactivation cooldown
+ *
+ * Set RELAY_LOOKUP_TABLES=false to send legacy transactions instead.
+ */
+
+import {
+  AddressLookupTableAccount,
+  AddressLookupTableProgram,
+  Connection,
+  Keypair,
+  PublicKey,
+} from '@solana/web3.js';
+import { TransactionSender } from './sender.js';
+
+// Addresses per extend instruction, so each extension fits one transaction
+const MAX_EXTEND_ADDRESSES = 20;
+const LOOKUP_TABLE_MAX_ADDRESSES = 256;
+// A deactivated table can be closed once its slot leaves the slot hashes
+// (512 slots); allow some margin
+const CLOSE_DELAY_MS = 240_000;
+const SLOT_POLL_MS = 400;
+
+const stats = {
+  created: 0,
+  extended: 0,
+  closed: 0,
+  lastError: undefined as string | undefined,
+};
+
+export function lookupTablesEnabled(): boolean {
+  return process.env.RELAY_LOOKUP_TABLES !== 'false';
+}
+
+function sleep(ms: number): Promise<void> {
+  return new Promise(resolve => setTimeout(resolve, ms));
+}
+
+export class LookupTableManager {
+  private tables = new Map<string, PublicKey>();
+
+  constructor(
+    private connection: Connection,
+    private authority: Keypair,
+    private sender: TransactionSender
+  ) {}
+
+  /**
+   * The table for `batchId`, holding at least `addresses`; undefined if
+   * tables are disabled or could not be set up, in which case callers send
+   * legacy transactions
+   */
+  async ensure(batchId: string, addresses: PublicKey[]): Promise<AddressLookupTableAccount | undefined> {
+    if (!lookupTablesEnabled()) {
+      return undefined;
+    }
+
+    try {
+      let address = this.tables.get(batchId);
+      if (!address) {
+        address = await this.create();
+        this.tables.set(batchId, address);
+      }
+
+      let table = await this.fetch(address);
+      const present = new Set(table.state.addresses.map(key => key.toBase58()));
+      const missing = new Map<string, PublicKey>();
+      for (const key of addresses) {
+        if (!present.has(key.toBase58())) {
+          missing.set(key.toBase58(), key);
+        }
+      }
+      if (missing.size === 0) {
+        return table;
+      }
+      if (present.size + missing.size > LOOKUP_TABLE_MAX_ADDRESSES) {
+        throw new Error(`batch ${batchId} needs ${present.size + missing.size} addresses, over ${LOOKUP_TABLE_MAX_ADDRESSES}`);
+      }
+
+      const keys = [...missing.values()];
+      for (let i = 0; i < keys.length; i += MAX_EXTEND_ADDRESSES) {
+        const result = await this.sender.send([
+          AddressLookupTableProgram.extendLookupTable({
+            lookupTable: address,
+            authority: this.authority.publicKey,
+            payer: this.authority.publicKey,
+            addresses: keys.slice(i, i + MAX_EXTEND_ADDRESSES),
+          }),
+        ]);
+        if (!result.success) {
+          throw new Error(`extend failed: ${result.error}`);
+        }
+        stats.extended++;
+      }
+
+      // Addresses added in a slot can't be looked up until the next one
+      table = await this.fetch(address);
+      while ((await this.connection.getSlot('confirmed')) <= table.state.lastExtendedSlot) {
+        await sleep(SLOT_POLL_MS);
+      }
+      return table;
+    } catch (error) {
+      stats.lastError = `${batchId}: ${error instanceof Error ? error.message : error}`;
+      console.error(`[LookupTables] Falling back to legacy transactions: ${stats.lastError}`);
+      return undefined;
+    }
+  }
+
+  /**
+   * Deactivate the table of a settled batch and close it once the cooldown
+   * has passed. Never throws.
+   */
+  async release(batchId: string): Promise<void> {
+    const address = this.tables.get(batchId);
+    if (!address) {
+      return;
+    }
+    this.tables.delete(batchId);
+
+    const deactivated = await this.sender.send([
+      AddressLookupTableProgram.deactivateLookupTable({
+        lookupTable: address,
+        authority: this.authority.publicKey,
+      }),
+    ]);
+    if (!deactivated.success) {
+      stats.lastError = `deactivate ${address.toBase58()}: ${deactivated.error}`;
+      console.error(`[LookupTables] ${stats.lastError}`);
+      return;
+    }
+
+    setTimeout(() => void this.close(address), CLOSE_DELAY_MS).unref();
+  }
+
+  private async create(): Promise<PublicKey> {
+    const recentSlot = await this.connection.getSlot('finalized');
+    const [instruction, address] = AddressLookupTableProgram.createLookupTable({
+      authority: this.authority.publicKey,
+      payer: this.authority.publicKey,
+      recentSlot,
+    });
+    const result = await this.sender.send([instruction]);
+    if (!result.success) {
+      throw new Error(`create failed: ${result.error}`);
+    }
+    stats.created++;
+    return address;
+  }
+
+  private async fetch(address: PublicKey): Promise<AddressLookupTableAccount> {
+    const { value } = await this.connection.getAddressLookupTable(address, { commitment: 'confirmed' });
+    if (!value) {
+      throw new Error(`lookup table ${address.toBase58()} not found`);
+    }
+    return value;
+  }
+
+  private async close(address: PublicKey): Promise<void> {
+    const result = await this.sender.send([
+      AddressLookupTableProgram.closeLookupTable({
+        lookupTable: address,
+        authority: this.authority.publicKey,
+        recipient: this.authority.publicKey,
+      }),
+    ]);
+    if (result.success) {
+      stats.closed++;
+    } else {
+      stats.lastError = `close ${address.toBase58()}: ${result.error}`;
+      console.error(`[LookupTables] ${stats.lastError}`);
+    }
+  }
+}
+
+/**
+ * Whether tables are on, and creation/extension/close counters
+ */
+export function getLookupTableStatus() {
+  return {
+    enabled: lookupTablesEnabled(),
+    ...stats,
+  };
+}
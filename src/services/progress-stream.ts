@@ -0,0 +1,130 @@
+/**
+ * Fill Progress Stream
+ *
+ * gRPC server (proto/progress.proto) pushing batch fill progress to market
+ * frontends, so they don't poll /relay/order for every user. Watchers pick
+ * a batch, an order, a market, or everything, and resume from the last
+ * cursor they saw after a reconnect.
+ *
+ * Order ids are only echoed to the watcher of that order; everyone else
+ * sees batch-level counts.
+ *
+ * Runs inside the relay, since batches live in its memory. Started when
+ * PROGRESS_GRPC_PORT is set.
+ */
+
+import * as grpc from '@grpc/grpc-js';
+import * as protoLoader from '@grpc/proto-loader';
+import { dirname, join } from 'path';
+import { fileURLToPath } from 'url';
+import type { ProgressUpdate, RelayBatch } from '../types/relay.js';
+import { batchProgress, getAllBatches, getBatch, getOrder } from './batch.js';
+import { progressSince, progressSnapshot, subscribeProgress } from './progress.js';
+
+const __dirname = dirname(fileURLToPath(import.meta.url));
+
+// Both src/services and dist/services sit two levels below the repo root
+const PROTO_PATH = join(__dirname, '../../proto/progress.proto');
+
+function toMessage(update: ProgressUpdate, includeOrder: boolean) {
+  const closesInSecs = update.closesAt
+    ? Math.max(0, update.closesAt - Math.floor(update.timestamp / 1000))
+    : 0;
+  return {
+    cursor: update.cursor,
+    batch_id: update.batchId,
+    market_id: update.marketId,
+    side: update.side,
+    stage: update.stage,
+    order_id: includeOrder ? update.orderId ?? '' : '',
+    orders_queued: update.ordersQueued,
+    closes_at: update.closesAt ?? 0,
+    closes_in_secs: closesInSecs,
+    distributions_done: update.distributionsDone ?? 0,
+    distributions_total: update.distributionsTotal ?? 0,
+    error: update.error ?? '',
+    timestamp: update.timestamp,
+  };
+}
+
+function isActive(batch: RelayBatch): boolean {
+  return batch.status !== 'completed' && batch.status !== 'failed';
+}
+
+/* eslint-disable @typescript-eslint/no-explicit-any */
+const handlers: grpc.UntypedServiceImplementation = {
+  Watch: (call: grpc.ServerWritableStream<any, any>) => {
+    const { order_id: orderId, market_id: marketId } = call.request;
+    let batchId: string = call.request.batch_id;
+    const cursor = Number(call.request.cursor);
+
+    if ([batchId, orderId, marketId].filter(Boolean).length > 1) {
+      call.emit('error', {
+        code: grpc.status.INVALID_ARGUMENT,
+        details: 'Set at most one of batch_id, order_id, market_id',
+      });
+      return;
+    }
+    if (orderId) {
+      const order = getOrder(orderId);
+      if (!order) {
+        call.emit('error', { code: grpc.status.NOT_FOUND, details: `Unknown order ${orderId}` });
+        return;
+      }
+      batchId = order.batchId;
+    }
+    if (batchId && !getBatch(batchId)) {
+      call.emit('error', { code: grpc.status.NOT_FOUND, details: `Unknown batch ${batchId}` });
+      return;
+    }
+
+    const matches = (update: ProgressUpdate) =>
+      batchId ? update.batchId === batchId : !marketId || update.marketId === marketId;
+    const send = (update: ProgressUpdate) => {
+      call.write(toMessage(update, !!orderId && update.orderId === orderId));
+    };
+
+    const missed = progressSince(cursor);
+    if (missed) {
+      missed.filter(matches).forEach(send);
+    } else {
+      // Can't resume: start over from where each watched batch is now
+      const current = batchId
+        ? [getBatch(batchId)!]
+        : getAllBatches().filter(b => isActive(b) && (!marketId || b.marketId === marketId));
+      current.forEach(batch => send(progressSnapshot(batch, batchProgress(batch))));
+    }
+
+    const unsubscribe = subscribeProgress(update => {
+      if (matches(update)) {
+        send(update);
+      }
+    });
+    call.on('cancelled', unsubscribe);
+    call.on('close', unsubscribe);
+  },
+};
+/* eslint-enable @typescript-eslint/no-explicit-any */
+
+/**
+ * Serve the progress stream on `port`
+ */
+export function startProgressStream(port: number): void {
+  const definition = protoLoader.loadSync(PROTO_PATH, {
+    keepCase: true,
+    longs: Number,
+    defaults: true,
+  });
+  // eslint-disable-next-line @typescript-eslint/no-explicit-any
+  const proto = grpc.loadPackageDefinition(definition) as any;
+
+  const server = new grpc.Server();
+  server.addService(proto.obsidian.progress.v1.Progress.service, handlers);
+  server.bindAsync(`0.0.0.0:${port}`, grpc.ServerCredentials.createInsecure(), (error, bound) => {
+    if (error) {
+      console.error('[Progress] Failed to bind progress stream:', error);
+      return;
+    }
+    console.log(`[Progress] Fill progress stream listening on port ${bound}`);
+  });
+}
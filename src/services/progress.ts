@@ -0,0 +1,106 @@
+/**
+ * Fill Progress Log
+ *
+ * Batch lifecycle updates for the progress stream (see progress-stream.ts):
+ * orders queued, batch closing, execution, and distribution X/Y done.
+ *
+ * Each update gets a cursor that increases across all batches. The last
+ * PROGRESS_LOG_SIZE updates (default 10000) are kept so a reconnecting
+ * client can resume from its last cursor without missing any.
+ */
+
+import type { BatchStatus, ProgressStage, ProgressUpdate, RelayBatch } from '../types/relay.js';
+
+const LOG_SIZE = Math.max(1, parseInt(process.env.PROGRESS_LOG_SIZE || '10000'));
+
+const STAGES: Record<BatchStatus, ProgressStage> = {
+  collecting: 'queued',
+  ready: 'closed',
+  mpc_computing: 'mpc_computing',
+  executing: 'executing',
+  proving: 'proving',
+  mpc_distributing: 'distributing',
+  distributing: 'distributing',
+  completed: 'completed',
+  failed: 'failed',
+};
+
+export type ProgressFields = Pick<
+  ProgressUpdate,
+  'orderId' | 'closesAt' | 'distributionsDone' | 'distributionsTotal' | 'error'
+>;
+
+type Listener = (update: ProgressUpdate) => void;
+
+const log: ProgressUpdate[] = [];
+const listeners: Set<Listener> = new Set();
+let lastCursor = 0;
+
+/**
+ * Update describing the batch's current stage, with the given cursor
+ */
+function toUpdate(batch: RelayBatch, cursor: number, fields: ProgressFields): ProgressUpdate {
+  return {
+    cursor,
+    batchId: batch.id,
+    marketId: batch.marketId,
+    side: batch.side,
+    stage: STAGES[batch.status],
+    ordersQueued: batch.orderIds.length,
+    ...fields,
+    timestamp: Date.now(),
+  };
+}
+
+/**
+ * Log an update for the batch's current stage and push it to watchers
+ */
+export function publishProgress(batch: RelayBatch, fields: ProgressFields = {}): ProgressUpdate {
+  const update = toUpdate(batch, ++lastCursor, fields);
+  log.push(update);
+  if (log.length > LOG_SIZE) {
+    log.splice(0, log.length - LOG_SIZE);
+  }
+
+  for (const listener of listeners) {
+    try {
+      listener(update);
+    } catch (error) {
+      console.error('[Progress] Watcher failed:', error);
+    }
+  }
+  return update;
+}
+
+/**
+ * Current state of a batch for a watcher that can't resume, at the latest cursor
+ */
+export function progressSnapshot(batch: RelayBatch, fields: ProgressFields = {}): ProgressUpdate {
+  return toUpdate(batch, lastCursor, fields);
+}
+
+/**
+ * Updates after `cursor`, or undefined if the log can't resume from it:
+ * older than the oldest retained update, or ahead of the log (a cursor
+ * from before a relay restart)
+ */
+export function progressSince(cursor: number): ProgressUpdate[] | undefined {
+  if (cursor > lastCursor) {
+    return undefined;
+  }
+  const oldest = log.length > 0 ? log[0].cursor : lastCursor + 1;
+  if (cursor < oldest - 1) {
+    return undefined;
+  }
+  return log.slice(log.length - (lastCursor - cursor));
+}
+
+/**
+ * Call `listener` with every new update; returns the unsubscribe function
+ */
+export function subscribeProgress(listener: Listener): () => void {
+  listeners.add(listener);
+  return () => {
+    listeners.delete(listener);
+  };
+}
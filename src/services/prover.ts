@@ -21,7 +21,7 @@ import { poseidonHash2, poseidonHash5, poseidonHashN } from './poseidon.js';
 const __dirname = dirname(fileURLToPath(import.meta.url));
 
 // Calculate the WASM path for bb.js
-function getWasmPath(): string {
+export function getWasmPath(): string {
   // Find the bb.js module directory
   const bbJsDir = dirname(fileURLToPath(import.meta.resolve('@aztec/bb.js')));
   return join(bbJsDir, 'barretenberg_wasm', 'barretenberg-threads.wasm.gz');
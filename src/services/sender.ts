@@ -0,0 +1,629 @@
+/**
+ * Transaction Sender
+ *
+ * Lands relay transactions (share distributions, refunds, MPC calls) without
+ * manual babysitting:
+ * - Each attempt is rebroadcast until it confirms or its blockhash expires
+ * - The priority fee is a percentile of recent fees paid for the accounts the
+ *   transaction writes, and rises with every attempt
+ * - Expired attempts are rebuilt with a fresh blockhash, up to
+ *   SENDER_MAX_ATTEMPTS, after a jittered backoff
+ * - With RELAY_NONCE_ACCOUNT set, the final attempt uses that durable nonce
+ *   instead, so it cannot expire while the cluster is congested
+ * - Given lookup tables, transactions are sent as v0 and resolve accounts
+ *   through them (see lookup-tables.ts)
+ *
+ * Before each new attempt, the signatures of earlier ones are checked: an
+ * attempt that lands late is reported, never paid a second time.
+ *
+ * sendBundle sends several transactions as one Jito bundle instead (see
+ * jito.ts), escalating the tip rather than the priority fee on retries.
+ */
+
+import {
+  AddressLookupTableAccount,
+  ComputeBudgetProgram,
+  Connection,
+  Keypair,
+  PACKET_DATA_SIZE,
+  PublicKey,
+  SendTransactionError,
+  SignatureStatus,
+  Signer,
+  SystemProgram,
+  Transaction,
+  TransactionInstruction,
+  TransactionMessage,
+  VersionedTransaction,
+} from '@solana/web3.js';
+import bs58 from 'bs58';
+import { JitoClient, MAX_BUNDLE_TRANSACTIONS, jitoConfigFromEnv } from './jito.js';
+
+// Cached blockhashes are reused by attempts started within this window
+const BLOCKHASH_TTL_MS = 10_000;
+// An unconfirmed attempt is resent, and its status polled, this often
+const REBROADCAST_MS = 2_000;
+const INITIAL_BACKOFF_MS = 500;
+// Percentile points added to the fee estimate per retry
+const FEE_PERCENTILE_STEP = 10;
+// Each retry pays at least this multiple of the previous attempt's fee
+const FEE_ESCALATION = 1.5;
+// A durable-nonce attempt gives up after this long
+const NONCE_TIMEOUT_MS = 90_000;
+// getRecentPrioritizationFees accepts at most this many accounts
+const MAX_FEE_ACCOUNTS = 128;
+// SetComputeUnitPrice, replaced by the sender's own
+const SET_COMPUTE_UNIT_PRICE = 3;
+
+export interface SenderConfig {
+  maxAttempts: number;
+  /** Percentile of recent fees the first attempt pays */
+  feePercentile: number;
+  /** Bounds on the priority fee, in micro-lamports per compute unit */
+  minPriorityFee: number;
+  maxPriorityFee: number;
+  /** Nonce account whose authority is the relay wallet */
+  nonceAccount?: PublicKey;
+}
+
+export interface SendOptions {
+  /** Signers besides the fee payer */
+  signers?: Signer[];
+  computeUnitLimit?: number;
+  /** Send as a v0 transaction resolving accounts through these tables */
+  lookupTables?: AddressLookupTableAccount[];
+}
+
+export interface SendResult {
+  success: boolean;
+  signature?: string;
+  error?: string;
+  attempts: number;
+  /** Micro-lamports per compute unit paid by the last attempt */
+  priorityFee?: number;
+  usedNonce?: boolean;
+}
+
+/**
+ * A bundle entry: instructions the sender signs with its own blockhash, or
+ * a transaction built elsewhere (a DFlow swap) that keeps its blockhash
+ * and only gets the relay's signature
+ */
+export type BundleTransaction = TransactionInstruction[] | VersionedTransaction;
+
+export interface BundleOptions {
+  /** Compile instruction entries as v0 transactions against these tables */
+  lookupTables?: AddressLookupTableAccount[];
+}
+
+export interface BundleResult {
+  success: boolean;
+  bundleId?: string;
+  /** Signature of each transaction in the bundle, in order */
+  signatures: string[];
+  error?: string;
+  attempts: number;
+  tipLamports?: number;
+}
+
+type Outcome =
+  | { kind: 'confirmed' }
+  | { kind: 'expired' }
+  | { kind: 'failed'; error: string };
+
+const stats = {
+  sent: 0,
+  landed: 0,
+  failed: 0,
+  retries: 0,
+  nonceFallbacks: 0,
+  lastPriorityFee: 0,
+  lastError: undefined as string | undefined,
+};
+
+function envNumber(name: string, fallback: number): number {
+  const value = Number(process.env[name]);
+  return process.env[name] && Number.isFinite(value) ? value : fallback;
+}
+
+/**
+ * Sender settings from SENDER_* and RELAY_NONCE_ACCOUNT
+ */
+export function senderConfigFromEnv(): SenderConfig {
+  let nonceAccount: PublicKey | undefined;
+  if (process.env.RELAY_NONCE_ACCOUNT) {
+    try {
+      nonceAccount = new PublicKey(process.env.RELAY_NONCE_ACCOUNT);
+    } catch {
+      console.error('[Sender] Invalid RELAY_NONCE_ACCOUNT, durable-nonce fallback disabled');
+    }
+  }
+
+  return {
+    maxAttempts: Math.max(1, Math.floor(envNumber('SENDER_MAX_ATTEMPTS', 4))),
+    feePercentile: Math.min(100, Math.max(0, envNumber('SENDER_FEE_PERCENTILE', 75))),
+    minPriorityFee: envNumber('SENDER_MIN_PRIORITY_FEE', 1_000),
+    maxPriorityFee: envNumber('SENDER_MAX_PRIORITY_FEE', 2_000_000),
+    nonceAccount,
+  };
+}
+
+/**
+ * The `percentile` (0-100) of `fees`, nearest-rank; 0 if there are none
+ */
+export function feePercentile(fees: number[], percentile: number): number {
+  if (fees.length === 0) {
+    return 0;
+  }
+  const sorted = [...fees].sort((a, b) => a - b);
+  const rank = Math.ceil((percentile / 100) * sorted.length) - 1;
+  return sorted[Math.min(sorted.length - 1, Math.max(0, rank))];
+}
+
+/**
+ * Delay before retry `attempt` (1-based): exponential, scaled by a random
+ * factor in [0.5, 1.5) so retries of stuck transactions don't line up
+ */
+export function backoffWithJitter(attempt: number, random: () => number = Math.random): number {
+  return Math.round(INITIAL_BACKOFF_MS * 2 ** (attempt - 1) * (0.5 + random()));
+}
+
+/**
+ * Whether `instructions`, plus the sender's compute budget and nonce
+ * instructions, fit one signed transaction paid by `payer`
+ */
+export function fitsInPacket(
+  payer: PublicKey,
+  instructions: TransactionInstruction[],
+  lookupTables: AddressLookupTableAccount[] = []
+): boolean {
+  const budget = [
+    SystemProgram.nonceAdvance({ noncePubkey: PublicKey.default, authorizedPubkey: payer }),
+    ComputeBudgetProgram.setComputeUnitLimit({ units: 0 }),
+    ComputeBudgetProgram.setComputeUnitPrice({ microLamports: 0 }),
+  ];
+  try {
+    const message = new TransactionMessage({
+      payerKey: payer,
+      recentBlockhash: PublicKey.default.toBase58(),
+      instructions: [...budget, ...instructions],
+    }).compileToV0Message(lookupTables);
+    return new VersionedTransaction(message).serialize().length <= PACKET_DATA_SIZE;
+  } catch {
+    // Compiling or serializing throws on transactions far over the limit
+    return false;
+  }
+}
+
+function sleep(ms: number): Promise<void> {
+  return new Promise(resolve => setTimeout(resolve, ms));
+}
+
+function errorMessage(error: unknown): string {
+  return error instanceof Error ? error.message : 'Unknown error';
+}
+
+export class TransactionSender {
+  private blockhash?: { blockhash: string; lastValidBlockHeight: number; fetchedAt: number };
+  private jito?: JitoClient;
+
+  constructor(
+    private connection: Connection,
+    private payer: Keypair,
+    private config: SenderConfig = senderConfigFromEnv()
+  ) {}
+
+  /**
+   * Send `instructions` paid by the relay wallet, retrying until one attempt
+   * confirms or every attempt has expired. Never throws.
+   *
+   * Any SetComputeUnitPrice among `instructions` is dropped for the
+   * sender's estimate.
+   */
+  async send(instructions: TransactionInstruction[], options: SendOptions = {}): Promise<SendResult> {
+    instructions = instructions.filter(
+      ix => !(ix.programId.equals(ComputeBudgetProgram.programId) && ix.data[0] === SET_COMPUTE_UNIT_PRICE)
+    );
+
+    const signatures: string[] = [];
+    let fee = 0;
+    let usedNonce = false;
+    let lastError = 'no attempt made';
+
+    for (let attempt = 1; attempt <= this.config.maxAttempts; attempt++) {
+      if (attempt > 1) {
+        stats.retries++;
+        await sleep(backoffWithJitter(attempt - 1));
+
+        // An earlier attempt may have landed after we stopped watching it
+        const landed = await this.findLanded(signatures);
+        if (landed) {
+          return this.succeeded(landed, attempt - 1, fee, usedNonce);
+        }
+      }
+
+      usedNonce = Boolean(this.config.nonceAccount) && attempt > 1 && attempt === this.config.maxAttempts;
+      fee = Math.max(
+        await this.estimatePriorityFee(instructions, attempt),
+        Math.ceil(fee * FEE_ESCALATION)
+      );
+      fee = Math.min(fee, this.config.maxPriorityFee);
+
+      let outcome: Outcome;
+      try {
+        const budget = [
+          ...(options.computeUnitLimit
+            ? [ComputeBudgetProgram.setComputeUnitLimit({ units: options.computeUnitLimit })]
+            : []),
+          ComputeBudgetProgram.setComputeUnitPrice({ microLamports: fee }),
+        ];
+        if (usedNonce) {
+          stats.nonceFallbacks++;
+          outcome = await this.attemptWithNonce(budget, instructions, options, signatures);
+        } else {
+          outcome = await this.attemptWithBlockhash(budget, instructions, options, signatures, attempt > 1);
+        }
+      } catch (error) {
+        outcome = { kind: 'failed', error: errorMessage(error) };
+      }
+
+      if (outcome.kind === 'confirmed') {
+        return this.succeeded(signatures[signatures.length - 1], attempt, fee, usedNonce);
+      }
+      if (outcome.kind === 'failed') {
+        // Failures other than expiry (insufficient funds, program errors) won't pass on retry
+        lastError = outcome.error;
+        const landed = await this.findLanded(signatures);
+        if (landed) {
+          return this.succeeded(landed, attempt, fee, usedNonce);
+        }
+        return this.failed(lastError, attempt, fee, usedNonce);
+      }
+      lastError = `attempt ${attempt} expired unconfirmed at ${fee} micro-lamports/CU`;
+      console.warn(`[Sender] ${lastError}`);
+    }
+
+    const landed = await this.findLanded(signatures);
+    if (landed) {
+      return this.succeeded(landed, this.config.maxAttempts, fee, usedNonce);
+    }
+    return this.failed(lastError, this.config.maxAttempts, fee, usedNonce);
+  }
+
+  /**
+   * Send `transactions` as one Jito bundle, in order, with a tip in the last
+   * one, retrying with a fresh blockhash and a higher tip until the bundle
+   * lands or SENDER_MAX_ATTEMPTS bundles have failed. Never throws.
+   *
+   * Bundles are all-or-nothing: either every transaction landed, or none.
+   */
+  async sendBundle(transactions: BundleTransaction[], options: BundleOptions = {}): Promise<BundleResult> {
+    const config = jitoConfigFromEnv();
+    if (!config) {
+      return { success: false, signatures: [], error: 'JITO_BLOCK_ENGINE_URL is not set', attempts: 0 };
+    }
+    const lastIsPrebuilt = transactions[transactions.length - 1] instanceof VersionedTransaction;
+    const count = transactions.length + (lastIsPrebuilt ? 1 : 0);
+    if (transactions.length === 0 || count > MAX_BUNDLE_TRANSACTIONS) {
+      return {
+        success: false,
+        signatures: [],
+        error: `bundle needs ${count} transactions, at most ${MAX_BUNDLE_TRANSACTIONS} allowed`,
+        attempts: 0,
+      };
+    }
+    const jito = (this.jito ??= new JitoClient(config));
+
+    let tip = config.tipLamports;
+    let signatures: string[] = [];
+    let bundleId: string | undefined;
+    let lastError = 'no attempt made';
+
+    for (let attempt = 1; attempt <= this.config.maxAttempts; attempt++) {
+      if (attempt > 1) {
+        stats.retries++;
+        await sleep(backoffWithJitter(attempt - 1));
+
+        // The bundle is atomic: its last transaction landing means all did
+        if (await this.findLanded(signatures.slice(-1))) {
+          return this.bundleSucceeded(bundleId, signatures, attempt - 1, tip);
+        }
+        tip = Math.min(config.maxTipLamports, Math.ceil(tip * FEE_ESCALATION));
+      }
+
+      let outcome: Outcome;
+      const attemptSignatures: string[] = [];
+      try {
+        const { blockhash, lastValidBlockHeight } = await this.latestBlockhash(attempt > 1);
+        const tipIx = SystemProgram.transfer({
+          fromPubkey: this.payer.publicKey,
+          toPubkey: await jito.tipAccount(),
+          lamports: tip,
+        });
+
+        const last = transactions[transactions.length - 1];
+        const entries: BundleTransaction[] = last instanceof VersionedTransaction
+          ? [...transactions, [tipIx]]
+          : [...transactions.slice(0, -1), [...last, tipIx]];
+        const raw = entries.map(entry => {
+          if (entry instanceof VersionedTransaction) {
+            entry.sign([this.payer]);
+            attemptSignatures.push(bs58.encode(entry.signatures[0]));
+            return entry.serialize();
+          }
+          return this.sign(entry, blockhash, { lookupTables: options.lookupTables }, attemptSignatures);
+        });
+
+        signatures = attemptSignatures;
+        bundleId = await jito.sendBundle(raw, tip);
+        stats.sent += raw.length;
+        outcome = await this.watchBundle(bundleId, signatures[signatures.length - 1], lastValidBlockHeight);
+      } catch (error) {
+        outcome = { kind: 'failed', error: errorMessage(error) };
+      }
+
+      if (outcome.kind === 'confirmed') {
+        return this.bundleSucceeded(bundleId, signatures, attempt, tip);
+      }
+      // A failed bundle changed nothing on-chain, so unlike a failed
+      // transaction it is always worth another attempt
+      lastError = outcome.kind === 'failed'
+        ? outcome.error
+        : `bundle attempt ${attempt} expired unlanded with a ${tip} lamport tip`;
+      console.warn(`[Sender] ${lastError}`);
+    }
+
+    if (await this.findLanded(signatures.slice(-1))) {
+      return this.bundleSucceeded(bundleId, signatures, this.config.maxAttempts, tip);
+    }
+    stats.failed++;
+    stats.lastError = lastError;
+    jito.recordOutcome(false, lastError);
+    console.error(`[Sender] Giving up on bundle after ${this.config.maxAttempts} attempt(s): ${lastError}`);
+    return { success: false, bundleId, signatures, error: lastError, attempts: this.config.maxAttempts, tipLamports: tip };
+  }
+
+  /**
+   * Fee for `attempt` from recent fees paid for the accounts `instructions` write
+   */
+  async estimatePriorityFee(instructions: TransactionInstruction[], attempt: number): Promise<number> {
+    const writable = new Map<string, PublicKey>();
+    for (const ix of instructions) {
+      for (const key of ix.keys) {
+        if (key.isWritable) {
+          writable.set(key.pubkey.toBase58(), key.pubkey);
+        }
+      }
+    }
+
+    let estimate = 0;
+    try {
+      const recent = await this.connection.getRecentPrioritizationFees({
+        lockedWritableAccounts: [...writable.values()].slice(0, MAX_FEE_ACCOUNTS),
+      });
+      const percentile = Math.min(100, this.config.feePercentile + (attempt - 1) * FEE_PERCENTILE_STEP);
+      estimate = feePercentile(recent.map(f => f.prioritizationFee), percentile);
+    } catch (error) {
+      // Fall back to the floor; retries still escalate from it
+      console.warn(`[Sender] Priority fee estimate failed: ${errorMessage(error)}`);
+    }
+    return Math.min(this.config.maxPriorityFee, Math.max(this.config.minPriorityFee, estimate));
+  }
+
+  private async latestBlockhash(fresh: boolean) {
+    const cached = this.blockhash;
+    if (!fresh && cached && Date.now() - cached.fetchedAt < BLOCKHASH_TTL_MS) {
+      return cached;
+    }
+    const latest = await this.connection.getLatestBlockhash('confirmed');
+    this.blockhash = { ...latest, fetchedAt: Date.now() };
+    return this.blockhash;
+  }
+
+  private sign(
+    instructions: TransactionInstruction[],
+    recentBlockhash: string,
+    options: SendOptions,
+    signatures: string[]
+  ): Buffer {
+    const signers = [this.payer, ...(options.signers ?? [])];
+    if (options.lookupTables) {
+      const message = new TransactionMessage({
+        payerKey: this.payer.publicKey,
+        recentBlockhash,
+        instructions,
+      }).compileToV0Message(options.lookupTables);
+      const tx = new VersionedTransaction(message);
+      tx.sign(signers);
+      signatures.push(bs58.encode(tx.signatures[0]));
+      return Buffer.from(tx.serialize());
+    }
+
+    const tx = new Transaction().add(...instructions);
+    tx.recentBlockhash = recentBlockhash;
+    tx.feePayer = this.payer.publicKey;
+    tx.sign(...signers);
+    signatures.push(bs58.encode(tx.signature!));
+    return tx.serialize();
+  }
+
+  private async attemptWithBlockhash(
+    budget: TransactionInstruction[],
+    instructions: TransactionInstruction[],
+    options: SendOptions,
+    signatures: string[],
+    fresh: boolean
+  ): Promise<Outcome> {
+    const { blockhash, lastValidBlockHeight } = await this.latestBlockhash(fresh);
+    const raw = this.sign([...budget, ...instructions], blockhash, options, signatures);
+    return this.broadcast(raw, signatures[signatures.length - 1], async () =>
+      (await this.connection.getBlockHeight('confirmed')) > lastValidBlockHeight
+    );
+  }
+
+  private async attemptWithNonce(
+    budget: TransactionInstruction[],
+    instructions: TransactionInstruction[],
+    options: SendOptions,
+    signatures: string[]
+  ): Promise<Outcome> {
+    const noncePubkey = this.config.nonceAccount!;
+    const nonce = await this.connection.getNonce(noncePubkey, 'confirmed');
+    if (!nonce) {
+      return { kind: 'failed', error: `nonce account ${noncePubkey.toBase58()} not found` };
+    }
+    if (!nonce.authorizedPubkey.equals(this.payer.publicKey)) {
+      return { kind: 'failed', error: `nonce account ${noncePubkey.toBase58()} is not authorized to the relay wallet` };
+    }
+
+    // nonceAdvance must be the first instruction
+    const advance = SystemProgram.nonceAdvance({ noncePubkey, authorizedPubkey: this.payer.publicKey });
+    const raw = this.sign([advance, ...budget, ...instructions], nonce.nonce, options, signatures);
+    const deadline = Date.now() + NONCE_TIMEOUT_MS;
+    return this.broadcast(raw, signatures[signatures.length - 1], async () => {
+      // Another transaction advancing the nonce also invalidates this one
+      const current = await this.connection.getNonce(noncePubkey, 'confirmed');
+      return Date.now() > deadline || current?.nonce !== nonce.nonce;
+    });
+  }
+
+  /**
+   * Resend `raw` until it confirms, fails or `expired()` says it never will
+   */
+  private async broadcast(raw: Buffer, signature: string, expired: () => Promise<boolean>): Promise<Outcome> {
+    stats.sent++;
+    let first = true;
+    for (;;) {
+      try {
+        // Preflight once so program errors surface before waiting on expiry
+        await this.connection.sendRawTransaction(raw, { skipPreflight: !first, maxRetries: 0 });
+      } catch (error) {
+        const message = errorMessage(error);
+        if (error instanceof SendTransactionError && /blockhash not found/i.test(message)) {
+          return { kind: 'expired' };
+        }
+        if (first) {
+          return { kind: 'failed', error: message };
+        }
+        // Rebroadcast errors are transient; the status poll decides
+      }
+      first = false;
+
+      await sleep(REBROADCAST_MS);
+      const status = await this.signatureStatus(signature);
+      if (status?.err) {
+        return { kind: 'failed', error: `transaction ${signature} failed: ${JSON.stringify(status.err)}` };
+      }
+      if (status?.confirmationStatus === 'confirmed' || status?.confirmationStatus === 'finalized') {
+        return { kind: 'confirmed' };
+      }
+      if (await expired()) {
+        // Checked after expiry too: it may have landed in the last blocks
+        const last = await this.signatureStatus(signature);
+        if (last && !last.err && last.confirmationStatus !== 'processed') {
+          return { kind: 'confirmed' };
+        }
+        return { kind: 'expired' };
+      }
+    }
+  }
+
+  /**
+   * Poll bundle `bundleId`, whose last transaction is `signature`, until it
+   * confirms, fails, or its blockhash expires
+   */
+  private async watchBundle(bundleId: string, signature: string, lastValidBlockHeight: number): Promise<Outcome> {
+    for (;;) {
+      await sleep(REBROADCAST_MS);
+      const status = await this.signatureStatus(signature);
+      if (status && !status.err && status.confirmationStatus !== 'processed') {
+        return { kind: 'confirmed' };
+      }
+
+      let bundle: string;
+      try {
+        bundle = await this.jito!.bundleStatus(bundleId);
+      } catch {
+        // The signature poll and expiry still decide
+        bundle = 'Pending';
+      }
+      if (bundle === 'Failed') {
+        return { kind: 'failed', error: `bundle ${bundleId} failed to land` };
+      }
+      if ((await this.connection.getBlockHeight('confirmed')) > lastValidBlockHeight) {
+        const last = await this.signatureStatus(signature);
+        if (last && !last.err && last.confirmationStatus !== 'processed') {
+          return { kind: 'confirmed' };
+        }
+        return { kind: 'expired' };
+      }
+    }
+  }
+
+  private async signatureStatus(signature: string): Promise<SignatureStatus | null> {
+    try {
+      const { value } = await this.connection.getSignatureStatuses([signature]);
+      return value[0];
+    } catch {
+      // Treated as not yet seen; the next poll asks again
+      return null;
+    }
+  }
+
+  /**
+   * First of `signatures` confirmed without error
+   */
+  private async findLanded(signatures: string[]): Promise<string | undefined> {
+    if (signatures.length === 0) {
+      return undefined;
+    }
+    try {
+      const { value } = await this.connection.getSignatureStatuses(signatures, { searchTransactionHistory: true });
+      const index = value.findIndex(
+        status => status && !status.err && status.confirmationStatus !== 'processed'
+      );
+      return index >= 0 ? signatures[index] : undefined;
+    } catch {
+      return undefined;
+    }
+  }
+
+  private succeeded(signature: string, attempts: number, priorityFee: number, usedNonce: boolean): SendResult {
+    stats.landed++;
+    stats.lastPriorityFee = priorityFee;
+    return { success: true, signature, attempts, priorityFee, usedNonce };
+  }
+
+  private bundleSucceeded(
+    bundleId: string | undefined,
+    signatures: string[],
+    attempts: number,
+    tipLamports: number
+  ): BundleResult {
+    stats.landed += signatures.length;
+    this.jito!.recordOutcome(true);
+    return { success: true, bundleId, signatures, attempts, tipLamports };
+  }
+
+  private failed(error: string, attempts: number, priorityFee: number, usedNonce: boolean): SendResult {
+    stats.failed++;
+    stats.lastError = error;
+    console.error(`[Sender] Giving up after ${attempts} attempt(s): ${error}`);
+    return { success: false, error, attempts, priorityFee, usedNonce };
+  }
+}
+
+/**
+ * Sender settings (nonce account only, no key material) and counters
+ */
+export function getSenderStatus() {
+  const config = senderConfigFromEnv();
+  return {
+    maxAttempts: config.maxAttempts,
+    feePercentile: config.feePercentile,
+    minPriorityFee: config.minPriorityFee,
+    maxPriorityFee: config.maxPriorityFee,
+    nonceAccount: config.nonceAccount?.toBase58() ?? null,
+    ...stats,
+  };
+}
@@ -2,9 +2,10 @@ import {
   Connection,
   Keypair,
   PublicKey,
+  SystemProgram,
+  TokenBalance,
   Transaction,
   VersionedTransaction,
-  sendAndConfirmTransaction,
   LAMPORTS_PER_SOL,
 } from '@solana/web3.js';
 import {
@@ -12,6 +13,8 @@ import {
   getAccount,
   createTransferInstruction,
   createAssociatedTokenAccountInstruction,
+  createAssociatedTokenAccountIdempotentInstruction,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
   TOKEN_PROGRAM_ID,
   TOKEN_2022_PROGRAM_ID,
   getMint,
@@ -21,6 +24,9 @@ import { readFile, writeFile } from 'fs/promises';
 import { existsSync } from 'fs';
 import { join, dirname } from 'path';
 import { fileURLToPath } from 'url';
+import { TransactionSender, fitsInPacket, type BundleResult } from './sender.js';
+import { MAX_BUNDLE_TRANSACTIONS } from './jito.js';
+import { LookupTableManager } from './lookup-tables.js';
 
 const __dirname = dirname(fileURLToPath(import.meta.url));
 
@@ -42,6 +48,11 @@ export interface TransferResult {
   error?: string;
 }
 
+export interface TokenTransfer {
+  recipient: string;
+  amount: number;
+}
+
 /**
  * Relay Wallet Service
  * Manages the Solana wallet for collecting funds and distributing shares
@@ -49,10 +60,14 @@ export interface TransferResult {
 export class RelayWallet {
   private keypair: Keypair;
   private connection: Connection;
+  private sender: TransactionSender;
+  private lookupTables: LookupTableManager;
 
   constructor(keypair: Keypair, rpcEndpoint?: string) {
     this.keypair = keypair;
     this.connection = new Connection(rpcEndpoint || RPC_ENDPOINT, 'confirmed');
+    this.sender = new TransactionSender(this.connection, keypair);
+    this.lookupTables = new LookupTableManager(this.connection, keypair, this.sender);
   }
 
   /**
@@ -154,25 +169,13 @@ export class RelayWallet {
   }
 
   /**
-   * Sign and send a transaction
+   * Sign and send a transaction's instructions through the sender
+   * (priority fee, retries); its blockhash and fee payer are replaced
    * Used by arcium-mpc.ts for MPC transactions
    */
   async signAndSendTransaction(tx: Transaction): Promise<{ success: boolean; signature?: string; error?: string }> {
-    try {
-      const { blockhash } = await this.connection.getLatestBlockhash();
-      tx.recentBlockhash = blockhash;
-      tx.feePayer = this.keypair.publicKey;
-
-      const signature = await this.connection.sendTransaction(tx, [this.keypair]);
-      await this.connection.confirmTransaction(signature, 'confirmed');
-
-      return { success: true, signature };
-    } catch (error) {
-      return {
-        success: false,
-        error: error instanceof Error ? error.message : 'Unknown error',
-      };
-    }
+    const { success, signature, error } = await this.sender.send(tx.instructions);
+    return { success, signature, error };
   }
 
   /**
@@ -197,15 +200,8 @@ export class RelayWallet {
         TOKEN_PROGRAM_ID
       );
 
-      // Build and send transaction
-      const transaction = new Transaction().add(transferIx);
-      const signature = await sendAndConfirmTransaction(
-        this.connection,
-        transaction,
-        [this.keypair]
-      );
-
-      return { success: true, signature };
+      const { success, signature, error } = await this.sender.send([transferIx]);
+      return { success, signature, error };
     } catch (error) {
       return {
         success: false,
@@ -228,22 +224,7 @@ export class RelayWallet {
       const mintPubkey = new PublicKey(tokenMint);
       const recipientPubkey = new PublicKey(recipient);
       const amountInSmallestUnit = BigInt(Math.floor(amount * Math.pow(10, decimals)));
-
-      // Detect if this is Token-2022 or regular SPL token
-      let programId = TOKEN_PROGRAM_ID;
-      try {
-        // Try to get mint info - will throw if using wrong program
-        await getMint(this.connection, mintPubkey, 'confirmed', TOKEN_PROGRAM_ID);
-      } catch {
-        // Try Token-2022
-        try {
-          await getMint(this.connection, mintPubkey, 'confirmed', TOKEN_2022_PROGRAM_ID);
-          programId = TOKEN_2022_PROGRAM_ID;
-          console.log(`Using Token-2022 program for mint ${tokenMint}`);
-        } catch {
-          throw new Error(`Could not find mint ${tokenMint} on either token program`);
-        }
-      }
+      const programId = await this.tokenProgramFor(mintPubkey);
 
       // Get ATAs with correct program
       const senderAta = await getAssociatedTokenAddress(
@@ -287,14 +268,8 @@ export class RelayWallet {
       );
       transaction.add(transferIx);
 
-      // Build and send transaction
-      const signature = await sendAndConfirmTransaction(
-        this.connection,
-        transaction,
-        [this.keypair]
-      );
-
-      return { success: true, signature };
+      const { success, signature, error } = await this.sender.send(transaction.instructions);
+      return { success, signature, error };
     } catch (error) {
       return {
         success: false,
@@ -303,6 +278,173 @@ export class RelayWallet {
     }
   }
 
+  /**
+   * Transfer an SPL token to many recipients, packing as many transfers into
+   * each transaction as fit. With `batchId`, transactions are v0 and name
+   * accounts through that batch's lookup table, which fits several times
+   * more transfers. One result per transfer, in order.
+   */
+  async transferTokenMany(
+    tokenMint: string,
+    transfers: TokenTransfer[],
+    decimals: number = 6,
+    batchId?: string
+  ): Promise<(TransferResult & { recipient: string })[]> {
+    try {
+      const { groups, lookupTables } = await this.packTokenTransfers(tokenMint, transfers, decimals, batchId);
+
+      const results: (TransferResult & { recipient: string })[] = [];
+      for (const group of groups) {
+        const { success, signature, error } = await this.sender.send(
+          group.flatMap(t => t.instructions),
+          { lookupTables }
+        );
+        for (const transfer of group) {
+          results.push({ recipient: transfer.recipient, success, signature, error });
+        }
+      }
+      console.log(`Sent ${transfers.length} transfers in ${groups.length} transaction(s)${lookupTables ? ' via lookup table' : ''}`);
+      return results;
+    } catch (error) {
+      const message = error instanceof Error ? error.message : 'Transfer failed';
+      return transfers.map(transfer => ({ recipient: transfer.recipient, success: false, error: message }));
+    }
+  }
+
+  /**
+   * Send `swap` and, after it, the leading `transfers` of an SPL token as one
+   * Jito bundle, so nothing can land between the trade and its payouts.
+   * Transfers are packed as in transferTokenMany into the transactions the
+   * bundle has left; `included` says how many made it in, and the rest are
+   * left to the caller. Each included transfer carries the signature of the
+   * transaction that paid it.
+   */
+  async bundleWithTokenTransfers(
+    swap: VersionedTransaction,
+    tokenMint: string,
+    transfers: TokenTransfer[],
+    decimals: number = 6,
+    batchId?: string
+  ): Promise<{ bundle: BundleResult; included: (TransferResult & { recipient: string })[] }> {
+    // Packing reserves room for a nonce advance and a compute budget, more
+    // than the tip transfer the sender appends to the last transaction
+    const { groups, lookupTables } = transfers.length > 0
+      ? await this.packTokenTransfers(tokenMint, transfers, decimals, batchId)
+      : { groups: [], lookupTables: undefined };
+    const bundled = groups.slice(0, MAX_BUNDLE_TRANSACTIONS - 1);
+
+    const bundle = await this.sender.sendBundle(
+      [swap, ...bundled.map(group => group.flatMap(t => t.instructions))],
+      { lookupTables }
+    );
+    const included = bundled.flatMap((group, i) => group.map(transfer => ({
+      recipient: transfer.recipient,
+      success: bundle.success,
+      signature: bundle.signatures[i + 1],
+      error: bundle.error,
+    })));
+    return { bundle, included };
+  }
+
+  /**
+   * How much of `tokenMint` the relay gained in transaction `signature`, in
+   * base units, from the transaction's own balance records
+   */
+  async tokenReceived(signature: string, tokenMint: string): Promise<bigint> {
+    const tx = await this.connection.getTransaction(signature, {
+      commitment: 'confirmed',
+      maxSupportedTransactionVersion: 0,
+    });
+    if (!tx?.meta) {
+      throw new Error(`transaction ${signature} not found`);
+    }
+    const owner = this.keypair.publicKey.toBase58();
+    const balance = (balances: TokenBalance[] | null | undefined) =>
+      BigInt(balances?.find(b => b.mint === tokenMint && b.owner === owner)?.uiTokenAmount.amount ?? '0');
+    return balance(tx.meta.postTokenBalances) - balance(tx.meta.preTokenBalances);
+  }
+
+  /**
+   * Instructions for each of `transfers`, grouped greedily into transactions
+   * that fit one packet, through `batchId`'s lookup table when it has one
+   */
+  private async packTokenTransfers(
+    tokenMint: string,
+    transfers: TokenTransfer[],
+    decimals: number,
+    batchId?: string
+  ) {
+    const payer = this.keypair.publicKey;
+    const mintPubkey = new PublicKey(tokenMint);
+    const programId = await this.tokenProgramFor(mintPubkey);
+    const senderAta = await getAssociatedTokenAddress(mintPubkey, payer, false, programId);
+
+    const shared = [mintPubkey, senderAta, programId, ASSOCIATED_TOKEN_PROGRAM_ID, SystemProgram.programId];
+    const prepared = await Promise.all(transfers.map(async transfer => {
+      const recipientPubkey = new PublicKey(transfer.recipient);
+      const recipientAta = await getAssociatedTokenAddress(mintPubkey, recipientPubkey, false, programId);
+      shared.push(recipientPubkey, recipientAta);
+      return {
+        recipient: transfer.recipient,
+        instructions: [
+          // Idempotent, so a retried transaction can't fail on an ATA created by an earlier attempt
+          createAssociatedTokenAccountIdempotentInstruction(payer, recipientAta, recipientPubkey, mintPubkey, programId),
+          createTransferInstruction(
+            senderAta,
+            recipientAta,
+            payer,
+            BigInt(Math.floor(transfer.amount * Math.pow(10, decimals))),
+            [],
+            programId
+          ),
+        ],
+      };
+    }));
+
+    const table = batchId ? await this.lookupTables.ensure(batchId, shared) : undefined;
+    const lookupTables = table ? [table] : undefined;
+
+    // Greedy packing: start a new transaction when the next transfer would overflow
+    const groups: (typeof prepared)[] = [];
+    for (const transfer of prepared) {
+      const current = groups[groups.length - 1];
+      const candidate = [...(current ?? []), transfer].flatMap(t => t.instructions);
+      if (current && fitsInPacket(payer, candidate, lookupTables)) {
+        current.push(transfer);
+      } else {
+        groups.push([transfer]);
+      }
+    }
+    return { groups, lookupTables };
+  }
+
+  /**
+   * Deactivate and later close `batchId`'s lookup table once its transfers are done
+   */
+  async releaseLookupTable(batchId: string): Promise<void> {
+    await this.lookupTables.release(batchId);
+  }
+
+  /**
+   * Token program owning `mint`: regular SPL or Token-2022 (prediction market tokens)
+   */
+  private async tokenProgramFor(mint: PublicKey): Promise<PublicKey> {
+    try {
+      // Try to get mint info - will throw if using wrong program
+      await getMint(this.connection, mint, 'confirmed', TOKEN_PROGRAM_ID);
+      return TOKEN_PROGRAM_ID;
+    } catch {
+      // Try Token-2022
+      try {
+        await getMint(this.connection, mint, 'confirmed', TOKEN_2022_PROGRAM_ID);
+        console.log(`Using Token-2022 program for mint ${mint.toBase58()}`);
+        return TOKEN_2022_PROGRAM_ID;
+      } catch {
+        throw new Error(`Could not find mint ${mint.toBase58()} on either token program`);
+      }
+    }
+  }
+
   /**
    * Sign a legacy transaction (for DFlow integration)
    */
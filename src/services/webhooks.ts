@@ -0,0 +1,207 @@
+/**
+ * Webhook Delivery Service
+ *
+ * POSTs batch lifecycle events to configured HTTP endpoints so market
+ * frontends and notification services don't each need their own chain indexer.
+ *
+ * Configured via WEBHOOKS, a JSON array of subscriptions:
+ *   [{ "url": "https://...", "secret": "...", "events": ["BatchCompleted"], "wallets": ["..."] }]
+ *
+ * Every request carries:
+ * - X-Obsidian-Event: event type
+ * - X-Obsidian-Delivery: event id (stable across retries, use it to dedupe)
+ * - X-Obsidian-Signature: t=<unix seconds>,v1=<hex HMAC-SHA256 of "<t>.<body>">
+ *
+ * Delivery is fire-and-forget: a slow or failing endpoint never blocks batch
+ * execution. Failed deliveries are retried with exponential backoff.
+ */
+
+import { createHmac, randomUUID, timingSafeEqual } from 'crypto';
+import type {
+  WebhookEventType,
+  WebhookPayload,
+  WebhookSubscription,
+} from '../types/relay.js';
+
+const MAX_ATTEMPTS = 4;
+const INITIAL_BACKOFF_MS = 1000;
+const REQUEST_TIMEOUT_MS = 10_000;
+
+// Signatures older than this are rejected by verifyWebhookSignature
+const SIGNATURE_TOLERANCE_SECONDS = 300;
+
+let subscriptions: WebhookSubscription[] | undefined;
+
+const stats = {
+  delivered: 0,
+  failed: 0,
+  lastError: undefined as string | undefined,
+};
+
+/**
+ * Parse WEBHOOKS once; an invalid config disables webhooks instead of crashing the relay
+ */
+function getSubscriptions(): WebhookSubscription[] {
+  if (subscriptions) {
+    return subscriptions;
+  }
+
+  subscriptions = [];
+  const raw = process.env.WEBHOOKS;
+  if (!raw) {
+    return subscriptions;
+  }
+
+  try {
+    const parsed = JSON.parse(raw);
+    if (!Array.isArray(parsed)) {
+      throw new Error('expected a JSON array');
+    }
+    for (const sub of parsed) {
+      if (typeof sub?.url !== 'string' || typeof sub?.secret !== 'string' || !sub.secret) {
+        throw new Error('each webhook needs a url and a non-empty secret');
+      }
+      new URL(sub.url);
+      subscriptions.push({
+        url: sub.url,
+        secret: sub.secret,
+        events: sub.events,
+        wallets: sub.wallets,
+      });
+    }
+    console.log(`[Webhooks] ${subscriptions.length} endpoint(s) configured`);
+  } catch (error) {
+    subscriptions = [];
+    console.error(`[Webhooks] Invalid WEBHOOKS config, webhooks disabled: ${error instanceof Error ? error.message : error}`);
+  }
+
+  return subscriptions;
+}
+
+/**
+ * Signature header value for a body sent at `timestamp` (unix seconds)
+ */
+export function signWebhook(secret: string, timestamp: number, body: string): string {
+  const digest = createHmac('sha256', secret).update(`${timestamp}.${body}`).digest('hex');
+  return `t=${timestamp},v1=${digest}`;
+}
+
+/**
+ * Check an X-Obsidian-Signature header against the raw request body (for receivers)
+ */
+export function verifyWebhookSignature(
+  secret: string,
+  header: string,
+  body: string,
+  now: number = Math.floor(Date.now() / 1000)
+): boolean {
+  const parts = Object.fromEntries(
+    header.split(',').map(part => part.split('=', 2) as [string, string])
+  );
+  const timestamp = parseInt(parts.t);
+  if (!Number.isFinite(timestamp) || !parts.v1) {
+    return false;
+  }
+  if (Math.abs(now - timestamp) > SIGNATURE_TOLERANCE_SECONDS) {
+    return false;
+  }
+
+  const expected = Buffer.from(signWebhook(secret, timestamp, body).split('v1=')[1], 'hex');
+  const actual = Buffer.from(parts.v1, 'hex');
+  return expected.length === actual.length && timingSafeEqual(expected, actual);
+}
+
+function wants(sub: WebhookSubscription, type: WebhookEventType, wallet?: string): boolean {
+  if (sub.events && !sub.events.includes(type)) {
+    return false;
+  }
+  if (wallet && sub.wallets && !sub.wallets.includes(wallet)) {
+    return false;
+  }
+  return true;
+}
+
+async function deliver(sub: WebhookSubscription, payload: WebhookPayload): Promise<void> {
+  const body = JSON.stringify(payload);
+  let lastError = '';
+
+  for (let attempt = 1; attempt <= MAX_ATTEMPTS; attempt++) {
+    try {
+      const response = await fetch(sub.url, {
+        method: 'POST',
+        headers: {
+          'Content-Type': 'application/json',
+          'X-Obsidian-Event': payload.type,
+          'X-Obsidian-Delivery': payload.id,
+          // Re-signed per attempt so retries stay inside the receiver's tolerance window
+          'X-Obsidian-Signature': signWebhook(sub.secret, Math.floor(Date.now() / 1000), body),
+        },
+        body,
+        signal: AbortSignal.timeout(REQUEST_TIMEOUT_MS),
+      });
+
+      if (response.ok) {
+        stats.delivered++;
+        return;
+      }
+
+      lastError = `HTTP ${response.status}`;
+      // Client errors other than rate limiting won't succeed on retry
+      if (response.status < 500 && response.status !== 429) {
+        break;
+      }
+    } catch (error) {
+      lastError = error instanceof Error ? error.message : 'Unknown error';
+    }
+
+    if (attempt < MAX_ATTEMPTS) {
+      await new Promise(resolve => setTimeout(resolve, INITIAL_BACKOFF_MS * 2 ** (attempt - 1)));
+    }
+  }
+
+  stats.failed++;
+  stats.lastError = `${payload.type} ${payload.id} -> ${new URL(sub.url).host}: ${lastError}`;
+  console.error(`[Webhooks] Delivery failed: ${stats.lastError}`);
+}
+
+/**
+ * Queue `type` for every subscribed endpoint. Never throws and never waits on delivery.
+ *
+ * `wallet` scopes per-user events (DistributionExecuted) to subscriptions that
+ * list that wallet, or that set no wallet filter.
+ */
+export function emitWebhook(
+  type: WebhookEventType,
+  data: Record<string, unknown>,
+  wallet?: string
+): void {
+  const targets = getSubscriptions().filter(sub => wants(sub, type, wallet));
+  if (targets.length === 0) {
+    return;
+  }
+
+  const payload: WebhookPayload = {
+    id: randomUUID(),
+    type,
+    createdAt: new Date().toISOString(),
+    data,
+  };
+
+  for (const sub of targets) {
+    void deliver(sub, payload);
+  }
+}
+
+/**
+ * Configured endpoints (host only, secrets omitted) and delivery counters
+ */
+export function getWebhookStatus() {
+  return {
+    endpoints: getSubscriptions().map(sub => ({
+      host: new URL(sub.url).host,
+      events: sub.events ?? 'all',
+      walletFilter: sub.wallets?.length ?? 0,
+    })),
+    ...stats,
+  };
+}
@@ -194,6 +194,7 @@ export interface RelayBatch {
   // DFlow transaction
   dflowOrderId?: string;
   dflowTxSignature?: string;
+  jitoBundleId?: string;          // Set when executed as a Jito bundle
 
   // ZK proof
   proof?: string;
@@ -205,6 +206,10 @@ export interface RelayBatch {
   executionStartedAt?: Date;
   executionCompletedAt?: Date;
   distributionCompletedAt?: Date;
+
+  // Distribution progress (transfers sent / to send)
+  distributionsDone?: number;
+  distributionsTotal?: number;
 }
 
 /**
@@ -316,6 +321,68 @@ export interface RelayProveRequest {
   }>;
 }
 
+/**
+ * Lifecycle events delivered to webhooks
+ */
+export type WebhookEventType =
+  | 'BatchCreated'          // New batch started collecting
+  | 'BatchClosed'           // Batch stopped accepting orders
+  | 'BatchExecuted'         // Trade filled on DFlow
+  | 'BatchCompleted'        // Shares and refunds distributed
+  | 'BatchFailed'           // Execution or MPC step failed
+  | 'DistributionExecuted'; // Shares sent to one user wallet
+
+/**
+ * Webhook endpoint
+ */
+export interface WebhookSubscription {
+  url: string;
+  secret: string;                 // HMAC-SHA256 key for X-Obsidian-Signature
+  events?: WebhookEventType[];    // If set, only these events are delivered
+  wallets?: string[];             // If set, DistributionExecuted only for these wallets
+}
+
+/**
+ * Body POSTed to a webhook
+ */
+export interface WebhookPayload {
+  id: string;                     // Unique per event, stable across retries
+  type: WebhookEventType;
+  createdAt: string;              // ISO timestamp
+  data: Record<string, unknown>;
+}
+
+/**
+ * Fill progress stages streamed to frontends (proto/progress.proto)
+ */
+export type ProgressStage =
+  | 'queued'                // Collecting; an order joined
+  | 'closed'                // Stopped accepting orders, execution pending
+  | 'mpc_computing'         // MPC revealing the batch total
+  | 'executing'             // Trade in progress on DFlow
+  | 'proving'               // Generating ZK proof
+  | 'distributing'          // Sending shares, see distributionsDone
+  | 'completed'
+  | 'failed';
+
+/**
+ * One fill progress update
+ */
+export interface ProgressUpdate {
+  cursor: number;                 // Increases across all batches
+  batchId: string;
+  marketId: string;
+  side: 'YES' | 'NO';
+  stage: ProgressStage;
+  orderId?: string;               // Order the update is about, if any
+  ordersQueued: number;
+  closesAt?: number;              // Unix seconds, while collecting
+  distributionsDone?: number;
+  distributionsTotal?: number;
+  error?: string;
+  timestamp: number;              // Unix milliseconds
+}
+
 /**
  * Relay configuration
  */
@@ -325,6 +392,12 @@ export interface RelayConfig {
   batchTimeoutSeconds: number;    // How long to wait before executing undersized batch
   minBatchSize: number;           // Minimum orders to execute
 
+  // Adaptive batch windows (mirror ProtocolConfig on-chain; max 0 = off,
+  // batchTimeoutSeconds applies instead)
+  minBatchWindowSeconds?: number; // Shortest window, even for busy markets
+  maxBatchWindowSeconds?: number; // Longest window, for quiet markets
+  targetBatchOrders?: number;     // Orders a window aims to collect
+
   // Fee settings
   relayFeePercent: number;        // Fee taken by relay (e.g., 0.1 = 0.1%)
 
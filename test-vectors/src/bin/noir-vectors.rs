@@ -0,0 +1,63 @@
+//! Print Noir tests for the note vectors and the Merkle vectors at the
+//! circuit's depth.
+//!
+//! cargo run -p test-vectors --bin noir-vectors -- balance_proof > circuits/balance_proof/src/vectors.nr
+//! cargo run -p test-vectors --bin noir-vectors -- split_withdrawal > circuits/split_withdrawal/src/vectors.nr
+
+fn main() {
+    let circuit = std::env::args()
+        .nth(1)
+        .expect("usage: noir-vectors <circuit>");
+    let vectors = test_vectors::notes();
+
+    println!("// Generated from test-vectors/vectors/notes.json and merkle.json; do not edit.");
+    println!("use poseidon::poseidon::bn254::{{hash_2, hash_3, hash_4}};");
+    for (i, note) in vectors.notes.iter().enumerate() {
+        println!();
+        println!("#[test]");
+        println!("fn test_note_vector_{i}() {{");
+        println!("    let secret: Field = {};", note.secret);
+        println!("    let pool_id: Field = {};", vectors.pool_id);
+        println!("    let asset: Field = {};", vectors.asset);
+        println!(
+            "    assert(hash_2([secret, {}]) == {});",
+            note.amount, note.commitment
+        );
+        println!(
+            "    assert(hash_4([1, secret, {}, asset]) == {});",
+            note.amount, note.versioned_commitment
+        );
+        println!(
+            "    assert(hash_4([secret, {}, pool_id, {}]) == {});",
+            note.leaf_index, note.purpose, note.nullifier
+        );
+        println!(
+            "    assert(hash_3([secret, {}, 1]) == {});",
+            note.leaf_index, note.legacy_nullifier
+        );
+        println!("}}");
+    }
+
+    // Roots the privacy pool's compute_merkle_root produces for these trees
+    let depth = test_vectors::circuit_merkle_depth(&circuit);
+    let trees = test_vectors::merkle().trees;
+    for (i, tree) in trees.iter().enumerate().filter(|(_, t)| t.depth == depth) {
+        println!();
+        println!("#[test]");
+        println!("fn test_merkle_vector_{i}() {{");
+        println!("    let path: [Field; crate::MERKLE_DEPTH] = [");
+        for sibling in &tree.path {
+            println!("        {sibling},");
+        }
+        println!("    ];");
+        println!(
+            "    let leaf: Field = {};",
+            tree.leaves[tree.path_index as usize]
+        );
+        println!(
+            "    assert(crate::compute_merkle_root(leaf, path, {}) == {});",
+            tree.path_index, tree.root
+        );
+        println!("}}");
+    }
+}
@@ -0,0 +1,105 @@
+//! Shared test vectors
+//!
+//! Known-answer fixtures for everything that has to agree across the Noir
+//! circuits, the on-chain programs, the Arcis circuits and the SDK. The JSON
+//! under `vectors/` is the source of truth; Rust consumers load it through
+//! this crate and the Noir tests are generated from it with
+//! `cargo run -p test-vectors --bin noir-vectors`.
+//!
+//! Field elements are 0x-prefixed, 32-byte big-endian hex strings.
+
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct NoteVectors {
+    /// Privacy pool PDA the nullifiers are bound to
+    pub pool: String,
+    pub pool_id: String,
+    /// Asset field of the devnet USDC mint, hashed into versioned commitments
+    pub asset: String,
+    pub notes: Vec<NoteVector>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NoteVector {
+    pub secret: String,
+    pub amount: u64,
+    pub leaf_index: u32,
+    pub purpose: u8,
+    /// Legacy (version 0) commitment
+    pub commitment: String,
+    /// Version 1 commitment
+    pub versioned_commitment: String,
+    pub nullifier: String,
+    pub legacy_nullifier: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MerkleVectors {
+    pub trees: Vec<MerkleVector>,
+}
+
+/// Tree of `depth` with `leaves` at indices 0.., zero-padded.
+#[derive(Debug, Deserialize)]
+pub struct MerkleVector {
+    pub depth: usize,
+    pub leaves: Vec<String>,
+    pub root: String,
+    /// Leaf `path` proves, bottom sibling first
+    pub path_index: u32,
+    pub path: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DistributionVectors {
+    pub cases: Vec<DistributionVector>,
+}
+
+/// One batch through `add_to_batch`, `reveal_batch_total` and
+/// `compute_distribution`.
+#[derive(Debug, Deserialize)]
+pub struct DistributionVector {
+    pub name: String,
+    pub order_amounts: Vec<u64>,
+    pub total_shares: u64,
+    pub batch_total: u64,
+    pub order_count: u8,
+    pub shares: Vec<u64>,
+}
+
+pub fn notes() -> NoteVectors {
+    serde_json::from_str(include_str!("../vectors/notes.json")).expect("notes.json")
+}
+
+pub fn merkle() -> MerkleVectors {
+    serde_json::from_str(include_str!("../vectors/merkle.json")).expect("merkle.json")
+}
+
+pub fn distribution() -> DistributionVectors {
+    serde_json::from_str(include_str!("../vectors/distribution.json")).expect("distribution.json")
+}
+
+/// `global MERKLE_DEPTH` of the Noir circuit under `circuits/<circuit>`.
+pub fn circuit_merkle_depth(circuit: &str) -> usize {
+    let path = format!(
+        "{}/../circuits/{circuit}/src/main.nr",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let source = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{path}: {e}"));
+    source
+        .lines()
+        .find_map(|line| line.strip_prefix("global MERKLE_DEPTH: u32 = "))
+        .and_then(|depth| depth.trim_end_matches(';').parse().ok())
+        .unwrap_or_else(|| panic!("no MERKLE_DEPTH in {path}"))
+}
+
+/// Decode a 0x-prefixed 32-byte hex field element.
+pub fn bytes32(hex: &str) -> [u8; 32] {
+    let hex = hex.strip_prefix("0x").expect("0x prefix");
+    assert_eq!(hex.len(), 64, "expected 32 bytes: {hex}");
+    let mut out = [0u8; 32];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).expect("hex digit");
+    }
+    out
+}
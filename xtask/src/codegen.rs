@@ -0,0 +1,452 @@
+//! IDLs and client bindings for every program.
+//!
+//! Builds each program's IDL the way `anchor idl build` does (the
+//! `__anchor_private_print_idl` test under the idl-build feature), requires
+//! docs on every instruction, event and error, and writes:
+//!
+//!   idls/<program>.json          read by `declare_program!` for Rust clients
+//!   src/idl/<program>.ts         relay bindings
+//!   frontend/src/idl/<program>.ts
+//!
+//! The TypeScript bindings carry the program id, instruction, account and
+//! event discriminators, error codes and an interface per IDL type, with the
+//! program's docs as JSDoc. With --check nothing is written and stale
+//! outputs are an error.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use serde_json::{json, Value};
+
+use crate::http::Result;
+use crate::root;
+
+/// Program name and directory, relative to the repo root
+const PROGRAMS: &[(&str, &str)] = &[
+    ("privacy_pool", "programs/privacy_pool"),
+    ("zk_verifier", "programs/zk_verifier"),
+    ("obsidian_mpc", "arcium-relay/programs/obsidian_mpc"),
+];
+
+const TS_DIRS: &[&str] = &["src/idl", "frontend/src/idl"];
+
+pub fn run(check: bool, only: &[String]) -> Result<()> {
+    let root = root();
+    for name in only {
+        if !PROGRAMS.iter().any(|(program, _)| program == name) {
+            return Err(format!("unknown program {name}").into());
+        }
+    }
+
+    let mut stale = Vec::new();
+    for (name, dir) in PROGRAMS {
+        if !only.is_empty() && !only.iter().any(|o| o == name) {
+            continue;
+        }
+        println!("Building the {name} IDL");
+        let idl = build_idl(&root.join(dir))?;
+        lint(name, &idl)?;
+
+        let mut outputs = vec![(
+            PathBuf::from(format!("idls/{name}.json")),
+            serde_json::to_string_pretty(&idl)? + "\n",
+        )];
+        let ts = typescript(name, &idl);
+        for ts_dir in TS_DIRS {
+            outputs.push((PathBuf::from(format!("{ts_dir}/{name}.ts")), ts.clone()));
+        }
+
+        for (path, contents) in outputs {
+            let full = root.join(&path);
+            if fs::read_to_string(&full).ok().as_deref() == Some(contents.as_str()) {
+                continue;
+            }
+            if check {
+                stale.push(path.display().to_string());
+            } else {
+                fs::create_dir_all(full.parent().expect("output in a directory"))?;
+                fs::write(&full, contents)?;
+                println!("  wrote {}", path.display());
+            }
+        }
+    }
+
+    if !stale.is_empty() {
+        return Err(format!(
+            "out of date, run `cargo xtask codegen`: {}",
+            stale.join(", ")
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// The program's IDL, assembled from the sections the idl-build print
+/// test writes, as anchor-lang-idl does
+fn build_idl(dir: &Path) -> Result<Value> {
+    let output = Command::new("cargo")
+        .args([
+            "test",
+            "--lib",
+            "__anchor_private_print_idl",
+            "--features",
+            "idl-build",
+            "--",
+            "--show-output",
+            "--quiet",
+        ])
+        .env("ANCHOR_IDL_BUILD_NO_DOCS", "FALSE")
+        .env("ANCHOR_IDL_BUILD_RESOLUTION", "TRUE")
+        .env("ANCHOR_IDL_BUILD_SKIP_LINT", "TRUE")
+        .env("ANCHOR_IDL_BUILD_PROGRAM_PATH", dir)
+        .env("RUSTFLAGS", "-A warnings")
+        .current_dir(dir)
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| format!("cargo: {e}"))?;
+    if !output.status.success() {
+        return Err(format!("building the IDL in {} failed", dir.display()).into());
+    }
+    let stdout = String::from_utf8(output.stdout)?;
+
+    let mut address = String::new();
+    let mut events = Vec::new();
+    let mut errors = Value::Array(Vec::new());
+    let mut types = BTreeMap::new();
+    let mut program: Option<Value> = None;
+
+    let mut lines = stdout.lines();
+    while let Some(line) = lines.next() {
+        let Some(section) = line
+            .strip_prefix("--- IDL begin ")
+            .and_then(|l| l.strip_suffix(" ---"))
+        else {
+            continue;
+        };
+        let end = format!("--- IDL end {section} ---");
+        let body: Vec<&str> = lines.by_ref().take_while(|l| *l != end).collect();
+        let body = body.join("\n");
+        match section {
+            "address" => address = body.replace(|c: char| !c.is_alphanumeric(), ""),
+            "event" => {
+                let mut print: Value = serde_json::from_str(&body)?;
+                for ty in take_array(&mut print["types"]) {
+                    types.insert(ty["name"].to_string(), ty);
+                }
+                events.push(print["event"].take());
+            }
+            "errors" => errors = serde_json::from_str(&body)?,
+            "program" => program = Some(serde_json::from_str(&body)?),
+            // Constants are not exported to clients
+            _ => {}
+        }
+    }
+
+    let mut idl = program.ok_or("no IDL in the print test output")?;
+    idl["address"] = json!(address);
+    for ty in take_array(&mut idl["types"]) {
+        types.insert(ty["name"].to_string(), ty);
+    }
+    idl["types"] = Value::Array(types.into_values().collect());
+    idl["events"] = Value::Array(events);
+    idl["errors"] = errors;
+
+    let mut idl = shorten_paths(idl);
+    for key in ["accounts", "events", "instructions", "types"] {
+        if let Some(items) = idl[key].as_array_mut() {
+            items.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+        }
+    }
+    Ok(idl)
+}
+
+fn take_array(value: &mut Value) -> Vec<Value> {
+    match value.take() {
+        Value::Array(items) => items,
+        _ => Vec::new(),
+    }
+}
+
+/// `privacy_pool::DepositEvent` becomes `DepositEvent` wherever the short
+/// name is unambiguous
+fn shorten_paths(idl: Value) -> Value {
+    fn paths(value: &Value, found: &mut BTreeSet<String>) {
+        match value {
+            Value::String(s) if is_path(s) => {
+                found.insert(s.clone());
+            }
+            Value::Array(items) => items.iter().for_each(|v| paths(v, found)),
+            Value::Object(map) => map.values().for_each(|v| paths(v, found)),
+            _ => {}
+        }
+    }
+    fn replace(value: Value, short: &BTreeMap<String, String>) -> Value {
+        match value {
+            Value::String(s) => Value::String(short.get(&s).cloned().unwrap_or(s)),
+            Value::Array(items) => items.into_iter().map(|v| replace(v, short)).collect(),
+            Value::Object(map) => map.into_iter().map(|(k, v)| (k, replace(v, short))).collect(),
+            other => other,
+        }
+    }
+    fn last(path: &str) -> &str {
+        path.rsplit("::").next().expect("rsplit yields one item")
+    }
+
+    let mut found = BTreeSet::new();
+    paths(&idl, &mut found);
+    let short = found
+        .iter()
+        .filter(|path| found.iter().filter(|p| last(p) == last(path)).count() == 1)
+        .map(|path| (path.clone(), last(path).to_string()))
+        .collect();
+    replace(idl, &short)
+}
+
+fn is_path(s: &str) -> bool {
+    s.contains("::")
+        && s.split("::")
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_alphanumeric() || c == '_'))
+}
+
+/// Every instruction and event documented, every error with a message
+fn lint(program: &str, idl: &Value) -> Result<()> {
+    let documented = |item: &Value| {
+        item["docs"]
+            .as_array()
+            .is_some_and(|docs| !docs.is_empty())
+    };
+    let type_def = |name: &Value| {
+        idl["types"]
+            .as_array()
+            .and_then(|types| types.iter().find(|ty| ty["name"] == *name))
+    };
+
+    let mut missing = Vec::new();
+    for ix in array(&idl["instructions"]) {
+        if !documented(ix) {
+            missing.push(format!("instruction {}", ix["name"]));
+        }
+    }
+    for event in array(&idl["events"]) {
+        if !type_def(&event["name"]).is_some_and(documented) {
+            missing.push(format!("event {}", event["name"]));
+        }
+    }
+    for error in array(&idl["errors"]) {
+        if error["msg"].as_str().is_none_or(str::is_empty) {
+            missing.push(format!("error {}", error["name"]));
+        }
+    }
+
+    if !missing.is_empty() {
+        return Err(format!("{program}: undocumented {}", missing.join(", ")).into());
+    }
+    Ok(())
+}
+
+fn array(value: &Value) -> &[Value] {
+    value.as_array().map_or(&[], Vec::as_slice)
+}
+
+fn typescript(program: &str, idl: &Value) -> String {
+    let mut out = format!(
+        "// Generated by `cargo xtask codegen` from idls/{program}.json. Do not edit.\n\n"
+    );
+    push_docs(&mut out, "", &idl["metadata"]["description"]);
+    out += &format!("export const PROGRAM_ID = '{}';\n", str_of(&idl["address"]));
+
+    for (title, key) in [
+        ("INSTRUCTIONS", "instructions"),
+        ("ACCOUNTS", "accounts"),
+        ("EVENTS", "events"),
+    ] {
+        out += &format!("\nexport const {title} = {{\n");
+        for item in array(&idl[key]) {
+            push_docs(&mut out, "  ", &item["docs"]);
+            out += &format!(
+                "  {}: [{}],\n",
+                if key == "instructions" {
+                    camel(str_of(&item["name"]))
+                } else {
+                    str_of(&item["name"]).to_string()
+                },
+                array(&item["discriminator"])
+                    .iter()
+                    .map(Value::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        out += "} as const;\n";
+    }
+
+    out += "\nexport enum ErrorCode {\n";
+    for error in array(&idl["errors"]) {
+        push_docs(&mut out, "  ", &error["msg"]);
+        out += &format!("  {} = {},\n", str_of(&error["name"]), error["code"]);
+    }
+    out += "}\n\nexport const ERROR_MESSAGES: Record<ErrorCode, string> = {\n";
+    for error in array(&idl["errors"]) {
+        out += &format!(
+            "  [ErrorCode.{}]: {},\n",
+            str_of(&error["name"]),
+            error["msg"]
+        );
+    }
+    out += "};\n";
+
+    for ix in array(&idl["instructions"]) {
+        if array(&ix["args"]).is_empty() {
+            continue;
+        }
+        out += "\n";
+        push_docs(&mut out, "", &ix["docs"]);
+        out += &format!("export interface {}Args {{\n", pascal(str_of(&ix["name"])));
+        push_fields(&mut out, &ix["args"]);
+        out += "}\n";
+    }
+
+    for ty in array(&idl["types"]) {
+        let name = str_of(&ty["name"]);
+        out += "\n";
+        push_docs(&mut out, "", &ty["docs"]);
+        match str_of(&ty["type"]["kind"]) {
+            "struct" => {
+                out += &format!("export interface {name} {{\n");
+                push_fields(&mut out, &ty["type"]["fields"]);
+                out += "}\n";
+            }
+            "enum" => {
+                let variants: Vec<String> = array(&ty["type"]["variants"])
+                    .iter()
+                    .map(|variant| {
+                        let variant_name = str_of(&variant["name"]);
+                        match variant["fields"].as_array() {
+                            None => format!("'{variant_name}'"),
+                            Some(fields) => {
+                                let fields: Vec<String> = fields
+                                    .iter()
+                                    .enumerate()
+                                    .map(|(i, field)| match field["name"].as_str() {
+                                        Some(field_name) => format!(
+                                            "{}: {}",
+                                            camel(field_name),
+                                            ts_type(&field["type"])
+                                        ),
+                                        None => format!("{i}: {}", ts_type(field)),
+                                    })
+                                    .collect();
+                                format!("{{ {variant_name}: {{ {} }} }}", fields.join("; "))
+                            }
+                        }
+                    })
+                    .collect();
+                out += &format!("export type {name} =\n  | {};\n", variants.join("\n  | "));
+            }
+            "type" => {
+                out += &format!(
+                    "export type {name} = {};\n",
+                    ts_type(&ty["type"]["alias"])
+                );
+            }
+            kind => out += &format!("// {name}: unsupported type kind {kind}\n"),
+        }
+    }
+    out
+}
+
+fn push_fields(out: &mut String, fields: &Value) {
+    for field in array(fields) {
+        push_docs(out, "  ", &field["docs"]);
+        *out += &format!(
+            "  {}: {};\n",
+            camel(str_of(&field["name"])),
+            ts_type(&field["type"])
+        );
+    }
+}
+
+fn push_docs(out: &mut String, indent: &str, docs: &Value) {
+    let lines: Vec<&str> = match docs {
+        Value::String(doc) => vec![doc.as_str()],
+        Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => return,
+    };
+    match lines.as_slice() {
+        [] => {}
+        [line] => *out += &format!("{indent}/** {} */\n", line.trim()),
+        lines => {
+            *out += &format!("{indent}/**\n");
+            for line in lines {
+                *out += &format!("{indent} * {}\n", line.trim()).replace(" \n", "\n");
+            }
+            *out += &format!("{indent} */\n");
+        }
+    }
+}
+
+/// u64 and wider map to bigint, public keys to their base58 string
+fn ts_type(ty: &Value) -> String {
+    match ty {
+        Value::String(primitive) => match primitive.as_str() {
+            "bool" => "boolean",
+            "u8" | "i8" | "u16" | "i16" | "u32" | "i32" | "f32" | "f64" => "number",
+            "u64" | "i64" | "u128" | "i128" | "u256" | "i256" => "bigint",
+            "string" | "pubkey" => "string",
+            "bytes" => "Uint8Array",
+            _ => "unknown",
+        }
+        .to_string(),
+        Value::Object(map) => {
+            if let Some(inner) = map.get("option").or_else(|| map.get("coption")) {
+                format!("{} | null", ts_type(inner))
+            } else if let Some(inner) = map.get("vec") {
+                format!("{}[]", element(inner))
+            } else if let Some(array) = map.get("array") {
+                format!("{}[]", element(&array[0]))
+            } else if let Some(defined) = map.get("defined") {
+                str_of(&defined["name"]).to_string()
+            } else {
+                "unknown".to_string()
+            }
+        }
+        _ => "unknown".to_string(),
+    }
+}
+
+fn element(ty: &Value) -> String {
+    let inner = ts_type(ty);
+    if inner.contains(' ') {
+        format!("({inner})")
+    } else {
+        inner
+    }
+}
+
+fn str_of(value: &Value) -> &str {
+    value.as_str().unwrap_or_default()
+}
+
+fn camel(snake: &str) -> String {
+    let pascal = pascal(snake);
+    let mut chars = pascal.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn pascal(snake: &str) -> String {
+    snake
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,385 @@
+//! Development tasks.
+//!
+//! cargo xtask dev-up [--arcium] [--skip-build]
+//!   Build the programs and start the localnet stack (localnet/docker-compose.yml):
+//!   solana-test-validator with both pool programs loaded, the mock DFlow
+//!   API, the relay and the indexer. With --arcium, `arcium localnet` runs
+//!   the validator and an MPC cluster instead and the relay has MPC enabled;
+//!   needs the arcium CLI.
+//!
+//! cargo xtask dev-down
+//!   Stop everything dev-up started. The ledger is reset on the next dev-up.
+//!
+//! cargo xtask dev-snapshot
+//!   Pull every program transaction and account from the localnet validator
+//!   into target/localnet and restart the indexer on them.
+//!
+//! cargo xtask mock-dflow [addr]
+//!   Serve the mock DFlow API (default 127.0.0.1:8790).
+//!
+//! cargo xtask codegen [--check] [program...]
+//!   Build the program IDLs into idls/ and generate the relay and frontend
+//!   TypeScript bindings from them (see codegen.rs). --check fails instead
+//!   of writing when any output is stale.
+//!
+//! cargo xtask bench [--update] [--skip-build]
+//!   Build both programs and run the compute-unit benchmarks in
+//!   sdk/tests/compute_units.rs against them. Fails when an instruction
+//!   uses more than its budget in sdk/tests/compute_units.json; --update
+//!   rewrites the budgets from this run instead, with BENCH_HEADROOM_PCT
+//!   on top. Needs the arcium CLI for the MPC program.
+
+mod codegen;
+mod http;
+mod mock_dflow;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+use http::Result;
+
+const RPC_URL: &str = "http://127.0.0.1:8899";
+const RELAY_URL: &str = "http://127.0.0.1:3001";
+const INDEXER_URL: &str = "http://127.0.0.1:8787";
+const MOCK_DFLOW_URL: &str = "http://127.0.0.1:8790";
+
+// Programs dev-snapshot indexes
+const PROGRAM_IDS: &[&str] = &[
+    "AfTSjfnT7M88XipRjPGLgDCcqcVfnrePrtuvNBF74hhP",
+    "8WvUQPQrAXckyF9aNYp3SLRsv2vzdMHTmfXpP1132Gpz",
+    "8postM9mUCTKTu6a1vkrhfg8erso2g8eHo8bmc9JZjZc",
+];
+
+// `arcium localnet` brings up its nodes before the validator answers
+const ARCIUM_STARTUP: Duration = Duration::from_secs(180);
+const STARTUP: Duration = Duration::from_secs(120);
+
+// Slack `bench --update` leaves above measured usage
+const BENCH_HEADROOM_PCT: u64 = 5;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let flag = |name: &str| args.iter().any(|a| a == name);
+
+    let result = match args.first().map(String::as_str) {
+        Some("dev-up") => dev_up(flag("--arcium"), flag("--skip-build")),
+        Some("dev-down") => dev_down(),
+        Some("dev-snapshot") => dev_snapshot(),
+        Some("mock-dflow") => {
+            mock_dflow::run(args.get(1).map(String::as_str).unwrap_or("127.0.0.1:8790"))
+        }
+        Some("codegen") => {
+            let programs: Vec<String> = args[1..]
+                .iter()
+                .filter(|a| !a.starts_with("--"))
+                .cloned()
+                .collect();
+            codegen::run(flag("--check"), &programs)
+        }
+        Some("bench") => bench(flag("--update"), flag("--skip-build")),
+        _ => {
+            eprintln!("usage: cargo xtask <dev-up [--arcium] [--skip-build] | dev-down | dev-snapshot | mock-dflow [addr] | codegen [--check] [program...] | bench [--update] [--skip-build]>");
+            return ExitCode::from(2);
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("xtask sits in the workspace root")
+        .to_path_buf()
+}
+
+/// Scratch space shared with the containers: indexer inputs and arcium state
+fn localnet_dir() -> PathBuf {
+    root().join("target/localnet")
+}
+
+fn run(dir: &Path, program: &str, args: &[&str]) -> Result<()> {
+    println!("$ {program} {}", args.join(" "));
+    let status = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .map_err(|e| format!("{program}: {e}"))?;
+    if !status.success() {
+        return Err(format!("{program} {} failed: {status}", args.join(" ")).into());
+    }
+    Ok(())
+}
+
+fn compose(args: &[&str], envs: &[(&str, &str)]) -> Result<()> {
+    let file = root().join("localnet/docker-compose.yml");
+    let mut full = vec!["compose", "-f", file.to_str().ok_or("non-UTF-8 path")?];
+    full.extend_from_slice(args);
+    println!("$ docker {}", full.join(" "));
+    let status = Command::new("docker")
+        .args(&full)
+        .envs(envs.iter().copied())
+        .status()?;
+    if !status.success() {
+        return Err(format!("docker compose {} failed: {status}", args.join(" ")).into());
+    }
+    Ok(())
+}
+
+fn wait_for(name: &str, timeout: Duration, mut ready: impl FnMut() -> bool) -> Result<()> {
+    let start = Instant::now();
+    print!("Waiting for {name}...");
+    while !ready() {
+        if start.elapsed() > timeout {
+            println!();
+            return Err(format!("{name} did not come up within {}s", timeout.as_secs()).into());
+        }
+        sleep(Duration::from_secs(2));
+    }
+    println!(" up ({}s)", start.elapsed().as_secs());
+    Ok(())
+}
+
+fn validator_healthy() -> bool {
+    matches!(http::rpc(RPC_URL, "getHealth", json!([])), Ok(v) if v == "ok")
+}
+
+fn http_healthy(url: &str) -> bool {
+    matches!(http::get(url), Ok((200, _)))
+}
+
+fn dev_up(arcium: bool, skip_build: bool) -> Result<()> {
+    let root = root();
+    let dir = localnet_dir();
+    fs::create_dir_all(&dir)?;
+
+    if !skip_build {
+        run(&root, "anchor", &["build"])?;
+        if arcium {
+            run(&root.join("arcium-relay"), "arcium", &["build"])?;
+        }
+    }
+
+    // The indexer needs inputs before the first dev-snapshot
+    for file in ["transactions.json", "accounts.json"] {
+        if !dir.join(file).exists() {
+            fs::write(dir.join(file), "[]")?;
+        }
+    }
+
+    if arcium {
+        // Runs until dev-down; its validator loads the pool programs via
+        // [[test.genesis]] in arcium-relay/Anchor.toml
+        let log = fs::File::create(dir.join("arcium.log"))?;
+        let child = Command::new("arcium")
+            .arg("localnet")
+            .current_dir(root.join("arcium-relay"))
+            .stdout(log.try_clone()?)
+            .stderr(log)
+            .stdin(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("arcium: {e}"))?;
+        fs::write(dir.join("arcium.pid"), child.id().to_string())?;
+        println!(
+            "arcium localnet started (pid {}), logging to {}",
+            child.id(),
+            dir.join("arcium.log").display()
+        );
+        wait_for("Arcium localnet", ARCIUM_STARTUP, validator_healthy)?;
+
+        compose(
+            &["up", "-d", "--build"],
+            &[
+                ("LOCALNET_RPC_URL", "http://host.docker.internal:8899"),
+                ("ARCIUM_MPC_ENABLED", "true"),
+            ],
+        )?;
+    } else {
+        compose(&["--profile", "validator", "up", "-d", "--build"], &[])?;
+        wait_for("validator", STARTUP, validator_healthy)?;
+    }
+
+    // First start compiles the mock and indexer inside their containers
+    wait_for("mock DFlow API", STARTUP * 5, || {
+        http_healthy(&format!("{MOCK_DFLOW_URL}/health"))
+    })?;
+    wait_for("relay", STARTUP * 5, || {
+        http_healthy(&format!("{RELAY_URL}/health"))
+    })?;
+    wait_for("indexer", STARTUP * 5, || {
+        http::get(&format!("{INDEXER_URL}/")).is_ok()
+    })?;
+
+    println!();
+    println!("Localnet is up:");
+    println!("  validator  {RPC_URL}");
+    println!("  relay      {RELAY_URL}");
+    println!("  indexer    {INDEXER_URL}  (refresh with `cargo xtask dev-snapshot`)");
+    println!("  mock DFlow {MOCK_DFLOW_URL}");
+    println!(
+        "  MPC        {}",
+        if arcium {
+            "Arcium localnet"
+        } else {
+            "disabled (use --arcium)"
+        }
+    );
+    Ok(())
+}
+
+fn dev_down() -> Result<()> {
+    compose(&["--profile", "validator", "down"], &[])?;
+
+    let pid_file = localnet_dir().join("arcium.pid");
+    if let Ok(pid) = fs::read_to_string(&pid_file) {
+        // Already gone is fine
+        let _ = Command::new("kill").arg(pid.trim()).status();
+        fs::remove_file(pid_file)?;
+        println!("Stopped arcium localnet");
+    }
+    Ok(())
+}
+
+/// Every transaction of `program`, newest first
+fn program_transactions(program: &str) -> Result<Vec<Value>> {
+    let mut transactions = Vec::new();
+    let mut before: Option<String> = None;
+    loop {
+        let mut options = json!({ "limit": 1000 });
+        if let Some(before) = &before {
+            options["before"] = json!(before);
+        }
+        let page = http::rpc(
+            RPC_URL,
+            "getSignaturesForAddress",
+            json!([program, options]),
+        )?;
+        let page = page
+            .as_array()
+            .ok_or("getSignaturesForAddress: expected an array")?;
+        for entry in page {
+            let signature = entry["signature"].as_str().ok_or("missing signature")?;
+            let transaction = http::rpc(
+                RPC_URL,
+                "getTransaction",
+                json!([signature, { "encoding": "json", "maxSupportedTransactionVersion": 0 }]),
+            )?;
+            transactions.push(transaction);
+        }
+        match page.last() {
+            Some(last) if page.len() == 1000 => {
+                before = last["signature"].as_str().map(String::from)
+            }
+            _ => return Ok(transactions),
+        }
+    }
+}
+
+fn dev_snapshot() -> Result<()> {
+    let dir = localnet_dir();
+    fs::create_dir_all(&dir)?;
+
+    let mut transactions: Vec<Value> = Vec::new();
+    let mut accounts: Vec<Value> = Vec::new();
+    for program in PROGRAM_IDS {
+        for transaction in program_transactions(program)? {
+            // A transaction touching several programs is listed under each
+            let signature = &transaction["transaction"]["signatures"][0];
+            if !transactions
+                .iter()
+                .any(|t| &t["transaction"]["signatures"][0] == signature)
+            {
+                transactions.push(transaction);
+            }
+        }
+        let owned = http::rpc(
+            RPC_URL,
+            "getProgramAccounts",
+            json!([program, { "encoding": "base64" }]),
+        )?;
+        accounts.extend(owned.as_array().cloned().unwrap_or_default());
+    }
+
+    fs::write(
+        dir.join("transactions.json"),
+        serde_json::to_string(&transactions)?,
+    )?;
+    fs::write(dir.join("accounts.json"), serde_json::to_string(&accounts)?)?;
+    println!(
+        "Snapshot: {} transactions, {} accounts",
+        transactions.len(),
+        accounts.len()
+    );
+
+    compose(&["restart", "indexer"], &[])
+}
+
+fn bench(update: bool, skip_build: bool) -> Result<()> {
+    let root = root();
+    if !skip_build {
+        run(&root, "anchor", &["build"])?;
+        run(&root.join("arcium-relay"), "arcium", &["build"])?;
+    }
+
+    let args = [
+        "test",
+        "-p",
+        "obsidian-sdk",
+        "--test",
+        "compute_units",
+        "--",
+        "--ignored",
+        "--nocapture",
+        "--test-threads=1",
+    ];
+    println!("$ cargo {}", args.join(" "));
+    let output = Command::new(env!("CARGO"))
+        .args(args)
+        .current_dir(&root)
+        .stderr(Stdio::inherit())
+        .output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    print!("{stdout}");
+
+    // One `compute-units <instruction> <units>` line per benchmark
+    let used: Vec<(&str, u64)> = stdout
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.strip_prefix("compute-units ")?.split_whitespace();
+            Some((parts.next()?, parts.next()?.parse().ok()?))
+        })
+        .collect();
+
+    let path = root.join("sdk/tests/compute_units.json");
+    let mut budgets: Value = serde_json::from_str(&fs::read_to_string(&path)?)?;
+    println!();
+    println!("{:<24} {:>10} {:>10}", "instruction", "used", "budget");
+    for (name, units) in &used {
+        let budget = budgets[*name].as_u64().unwrap_or_default();
+        println!("{name:<24} {units:>10} {budget:>10}");
+    }
+
+    if update {
+        for (name, units) in &used {
+            budgets[*name] = json!(units * (100 + BENCH_HEADROOM_PCT) / 100);
+        }
+        fs::write(&path, serde_json::to_string_pretty(&budgets)? + "\n")?;
+        println!("Updated {}", path.display());
+        return Ok(());
+    }
+    if !output.status.success() {
+        return Err("compute-unit benchmarks failed".into());
+    }
+    Ok(())
+}
@@ -0,0 +1,294 @@
+//! Mock of the DFlow / Kalshi / MCP APIs the relay trades through.
+//!
+//! Serves a fixed catalogue of markets, quotes at fixed prices and fills
+//! every buy in full. Trades and transfers return fresh fake signatures and
+//! move nothing on chain; the relay's bookkeeping runs as it would against
+//! the real APIs.
+//!
+//!   GET  /health
+//!   GET  /api/v1/event/{event}        DFlow prediction markets API
+//!   GET  /trade-api/v2/markets/{id}   Kalshi API
+//!   GET  /quote                       DFlow quote API
+//!   POST /mcp                         MCP JSON-RPC `tools/call`
+
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde_json::{json, Value};
+
+use crate::http::{read_request, respond, Result};
+
+const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+struct Market {
+    ticker: &'static str,
+    title: &'static str,
+    yes_price: f64,
+    yes_mint: &'static str,
+    no_mint: &'static str,
+}
+
+// Same tickers and mints as the relay's KNOWN_TOKEN_MINTS
+const MARKETS: &[Market] = &[
+    Market {
+        ticker: "KXSB-26-BUF",
+        title: "Buffalo wins the Super Bowl",
+        yes_price: 0.18,
+        yes_mint: "6kFSnPEBFdpSrUi9KsiFg2w3W5Yj2fBcngUHsVA9ZSJd",
+        no_mint: "2Bdt3J34TtSCmpsSf2Ke9TfbxHEdDacBbNkigVhy9wQH",
+    },
+    Market {
+        ticker: "KXSB-26-DEN",
+        title: "Denver wins the Super Bowl",
+        yes_price: 0.09,
+        yes_mint: "8DDZQdeUMB1dzsPb5jGvFJ1bJU5mQbN3hph8C96gZPZ6",
+        no_mint: "DS9kp4EedXUkTxTbAnJ5dD8PZPvi9uwQ3awoWCjsEiAF",
+    },
+    Market {
+        ticker: "KXSB-26-SEA",
+        title: "Seattle wins the Super Bowl",
+        yes_price: 0.21,
+        yes_mint: "GNr3UXmnwokHCBwpe2QWC9qr3M8v3mTjoAUQj6TVTbyP",
+        no_mint: "5jvnRRxgPQzrEA9vBiCzD9oN3zhAUn8zEhbMSsEgqJC8",
+    },
+];
+
+// Funded well past anything a localnet batch commits
+const MOCK_USDC_BALANCE: f64 = 1_000_000.0;
+const MOCK_SOL_BALANCE: f64 = 100.0;
+
+static SIGNATURES: AtomicU64 = AtomicU64::new(1);
+
+/// Unique, well-formed transaction signature
+fn fake_signature() -> String {
+    let mut bytes = [0x4d_u8; 64];
+    bytes[..8].copy_from_slice(&SIGNATURES.fetch_add(1, Ordering::Relaxed).to_le_bytes());
+    bs58::encode(bytes).into_string()
+}
+
+fn mock_wallet() -> String {
+    bs58::encode([0x4d_u8; 32]).into_string()
+}
+
+fn market(ticker: &str) -> Option<&'static Market> {
+    MARKETS.iter().find(|m| m.ticker == ticker)
+}
+
+fn price(m: &Market, yes: bool) -> f64 {
+    if yes {
+        m.yes_price
+    } else {
+        1.0 - m.yes_price
+    }
+}
+
+/// Market as the DFlow API and MCP search tools return it
+fn dflow_market(m: &Market) -> Value {
+    json!({
+        "ticker": m.ticker,
+        "eventTicker": event_ticker(m.ticker),
+        "title": m.title,
+        "status": "active",
+        "yesBid": format!("{:.2}", m.yes_price - 0.01),
+        "yesAsk": format!("{:.2}", m.yes_price),
+        "noBid": format!("{:.2}", price(m, false) - 0.01),
+        "noAsk": format!("{:.2}", price(m, false)),
+        "accounts": {
+            USDC_MINT: { "yesMint": m.yes_mint, "noMint": m.no_mint, "isInitialized": true }
+        },
+    })
+}
+
+fn event_ticker(ticker: &str) -> String {
+    ticker.split('-').take(2).collect::<Vec<_>>().join("-")
+}
+
+/// Events grouping the catalogue, as MCP search/list tools return them
+fn events() -> Value {
+    let mut events: Vec<Value> = Vec::new();
+    for m in MARKETS {
+        let ticker = event_ticker(m.ticker);
+        match events.iter_mut().find(|e| e["ticker"] == ticker.as_str()) {
+            Some(event) => event["markets"]
+                .as_array_mut()
+                .unwrap()
+                .push(dflow_market(m)),
+            None => events.push(json!({
+                "ticker": ticker,
+                "title": "Super Bowl LX",
+                "markets": [dflow_market(m)],
+            })),
+        }
+    }
+    json!({ "events": events })
+}
+
+/// Quote for `amount` USDC base units into the outcome token `mint`
+fn quote(mint: &str, amount: u64) -> Option<Value> {
+    let (m, yes) = MARKETS.iter().find_map(|m| {
+        (m.yes_mint == mint)
+            .then_some((m, true))
+            .or((m.no_mint == mint).then_some((m, false)))
+    })?;
+    let out = (amount as f64 / price(m, yes)).floor() as u64;
+    Some(json!({
+        "inputMint": USDC_MINT,
+        "outputMint": mint,
+        "inAmount": amount.to_string(),
+        "outAmount": out.to_string(),
+        "priceImpactPct": "0",
+        "slippageBps": 0,
+    }))
+}
+
+fn transfer() -> Value {
+    json!({ "success": true, "txSignature": fake_signature() })
+}
+
+/// MCP tool result, or an error message
+fn call_tool(name: &str, args: &Value) -> std::result::Result<Value, String> {
+    match name {
+        "kalshi_get_balances" | "kalshi_get_wallet_status" => Ok(json!({
+            "publicKey": mock_wallet(),
+            "balances": { "sol": MOCK_SOL_BALANCE, "usdc": MOCK_USDC_BALANCE },
+        })),
+        "kalshi_get_deposit_address" => Ok(json!({ "publicKey": mock_wallet() })),
+        "kalshi_get_token_holdings" => Ok(json!({ "holdings": [] })),
+        "kalshi_search_markets" | "kalshi_get_events" => Ok(events()),
+        "kalshi_get_market" | "kalshi_get_live_data" => {
+            let ticker = args["ticker"]
+                .as_str()
+                .or(args["marketTicker"].as_str())
+                .unwrap_or_default();
+            let m = market(ticker).ok_or_else(|| format!("Unknown market {ticker}"))?;
+            Ok(json!({
+                "ticker": m.ticker,
+                "title": m.title,
+                "status": "active",
+                "yesPrice": m.yes_price,
+                "noPrice": price(m, false),
+                "yesMint": m.yes_mint,
+                "noMint": m.no_mint,
+            }))
+        }
+        "kalshi_get_quote" => {
+            let mint = args["outputMint"].as_str().unwrap_or_default();
+            let amount = args["amount"].as_u64().unwrap_or_default();
+            let quote = quote(mint, amount).ok_or_else(|| format!("Unknown mint {mint}"))?;
+            Ok(json!({ "quote": quote }))
+        }
+        "kalshi_buy_yes" | "kalshi_buy_no" => {
+            let mint = args["yesOutcomeMint"]
+                .as_str()
+                .or(args["noOutcomeMint"].as_str())
+                .unwrap_or_default();
+            let usdc = args["usdcAmount"].as_f64().unwrap_or_default();
+            let quote = quote(mint, (usdc * 1e6).round() as u64)
+                .ok_or_else(|| format!("Unknown mint {mint}"))?;
+            Ok(json!({
+                "message": "Filled (mock)",
+                "quote": quote,
+                "txSignature": fake_signature(),
+                "status": "filled",
+            }))
+        }
+        "kalshi_send_token" | "kalshi_send_usdc" | "kalshi_send_sol" => Ok(transfer()),
+        _ => Err(format!("Tool {name} is not mocked")),
+    }
+}
+
+fn mcp(body: &str) -> Value {
+    let request: Value = serde_json::from_str(body).unwrap_or_default();
+    let id = request["id"].clone();
+    let name = request["params"]["name"].as_str().unwrap_or_default();
+    println!("[mock-dflow] {name}");
+
+    match call_tool(name, &request["params"]["arguments"]) {
+        Ok(result) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "result": { "content": [{ "type": "text", "text": result.to_string() }] },
+        }),
+        Err(message) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": -32601, "message": message },
+        }),
+    }
+}
+
+fn route(method: &str, path: &str, body: &str) -> (u16, Value) {
+    let (path, query) = path.split_once('?').unwrap_or((path, ""));
+    let param = |name: &str| {
+        query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+            .unwrap_or_default()
+    };
+
+    match (method, path) {
+        ("GET", "/health") => (200, json!({ "status": "ok" })),
+        ("POST", "/mcp") => (200, mcp(body)),
+        ("GET", "/quote") => match quote(param("outputMint"), param("amount").parse().unwrap_or(0))
+        {
+            Some(quote) => (200, quote),
+            None => (400, json!({ "error": "unknown outputMint" })),
+        },
+        ("GET", _) => {
+            if let Some(event) = path.strip_prefix("/api/v1/event/") {
+                let markets: Vec<Value> = MARKETS
+                    .iter()
+                    .filter(|m| event_ticker(m.ticker) == event)
+                    .map(dflow_market)
+                    .collect();
+                if markets.is_empty() {
+                    return (404, json!({ "error": format!("unknown event {event}") }));
+                }
+                return (200, json!({ "ticker": event, "markets": markets }));
+            }
+            if let Some(ticker) = path.strip_prefix("/trade-api/v2/markets/") {
+                return match market(ticker) {
+                    Some(m) => (
+                        200,
+                        json!({ "market": {
+                            "ticker": m.ticker,
+                            "subtitle": m.title,
+                            "status": "active",
+                            "yes_ask_dollars": format!("{:.2}", m.yes_price),
+                            "no_ask_dollars": format!("{:.2}", price(m, false)),
+                        }}),
+                    ),
+                    None => (404, json!({ "error": format!("unknown market {ticker}") })),
+                };
+            }
+            (404, json!({ "error": "not found" }))
+        }
+        _ => (404, json!({ "error": "not found" })),
+    }
+}
+
+fn serve(stream: TcpStream) -> std::io::Result<()> {
+    let Some(request) = read_request(&stream)? else {
+        return Ok(());
+    };
+    let (status, body) = route(&request.method, &request.path, &request.body);
+    respond(&stream, status, &body)
+}
+
+pub fn run(addr: &str) -> Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    println!("[mock-dflow] listening on {addr}");
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                std::thread::spawn(move || {
+                    if let Err(e) = serve(stream) {
+                        eprintln!("[mock-dflow] request failed: {e}");
+                    }
+                });
+            }
+            Err(e) => eprintln!("[mock-dflow] accept failed: {e}"),
+        }
+    }
+    Ok(())
+}